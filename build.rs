@@ -0,0 +1,36 @@
+//! Program ID is selected at build time, `TOKEN_PROGRAM_ID` overrides the default one
+//! so forks and per-cluster deployments build without patching source. `TOKEN_CONFIG_ADMIN`
+//! names the only key besides the upgrade authority allowed to create program config.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_PROGRAM_ID: &str = "4ieTTSrJzX1GbW9susJJpLE3bv6kuWqguCjrzYr3jUJ1";
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=TOKEN_PROGRAM_ID");
+    println!("cargo:rerun-if-env-changed=TOKEN_CONFIG_ADMIN");
+    let program_id = env::var("TOKEN_PROGRAM_ID").unwrap_or_else(|_| DEFAULT_PROGRAM_ID.to_string());
+    check_pubkey("TOKEN_PROGRAM_ID", &program_id);
+    let config_admin = match env::var("TOKEN_CONFIG_ADMIN") {
+        Ok(config_admin) => {
+            check_pubkey("TOKEN_CONFIG_ADMIN", &config_admin);
+            format!("Some(solana_program::pubkey!(\"{}\"))", config_admin)
+        }
+        Err(_) => "None".to_string(),
+    };
+
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("program_id.rs");
+    fs::write(out_path, format!(
+        "solana_program::declare_id!(\"{}\");\n\
+         /// Key allowed to create program config besides the upgrade authority, see `TOKEN_CONFIG_ADMIN`\n\
+         pub const CONFIG_ADMIN: Option<solana_program::pubkey::Pubkey> = {};\n",
+        program_id,
+        config_admin,
+    )).unwrap();
+}
+
+fn check_pubkey(var: &str, value: &str) {
+    let bytes = bs58::decode(value).into_vec().unwrap_or_else(|_| panic!("{} {} is not base58", var, value));
+    assert_eq!(bytes.len(), 32, "{} {} is not a pubkey", var, value);
+}
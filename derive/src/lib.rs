@@ -0,0 +1,312 @@
+//! Derive instruction builders from account annotations of instruction enum variants.
+//!
+//! Every variant lists its accounts in processor order with `#[account(...)]`:
+//! - `name = "..."` - builder parameter or local binding, required
+//! - `desc = "..."` - documentation of the account, defaults to name with spaces
+//! - `signer`, `writable` - account meta flags
+//! - `address = "..."` - expression computing the key, e.g. PDA or program id, may use builder parameters
+//! - `optional` - trailing account appended by the caller, not passed to the builder
+//!
+//! Builder `snake_case(accounts.., args.., fields..)` is generated for each variant,
+//! `#[builder(args = "...")]` adds parameters used by `address` expressions,
+//! `#[builder(policy = "...")]` starts the accounts with transfer policy accounts of the named authority account,
+//! see `TokenInstruction::transfer_policy_accounts`, for the mint named by `#[builder(policy_mint = "...")]`
+//! or lamports without it, and `#[builder(skip)]` leaves the builder to be written by hand.
+//!
+//! `InstructionCodec` encodes the enum as its variant index followed by fields in declaration order,
+//! the layout of Borsh, through `codec::Field` of each field.
+use std::str::FromStr;
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, Lit, Meta, NestedMeta, Variant};
+
+#[proc_macro_derive(InstructionBuilder, attributes(account, builder))]
+pub fn derive_instruction_builder(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(InstructionCodec)]
+pub fn derive_instruction_codec(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_codec(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Account {
+    name: Ident,
+    desc: String,
+    signer: bool,
+    writable: bool,
+    address: Option<TokenStream2>,
+    optional: bool,
+}
+
+impl Account {
+    fn doc(&self, index: usize) -> String {
+        let flags: Vec<&str> = [(self.signer, "signer"), (self.writable, "writable")]
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, flag)| *flag)
+            .collect();
+        let optional = if self.optional { "optional, " } else { "" };
+        format!(" {}. [{}] - {}{}", index, flags.join(", "), optional, self.desc)
+    }
+
+    fn meta(&self) -> TokenStream2 {
+        let name = &self.name;
+        let signer = self.signer;
+        if self.writable {
+            quote! { solana_program::instruction::AccountMeta::new(#name, #signer) }
+        } else {
+            quote! { solana_program::instruction::AccountMeta::new_readonly(#name, #signer) }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    skip: bool,
+    args: Option<TokenStream2>,
+    policy: Option<Ident>,
+    policy_mint: Option<Ident>,
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let enum_name = &input.ident;
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => return Err(Error::new_spanned(input, "InstructionBuilder supports only enums")),
+    };
+    let tag_count = variants.len() as u8;
+    let builders = variants
+        .iter()
+        .map(|variant| expand_variant(enum_name, variant))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(quote! {
+        impl #enum_name {
+            /// Number of known instructions, the first byte of instruction data is the tag
+            pub const TAG_COUNT: u8 = #tag_count;
+
+            #(#builders)*
+        }
+    })
+}
+
+fn expand_codec(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let enum_name = &input.ident;
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => return Err(Error::new_spanned(input, "InstructionCodec supports only enums")),
+    };
+    let mut pack_arms = vec![];
+    let mut unpack_arms = vec![];
+    for (tag, variant) in variants.iter().enumerate() {
+        let tag = tag as u8;
+        let variant_name = &variant.ident;
+        let field_names: Vec<_> = match &variant.fields {
+            Fields::Named(fields) => fields.named.iter().map(|field| field.ident.clone()).collect(),
+            Fields::Unit => vec![],
+            Fields::Unnamed(_) => return Err(Error::new_spanned(variant, "tuple variants are not supported")),
+        };
+        pack_arms.push(quote! {
+            #enum_name::#variant_name { #(#field_names),* } => {
+                data.push(#tag);
+                #(crate::codec::Field::pack(#field_names, &mut data);)*
+            }
+        });
+        unpack_arms.push(quote! {
+            #tag => #enum_name::#variant_name { #(#field_names: crate::codec::Field::unpack(input)?),* },
+        });
+    }
+
+    Ok(quote! {
+        impl #enum_name {
+            /// Encode as tag followed by fields
+            pub fn pack(&self) -> Vec<u8> {
+                let mut data = vec![];
+                match self {
+                    #(#pack_arms)*
+                }
+                data
+            }
+
+            /// Decode tag and fields from the front of `input`, advancing it past them
+            fn unpack_fields(input: &mut &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+                let tag: u8 = crate::codec::Field::unpack(input)?;
+                Ok(match tag {
+                    #(#unpack_arms)*
+                    _ => return Err(crate::error::TransferError::InvalidInstructionData.into()),
+                })
+            }
+        }
+    })
+}
+
+fn expand_variant(enum_name: &Ident, variant: &Variant) -> Result<TokenStream2, Error> {
+    let builder = parse_builder(variant)?;
+    let accounts = parse_accounts(variant)?;
+    if builder.skip {
+        return Ok(TokenStream2::new())
+    }
+
+    let variant_name = &variant.ident;
+    let fn_name = Ident::new(&snake_case(&variant_name.to_string()), Span::call_site());
+    let (field_names, field_types): (Vec<_>, Vec<_>) = match &variant.fields {
+        Fields::Named(fields) => fields.named.iter().map(|field| (field.ident.clone(), field.ty.clone())).unzip(),
+        Fields::Unit => (vec![], vec![]),
+        Fields::Unnamed(_) => return Err(Error::new_spanned(variant, "tuple variants are not supported")),
+    };
+    let instr = match &variant.fields {
+        Fields::Named(_) => quote! { #enum_name::#variant_name { #(#field_names),* } },
+        _ => quote! { #enum_name::#variant_name },
+    };
+
+    let accounts: Vec<_> = accounts.iter().filter(|acc| !acc.optional).collect();
+    let params = accounts.iter().filter(|acc| acc.address.is_none()).map(|acc| &acc.name);
+    let bindings = accounts.iter().filter_map(|acc| {
+        let name = &acc.name;
+        acc.address.as_ref().map(|address| quote! { let #name: solana_program::pubkey::Pubkey = #address; })
+    });
+    let metas = accounts.iter().map(|acc| acc.meta());
+    let args = builder.args.map(|args| quote! { #args, });
+    let policy_docs = builder.policy.iter().map(|authority| {
+        format!(" Transfer policy accounts of `{}` go first, see `transfer_policy_accounts`", authority)
+    });
+    let docs = std::iter::once(format!(" Build `{}` instruction", variant_name))
+        .chain(policy_docs)
+        .chain(accounts.iter().enumerate().map(|(index, acc)| acc.doc(index)));
+    let policy_mint = match &builder.policy_mint {
+        Some(mint) => quote! { #mint },
+        None => quote! { solana_program::pubkey::Pubkey::default() },
+    };
+    let account_metas = match &builder.policy {
+        Some(authority) => quote! {
+            [#enum_name::transfer_policy_accounts(&#authority, &#policy_mint), vec![#(#metas),*]].concat()
+        },
+        None => quote! { vec![#(#metas),*] },
+    };
+
+    Ok(quote! {
+        #(#[doc = #docs])*
+        #[allow(clippy::too_many_arguments)]
+        pub fn #fn_name(
+            #(#params: solana_program::pubkey::Pubkey,)*
+            #args
+            #(#field_names: #field_types),*
+        ) -> solana_program::instruction::Instruction {
+            #(#bindings)*
+            solana_program::instruction::Instruction::new_with_bytes(
+                crate::id(),
+                &#instr.pack(),
+                #account_metas,
+            )
+        }
+    })
+}
+
+fn parse_builder(variant: &Variant) -> Result<Builder, Error> {
+    let mut builder = Builder::default();
+    for attr in variant.attrs.iter().filter(|attr| attr.path.is_ident("builder")) {
+        for nested in nested_metas(attr.parse_meta()?)? {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => builder.skip = true,
+                NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("args") => {
+                    builder.args = Some(parse_tokens(&pair.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("policy") => {
+                    builder.policy = Some(Ident::new(&lit_str(&pair.lit)?, Span::call_site()));
+                }
+                NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("policy_mint") => {
+                    builder.policy_mint = Some(Ident::new(&lit_str(&pair.lit)?, Span::call_site()));
+                }
+                other => {
+                    let expected = "expected `skip`, `args = \"...\"`, `policy = \"...\"` or `policy_mint = \"...\"`";
+                    return Err(Error::new_spanned(other, expected))
+                }
+            }
+        }
+    }
+    Ok(builder)
+}
+
+fn parse_accounts(variant: &Variant) -> Result<Vec<Account>, Error> {
+    let mut accounts = vec![];
+    for attr in variant.attrs.iter().filter(|attr| attr.path.is_ident("account")) {
+        let (mut name, mut desc, mut address) = (None, None, None);
+        let (mut signer, mut writable, mut optional) = (false, false, false);
+        for nested in nested_metas(attr.parse_meta()?)? {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("signer") => signer = true,
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("writable") => writable = true,
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("optional") => optional = true,
+                NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("name") => {
+                    name = Some(Ident::new(&lit_str(&pair.lit)?, Span::call_site()));
+                }
+                NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("desc") => {
+                    desc = Some(lit_str(&pair.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("address") => {
+                    address = Some(parse_tokens(&pair.lit)?);
+                }
+                other => return Err(Error::new_spanned(other, "unknown account attribute")),
+            }
+        }
+        let name = name.ok_or_else(|| Error::new_spanned(attr, "account `name` is required"))?;
+        let desc = desc.unwrap_or_else(|| name.to_string().replace('_', " "));
+        let account = Account { name, desc, signer, writable, address, optional };
+        accounts.push(account);
+    }
+    Ok(accounts)
+}
+
+fn nested_metas(meta: Meta) -> Result<Vec<NestedMeta>, Error> {
+    match meta {
+        Meta::List(list) => Ok(list.nested.into_iter().collect()),
+        other => Err(Error::new_spanned(other, "expected list attribute")),
+    }
+}
+
+fn lit_str(lit: &Lit) -> Result<String, Error> {
+    match lit {
+        Lit::Str(lit) => Ok(lit.value()),
+        other => Err(Error::new_spanned(other, "expected string literal")),
+    }
+}
+
+fn parse_tokens(lit: &Lit) -> Result<TokenStream2, Error> {
+    TokenStream2::from_str(&lit_str(lit)?).map_err(|err| Error::new_spanned(lit, err.to_string()))
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, ch) in name.chars().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod derive_test {
+    use crate::snake_case;
+
+    #[test]
+    fn when_snake_case_variant_expect_builder_name() {
+        assert_eq!(snake_case("TransferSplTokenToWallet"), "transfer_spl_token_to_wallet");
+        assert_eq!(snake_case("Tip"), "tip");
+    }
+}
@@ -0,0 +1,65 @@
+//! Airdrop claim: a project funds a distributor, an off-chain attestor signs eligibility
+//! of each wallet and the wallets claim their share once, paying the claim record themselves.
+//!
+//! ```text
+//! $ cargo run --example airdrop_claim --features test-bpf
+//! ```
+mod common;
+
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use token::eligibility;
+use token::instruction::TokenInstruction;
+use token::state::{Config, Distributor};
+use common::Cluster;
+
+const DISTRIBUTOR_ID: u64 = 7;
+const FUNDING: u64 = 10_000_000;
+const SHARE: u64 = 2_000_000;
+
+pub async fn run() {
+    let mut cluster = Cluster::start().await;
+    let project = cluster.wallet().await;
+    let attestor = Keypair::new();
+    cluster.enable_features(Config::FEATURE_GATED_CLAIMS).await;
+
+    let create_instr = TokenInstruction::create_distributor(
+        project.pubkey(),
+        DISTRIBUTOR_ID,
+        attestor.pubkey(),
+        FUNDING,
+    );
+    cluster.process(&[create_instr], &[&project]).await.unwrap();
+    let (distributor, _) = Distributor::find_address(&project.pubkey(), DISTRIBUTOR_ID);
+
+    // Attestor signs off-chain, the signature travels in an ed25519 instruction ahead of the claim
+    let claim = |claimer: &Keypair| {
+        let message = eligibility::message(&distributor, &claimer.pubkey(), SHARE);
+        let signature: [u8; 64] = attestor.sign_message(&message).into();
+        [
+            eligibility::ed25519_instruction(&attestor.pubkey(), &signature, &message),
+            TokenInstruction::claim_distribution(claimer.pubkey(), distributor, SHARE),
+        ]
+    };
+    let claimers = [cluster.wallet().await, cluster.wallet().await, cluster.wallet().await];
+    for claimer in claimers.iter() {
+        let balance = cluster.ctx.banks_client.get_balance(claimer.pubkey()).await.unwrap();
+        cluster.process(&claim(claimer), &[claimer]).await.unwrap();
+        assert!(cluster.ctx.banks_client.get_balance(claimer.pubkey()).await.unwrap() > balance);
+    }
+
+    let distributor_state: Distributor = cluster.state(&distributor).await;
+    assert_eq!(distributor_state.claimed_total, SHARE * claimers.len() as u64);
+    assert_eq!(distributor_state.claim_count as usize, claimers.len());
+
+    // Claim record of the wallet already exists, a second claim fails
+    cluster.warp(1).await;
+    assert!(cluster.process(&claim(&claimers[0]), &[&claimers[0]]).await.is_err());
+}
+
+#[allow(dead_code)]
+#[tokio::main]
+async fn main() {
+    run().await;
+    println!("airdrop claim done");
+}
@@ -0,0 +1,179 @@
+//! Program test cluster shared by the example flows: the program under test, funded wallets and custom tokens.
+//!
+//! Every example uses a part of it only.
+#![allow(dead_code)]
+
+use borsh::BorshDeserialize;
+use solana_program::clock::{Clock, DEFAULT_MS_PER_SLOT};
+use solana_program::instruction::Instruction;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_program::{system_instruction, system_program};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_token::state::{Account, Mint};
+use token::entrypoint::process_instruction;
+use token::id;
+use token::instruction::TokenInstruction;
+use token::state::Config;
+
+pub struct Cluster {
+    pub ctx: ProgramTestContext,
+    /// Upgrade authority of the program, creates program config
+    pub admin: Keypair,
+}
+
+impl Cluster {
+    pub const WALLET_LAMPORTS: u64 = 5_000_000_000;
+
+    pub async fn start() -> Cluster {
+        let mut program_test = ProgramTest::new("token", id(), processor!(process_instruction));
+        let admin = Keypair::new();
+        let (program_data, _) = Pubkey::find_program_address(&[id().as_ref()], &bpf_loader_upgradeable::id());
+        let state = UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address: Some(admin.pubkey()) };
+        let account = solana_sdk::account::Account::new_data(1_000_000_000, &state, &bpf_loader_upgradeable::id());
+        program_test.add_account(program_data, account.unwrap());
+        program_test.add_account(
+            admin.pubkey(),
+            solana_sdk::account::Account::new(Cluster::WALLET_LAMPORTS, 0, &system_program::id()),
+        );
+        Cluster { ctx: program_test.start_with_context().await, admin }
+    }
+
+    /// Send `instructions` in one transaction paid by the first signer
+    pub async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signers[0].pubkey()),
+            &signers.to_vec(),
+            self.ctx.last_blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    /// New wallet holding `WALLET_LAMPORTS`
+    pub async fn wallet(&mut self) -> Keypair {
+        let wallet = Keypair::new();
+        let payer = Keypair::from_bytes(&self.ctx.payer.to_bytes()).unwrap();
+        let deposit_instr = system_instruction::transfer(&payer.pubkey(), &wallet.pubkey(), Cluster::WALLET_LAMPORTS);
+        self.process(&[deposit_instr], &[&payer]).await.unwrap();
+        wallet
+    }
+
+    /// Config admin enables instruction families `features` on top of the defaults
+    pub async fn enable_features(&mut self, features: u64) {
+        let admin = Keypair::from_bytes(&self.admin.to_bytes()).unwrap();
+        let features_instr = TokenInstruction::with_program_data(
+            TokenInstruction::set_features(admin.pubkey(), Config::DEFAULT_FEATURES | features),
+        );
+        self.process(&[features_instr], &[&admin]).await.unwrap();
+    }
+
+    /// Mint with zero decimals, `authority` pays and mints
+    pub async fn create_mint(&mut self, authority: &Keypair) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let create_instr = system_instruction::create_account(
+            &authority.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            &spl_token::id(),
+        );
+        let initialize_instr = spl_token::instruction::initialize_mint(
+            &spl_token::id(),
+            &mint.pubkey(),
+            &authority.pubkey(),
+            None,
+            0,
+        ).unwrap();
+        self.process(&[create_instr, initialize_instr], &[authority, &mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Empty associated token account of `wallet` created by `payer`
+    pub async fn associated_token_account(&mut self, payer: &Keypair, mint: &Pubkey, wallet: &Pubkey) -> Pubkey {
+        let create_instr = create_associated_token_account(&payer.pubkey(), wallet, mint, &spl_token::id());
+        self.process(&[create_instr], &[payer]).await.unwrap();
+        get_associated_token_address(wallet, mint)
+    }
+
+    /// Associated token account of `wallet` created by mint `authority`, holding `amount` minted by it
+    pub async fn mint_to_wallet(
+        &mut self,
+        authority: &Keypair,
+        mint: &Pubkey,
+        wallet: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let token_account = self.associated_token_account(authority, mint, wallet).await;
+        self.mint_to(authority, mint, &token_account, amount).await;
+        token_account
+    }
+
+    pub async fn mint_to(&mut self, authority: &Keypair, mint: &Pubkey, token_account: &Pubkey, amount: u64) {
+        let mint_to_instr = spl_token::instruction::mint_to(
+            &spl_token::id(),
+            mint,
+            token_account,
+            &authority.pubkey(),
+            &[],
+            amount,
+        ).unwrap();
+        self.process(&[mint_to_instr], &[authority]).await.unwrap();
+    }
+
+    /// Token account of `owner` outside the associated token account program, e.g. escrow temp account
+    pub async fn token_account(&mut self, payer: &Keypair, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let token_account = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let create_instr = system_instruction::create_account(
+            &payer.pubkey(),
+            &token_account.pubkey(),
+            rent.minimum_balance(Account::LEN),
+            Account::LEN as u64,
+            &spl_token::id(),
+        );
+        let initialize_instr = spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            &token_account.pubkey(),
+            mint,
+            owner,
+        ).unwrap();
+        self.process(&[create_instr, initialize_instr], &[payer, &token_account]).await.unwrap();
+        token_account.pubkey()
+    }
+
+    /// Program account state, `get_account_data_with_borsh` of program-test expects another borsh version
+    pub async fn state<T: BorshDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self.ctx.banks_client.get_account(*address).await.unwrap().unwrap();
+        T::deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn token_balance(&mut self, token_account: &Pubkey) -> u64 {
+        self.ctx.banks_client.get_packed_account_data::<Account>(*token_account).await.unwrap().amount
+    }
+
+    pub async fn exists(&mut self, address: &Pubkey) -> bool {
+        self.ctx.banks_client.get_account(*address).await.unwrap().is_some()
+    }
+
+    pub async fn unix_timestamp(&mut self) -> i64 {
+        self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp
+    }
+
+    /// Advance the clock by `slots`, roughly 400ms each
+    pub async fn warp(&mut self, slots: u64) {
+        let clock = self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+        self.ctx.warp_to_slot(clock.slot + slots).unwrap();
+        let warped = self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+        let seconds = (slots * DEFAULT_MS_PER_SLOT / 1_000) as i64;
+        self.ctx.set_sysvar(&Clock { unix_timestamp: clock.unix_timestamp + seconds, ..warped });
+        self.ctx.last_blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+    }
+}
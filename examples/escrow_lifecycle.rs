@@ -0,0 +1,89 @@
+//! Escrow lifecycle: a seller offers tokens for a price, a buyer takes the offer,
+//! a second offer nobody takes is refunded once it expires.
+//!
+//! ```text
+//! $ cargo run --example escrow_lifecycle --features test-bpf
+//! ```
+mod common;
+
+use solana_sdk::signer::Signer;
+use token::instruction::TokenInstruction;
+use token::state::{Config, Escrow};
+use common::Cluster;
+
+const OFFER: u64 = 100;
+const PRICE: u64 = 250;
+const TIMEOUT: i64 = 60;
+
+pub async fn run() {
+    let mut cluster = Cluster::start().await;
+    let (seller, buyer) = (cluster.wallet().await, cluster.wallet().await);
+    cluster.enable_features(Config::FEATURE_ESCROWS).await;
+    let goods = cluster.create_mint(&seller).await;
+    let payment = cluster.create_mint(&buyer).await;
+
+    // Seller parks the offer in a temp token account and waits for the price in its payment account
+    let temp_token = cluster.token_account(&seller, &goods, &seller.pubkey()).await;
+    cluster.mint_to(&seller, &goods, &temp_token, OFFER).await;
+    let seller_payment = cluster.associated_token_account(&seller, &payment, &seller.pubkey()).await;
+    let expires_at = cluster.unix_timestamp().await + TIMEOUT;
+    let initialize_instr = TokenInstruction::initialize_escrow(
+        seller.pubkey(),
+        temp_token,
+        seller_payment,
+        goods,
+        payment,
+        PRICE,
+        expires_at,
+    );
+    cluster.process(&[initialize_instr], &[&seller]).await.unwrap();
+
+    // Buyer pays the price and receives the whole offer, escrow closes to the seller
+    let buyer_payment = cluster.mint_to_wallet(&buyer, &payment, &buyer.pubkey(), PRICE).await;
+    let buyer_goods = cluster.associated_token_account(&buyer, &goods, &buyer.pubkey()).await;
+    let exchange_instr = TokenInstruction::exchange_escrow(
+        buyer.pubkey(),
+        buyer_payment,
+        buyer_goods,
+        seller.pubkey(),
+        seller_payment,
+        temp_token,
+        OFFER,
+    );
+    cluster.process(&[exchange_instr], &[&buyer]).await.unwrap();
+
+    assert_eq!(cluster.token_balance(&buyer_goods).await, OFFER);
+    assert_eq!(cluster.token_balance(&seller_payment).await, PRICE);
+    assert!(!cluster.exists(&Escrow::find_address(&temp_token).0).await);
+
+    // Nobody takes the second offer, seller gets it back after expiry
+    let unsold_temp_token = cluster.token_account(&seller, &goods, &seller.pubkey()).await;
+    cluster.mint_to(&seller, &goods, &unsold_temp_token, OFFER).await;
+    let seller_goods = cluster.associated_token_account(&seller, &goods, &seller.pubkey()).await;
+    let expires_at = cluster.unix_timestamp().await + TIMEOUT;
+    let initialize_instr = TokenInstruction::initialize_escrow(
+        seller.pubkey(),
+        unsold_temp_token,
+        seller_payment,
+        goods,
+        payment,
+        PRICE,
+        expires_at,
+    );
+    cluster.process(&[initialize_instr], &[&seller]).await.unwrap();
+
+    let cancel = || TokenInstruction::cancel_escrow(seller.pubkey(), seller_goods, unsold_temp_token);
+    assert!(cluster.process(&[cancel()], &[&seller]).await.is_err());
+    cluster.warp(500).await;
+    cluster.process(&[cancel()], &[&seller]).await.unwrap();
+
+    assert_eq!(cluster.token_balance(&seller_goods).await, OFFER);
+    assert!(!cluster.exists(&Escrow::find_address(&unsold_temp_token).0).await);
+}
+
+#[allow(dead_code)]
+#[tokio::main]
+async fn main() {
+    run().await;
+    println!("escrow lifecycle done");
+}
@@ -0,0 +1,76 @@
+//! Payroll run: an employer commits the Merkle root of the payroll before paying,
+//! then pays every line against the commitment, each line at most once.
+//!
+//! ```text
+//! $ cargo run --example payroll_run --features test-bpf
+//! ```
+mod common;
+
+use solana_program::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_program_test::BanksClientError;
+use solana_sdk::transaction::TransactionError;
+use token::error::TransferError;
+use token::instruction::TokenInstruction;
+use token::merkle;
+use token::state::{Config, PayoutBatch};
+use common::Cluster;
+
+const BATCH_ID: u64 = 202_401;
+const SALARIES: [u64; 3] = [1_000_000, 2_500_000, 1_750_000];
+
+pub async fn run() {
+    let mut cluster = Cluster::start().await;
+    let employer = cluster.wallet().await;
+    let employees: Vec<Keypair> = SALARIES.iter().map(|_| Keypair::new()).collect();
+    cluster.enable_features(Config::FEATURE_PAYOUT_COMMITMENTS).await;
+
+    // Payroll is committed as a whole before the first payment
+    let leaves: Vec<[u8; 32]> = employees.iter().zip(SALARIES.iter()).enumerate()
+        .map(|(index, (employee, &salary))| merkle::payout_leaf(index as u32, &employee.pubkey(), salary))
+        .collect();
+    let publish_instr = TokenInstruction::publish_payout_root(
+        employer.pubkey(),
+        BATCH_ID,
+        merkle::root(&leaves),
+        leaves.len() as u32,
+    );
+    cluster.process(&[publish_instr], &[&employer]).await.unwrap();
+
+    // Every line is paid with its proof, employees need no account beforehand
+    let pay = |index: usize| TokenInstruction::execute_payout(
+        employer.pubkey(),
+        employees[index].pubkey(),
+        BATCH_ID,
+        index as u32,
+        SALARIES[index],
+        merkle::proof(&leaves, index),
+    );
+    let payout_instrs: Vec<_> = (0..SALARIES.len()).map(pay).collect();
+    cluster.process(&payout_instrs, &[&employer]).await.unwrap();
+
+    for (employee, &salary) in employees.iter().zip(SALARIES.iter()) {
+        assert_eq!(cluster.ctx.banks_client.get_balance(employee.pubkey()).await.unwrap(), salary);
+    }
+    let (payout_batch, _) = PayoutBatch::find_address(&employer.pubkey(), BATCH_ID);
+    let batch: PayoutBatch = cluster.state(&payout_batch).await;
+    assert_eq!(batch.executed_count as usize, SALARIES.len());
+
+    // A line paid twice is rejected, e.g. when a crashed run is restarted from the beginning
+    let err = cluster.process(&[pay(1), pay(0)], &[&employer]).await.unwrap_err();
+    assert!(matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(code),
+        )) if code == TransferError::PayoutAlreadyExecuted as u32
+    ));
+}
+
+#[allow(dead_code)]
+#[tokio::main]
+async fn main() {
+    run().await;
+    println!("payroll run done");
+}
@@ -0,0 +1,84 @@
+//! Vesting grant: a company grants tokens vesting linearly after a cliff,
+//! the grantee claims what has vested and the company cancels the rest on departure.
+//!
+//! ```text
+//! $ cargo run --example vesting_grant --features test-bpf
+//! ```
+mod common;
+
+use solana_program::instruction::InstructionError;
+use solana_program_test::BanksClientError;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use token::error::TransferError;
+use token::instruction::TokenInstruction;
+use token::state::{Config, VestingSchedule};
+use common::Cluster;
+
+const GRANT: u64 = 48_000;
+const CLIFF: i64 = 100;
+const DURATION: i64 = 1_000;
+
+pub async fn run() {
+    let mut cluster = Cluster::start().await;
+    let (company, grantee) = (cluster.wallet().await, cluster.wallet().await);
+    cluster.enable_features(Config::FEATURE_VESTING).await;
+    let mint = cluster.create_mint(&company).await;
+    let company_token = cluster.mint_to_wallet(&company, &mint, &company.pubkey(), GRANT).await;
+    let (vesting, _) = VestingSchedule::find_address(&company.pubkey(), &grantee.pubkey(), &mint);
+
+    // Grant is locked in the vesting token account, nothing is claimable before the cliff
+    cluster.associated_token_account(&company, &mint, &vesting).await;
+    let grantee_token = cluster.associated_token_account(&grantee, &mint, &grantee.pubkey()).await;
+    let start = cluster.unix_timestamp().await;
+    let create_instr = TokenInstruction::create_vesting(
+        company.pubkey(),
+        company_token,
+        grantee.pubkey(),
+        mint,
+        GRANT,
+        start,
+        start + CLIFF,
+        start + DURATION,
+    );
+    cluster.process(&[create_instr], &[&company]).await.unwrap();
+
+    let claim = || TokenInstruction::claim_vested(grantee.pubkey(), grantee_token, company.pubkey(), mint);
+    let err = cluster.process(&[claim()], &[&grantee]).await.unwrap_err();
+    assert!(matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(code),
+        )) if code == TransferError::NothingVested as u32
+    ));
+
+    // Past the cliff the grantee claims the linearly vested part
+    cluster.warp(500).await;
+    cluster.process(&[claim()], &[&grantee]).await.unwrap();
+    let claimed = cluster.token_balance(&grantee_token).await;
+    assert!(claimed > 0 && claimed < GRANT);
+
+    // On departure the company cancels, vested but unclaimed tokens still go to the grantee
+    let cancel_instr = TokenInstruction::cancel_vesting(
+        company.pubkey(),
+        company_token,
+        grantee_token,
+        grantee.pubkey(),
+        mint,
+    );
+    cluster.process(&[cancel_instr], &[&company]).await.unwrap();
+
+    let vested = cluster.token_balance(&grantee_token).await;
+    let refunded = cluster.token_balance(&company_token).await;
+    assert!(vested >= claimed);
+    assert_eq!(vested + refunded, GRANT);
+    assert!(!cluster.exists(&vesting).await);
+}
+
+#[allow(dead_code)]
+#[tokio::main]
+async fn main() {
+    run().await;
+    println!("vesting grant done");
+}
@@ -0,0 +1,267 @@
+//! Typed wrappers over `AccountInfo`, validated at construction.
+//!
+//! Handlers build wrappers right after parsing accounts and work with typed data afterwards,
+//! so owner, discriminator and unpack checks can't be skipped by a new instruction.
+use std::ops::{Deref, DerefMut};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::{IsInitialized, Pack};
+use solana_program::pubkey::Pubkey;
+use crate::error::TransferError;
+use crate::state::AccountState;
+
+/// Account that signed the transaction
+pub struct SignerAccount<'a, 'b> {
+    pub info: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> SignerAccount<'a, 'b> {
+    pub fn new(info: &'b AccountInfo<'a>) -> Result<Self, ProgramError> {
+        if !info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature)
+        }
+        Ok(SignerAccount { info })
+    }
+
+    pub fn key(&self) -> &'b Pubkey {
+        self.info.key
+    }
+}
+
+/// Account the transaction lets the program modify
+pub struct WritableAccount<'a, 'b> {
+    pub info: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> WritableAccount<'a, 'b> {
+    pub fn new(info: &'b AccountInfo<'a>) -> Result<Self, ProgramError> {
+        if !info.is_writable {
+            return Err(TransferError::AccountNonWritable.into())
+        }
+        Ok(WritableAccount { info })
+    }
+
+    pub fn key(&self) -> &'b Pubkey {
+        self.info.key
+    }
+}
+
+/// Account of the program with `program_id`, passed for a CPI into it
+pub struct InvokedProgram<'a, 'b> {
+    pub info: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> InvokedProgram<'a, 'b> {
+    pub fn new(info: &'b AccountInfo<'a>, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        if info.key != program_id {
+            return Err(ProgramError::IncorrectProgramId)
+        }
+        Ok(InvokedProgram { info })
+    }
+}
+
+/// Initialized SPL token account
+pub struct TokenAccount<'a, 'b> {
+    pub info: &'b AccountInfo<'a>,
+    pub account: spl_token::state::Account,
+}
+
+impl<'a, 'b> TokenAccount<'a, 'b> {
+    pub fn new(info: &'b AccountInfo<'a>) -> Result<Self, ProgramError> {
+        let account = unpack_spl_token(info)?;
+        Ok(TokenAccount { info, account })
+    }
+
+    pub fn key(&self) -> &'b Pubkey {
+        self.info.key
+    }
+}
+
+impl Deref for TokenAccount<'_, '_> {
+    type Target = spl_token::state::Account;
+
+    fn deref(&self) -> &Self::Target {
+        &self.account
+    }
+}
+
+/// Initialized SPL token mint
+pub struct MintAccount<'a, 'b> {
+    pub info: &'b AccountInfo<'a>,
+    pub mint: spl_token::state::Mint,
+}
+
+impl<'a, 'b> MintAccount<'a, 'b> {
+    pub fn new(info: &'b AccountInfo<'a>) -> Result<Self, ProgramError> {
+        let mint = unpack_spl_token(info)?;
+        Ok(MintAccount { info, mint })
+    }
+
+    pub fn key(&self) -> &'b Pubkey {
+        self.info.key
+    }
+}
+
+impl Deref for MintAccount<'_, '_> {
+    type Target = spl_token::state::Mint;
+
+    fn deref(&self) -> &Self::Target {
+        &self.mint
+    }
+}
+
+/// Account owned by this program holding state `T`, see `AccountState::load`
+pub struct ProgramAccount<'a, 'b, T: AccountState> {
+    pub info: &'b AccountInfo<'a>,
+    pub state: T,
+}
+
+impl<'a, 'b, T: AccountState> ProgramAccount<'a, 'b, T> {
+    pub fn new(info: &'b AccountInfo<'a>, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        let state = T::load(info, program_id)?;
+        Ok(ProgramAccount { info, state })
+    }
+
+    pub fn key(&self) -> &'b Pubkey {
+        self.info.key
+    }
+
+    pub fn save(&self) -> ProgramResult {
+        self.state.save(self.info)
+    }
+}
+
+impl<T: AccountState> Deref for ProgramAccount<'_, '_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state
+    }
+}
+
+impl<T: AccountState> DerefMut for ProgramAccount<'_, '_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.state
+    }
+}
+
+fn unpack_spl_token<T: Pack + IsInitialized>(info: &AccountInfo) -> Result<T, ProgramError> {
+    if *info.owner != spl_token::id() {
+        return Err(TransferError::InvalidOwner.into())
+    }
+    T::unpack(&info.try_borrow_data()?)
+}
+
+#[cfg(test)]
+mod account_test {
+    use solana_program::account_info::AccountInfo;
+    use solana_program::program_error::ProgramError;
+    use solana_program::program_option::COption;
+    use solana_program::program_pack::Pack;
+    use solana_program::pubkey::Pubkey;
+    use borsh::BorshSerialize;
+    use crate::account::{InvokedProgram, MintAccount, ProgramAccount, SignerAccount, TokenAccount, WritableAccount};
+    use crate::error::TransferError;
+    use crate::state::{AccountState, AccountType, SavingsVault};
+
+    #[test]
+    fn when_not_signer_expect_error() {
+        let (key, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (0, vec![]);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+        assert_eq!(SignerAccount::new(&info).err(), Some(ProgramError::MissingRequiredSignature));
+    }
+
+    #[test]
+    fn when_not_writable_expect_error() {
+        let (key, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (0, vec![]);
+        let info = AccountInfo::new(&key, true, false, &mut lamports, &mut data, &owner, false, 0);
+
+        assert_eq!(WritableAccount::new(&info).err(), Some(TransferError::AccountNonWritable.into()));
+    }
+
+    #[test]
+    fn when_invoked_program_of_other_id_expect_error() {
+        let (key, owner) = (solana_program::system_program::id(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (0, vec![]);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, true, 0);
+
+        assert!(InvokedProgram::new(&info, &solana_program::system_program::id()).is_ok());
+        assert_eq!(InvokedProgram::new(&info, &spl_token::id()).err(), Some(ProgramError::IncorrectProgramId));
+    }
+
+    #[test]
+    fn when_token_account_expect_unpacked() {
+        let (key, owner) = (Pubkey::new_unique(), spl_token::id());
+        let token_account = spl_token::state::Account {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount: 1_000,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(token_account, &mut data).unwrap();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+
+        let wrapped = TokenAccount::new(&info).unwrap();
+        assert_eq!(wrapped.account, token_account);
+        assert_eq!(wrapped.amount, 1_000);
+    }
+
+    #[test]
+    fn when_token_account_wrong_owner_expect_error() {
+        let (key, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (0, vec![0; spl_token::state::Account::LEN]);
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+
+        assert_eq!(TokenAccount::new(&info).err(), Some(TransferError::InvalidOwner.into()));
+    }
+
+    #[test]
+    fn when_mint_uninitialized_expect_error() {
+        let (key, owner) = (Pubkey::new_unique(), spl_token::id());
+        let (mut lamports, mut data) = (0, vec![0; spl_token::state::Mint::LEN]);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+        assert_eq!(MintAccount::new(&info).err(), Some(ProgramError::UninitializedAccount));
+    }
+
+    #[test]
+    fn when_program_account_wrong_type_expect_error() {
+        let (key, program_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (0, vec![AccountType::Bond as u8; SavingsVault::LEN]);
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+
+        let result = ProgramAccount::<SavingsVault>::new(&info, &program_id);
+        assert_eq!(result.err(), Some(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn when_program_account_save_expect_persisted() {
+        let (key, program_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let savings_vault = SavingsVault {
+            account_type: AccountType::SavingsVault,
+            owner: Pubkey::new_unique(),
+            granularity: 1_000,
+            saved: 0,
+            bump: 255,
+        };
+        let (mut lamports, mut data) = (0, savings_vault.try_to_vec().unwrap());
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+
+        let mut wrapped = ProgramAccount::<SavingsVault>::new(&info, &program_id).unwrap();
+        wrapped.saved = 500;
+        wrapped.save().unwrap();
+
+        let reloaded = ProgramAccount::<SavingsVault>::new(&info, &program_id).unwrap();
+        assert_eq!(reloaded.saved, 500);
+    }
+}
@@ -0,0 +1,341 @@
+//! Command line interface of the program for operators, enabled by `cli` feature.
+//!
+//! RPC endpoint and keypair default to the Solana CLI config, the same as `solana` command does.
+//! A profile, built-in or from `profiles.json` next to that config, switches endpoint, deployment, commitment
+//! and fee strategy at once, explicit flags still take precedence.
+//! Recipients are given as pubkeys or as aliases of the address book kept next to that config.
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::exit;
+use std::str::FromStr;
+use clap::{crate_version, App, AppSettings, Arg, ArgMatches, SubCommand};
+use solana_clap_utils::input_parsers::{pubkey_of, value_of};
+use solana_clap_utils::input_validators::{is_keypair, is_parsable, is_url_or_moniker, is_valid_pubkey};
+use solana_clap_utils::input_validators::normalize_to_url_if_moniker;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature};
+use spl_associated_token_account::get_associated_token_address;
+use token::client::address_book::{checksum, AddressBook, Contact};
+use token::client::config::{Profile, Profiles, ProgramConfig};
+use token::client::error::ClientResult;
+use token::client::rpc::TokenClient;
+
+const ADDRESS_BOOK_FILE: &str = "address-book.json";
+const PROFILES_FILE: &str = "profiles.json";
+
+fn pubkey_arg<'a, 'b>(name: &'a str, value_name: &'a str, index: u64, help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name).index(index).value_name(value_name).required(true).validator(is_valid_pubkey).help(help)
+}
+
+fn recipient_arg<'a, 'b>(name: &'a str, value_name: &'a str, index: u64, help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name).index(index).value_name(value_name).required(true).help(help)
+}
+
+fn amount_arg<'a, 'b>(index: u64) -> Arg<'a, 'b> {
+    Arg::with_name("amount")
+        .index(index)
+        .value_name("AMOUNT")
+        .required(true)
+        .validator(is_parsable::<u64>)
+        .help("Amount in base units, lamports or the smallest unit of the mint")
+}
+
+fn mint_arg<'a, 'b>(help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name("mint").long("mint").value_name("MINT").validator(is_valid_pubkey).help(help)
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("token-cli")
+        .about("Transfers and approvals through the token program")
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("config_file")
+                .long("config")
+                .value_name("PATH")
+                .global(true)
+                .help("Solana CLI config file"),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .value_name("NAME")
+                .global(true)
+                .help("Cluster profile: mainnet, devnet or one of `profiles.json` next to the Solana CLI config"),
+        )
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .short("u")
+                .long("url")
+                .value_name("URL_OR_MONIKER")
+                .global(true)
+                .validator(is_url_or_moniker)
+                .help("RPC endpoint or moniker: mainnet-beta, testnet, devnet, localhost"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .short("k")
+                .long("keypair")
+                .value_name("PATH")
+                .global(true)
+                .validator(is_keypair)
+                .help("Authority keypair file, signs the transfer or approval"),
+        )
+        .arg(
+            Arg::with_name("fee_payer")
+                .long("fee-payer")
+                .value_name("PATH")
+                .global(true)
+                .validator(is_keypair)
+                .help("Fee payer keypair file, the authority by default"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .global(true)
+                .validator(is_valid_pubkey)
+                .help("Program deployment to call, the ID this CLI was built with by default"),
+        )
+        .arg(
+            Arg::with_name("address_book")
+                .long("address-book")
+                .value_name("PATH")
+                .global(true)
+                .help("Address book file, `address-book.json` next to the Solana CLI config by default"),
+        )
+        .arg(
+            Arg::with_name("memo")
+                .long("memo")
+                .value_name("TEXT")
+                .global(true)
+                .help("Memo attached to the transaction, overrides the memo stored for the contact"),
+        )
+        .subcommand(
+            SubCommand::with_name("transfer-lamports")
+                .about("Transfer lamports from the authority")
+                .arg(recipient_arg("to", "TO", 1, "Recipient wallet or its alias"))
+                .arg(amount_arg(2)),
+        )
+        .subcommand(
+            SubCommand::with_name("transfer-spl")
+                .about("Transfer custom token from a token account of the authority")
+                .arg(pubkey_arg("from", "FROM", 1, "Source token account"))
+                .arg(recipient_arg("to", "TO", 2, "Recipient token account, or alias of a wallet paid to its ATA"))
+                .arg(amount_arg(3))
+                .arg(mint_arg("Mint of both token accounts, the preferred mint of the contact by default")),
+        )
+        .subcommand(
+            SubCommand::with_name("approve")
+                .about("Let a delegate transfer custom token from a token account of the authority")
+                .arg(pubkey_arg("from", "FROM", 1, "Source token account"))
+                .arg(recipient_arg("delegate", "DELEGATE", 2, "Delegate or its alias"))
+                .arg(amount_arg(3)),
+        )
+        .subcommand(
+            SubCommand::with_name("address-book")
+                .about("Manage recipient aliases")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Add or replace contact, it stays unconfirmed until its checksum is typed")
+                        .arg(recipient_arg("alias", "ALIAS", 1, "Alias"))
+                        .arg(pubkey_arg("wallet", "WALLET", 2, "Wallet"))
+                        .arg(mint_arg("Mint transferred to the contact when none is given"))
+                        .arg(
+                            Arg::with_name("contact_memo")
+                                .long("contact-memo")
+                                .value_name("TEXT")
+                                .help("Memo attached to every transfer to the contact, e.g. deposit tag"),
+                        )
+                        .arg(
+                            Arg::with_name("memo_required")
+                                .long("memo-required")
+                                .help("Reject transfers to the contact without memo"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("Remove contact")
+                        .arg(recipient_arg("alias", "ALIAS", 1, "Alias")),
+                )
+                .subcommand(SubCommand::with_name("list").about("List contacts"))
+                .subcommand(
+                    SubCommand::with_name("checksum")
+                        .about("Checksum of a wallet, for recipients to communicate along with the wallet")
+                        .arg(pubkey_arg("wallet", "WALLET", 1, "Wallet")),
+                ),
+        )
+}
+
+fn read_keypair(path: &str) -> Keypair {
+    read_keypair_file(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read keypair {}: {}", path, e);
+        exit(1)
+    })
+}
+
+/// Recipient given as pubkey or resolved from the address book
+struct Recipient {
+    address: Pubkey,
+    contact: Option<(String, Contact)>,
+}
+
+impl Recipient {
+    /// Pubkey as is, otherwise alias of a contact. Operator types the checksum of an unconfirmed contact
+    fn resolve(address_book: &mut AddressBook, value: &str) -> ClientResult<Self> {
+        if let Ok(address) = Pubkey::from_str(value) {
+            return Ok(Recipient { address, contact: None })
+        }
+        let contact = address_book.get(value)?.clone();
+        if !contact.confirmed {
+            print!("First transfer to {}, wallet {}. Type its checksum from the recipient: ", value, contact.wallet);
+            io::stdout().flush()?;
+            let mut typed = String::new();
+            io::stdin().read_line(&mut typed)?;
+            address_book.confirm(value, &typed)?;
+        }
+        Ok(Recipient { address: contact.wallet()?, contact: Some((value.to_string(), contact)) })
+    }
+
+    fn memo(&self, memo: Option<&str>) -> ClientResult<Option<String>> {
+        let memo = memo.map(str::to_string);
+        match &self.contact {
+            Some((alias, contact)) => contact.transfer_memo(alias, memo),
+            None => Ok(memo),
+        }
+    }
+
+    fn preferred_mint(&self) -> ClientResult<Option<Pubkey>> {
+        match &self.contact {
+            Some((_, contact)) => contact.mint(),
+            None => Ok(None),
+        }
+    }
+}
+
+fn process(
+    client: TokenClient,
+    authority: &Keypair,
+    address_book: &mut AddressBook,
+    (name, matches): (&str, Option<&ArgMatches>),
+) -> ClientResult<Signature> {
+    let matches = matches.unwrap();
+    let amount = value_of::<u64>(matches, "amount").unwrap();
+    let recipient = matches.value_of("to").or_else(|| matches.value_of("delegate")).unwrap();
+    let recipient = Recipient::resolve(address_book, recipient)?;
+    let client = match recipient.memo(matches.value_of("memo"))? {
+        Some(memo) => client.with_memo(memo),
+        None => client,
+    };
+    match name {
+        "transfer-lamports" => client.transfer_lamports(authority, recipient.address, amount),
+        "transfer-spl" => {
+            let mint = pubkey_of(matches, "mint").or(recipient.preferred_mint()?).unwrap_or_else(|| {
+                eprintln!("Mint is required, the recipient has no preferred one");
+                exit(1)
+            });
+            let to_spl_token = match recipient.contact {
+                Some(_) => get_associated_token_address(&recipient.address, &mint),
+                None => recipient.address,
+            };
+            client.transfer_spl_token(authority, pubkey_of(matches, "from").unwrap(), to_spl_token, mint, amount)
+        }
+        "approve" => client.approve(authority, pubkey_of(matches, "from").unwrap(), recipient.address, amount),
+        _ => unreachable!(),
+    }
+}
+
+fn process_address_book(
+    address_book: &mut AddressBook,
+    (name, matches): (&str, Option<&ArgMatches>),
+) -> ClientResult<()> {
+    let matches = matches.unwrap();
+    match name {
+        "add" => {
+            let contact = Contact {
+                mint: matches.value_of("mint").map(str::to_string),
+                memo: matches.value_of("contact_memo").map(str::to_string),
+                memo_required: matches.is_present("memo_required"),
+                ..Contact::new(&pubkey_of(matches, "wallet").unwrap())
+            };
+            address_book.add(matches.value_of("alias").unwrap(), contact)
+        }
+        "remove" => address_book.remove(matches.value_of("alias").unwrap()),
+        "list" => {
+            for (alias, contact) in address_book.contacts() {
+                let status = if contact.confirmed { "confirmed" } else { "unconfirmed" };
+                println!("{} {} mint={:?} memo={:?} {}", alias, contact.wallet, contact.mint, contact.memo, status);
+            }
+            Ok(())
+        }
+        "checksum" => {
+            println!("{}", checksum(&pubkey_of(matches, "wallet").unwrap()));
+            Ok(())
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Profile of `name` with its deployment, custom profiles are kept next to the Solana CLI config
+fn load_profile(config_file: Option<&str>, name: &str) -> ClientResult<(ProgramConfig, Profile)> {
+    let profile = Profiles::open(Path::new(config_file.unwrap_or_default()).with_file_name(PROFILES_FILE))?.get(name)?;
+    Ok((profile.program_config(name)?, profile))
+}
+
+fn main() {
+    let matches = app().get_matches();
+    let config_file = matches.value_of("config_file").or_else(|| solana_cli_config::CONFIG_FILE.as_deref());
+    let cli_config = match config_file {
+        Some(config_file) => solana_cli_config::Config::load(config_file).unwrap_or_default(),
+        None => solana_cli_config::Config::default(),
+    };
+    let address_book_path = match matches.value_of("address_book") {
+        Some(path) => Path::new(path).to_path_buf(),
+        None => Path::new(config_file.unwrap_or_default()).with_file_name(ADDRESS_BOOK_FILE),
+    };
+    let mut address_book = AddressBook::open(address_book_path).unwrap_or_else(|e| {
+        eprintln!("Failed to open address book: {}", e);
+        exit(1)
+    });
+
+    let profile = matches.value_of("profile").map(|name| {
+        load_profile(config_file, name).unwrap_or_else(|e| {
+            eprintln!("Failed to load profile: {}", e);
+            exit(1)
+        })
+    });
+
+    let result = match matches.subcommand() {
+        ("address-book", Some(matches)) => process_address_book(&mut address_book, matches.subcommand()).map(|_| None),
+        subcommand => {
+            let (mut config, profile) =
+                profile.unwrap_or_else(|| (ProgramConfig::default(), Profile::new(&cli_config.json_rpc_url)));
+            let json_rpc_url = matches.value_of("json_rpc_url").unwrap_or(&profile.json_rpc_url);
+            let commitment = profile.commitment_config();
+            let rpc_client = RpcClient::new_with_commitment(normalize_to_url_if_moniker(json_rpc_url), commitment);
+            let authority = read_keypair(matches.value_of("keypair").unwrap_or(&cli_config.keypair_path));
+            let fee_payer = matches.value_of("fee_payer").map(read_keypair);
+            if let Some(program_id) = pubkey_of(&matches, "program_id") {
+                config.program_id = program_id;
+            }
+            let client = TokenClient::new(&rpc_client, fee_payer.as_ref().unwrap_or(&authority))
+                .with_config(config)
+                .with_fee_strategy(profile.fee_strategy);
+            process(client, &authority, &mut address_book, subcommand).map(Some)
+        }
+    };
+
+    match result {
+        Ok(Some(signature)) => println!("{}", signature),
+        Ok(None) => {}
+        Err(e) => {
+            match e.transfer_error() {
+                Some(transfer_error) => eprintln!("Program error: {}", transfer_error),
+                None => eprintln!("{}", e),
+            }
+            exit(1)
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Address book mapping human aliases to recipients, so operators never paste raw base58 destinations.
+//!
+//! A contact is unconfirmed until the operator once types the checksum of its wallet,
+//! compared against the one the recipient communicated out of band.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use crate::client::error::{ClientError, ClientResult};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    /// Wallet, base58 encoded
+    pub wallet: String,
+    /// Mint transferred to the contact when none is given, base58 encoded
+    #[serde(default)]
+    pub mint: Option<String>,
+    /// Memo the recipient expects on every transfer, e.g. exchange deposit tag
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Transfers without memo are rejected
+    #[serde(default)]
+    pub memo_required: bool,
+    /// Operator confirmed the checksum of the wallet
+    #[serde(default)]
+    pub confirmed: bool,
+}
+
+impl Contact {
+    pub fn new(wallet: &Pubkey) -> Self {
+        Contact { wallet: wallet.to_string(), mint: None, memo: None, memo_required: false, confirmed: false }
+    }
+
+    pub fn wallet(&self) -> ClientResult<Pubkey> {
+        self.wallet.parse().map_err(|_| ClientError::InvalidAddressBook)
+    }
+
+    pub fn mint(&self) -> ClientResult<Option<Pubkey>> {
+        self.mint.as_ref().map(|mint| mint.parse().map_err(|_| ClientError::InvalidAddressBook)).transpose()
+    }
+
+    /// Memo of a transfer to the contact, `memo` given by the operator takes precedence over the stored one
+    pub fn transfer_memo(&self, alias: &str, memo: Option<String>) -> ClientResult<Option<String>> {
+        match memo.or_else(|| self.memo.clone()) {
+            None if self.memo_required => Err(ClientError::MemoRequired(alias.to_string())),
+            memo => Ok(memo),
+        }
+    }
+}
+
+/// Short checksum of `wallet` read aloud or typed by operators, e.g. `9F3A-07C2`
+pub fn checksum(wallet: &Pubkey) -> String {
+    let hash = Sha256::digest(wallet.as_ref());
+    format!("{:02X}{:02X}-{:02X}{:02X}", hash[0], hash[1], hash[2], hash[3])
+}
+
+/// Contacts persisted as a JSON object keyed by alias, rewritten as a whole on every change
+pub struct AddressBook {
+    path: PathBuf,
+    contacts: BTreeMap<String, Contact>,
+}
+
+impl AddressBook {
+    /// Open address book at `path`, missing file starts empty
+    pub fn open<P: AsRef<Path>>(path: P) -> ClientResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let contacts = if path.exists() { serde_json::from_slice(&fs::read(&path)?)? } else { BTreeMap::new() };
+        Ok(AddressBook { path, contacts })
+    }
+
+    pub fn contacts(&self) -> &BTreeMap<String, Contact> {
+        &self.contacts
+    }
+
+    pub fn get(&self, alias: &str) -> ClientResult<&Contact> {
+        self.contacts.get(alias).ok_or_else(|| ClientError::UnknownAlias(alias.to_string()))
+    }
+
+    /// Add contact unconfirmed, replacing one with the same alias
+    pub fn add(&mut self, alias: &str, mut contact: Contact) -> ClientResult<()> {
+        contact.confirmed = false;
+        self.contacts.insert(alias.to_string(), contact);
+        self.save()
+    }
+
+    pub fn remove(&mut self, alias: &str) -> ClientResult<()> {
+        self.contacts.remove(alias).ok_or_else(|| ClientError::UnknownAlias(alias.to_string()))?;
+        self.save()
+    }
+
+    /// Mark contact confirmed when `typed` matches the checksum of its wallet, case insensitive
+    pub fn confirm(&mut self, alias: &str, typed: &str) -> ClientResult<()> {
+        let contact = self.contacts.get_mut(alias).ok_or_else(|| ClientError::UnknownAlias(alias.to_string()))?;
+        if !typed.trim().eq_ignore_ascii_case(&checksum(&contact.wallet()?)) {
+            return Err(ClientError::ChecksumMismatch(alias.to_string()))
+        }
+        contact.confirmed = true;
+        self.save()
+    }
+
+    /// Write to a temporary file renamed over the address book, so a crash never leaves it truncated
+    fn save(&self) -> ClientResult<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&self.contacts)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod address_book_test {
+    use std::env::temp_dir;
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::address_book::{checksum, AddressBook, Contact};
+    use crate::client::error::ClientError;
+
+    fn address_book(name: &str) -> AddressBook {
+        let path = temp_dir().join(format!("address_book_test_{}_{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        AddressBook::open(path).unwrap()
+    }
+
+    #[test]
+    fn when_reopened_expect_contacts_persisted_unconfirmed() {
+        let mut book = address_book("reopened");
+        let wallet = Pubkey::new_unique();
+        let contact = Contact { memo_required: true, confirmed: true, ..Contact::new(&wallet) };
+
+        book.add("treasury", contact).unwrap();
+        let reopened = AddressBook::open(&book.path).unwrap();
+
+        let contact = reopened.get("treasury").unwrap();
+        assert_eq!(contact.wallet().unwrap(), wallet);
+        assert!(contact.memo_required);
+        assert!(!contact.confirmed);
+    }
+
+    #[test]
+    fn when_checksum_typed_expect_confirmed() {
+        let mut book = address_book("confirmed");
+        let wallet = Pubkey::new_unique();
+        book.add("exchange", Contact::new(&wallet)).unwrap();
+
+        book.confirm("exchange", &checksum(&wallet).to_lowercase()).unwrap();
+
+        assert!(book.get("exchange").unwrap().confirmed);
+    }
+
+    #[test]
+    fn when_wrong_checksum_expect_unconfirmed() {
+        let mut book = address_book("mismatch");
+        book.add("exchange", Contact::new(&Pubkey::new_unique())).unwrap();
+
+        let result = book.confirm("exchange", &checksum(&Pubkey::new_unique()));
+
+        assert!(matches!(result, Err(ClientError::ChecksumMismatch(_))));
+        assert!(!book.get("exchange").unwrap().confirmed);
+    }
+
+    #[test]
+    fn when_unknown_alias_expect_error() {
+        let book = address_book("unknown");
+
+        assert!(matches!(book.get("nobody"), Err(ClientError::UnknownAlias(alias)) if alias == "nobody"));
+    }
+
+    #[test]
+    fn when_memo_required_and_missing_expect_error() {
+        let contact = Contact { memo_required: true, ..Contact::new(&Pubkey::new_unique()) };
+        let tagged = Contact { memo: Some("104729".to_string()), ..contact.clone() };
+
+        assert!(matches!(contact.transfer_memo("exchange", None), Err(ClientError::MemoRequired(_))));
+        assert_eq!(tagged.transfer_memo("exchange", None).unwrap(), Some("104729".to_string()));
+        assert_eq!(tagged.transfer_memo("exchange", Some("7".to_string())).unwrap(), Some("7".to_string()));
+    }
+}
@@ -0,0 +1,366 @@
+//! Alerting on program activity for operational monitoring. Rules are evaluated over decoded events,
+//! e.g. records of `EventConsumer` or `GeyserSink`, and over failed transactions of a Geyser stream.
+//! Raised alerts go out through an `AlertSink`, a signed webhook or PagerDuty Events API v2.
+//!
+//! Time is passed in as unix seconds, so a replayed stream is evaluated in its own time. Velocity and
+//! spike rules raise an alert when their limit is crossed, not again until activity drops below it.
+use std::collections::{HashMap, HashSet, VecDeque};
+use borsh::BorshSerialize;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use crate::client::error::ClientResult;
+use crate::client::events::EventFields;
+use crate::client::geyser::{program_instructions, StreamItem, TransactionUpdate};
+use crate::client::policy::parse_transfers;
+use crate::client::webhook::{sign, SIGNATURE_HEADER};
+use crate::event::EventRecord;
+
+pub const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+const SOURCE: &str = "solana-token";
+
+#[derive(Debug, Clone)]
+pub enum AlertRule {
+    /// Event moving at least `min_amount`, of `mint` when set, raised as warning
+    Threshold { min_amount: u64, mint: Option<Pubkey> },
+    /// Events of an account moving more than `max_amount` within `window` seconds, raised as error
+    Velocity { max_amount: u64, window: i64 },
+    /// Event with any of the accounts, raised as critical
+    Denylist(HashSet<Pubkey>),
+    /// More than `max_failures` failed transfer transactions within `window` seconds, raised as error
+    FailedTransfers { max_failures: usize, window: i64 },
+}
+
+/// Severity levels of PagerDuty
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Alert {
+    /// `threshold`, `velocity`, `denylist` or `failed_transfers`
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub summary: String,
+    /// Sequence of the event raising the alert
+    pub sequence: Option<u64>,
+    pub accounts: Vec<String>,
+    pub time: i64,
+}
+
+impl Alert {
+    fn new(
+        rule: &'static str,
+        severity: Severity,
+        summary: String,
+        sequence: Option<u64>,
+        accounts: &[Pubkey],
+        time: i64,
+    ) -> Self {
+        Alert { rule, severity, summary, sequence, accounts: accounts.iter().map(Pubkey::to_string).collect(), time }
+    }
+}
+
+pub struct AlertEngine {
+    program_id: Pubkey,
+    rules: Vec<AlertRule>,
+    /// Times and amounts moved by account within the longest velocity window
+    flows: HashMap<Pubkey, VecDeque<(i64, u64)>>,
+    /// Times of failed transfer transactions within the longest spike window
+    failures: VecDeque<i64>,
+}
+
+impl AlertEngine {
+    pub fn new(program_id: Pubkey, rules: Vec<AlertRule>) -> Self {
+        AlertEngine { program_id, rules, flows: HashMap::new(), failures: VecDeque::new() }
+    }
+
+    pub fn event(&mut self, record: &EventRecord, now: i64) -> Vec<Alert> {
+        let fields = EventFields::new(&record.event);
+        let mut alerts = vec![];
+        for rule in &self.rules {
+            match rule {
+                AlertRule::Threshold { min_amount, mint } => {
+                    let mint_matches = mint.is_none_or(|mint| fields.mint == Some(mint));
+                    if let Some(amount) = fields.amount.filter(|amount| amount >= min_amount && mint_matches) {
+                        let summary = format!("{} of {} reached threshold {}", fields.name, amount, min_amount);
+                        let (sequence, accounts) = (record.sequence, &fields.accounts);
+                        alerts.push(Alert::new("threshold", Severity::Warning, summary, sequence, accounts, now));
+                    }
+                }
+                AlertRule::Denylist(denied) => {
+                    let hits: Vec<Pubkey> = fields.accounts
+                        .iter()
+                        .filter(|account| denied.contains(account))
+                        .copied()
+                        .collect();
+                    if !hits.is_empty() {
+                        let summary = format!("{} involves denylisted accounts", fields.name);
+                        alerts.push(Alert::new("denylist", Severity::Critical, summary, record.sequence, &hits, now));
+                    }
+                }
+                AlertRule::Velocity { .. } | AlertRule::FailedTransfers { .. } => {}
+            }
+        }
+        if let Some(amount) = fields.amount {
+            alerts.extend(self.velocity(&fields.accounts, amount, record.sequence, now));
+        }
+        alerts
+    }
+
+    /// Failed transactions moving value through the program count towards spike rules, others are ignored
+    pub fn transaction(&mut self, update: &TransactionUpdate, now: i64) -> Vec<Alert> {
+        let window = self.max_window(|rule| match rule {
+            AlertRule::FailedTransfers { window, .. } => Some(*window),
+            _ => None,
+        });
+        let window = match window {
+            Some(window) if update.failed && self.moves_value(update) => window,
+            _ => return vec![],
+        };
+        self.failures.push_back(now);
+        prune(&mut self.failures, |time| *time, now - window);
+
+        let mut alerts = vec![];
+        for rule in &self.rules {
+            if let AlertRule::FailedTransfers { max_failures, window } = rule {
+                let failures = self.failures.iter().filter(|time| **time > now - window).count();
+                if failures == max_failures + 1 {
+                    let summary = format!("{} failed transfers within {}s, above {}", failures, window, max_failures);
+                    alerts.push(Alert::new("failed_transfers", Severity::Error, summary, None, &[], now));
+                }
+            }
+        }
+        alerts
+    }
+
+    fn velocity(&mut self, accounts: &[Pubkey], amount: u64, sequence: Option<u64>, now: i64) -> Vec<Alert> {
+        let longest = match self.max_window(|rule| match rule {
+            AlertRule::Velocity { window, .. } => Some(*window),
+            _ => None,
+        }) {
+            Some(window) => window,
+            None => return vec![],
+        };
+        self.flows.retain(|_, flow| flow.back().is_some_and(|(time, _)| *time > now - longest));
+
+        let mut alerts = vec![];
+        for account in accounts {
+            let flow = self.flows.entry(*account).or_default();
+            flow.push_back((now, amount));
+            prune(flow, |(time, _)| *time, now - longest);
+            for rule in &self.rules {
+                if let AlertRule::Velocity { max_amount, window } = rule {
+                    let moved = flow
+                        .iter()
+                        .filter(|(time, _)| *time > now - window)
+                        .fold(0u64, |moved, (_, amount)| moved.saturating_add(*amount));
+                    // Raised only by the event crossing the limit
+                    if moved > *max_amount && moved - amount <= *max_amount {
+                        let summary = format!("{} moved {} within {}s, above {}", account, moved, window, max_amount);
+                        alerts.push(Alert::new("velocity", Severity::Error, summary, sequence, &[*account], now));
+                    }
+                }
+            }
+        }
+        alerts
+    }
+
+    fn moves_value(&self, update: &TransactionUpdate) -> bool {
+        program_instructions(&self.program_id, update).iter().any(|item| match item {
+            StreamItem::Instruction { instruction, accounts, .. } => instruction
+                .try_to_vec()
+                .is_ok_and(|data| !parse_transfers(accounts, &data).is_empty()),
+            _ => false,
+        })
+    }
+
+    fn max_window<F: Fn(&AlertRule) -> Option<i64>>(&self, window: F) -> Option<i64> {
+        self.rules.iter().filter_map(window).max()
+    }
+}
+
+/// Drop entries at or before `since` from the front of time ordered `entries`
+fn prune<T, F: Fn(&T) -> i64>(entries: &mut VecDeque<T>, time: F, since: i64) {
+    while entries.front().is_some_and(|entry| time(entry) <= since) {
+        entries.pop_front();
+    }
+}
+
+pub trait AlertSink {
+    fn send(&self, alert: &Alert) -> ClientResult<()>;
+}
+
+/// `POST`s alert as JSON signed like webhook notifications, see `verify_signature`
+pub struct WebhookAlertSink {
+    url: String,
+    secret: Vec<u8>,
+    client: reqwest::blocking::Client,
+}
+
+impl WebhookAlertSink {
+    pub fn new(url: String, secret: Vec<u8>) -> Self {
+        WebhookAlertSink { url, secret, client: reqwest::blocking::Client::new() }
+    }
+}
+
+impl AlertSink for WebhookAlertSink {
+    fn send(&self, alert: &Alert) -> ClientResult<()> {
+        let body = serde_json::to_vec(alert)?;
+        self.client
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, sign(&self.secret, &body))
+            .body(body)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Triggers PagerDuty incidents through Events API v2 of a service integration
+pub struct PagerDutySink {
+    routing_key: String,
+    url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl PagerDutySink {
+    pub fn new(routing_key: String) -> Self {
+        PagerDutySink { routing_key, url: PAGERDUTY_EVENTS_URL.to_string(), client: reqwest::blocking::Client::new() }
+    }
+
+    /// Events API endpoint other than `PAGERDUTY_EVENTS_URL`, e.g. of a proxy
+    pub fn with_url(mut self, url: String) -> Self {
+        self.url = url;
+        self
+    }
+
+    fn trigger(&self, alert: &Alert) -> serde_json::Value {
+        json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "payload": {
+                "summary": alert.summary,
+                "source": SOURCE,
+                "severity": alert.severity,
+                "component": alert.rule,
+                "custom_details": alert,
+            },
+        })
+    }
+}
+
+impl AlertSink for PagerDutySink {
+    fn send(&self, alert: &Alert) -> ClientResult<()> {
+        self.client.post(&self.url).json(&self.trigger(alert)).send()?.error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod alerts_test {
+    use borsh::BorshSerialize;
+    use solana_sdk::instruction::CompiledInstruction;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use crate::client::alerts::{Alert, AlertEngine, AlertRule, PagerDutySink, Severity};
+    use crate::client::geyser::TransactionUpdate;
+    use crate::event::{EventRecord, TipEvent, TokenEvent};
+    use crate::instruction::TokenInstruction;
+
+    fn tip(sequence: u64, tipper: Pubkey, amount: u64) -> EventRecord {
+        EventRecord {
+            sequence: Some(sequence),
+            stream: Some(Pubkey::new_from_array([3; 32])),
+            event: TokenEvent::Tip(TipEvent {
+                creator: Pubkey::new_unique(),
+                tipper,
+                amount,
+                tipper_total: amount,
+                creator_total: amount,
+            }),
+        }
+    }
+
+    fn rules(alerts: &[Alert]) -> Vec<&'static str> {
+        alerts.iter().map(|alert| alert.rule).collect()
+    }
+
+    #[test]
+    fn when_threshold_reached_or_denylisted_expect_alerts() {
+        let tipper = Pubkey::new_unique();
+        let mut engine = AlertEngine::new(Pubkey::new_unique(), vec![
+            AlertRule::Threshold { min_amount: 1_000, mint: Some(spl_token::native_mint::id()) },
+            AlertRule::Denylist(vec![tipper].into_iter().collect()),
+        ]);
+
+        assert!(engine.event(&tip(0, Pubkey::new_unique(), 999), 0).is_empty());
+        assert_eq!(rules(&engine.event(&tip(1, Pubkey::new_unique(), 1_000), 0)), vec!["threshold"]);
+
+        let alerts = engine.event(&tip(2, tipper, 10), 0);
+        assert_eq!(rules(&alerts), vec!["denylist"]);
+        assert_eq!(alerts[0].severity, Severity::Critical);
+        assert_eq!(alerts[0].accounts, vec![tipper.to_string()]);
+        assert_eq!(alerts[0].sequence, Some(2));
+    }
+
+    #[test]
+    fn when_velocity_crossed_within_window_expect_single_alert() {
+        let tipper = Pubkey::new_unique();
+        let mut engine = AlertEngine::new(Pubkey::new_unique(), vec![
+            AlertRule::Velocity { max_amount: 1_000, window: 60 },
+        ]);
+
+        assert!(engine.event(&tip(0, tipper, 600), 0).is_empty());
+        assert_eq!(rules(&engine.event(&tip(1, tipper, 600), 30)), vec!["velocity"]);
+        assert!(engine.event(&tip(2, tipper, 600), 50).is_empty());
+        // Only the last two tips remain in the window
+        assert!(engine.event(&tip(3, tipper, 100), 100).is_empty());
+    }
+
+    #[test]
+    fn when_failed_transfers_spike_expect_alert() {
+        let program_id = Pubkey::new_unique();
+        let spike = vec![AlertRule::FailedTransfers { max_failures: 1, window: 60 }];
+        let mut engine = AlertEngine::new(program_id, spike);
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let update = |failed: bool| TransactionUpdate {
+            slot: 1,
+            signature: Signature::default(),
+            account_keys: vec![from, to, program_id],
+            instructions: vec![CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1],
+                data: TokenInstruction::TransferLamports { amount: 100 }.try_to_vec().unwrap(),
+            }],
+            log_messages: vec![],
+            failed,
+        };
+
+        assert!(engine.transaction(&update(false), 0).is_empty());
+        assert!(engine.transaction(&update(true), 0).is_empty());
+        assert_eq!(rules(&engine.transaction(&update(true), 10)), vec!["failed_transfers"]);
+        assert!(engine.transaction(&update(true), 20).is_empty());
+    }
+
+    #[test]
+    fn when_pagerduty_trigger_expect_events_v2_payload() {
+        let summary = "tip involves denylisted accounts".to_string();
+        let alert = Alert::new("denylist", Severity::Critical, summary, Some(2), &[], 7);
+
+        let trigger = PagerDutySink::new("key".to_string()).trigger(&alert);
+
+        assert_eq!(trigger["routing_key"], "key");
+        assert_eq!(trigger["event_action"], "trigger");
+        assert_eq!(trigger["payload"]["severity"], "critical");
+        assert_eq!(trigger["payload"]["summary"], "tip involves denylisted accounts");
+        assert_eq!(trigger["payload"]["custom_details"]["sequence"], 2);
+    }
+}
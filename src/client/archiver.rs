@@ -0,0 +1,431 @@
+//! Archive of program activity in a relational database, enabled by `archiver` feature. Feed it with
+//! `GeyserSink` items or `EventConsumer` records, it keeps tables
+//! - `transfers` value moved by each instruction, batches have a row per recipient
+//! - `events` every sequenced event with its event stream and Borsh encoded record
+//! - `escrows` current status of each escrow
+//! - `accounts` latest state of program owned accounts
+//!
+//! Writes are idempotent upserts, replaying a stream or delivering an item twice leaves tables unchanged.
+//! Escrow status follows the event of the highest sequence and account state the highest slot, so items
+//! may arrive out of order; events of one escrow are expected in one event stream. Works with SQLite via
+//! `rusqlite` and Postgres via `postgres`.
+use std::convert::TryFrom;
+use borsh::BorshSerialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use crate::client::error::{ClientError, ClientResult};
+use crate::client::events::EventFields;
+use crate::client::geyser::StreamItem;
+use crate::client::policy::parse_transfers;
+use crate::event::{EventRecord, TokenEvent};
+use crate::instruction::TokenInstruction;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transfers (
+    signature TEXT NOT NULL,
+    instruction_index BIGINT NOT NULL,
+    transfer_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    source TEXT,
+    destination TEXT NOT NULL,
+    mint TEXT,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (signature, instruction_index, transfer_index)
+);
+CREATE INDEX IF NOT EXISTS transfers_source ON transfers (source);
+CREATE INDEX IF NOT EXISTS transfers_destination ON transfers (destination);
+CREATE TABLE IF NOT EXISTS events (
+    stream TEXT NOT NULL,
+    sequence BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    mint TEXT,
+    accounts TEXT NOT NULL,
+    amount BIGINT,
+    data TEXT NOT NULL,
+    PRIMARY KEY (stream, sequence)
+);
+CREATE TABLE IF NOT EXISTS escrows (
+    escrow TEXT PRIMARY KEY,
+    initializer TEXT NOT NULL,
+    status TEXT NOT NULL,
+    amount BIGINT,
+    expected_amount BIGINT,
+    expires_at BIGINT,
+    taker TEXT,
+    sequence BIGINT
+);
+CREATE TABLE IF NOT EXISTS accounts (
+    address TEXT PRIMARY KEY,
+    account_type TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    data TEXT NOT NULL
+);
+";
+
+const INSERT_TRANSFER: &str = "
+INSERT INTO transfers (signature, instruction_index, transfer_index, slot, source, destination, mint, amount)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT DO NOTHING";
+
+const INSERT_EVENT: &str = "
+INSERT INTO events (stream, sequence, name, mint, accounts, amount, data)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT DO NOTHING";
+
+const UPSERT_ESCROW: &str = "
+INSERT INTO escrows (escrow, initializer, status, amount, expected_amount, expires_at, taker, sequence)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT (escrow) DO UPDATE SET
+    status = CASE WHEN excluded.sequence > escrows.sequence THEN excluded.status ELSE escrows.status END,
+    sequence = CASE WHEN excluded.sequence > escrows.sequence THEN excluded.sequence ELSE escrows.sequence END,
+    amount = COALESCE(escrows.amount, excluded.amount),
+    expected_amount = COALESCE(escrows.expected_amount, excluded.expected_amount),
+    expires_at = COALESCE(escrows.expires_at, excluded.expires_at),
+    taker = COALESCE(escrows.taker, excluded.taker)";
+
+const UPSERT_ACCOUNT: &str = "
+INSERT INTO accounts (address, account_type, slot, data)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (address) DO UPDATE SET account_type = excluded.account_type, slot = excluded.slot, data = excluded.data
+WHERE accounts.slot <= excluded.slot";
+
+/// Statement parameter, integers are stored as `BIGINT`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Int(Option<i64>),
+    Text(Option<String>),
+}
+
+impl SqlValue {
+    pub(crate) fn text<T: ToString>(value: T) -> Self {
+        SqlValue::Text(Some(value.to_string()))
+    }
+
+    /// Fails for values not fitting `BIGINT`
+    pub(crate) fn int(value: u64) -> ClientResult<Self> {
+        i64::try_from(value).map(|value| SqlValue::Int(Some(value))).map_err(|_| ClientError::ArchiveOverflow(value))
+    }
+
+    /// None for `NULL` and text
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            SqlValue::Int(value) => *value,
+            SqlValue::Text(_) => None,
+        }
+    }
+
+    /// None for `NULL` and integers
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            SqlValue::Int(_) => None,
+            SqlValue::Text(value) => value.as_deref(),
+        }
+    }
+}
+
+/// Database connection of the archive, statements use `$1`, `$2`, .. placeholders
+pub trait ArchiveBackend {
+    fn execute_batch(&mut self, sql: &str) -> ClientResult<()>;
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> ClientResult<()>;
+    /// Rows of a `SELECT` of `BIGINT` and `TEXT` columns
+    fn query(&mut self, sql: &str, params: &[SqlValue]) -> ClientResult<Vec<Vec<SqlValue>>>;
+}
+
+impl ArchiveBackend for rusqlite::Connection {
+    fn execute_batch(&mut self, sql: &str) -> ClientResult<()> {
+        Ok(rusqlite::Connection::execute_batch(self, sql)?)
+    }
+
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> ClientResult<()> {
+        rusqlite::Connection::execute(self, &sql.replace('$', "?"), sqlite_params(params))?;
+        Ok(())
+    }
+
+    fn query(&mut self, sql: &str, params: &[SqlValue]) -> ClientResult<Vec<Vec<SqlValue>>> {
+        let mut statement = self.prepare(&sql.replace('$', "?"))?;
+        let columns = statement.column_count();
+        let rows = statement.query_map(sqlite_params(params), |row| {
+            (0..columns)
+                .map(|index| {
+                    row.get(index).map(|value| match value {
+                        rusqlite::types::Value::Integer(value) => SqlValue::Int(Some(value)),
+                        rusqlite::types::Value::Text(value) => SqlValue::Text(Some(value)),
+                        _ => SqlValue::Int(None),
+                    })
+                })
+                .collect()
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+fn sqlite_params(params: &[SqlValue]) -> impl Iterator<Item = &dyn rusqlite::ToSql> {
+    params.iter().map(|param| match param {
+        SqlValue::Int(value) => value as &dyn rusqlite::ToSql,
+        SqlValue::Text(value) => value as &dyn rusqlite::ToSql,
+    })
+}
+
+impl ArchiveBackend for postgres::Client {
+    fn execute_batch(&mut self, sql: &str) -> ClientResult<()> {
+        Ok(self.batch_execute(sql)?)
+    }
+
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> ClientResult<()> {
+        postgres::Client::execute(self, sql, &postgres_params(params))?;
+        Ok(())
+    }
+
+    fn query(&mut self, sql: &str, params: &[SqlValue]) -> ClientResult<Vec<Vec<SqlValue>>> {
+        let rows = postgres::Client::query(self, sql, &postgres_params(params))?;
+        rows.iter()
+            .map(|row| {
+                (0..row.len())
+                    .map(|index| match *row.columns()[index].type_() {
+                        postgres::types::Type::INT8 => Ok(SqlValue::Int(row.try_get(index)?)),
+                        _ => Ok(SqlValue::Text(row.try_get(index)?)),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+fn postgres_params(params: &[SqlValue]) -> Vec<&(dyn postgres::types::ToSql + Sync)> {
+    params.iter()
+        .map(|param| match param {
+            SqlValue::Int(value) => value as &(dyn postgres::types::ToSql + Sync),
+            SqlValue::Text(value) => value as &(dyn postgres::types::ToSql + Sync),
+        })
+        .collect()
+}
+
+pub struct Archiver<B: ArchiveBackend> {
+    backend: B,
+}
+
+impl<B: ArchiveBackend> Archiver<B> {
+    /// Archive in the database of `backend`, tables are created if missing
+    pub fn new(mut backend: B) -> ClientResult<Self> {
+        backend.execute_batch(SCHEMA)?;
+        Ok(Archiver { backend })
+    }
+
+    pub fn archive(&mut self, item: &StreamItem) -> ClientResult<()> {
+        match item {
+            StreamItem::Instruction { slot, signature, index, instruction, accounts } => {
+                self.archive_transfers(*slot, signature, *index, instruction, accounts)
+            }
+            StreamItem::Event(record) => self.archive_event(record),
+            StreamItem::Account { slot, pubkey, account_type, data } => {
+                self.backend.execute(UPSERT_ACCOUNT, &[
+                    SqlValue::text(pubkey),
+                    SqlValue::text(format!("{:?}", account_type)),
+                    SqlValue::int(*slot)?,
+                    SqlValue::text(base64::encode(data)),
+                ])
+            }
+        }
+    }
+
+    /// Unsequenced events are not archived, they can't be told apart on replay
+    pub fn archive_event(&mut self, record: &EventRecord) -> ClientResult<()> {
+        let (stream, sequence) = match (record.stream, record.sequence) {
+            (Some(stream), Some(sequence)) => (SqlValue::text(stream), SqlValue::int(sequence)?),
+            _ => return Ok(()),
+        };
+        let fields = EventFields::new(&record.event);
+        let accounts: Vec<String> = fields.accounts.iter().map(Pubkey::to_string).collect();
+        self.backend.execute(INSERT_EVENT, &[
+            stream,
+            sequence.clone(),
+            SqlValue::text(fields.name),
+            SqlValue::Text(fields.mint.map(|mint| mint.to_string())),
+            SqlValue::text(serde_json::to_string(&accounts)?),
+            fields.amount.map(SqlValue::int).transpose()?.unwrap_or(SqlValue::Int(None)),
+            SqlValue::text(base64::encode(record.try_to_vec()?)),
+        ])?;
+        self.archive_escrow(&record.event, sequence)
+    }
+
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    fn archive_transfers(
+        &mut self,
+        slot: u64,
+        signature: &Signature,
+        index: usize,
+        instruction: &TokenInstruction,
+        accounts: &[Pubkey],
+    ) -> ClientResult<()> {
+        let transfers = parse_transfers(accounts, &instruction.try_to_vec()?);
+        let source = SqlValue::Text(transfer_source(instruction, accounts).map(|source| source.to_string()));
+        for (transfer_index, transfer) in transfers.into_iter().enumerate() {
+            self.backend.execute(INSERT_TRANSFER, &[
+                SqlValue::text(signature),
+                SqlValue::int(index as u64)?,
+                SqlValue::int(transfer_index as u64)?,
+                SqlValue::int(slot)?,
+                source.clone(),
+                SqlValue::text(transfer.destination),
+                SqlValue::Text(transfer.mint.map(|mint| mint.to_string())),
+                SqlValue::int(transfer.amount)?,
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn archive_escrow(&mut self, event: &TokenEvent, sequence: SqlValue) -> ClientResult<()> {
+        let none = SqlValue::Int(None);
+        let (escrow, initializer, status, amount, expected_amount, expires_at, taker) = match event {
+            TokenEvent::EscrowInitialized(e) => (
+                e.escrow, e.initializer, "open", SqlValue::int(e.amount)?, SqlValue::int(e.expected_amount)?,
+                SqlValue::Int(Some(e.expires_at)), SqlValue::Text(None),
+            ),
+            TokenEvent::EscrowExchanged(e) => (
+                e.escrow, e.initializer, "exchanged", SqlValue::int(e.amount)?, SqlValue::int(e.expected_amount)?,
+                none, SqlValue::text(e.taker),
+            ),
+            TokenEvent::EscrowCancelled(e) => (
+                e.escrow, e.initializer, "cancelled", SqlValue::int(e.refunded)?, none.clone(), none,
+                SqlValue::Text(None),
+            ),
+            _ => return Ok(()),
+        };
+        self.backend.execute(UPSERT_ESCROW, &[
+            SqlValue::text(escrow),
+            SqlValue::text(initializer),
+            SqlValue::text(status),
+            amount,
+            expected_amount,
+            expires_at,
+            taker,
+            sequence,
+        ])
+    }
+}
+
+/// Account value of a transfer instruction leaves, SPL token instructions take the source token account second
+/// past transfer policy accounts, see `TokenInstruction` accounts
+fn transfer_source(instruction: &TokenInstruction, accounts: &[Pubkey]) -> Option<Pubkey> {
+    let index = match instruction {
+        TokenInstruction::TransferLamports { .. }
+        | TokenInstruction::TransferLamportsV2 { .. }
+        | TokenInstruction::TransferLamportsWithMemo { .. }
+        | TokenInstruction::TransferLamportsBatch { .. }
+        | TokenInstruction::TransferLamportsRoundUp { .. }
+        | TokenInstruction::SplitLamports { .. }
+        | TokenInstruction::CreateStream { .. }
+        | TokenInstruction::PostBond { .. }
+        | TokenInstruction::Tip { .. }
+        | TokenInstruction::CreateMatchingPool { .. }
+        | TokenInstruction::Donate { .. }
+        | TokenInstruction::ExecutePayout { .. } => 0,
+        TokenInstruction::SlashBond { .. } | TokenInstruction::WithdrawFromVault { .. } => 2,
+        _ => 1,
+    };
+    instruction.skip_transfer_policy(accounts).get(index).copied()
+}
+
+#[cfg(test)]
+mod archiver_test {
+    use rusqlite::Connection;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use crate::client::archiver::Archiver;
+    use crate::client::geyser::StreamItem;
+    use crate::event::{EscrowExchangedEvent, EscrowInitializedEvent, EventRecord, TokenEvent};
+    use crate::instruction::TokenInstruction;
+    use crate::state::AccountType;
+
+    fn count(connection: &Connection, table: &str) -> i64 {
+        connection
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), rusqlite::NO_PARAMS, |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn when_stream_replayed_expect_rows_once() {
+        let mut archiver = Archiver::new(Connection::open_in_memory().unwrap()).unwrap();
+        let (from, to_a, to_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let items = [
+            StreamItem::Instruction {
+                slot: 10,
+                signature: Signature::default(),
+                index: 0,
+                instruction: TokenInstruction::TransferLamportsBatch { amounts: vec![100, 200] },
+                accounts: TokenInstruction::transfer_policy_accounts(&from, &Pubkey::default())
+                    .into_iter()
+                    .map(|meta| meta.pubkey)
+                    .chain([from, Pubkey::new_unique(), to_a, to_b])
+                    .collect(),
+            },
+            StreamItem::Event(EventRecord {
+                sequence: Some(0),
+                stream: Some(Pubkey::new_from_array([3; 32])),
+                event: TokenEvent::EscrowInitialized(EscrowInitializedEvent {
+                    escrow: Pubkey::new_unique(),
+                    initializer: from,
+                    amount: 100,
+                    expected_amount: 50,
+                    expires_at: 1_000,
+                }),
+            }),
+        ];
+
+        for item in items.iter().chain(items.iter()) {
+            archiver.archive(item).unwrap();
+        }
+
+        let connection = archiver.into_inner();
+        assert_eq!(count(&connection, "transfers"), 2);
+        assert_eq!(count(&connection, "events"), 1);
+        assert_eq!(count(&connection, "escrows"), 1);
+    }
+
+    #[test]
+    fn when_escrow_events_out_of_order_expect_latest_status() {
+        let mut archiver = Archiver::new(Connection::open_in_memory().unwrap()).unwrap();
+        let (escrow, initializer, taker) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let exchanged = EscrowExchangedEvent { escrow, initializer, taker, amount: 100, expected_amount: 50 };
+        let initialized = EscrowInitializedEvent {
+            escrow, initializer, amount: 100, expected_amount: 50, expires_at: 1_000,
+        };
+        let stream = Some(Pubkey::new_from_array([3; 32]));
+
+        let exchanged = EventRecord { sequence: Some(5), stream, event: TokenEvent::EscrowExchanged(exchanged) };
+        let initialized = EventRecord { sequence: Some(4), stream, event: TokenEvent::EscrowInitialized(initialized) };
+        archiver.archive_event(&exchanged).unwrap();
+        archiver.archive_event(&initialized).unwrap();
+
+        let row: (String, i64, String, i64) = archiver.into_inner()
+            .query_row(
+                "SELECT status, expires_at, taker, sequence FROM escrows WHERE escrow = ?",
+                &[escrow.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(row, ("exchanged".to_string(), 1_000, taker.to_string(), 5));
+    }
+
+    #[test]
+    fn when_account_update_of_older_slot_expect_ignored() {
+        let mut archiver = Archiver::new(Connection::open_in_memory().unwrap()).unwrap();
+        let pubkey = Pubkey::new_unique();
+        let update = |slot: u64, data: Vec<u8>| {
+            StreamItem::Account { slot, pubkey, account_type: AccountType::Keeper, data }
+        };
+
+        archiver.archive(&update(11, vec![AccountType::Keeper as u8, 2])).unwrap();
+        archiver.archive(&update(10, vec![AccountType::Keeper as u8, 1])).unwrap();
+
+        let row: (String, i64, String) = archiver.into_inner()
+            .query_row("SELECT account_type, slot, data FROM accounts", rusqlite::NO_PARAMS, |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(row, ("Keeper".to_string(), 11, base64::encode([AccountType::Keeper as u8, 2])));
+    }
+}
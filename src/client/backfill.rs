@@ -0,0 +1,285 @@
+//! Backfill of an archive with historical program activity, enabled by `archiver` feature. Signatures of
+//! the program are walked from the newest back to `from_slot`, each successful transaction is decoded
+//! like a Geyser stream and archived together with its events.
+//!
+//! The walk is checkpointed to a JSON file after each page of signatures, an interrupted run resumes
+//! from the last archived page. Archiving is idempotent, so a page archived twice is harmless. Rate
+//! limited RPC requests are retried with exponential backoff on top of the RPC client's own retries.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::ClientErrorKind;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use crate::client::archiver::{ArchiveBackend, Archiver};
+use crate::client::error::{ClientError, ClientResult};
+use crate::client::events::parse_logs;
+use crate::client::geyser::{program_instructions, StreamItem, TransactionUpdate};
+
+/// Progress of a backfill, `before` is the oldest signature archived
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Checkpoint {
+    pub before: Option<String>,
+    pub complete: bool,
+}
+
+impl Checkpoint {
+    /// Missing file is a backfill not started yet
+    pub fn load<P: AsRef<Path>>(path: P) -> ClientResult<Self> {
+        match fs::read(path.as_ref()) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Checkpoint::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Written to a temporary file first, so a crash never leaves a partial checkpoint
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> ClientResult<()> {
+        let path = path.as_ref();
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec(self)?)?;
+        Ok(fs::rename(temp, path)?)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BackfillEvent {
+    /// Page of signatures archived and checkpointed
+    Page { transactions: usize, checkpoint: Option<String>, complete: bool },
+    /// Request rate limited, retried after `delay`
+    RateLimited { attempt: u32, delay: Duration },
+}
+
+pub struct Backfill<'a> {
+    rpc_client: &'a RpcClient,
+    program_id: Pubkey,
+    from_slot: u64,
+    checkpoint: PathBuf,
+    max_attempts: u32,
+    backoff: Duration,
+    interval: Duration,
+}
+
+impl<'a> Backfill<'a> {
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+    pub const DEFAULT_BACKOFF: Duration = Duration::from_secs(1);
+
+    /// Backfill of history since `from_slot`, 0 for complete history, checkpointed at `checkpoint` path
+    pub fn new<P: AsRef<Path>>(rpc_client: &'a RpcClient, program_id: Pubkey, from_slot: u64, checkpoint: P) -> Self {
+        Backfill {
+            rpc_client,
+            program_id,
+            from_slot,
+            checkpoint: checkpoint.as_ref().to_path_buf(),
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            backoff: Self::DEFAULT_BACKOFF,
+            interval: Duration::from_millis(0),
+        }
+    }
+
+    /// Attempts per rate limited request, at least one, with `backoff` before the first retry doubling after each
+    pub fn with_retries(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.backoff = backoff;
+        self
+    }
+
+    /// Pause between requests to stay under the RPC node rate limit
+    pub fn with_request_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Archive history until `from_slot` is reached, returns number of transactions archived by this run
+    pub fn run<B, E>(&self, archiver: &mut Archiver<B>, on_event: &mut E) -> ClientResult<usize>
+    where
+        B: ArchiveBackend,
+        E: FnMut(BackfillEvent),
+    {
+        let mut checkpoint = Checkpoint::load(&self.checkpoint)?;
+        let mut archived = 0;
+        while !checkpoint.complete {
+            let before = checkpoint.before
+                .as_deref()
+                .map(|signature| Signature::from_str(signature).map_err(|_| ClientError::InvalidSignature))
+                .transpose()?;
+            let config = || GetConfirmedSignaturesForAddress2Config { before, ..Default::default() };
+            let statuses = self.request(on_event, || {
+                Ok(self.rpc_client.get_signatures_for_address_with_config(&self.program_id, config())?)
+            })?;
+            checkpoint.complete = statuses.is_empty();
+
+            let mut transactions = 0;
+            for status in statuses {
+                if status.slot < self.from_slot {
+                    checkpoint.complete = true;
+                    break
+                }
+                if status.err.is_none() {
+                    let signature = Signature::from_str(&status.signature).map_err(|_| ClientError::InvalidSignature)?;
+                    let transaction = self.request(on_event, || {
+                        Ok(self.rpc_client.get_transaction(&signature, UiTransactionEncoding::Base64)?)
+                    })?;
+                    for item in decode(&self.program_id, signature, &transaction) {
+                        archiver.archive(&item)?;
+                    }
+                    transactions += 1;
+                }
+                checkpoint.before = Some(status.signature);
+            }
+            checkpoint.save(&self.checkpoint)?;
+            archived += transactions;
+            on_event(BackfillEvent::Page {
+                transactions,
+                checkpoint: checkpoint.before.clone(),
+                complete: checkpoint.complete,
+            });
+        }
+        Ok(archived)
+    }
+
+    fn request<T, E, R>(&self, on_event: &mut E, request: R) -> ClientResult<T>
+    where
+        E: FnMut(BackfillEvent),
+        R: Fn() -> ClientResult<T>,
+    {
+        let mut delay = self.backoff;
+        let mut attempt = 1;
+        loop {
+            thread::sleep(self.interval);
+            match request() {
+                Err(e) if attempt < self.max_attempts && rate_limited(&e) => {
+                    on_event(BackfillEvent::RateLimited { attempt, delay });
+                }
+                result => return result,
+            }
+            thread::sleep(delay);
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+fn rate_limited(error: &ClientError) -> bool {
+    match error {
+        ClientError::Rpc(e) => {
+            matches!(e.kind(), ClientErrorKind::Reqwest(e) if e.status() == Some(StatusCode::TOO_MANY_REQUESTS))
+        }
+        _ => false,
+    }
+}
+
+/// Instructions and events of this program in a successful transaction
+fn decode(program_id: &Pubkey, signature: Signature, confirmed: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<StreamItem> {
+    let (transaction, meta) = match (confirmed.transaction.transaction.decode(), &confirmed.transaction.meta) {
+        (Some(transaction), Some(meta)) if meta.err.is_none() => (transaction, meta),
+        _ => return vec![],
+    };
+    let update = TransactionUpdate {
+        slot: confirmed.slot,
+        signature,
+        account_keys: transaction.message.static_account_keys().to_vec(),
+        instructions: transaction.message.instructions().to_vec(),
+        log_messages: Option::from(meta.log_messages.clone()).unwrap_or_default(),
+        failed: false,
+    };
+    let mut items = program_instructions(program_id, &update);
+    items.extend(parse_logs(program_id, &update.log_messages).into_iter().map(StreamItem::Event));
+    items
+}
+
+#[cfg(test)]
+mod backfill_test {
+    use borsh::BorshSerialize;
+    use solana_sdk::message::Message;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::{Transaction, TransactionError};
+    use solana_transaction_status::option_serializer::OptionSerializer;
+    use solana_transaction_status::{
+        Encodable, EncodedConfirmedTransactionWithStatusMeta, EncodedTransactionWithStatusMeta, UiTransactionEncoding,
+        UiTransactionStatusMeta,
+    };
+    use crate::client::backfill::{decode, Checkpoint};
+    use crate::client::geyser::StreamItem;
+    use crate::event::{EventRecord, KeeperRegisteredEvent, TokenEvent};
+    use crate::instruction::TokenInstruction;
+
+    fn confirmed(
+        program_id: Pubkey,
+        keeper: Pubkey,
+        err: Option<TransactionError>,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let record = EventRecord {
+            sequence: Some(3),
+            stream: Some(Pubkey::new_from_array([3; 32])),
+            event: TokenEvent::KeeperRegistered(KeeperRegisteredEvent { keeper }),
+        };
+        let mut instruction = TokenInstruction::transfer_lamports(keeper, Pubkey::new_unique(), 100);
+        instruction.program_id = program_id;
+        let transaction = Transaction::new_unsigned(Message::new(&[instruction], Some(&keeper)));
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 42,
+            transaction: EncodedTransactionWithStatusMeta {
+                transaction: transaction.encode(UiTransactionEncoding::Base64),
+                meta: Some(UiTransactionStatusMeta {
+                    status: err.clone().map_or(Ok(()), Err),
+                    err,
+                    fee: 5_000,
+                    pre_balances: vec![],
+                    post_balances: vec![],
+                    inner_instructions: OptionSerializer::None,
+                    log_messages: OptionSerializer::Some(vec![
+                        format!("Program {} invoke [1]", program_id),
+                        format!("Program data: {}", base64::encode(record.try_to_vec().unwrap())),
+                        format!("Program {} success", program_id),
+                    ]),
+                    pre_token_balances: OptionSerializer::None,
+                    post_token_balances: OptionSerializer::None,
+                    rewards: OptionSerializer::None,
+                    loaded_addresses: OptionSerializer::Skip,
+                    return_data: OptionSerializer::Skip,
+                    compute_units_consumed: OptionSerializer::Skip,
+                }),
+                version: None,
+            },
+            block_time: None,
+        }
+    }
+
+    #[test]
+    fn when_transaction_decoded_expect_instructions_and_events() {
+        let (program_id, keeper) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let items = decode(&program_id, Signature::default(), &confirmed(program_id, keeper, None));
+
+        assert!(matches!(items[0], StreamItem::Instruction {
+            slot: 42,
+            index: 0,
+            instruction: TokenInstruction::TransferLamports { amount: 100 },
+            ..
+        }));
+        assert!(matches!(items[1], StreamItem::Event(EventRecord { sequence: Some(3), .. })));
+        assert_eq!(items.len(), 2);
+        let failed = confirmed(program_id, keeper, Some(TransactionError::AccountInUse));
+        assert!(decode(&program_id, Signature::default(), &failed).is_empty());
+    }
+
+    #[test]
+    fn when_checkpoint_saved_expect_loaded() {
+        let path = std::env::temp_dir().join(format!("backfill-{}.json", Pubkey::new_unique()));
+        assert_eq!(Checkpoint::load(&path).unwrap(), Checkpoint::default());
+
+        let checkpoint = Checkpoint { before: Some(Signature::default().to_string()), complete: true };
+        checkpoint.save(&path).unwrap();
+
+        assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,318 @@
+//! Concurrent submission of independent transactions, e.g. payouts to many recipients.
+//!
+//! A transaction is re-signed with a fresh blockhash only after its previous blockhash expired
+//! without the signature landing, so a retry never pays the same recipient twice. With a deadline
+//! slot every transaction starts with a `CheckDeadline` guard, nothing lands or is retried past it.
+//!
+//! With priority fees items are submitted in SLA order and every attempt bids a compute unit price from
+//! the current fee market, see `client::priority`.
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use crate::client::error::{ClientError, ClientResult};
+use crate::client::fee_payer::FeePayerPool;
+use crate::client::priority::{self, FeeMarket, PriorityFees, Sla};
+use crate::compute_budget;
+use crate::instruction::TokenInstruction;
+
+/// Cached blockhash older than this is refreshed before signing
+const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(30);
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct BatchExecutor<'a, S: Signer + Sync> {
+    rpc_client: &'a RpcClient,
+    fee_payers: &'a FeePayerPool,
+    authority: &'a S,
+    max_parallel: usize,
+    max_attempts: usize,
+    valid_until_slot: Option<u64>,
+    priority_fees: Option<PriorityFees>,
+    blockhash: Mutex<Option<(Hash, Instant)>>,
+}
+
+/// Progress of a single item, reported as soon as it happens
+#[derive(Debug)]
+pub enum BatchEvent {
+    /// Transaction signed and about to be sent
+    Signed { index: usize, signature: Signature, blockhash: Hash },
+    Confirmed { index: usize, signature: Signature },
+}
+
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// Index of the submitted item and its confirmed signature
+    pub succeeded: Vec<(usize, Signature)>,
+    pub failed: Vec<(usize, ClientError)>,
+}
+
+impl<'a, S: Signer + Sync> BatchExecutor<'a, S> {
+    pub fn new(
+        rpc_client: &'a RpcClient,
+        fee_payers: &'a FeePayerPool,
+        authority: &'a S,
+        max_parallel: usize,
+        max_attempts: usize,
+    ) -> Self {
+        assert!(max_parallel > 0, "Parallelism must be positive");
+        BatchExecutor {
+            rpc_client,
+            fee_payers,
+            authority,
+            max_parallel,
+            max_attempts,
+            valid_until_slot: None,
+            priority_fees: None,
+            blockhash: Mutex::new(None),
+        }
+    }
+
+    /// Transactions fail on-chain once past `valid_until_slot`, see `TokenInstruction::CheckDeadline`
+    pub fn with_valid_until_slot(mut self, valid_until_slot: u64) -> Self {
+        self.valid_until_slot = Some(valid_until_slot);
+        self
+    }
+
+    /// Every transaction sets a compute unit price by the SLA of its item, see `PriorityFees::price`
+    pub fn with_priority_fees(mut self, priority_fees: PriorityFees) -> Self {
+        self.priority_fees = Some(priority_fees);
+        self
+    }
+
+    /// Submit each instruction set as its own transaction signed by fee payer and authority
+    pub fn execute(&self, items: &[Vec<Instruction>]) -> BatchReport {
+        self.execute_with_events(items, |_| {})
+    }
+
+    pub fn execute_with_events<E>(&self, items: &[Vec<Instruction>], on_event: E) -> BatchReport
+    where
+        E: Fn(BatchEvent) + Sync,
+    {
+        self.execute_by_sla(items, &vec![Sla::Standard; items.len()], on_event)
+    }
+
+    /// Submit items with `slas` of the same order, more urgent items first
+    pub fn execute_by_sla<E>(&self, items: &[Vec<Instruction>], slas: &[Sla], on_event: E) -> BatchReport
+    where
+        E: Fn(BatchEvent) + Sync,
+    {
+        assert_eq!(items.len(), slas.len(), "Every item needs an SLA");
+        let indexed_items: Vec<_> = by_sla(slas).into_iter().map(|index| (index, &items[index])).collect();
+        let results = run_concurrent(&indexed_items, self.max_parallel, |(index, instructions)| {
+            let result = self.submit(*index, instructions, slas[*index], &on_event);
+            if let Ok(signature) = result {
+                on_event(BatchEvent::Confirmed { index: *index, signature });
+            }
+            result
+        });
+        let mut report = BatchReport::default();
+        for ((index, _), result) in indexed_items.into_iter().zip(results) {
+            match result {
+                Ok(signature) => report.succeeded.push((index, signature)),
+                Err(e) => report.failed.push((index, e)),
+            }
+        }
+        report
+    }
+
+    fn submit<E>(&self, index: usize, instructions: &[Instruction], sla: Sla, on_event: &E) -> ClientResult<Signature>
+    where
+        E: Fn(BatchEvent),
+    {
+        let guarded_instructions = guarded(instructions, self.valid_until_slot);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let instructions = match self.priority_fees {
+                Some(priority_fees) => {
+                    let price = self.compute_unit_price(&priority_fees, instructions, sla, attempt)?;
+                    prioritized(&guarded_instructions, price)
+                }
+                None => guarded_instructions.clone(),
+            };
+            let blockhash = self.blockhash(attempt > 1)?;
+            let fee_payer = self.fee_payers.next_payer(self.rpc_client)?;
+            let tx = Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&fee_payer.pubkey()),
+                &[fee_payer as &dyn Signer, self.authority as &dyn Signer],
+                blockhash,
+            );
+            on_event(BatchEvent::Signed { index, signature: tx.signatures[0], blockhash });
+            let send_error = match self.rpc_client.send_and_confirm_transaction(&tx) {
+                Ok(signature) => return Ok(signature),
+                Err(e) => e,
+            };
+            if let Some(result) = self.wait_final_status(&tx.signatures[0], &blockhash)? {
+                return result.map(|_| tx.signatures[0]).map_err(ClientError::Transaction)
+            }
+            if attempt >= self.max_attempts || self.deadline_passed()? {
+                return Err(send_error.into())
+            }
+        }
+    }
+
+    /// Final status of `signature`, or None once `blockhash` expired without it landing
+    pub fn wait_final_status(&self, signature: &Signature, blockhash: &Hash) -> ClientResult<Option<Result<(), TransactionError>>> {
+        loop {
+            if let Some(result) = self.rpc_client.get_signature_status(signature)? {
+                return Ok(Some(result))
+            }
+            if !self.rpc_client.is_blockhash_valid(blockhash, self.rpc_client.commitment())? {
+                return Ok(None)
+            }
+            thread::sleep(STATUS_POLL_INTERVAL);
+        }
+    }
+
+    /// Price of `attempt` at the current fee market of accounts written by `instructions`
+    fn compute_unit_price(
+        &self,
+        priority_fees: &PriorityFees,
+        instructions: &[Instruction],
+        sla: Sla,
+        attempt: usize,
+    ) -> ClientResult<u64> {
+        let market = FeeMarket::fetch(self.rpc_client, &priority::writable_accounts(instructions))?;
+        let slots_left = match self.valid_until_slot {
+            Some(valid_until_slot) => Some(valid_until_slot.saturating_sub(self.rpc_client.get_slot()?)),
+            None => None,
+        };
+        Ok(priority_fees.price(sla, &market, attempt, slots_left))
+    }
+
+    fn deadline_passed(&self) -> ClientResult<bool> {
+        match self.valid_until_slot {
+            Some(valid_until_slot) => Ok(self.rpc_client.get_slot()? > valid_until_slot),
+            None => Ok(false),
+        }
+    }
+
+    fn blockhash(&self, refresh: bool) -> ClientResult<Hash> {
+        let mut cached = self.blockhash.lock().unwrap();
+        match *cached {
+            Some((blockhash, fetched)) if !refresh && fetched.elapsed() < BLOCKHASH_MAX_AGE => Ok(blockhash),
+            _ => {
+                let blockhash = self.rpc_client.get_latest_blockhash()?;
+                *cached = Some((blockhash, Instant::now()));
+                Ok(blockhash)
+            }
+        }
+    }
+}
+
+/// Instructions of an item behind the deadline guard, when there is a deadline
+fn guarded(instructions: &[Instruction], valid_until_slot: Option<u64>) -> Vec<Instruction> {
+    valid_until_slot
+        .map(TokenInstruction::check_deadline)
+        .into_iter()
+        .chain(instructions.iter().cloned())
+        .collect()
+}
+
+/// Instructions of an item behind the compute unit price
+fn prioritized(instructions: &[Instruction], micro_lamports: u64) -> Vec<Instruction> {
+    std::iter::once(compute_budget::set_compute_unit_price(micro_lamports))
+        .chain(instructions.iter().cloned())
+        .collect()
+}
+
+/// Item indexes in submission order, by SLA and then by item order
+fn by_sla(slas: &[Sla]) -> Vec<usize> {
+    let mut indexes: Vec<usize> = (0..slas.len()).collect();
+    indexes.sort_by_key(|index| slas[*index]);
+    indexes
+}
+
+/// Apply `f` to every item on at most `max_parallel` threads, results keep item order
+fn run_concurrent<T, R, F>(items: &[T], max_parallel: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<Option<R>>>());
+    thread::scope(|scope| {
+        for _ in 0..max_parallel.min(items.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= items.len() {
+                    break
+                }
+                let result = f(&items[index]);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(|result| result.unwrap()).collect()
+}
+
+#[cfg(test)]
+mod batch_test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::batch::{by_sla, guarded, prioritized, run_concurrent};
+    use crate::client::priority::Sla;
+    use crate::compute_budget::set_compute_unit_price;
+    use crate::instruction::TokenInstruction;
+
+    #[test]
+    fn when_run_concurrent_expect_item_order() {
+        let items: Vec<u64> = (0..50).collect();
+
+        let results = run_concurrent(&items, 4, |item| item * 2);
+
+        assert_eq!(results, items.iter().map(|item| item * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn when_deadline_expect_guard_first() {
+        let transfer = TokenInstruction::transfer_lamports(Pubkey::new_unique(), Pubkey::new_unique(), 100);
+
+        let instructions = guarded(std::slice::from_ref(&transfer), Some(1_000));
+
+        assert_eq!(instructions, vec![TokenInstruction::check_deadline(1_000), transfer.clone()]);
+        assert_eq!(guarded(std::slice::from_ref(&transfer), None), vec![transfer]);
+    }
+
+    #[test]
+    fn when_prioritized_expect_price_before_guard() {
+        let transfer = TokenInstruction::transfer_lamports(Pubkey::new_unique(), Pubkey::new_unique(), 100);
+
+        let instructions = prioritized(&guarded(std::slice::from_ref(&transfer), Some(1_000)), 5_000);
+
+        let expected = vec![set_compute_unit_price(5_000), TokenInstruction::check_deadline(1_000), transfer];
+        assert_eq!(instructions, expected);
+    }
+
+    #[test]
+    fn when_by_sla_expect_urgent_first_and_stable() {
+        let slas = [Sla::BestEffort, Sla::Urgent, Sla::Standard, Sla::Urgent];
+
+        assert_eq!(by_sla(&slas), vec![1, 3, 2, 0]);
+    }
+
+    #[test]
+    fn when_run_concurrent_expect_bounded_parallelism() {
+        let items = vec![(); 20];
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+
+        run_concurrent(&items, 3, |_| {
+            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now_running, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+    }
+}
@@ -0,0 +1,248 @@
+//! Jito bundle submission, enabled by `jito` feature.
+//!
+//! Transactions of a bundle land in one block in the given order or none of them does, e.g. a settlement
+//! batch spanning several transactions during congestion. The block engine only auctions bundles paying
+//! a tip, so the last transaction transfers the tip to a Jito tip account and it's paid only with the bundle.
+use std::thread;
+use std::time::Duration;
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::TransactionStatus;
+use crate::client::error::{ClientError, ClientResult};
+
+/// Max transactions of a bundle accepted by the block engine
+pub const MAX_BUNDLE_LEN: usize = 5;
+pub const MAINNET_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf/api/v1/bundles";
+/// Tip accounts of mainnet block engines, as returned by their `getTipAccounts`
+pub const MAINNET_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct BundleClient<'a> {
+    rpc_client: &'a RpcClient,
+    http_client: reqwest::blocking::Client,
+    fee_payer: &'a dyn Signer,
+    block_engine_url: String,
+    tip_accounts: Vec<Pubkey>,
+    tip: u64,
+}
+
+impl<'a> BundleClient<'a> {
+    /// Client of mainnet block engine, `fee_payer` pays fees and `tip` lamports per bundle
+    pub fn new(rpc_client: &'a RpcClient, fee_payer: &'a dyn Signer, tip: u64) -> Self {
+        BundleClient {
+            rpc_client,
+            http_client: reqwest::blocking::Client::new(),
+            fee_payer,
+            block_engine_url: MAINNET_BLOCK_ENGINE_URL.to_string(),
+            tip_accounts: MAINNET_TIP_ACCOUNTS.iter().map(|tip_account| tip_account.parse().unwrap()).collect(),
+            tip,
+        }
+    }
+
+    /// Block engine of another region or cluster and its tip accounts
+    pub fn with_block_engine(mut self, block_engine_url: String, tip_accounts: Vec<Pubkey>) -> Self {
+        assert!(!tip_accounts.is_empty(), "Block engine needs a tip account");
+        self.block_engine_url = block_engine_url;
+        self.tip_accounts = tip_accounts;
+        self
+    }
+
+    /// Send each instruction set as a transaction of one bundle and wait until the bundle lands,
+    /// signatures keep item order
+    pub fn send_and_confirm(
+        &self,
+        items: &[Vec<Instruction>],
+        signers: &[&dyn Signer],
+    ) -> ClientResult<Vec<Signature>> {
+        let blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transactions = self.bundle(items, signers, blockhash)?;
+        self.send(&transactions)?;
+        let signatures: Vec<Signature> = transactions.iter().map(|transaction| transaction.signatures[0]).collect();
+        self.wait_landed(&signatures, &blockhash)?;
+        Ok(signatures)
+    }
+
+    /// Sign every instruction set as a transaction paid by fee payer, tip transfer goes last into the last one.
+    /// Transactions are signed by those of `signers` they require
+    pub fn bundle(
+        &self,
+        items: &[Vec<Instruction>],
+        signers: &[&dyn Signer],
+        blockhash: Hash,
+    ) -> ClientResult<Vec<Transaction>> {
+        if items.is_empty() || items.len() > MAX_BUNDLE_LEN {
+            return Err(ClientError::InvalidBundleLen(items.len()))
+        }
+        let fee_payer = self.fee_payer.pubkey();
+        let tip_instr = system_instruction::transfer(&fee_payer, &self.tip_account(&blockhash), self.tip);
+        items.iter().enumerate().map(|(index, instructions)| {
+            let mut instructions = instructions.clone();
+            if index == items.len() - 1 {
+                instructions.push(tip_instr.clone());
+            }
+            let mut transaction = Transaction::new_with_payer(&instructions, Some(&fee_payer));
+            let message = &transaction.message;
+            let required = &message.account_keys[..message.header.num_required_signatures as usize];
+            let mut transaction_signers = vec![self.fee_payer];
+            transaction_signers.extend(signers.iter().filter(|signer| {
+                signer.pubkey() != fee_payer && required.contains(&signer.pubkey())
+            }));
+            transaction.try_sign(&transaction_signers, blockhash)?;
+            Ok(transaction)
+        }).collect()
+    }
+
+    /// Submit signed bundle, returns bundle ID assigned by the block engine
+    pub fn send(&self, transactions: &[Transaction]) -> ClientResult<String> {
+        let request = send_bundle_request(transactions)?;
+        let response: Value = self.http_client.post(&self.block_engine_url).json(&request).send()?.json()?;
+        bundle_id(response)
+    }
+
+    /// Tip accounts are picked by blockhash, spreading bundles over them against write lock contention
+    fn tip_account(&self, blockhash: &Hash) -> Pubkey {
+        self.tip_accounts[blockhash.as_ref()[0] as usize % self.tip_accounts.len()]
+    }
+
+    /// Transactions of a bundle land together, so every signature is waited for until `blockhash` expires
+    fn wait_landed(&self, signatures: &[Signature], blockhash: &Hash) -> ClientResult<()> {
+        let commitment = self.rpc_client.commitment();
+        loop {
+            let statuses = self.rpc_client.get_signature_statuses(signatures)?.value;
+            if let Some(e) = statuses.iter().flatten().find_map(|status| status.err.clone()) {
+                return Err(ClientError::Transaction(e))
+            }
+            let landed = |status: &Option<TransactionStatus>| {
+                status.as_ref().is_some_and(|status| status.satisfies_commitment(commitment))
+            };
+            if statuses.iter().all(landed) {
+                return Ok(())
+            }
+            if !self.rpc_client.is_blockhash_valid(blockhash, self.rpc_client.commitment())? {
+                return Err(ClientError::BundleExpired)
+            }
+            thread::sleep(STATUS_POLL_INTERVAL);
+        }
+    }
+}
+
+/// JSON-RPC `sendBundle` request of base64 encoded wire transactions
+fn send_bundle_request(transactions: &[Transaction]) -> ClientResult<Value> {
+    let encoded = transactions.iter()
+        .map(|transaction| Ok(base64::encode(bincode::serialize(transaction)?)))
+        .collect::<ClientResult<Vec<String>>>()?;
+    Ok(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "sendBundle",
+        "params": [encoded, {"encoding": "base64"}],
+    }))
+}
+
+fn bundle_id(response: Value) -> ClientResult<String> {
+    if let Some(error) = response.get("error") {
+        let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+        return Err(ClientError::BundleRejected(message.to_string()))
+    }
+    response.get("result")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| ClientError::BundleRejected("response has no bundle ID".to_string()))
+}
+
+#[cfg(test)]
+mod bundle_test {
+    use serde_json::json;
+    use solana_client::rpc_client::RpcClient;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction::{self, SystemInstruction};
+    use solana_sdk::transaction::Transaction;
+    use crate::client::bundle::{bundle_id, send_bundle_request, BundleClient, MAX_BUNDLE_LEN};
+    use crate::client::error::ClientError;
+    use crate::instruction::TokenInstruction;
+
+    fn rpc_client() -> RpcClient {
+        RpcClient::new("http://127.0.0.1:8899".to_string())
+    }
+
+    #[test]
+    fn when_bundled_expect_tip_last_and_signed_by_required_signers() {
+        let (rpc_client, fee_payer, from, to) = (rpc_client(), Keypair::new(), Keypair::new(), Keypair::new());
+        let tip_account = Pubkey::new_unique();
+        let client = BundleClient::new(&rpc_client, &fee_payer, 10_000)
+            .with_block_engine("http://127.0.0.1:1".to_string(), vec![tip_account]);
+        let items = vec![
+            vec![TokenInstruction::transfer_lamports(from.pubkey(), Pubkey::new_unique(), 100)],
+            vec![TokenInstruction::transfer_lamports(to.pubkey(), Pubkey::new_unique(), 200)],
+        ];
+
+        let transactions = client.bundle(&items, &[&from, &to], Hash::new_unique()).unwrap();
+
+        assert!(transactions.iter().all(|transaction| transaction.verify().is_ok()));
+        assert_eq!(transactions[0].signatures.len(), 2);
+        assert_eq!(transactions[0].message.instructions.len(), 1);
+        let last = &transactions[1].message;
+        let tip_instr = last.instructions.last().unwrap();
+        assert_eq!(last.account_keys[tip_instr.program_id_index as usize], solana_sdk::system_program::id());
+        assert_eq!(bincode::deserialize(&tip_instr.data).ok(), Some(SystemInstruction::Transfer { lamports: 10_000 }));
+        assert_eq!(last.account_keys[tip_instr.accounts[1] as usize], tip_account);
+    }
+
+    #[test]
+    fn when_bundle_empty_or_too_long_expect_error() {
+        let (rpc_client, fee_payer) = (rpc_client(), Keypair::new());
+        let client = BundleClient::new(&rpc_client, &fee_payer, 10_000);
+        let item = vec![system_instruction::transfer(&fee_payer.pubkey(), &Pubkey::new_unique(), 1)];
+
+        for len in [0, MAX_BUNDLE_LEN + 1] {
+            let result = client.bundle(&vec![item.clone(); len], &[], Hash::new_unique());
+
+            assert!(matches!(result, Err(ClientError::InvalidBundleLen(bundle_len)) if bundle_len == len));
+        }
+    }
+
+    #[test]
+    fn when_send_bundle_request_expect_base64_wire_transactions() {
+        let fee_payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&fee_payer.pubkey(), &Pubkey::new_unique(), 1)],
+            Some(&fee_payer.pubkey()),
+            &[&fee_payer],
+            Hash::new_unique(),
+        );
+
+        let request = send_bundle_request(std::slice::from_ref(&transaction)).unwrap();
+
+        assert_eq!(request["method"], "sendBundle");
+        assert_eq!(request["params"][1]["encoding"], "base64");
+        let wire = base64::decode(request["params"][0][0].as_str().unwrap()).unwrap();
+        assert_eq!(bincode::deserialize::<Transaction>(&wire).unwrap(), transaction);
+    }
+
+    #[test]
+    fn when_block_engine_responds_expect_bundle_id_or_rejection() {
+        let accepted = json!({"jsonrpc": "2.0", "id": 1, "result": "2id3YC2jgd"});
+        let rejected = json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "bundle has no tip"}});
+
+        assert_eq!(bundle_id(accepted).unwrap(), "2id3YC2jgd");
+        let result = bundle_id(rejected);
+        assert!(matches!(result, Err(ClientError::BundleRejected(message)) if message == "bundle has no tip"));
+    }
+}
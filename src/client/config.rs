@@ -0,0 +1,186 @@
+//! Program deployment targeted by the client, defaults to the ID this crate was built with.
+//!
+//! A profile names everything that differs between clusters: RPC endpoint, deployment, commitment and
+//! fee strategy. Built-in `mainnet` and `devnet` profiles target the default deployment, a profiles file
+//! adds custom ones or overrides them.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::pubkey::Pubkey;
+use crate::client::error::{ClientError, ClientResult};
+use crate::client::priority::{PriorityFees, Sla};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgramConfig {
+    pub program_id: Pubkey,
+    pub token_program_id: Pubkey,
+}
+
+impl Default for ProgramConfig {
+    fn default() -> Self {
+        ProgramConfig { program_id: crate::id(), token_program_id: spl_token::id() }
+    }
+}
+
+impl ProgramConfig {
+    /// Both programs must be deployed on the cluster behind `rpc_client`
+    pub fn check_deployed(&self, rpc_client: &RpcClient) -> ClientResult<()> {
+        for program_id in [self.program_id, self.token_program_id] {
+            let account = rpc_client.get_account(&program_id).map_err(|_| ClientError::ProgramNotDeployed(program_id))?;
+            if !account.executable {
+                return Err(ClientError::ProgramNotDeployed(program_id))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compute unit price paid by transactions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FeeStrategy {
+    /// Base fee only
+    #[default]
+    Base,
+    /// Price bid by `sla` on the fee market, see `PriorityFees::price`
+    Priority { min_price: u64, max_price: u64, sla: Sla },
+}
+
+impl FeeStrategy {
+    pub fn priority_fees(&self) -> Option<(PriorityFees, Sla)> {
+        match *self {
+            FeeStrategy::Base => None,
+            FeeStrategy::Priority { min_price, max_price, sla } => {
+                Some((PriorityFees::new(min_price, max_price), sla))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// RPC endpoint URL or moniker of the Solana CLI, e.g. `devnet`
+    pub json_rpc_url: String,
+    /// Program deployment, base58 encoded, the ID this crate was built with by default
+    #[serde(default)]
+    pub program_id: Option<String>,
+    /// Token program of the mints, base58 encoded, SPL Token by default
+    #[serde(default)]
+    pub token_program_id: Option<String>,
+    /// Confirmed by default
+    #[serde(default)]
+    pub commitment: Option<CommitmentLevel>,
+    #[serde(default)]
+    pub fee_strategy: FeeStrategy,
+}
+
+impl Profile {
+    pub fn new(json_rpc_url: &str) -> Self {
+        Profile {
+            json_rpc_url: json_rpc_url.to_string(),
+            program_id: None,
+            token_program_id: None,
+            commitment: None,
+            fee_strategy: FeeStrategy::Base,
+        }
+    }
+
+    /// Built-in profile of `name`, `mainnet` bids priority fees for standard payouts
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "mainnet" => Some(Profile {
+                commitment: Some(CommitmentLevel::Finalized),
+                fee_strategy: FeeStrategy::Priority { min_price: 1_000, max_price: 1_000_000, sla: Sla::Standard },
+                ..Profile::new("https://api.mainnet-beta.solana.com")
+            }),
+            "devnet" => Some(Profile::new("https://api.devnet.solana.com")),
+            _ => None,
+        }
+    }
+
+    pub fn program_config(&self, name: &str) -> ClientResult<ProgramConfig> {
+        let parse = |key: &Option<String>, default: Pubkey| match key {
+            Some(key) => key.parse().map_err(|_| ClientError::InvalidProfile(name.to_string())),
+            None => Ok(default),
+        };
+        let default = ProgramConfig::default();
+        Ok(ProgramConfig {
+            program_id: parse(&self.program_id, default.program_id)?,
+            token_program_id: parse(&self.token_program_id, default.token_program_id)?,
+        })
+    }
+
+    pub fn commitment_config(&self) -> CommitmentConfig {
+        self.commitment.map(|commitment| CommitmentConfig { commitment }).unwrap_or_else(CommitmentConfig::confirmed)
+    }
+}
+
+/// Custom profiles persisted as a JSON object keyed by name
+#[derive(Debug, Default)]
+pub struct Profiles {
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    /// Open profiles file at `path`, missing file has no custom profiles
+    pub fn open<P: AsRef<Path>>(path: P) -> ClientResult<Self> {
+        let path = path.as_ref();
+        let profiles = if path.exists() { serde_json::from_slice(&fs::read(path)?)? } else { BTreeMap::new() };
+        Ok(Profiles { profiles })
+    }
+
+    /// Custom profile of `name`, otherwise the built-in one
+    pub fn get(&self, name: &str) -> ClientResult<Profile> {
+        self.profiles.get(name)
+            .cloned()
+            .or_else(|| Profile::builtin(name))
+            .ok_or_else(|| ClientError::UnknownProfile(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod config_test {
+    use std::fs;
+    use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::config::{FeeStrategy, Profile, Profiles, ProgramConfig};
+    use crate::client::error::ClientError;
+    use crate::client::priority::Sla;
+
+    #[test]
+    fn when_custom_profile_expect_it_overrides_builtin() {
+        let path = std::env::temp_dir().join(format!("profiles-{}.json", Pubkey::new_unique()));
+        let program_id = Pubkey::new_unique();
+        fs::write(&path, format!(r#"{{
+            "devnet": {{"json_rpc_url": "http://127.0.0.1:8899", "program_id": "{}", "commitment": "processed"}},
+            "staging": {{
+                "json_rpc_url": "https://staging.example.com",
+                "fee_strategy": {{"kind": "priority", "min_price": 10, "max_price": 100, "sla": "urgent"}}
+            }}
+        }}"#, program_id)).unwrap();
+
+        let profiles = Profiles::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let devnet = profiles.get("devnet").unwrap();
+        assert_eq!(devnet.json_rpc_url, "http://127.0.0.1:8899");
+        assert_eq!(devnet.program_config("devnet").unwrap(), ProgramConfig { program_id, ..ProgramConfig::default() });
+        assert_eq!(devnet.commitment_config(), CommitmentConfig::processed());
+        let staging = profiles.get("staging").unwrap();
+        assert_eq!(staging.fee_strategy, FeeStrategy::Priority { min_price: 10, max_price: 100, sla: Sla::Urgent });
+        assert_eq!(staging.commitment_config(), CommitmentConfig::confirmed());
+        assert_eq!(profiles.get("mainnet").unwrap(), Profile::builtin("mainnet").unwrap());
+        assert_eq!(profiles.get("mainnet").unwrap().commitment, Some(CommitmentLevel::Finalized));
+    }
+
+    #[test]
+    fn when_unknown_or_invalid_profile_expect_error() {
+        let profile = Profile { program_id: Some("not base58".to_string()), ..Profile::new("devnet") };
+
+        assert!(matches!(Profiles::default().get("prod"), Err(ClientError::UnknownProfile(name)) if name == "prod"));
+        assert!(matches!(profile.program_config("qa"), Err(ClientError::InvalidProfile(name)) if name == "qa"));
+    }
+}
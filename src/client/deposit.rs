@@ -0,0 +1,107 @@
+//! Per-customer deposit addresses of an exchange, see `DepositScheme`.
+//!
+//! Every user ID gets its own address derived from the scheme, so addresses are unlimited and need no
+//! keypairs to store. Anyone can sweep a deposit into the omnibus wallet of the scheme, and the user to
+//! credit is found by deriving addresses again instead of keeping an address to user table.
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use crate::instruction::TokenInstruction;
+use crate::state::DepositScheme;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepositAddresses {
+    pub scheme: Pubkey,
+    pub omnibus: Pubkey,
+}
+
+impl DepositAddresses {
+    /// Deposit addresses of the scheme of exchange `authority`
+    pub fn new(authority: &Pubkey, omnibus: Pubkey) -> Self {
+        DepositAddresses { scheme: DepositScheme::find_address(authority).0, omnibus }
+    }
+
+    /// Wallet to give `user_id` for lamport deposits
+    pub fn address(&self, user_id: u64) -> Pubkey {
+        DepositScheme::find_deposit_address(&self.scheme, user_id).0
+    }
+
+    /// Token account to give `user_id` for deposits of `mint`
+    pub fn token_address(&self, user_id: u64, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address(&self.address(user_id), mint)
+    }
+
+    /// User ID among `candidates` the deposit `address` belongs to
+    pub fn user_id(&self, address: &Pubkey, candidates: impl IntoIterator<Item = u64>) -> Option<u64> {
+        candidates.into_iter().find(|user_id| self.address(*user_id) == *address)
+    }
+
+    /// User ID among `candidates` the deposit token account `address` of `mint` belongs to
+    pub fn token_user_id(
+        &self,
+        address: &Pubkey,
+        mint: &Pubkey,
+        candidates: impl IntoIterator<Item = u64>,
+    ) -> Option<u64> {
+        candidates.into_iter().find(|user_id| self.token_address(*user_id, mint) == *address)
+    }
+
+    /// Create token account of `mint` for `user_id`, paid by `payer`
+    pub fn create_token_address(&self, payer: &Pubkey, user_id: u64, mint: &Pubkey) -> Instruction {
+        create_associated_token_account(payer, &self.address(user_id), mint, &spl_token::id())
+    }
+
+    pub fn sweep(&self, user_id: u64) -> Instruction {
+        TokenInstruction::sweep_deposit(self.scheme, self.omnibus, user_id)
+    }
+
+    /// Sweep into the associated token account of the omnibus wallet
+    pub fn sweep_spl_token(&self, user_id: u64, mint: Pubkey) -> Instruction {
+        let omnibus_token = get_associated_token_address(&self.omnibus, &mint);
+        TokenInstruction::sweep_deposit_spl_token(self.scheme, omnibus_token, mint, user_id)
+    }
+}
+
+#[cfg(test)]
+mod deposit_test {
+    use solana_sdk::pubkey::Pubkey;
+    use spl_associated_token_account::get_associated_token_address;
+    use crate::client::deposit::DepositAddresses;
+
+    #[test]
+    fn when_address_expect_user_id_recovered() {
+        let deposits = DepositAddresses::new(&Pubkey::new_unique(), Pubkey::new_unique());
+        let address = deposits.address(42);
+
+        assert_ne!(address, deposits.address(43));
+        assert_eq!(deposits.user_id(&address, 0..100), Some(42));
+        assert_eq!(deposits.user_id(&address, 0..42), None);
+        assert_eq!(deposits.user_id(&Pubkey::new_unique(), 0..100), None);
+    }
+
+    #[test]
+    fn when_token_address_expect_associated_token_account_of_address() {
+        let deposits = DepositAddresses::new(&Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = Pubkey::new_unique();
+        let token_address = deposits.token_address(7, &mint);
+
+        assert_eq!(token_address, get_associated_token_address(&deposits.address(7), &mint));
+        assert_eq!(deposits.token_user_id(&token_address, &mint, 0..10), Some(7));
+    }
+
+    #[test]
+    fn when_sweep_expect_deposit_and_omnibus_accounts() {
+        let deposits = DepositAddresses::new(&Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = Pubkey::new_unique();
+
+        let sweep = deposits.sweep(7);
+        assert_eq!(sweep.accounts[0].pubkey, deposits.scheme);
+        assert_eq!(sweep.accounts[1].pubkey, deposits.address(7));
+        assert_eq!(sweep.accounts[2].pubkey, deposits.omnibus);
+
+        let sweep = deposits.sweep_spl_token(7, mint);
+        assert_eq!(sweep.accounts[2].pubkey, deposits.token_address(7, &mint));
+        assert_eq!(sweep.accounts[3].pubkey, get_associated_token_address(&deposits.omnibus, &mint));
+    }
+}
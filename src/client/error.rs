@@ -0,0 +1,117 @@
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+use thiserror::Error;
+use crate::error::TransferError;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error(transparent)]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error("Transaction failed: {0}")]
+    Transaction(#[from] solana_sdk::transaction::TransactionError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("Payout job journal is corrupted")]
+    InvalidJournal,
+    #[error(transparent)]
+    Signer(#[from] solana_sdk::signer::SignerError),
+    #[error("Partial signature of participant {0} already added")]
+    DuplicatePartialSignature(u16),
+    #[error("Not enough partial signatures")]
+    ThresholdNotReached,
+    #[error("Signature does not verify against the expected pubkey")]
+    InvalidSignature,
+    #[error("Program {0} is not deployed on the cluster")]
+    ProgramNotDeployed(solana_sdk::pubkey::Pubkey),
+    #[error("Transaction violates policy: {0:?}")]
+    PolicyViolation(Vec<crate::client::policy::Violation>),
+    #[error("Preflight found problems: {0:?}")]
+    PreflightFailed(Vec<crate::client::preflight::Problem>),
+    #[error("Wallet payload is not a valid message")]
+    InvalidWalletPayload,
+    #[error(transparent)]
+    Nonce(#[from] solana_client::nonce_utils::Error),
+    #[error("Transfer intent is corrupted")]
+    InvalidIntent,
+    #[error("Events {0:?} are missing from transaction history")]
+    EventGap(std::ops::Range<u64>),
+    #[cfg(feature = "archiver")]
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "archiver")]
+    #[error(transparent)]
+    Postgres(#[from] postgres::Error),
+    #[error("Value {0} does not fit archive BIGINT column")]
+    ArchiveOverflow(u64),
+    #[error("Archive has a row of unexpected values")]
+    InvalidArchive,
+    #[error("Address book has no contact {0}")]
+    UnknownAlias(String),
+    #[error("Address book has an invalid contact")]
+    InvalidAddressBook,
+    #[error("Checksum does not match the wallet of contact {0}")]
+    ChecksumMismatch(String),
+    #[error("Contact {0} requires a memo")]
+    MemoRequired(String),
+    #[error("Bundle needs 1 to 5 transactions, got {0}")]
+    InvalidBundleLen(usize),
+    #[error("Block engine rejected bundle: {0}")]
+    BundleRejected(String),
+    #[error("Bundle did not land before its blockhash expired")]
+    BundleExpired,
+    #[error("Migration {0} is not started")]
+    MigrationNotStarted(solana_sdk::pubkey::Pubkey),
+    #[error("Profile {0} is not defined")]
+    UnknownProfile(String),
+    #[error("Profile {0} has an invalid program ID")]
+    InvalidProfile(String),
+    #[cfg(feature = "jito")]
+    #[error(transparent)]
+    Bincode(#[from] bincode::Error),
+}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(Box::new(e))
+    }
+}
+
+impl ClientError {
+    /// Program error a failed transaction returned, decoded from its custom error code
+    pub fn transfer_error(&self) -> Option<TransferError> {
+        let transaction_error = match self {
+            ClientError::Transaction(e) => Some(e.clone()),
+            ClientError::Rpc(e) => e.get_transaction_error(),
+            _ => None,
+        };
+        match transaction_error {
+            Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) => {
+                TransferError::from_code(code)
+            }
+            _ => None,
+        }
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+#[cfg(test)]
+mod error_test {
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+    use crate::client::error::ClientError;
+    use crate::error::TransferError;
+
+    #[test]
+    fn when_custom_instruction_error_expect_transfer_error() {
+        let code = TransferError::InvalidTokenProgram as u32;
+        let error = ClientError::Transaction(TransactionError::InstructionError(1, InstructionError::Custom(code)));
+
+        assert_eq!(error.transfer_error(), Some(TransferError::InvalidTokenProgram));
+        assert_eq!(ClientError::InvalidJournal.transfer_error(), None);
+    }
+}
@@ -0,0 +1,471 @@
+//! Sequenced event consumer for indexers. Events of instructions carrying an event stream are logged as
+//! `EventRecord` stamped with the stream and a sequence number within it, the consumer follows one stream,
+//! delivers its events in sequence order and backfills gaps from transaction history.
+//!
+//! Every sequenced event write-locks its event stream, so signature history of the event stream account
+//! contains all transactions that emitted one.
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::str::FromStr;
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use crate::client::error::{ClientError, ClientResult};
+use crate::event::{EventRecord, TokenEvent};
+use crate::state::EventStream;
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+/// Event records logged by `program_id` itself, logs of programs it invokes are skipped
+pub fn parse_logs(program_id: &Pubkey, logs: &[String]) -> Vec<EventRecord> {
+    let program_id = program_id.to_string();
+    let mut invoked: Vec<&str> = Vec::new();
+    let mut records = Vec::new();
+    for log in logs {
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+            if invoked.last() == Some(&program_id.as_str()) {
+                let record = base64::decode(data).ok().and_then(|data| EventRecord::try_from_slice(&data).ok());
+                records.extend(record);
+            }
+            continue
+        }
+        let mut words = log.split(' ').skip(1);
+        match (words.next(), words.next()) {
+            (Some(program), Some("invoke")) => invoked.push(program),
+            (Some(_), Some("success")) | (Some(_), Some("failed:")) => {
+                invoked.pop();
+            }
+            _ => {}
+        }
+    }
+    records
+}
+
+/// Orders records of event stream `stream` by sequence starting at the expected next one, holding back
+/// records after a gap
+#[derive(Debug)]
+pub struct SequenceTracker {
+    stream: Pubkey,
+    next_sequence: u64,
+    pending: BTreeMap<u64, TokenEvent>,
+}
+
+impl SequenceTracker {
+    pub fn new(stream: Pubkey, next_sequence: u64) -> Self {
+        SequenceTracker { stream, next_sequence, pending: BTreeMap::new() }
+    }
+
+    pub fn stream(&self) -> Pubkey {
+        self.stream
+    }
+
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// Events deliverable after accepting `record`, in sequence order. Unsequenced events and events of
+    /// other streams are delivered at once, already delivered sequences are dropped.
+    pub fn push(&mut self, record: EventRecord) -> Vec<EventRecord> {
+        let sequence = match record.sequence {
+            Some(sequence) if record.stream == Some(self.stream) => sequence,
+            _ => return vec![record],
+        };
+        if sequence >= self.next_sequence {
+            self.pending.insert(sequence, record.event);
+        }
+        let mut ready = Vec::new();
+        while let Some(event) = self.pending.remove(&self.next_sequence) {
+            ready.push(EventRecord { sequence: Some(self.next_sequence), stream: Some(self.stream), event });
+            self.next_sequence += 1;
+        }
+        ready
+    }
+
+    /// Sequences not received yet but preceding held back records
+    pub fn missing(&self) -> Option<Range<u64>> {
+        self.pending.keys().next().map(|&first| self.next_sequence..first)
+    }
+}
+
+pub struct EventConsumer<'a> {
+    rpc_client: &'a RpcClient,
+    program_id: Pubkey,
+    tracker: SequenceTracker,
+}
+
+impl<'a> EventConsumer<'a> {
+    /// Consumer of event stream of `key`, see `EventStream::find_address`, expecting `next_sequence` first,
+    /// 0 for a fresh stream
+    pub fn new(rpc_client: &'a RpcClient, program_id: Pubkey, key: &Pubkey, next_sequence: u64) -> Self {
+        let (stream, _) = Pubkey::find_program_address(&[EventStream::SEED, key.as_ref()], &program_id);
+        EventConsumer { rpc_client, program_id, tracker: SequenceTracker::new(stream, next_sequence) }
+    }
+
+    pub fn stream(&self) -> Pubkey {
+        self.tracker.stream()
+    }
+
+    pub fn next_sequence(&self) -> u64 {
+        self.tracker.next_sequence()
+    }
+
+    /// Events of a transaction with `logs`, e.g. from a log subscription, in sequence order
+    /// together with any missed events before them
+    pub fn consume(&mut self, logs: &[String]) -> ClientResult<Vec<EventRecord>> {
+        let mut events = Vec::new();
+        for record in parse_logs(&self.program_id, logs) {
+            events.extend(self.tracker.push(record));
+        }
+        if self.tracker.missing().is_some() {
+            events.extend(self.backfill()?);
+        }
+        Ok(events)
+    }
+
+    /// Walk event stream signature history back from the newest transaction until missing events are found
+    fn backfill(&mut self) -> ClientResult<Vec<EventRecord>> {
+        let stream = self.tracker.stream();
+        let mut events = Vec::new();
+        let mut before = None;
+        while let Some(missing) = self.tracker.missing() {
+            let statuses = self.rpc_client.get_signatures_for_address_with_config(
+                &stream,
+                GetConfirmedSignaturesForAddress2Config { before, ..GetConfirmedSignaturesForAddress2Config::default() },
+            )?;
+            if statuses.is_empty() {
+                return Err(ClientError::EventGap(missing))
+            }
+            for status in statuses {
+                let signature = Signature::from_str(&status.signature).map_err(|_| ClientError::InvalidSignature)?;
+                before = Some(signature);
+                if status.err.is_some() {
+                    continue
+                }
+                let transaction = self.rpc_client.get_transaction(&signature, UiTransactionEncoding::Json)?;
+                let logs: Vec<String> = transaction.transaction.meta
+                    .and_then(|meta| meta.log_messages.into())
+                    .unwrap_or_default();
+                let records: Vec<EventRecord> = parse_logs(&self.program_id, &logs)
+                    .into_iter()
+                    .filter(|record| record.sequence.is_some() && record.stream == Some(stream))
+                    .collect();
+                // Sequences grow with history, anything older than the gap means it can't be filled
+                if records.iter().any(|record| record.sequence.is_some_and(|sequence| sequence < missing.start)) {
+                    return Err(ClientError::EventGap(missing))
+                }
+                for record in records {
+                    events.extend(self.tracker.push(record));
+                }
+                if self.tracker.missing().is_none() {
+                    break
+                }
+            }
+        }
+        Ok(events)
+    }
+}
+
+/// Name, mint, accounts and amount of an event, what webhook filters and the archive see
+#[derive(Debug, PartialEq)]
+pub(crate) struct EventFields {
+    pub name: &'static str,
+    /// Lamport events count as `spl_token::native_mint`, None when unknown
+    pub mint: Option<Pubkey>,
+    pub accounts: Vec<Pubkey>,
+    pub amount: Option<u64>,
+}
+
+impl EventFields {
+    pub fn new(event: &TokenEvent) -> Self {
+        let native = Some(spl_token::native_mint::id());
+        let (name, mint, accounts, amount) = match event {
+            TokenEvent::BondPosted(e) => {
+                ("bond_posted", native, vec![e.bond, e.depositor, e.arbiter, e.beneficiary], Some(e.amount))
+            }
+            TokenEvent::BondRefunded(e) => ("bond_refunded", native, vec![e.bond, e.depositor], Some(e.amount)),
+            TokenEvent::BondSlashed(e) => ("bond_slashed", native, vec![e.bond, e.beneficiary], Some(e.amount)),
+            TokenEvent::Tip(e) => ("tip", native, vec![e.creator, e.tipper], Some(e.amount)),
+            TokenEvent::MatchingPoolCreated(e) => {
+                ("matching_pool_created", native, vec![e.pool, e.sponsor, e.recipient], Some(e.amount))
+            }
+            TokenEvent::DonationMatched(e) => {
+                ("donation_matched", native, vec![e.pool, e.donor, e.recipient], Some(e.amount))
+            }
+            TokenEvent::MatchingPoolClosed(e) => {
+                ("matching_pool_closed", native, vec![e.pool, e.sponsor], Some(e.returned))
+            }
+            TokenEvent::RoundUpSaved(e) => ("round_up_saved", native, vec![e.owner, e.to], Some(e.amount)),
+            TokenEvent::SavingsWithdrawn(e) => ("savings_withdrawn", native, vec![e.owner], Some(e.amount)),
+            TokenEvent::NettingOpened(e) => ("netting_opened", native, vec![e.netting, e.party_a, e.party_b], None),
+            TokenEvent::ObligationRegistered(e) => {
+                ("obligation_registered", native, vec![e.netting, e.debtor, e.creditor], Some(e.amount))
+            }
+            TokenEvent::NettingSettled(e) => {
+                ("netting_settled", native, vec![e.netting, e.debtor, e.creditor], Some(e.net_amount))
+            }
+            TokenEvent::DepositToProgram(e) => {
+                ("deposit_to_program", None, vec![e.depositor, e.program, e.destination], Some(e.amount))
+            }
+            TokenEvent::UnsupportedInstruction(_) => ("unsupported_instruction", None, vec![], None),
+            TokenEvent::PayoutRootPublished(e) => {
+                ("payout_root_published", native, vec![e.payout_batch, e.authority], None)
+            }
+            TokenEvent::PayoutExecuted(e) => {
+                ("payout_executed", native, vec![e.payout_batch, e.recipient], Some(e.amount))
+            }
+            TokenEvent::DistributorCreated(e) => {
+                ("distributor_created", native, vec![e.distributor, e.authority, e.attestor], Some(e.amount))
+            }
+            TokenEvent::DistributionClaimed(e) => {
+                ("distribution_claimed", native, vec![e.distributor, e.claimer], Some(e.amount))
+            }
+            TokenEvent::AttestationSet(e) => ("attestation_set", None, vec![e.wallet], None),
+            TokenEvent::TravelRuleData(e) => ("travel_rule_data", None, vec![e.from, e.to], Some(e.amount)),
+            TokenEvent::MintConfigSet(e) => ("mint_config_set", Some(e.mint), vec![e.issuer], None),
+            TokenEvent::RentalListed(e) => ("rental_listed", Some(e.mint), vec![e.rental, e.owner], Some(e.price)),
+            TokenEvent::NftRented(e) => ("nft_rented", native, vec![e.rental, e.renter], Some(e.price)),
+            TokenEvent::RentalReturned(e) => ("rental_returned", None, vec![e.rental, e.owner, e.renter], None),
+            TokenEvent::NftFractionalized(e) => (
+                "nft_fractionalized",
+                Some(e.nft_mint),
+                vec![e.fraction, e.depositor, e.share_mint],
+                Some(e.shares),
+            ),
+            TokenEvent::NftRedeemed(e) => ("nft_redeemed", Some(e.nft_mint), vec![e.fraction, e.redeemer], None),
+            TokenEvent::OrderPosted(e) => (
+                "order_posted",
+                Some(e.sell_mint),
+                vec![e.order, e.maker, e.buy_mint],
+                Some(e.sell_amount),
+            ),
+            TokenEvent::OrderFilled(e) => ("order_filled", None, vec![e.order, e.taker], Some(e.amount)),
+            TokenEvent::OrderPartiallyFilled(e) => {
+                ("order_partially_filled", None, vec![e.order, e.taker], Some(e.amount))
+            }
+            TokenEvent::OrderCancelled(e) => ("order_cancelled", None, vec![e.order, e.maker], Some(e.refunded)),
+            TokenEvent::PriceUpdated(e) => ("price_updated", Some(e.mint), vec![], None),
+            TokenEvent::KeeperRegistered(e) => ("keeper_registered", None, vec![e.keeper], None),
+            TokenEvent::KeeperBanSet(e) => ("keeper_ban_set", None, vec![e.keeper], None),
+            TokenEvent::VaultInitialized(e) => ("vault_initialized", Some(e.mint), vec![e.vault, e.owner], None),
+            TokenEvent::VaultDeposited(e) => ("vault_deposited", None, vec![e.vault, e.depositor], Some(e.amount)),
+            TokenEvent::VaultWithdrawn(e) => ("vault_withdrawn", None, vec![e.vault, e.to], Some(e.amount)),
+            TokenEvent::EscrowInitialized(e) => {
+                ("escrow_initialized", None, vec![e.escrow, e.initializer], Some(e.amount))
+            }
+            TokenEvent::EscrowExchanged(e) => {
+                ("escrow_exchanged", None, vec![e.escrow, e.initializer, e.taker], Some(e.amount))
+            }
+            TokenEvent::EscrowCancelled(e) => {
+                ("escrow_cancelled", None, vec![e.escrow, e.initializer], Some(e.refunded))
+            }
+            TokenEvent::SelfTestPassed(e) => ("self_test_passed", None, vec![e.caller], None),
+            TokenEvent::VestingCreated(e) => (
+                "vesting_created",
+                Some(e.mint),
+                vec![e.vesting, e.creator, e.beneficiary],
+                Some(e.total_amount),
+            ),
+            TokenEvent::VestingClaimed(e) => ("vesting_claimed", None, vec![e.vesting, e.beneficiary], Some(e.amount)),
+            TokenEvent::VestingCancelled(e) => {
+                ("vesting_cancelled", None, vec![e.vesting, e.creator], Some(e.released + e.refunded))
+            }
+            TokenEvent::SponsorPoolCreated(e) => {
+                ("sponsor_pool_created", None, vec![e.pool, e.sponsor, e.operator], Some(e.amount))
+            }
+            TokenEvent::SponsorPoolFunded(e) => ("sponsor_pool_funded", None, vec![e.pool, e.funder], Some(e.amount)),
+            TokenEvent::AccountSponsored(e) => (
+                "account_sponsored",
+                Some(e.mint),
+                vec![e.pool, e.wallet, e.token_account],
+                Some(e.rent),
+            ),
+            TokenEvent::SponsorPoolClosed(e) => {
+                ("sponsor_pool_closed", None, vec![e.pool, e.sponsor], Some(e.returned))
+            }
+            TokenEvent::StreamCreated(e) => {
+                let mint = if e.mint == Pubkey::default() { None } else { Some(e.mint) };
+                ("stream_created", mint, vec![e.stream, e.sender, e.recipient], Some(e.deposit))
+            }
+            TokenEvent::StreamWithdrawn(e) => ("stream_withdrawn", None, vec![e.stream, e.recipient], Some(e.amount)),
+            TokenEvent::StreamClosed(e) => {
+                ("stream_closed", None, vec![e.stream, e.sender], Some(e.paid + e.refunded))
+            }
+            TokenEvent::SplTokenParked(e) => {
+                ("spl_token_parked", Some(e.mint), vec![e.claimable, e.from, e.wallet], Some(e.amount))
+            }
+            TokenEvent::BalanceClaimed(e) => {
+                ("balance_claimed", Some(e.mint), vec![e.claimable, e.wallet], Some(e.amount))
+            }
+            TokenEvent::DelegateRevoked(e) => {
+                ("delegate_revoked", None, vec![e.revocation, e.delegate, e.authority], None)
+            }
+            TokenEvent::DelegateReinstated(e) => {
+                ("delegate_reinstated", None, vec![e.revocation, e.delegate, e.authority], None)
+            }
+            TokenEvent::RecipientRegistered(e) => {
+                ("recipient_registered", None, vec![e.roster_entry, e.payer, e.recipient], None)
+            }
+            TokenEvent::RecipientRemoved(e) => {
+                ("recipient_removed", None, vec![e.roster_entry, e.payer, e.recipient], None)
+            }
+            TokenEvent::TransferLamports(e) => ("transfer_lamports", native, vec![e.from, e.to], Some(e.amount)),
+            TokenEvent::TransferSpl(e) => ("transfer_spl", Some(e.mint), vec![e.from, e.to, e.owner], Some(e.amount)),
+            TokenEvent::Approve(e) => ("approve", None, vec![e.owner, e.source, e.delegate], Some(e.amount)),
+            TokenEvent::ContractCreated(e) => (
+                "contract_created",
+                Some(e.mint),
+                vec![e.contract, e.funder, e.payee, e.arbiter],
+                Some(e.total_amount),
+            ),
+            TokenEvent::DeliverableSubmitted(e) => ("deliverable_submitted", None, vec![e.contract, e.payee], None),
+            TokenEvent::MilestoneReleased(e) => {
+                ("milestone_released", None, vec![e.contract, e.authority], Some(e.amount))
+            }
+            TokenEvent::ContractClosed(e) => {
+                ("contract_closed", None, vec![e.contract, e.closed_by], Some(e.refunded))
+            }
+            TokenEvent::SplTokenAccountFrozen(e) => {
+                ("spl_token_account_frozen", Some(e.mint), vec![e.account, e.freeze_authority], None)
+            }
+            TokenEvent::SplTokenAccountThawed(e) => {
+                ("spl_token_account_thawed", Some(e.mint), vec![e.account, e.freeze_authority], None)
+            }
+            TokenEvent::ErrorContext(_) => ("error_context", None, vec![], None),
+            TokenEvent::MerkleDistributionCreated(e) => (
+                "merkle_distribution_created",
+                Some(e.mint),
+                vec![e.distribution, e.authority],
+                Some(e.total_amount),
+            ),
+            TokenEvent::MerkleDistributionClaimed(e) => {
+                ("merkle_distribution_claimed", None, vec![e.distribution, e.claimer], Some(e.amount))
+            }
+            TokenEvent::DepositSchemeInitialized(e) => {
+                ("deposit_scheme_initialized", None, vec![e.scheme, e.authority, e.omnibus], None)
+            }
+            TokenEvent::DepositSwept(e) => {
+                ("deposit_swept", Some(e.mint), vec![e.scheme, e.deposit], Some(e.amount))
+            }
+            TokenEvent::ManagedMintInitialized(e) => {
+                ("managed_mint_initialized", Some(e.mint), vec![e.managed_mint, e.admin], None)
+            }
+            TokenEvent::IssuerSet(e) => ("issuer_set", None, vec![e.managed_mint, e.issuer, e.admin], None),
+            TokenEvent::Issued(e) => ("issued", Some(e.mint), vec![e.issuer, e.to], Some(e.amount)),
+            TokenEvent::Redeemed(e) => ("redeemed", Some(e.mint), vec![e.redeemer, e.from], Some(e.amount)),
+            TokenEvent::DividendCreated(e) => {
+                ("dividend_created", Some(e.mint), vec![e.dividend, e.admin], Some(e.pool_amount))
+            }
+            TokenEvent::DividendClaimed(e) => ("dividend_claimed", None, vec![e.dividend, e.holder], Some(e.amount)),
+            TokenEvent::DividendClawedBack(e) => {
+                ("dividend_clawed_back", Some(e.mint), vec![e.dividend, e.admin], Some(e.amount))
+            }
+        };
+        EventFields { name, mint, accounts, amount }
+    }
+}
+
+#[cfg(test)]
+mod events_test {
+    use borsh::BorshSerialize;
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::events::{parse_logs, EventFields, SequenceTracker};
+    use crate::event::{EventRecord, TipEvent, TokenEvent, TransferSplEvent};
+
+    const STREAM: Pubkey = Pubkey::new_from_array([3; 32]);
+
+    fn record(sequence: Option<u64>) -> EventRecord {
+        let event = TokenEvent::Tip(TipEvent {
+            creator: Pubkey::new_from_array([1; 32]),
+            tipper: Pubkey::new_from_array([2; 32]),
+            amount: 100,
+            tipper_total: 100,
+            creator_total: 100,
+        });
+        EventRecord { sequence, stream: sequence.map(|_| STREAM), event }
+    }
+
+    fn sequences(records: &[EventRecord]) -> Vec<Option<u64>> {
+        records.iter().map(|record| record.sequence).collect()
+    }
+
+    #[test]
+    fn when_program_data_of_invoked_program_expect_skipped() {
+        let (program_id, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = base64::encode(record(Some(7)).try_to_vec().unwrap());
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            format!("Program {} invoke [2]", other),
+            format!("Program data: {}", data),
+            format!("Program {} success", other),
+            format!("Program data: {}", data),
+            format!("Program {} success", program_id),
+        ];
+
+        assert_eq!(parse_logs(&program_id, &logs), vec![record(Some(7))]);
+    }
+
+    #[test]
+    fn when_gap_expect_held_back_until_filled() {
+        let mut tracker = SequenceTracker::new(STREAM, 5);
+
+        assert_eq!(sequences(&tracker.push(record(Some(5)))), vec![Some(5)]);
+        assert!(tracker.push(record(Some(8))).is_empty());
+        assert_eq!(tracker.missing(), Some(6..8));
+        assert!(tracker.push(record(Some(7))).is_empty());
+        assert_eq!(sequences(&tracker.push(record(Some(6)))), vec![Some(6), Some(7), Some(8)]);
+        assert_eq!(tracker.missing(), None);
+        assert_eq!(tracker.next_sequence(), 9);
+    }
+
+    #[test]
+    fn when_duplicate_or_unsequenced_expect_dropped_or_delivered() {
+        let mut tracker = SequenceTracker::new(STREAM, 3);
+
+        assert!(tracker.push(record(Some(2))).is_empty());
+        assert_eq!(sequences(&tracker.push(record(None))), vec![None]);
+        assert_eq!(tracker.next_sequence(), 3);
+    }
+
+    #[test]
+    fn when_event_of_other_stream_expect_delivered_without_counting() {
+        let mut tracker = SequenceTracker::new(STREAM, 3);
+        let other = || EventRecord { stream: Some(Pubkey::new_from_array([4; 32])), ..record(Some(9)) };
+
+        assert_eq!(tracker.push(other()), vec![other()]);
+        assert_eq!(tracker.missing(), None);
+        assert_eq!(tracker.next_sequence(), 3);
+    }
+
+    #[test]
+    fn when_transfer_spl_logged_expect_decoded_with_mint() {
+        let program_id = Pubkey::new_unique();
+        let event = TransferSplEvent {
+            from: Pubkey::new_unique(),
+            to: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            amount: 100,
+            fee: 1,
+            memo: Some("invoice 42".to_string()),
+        };
+        let (from, to, owner, mint) = (event.from, event.to, event.owner, event.mint);
+        let record = EventRecord { sequence: Some(0), stream: Some(STREAM), event: TokenEvent::TransferSpl(event) };
+        let data = base64::encode(record.try_to_vec().unwrap());
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            format!("Program data: {}", data),
+            format!("Program {} success", program_id),
+        ];
+
+        let records = parse_logs(&program_id, &logs);
+
+        assert_eq!(records.len(), 1);
+        let fields = EventFields::new(&records[0].event);
+        assert_eq!(fields.name, "transfer_spl");
+        assert_eq!(fields.mint, Some(mint));
+        assert_eq!(fields.accounts, vec![from, to, owner]);
+        assert_eq!(fields.amount, Some(100));
+    }
+}
@@ -0,0 +1,95 @@
+//! Pool of fee payer keypairs rotated between transactions, so a single payer
+//! transaction queue does not bound submission throughput.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use crate::client::error::ClientResult;
+
+pub struct FeePayerPool {
+    payers: Vec<Keypair>,
+    funder: Keypair,
+    /// Payers below this balance are skipped
+    min_balance: u64,
+    /// Lamports sent by funder to a low payer
+    top_up_amount: u64,
+    cursor: AtomicUsize,
+}
+
+impl FeePayerPool {
+    pub fn new(payers: Vec<Keypair>, funder: Keypair, min_balance: u64, top_up_amount: u64) -> Self {
+        assert!(!payers.is_empty(), "Fee payer pool is empty");
+        FeePayerPool { payers, funder, min_balance, top_up_amount, cursor: AtomicUsize::new(0) }
+    }
+
+    pub fn payers(&self) -> &[Keypair] {
+        &self.payers
+    }
+
+    /// Next payer in rotation holding at least `min_balance`,
+    /// when every payer runs low the next one in rotation is topped up from funder
+    pub fn next_payer(&self, rpc_client: &RpcClient) -> ClientResult<&Keypair> {
+        let start = self.cursor.fetch_add(1, Ordering::Relaxed) % self.payers.len();
+        let selected = select_payer(self.payers.len(), start, self.min_balance, |index| {
+            Ok(rpc_client.get_balance(&self.payers[index].pubkey())?)
+        })?;
+        let index = match selected {
+            Some(index) => index,
+            None => {
+                self.top_up(rpc_client, &self.payers[start])?;
+                start
+            }
+        };
+        Ok(&self.payers[index])
+    }
+
+    pub fn top_up(&self, rpc_client: &RpcClient, payer: &Keypair) -> ClientResult<Signature> {
+        let blockhash = rpc_client.get_latest_blockhash()?;
+        let top_up_tx = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&self.funder.pubkey(), &payer.pubkey(), self.top_up_amount)],
+            Some(&self.funder.pubkey()),
+            &[&self.funder],
+            blockhash,
+        );
+        Ok(rpc_client.send_and_confirm_transaction(&top_up_tx)?)
+    }
+}
+
+/// Index of the first payer starting from `start` whose balance reaches `min_balance`
+fn select_payer<F>(len: usize, start: usize, min_balance: u64, mut balance: F) -> ClientResult<Option<usize>>
+where
+    F: FnMut(usize) -> ClientResult<u64>,
+{
+    for offset in 0..len {
+        let index = (start + offset) % len;
+        if balance(index)? >= min_balance {
+            return Ok(Some(index))
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod fee_payer_test {
+    use crate::client::fee_payer::select_payer;
+
+    fn select(balances: &[u64], start: usize) -> Option<usize> {
+        select_payer(balances.len(), start, 100, |index| Ok(balances[index])).unwrap()
+    }
+
+    #[test]
+    fn when_start_payer_funded_expect_start() {
+        assert_eq!(select(&[100, 500, 500], 0), Some(0));
+    }
+
+    #[test]
+    fn when_start_payer_low_expect_next_funded() {
+        assert_eq!(select(&[500, 10, 10], 1), Some(0));
+    }
+
+    #[test]
+    fn when_all_payers_low_expect_none() {
+        assert_eq!(select(&[10, 20, 99], 2), None);
+    }
+}
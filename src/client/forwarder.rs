@@ -0,0 +1,192 @@
+//! Incoming deposit detection and forwarding. Balances of deposit addresses are polled
+//! and everything above the reserve of a rule is swept to its treasury destination.
+//!
+//! Deposit addresses sign their transfers, fees are paid by a separate fee payer.
+//! Every step is reported as a `ForwardEvent`, serializable as a structured log line.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use crate::client::error::ClientResult;
+use crate::instruction::TokenInstruction;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Asset {
+    Lamports,
+    /// SPL token account of `mint` owned by the deposit address
+    SplToken { token_account: Pubkey, mint: Pubkey },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForwardRule {
+    pub asset: Asset,
+    /// Treasury wallet for lamports, treasury token account for SPL tokens
+    pub destination: Pubkey,
+    /// Sweep only when at least this amount is available above the reserve
+    pub min_amount: u64,
+    /// Left on the deposit address, e.g. rent exempt minimum
+    pub reserve: u64,
+}
+
+impl ForwardRule {
+    fn sweep_amount(&self, balance: u64) -> Option<u64> {
+        balance.checked_sub(self.reserve).filter(|amount| *amount > 0 && *amount >= self.min_amount)
+    }
+
+    fn instruction(&self, owner: Pubkey, amount: u64) -> Instruction {
+        match self.asset {
+            Asset::Lamports => TokenInstruction::transfer_lamports_v2(owner, self.destination, amount),
+            Asset::SplToken { token_account, mint } => {
+                TokenInstruction::transfer_spl_token_v2(owner, token_account, self.destination, mint, amount)
+            }
+        }
+    }
+}
+
+pub struct DepositAddress {
+    pub keypair: Keypair,
+    pub rules: Vec<ForwardRule>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ForwardEvent {
+    Detected { address: String, source: String, balance: u64 },
+    Forwarded { address: String, destination: String, amount: u64, signature: String },
+    Failed { address: String, source: String, error: String },
+}
+
+pub struct DepositForwarder<'a> {
+    rpc_client: &'a RpcClient,
+    fee_payer: &'a Keypair,
+    addresses: Vec<DepositAddress>,
+}
+
+impl<'a> DepositForwarder<'a> {
+    pub fn new(rpc_client: &'a RpcClient, fee_payer: &'a Keypair, addresses: Vec<DepositAddress>) -> Self {
+        DepositForwarder { rpc_client, fee_payer, addresses }
+    }
+
+    /// Check every rule once, failure of a rule is reported and doesn't stop the others.
+    /// Returns number of forwarded transfers.
+    pub fn poll<E: FnMut(ForwardEvent)>(&self, on_event: &mut E) -> usize {
+        let mut forwarded = 0;
+        for address in &self.addresses {
+            let owner = address.keypair.pubkey();
+            for rule in &address.rules {
+                let source = match rule.asset {
+                    Asset::Lamports => owner,
+                    Asset::SplToken { token_account, .. } => token_account,
+                };
+                match self.forward(address, rule, source, on_event) {
+                    Ok(Some(_)) => forwarded += 1,
+                    Ok(None) => {}
+                    Err(e) => on_event(ForwardEvent::Failed {
+                        address: owner.to_string(),
+                        source: source.to_string(),
+                        error: e.to_string(),
+                    }),
+                }
+            }
+        }
+        forwarded
+    }
+
+    /// Poll every `interval` until `stop` is set
+    pub fn run<E: FnMut(ForwardEvent)>(&self, interval: Duration, stop: &AtomicBool, mut on_event: E) {
+        while !stop.load(Ordering::Relaxed) {
+            self.poll(&mut on_event);
+            thread::sleep(interval);
+        }
+    }
+
+    fn forward<E: FnMut(ForwardEvent)>(
+        &self,
+        address: &DepositAddress,
+        rule: &ForwardRule,
+        source: Pubkey,
+        on_event: &mut E,
+    ) -> ClientResult<Option<Signature>> {
+        let balance = self.balance(rule, source)?;
+        let amount = match rule.sweep_amount(balance) {
+            Some(amount) => amount,
+            None => return Ok(None),
+        };
+        let owner = address.keypair.pubkey();
+        on_event(ForwardEvent::Detected { address: owner.to_string(), source: source.to_string(), balance });
+
+        let blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[rule.instruction(owner, amount)],
+            Some(&self.fee_payer.pubkey()),
+            &[self.fee_payer, &address.keypair],
+            blockhash,
+        );
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        on_event(ForwardEvent::Forwarded {
+            address: owner.to_string(),
+            destination: rule.destination.to_string(),
+            amount,
+            signature: signature.to_string(),
+        });
+        Ok(Some(signature))
+    }
+
+    fn balance(&self, rule: &ForwardRule, source: Pubkey) -> ClientResult<u64> {
+        match rule.asset {
+            Asset::Lamports => Ok(self.rpc_client.get_balance(&source)?),
+            Asset::SplToken { .. } => {
+                let balance = self.rpc_client.get_token_account_balance(&source)?;
+                Ok(balance.amount.parse().unwrap_or_default())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod forwarder_test {
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::forwarder::{Asset, ForwardEvent, ForwardRule};
+    use crate::instruction::TokenInstruction;
+
+    fn rule(asset: Asset) -> ForwardRule {
+        ForwardRule { asset, destination: Pubkey::new_unique(), min_amount: 100, reserve: 50 }
+    }
+
+    #[test]
+    fn when_balance_above_reserve_expect_sweep_rest() {
+        let rule = rule(Asset::Lamports);
+
+        assert_eq!(rule.sweep_amount(1_000), Some(950));
+        assert_eq!(rule.sweep_amount(149), None);
+        assert_eq!(rule.sweep_amount(10), None);
+    }
+
+    #[test]
+    fn when_spl_token_rule_expect_transfer_from_token_account() {
+        let (owner, token_account, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rule = rule(Asset::SplToken { token_account, mint });
+
+        let instr = rule.instruction(owner, 500);
+
+        let accounts = &instr.accounts[TokenInstruction::TRANSFER_POLICY_LEN..];
+        assert_eq!(accounts[0].pubkey, owner);
+        assert_eq!(accounts[1].pubkey, token_account);
+        assert_eq!(accounts[2].pubkey, rule.destination);
+    }
+
+    #[test]
+    fn when_event_serialized_expect_tagged_log_line() {
+        let event = ForwardEvent::Detected { address: "a".into(), source: "b".into(), balance: 5 };
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"detected","address":"a","source":"b","balance":5}"#,
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Event sink for Geyser plugin streams, e.g. Yellowstone gRPC, for operators running their own validators.
+//! The gRPC subscription is left to the operator's client: its transaction and account updates are mapped
+//! into `TransactionUpdate` and `AccountUpdate`, the sink decodes instructions, events and account types
+//! of this program.
+//!
+//! Events are delivered in sequence order like `EventConsumer`. A stream has no history to backfill from,
+//! events after a gap are held back until `missing` sequences are fetched, e.g. with `EventConsumer`.
+use std::ops::Range;
+use borsh::BorshDeserialize;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use crate::client::events::{parse_logs, SequenceTracker};
+use crate::event::EventRecord;
+use crate::instruction::TokenInstruction;
+use crate::state::AccountType;
+
+/// Transaction update, account keys include addresses loaded from lookup tables
+#[derive(Debug, Clone)]
+pub struct TransactionUpdate {
+    pub slot: u64,
+    pub signature: Signature,
+    pub account_keys: Vec<Pubkey>,
+    pub instructions: Vec<CompiledInstruction>,
+    pub log_messages: Vec<String>,
+    pub failed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountUpdate {
+    pub slot: u64,
+    pub pubkey: Pubkey,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StreamItem {
+    /// Top level instruction of this program in a successful transaction, `index` is its position there
+    Instruction {
+        slot: u64,
+        signature: Signature,
+        index: usize,
+        instruction: TokenInstruction,
+        accounts: Vec<Pubkey>,
+    },
+    Event(EventRecord),
+    /// Program owned account written, `data` is its Borsh state of `account_type`
+    Account { slot: u64, pubkey: Pubkey, account_type: AccountType, data: Vec<u8> },
+}
+
+pub struct GeyserSink {
+    program_id: Pubkey,
+    tracker: SequenceTracker,
+}
+
+impl GeyserSink {
+    /// Sink ordering events of event stream `stream`, expecting `next_sequence` first, 0 for a fresh stream
+    pub fn new(program_id: Pubkey, stream: Pubkey, next_sequence: u64) -> Self {
+        GeyserSink { program_id, tracker: SequenceTracker::new(stream, next_sequence) }
+    }
+
+    pub fn next_sequence(&self) -> u64 {
+        self.tracker.next_sequence()
+    }
+
+    /// Sequences not received yet but preceding held back events
+    pub fn missing(&self) -> Option<Range<u64>> {
+        self.tracker.missing()
+    }
+
+    /// Instructions of the transaction followed by events it made deliverable, failed transactions are skipped
+    pub fn transaction(&mut self, update: &TransactionUpdate) -> Vec<StreamItem> {
+        if update.failed {
+            return vec![]
+        }
+        let mut items = program_instructions(&self.program_id, update);
+        for record in parse_logs(&self.program_id, &update.log_messages) {
+            items.extend(self.tracker.push(record).into_iter().map(StreamItem::Event));
+        }
+        items
+    }
+
+    /// Account update of a program owned account, skipped when closed or not initialized
+    pub fn account(&self, update: &AccountUpdate) -> Option<StreamItem> {
+        if update.owner != self.program_id {
+            return None
+        }
+        let account_type = update.data.first().and_then(|tag| AccountType::try_from_slice(&[*tag]).ok())?;
+        if account_type == AccountType::Uninitialized {
+            return None
+        }
+        Some(StreamItem::Account { slot: update.slot, pubkey: update.pubkey, account_type, data: update.data.clone() })
+    }
+}
+
+/// Top level instructions of `program_id` in `update`, undecodable ones are skipped
+pub(crate) fn program_instructions(program_id: &Pubkey, update: &TransactionUpdate) -> Vec<StreamItem> {
+    let instruction = |index: usize, instr: &CompiledInstruction| {
+        if update.account_keys.get(instr.program_id_index as usize) != Some(program_id) {
+            return None
+        }
+        let accounts = instr.accounts
+            .iter()
+            .map(|&index| update.account_keys.get(index as usize).copied())
+            .collect::<Option<Vec<Pubkey>>>()?;
+        let instruction = TokenInstruction::unpack(&instr.data).ok()?;
+        Some(StreamItem::Instruction { slot: update.slot, signature: update.signature, index, instruction, accounts })
+    };
+    update.instructions.iter().enumerate().filter_map(|(index, instr)| instruction(index, instr)).collect()
+}
+
+#[cfg(test)]
+mod geyser_test {
+    use borsh::BorshSerialize;
+    use solana_sdk::instruction::CompiledInstruction;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use crate::client::geyser::{AccountUpdate, GeyserSink, StreamItem, TransactionUpdate};
+    use crate::event::{EventRecord, KeeperRegisteredEvent, TokenEvent};
+    use crate::instruction::TokenInstruction;
+    use crate::state::AccountType;
+
+    const STREAM: Pubkey = Pubkey::new_from_array([3; 32]);
+
+    fn register_keeper_update(program_id: Pubkey, keeper: Pubkey, failed: bool) -> TransactionUpdate {
+        let record = EventRecord {
+            sequence: Some(0),
+            stream: Some(STREAM),
+            event: TokenEvent::KeeperRegistered(KeeperRegisteredEvent { keeper }),
+        };
+        TransactionUpdate {
+            slot: 10,
+            signature: Signature::default(),
+            account_keys: vec![keeper, program_id],
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![0],
+                data: TokenInstruction::RegisterKeeper.try_to_vec().unwrap(),
+            }],
+            log_messages: vec![
+                format!("Program {} invoke [1]", program_id),
+                format!("Program data: {}", base64::encode(record.try_to_vec().unwrap())),
+                format!("Program {} success", program_id),
+            ],
+            failed,
+        }
+    }
+
+    #[test]
+    fn when_transaction_of_program_expect_instruction_and_event() {
+        let (program_id, keeper) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut sink = GeyserSink::new(program_id, STREAM, 0);
+
+        let items = sink.transaction(&register_keeper_update(program_id, keeper, false));
+
+        assert_eq!(items, vec![
+            StreamItem::Instruction {
+                slot: 10,
+                signature: Signature::default(),
+                index: 0,
+                instruction: TokenInstruction::RegisterKeeper,
+                accounts: vec![keeper],
+            },
+            StreamItem::Event(EventRecord {
+                sequence: Some(0),
+                stream: Some(STREAM),
+                event: TokenEvent::KeeperRegistered(KeeperRegisteredEvent { keeper }),
+            }),
+        ]);
+        assert_eq!(sink.next_sequence(), 1);
+    }
+
+    #[test]
+    fn when_transaction_failed_expect_skipped() {
+        let (program_id, keeper) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut sink = GeyserSink::new(program_id, STREAM, 0);
+
+        assert!(sink.transaction(&register_keeper_update(program_id, keeper, true)).is_empty());
+        assert_eq!(sink.next_sequence(), 0);
+    }
+
+    #[test]
+    fn when_account_update_expect_only_initialized_program_accounts() {
+        let program_id = Pubkey::new_unique();
+        let sink = GeyserSink::new(program_id, STREAM, 0);
+        let update = |owner: Pubkey, data: Vec<u8>| {
+            AccountUpdate { slot: 10, pubkey: Pubkey::new_unique(), owner, data }
+        };
+
+        let keeper = update(program_id, vec![AccountType::Keeper as u8, 1]);
+        assert!(matches!(sink.account(&keeper), Some(StreamItem::Account { account_type: AccountType::Keeper, .. })));
+        assert_eq!(sink.account(&update(program_id, vec![])), None);
+        assert_eq!(sink.account(&update(program_id, vec![AccountType::Uninitialized as u8])), None);
+        assert_eq!(sink.account(&update(Pubkey::new_unique(), vec![AccountType::Keeper as u8])), None);
+    }
+}
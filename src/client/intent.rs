@@ -0,0 +1,216 @@
+//! Transfer intents: a transaction built and signed now, sent once its trigger fires.
+//!
+//! Intent transaction uses a durable nonce instead of a recent blockhash, so it stays valid
+//! until sent or the nonce is advanced by its authority, which cancels it. Intents are kept
+//! off-chain in a JSON file, the executor polls triggers and removes sent intents.
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use solana_client::nonce_utils;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::program_utils::limited_deserialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::signers::Signers;
+use solana_sdk::transaction::Transaction;
+use crate::client::error::{ClientError, ClientResult};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    /// Unix timestamp reached
+    At { timestamp: i64 },
+    /// Lamport balance of account reached, e.g. an expected incoming deposit
+    Balance { account: String, min_lamports: u64 },
+}
+
+/// Durable nonce account used by intent transactions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nonce {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl Nonce {
+    /// Blockhash currently stored in the nonce account
+    pub fn blockhash(&self, rpc_client: &RpcClient) -> ClientResult<Hash> {
+        let account = nonce_utils::get_account(rpc_client, &self.account)?;
+        Ok(nonce_utils::data_from_account(&account)?.blockhash())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferIntent {
+    /// Reference, unique within the store
+    pub id: String,
+    pub trigger: Trigger,
+    /// Serialized message, base64 encoded
+    pub message: String,
+    /// Signatures in message signer order, base58 encoded
+    pub signatures: Vec<String>,
+}
+
+impl TransferIntent {
+    /// Sign `instructions` prefixed with nonce advance, `nonce_blockhash` is the stored nonce, see `Nonce::blockhash`
+    pub fn new<S: Signers>(
+        id: String,
+        trigger: Trigger,
+        instructions: Vec<Instruction>,
+        payer: &Pubkey,
+        nonce: &Nonce,
+        nonce_blockhash: Hash,
+        signers: &S,
+    ) -> ClientResult<Self> {
+        let message = Message::new_with_nonce(instructions, Some(payer), &nonce.account, &nonce.authority);
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction.try_sign(signers, nonce_blockhash)?;
+        Ok(TransferIntent {
+            id,
+            trigger,
+            message: base64::encode(transaction.message.serialize()),
+            signatures: transaction.signatures.iter().map(Signature::to_string).collect(),
+        })
+    }
+
+    pub fn transaction(&self) -> ClientResult<Transaction> {
+        let data = base64::decode(&self.message).map_err(|_| ClientError::InvalidIntent)?;
+        let message = limited_deserialize(&data).map_err(|_| ClientError::InvalidIntent)?;
+        let signatures = self.signatures
+            .iter()
+            .map(|signature| signature.parse().map_err(|_| ClientError::InvalidIntent))
+            .collect::<ClientResult<_>>()?;
+        Ok(Transaction { signatures, message })
+    }
+}
+
+/// Intents persisted as a JSON array, rewritten as a whole on every change
+pub struct IntentStore {
+    path: PathBuf,
+    intents: Vec<TransferIntent>,
+}
+
+impl IntentStore {
+    /// Open store at `path`, missing file starts empty
+    pub fn open<P: AsRef<Path>>(path: P) -> ClientResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let intents = if path.exists() { serde_json::from_slice(&fs::read(&path)?)? } else { vec![] };
+        Ok(IntentStore { path, intents })
+    }
+
+    pub fn intents(&self) -> &[TransferIntent] {
+        &self.intents
+    }
+
+    /// Add intent, replacing one with the same id
+    pub fn add(&mut self, intent: TransferIntent) -> ClientResult<()> {
+        self.intents.retain(|stored| stored.id != intent.id);
+        self.intents.push(intent);
+        self.save()
+    }
+
+    pub fn remove(&mut self, id: &str) -> ClientResult<()> {
+        self.intents.retain(|stored| stored.id != id);
+        self.save()
+    }
+
+    /// Write to a temporary file renamed over the store, so a crash never leaves it truncated
+    fn save(&self) -> ClientResult<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&self.intents)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+pub struct IntentExecutor<'a> {
+    rpc_client: &'a RpcClient,
+}
+
+impl<'a> IntentExecutor<'a> {
+    pub fn new(rpc_client: &'a RpcClient) -> Self {
+        IntentExecutor { rpc_client }
+    }
+
+    pub fn is_triggered(&self, trigger: &Trigger, now: i64) -> ClientResult<bool> {
+        match trigger {
+            Trigger::At { timestamp } => Ok(now >= *timestamp),
+            Trigger::Balance { account, min_lamports } => {
+                let account = account.parse().map_err(|_| ClientError::InvalidIntent)?;
+                Ok(self.rpc_client.get_balance(&account)? >= *min_lamports)
+            }
+        }
+    }
+
+    /// Send intents with fired triggers and remove them from the store once confirmed
+    pub fn execute_triggered(&self, store: &mut IntentStore, now: i64) -> ClientResult<Vec<(String, Signature)>> {
+        let mut executed = vec![];
+        for intent in store.intents().to_vec() {
+            if !self.is_triggered(&intent.trigger, now)? {
+                continue
+            }
+            let signature = self.rpc_client.send_and_confirm_transaction(&intent.transaction()?)?;
+            store.remove(&intent.id)?;
+            executed.push((intent.id, signature));
+        }
+        Ok(executed)
+    }
+}
+
+#[cfg(test)]
+mod intent_test {
+    use solana_client::rpc_client::RpcClient;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_program;
+    use crate::client::intent::{IntentExecutor, IntentStore, Nonce, TransferIntent, Trigger};
+    use crate::instruction::TokenInstruction;
+
+    fn intent(id: &str, from: &Keypair) -> TransferIntent {
+        let nonce = Nonce { account: Pubkey::new_unique(), authority: from.pubkey() };
+        let instr = TokenInstruction::transfer_lamports(from.pubkey(), Pubkey::new_unique(), 100);
+        let trigger = Trigger::At { timestamp: 1_000 };
+        TransferIntent::new(id.into(), trigger, vec![instr], &from.pubkey(), &nonce, Hash::new_unique(), &[from])
+            .unwrap()
+    }
+
+    #[test]
+    fn when_intent_built_expect_signed_nonce_transaction() {
+        let from = Keypair::new();
+
+        let transaction = intent("payout", &from).transaction().unwrap();
+
+        assert!(transaction.verify().is_ok());
+        let advance = &transaction.message.instructions[0];
+        assert_eq!(transaction.message.account_keys[advance.program_id_index as usize], system_program::id());
+    }
+
+    #[test]
+    fn when_store_reopened_expect_intents_persisted() {
+        let path = std::env::temp_dir().join(format!("intents-{}.json", Pubkey::new_unique()));
+        let from = Keypair::new();
+        let mut store = IntentStore::open(&path).unwrap();
+        store.add(intent("first", &from)).unwrap();
+        store.add(intent("second", &from)).unwrap();
+        store.remove("first").unwrap();
+
+        let reopened = IntentStore::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.intents(), store.intents());
+        assert_eq!(reopened.intents()[0].id, "second");
+    }
+
+    #[test]
+    fn when_timestamp_reached_expect_triggered() {
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let executor = IntentExecutor::new(&rpc_client);
+        let trigger = Trigger::At { timestamp: 1_000 };
+
+        assert!(!executor.is_triggered(&trigger, 999).unwrap());
+        assert!(executor.is_triggered(&trigger, 1_000).unwrap());
+    }
+}
@@ -0,0 +1,202 @@
+//! Resumable payout jobs. Progress is appended to a JSON lines journal keyed by the
+//! idempotency reference of each payout, so an interrupted run resumes where it stopped.
+//!
+//! A transaction is journaled before it is sent. On resume, a payout with journaled but
+//! unconfirmed signatures is resolved on chain first and only resubmitted when none landed.
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Signature, Signer};
+use crate::client::batch::{BatchEvent, BatchExecutor, BatchReport};
+use crate::client::error::{ClientError, ClientResult};
+use crate::client::priority::Sla;
+
+pub struct PayoutItem {
+    /// Idempotency reference, unique within the job
+    pub reference: String,
+    pub instructions: Vec<Instruction>,
+    /// Submission order and compute unit price with priority fees of the executor
+    pub sla: Sla,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JournalEntry {
+    Signed { reference: String, signature: String, blockhash: String },
+    Confirmed { reference: String, signature: String },
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct PayoutProgress {
+    confirmed: Option<Signature>,
+    /// Sent signatures with the blockhash they were signed with
+    pending: Vec<(Signature, Hash)>,
+}
+
+pub struct PayoutJob {
+    path: PathBuf,
+    progress: BTreeMap<String, PayoutProgress>,
+}
+
+impl PayoutJob {
+    /// Open journal at `path`, missing file starts a new job
+    pub fn open<P: AsRef<Path>>(path: P) -> ClientResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = Vec::new();
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    entries.push(serde_json::from_str(&line)?);
+                }
+            }
+        }
+        Ok(PayoutJob { path, progress: replay(entries)? })
+    }
+
+    pub fn is_confirmed(&self, reference: &str) -> bool {
+        matches!(self.progress.get(reference), Some(PayoutProgress { confirmed: Some(_), .. }))
+    }
+
+    /// Submit payouts not yet confirmed, report indexes refer to the submitted subset
+    pub fn run<S: Signer + Sync>(
+        &mut self,
+        executor: &BatchExecutor<S>,
+        items: &[PayoutItem],
+    ) -> ClientResult<BatchReport> {
+        self.resolve_pending(executor)?;
+
+        let remaining: Vec<&PayoutItem> = items.iter().filter(|item| !self.is_confirmed(&item.reference)).collect();
+        let instructions: Vec<Vec<Instruction>> = remaining.iter().map(|item| item.instructions.clone()).collect();
+        let slas: Vec<Sla> = remaining.iter().map(|item| item.sla).collect();
+        let journal = Mutex::new(self.journal()?);
+        let journal_error = Mutex::new(None);
+        let report = executor.execute_by_sla(&instructions, &slas, |event| {
+            let entry = match event {
+                BatchEvent::Signed { index, signature, blockhash } => JournalEntry::Signed {
+                    reference: remaining[index].reference.clone(),
+                    signature: signature.to_string(),
+                    blockhash: blockhash.to_string(),
+                },
+                BatchEvent::Confirmed { index, signature } => JournalEntry::Confirmed {
+                    reference: remaining[index].reference.clone(),
+                    signature: signature.to_string(),
+                },
+            };
+            if let Err(e) = append(&mut journal.lock().unwrap(), &entry) {
+                journal_error.lock().unwrap().get_or_insert(e);
+            }
+        });
+        if let Some(e) = journal_error.into_inner().unwrap() {
+            return Err(e)
+        }
+
+        for (index, signature) in &report.succeeded {
+            self.progress.entry(remaining[*index].reference.clone()).or_default().confirmed = Some(*signature);
+        }
+        Ok(report)
+    }
+
+    /// Settle payouts interrupted after sending, waiting for their blockhash to expire when needed
+    fn resolve_pending<S: Signer + Sync>(&mut self, executor: &BatchExecutor<S>) -> ClientResult<()> {
+        let mut journal = self.journal()?;
+        for (reference, progress) in self.progress.iter_mut().filter(|(_, progress)| progress.confirmed.is_none()) {
+            for (signature, blockhash) in progress.pending.drain(..) {
+                if let Some(Ok(())) = executor.wait_final_status(&signature, &blockhash)? {
+                    append(
+                        &mut journal,
+                        &JournalEntry::Confirmed { reference: reference.clone(), signature: signature.to_string() },
+                    )?;
+                    progress.confirmed = Some(signature);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn journal(&self) -> ClientResult<File> {
+        Ok(OpenOptions::new().create(true).append(true).open(&self.path)?)
+    }
+}
+
+fn append(journal: &mut File, entry: &JournalEntry) -> ClientResult<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    journal.write_all(line.as_bytes())?;
+    journal.sync_data()?;
+    Ok(())
+}
+
+fn replay(entries: Vec<JournalEntry>) -> ClientResult<BTreeMap<String, PayoutProgress>> {
+    let mut progress: BTreeMap<String, PayoutProgress> = BTreeMap::new();
+    for entry in entries {
+        match entry {
+            JournalEntry::Signed { reference, signature, blockhash } => {
+                let signature = signature.parse().map_err(|_| ClientError::InvalidJournal)?;
+                let blockhash = blockhash.parse().map_err(|_| ClientError::InvalidJournal)?;
+                progress.entry(reference).or_default().pending.push((signature, blockhash));
+            }
+            JournalEntry::Confirmed { reference, signature } => {
+                let signature = signature.parse().map_err(|_| ClientError::InvalidJournal)?;
+                let payout = progress.entry(reference).or_default();
+                payout.confirmed = Some(signature);
+                payout.pending.clear();
+            }
+        }
+    }
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod job_test {
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::Signature;
+    use crate::client::job::{replay, JournalEntry};
+
+    fn signed(reference: &str, signature: &Signature) -> JournalEntry {
+        JournalEntry::Signed {
+            reference: reference.to_string(),
+            signature: signature.to_string(),
+            blockhash: Hash::default().to_string(),
+        }
+    }
+
+    #[test]
+    fn when_replay_confirmed_expect_no_pending() {
+        let signature = Signature::from([1; 64]);
+        let entries = vec![
+            signed("a", &signature),
+            JournalEntry::Confirmed { reference: "a".to_string(), signature: signature.to_string() },
+        ];
+
+        let progress = replay(entries).unwrap();
+
+        assert_eq!(progress["a"].confirmed, Some(signature));
+        assert!(progress["a"].pending.is_empty());
+    }
+
+    #[test]
+    fn when_replay_signed_only_expect_pending() {
+        let first = Signature::from([1; 64]);
+        let retry = Signature::from([2; 64]);
+
+        let progress = replay(vec![signed("a", &first), signed("a", &retry)]).unwrap();
+
+        assert_eq!(progress["a"].confirmed, None);
+        assert_eq!(progress["a"].pending, vec![(first, Hash::default()), (retry, Hash::default())]);
+    }
+
+    #[test]
+    fn when_journal_entry_round_trip_expect_same() {
+        let entry = signed("payout-1", &Signature::from([3; 64]));
+
+        let line = serde_json::to_string(&entry).unwrap();
+
+        assert_eq!(serde_json::from_str::<JournalEntry>(&line).unwrap(), entry);
+    }
+}
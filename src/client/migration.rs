@@ -0,0 +1,265 @@
+//! Tooling of two-phase migration of vaults and escrows to a successor deployment, see
+//! `TokenInstruction::BeginExport`. Each deployment runs its side with its own build: the predecessor exports
+//! its vaults and escrows, the successor imports what the predecessor exported. Accounts are listed over RPC
+//! and turned into one transaction each, so an interrupted run is resumed by listing again.
+//! The migration is complete once both sides folded the same accounts into their commitments.
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use crate::client::error::{ClientError, ClientResult};
+use crate::instruction::TokenInstruction;
+use crate::state::{AccountState, AccountType, Escrow, Migration, Vault};
+
+/// Accounts fetched per `getMultipleAccounts` request, the RPC limit
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Vault or escrow state at its address
+#[derive(Debug, PartialEq)]
+pub enum MigratedAccount {
+    Vault(Pubkey, Vault),
+    Escrow(Pubkey, Escrow),
+}
+
+impl MigratedAccount {
+    /// Export to `successor`, a vault export is preceded by creation of its destination paid by `admin`
+    /// unless `destination_exists`
+    pub fn export_instructions(&self, admin: Pubkey, successor: Pubkey, destination_exists: bool) -> Vec<Instruction> {
+        match self {
+            MigratedAccount::Vault(_, vault) => {
+                let mut instructions = Vec::with_capacity(2);
+                if !destination_exists {
+                    let (successor_vault, _) = Vault::find_address_in(&successor, &vault.owner, &vault.mint);
+                    instructions.push(create_associated_token_account(
+                        &admin,
+                        &successor_vault,
+                        &vault.mint,
+                        &spl_token::id(),
+                    ));
+                }
+                instructions.push(TokenInstruction::export_vault(admin, vault.owner, vault.mint, successor));
+                instructions
+            }
+            MigratedAccount::Escrow(_, escrow) => vec![TokenInstruction::export_escrow(admin, escrow.temp_token)],
+        }
+    }
+
+    /// Token account of successor receiving the tokens of a vault, created before the export
+    pub fn export_destination(&self, successor: &Pubkey) -> Option<Pubkey> {
+        match self {
+            MigratedAccount::Vault(_, vault) => {
+                let (successor_vault, _) = Vault::find_address_in(successor, &vault.owner, &vault.mint);
+                Some(get_associated_token_address(&successor_vault, &vault.mint))
+            }
+            MigratedAccount::Escrow(..) => None,
+        }
+    }
+
+    /// Import of the account exported by `predecessor`
+    pub fn import_instruction(&self, admin: Pubkey, predecessor: Pubkey) -> Instruction {
+        match self {
+            MigratedAccount::Vault(_, vault) => {
+                TokenInstruction::import_vault(admin, predecessor, vault.owner, vault.mint)
+            }
+            MigratedAccount::Escrow(_, escrow) => {
+                TokenInstruction::import_escrow(admin, escrow.temp_token, predecessor)
+            }
+        }
+    }
+
+    pub fn key(&self) -> Pubkey {
+        match self {
+            MigratedAccount::Vault(key, _) | MigratedAccount::Escrow(key, _) => *key,
+        }
+    }
+
+    /// Address the account is imported at by this deployment
+    pub fn import_address(&self) -> Pubkey {
+        match self {
+            MigratedAccount::Vault(_, vault) => Vault::find_address(&vault.owner, &vault.mint).0,
+            MigratedAccount::Escrow(_, escrow) => Escrow::find_address(&escrow.temp_token).0,
+        }
+    }
+}
+
+/// Accounts moved by each side, read from export side of predecessor and import side of successor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MigrationProgress {
+    pub exported: u64,
+    pub imported: u64,
+    /// Both sides moved the same accounts
+    pub complete: bool,
+}
+
+impl MigrationProgress {
+    pub fn new(export: &Migration, import: &Migration) -> Self {
+        MigrationProgress { exported: export.count, imported: import.count, complete: export.matches(import) }
+    }
+}
+
+pub struct Migrator<'a> {
+    rpc_client: &'a RpcClient,
+    admin: Pubkey,
+}
+
+impl<'a> Migrator<'a> {
+    /// `admin` is the migration admin of the side run, it pays created accounts
+    pub fn new(rpc_client: &'a RpcClient, admin: Pubkey) -> Self {
+        Migrator { rpc_client, admin }
+    }
+
+    /// Export of every vault and escrow of this deployment not exported yet, one transaction each.
+    /// Vaults of namespaces aren't exported, the successor imports at addresses of its default namespace
+    pub fn exports(&self, successor: Pubkey) -> ClientResult<Vec<Vec<Instruction>>> {
+        let accounts: Vec<MigratedAccount> = self.list_migrated(&crate::id(), AccountType::Vault, AccountType::Escrow)?
+            .into_iter()
+            .filter(|account| account.key() == account.import_address())
+            .collect();
+        let destinations: Vec<Pubkey> =
+            accounts.iter().filter_map(|account| account.export_destination(&successor)).collect();
+        let mut destinations_exist = self.exist(&destinations)?.into_iter();
+        Ok(accounts.iter().map(|account| {
+            let destination_exists = match account.export_destination(&successor) {
+                Some(_) => destinations_exist.next().unwrap_or_default(),
+                None => true,
+            };
+            account.export_instructions(self.admin, successor, destination_exists)
+        }).collect())
+    }
+
+    /// Import of every account `predecessor` exported and this deployment didn't import yet, one transaction each
+    pub fn imports(&self, predecessor: Pubkey) -> ClientResult<Vec<Instruction>> {
+        let accounts = self.list_migrated(&predecessor, AccountType::Exported, AccountType::Exported)?;
+        let addresses: Vec<Pubkey> = accounts.iter().map(MigratedAccount::import_address).collect();
+        let imported = self.exist(&addresses)?;
+        Ok(accounts.iter()
+            .zip(imported)
+            .filter(|(_, imported)| !imported)
+            .map(|(account, _)| account.import_instruction(self.admin, predecessor))
+            .collect())
+    }
+
+    /// Compare export side of `predecessor` with import side of this deployment
+    pub fn progress(&self, predecessor: Pubkey) -> ClientResult<MigrationProgress> {
+        let export = self.load_migration(Migration::find_export_address_in(&predecessor).0)?;
+        let import = self.load_migration(Migration::find_import_address().0)?;
+        Ok(MigrationProgress::new(&export, &import))
+    }
+
+    /// Vaults with `vault_type` and escrows with `escrow_type` discriminator owned by `program_id`
+    fn list_migrated(
+        &self,
+        program_id: &Pubkey,
+        vault_type: AccountType,
+        escrow_type: AccountType,
+    ) -> ClientResult<Vec<MigratedAccount>> {
+        let vaults = self.list::<Vault>(program_id, vault_type)?;
+        let escrows = self.list::<Escrow>(program_id, escrow_type)?;
+        Ok(vaults.into_iter()
+            .map(|(key, vault)| MigratedAccount::Vault(key, vault))
+            .chain(escrows.into_iter().map(|(key, escrow)| MigratedAccount::Escrow(key, escrow)))
+            .collect())
+    }
+
+    /// Accounts of `program_id` with `account_type` discriminator and the layout of `T`. Vaults and escrows
+    /// are small enough for the default encoding of account data
+    fn list<T: AccountState>(&self, program_id: &Pubkey, account_type: AccountType) -> ClientResult<Vec<(Pubkey, T)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(T::LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &[account_type as u8])),
+            ]),
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.rpc_client.get_program_accounts_with_config(program_id, config)?
+            .into_iter()
+            .map(|(key, account)| Ok((key, T::deserialize(&mut &account.data[..])?)))
+            .collect()
+    }
+
+    fn exist(&self, keys: &[Pubkey]) -> ClientResult<Vec<bool>> {
+        let mut exist = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            exist.extend(self.rpc_client.get_multiple_accounts(chunk)?.iter().map(Option::is_some));
+        }
+        Ok(exist)
+    }
+
+    fn load_migration(&self, key: Pubkey) -> ClientResult<Migration> {
+        let account = self.rpc_client.get_account_with_commitment(&key, self.rpc_client.commitment())?.value;
+        let account = account.ok_or(ClientError::MigrationNotStarted(key))?;
+        Ok(Migration::deserialize(&mut &account.data[..])?)
+    }
+}
+
+#[cfg(test)]
+mod migration_test {
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::migration::{MigratedAccount, MigrationProgress};
+    use crate::instruction::TokenInstruction;
+    use crate::state::{AccountType, Escrow, Migration, Vault};
+
+    fn vault() -> MigratedAccount {
+        let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let vault = Vault { account_type: AccountType::Vault, owner, mint, bump: 255 };
+        MigratedAccount::Vault(Pubkey::new_unique(), vault)
+    }
+
+    #[test]
+    fn when_vault_destination_missing_expect_created_before_export() {
+        let (admin, successor, vault) = (Pubkey::new_unique(), Pubkey::new_unique(), vault());
+
+        let instructions = vault.export_instructions(admin, successor, false);
+
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].program_id, spl_associated_token_account::id());
+        assert_eq!(instructions[1].program_id, crate::id());
+        assert_eq!(instructions[0].accounts[1].pubkey, instructions[1].accounts[4].pubkey);
+        assert_eq!(vault.export_instructions(admin, successor, true).len(), 1);
+    }
+
+    #[test]
+    fn when_import_expect_instruction_for_account_kind() {
+        let (admin, predecessor, temp_token) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let escrow = MigratedAccount::Escrow(Pubkey::new_unique(), Escrow {
+            account_type: AccountType::Exported,
+            initializer: Pubkey::new_unique(),
+            temp_token,
+            receive_token: Pubkey::new_unique(),
+            expected_amount: 3_000,
+            expires_at: 1_700_000_000,
+            bump: 255,
+        });
+
+        let instr = escrow.import_instruction(admin, predecessor);
+
+        assert_eq!(instr, TokenInstruction::import_escrow(admin, temp_token, predecessor));
+        assert_eq!(instr.accounts[4].pubkey, escrow.import_address());
+    }
+
+    #[test]
+    fn when_sides_folded_same_accounts_expect_complete() {
+        let record = [7; 32];
+        let migration = |peer: Pubkey| Migration {
+            account_type: AccountType::Migration,
+            admin: Pubkey::new_unique(),
+            peer,
+            commitment: [0; 32],
+            count: 0,
+            bump: 255,
+        };
+        let (mut export, mut import) = (migration(Pubkey::new_unique()), migration(Pubkey::new_unique()));
+        export.fold(record);
+
+        assert_eq!(
+            MigrationProgress::new(&export, &import),
+            MigrationProgress { exported: 1, imported: 0, complete: false },
+        );
+        import.fold(record);
+        assert!(MigrationProgress::new(&export, &import).complete);
+    }
+}
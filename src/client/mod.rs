@@ -0,0 +1,31 @@
+//! Off-chain helpers for building and submitting program transactions, enabled by `client` feature
+pub mod address_book;
+pub mod alerts;
+#[cfg(feature = "archiver")]
+pub mod archiver;
+#[cfg(feature = "archiver")]
+pub mod backfill;
+pub mod batch;
+#[cfg(feature = "jito")]
+pub mod bundle;
+pub mod config;
+pub mod deposit;
+pub mod error;
+pub mod events;
+pub mod fee_payer;
+pub mod forwarder;
+pub mod geyser;
+pub mod intent;
+pub mod job;
+pub mod migration;
+pub mod policy;
+pub mod preflight;
+pub mod priority;
+#[cfg(feature = "archiver")]
+pub mod query;
+pub mod rpc;
+pub mod signer;
+pub mod threshold;
+pub mod wallet;
+pub mod wallet_transfer;
+pub mod webhook;
@@ -0,0 +1,339 @@
+//! Client side authorization policy evaluated before signing, mirroring on-chain limits
+//! as a software control. Transfers of this program are checked against the rules, a violation
+//! blocks signing unless the approval callback, e.g. a manual review, accepts it.
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+use solana_sdk::hash::Hash;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::signers::Signers;
+use solana_sdk::transaction::Transaction;
+use crate::client::error::{ClientError, ClientResult};
+use crate::instruction::TokenInstruction;
+use crate::split;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone)]
+pub enum Rule {
+    MaxAmount(u64),
+    DestinationAllowlist(HashSet<Pubkey>),
+    /// Lamport transfers count as `spl_token::native_mint`, unknown mint never matches
+    MintAllowlist(HashSet<Pubkey>),
+    /// Seconds since UTC midnight, wraps over midnight when start is after end
+    TimeWindow { start: u32, end: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    AmountExceeded { amount: u64, max: u64 },
+    DestinationNotAllowed(Pubkey),
+    MintNotAllowed(Option<Pubkey>),
+    OutsideTimeWindow,
+    /// Instruction of other program, not understood by the policy
+    ForeignProgram(Pubkey),
+}
+
+/// Value moved by a single instruction
+#[derive(Debug, PartialEq)]
+pub(crate) struct Transfer {
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub mint: Option<Pubkey>,
+}
+
+type Approval = Box<dyn Fn(&[Violation]) -> bool + Send + Sync>;
+
+pub struct PolicyEngine {
+    rules: Vec<Rule>,
+    allow_foreign_programs: bool,
+    approval: Option<Approval>,
+}
+
+impl PolicyEngine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        PolicyEngine { rules, allow_foreign_programs: false, approval: None }
+    }
+
+    pub fn allow_foreign_programs(mut self) -> Self {
+        self.allow_foreign_programs = true;
+        self
+    }
+
+    /// Called with violations of a transaction, returning true signs it anyway
+    pub fn with_approval<F>(mut self, approval: F) -> Self
+    where
+        F: Fn(&[Violation]) -> bool + Send + Sync + 'static,
+    {
+        self.approval = Some(Box::new(approval));
+        self
+    }
+
+    pub fn violations(&self, message: &Message, now: i64) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for instr in &message.instructions {
+            let program_id = message.account_keys[instr.program_id_index as usize];
+            if program_id != crate::id() {
+                if !self.allow_foreign_programs {
+                    violations.push(Violation::ForeignProgram(program_id));
+                }
+                continue
+            }
+            let accounts: Vec<Pubkey> = instr.accounts
+                .iter()
+                .map(|&index| message.account_keys[index as usize])
+                .collect();
+            let sweep = parse_sweep(&accounts, &instr.data);
+            for transfer in parse_transfers(&accounts, &instr.data).into_iter().chain(sweep) {
+                violations.extend(self.rules.iter().filter_map(|rule| check_rule(rule, &transfer, now)));
+            }
+        }
+        violations
+    }
+
+    pub fn check(&self, message: &Message, now: i64) -> ClientResult<()> {
+        let violations = self.violations(message, now);
+        if violations.is_empty() || self.approval.as_ref().is_some_and(|approval| approval(&violations)) {
+            Ok(())
+        } else {
+            Err(ClientError::PolicyViolation(violations))
+        }
+    }
+
+    pub fn sign<S: Signers>(&self, transaction: &mut Transaction, signers: &S, blockhash: Hash) -> ClientResult<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() as i64);
+        self.check(&transaction.message, now)?;
+        transaction.try_sign(signers, blockhash)?;
+        Ok(())
+    }
+}
+
+fn check_rule(rule: &Rule, transfer: &Transfer, now: i64) -> Option<Violation> {
+    match rule {
+        Rule::MaxAmount(max) if transfer.amount > *max => {
+            Some(Violation::AmountExceeded { amount: transfer.amount, max: *max })
+        }
+        Rule::DestinationAllowlist(allowed) if !allowed.contains(&transfer.destination) => {
+            Some(Violation::DestinationNotAllowed(transfer.destination))
+        }
+        Rule::MintAllowlist(allowed) if !transfer.mint.is_some_and(|mint| allowed.contains(&mint)) => {
+            Some(Violation::MintNotAllowed(transfer.mint))
+        }
+        Rule::TimeWindow { start, end } if !in_window(now, *start, *end) => Some(Violation::OutsideTimeWindow),
+        _ => None,
+    }
+}
+
+fn in_window(now: i64, start: u32, end: u32) -> bool {
+    let time_of_day = now.rem_euclid(SECONDS_PER_DAY) as u32;
+    if start <= end {
+        start <= time_of_day && time_of_day < end
+    } else {
+        start <= time_of_day || time_of_day < end
+    }
+}
+
+/// Transfers of value moving instructions, a batch moves value to each of its recipients
+pub(crate) fn parse_transfers(accounts: &[Pubkey], data: &[u8]) -> Vec<Transfer> {
+    let native = Some(spl_token::native_mint::id());
+    let instr = match TokenInstruction::unpack(data) {
+        Ok(instr) => instr,
+        Err(_) => return vec![],
+    };
+    let accounts = instr.skip_transfer_policy(accounts);
+    let (amounts, recipients, mint) = match instr {
+        TokenInstruction::TransferLamportsBatch { amounts } => (amounts, accounts.get(2..), native),
+        TokenInstruction::TransferSplTokenBatch { amounts } => (amounts, accounts.get(4..), None),
+        TokenInstruction::SplitLamports { amount, weights } => return parse_split(accounts, amount, &weights),
+        instr => return parse_transfer(accounts, instr).into_iter().collect(),
+    };
+    amounts.into_iter()
+        .zip(recipients.unwrap_or_default())
+        .map(|(amount, destination)| Transfer { amount, destination: *destination, mint })
+        .collect()
+}
+
+/// Destination of a sweep, its amount is the whole source balance known only on-chain and counts as `u64::MAX`.
+/// Left out of `parse_transfers`, so archives take the swept amount from the transfer event
+fn parse_sweep(accounts: &[Pubkey], data: &[u8]) -> Option<Transfer> {
+    let instr = TokenInstruction::unpack(data).ok()?;
+    let accounts = instr.skip_transfer_policy(accounts);
+    let (destination, mint) = match instr {
+        TokenInstruction::SweepLamports => (accounts.get(1)?, Some(spl_token::native_mint::id())),
+        TokenInstruction::SweepSplToken => (accounts.get(2)?, None),
+        _ => return None,
+    };
+    Some(Transfer { amount: u64::MAX, destination: *destination, mint })
+}
+
+/// Share of each recipient and the remainder of `SplitLamports`, nothing while the split is invalid
+fn parse_split(accounts: &[Pubkey], amount: u64, weights: &[u32]) -> Vec<Transfer> {
+    let recipients = accounts.get(3..).unwrap_or_default();
+    let (shares, remainder) = match split::shares(amount, weights, recipients.len()) {
+        Ok(split) => split,
+        Err(_) => return vec![],
+    };
+    let mint = Some(spl_token::native_mint::id());
+    recipients.iter()
+        .zip(shares)
+        .chain(accounts.get(1).map(|remainder_acc| (remainder_acc, remainder)))
+        .map(|(destination, amount)| Transfer { amount, destination: *destination, mint })
+        .collect()
+}
+
+/// Amount, destination and mint of single transfer instructions, see `TokenInstruction` accounts
+fn parse_transfer(accounts: &[Pubkey], instr: TokenInstruction) -> Option<Transfer> {
+    let native = Some(spl_token::native_mint::id());
+    let (amount, destination, mint) = match instr {
+        TokenInstruction::TransferLamports { amount } => (amount, accounts.get(1)?, native),
+        TokenInstruction::TransferLamportsV2 { amount } => (amount, accounts.get(1)?, native),
+        TokenInstruction::TransferLamportsWithMemo { amount, .. } => (amount, accounts.get(1)?, native),
+        TokenInstruction::TransferSplToken { amount } => (amount, accounts.get(2)?, None),
+        TokenInstruction::TransferSplTokenV2 { amount } => (amount, accounts.get(2)?, None),
+        TokenInstruction::TransferSplTokenWithMemo { amount, .. } => (amount, accounts.get(2)?, None),
+        TokenInstruction::TransferSplTokenWithTravelRule { amount, .. } => (amount, accounts.get(2)?, None),
+        TokenInstruction::TransferSplTokenAsDelegate { amount } => (amount, accounts.get(2)?, None),
+        TokenInstruction::TransferSplTokenChecked { amount, .. } => {
+            (amount, accounts.get(2)?, accounts.get(3).copied())
+        }
+        TokenInstruction::ApproveSplToken { amount } => (amount, accounts.get(2)?, None),
+        TokenInstruction::PostBond { amount, .. } => (amount, accounts.get(2)?, native),
+        TokenInstruction::SlashBond { amount, .. } => (amount, accounts.get(1)?, native),
+        TokenInstruction::Tip { amount } => (amount, accounts.get(1)?, native),
+        TokenInstruction::CreateMatchingPool { amount, .. } => (amount, accounts.get(1)?, native),
+        TokenInstruction::Donate { amount } => (amount, accounts.get(1)?, native),
+        TokenInstruction::TransferLamportsRoundUp { amount } => (amount, accounts.get(1)?, native),
+        TokenInstruction::DepositToProgram { amount } => {
+            let mint = if *accounts.get(3)? == solana_sdk::system_program::id() { native } else { None };
+            (amount, accounts.get(2)?, mint)
+        }
+        TokenInstruction::TransferSplTokenToWallet { amount } => (amount, accounts.get(2)?, accounts.get(4).copied()),
+        TokenInstruction::TransferSplTokenWithAtaCreation { amount } => {
+            (amount, accounts.get(2)?, accounts.get(4).copied())
+        }
+        TokenInstruction::ExecutePayout { amount, .. } => (amount, accounts.get(1)?, native),
+        TokenInstruction::TransferSplTokenAttested { amount, .. } => {
+            (amount, accounts.get(2)?, accounts.get(4).copied())
+        }
+        TokenInstruction::TransferSplTokenPriceProtected { amount, .. } => {
+            (amount, accounts.get(2)?, accounts.get(3).copied())
+        }
+        TokenInstruction::DepositToVault { amount } => (amount, accounts.get(3)?, None),
+        TokenInstruction::WithdrawFromVault { amount } => (amount, accounts.get(3)?, None),
+        TokenInstruction::ExchangeEscrow { amount } => (amount, accounts.get(2)?, None),
+        TokenInstruction::MintSplToken { amount } => (amount, accounts.get(2)?, accounts.get(1).copied()),
+        _ => return None,
+    };
+    Some(Transfer { amount, destination: *destination, mint })
+}
+
+#[cfg(test)]
+mod policy_test {
+    use std::collections::HashSet;
+    use solana_sdk::message::Message;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::system_instruction;
+    use crate::client::error::ClientError;
+    use crate::client::policy::{in_window, PolicyEngine, Rule, Violation};
+    use crate::instruction::TokenInstruction;
+
+    fn transfer_message(to: Pubkey, amount: u64) -> Message {
+        let from = Pubkey::new_unique();
+        Message::new(&[TokenInstruction::transfer_lamports(from, to, amount)], Some(&from))
+    }
+
+    #[test]
+    fn when_within_rules_expect_no_violations() {
+        let to = Pubkey::new_unique();
+        let policy = PolicyEngine::new(vec![
+            Rule::MaxAmount(1_000),
+            Rule::DestinationAllowlist(HashSet::from([to])),
+            Rule::MintAllowlist(HashSet::from([spl_token::native_mint::id()])),
+        ]);
+
+        assert!(policy.violations(&transfer_message(to, 1_000), 0).is_empty());
+    }
+
+    #[test]
+    fn when_amount_and_destination_violated_expect_both() {
+        let to = Pubkey::new_unique();
+        let policy = PolicyEngine::new(vec![Rule::MaxAmount(1_000), Rule::DestinationAllowlist(HashSet::new())]);
+
+        assert_eq!(
+            policy.violations(&transfer_message(to, 1_001), 0),
+            vec![Violation::AmountExceeded { amount: 1_001, max: 1_000 }, Violation::DestinationNotAllowed(to)],
+        );
+    }
+
+    #[test]
+    fn when_sweep_expect_amount_unbounded() {
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let policy = PolicyEngine::new(vec![Rule::MaxAmount(1_000), Rule::DestinationAllowlist(HashSet::from([to]))]);
+        let message = Message::new(&[TokenInstruction::sweep_lamports(from, to)], Some(&from));
+
+        assert_eq!(policy.violations(&message, 0), vec![Violation::AmountExceeded { amount: u64::MAX, max: 1_000 }]);
+    }
+
+    #[test]
+    fn when_batch_expect_each_recipient_checked() {
+        let (from, allowed, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let batch = TokenInstruction::transfer_lamports_batch(from, &[(allowed, 10), (other, 2_000)]);
+        let policy = PolicyEngine::new(vec![
+            Rule::MaxAmount(1_000),
+            Rule::DestinationAllowlist(HashSet::from([allowed])),
+        ]);
+
+        assert_eq!(
+            policy.violations(&Message::new(&[batch], Some(&from)), 0),
+            vec![Violation::AmountExceeded { amount: 2_000, max: 1_000 }, Violation::DestinationNotAllowed(other)],
+        );
+    }
+
+    #[test]
+    fn when_split_expect_each_share_and_remainder_checked() {
+        let (from, remainder, allowed, other) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let split = TokenInstruction::split_lamports(from, remainder, &[allowed, other], 2_001, &[]);
+        let policy = PolicyEngine::new(vec![Rule::DestinationAllowlist(HashSet::from([allowed]))]);
+
+        assert_eq!(
+            policy.violations(&Message::new(&[split], Some(&from)), 0),
+            vec![Violation::DestinationNotAllowed(other), Violation::DestinationNotAllowed(remainder)],
+        );
+    }
+
+    #[test]
+    fn when_foreign_program_expect_violation() {
+        let from = Pubkey::new_unique();
+        let message = Message::new(&[system_instruction::transfer(&from, &Pubkey::new_unique(), 1)], Some(&from));
+
+        let violations = PolicyEngine::new(vec![]).violations(&message, 0);
+
+        assert_eq!(violations, vec![Violation::ForeignProgram(solana_sdk::system_program::id())]);
+    }
+
+    #[test]
+    fn when_violation_approved_expect_ok() {
+        let policy = PolicyEngine::new(vec![Rule::MaxAmount(1)]).with_approval(|_| true);
+
+        assert!(policy.check(&transfer_message(Pubkey::new_unique(), 2), 0).is_ok());
+    }
+
+    #[test]
+    fn when_violation_rejected_expect_error() {
+        let policy = PolicyEngine::new(vec![Rule::MaxAmount(1)]).with_approval(|_| false);
+
+        let result = policy.check(&transfer_message(Pubkey::new_unique(), 2), 0);
+
+        assert!(matches!(result, Err(ClientError::PolicyViolation(_))));
+    }
+
+    #[test]
+    fn when_time_window_wraps_midnight_expect_both_sides_inside() {
+        let (start, end) = (22 * 3600, 2 * 3600);
+
+        assert!(in_window(23 * 3600, start, end));
+        assert!(in_window(3600, start, end));
+        assert!(!in_window(12 * 3600, start, end));
+    }
+}
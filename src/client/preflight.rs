@@ -0,0 +1,347 @@
+//! Client side preflight of transfer accounts before submitting a transaction.
+//! Involved accounts are fetched once and checked for existence, ownership, mint match,
+//! balance and frozen state, so problems are reported by account role instead of a simulation error.
+use std::collections::HashMap;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+use crate::client::config::ProgramConfig;
+use crate::client::error::{ClientError, ClientResult};
+use crate::instruction::TokenInstruction;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Problem {
+    #[error("{role} {key} missing")]
+    Missing { role: &'static str, key: Pubkey },
+    #[error("{role} {key} is owned by {owner}")]
+    WrongOwner { role: &'static str, key: Pubkey, owner: Pubkey },
+    #[error("{role} {key} is not initialized")]
+    Uninitialized { role: &'static str, key: Pubkey },
+    #[error("{role} {key} holds mint {actual}, expected {expected}")]
+    MintMismatch { role: &'static str, key: Pubkey, expected: Pubkey, actual: Pubkey },
+    #[error("{role} {key} has {available}, required {required}")]
+    InsufficientBalance { role: &'static str, key: Pubkey, required: u64, available: u64 },
+    #[error("{role} {key} is frozen")]
+    Frozen { role: &'static str, key: Pubkey },
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PreflightReport {
+    pub problems: Vec<Problem>,
+}
+
+impl PreflightReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn into_result(self) -> ClientResult<()> {
+        if self.is_ok() {
+            Ok(())
+        } else {
+            Err(ClientError::PreflightFailed(self.problems))
+        }
+    }
+}
+
+pub struct Preflight {
+    config: ProgramConfig,
+}
+
+impl Preflight {
+    pub fn new(config: ProgramConfig) -> Self {
+        Preflight { config }
+    }
+
+    /// Fetch accounts of `instructions` in one request and check them
+    pub fn run(&self, rpc_client: &RpcClient, instructions: &[Instruction]) -> ClientResult<PreflightReport> {
+        let mut keys: Vec<Pubkey> = instructions
+            .iter()
+            .filter(|instr| instr.program_id == self.config.program_id)
+            .flat_map(|instr| instr.accounts.iter().map(|meta| meta.pubkey))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        let accounts = rpc_client.get_multiple_accounts(&keys)?;
+        let fetched = keys
+            .into_iter()
+            .zip(accounts)
+            .filter_map(|(key, account)| account.map(|account| (key, account)))
+            .collect();
+        Ok(self.check(instructions, &fetched))
+    }
+
+    /// Check instructions of this program against fetched accounts, missing from `accounts` don't exist.
+    /// Amounts of several instructions moving value from the same account are summed up.
+    pub fn check(&self, instructions: &[Instruction], accounts: &HashMap<Pubkey, Account>) -> PreflightReport {
+        let mut checker = Checker { token_program_id: self.config.token_program_id, accounts, spent: HashMap::new() };
+        let mut report = PreflightReport::default();
+        for instr in instructions.iter().filter(|instr| instr.program_id == self.config.program_id) {
+            let keys: Vec<Pubkey> = instr.accounts.iter().map(|meta| meta.pubkey).collect();
+            report.problems.extend(checker.check_instruction(&keys, &instr.data));
+        }
+        report
+    }
+}
+
+struct Checker<'a> {
+    token_program_id: Pubkey,
+    accounts: &'a HashMap<Pubkey, Account>,
+    spent: HashMap<Pubkey, u64>,
+}
+
+impl Checker<'_> {
+    /// Accounts are checked by position past transfer policy accounts, see `TokenInstruction` accounts
+    fn check_instruction(&mut self, keys: &[Pubkey], data: &[u8]) -> Vec<Problem> {
+        let instr = match TokenInstruction::unpack(data) {
+            Ok(instr) => instr,
+            Err(_) => return vec![],
+        };
+        let keys = instr.skip_transfer_policy(keys);
+        let key = |index: usize| keys.get(index).copied().unwrap_or_default();
+        let result = match instr {
+            TokenInstruction::TransferLamports { amount }
+            | TokenInstruction::TransferLamportsV2 { amount }
+            | TokenInstruction::TransferLamportsWithMemo { amount, .. }
+            | TokenInstruction::TransferLamportsRoundUp { amount }
+            | TokenInstruction::WrapSol { amount } => {
+                self.check_lamports("source", key(0), amount)
+            }
+            TokenInstruction::TransferSplToken { amount }
+            | TokenInstruction::TransferSplTokenV2 { amount }
+            | TokenInstruction::TransferSplTokenWithTravelRule { amount, .. }
+            | TokenInstruction::TransferSplTokenWithMemo { amount, .. }
+            | TokenInstruction::TransferSplTokenAsDelegate { amount } => {
+                self.check_tokens(key(1), key(2), None, amount)
+            }
+            TokenInstruction::SweepLamports => self.check_lamports("source", key(0), 0),
+            TokenInstruction::SweepSplToken => self.check_tokens(key(1), key(2), None, 0),
+            TokenInstruction::DepositToVault { amount } => self.check_tokens(key(1), key(3), None, amount),
+            TokenInstruction::WithdrawFromVault { amount } => self.check_tokens(key(2), key(3), None, amount),
+            TokenInstruction::ApproveSplToken { .. } => {
+                let (_, problems) = self.check_token_account("source token account", key(1), None, 0);
+                Err(problems)
+            }
+            TokenInstruction::TransferSplTokenToWallet { amount }
+            | TokenInstruction::TransferSplTokenAttested { amount, .. } => {
+                self.check_mint(key(4)).and_then(|_| self.check_tokens(key(1), key(3), Some(key(4)), amount))
+            }
+            TokenInstruction::TransferSplTokenPriceProtected { amount, .. }
+            | TokenInstruction::TransferSplTokenChecked { amount, .. } => {
+                self.check_mint(key(3)).and_then(|_| self.check_tokens(key(1), key(2), Some(key(3)), amount))
+            }
+            TokenInstruction::MintSplToken { .. } => self.check_mint(key(1)).and_then(|_| {
+                let (_, problems) = self.check_token_account("destination token account", key(2), Some(key(1)), 0);
+                if problems.is_empty() { Ok(()) } else { Err(problems) }
+            }),
+            TokenInstruction::TransferSplTokenWithAtaCreation { amount } => self.check_mint(key(4)).and_then(|_| {
+                let (_, problems) = self.check_token_account("source token account", key(1), Some(key(4)), amount);
+                if problems.is_empty() { Ok(()) } else { Err(problems) }
+            }),
+            TokenInstruction::BurnSplToken { amount } => self.check_mint(key(2)).and_then(|_| {
+                let (_, problems) = self.check_token_account("source token account", key(1), Some(key(2)), amount);
+                if problems.is_empty() { Ok(()) } else { Err(problems) }
+            }),
+            TokenInstruction::TransferLamportsBatch { amounts } => {
+                self.check_lamports("source", key(0), total(&amounts))
+            }
+            TokenInstruction::SplitLamports { amount, .. } => self.check_lamports("source", key(0), amount),
+            TokenInstruction::TransferSplTokenBatch { amounts } => {
+                let destinations: Vec<Pubkey> = (0..amounts.len()).map(|index| key(4 + index)).collect();
+                self.check_token_batch(key(1), &destinations, total(&amounts))
+            }
+            _ => Ok(()),
+        };
+        result.err().unwrap_or_default()
+    }
+
+    fn check_lamports(&mut self, role: &'static str, key: Pubkey, amount: u64) -> Result<(), Vec<Problem>> {
+        let account = self.accounts.get(&key).ok_or_else(|| vec![Problem::Missing { role, key }])?;
+        self.spend(role, key, account.lamports, amount).map_err(|problem| vec![problem])
+    }
+
+    /// Source and destination are checked together, so both are reported
+    fn check_tokens(
+        &mut self,
+        source: Pubkey,
+        destination: Pubkey,
+        mint: Option<Pubkey>,
+        amount: u64,
+    ) -> Result<(), Vec<Problem>> {
+        let (source_mint, mut problems) = self.check_token_account("source token account", source, mint, amount);
+        let mint = mint.or(source_mint);
+        problems.extend(self.check_token_account("destination token account", destination, mint, 0).1);
+        if problems.is_empty() { Ok(()) } else { Err(problems) }
+    }
+
+    /// Source is checked once for the batch total, every destination against the source mint
+    fn check_token_batch(&mut self, source: Pubkey, destinations: &[Pubkey], total: u64) -> Result<(), Vec<Problem>> {
+        let (mint, mut problems) = self.check_token_account("source token account", source, None, total);
+        for destination in destinations {
+            problems.extend(self.check_token_account("destination token account", *destination, mint, 0).1);
+        }
+        if problems.is_empty() { Ok(()) } else { Err(problems) }
+    }
+
+    /// Returns mint of the token account, if it could be unpacked, and its problems
+    fn check_token_account(
+        &mut self,
+        role: &'static str,
+        key: Pubkey,
+        mint: Option<Pubkey>,
+        amount: u64,
+    ) -> (Option<Pubkey>, Vec<Problem>) {
+        let account = match self.token_program_account(role, key) {
+            Ok(account) => account,
+            Err(problems) => return (None, problems),
+        };
+        let token_account = match spl_token::state::Account::unpack(&account.data) {
+            Ok(token_account) => token_account,
+            Err(_) => return (None, vec![Problem::Uninitialized { role, key }]),
+        };
+        let mut problems = vec![];
+        if let Some(expected) = mint.filter(|expected| *expected != token_account.mint) {
+            problems.push(Problem::MintMismatch { role, key, expected, actual: token_account.mint });
+        }
+        if token_account.is_frozen() {
+            problems.push(Problem::Frozen { role, key });
+        }
+        if let Err(problem) = self.spend(role, key, token_account.amount, amount) {
+            problems.push(problem);
+        }
+        (Some(token_account.mint), problems)
+    }
+
+    fn check_mint(&self, key: Pubkey) -> Result<(), Vec<Problem>> {
+        let role = "mint";
+        let account = self.token_program_account(role, key)?;
+        spl_token::state::Mint::unpack(&account.data).map_err(|_| vec![Problem::Uninitialized { role, key }])?;
+        Ok(())
+    }
+
+    fn token_program_account(&self, role: &'static str, key: Pubkey) -> Result<&Account, Vec<Problem>> {
+        let account = self.accounts.get(&key).ok_or_else(|| vec![Problem::Missing { role, key }])?;
+        if account.owner != self.token_program_id {
+            return Err(vec![Problem::WrongOwner { role, key, owner: account.owner }])
+        }
+        Ok(account)
+    }
+
+    fn spend(&mut self, role: &'static str, key: Pubkey, available: u64, amount: u64) -> Result<(), Problem> {
+        let spent = self.spent.entry(key).or_default();
+        let required = spent.saturating_add(amount);
+        if required > available {
+            return Err(Problem::InsufficientBalance { role, key, required, available })
+        }
+        *spent = required;
+        Ok(())
+    }
+}
+
+fn total(amounts: &[u64]) -> u64 {
+    amounts.iter().fold(0, |total: u64, amount| total.saturating_add(*amount))
+}
+
+#[cfg(test)]
+mod preflight_test {
+    use std::collections::HashMap;
+    use solana_sdk::account::Account;
+    use solana_sdk::program_pack::Pack;
+    use solana_sdk::pubkey::Pubkey;
+    use spl_token::state::AccountState;
+    use crate::client::config::ProgramConfig;
+    use crate::client::preflight::{Preflight, Problem};
+    use crate::instruction::TokenInstruction;
+
+    fn keys() -> (Pubkey, Pubkey, Pubkey, Pubkey) {
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique())
+    }
+
+    fn token_account(mint: Pubkey, amount: u64, state: AccountState) -> Account {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        let account = spl_token::state::Account { mint, amount, state, ..Default::default() };
+        spl_token::state::Account::pack(account, &mut data).unwrap();
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn when_accounts_valid_expect_ok() {
+        let (owner, from, to, mint) = keys();
+        let accounts = HashMap::from([
+            (from, token_account(mint, 100, AccountState::Initialized)),
+            (to, token_account(mint, 0, AccountState::Initialized)),
+        ]);
+
+        let instr = TokenInstruction::transfer_spl_token(owner, from, to, mint, 100);
+        let report = Preflight::new(ProgramConfig::default()).check(&[instr], &accounts);
+
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn when_destination_missing_expect_problem() {
+        let (owner, from, to, mint) = keys();
+        let accounts = HashMap::from([(from, token_account(mint, 100, AccountState::Initialized))]);
+
+        let instr = TokenInstruction::transfer_spl_token(owner, from, to, mint, 100);
+        let report = Preflight::new(ProgramConfig::default()).check(&[instr], &accounts);
+
+        assert_eq!(report.problems, vec![Problem::Missing { role: "destination token account", key: to }]);
+        assert_eq!(report.problems[0].to_string(), format!("destination token account {} missing", to));
+    }
+
+    #[test]
+    fn when_source_frozen_and_mint_mismatch_expect_both_reported() {
+        let (owner, from, to) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let accounts = HashMap::from([
+            (from, token_account(mint, 100, AccountState::Frozen)),
+            (to, token_account(other_mint, 0, AccountState::Initialized)),
+        ]);
+
+        let instr = TokenInstruction::transfer_spl_token(owner, from, to, mint, 100);
+        let report = Preflight::new(ProgramConfig::default()).check(&[instr], &accounts);
+
+        assert_eq!(report.problems, vec![
+            Problem::Frozen { role: "source token account", key: from },
+            Problem::MintMismatch { role: "destination token account", key: to, expected: mint, actual: other_mint },
+        ]);
+    }
+
+    #[test]
+    fn when_transfers_overspend_together_expect_insufficient_balance() {
+        let (owner, from, to, mint) = keys();
+        let accounts = HashMap::from([
+            (from, token_account(mint, 150, AccountState::Initialized)),
+            (to, token_account(mint, 0, AccountState::Initialized)),
+        ]);
+
+        let instrs = [
+            TokenInstruction::transfer_spl_token(owner, from, to, mint, 100),
+            TokenInstruction::transfer_spl_token(owner, from, to, mint, 100),
+        ];
+        let report = Preflight::new(ProgramConfig::default()).check(&instrs, &accounts);
+
+        assert_eq!(report.problems, vec![
+            Problem::InsufficientBalance { role: "source token account", key: from, required: 200, available: 150 },
+        ]);
+    }
+
+    #[test]
+    fn when_lamports_insufficient_expect_problem() {
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let owner = solana_sdk::system_program::id();
+        let account = Account { lamports: 50, data: vec![], owner, executable: false, rent_epoch: 0 };
+        let accounts = HashMap::from([(from, account)]);
+
+        let instr = TokenInstruction::transfer_lamports(from, to, 100);
+        let report = Preflight::new(ProgramConfig::default()).check(&[instr], &accounts);
+
+        assert_eq!(report.problems, vec![
+            Problem::InsufficientBalance { role: "source", key: from, required: 100, available: 50 },
+        ]);
+    }
+}
@@ -0,0 +1,188 @@
+//! Compute unit prices of payout transactions by SLA.
+//!
+//! Each payout is tagged with an `Sla`. A transaction bids a percentile of prioritization fees recently paid
+//! for the accounts it writes, a higher one for a tighter SLA, and every resubmission of the same payout bids
+//! more, faster for a tighter SLA. During congestion urgent payouts outbid best effort ones instead of
+//! competing with them evenly.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use crate::client::error::ClientResult;
+
+/// Accounts accepted by `getRecentPrioritizationFees`
+const MAX_FEE_ACCOUNTS: usize = 128;
+/// Payouts with fewer slots left before the deadline are priced as urgent
+const DEFAULT_LATE_SLOTS: u64 = 150;
+
+/// Service level of a payout, ordered from the most to the least urgent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sla {
+    Urgent,
+    Standard,
+    BestEffort,
+}
+
+impl Sla {
+    /// Percentile of recent fees bid by the first attempt
+    fn percentile(self) -> usize {
+        match self {
+            Sla::Urgent => 90,
+            Sla::Standard => 50,
+            Sla::BestEffort => 25,
+        }
+    }
+
+    /// Price increase of each resubmission in percent
+    fn bump_percent(self) -> u64 {
+        match self {
+            Sla::Urgent => 100,
+            Sla::Standard => 50,
+            Sla::BestEffort => 20,
+        }
+    }
+}
+
+/// Prioritization fees recently paid, micro-lamports per compute unit
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FeeMarket {
+    /// Ascending
+    fees: Vec<u64>,
+}
+
+impl FeeMarket {
+    pub fn new(mut fees: Vec<u64>) -> Self {
+        fees.sort_unstable();
+        FeeMarket { fees }
+    }
+
+    /// Fees paid over recent slots by transactions writing any of `accounts`
+    pub fn fetch(rpc_client: &RpcClient, accounts: &[Pubkey]) -> ClientResult<Self> {
+        let accounts: Vec<String> = accounts.iter().take(MAX_FEE_ACCOUNTS).map(Pubkey::to_string).collect();
+        let request = RpcRequest::Custom { method: "getRecentPrioritizationFees" };
+        let samples: Vec<Value> = rpc_client.send(request, json!([accounts]))?;
+        Ok(FeeMarket::new(samples.iter().filter_map(|sample| sample["prioritizationFee"].as_u64()).collect()))
+    }
+
+    /// Fee not exceeded by `percentile` percent of recent fees, 0 without fees
+    pub fn percentile(&self, percentile: usize) -> u64 {
+        match self.fees.len() {
+            0 => 0,
+            len => self.fees[(len - 1) * percentile.min(100) / 100],
+        }
+    }
+}
+
+/// Compute unit price bounds of prioritized transactions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriorityFees {
+    min_price: u64,
+    max_price: u64,
+    late_slots: u64,
+}
+
+impl PriorityFees {
+    /// Prices stay between `min_price` and `max_price` micro-lamports per compute unit
+    pub fn new(min_price: u64, max_price: u64) -> Self {
+        assert!(min_price <= max_price, "Min price must not exceed max price");
+        PriorityFees { min_price, max_price, late_slots: DEFAULT_LATE_SLOTS }
+    }
+
+    /// Payouts with fewer than `late_slots` slots left before the deadline are priced as urgent
+    pub fn with_late_slots(mut self, late_slots: u64) -> Self {
+        self.late_slots = late_slots;
+        self
+    }
+
+    /// Price of `attempt`, starting at 1, of a payout with `sla` and `slots_left` before the deadline if any
+    pub fn price(&self, sla: Sla, market: &FeeMarket, attempt: usize, slots_left: Option<u64>) -> u64 {
+        let sla = match slots_left {
+            Some(slots_left) if slots_left < self.late_slots => Sla::Urgent,
+            _ => sla,
+        };
+        let mut price = market.percentile(sla.percentile()).max(self.min_price);
+        for _ in 1..attempt {
+            if price >= self.max_price {
+                break
+            }
+            price = price.saturating_add((price.saturating_mul(sla.bump_percent()) / 100).max(1));
+        }
+        price.min(self.max_price)
+    }
+}
+
+/// Accounts written by `instructions`, the ones whose fee market the transaction competes in
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = instructions.iter()
+        .flat_map(|instr| instr.accounts.iter())
+        .filter(|meta| meta.is_writable)
+        .map(|meta| meta.pubkey)
+        .collect();
+    accounts.sort_unstable();
+    accounts.dedup();
+    accounts
+}
+
+#[cfg(test)]
+mod priority_test {
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::priority::{writable_accounts, FeeMarket, PriorityFees, Sla};
+    use crate::instruction::TokenInstruction;
+
+    fn market() -> FeeMarket {
+        FeeMarket::new((1..=100).rev().map(|fee| fee * 1_000).collect())
+    }
+
+    #[test]
+    fn when_first_attempt_expect_tighter_sla_bids_higher() {
+        let fees = PriorityFees::new(0, u64::MAX);
+
+        let prices: Vec<u64> = [Sla::Urgent, Sla::Standard, Sla::BestEffort].iter()
+            .map(|sla| fees.price(*sla, &market(), 1, None))
+            .collect();
+
+        assert_eq!(prices, vec![90_000, 50_000, 25_000]);
+    }
+
+    #[test]
+    fn when_resubmitted_expect_bumped_by_sla_and_capped() {
+        let fees = PriorityFees::new(0, 300_000);
+
+        assert_eq!(fees.price(Sla::Standard, &market(), 3, None), 112_500);
+        assert_eq!(fees.price(Sla::BestEffort, &market(), 2, None), 30_000);
+        assert_eq!(fees.price(Sla::Urgent, &market(), 3, None), 300_000);
+        assert_eq!(fees.price(Sla::Urgent, &market(), usize::MAX, None), 300_000);
+    }
+
+    #[test]
+    fn when_below_min_price_expect_min_price() {
+        let fees = PriorityFees::new(0, 1_000);
+
+        assert_eq!(fees.price(Sla::Urgent, &FeeMarket::default(), 1, None), 0);
+        assert_eq!(fees.price(Sla::Urgent, &FeeMarket::default(), 3, None), 2);
+        assert_eq!(PriorityFees::new(30_000, u64::MAX).price(Sla::BestEffort, &market(), 1, None), 30_000);
+    }
+
+    #[test]
+    fn when_deadline_close_expect_priced_as_urgent() {
+        let fees = PriorityFees::new(0, u64::MAX).with_late_slots(100);
+
+        assert_eq!(fees.price(Sla::BestEffort, &market(), 1, Some(99)), 90_000);
+        assert_eq!(fees.price(Sla::BestEffort, &market(), 1, Some(100)), 25_000);
+    }
+
+    #[test]
+    fn when_writable_accounts_expect_deduplicated_writable_only() {
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let transfer = TokenInstruction::transfer_lamports(from, to, 100);
+
+        let accounts = writable_accounts(&[transfer.clone(), transfer]);
+
+        let mut expected = vec![from, to];
+        expected.sort_unstable();
+        assert_eq!(accounts, expected);
+    }
+}
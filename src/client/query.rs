@@ -0,0 +1,382 @@
+//! Read API over the tables of `Archiver`, enabled by `archiver` feature, so dashboards don't depend on
+//! the schema or RPC. `ArchiveQuery` answers in Rust, `QueryServer` serves it as JSON over HTTP:
+//! - `GET /accounts/{address}/balances?mint={mint}` balance after each slot moving value of the account
+//! - `GET /accounts/{address}/transfers?counterparty={address}&limit={n}` transfers, latest first
+//! - `GET /escrows/{escrow}` current status of an escrow
+//!
+//! Balances are net flows of archived transfers, so they start from zero at the first archived slot and
+//! miss value moved by other programs.
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use serde::Serialize;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use crate::client::archiver::{ArchiveBackend, SqlValue};
+use crate::client::error::{ClientError, ClientResult};
+
+const SELECT_FLOWS: &str = "
+SELECT slot, source, destination, mint, amount FROM transfers
+WHERE source = $1 OR destination = $1
+ORDER BY slot, signature, instruction_index, transfer_index";
+
+const SELECT_TRANSFERS: &str = "
+SELECT signature, instruction_index, transfer_index, slot, source, destination, mint, amount FROM transfers
+WHERE source = $1 OR destination = $1
+ORDER BY slot DESC, signature, instruction_index, transfer_index
+LIMIT $2";
+
+const SELECT_TRANSFERS_BETWEEN: &str = "
+SELECT signature, instruction_index, transfer_index, slot, source, destination, mint, amount FROM transfers
+WHERE (source = $1 AND destination = $2) OR (source = $2 AND destination = $1)
+ORDER BY slot DESC, signature, instruction_index, transfer_index
+LIMIT $3";
+
+const SELECT_ESCROW: &str = "
+SELECT escrow, initializer, status, amount, expected_amount, expires_at, taker, sequence FROM escrows
+WHERE escrow = $1";
+
+/// Balance of `mint` after `slot`, None mint for transfers of unknown mint
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BalancePoint {
+    pub slot: u64,
+    pub mint: Option<String>,
+    pub balance: i128,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TransferRow {
+    pub signature: String,
+    pub instruction_index: u64,
+    pub transfer_index: u64,
+    pub slot: u64,
+    pub source: Option<String>,
+    pub destination: String,
+    pub mint: Option<String>,
+    pub amount: u64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EscrowRow {
+    pub escrow: String,
+    pub initializer: String,
+    /// `open`, `exchanged` or `cancelled`
+    pub status: String,
+    pub amount: Option<u64>,
+    pub expected_amount: Option<u64>,
+    pub expires_at: Option<i64>,
+    pub taker: Option<String>,
+    pub sequence: Option<u64>,
+}
+
+/// Queries of a database with tables made by `Archiver`
+pub struct ArchiveQuery<B: ArchiveBackend> {
+    backend: B,
+}
+
+impl<B: ArchiveBackend> ArchiveQuery<B> {
+    pub fn new(backend: B) -> Self {
+        ArchiveQuery { backend }
+    }
+
+    /// Balances of `account` in slot order, of every mint unless `mint` is set
+    pub fn balance_history(&mut self, account: &Pubkey, mint: Option<&str>) -> ClientResult<Vec<BalancePoint>> {
+        let account = account.to_string();
+        let rows = self.backend.query(SELECT_FLOWS, &[SqlValue::text(&account)])?;
+
+        let mut balances: HashMap<Option<String>, i128> = HashMap::new();
+        let mut points: Vec<BalancePoint> = vec![];
+        for row in rows {
+            let row_mint = row[3].as_text().map(str::to_string);
+            if mint.is_some() && row_mint.as_deref() != mint {
+                continue
+            }
+            let (slot, amount) = (unsigned(&row[0])?, i128::from(unsigned(&row[4])?));
+            let change = match (row[1].as_text() == Some(&account), row[2].as_text() == Some(&account)) {
+                (true, false) => -amount,
+                (false, true) => amount,
+                _ => 0,
+            };
+            let balance = balances.entry(row_mint.clone()).or_default();
+            *balance += change;
+
+            let mut same_slot = points.iter_mut().rev().take_while(|point| point.slot == slot);
+            match same_slot.find(|point| point.mint == row_mint) {
+                Some(point) => point.balance = *balance,
+                None => points.push(BalancePoint { slot, mint: row_mint, balance: *balance }),
+            }
+        }
+        Ok(points)
+    }
+
+    /// Latest transfers from or to `account`, only those with `counterparty` when set
+    pub fn transfers(
+        &mut self,
+        account: &Pubkey,
+        counterparty: Option<&Pubkey>,
+        limit: u64,
+    ) -> ClientResult<Vec<TransferRow>> {
+        let rows = match counterparty {
+            Some(counterparty) => self.backend.query(SELECT_TRANSFERS_BETWEEN, &[
+                SqlValue::text(account),
+                SqlValue::text(counterparty),
+                SqlValue::int(limit)?,
+            ])?,
+            None => self.backend.query(SELECT_TRANSFERS, &[SqlValue::text(account), SqlValue::int(limit)?])?,
+        };
+        rows.iter()
+            .map(|row| {
+                Ok(TransferRow {
+                    signature: text(&row[0])?,
+                    instruction_index: unsigned(&row[1])?,
+                    transfer_index: unsigned(&row[2])?,
+                    slot: unsigned(&row[3])?,
+                    source: row[4].as_text().map(str::to_string),
+                    destination: text(&row[5])?,
+                    mint: row[6].as_text().map(str::to_string),
+                    amount: unsigned(&row[7])?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn escrow(&mut self, escrow: &Pubkey) -> ClientResult<Option<EscrowRow>> {
+        let rows = self.backend.query(SELECT_ESCROW, &[SqlValue::text(escrow)])?;
+        rows.first()
+            .map(|row| {
+                Ok(EscrowRow {
+                    escrow: text(&row[0])?,
+                    initializer: text(&row[1])?,
+                    status: text(&row[2])?,
+                    amount: optional_unsigned(&row[3])?,
+                    expected_amount: optional_unsigned(&row[4])?,
+                    expires_at: row[5].as_int(),
+                    taker: row[6].as_text().map(str::to_string),
+                    sequence: optional_unsigned(&row[7])?,
+                })
+            })
+            .transpose()
+    }
+
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+}
+
+fn unsigned(value: &SqlValue) -> ClientResult<u64> {
+    value.as_int().and_then(|value| u64::try_from(value).ok()).ok_or(ClientError::InvalidArchive)
+}
+
+fn optional_unsigned(value: &SqlValue) -> ClientResult<Option<u64>> {
+    value.as_int().map(|value| u64::try_from(value).map_err(|_| ClientError::InvalidArchive)).transpose()
+}
+
+fn text(value: &SqlValue) -> ClientResult<String> {
+    value.as_text().map(str::to_string).ok_or(ClientError::InvalidArchive)
+}
+
+/// Serves `ArchiveQuery` over HTTP, one connection at a time
+pub struct QueryServer<B: ArchiveBackend> {
+    query: ArchiveQuery<B>,
+}
+
+impl<B: ArchiveBackend> QueryServer<B> {
+    pub const DEFAULT_LIMIT: u64 = 100;
+    pub const MAX_LIMIT: u64 = 1_000;
+
+    pub fn new(query: ArchiveQuery<B>) -> Self {
+        QueryServer { query }
+    }
+
+    /// Answer connections of `listener` until it fails, a failed connection doesn't stop the server
+    pub fn serve(&mut self, listener: &TcpListener) -> ClientResult<()> {
+        for stream in listener.incoming() {
+            let _ = self.connection(stream?);
+        }
+        Ok(())
+    }
+
+    fn connection(&mut self, mut stream: TcpStream) -> ClientResult<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 0 && header.trim_end() != "" {
+            header.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (status, body) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => self.respond(method, target),
+            _ => (400, json!({ "error": "malformed request" })),
+        };
+        let body = serde_json::to_vec(&body)?;
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            reason(status),
+            body.len(),
+        )?;
+        stream.write_all(&body)?;
+        Ok(())
+    }
+
+    /// Status code and JSON body answering `method` of `target` path with optional query string
+    pub fn respond(&mut self, method: &str, target: &str) -> (u16, serde_json::Value) {
+        if method != "GET" {
+            return (405, json!({ "error": "only GET is supported" }))
+        }
+        match self.route(target) {
+            Ok(body) => (200, body),
+            Err(RouteError::BadRequest(param)) => (400, json!({ "error": format!("invalid {}", param) })),
+            Err(RouteError::NotFound) => (404, json!({ "error": "not found" })),
+            Err(RouteError::Client(e)) => (500, json!({ "error": e.to_string() })),
+        }
+    }
+
+    fn route(&mut self, target: &str) -> Result<serde_json::Value, RouteError> {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let params: HashMap<&str, &str> = query.split('&').filter_map(|param| param.split_once('=')).collect();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        match segments.as_slice() {
+            ["accounts", account, "balances"] => {
+                let points = self.query.balance_history(&parse_pubkey(account)?, params.get("mint").copied())?;
+                Ok(json!(points))
+            }
+            ["accounts", account, "transfers"] => {
+                let counterparty = params.get("counterparty").map(|address| parse_pubkey(address)).transpose()?;
+                let limit = match params.get("limit") {
+                    Some(limit) => limit.parse::<u64>().map_err(|_| RouteError::BadRequest("limit"))?,
+                    None => Self::DEFAULT_LIMIT,
+                };
+                let account = parse_pubkey(account)?;
+                Ok(json!(self.query.transfers(&account, counterparty.as_ref(), limit.min(Self::MAX_LIMIT))?))
+            }
+            ["escrows", escrow] => match self.query.escrow(&parse_pubkey(escrow)?)? {
+                Some(escrow) => Ok(json!(escrow)),
+                None => Err(RouteError::NotFound),
+            },
+            _ => Err(RouteError::NotFound),
+        }
+    }
+}
+
+enum RouteError {
+    BadRequest(&'static str),
+    NotFound,
+    Client(ClientError),
+}
+
+impl From<ClientError> for RouteError {
+    fn from(e: ClientError) -> Self {
+        RouteError::Client(e)
+    }
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, RouteError> {
+    Pubkey::from_str(value).map_err(|_| RouteError::BadRequest("address"))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod query_test {
+    use rusqlite::Connection;
+    use serde_json::json;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use crate::client::archiver::Archiver;
+    use crate::client::geyser::StreamItem;
+    use crate::client::query::{ArchiveQuery, BalancePoint, QueryServer};
+    use crate::event::{EscrowInitializedEvent, EventRecord, TokenEvent};
+    use crate::instruction::TokenInstruction;
+
+    fn transfer(slot: u64, index: usize, from: Pubkey, to: Pubkey, amount: u64) -> StreamItem {
+        StreamItem::Instruction {
+            slot,
+            signature: Signature::from([slot as u8; 64]),
+            index,
+            instruction: TokenInstruction::TransferLamports { amount },
+            accounts: vec![from, to],
+        }
+    }
+
+    fn archive(items: &[StreamItem]) -> ArchiveQuery<Connection> {
+        let mut archiver = Archiver::new(Connection::open_in_memory().unwrap()).unwrap();
+        for item in items {
+            archiver.archive(item).unwrap();
+        }
+        ArchiveQuery::new(archiver.into_inner())
+    }
+
+    #[test]
+    fn when_transfers_in_and_out_expect_balance_per_slot() {
+        let (alice, bob, carol) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut query = archive(&[
+            transfer(10, 0, bob, alice, 500),
+            transfer(11, 0, alice, carol, 200),
+            transfer(11, 1, bob, alice, 50),
+        ]);
+        let native = Some(spl_token::native_mint::id().to_string());
+
+        assert_eq!(query.balance_history(&alice, None).unwrap(), vec![
+            BalancePoint { slot: 10, mint: native.clone(), balance: 500 },
+            BalancePoint { slot: 11, mint: native.clone(), balance: 350 },
+        ]);
+        assert_eq!(query.balance_history(&carol, native.as_deref()).unwrap(), vec![
+            BalancePoint { slot: 11, mint: native, balance: 200 },
+        ]);
+        assert!(query.balance_history(&alice, Some("other")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn when_counterparty_expect_only_transfers_between_them_latest_first() {
+        let (alice, bob, carol) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut query = archive(&[
+            transfer(10, 0, bob, alice, 500),
+            transfer(11, 0, alice, carol, 200),
+            transfer(12, 0, alice, bob, 50),
+        ]);
+
+        let transfers = query.transfers(&alice, Some(&bob), 10).unwrap();
+        let amounts: Vec<u64> = transfers.iter().map(|transfer| transfer.amount).collect();
+        assert_eq!(amounts, vec![50, 500]);
+        assert_eq!(transfers[0].source, Some(alice.to_string()));
+        assert_eq!(query.transfers(&alice, None, 2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn when_request_routed_expect_status_and_json() {
+        let (escrow, initializer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let query = archive(&[StreamItem::Event(EventRecord {
+            sequence: Some(0),
+            stream: Some(Pubkey::new_from_array([3; 32])),
+            event: TokenEvent::EscrowInitialized(EscrowInitializedEvent {
+                escrow, initializer, amount: 100, expected_amount: 50, expires_at: 1_000,
+            }),
+        })]);
+        let mut server = QueryServer::new(query);
+
+        let (status, body) = server.respond("GET", &format!("/escrows/{}", escrow));
+        assert_eq!(status, 200);
+        assert_eq!(body["status"], json!("open"));
+        assert_eq!(body["expected_amount"], json!(50));
+        assert_eq!(server.respond("GET", &format!("/escrows/{}", Pubkey::new_unique())).0, 404);
+        assert_eq!(server.respond("GET", "/escrows/not-a-pubkey").0, 400);
+        assert_eq!(server.respond("GET", &format!("/accounts/{}/transfers?limit=x", escrow)).0, 400);
+        assert_eq!(server.respond("POST", &format!("/escrows/{}", escrow)).0, 405);
+        assert_eq!(server.respond("GET", "/unknown").0, 404);
+    }
+}
@@ -0,0 +1,190 @@
+//! High-level calls of the program over RPC: every call builds the instruction, fetches a blockhash,
+//! signs with the fee payer and the authority and waits for confirmation.
+//!
+//! Instructions are retargeted to `ProgramConfig::program_id`, so forks deployed under another ID are supported.
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use crate::client::config::{FeeStrategy, ProgramConfig};
+use crate::client::error::ClientResult;
+use crate::client::priority::{self, FeeMarket};
+use crate::compute_budget;
+use crate::instruction::TokenInstruction;
+
+pub struct TokenClient<'a> {
+    rpc_client: &'a RpcClient,
+    fee_payer: &'a dyn Signer,
+    config: ProgramConfig,
+    memo: Option<String>,
+    fee_strategy: FeeStrategy,
+}
+
+impl<'a> TokenClient<'a> {
+    pub fn new(rpc_client: &'a RpcClient, fee_payer: &'a dyn Signer) -> Self {
+        TokenClient {
+            rpc_client,
+            fee_payer,
+            config: ProgramConfig::default(),
+            memo: None,
+            fee_strategy: FeeStrategy::Base,
+        }
+    }
+
+    pub fn with_config(mut self, config: ProgramConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Append SPL memo instruction with `memo` to every transaction, e.g. deposit tag required by an exchange
+    pub fn with_memo(mut self, memo: String) -> Self {
+        self.memo = Some(memo);
+        self
+    }
+
+    /// Prepend compute unit price of `fee_strategy` bid on the current fee market to every transaction
+    pub fn with_fee_strategy(mut self, fee_strategy: FeeStrategy) -> Self {
+        self.fee_strategy = fee_strategy;
+        self
+    }
+
+    pub fn transfer_lamports(&self, from: &dyn Signer, to: Pubkey, amount: u64) -> ClientResult<Signature> {
+        self.send(vec![TokenInstruction::transfer_lamports_v2(from.pubkey(), to, amount)], &[from])
+    }
+
+    /// Transfer `amount` of `mint` between token accounts, `owner` is the authority of `from_spl_token`
+    pub fn transfer_spl_token(
+        &self,
+        owner: &dyn Signer,
+        from_spl_token: Pubkey,
+        to_spl_token: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    ) -> ClientResult<Signature> {
+        let instr = TokenInstruction::transfer_spl_token_v2(owner.pubkey(), from_spl_token, to_spl_token, mint, amount);
+        self.send(vec![instr], &[owner])
+    }
+
+    /// Let `delegate` transfer up to `amount` from `from_spl_token`
+    pub fn approve(
+        &self,
+        owner: &dyn Signer,
+        from_spl_token: Pubkey,
+        delegate: Pubkey,
+        amount: u64,
+    ) -> ClientResult<Signature> {
+        let instr = TokenInstruction::approve_spl_token(owner.pubkey(), from_spl_token, delegate, amount);
+        self.send(vec![instr], &[owner])
+    }
+
+    /// Sign `instructions` with the fee payer and `signers` against a fresh blockhash, send and confirm
+    pub fn send(&self, mut instructions: Vec<Instruction>, signers: &[&dyn Signer]) -> ClientResult<Signature> {
+        if let Some((priority_fees, sla)) = self.fee_strategy.priority_fees() {
+            let market = FeeMarket::fetch(self.rpc_client, &priority::writable_accounts(&instructions))?;
+            instructions.insert(0, compute_budget::set_compute_unit_price(priority_fees.price(sla, &market, 1, None)));
+        }
+        let blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.transaction(instructions, signers, blockhash)?;
+        Ok(self.rpc_client.send_and_confirm_transaction(&transaction)?)
+    }
+
+    fn transaction(
+        &self,
+        mut instructions: Vec<Instruction>,
+        signers: &[&dyn Signer],
+        blockhash: Hash,
+    ) -> ClientResult<Transaction> {
+        for instruction in instructions.iter_mut().filter(|instruction| instruction.program_id == crate::id()) {
+            instruction.program_id = self.config.program_id;
+        }
+        if let Some(memo) = &self.memo {
+            instructions.push(spl_memo::build_memo(memo.as_bytes(), &[]));
+        }
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&self.fee_payer.pubkey()));
+        let mut all_signers = vec![self.fee_payer];
+        all_signers.extend(signers.iter().filter(|signer| signer.pubkey() != self.fee_payer.pubkey()));
+        transaction.try_sign(&all_signers, blockhash)?;
+        Ok(transaction)
+    }
+}
+
+#[cfg(test)]
+mod rpc_test {
+    use solana_client::rpc_client::RpcClient;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use crate::client::config::ProgramConfig;
+    use crate::client::error::ClientError;
+    use crate::client::rpc::TokenClient;
+    use crate::instruction::TokenInstruction;
+
+    fn rpc_client() -> RpcClient {
+        RpcClient::new("http://127.0.0.1:8899".to_string())
+    }
+
+    #[test]
+    fn when_signed_expect_fee_payer_first_and_verified() {
+        let (rpc_client, fee_payer, from) = (rpc_client(), Keypair::new(), Keypair::new());
+        let client = TokenClient::new(&rpc_client, &fee_payer);
+        let instr = TokenInstruction::transfer_lamports(from.pubkey(), Pubkey::new_unique(), 100);
+
+        let transaction = client.transaction(vec![instr], &[&from], Hash::new_unique()).unwrap();
+
+        assert_eq!(transaction.message.account_keys[..2], [fee_payer.pubkey(), from.pubkey()]);
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn when_authority_pays_fee_expect_single_signature() {
+        let (rpc_client, from) = (rpc_client(), Keypair::new());
+        let client = TokenClient::new(&rpc_client, &from);
+        let instr = TokenInstruction::transfer_lamports(from.pubkey(), Pubkey::new_unique(), 100);
+
+        let transaction = client.transaction(vec![instr], &[&from], Hash::new_unique()).unwrap();
+
+        assert_eq!(transaction.signatures.len(), 1);
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn when_program_id_configured_expect_instructions_retargeted() {
+        let (rpc_client, fee_payer, from) = (rpc_client(), Keypair::new(), Keypair::new());
+        let program_id = Pubkey::new_unique();
+        let config = ProgramConfig { program_id, ..ProgramConfig::default() };
+        let client = TokenClient::new(&rpc_client, &fee_payer).with_config(config);
+        let instr = TokenInstruction::transfer_lamports(from.pubkey(), Pubkey::new_unique(), 100);
+
+        let transaction = client.transaction(vec![instr], &[&from], Hash::new_unique()).unwrap();
+
+        let message = &transaction.message;
+        assert_eq!(message.account_keys[message.instructions[0].program_id_index as usize], program_id);
+    }
+
+    #[test]
+    fn when_memo_expect_memo_instruction_last() {
+        let (rpc_client, fee_payer, from) = (rpc_client(), Keypair::new(), Keypair::new());
+        let client = TokenClient::new(&rpc_client, &fee_payer).with_memo("104729".to_string());
+        let instr = TokenInstruction::transfer_lamports(from.pubkey(), Pubkey::new_unique(), 100);
+
+        let transaction = client.transaction(vec![instr], &[&from], Hash::new_unique()).unwrap();
+
+        let message = &transaction.message;
+        let memo_instr = &message.instructions[1];
+        assert_eq!(message.account_keys[memo_instr.program_id_index as usize], spl_memo::id());
+        assert_eq!(memo_instr.data, b"104729");
+    }
+
+    #[test]
+    fn when_authority_signature_missing_expect_error() {
+        let (rpc_client, fee_payer, from) = (rpc_client(), Keypair::new(), Keypair::new());
+        let client = TokenClient::new(&rpc_client, &fee_payer);
+        let instr = TokenInstruction::transfer_lamports(from.pubkey(), Pubkey::new_unique(), 100);
+
+        let result = client.transaction(vec![instr], &[], Hash::new_unique());
+
+        assert!(matches!(result, Err(ClientError::Signer(_))));
+    }
+}
@@ -0,0 +1,190 @@
+//! Signing backends, so production deployments never load raw keys into the payout process.
+//!
+//! Any `SignerProvider` is usable wherever a Solana `Signer` is expected through `ProviderSigner`.
+//! Signatures from remote backends are verified against the expected pubkey before use.
+use std::convert::TryFrom;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::signer::SignerError;
+
+pub trait SignerProvider: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+
+    /// Ed25519 signature over serialized transaction message
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignerError>;
+}
+
+/// Adapter implementing Solana `Signer` for any provider
+pub struct ProviderSigner<P: SignerProvider>(pub P);
+
+impl<P: SignerProvider> Signer for ProviderSigner<P> {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.0.pubkey())
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.0.sign(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+pub struct LocalSigner(pub Keypair);
+
+impl SignerProvider for LocalSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.0.pubkey()
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.0.try_sign_message(message)
+    }
+}
+
+/// Remote signing service, `POST url` with `{"pubkey", "message"}` answering `{"signature"}`,
+/// message is base64 and signature base58 encoded
+pub struct HttpSigner {
+    url: String,
+    pubkey: Pubkey,
+    bearer_token: Option<String>,
+    client: reqwest::blocking::Client,
+}
+
+#[derive(Serialize)]
+struct HttpSignRequest {
+    pubkey: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct HttpSignResponse {
+    signature: String,
+}
+
+impl HttpSigner {
+    pub fn new(url: String, pubkey: Pubkey, bearer_token: Option<String>) -> Self {
+        HttpSigner { url, pubkey, bearer_token, client: reqwest::blocking::Client::new() }
+    }
+}
+
+impl SignerProvider for HttpSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let mut request = self.client.post(&self.url).json(&HttpSignRequest {
+            pubkey: self.pubkey.to_string(),
+            message: base64::encode(message),
+        });
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+        let response: HttpSignResponse = request
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .map_err(|e| SignerError::Connection(e.to_string()))?;
+        let signature = response.signature.parse().map_err(|_| SignerError::Protocol("Invalid signature".into()))?;
+        verified(signature, &self.pubkey, message)
+    }
+}
+
+/// Google Cloud KMS `EC_SIGN_ED25519` key version,
+/// `access_token` supplies OAuth token of the calling service account
+pub struct GcpKmsSigner {
+    key_version: String,
+    pubkey: Pubkey,
+    access_token: Box<dyn Fn() -> Result<String, SignerError> + Send + Sync>,
+    client: reqwest::blocking::Client,
+}
+
+#[derive(Serialize)]
+struct KmsSignRequest {
+    data: String,
+}
+
+#[derive(Deserialize)]
+struct KmsSignResponse {
+    signature: String,
+}
+
+impl GcpKmsSigner {
+    const API_URL: &'static str = "https://cloudkms.googleapis.com/v1";
+
+    /// `key_version` is the full resource name,
+    /// `projects/*/locations/*/keyRings/*/cryptoKeys/*/cryptoKeyVersions/*`
+    pub fn new<F>(key_version: String, pubkey: Pubkey, access_token: F) -> Self
+    where
+        F: Fn() -> Result<String, SignerError> + Send + Sync + 'static,
+    {
+        GcpKmsSigner {
+            key_version,
+            pubkey,
+            access_token: Box::new(access_token),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+impl SignerProvider for GcpKmsSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let url = format!("{}/{}:asymmetricSign", Self::API_URL, self.key_version);
+        let response: KmsSignResponse = self.client
+            .post(&url)
+            .bearer_auth((self.access_token)()?)
+            .json(&KmsSignRequest { data: base64::encode(message) })
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .map_err(|e| SignerError::Connection(e.to_string()))?;
+        let signature_bytes = base64::decode(&response.signature)
+            .map_err(|_| SignerError::Protocol("Invalid signature".into()))?;
+        let signature = Signature::try_from(signature_bytes)
+            .map_err(|_| SignerError::Protocol("Invalid signature".into()))?;
+        verified(signature, &self.pubkey, message)
+    }
+}
+
+fn verified(signature: Signature, pubkey: &Pubkey, message: &[u8]) -> Result<Signature, SignerError> {
+    if signature.verify(pubkey.as_ref(), message) {
+        Ok(signature)
+    } else {
+        Err(SignerError::KeypairPubkeyMismatch)
+    }
+}
+
+#[cfg(test)]
+mod signer_test {
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::signer::SignerError;
+    use crate::client::signer::{verified, LocalSigner, ProviderSigner};
+
+    #[test]
+    fn when_local_signer_expect_verifiable_signature() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let signer = ProviderSigner(LocalSigner(keypair));
+
+        let signature = signer.try_sign_message(b"message").unwrap();
+
+        assert_eq!(signer.pubkey(), pubkey);
+        assert!(signature.verify(pubkey.as_ref(), b"message"));
+    }
+
+    #[test]
+    fn when_signature_of_other_key_expect_error() {
+        let signature = Keypair::new().sign_message(b"message");
+
+        let result = verified(signature, &Keypair::new().pubkey(), b"message");
+
+        assert!(matches!(result, Err(SignerError::KeypairPubkeyMismatch)));
+    }
+}
@@ -0,0 +1,152 @@
+//! Transfers whose owner key is controlled by a threshold signature scheme, e.g. MPC custody.
+//!
+//! The session exposes the exact message participants sign, collects their partial signatures
+//! and assembles the transaction once the scheme specific aggregator produced the final signature.
+use std::collections::BTreeMap;
+use solana_sdk::hash::{hash, Hash};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::signers::Signers;
+use solana_sdk::transaction::Transaction;
+use crate::client::error::{ClientError, ClientResult};
+
+/// Scheme specific combination of partial signatures into one Ed25519 signature
+pub trait SignatureAggregator {
+    fn aggregate(&self, message: &[u8], partials: &BTreeMap<u16, Vec<u8>>) -> ClientResult<Signature>;
+}
+
+pub struct ThresholdSigningSession {
+    transaction: Transaction,
+    /// Aggregate public key of the threshold group
+    owner: Pubkey,
+    threshold: usize,
+    partials: BTreeMap<u16, Vec<u8>>,
+}
+
+impl ThresholdSigningSession {
+    pub fn new(
+        instructions: &[Instruction],
+        fee_payer: &Pubkey,
+        owner: Pubkey,
+        threshold: usize,
+        blockhash: Hash,
+    ) -> Self {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(fee_payer));
+        transaction.message.recent_blockhash = blockhash;
+        ThresholdSigningSession { transaction, owner, threshold, partials: BTreeMap::new() }
+    }
+
+    /// Serialized message, the bytes every participant signs
+    pub fn message_data(&self) -> Vec<u8> {
+        self.transaction.message_data()
+    }
+
+    /// Hash of the message, for participants to confirm they sign the same transaction
+    pub fn message_hash(&self) -> Hash {
+        hash(&self.message_data())
+    }
+
+    pub fn add_partial(&mut self, participant: u16, partial: Vec<u8>) -> ClientResult<()> {
+        if self.partials.contains_key(&participant) {
+            return Err(ClientError::DuplicatePartialSignature(participant))
+        }
+        self.partials.insert(participant, partial);
+        Ok(())
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.partials.len() >= self.threshold
+    }
+
+    pub fn aggregate<A: SignatureAggregator>(&self, aggregator: &A) -> ClientResult<Signature> {
+        if !self.is_ready() {
+            return Err(ClientError::ThresholdNotReached)
+        }
+        aggregator.aggregate(&self.message_data(), &self.partials)
+    }
+
+    /// Transaction signed by the owner aggregate signature and the remaining `signers`, e.g. fee payer
+    pub fn assemble<S: Signers>(mut self, owner_signature: Signature, signers: &S) -> ClientResult<Transaction> {
+        if !owner_signature.verify(self.owner.as_ref(), &self.message_data()) {
+            return Err(ClientError::InvalidSignature)
+        }
+        let owner_position = self.transaction
+            .get_signing_keypair_positions(&[self.owner])?[0]
+            .ok_or(ClientError::InvalidSignature)?;
+        self.transaction.signatures[owner_position] = owner_signature;
+
+        let blockhash = self.transaction.message.recent_blockhash;
+        self.transaction.try_partial_sign(signers, blockhash)?;
+        self.transaction.verify()?;
+        Ok(self.transaction)
+    }
+}
+
+#[cfg(test)]
+mod threshold_test {
+    use std::collections::BTreeMap;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::{Keypair, Signature, Signer};
+    use crate::client::error::{ClientError, ClientResult};
+    use crate::client::threshold::{SignatureAggregator, ThresholdSigningSession};
+    use crate::instruction::TokenInstruction;
+
+    /// Stands in for a real scheme, group key is held by a single keypair
+    struct KeypairAggregator(Keypair);
+
+    impl SignatureAggregator for KeypairAggregator {
+        fn aggregate(&self, message: &[u8], _partials: &BTreeMap<u16, Vec<u8>>) -> ClientResult<Signature> {
+            Ok(self.0.sign_message(message))
+        }
+    }
+
+    fn session(owner: &Keypair, fee_payer: &Keypair) -> ThresholdSigningSession {
+        let instr = TokenInstruction::transfer_lamports(owner.pubkey(), Keypair::new().pubkey(), 1_000);
+        ThresholdSigningSession::new(&[instr], &fee_payer.pubkey(), owner.pubkey(), 2, Hash::new_unique())
+    }
+
+    #[test]
+    fn when_threshold_not_reached_expect_error() {
+        let owner = Keypair::new();
+        let mut session = session(&owner, &Keypair::new());
+        session.add_partial(1, vec![1]).unwrap();
+
+        let result = session.aggregate(&KeypairAggregator(owner));
+
+        assert!(matches!(result, Err(ClientError::ThresholdNotReached)));
+    }
+
+    #[test]
+    fn when_duplicate_partial_expect_error() {
+        let mut session = session(&Keypair::new(), &Keypair::new());
+        session.add_partial(1, vec![1]).unwrap();
+
+        assert!(matches!(session.add_partial(1, vec![2]), Err(ClientError::DuplicatePartialSignature(1))));
+    }
+
+    #[test]
+    fn when_assemble_aggregated_signature_expect_verified_transaction() {
+        let owner = Keypair::new();
+        let fee_payer = Keypair::new();
+        let mut session = session(&owner, &fee_payer);
+        session.add_partial(1, vec![1]).unwrap();
+        session.add_partial(2, vec![2]).unwrap();
+
+        let owner_signature = session.aggregate(&KeypairAggregator(owner)).unwrap();
+        let transaction = session.assemble(owner_signature, &[&fee_payer]).unwrap();
+
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn when_assemble_foreign_signature_expect_error() {
+        let mut session = session(&Keypair::new(), &Keypair::new());
+        let foreign_signature = Keypair::new().sign_message(&session.message_data());
+        session.add_partial(1, vec![1]).unwrap();
+
+        let result = session.assemble(foreign_signature, &[&Keypair::new()]);
+
+        assert!(matches!(result, Err(ClientError::InvalidSignature)));
+    }
+}
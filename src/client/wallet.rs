@@ -0,0 +1,113 @@
+//! Payloads for browser and mobile wallet adapters, so dApps delegate signing to a wallet
+//! while instructions are still built by this crate.
+//!
+//! Message is serialized in legacy format, byte compatible with `VersionedMessage::Legacy`
+//! of wallet-standard `signTransaction`, and listed with the accounts that must sign it.
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::program_utils::limited_deserialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use crate::client::error::{ClientError, ClientResult};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletPayload {
+    /// Serialized message, base64 encoded
+    pub message: String,
+    /// Required signers in message order, fee payer first, base58 encoded
+    pub signers: Vec<String>,
+}
+
+impl WalletPayload {
+    pub fn new(instructions: &[Instruction], fee_payer: &Pubkey, blockhash: Hash) -> Self {
+        let mut message = Message::new(instructions, Some(fee_payer));
+        message.recent_blockhash = blockhash;
+        WalletPayload::from_message(&message)
+    }
+
+    pub fn from_message(message: &Message) -> Self {
+        let signers = message.account_keys[..message.header.num_required_signatures as usize]
+            .iter()
+            .map(Pubkey::to_string)
+            .collect();
+        WalletPayload { message: base64::encode(message.serialize()), signers }
+    }
+
+    pub fn to_json(&self) -> ClientResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn message(&self) -> ClientResult<Message> {
+        let data = base64::decode(&self.message).map_err(|_| ClientError::InvalidWalletPayload)?;
+        limited_deserialize(&data).map_err(|_| ClientError::InvalidWalletPayload)
+    }
+
+    /// Transaction with signatures returned by the wallet, in `signers` order
+    pub fn signed_transaction(&self, signatures: Vec<Signature>) -> ClientResult<Transaction> {
+        let transaction = Transaction { signatures, message: self.message()? };
+        if transaction.signatures.len() != self.signers.len() || transaction.verify().is_err() {
+            return Err(ClientError::InvalidSignature)
+        }
+        Ok(transaction)
+    }
+}
+
+#[cfg(test)]
+mod wallet_test {
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::{Keypair, Signer};
+    use crate::client::error::ClientError;
+    use crate::client::wallet::WalletPayload;
+    use crate::instruction::TokenInstruction;
+
+    fn payload(payer: &Keypair, from: &Keypair) -> WalletPayload {
+        let instr = TokenInstruction::transfer_lamports(from.pubkey(), Keypair::new().pubkey(), 100);
+        WalletPayload::new(&[instr], &payer.pubkey(), Hash::new_unique())
+    }
+
+    #[test]
+    fn when_export_expect_signers_fee_payer_first() {
+        let (payer, from) = (Keypair::new(), Keypair::new());
+
+        let payload = payload(&payer, &from);
+
+        assert_eq!(payload.signers, vec![payer.pubkey().to_string(), from.pubkey().to_string()]);
+        assert_eq!(payload.message().unwrap().account_keys[0], payer.pubkey());
+    }
+
+    #[test]
+    fn when_export_expect_json_fields() {
+        let payload = payload(&Keypair::new(), &Keypair::new());
+
+        let json: serde_json::Value = serde_json::from_str(&payload.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["message"], payload.message);
+        assert_eq!(json["signers"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn when_wallet_signatures_expect_verified_transaction() {
+        let (payer, from) = (Keypair::new(), Keypair::new());
+        let payload = payload(&payer, &from);
+        let message = payload.message().unwrap().serialize();
+
+        let signatures = vec![payer.sign_message(&message), from.sign_message(&message)];
+        let transaction = payload.signed_transaction(signatures).unwrap();
+
+        assert_eq!(transaction.message.serialize(), message);
+    }
+
+    #[test]
+    fn when_signature_of_other_key_expect_error() {
+        let (payer, from) = (Keypair::new(), Keypair::new());
+        let payload = payload(&payer, &from);
+        let message = payload.message().unwrap().serialize();
+
+        let signatures = vec![payer.sign_message(&message), Keypair::new().sign_message(&message)];
+
+        assert!(matches!(payload.signed_transaction(signatures), Err(ClientError::InvalidSignature)));
+    }
+}
@@ -0,0 +1,135 @@
+//! SPL token transfer to a wallet, creating its associated token account first when missing.
+//! Associated token account program in use has no idempotent create, so existence is checked
+//! before building and the transaction fails if the account is created by someone else meanwhile.
+//! With the claimable fallback and no payer the tokens are parked for the wallet to claim instead.
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use crate::client::error::ClientResult;
+use crate::instruction::TokenInstruction;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WalletTransfer {
+    pub from: Pubkey,
+    pub from_spl_token: Pubkey,
+    pub to_wallet: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    payer: Option<Pubkey>,
+    claimable_fallback: bool,
+}
+
+impl WalletTransfer {
+    pub fn new(from: Pubkey, from_spl_token: Pubkey, to_wallet: Pubkey, mint: Pubkey, amount: u64) -> Self {
+        WalletTransfer { from, from_spl_token, to_wallet, mint, amount, payer: None, claimable_fallback: false }
+    }
+
+    /// Funds associated token account creation, defaults to `from`
+    pub fn with_payer(mut self, payer: Pubkey) -> Self {
+        self.payer = Some(payer);
+        self
+    }
+
+    /// Park tokens in a claimable balance when the destination is missing and no payer is set
+    pub fn with_claimable_fallback(mut self) -> Self {
+        self.claimable_fallback = true;
+        self
+    }
+
+    pub fn destination(&self) -> Pubkey {
+        get_associated_token_address(&self.to_wallet, &self.mint)
+    }
+
+    /// Transfer instruction, preceded by associated token account creation unless `destination_exists`
+    pub fn instructions(&self, destination_exists: bool) -> Vec<Instruction> {
+        if !destination_exists && self.payer.is_none() && self.claimable_fallback {
+            return vec![TokenInstruction::park_spl_token(
+                self.from,
+                self.from_spl_token,
+                self.to_wallet,
+                self.mint,
+                self.amount,
+            )];
+        }
+        let mut instructions = Vec::with_capacity(2);
+        if !destination_exists {
+            let payer = self.payer.unwrap_or(self.from);
+            instructions.push(create_associated_token_account(&payer, &self.to_wallet, &self.mint, &spl_token::id()));
+        }
+        instructions.push(TokenInstruction::transfer_spl_token_to_wallet(
+            self.from,
+            self.from_spl_token,
+            self.to_wallet,
+            self.destination(),
+            self.mint,
+            self.amount,
+        ));
+        instructions
+    }
+
+    /// Look up destination on the cluster and build instructions, see `instructions`
+    pub fn build(&self, rpc_client: &RpcClient) -> ClientResult<Vec<Instruction>> {
+        let destination = rpc_client.get_account_with_commitment(&self.destination(), rpc_client.commitment())?;
+        Ok(self.instructions(destination.value.is_some()))
+    }
+}
+
+#[cfg(test)]
+mod wallet_transfer_test {
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::wallet_transfer::WalletTransfer;
+    use crate::instruction::TokenInstruction;
+
+    fn transfer() -> WalletTransfer {
+        let (from, from_spl_token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        WalletTransfer::new(from, from_spl_token, Pubkey::new_unique(), Pubkey::new_unique(), 100)
+    }
+
+    #[test]
+    fn when_destination_exists_expect_only_transfer() {
+        let instructions = transfer().instructions(true);
+
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].program_id, crate::id());
+    }
+
+    #[test]
+    fn when_destination_missing_expect_create_funded_by_payer() {
+        let payer = Pubkey::new_unique();
+        let transfer = transfer().with_payer(payer);
+
+        let instructions = transfer.instructions(false);
+
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].program_id, spl_associated_token_account::id());
+        assert_eq!(instructions[0].accounts[0].pubkey, payer);
+        assert_eq!(instructions[0].accounts[1].pubkey, transfer.destination());
+        let destination_index = TokenInstruction::TRANSFER_POLICY_LEN + 3;
+        assert_eq!(instructions[1].accounts[destination_index].pubkey, transfer.destination());
+    }
+
+    #[test]
+    fn when_payer_not_set_expect_sender_pays() {
+        let transfer = transfer();
+
+        let instructions = transfer.instructions(false);
+
+        assert_eq!(instructions[0].accounts[0].pubkey, transfer.from);
+    }
+
+    #[test]
+    fn when_claimable_fallback_expect_park_unless_payer_set() {
+        let transfer = transfer().with_claimable_fallback();
+
+        let parked = transfer.instructions(false);
+        let sponsored = transfer.with_payer(Pubkey::new_unique()).instructions(false);
+
+        assert_eq!(parked.len(), 1);
+        assert_eq!(parked[0].data[0], 75);
+        assert_eq!(parked[0].accounts[2].pubkey, transfer.to_wallet);
+        assert_eq!(sponsored.len(), 2);
+        assert_eq!(transfer.instructions(true).len(), 1);
+    }
+}
@@ -0,0 +1,241 @@
+//! Webhook notifications of program events. Events matching a subscription filter are `POST`ed
+//! as JSON to the subscriber URL, failed deliveries are retried with exponential backoff.
+//!
+//! Body is signed with HMAC-SHA256 keyed by the subscription secret and sent in `X-Signature`
+//! as `sha256=<hex>`, subscribers check it with `verify_signature`. Feed the dispatcher with
+//! records of `EventConsumer`, so notifications follow event sequence.
+use std::thread;
+use std::time::Duration;
+use borsh::BorshSerialize;
+use hmac::{Hmac, Mac, NewMac};
+use reqwest::StatusCode;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use sha2::Sha256;
+use solana_sdk::pubkey::Pubkey;
+use crate::client::error::ClientResult;
+use crate::client::events::EventFields;
+use crate::event::EventRecord;
+
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// Unset criteria match any event
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventFilter {
+    /// Lamport events count as `spl_token::native_mint`, events of unknown mint never match
+    pub mint: Option<Pubkey>,
+    /// Any account of the event, e.g. depositor, recipient or order
+    pub account: Option<Pubkey>,
+    /// Events without amount never match
+    pub min_amount: Option<u64>,
+}
+
+impl EventFilter {
+    fn matches(&self, fields: &EventFields) -> bool {
+        self.mint.is_none_or(|mint| fields.mint == Some(mint))
+            && self.account.is_none_or(|account| fields.accounts.contains(&account))
+            && self.min_amount.is_none_or(|min_amount| fields.amount.is_some_and(|amount| amount >= min_amount))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub url: String,
+    pub secret: Vec<u8>,
+    pub filter: EventFilter,
+}
+
+/// Request body of a notification
+#[derive(Debug, Serialize, PartialEq)]
+pub struct WebhookPayload {
+    pub sequence: Option<u64>,
+    pub event: &'static str,
+    pub mint: Option<String>,
+    pub accounts: Vec<String>,
+    pub amount: Option<u64>,
+    /// Base64 Borsh encoded `EventRecord` with every field of the event
+    pub data: String,
+}
+
+impl WebhookPayload {
+    fn new(fields: &EventFields, sequence: Option<u64>, data: Vec<u8>) -> Self {
+        WebhookPayload {
+            sequence,
+            event: fields.name,
+            mint: fields.mint.map(|mint| mint.to_string()),
+            accounts: fields.accounts.iter().map(Pubkey::to_string).collect(),
+            amount: fields.amount,
+            data: base64::encode(data),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Delivered { url: String, sequence: Option<u64>, attempts: u32 },
+    Failed { url: String, sequence: Option<u64>, error: String },
+}
+
+pub struct WebhookDispatcher {
+    subscriptions: Vec<Subscription>,
+    max_attempts: u32,
+    backoff: Duration,
+    client: reqwest::blocking::Client,
+}
+
+impl WebhookDispatcher {
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+    pub const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+
+    pub fn new(subscriptions: Vec<Subscription>) -> Self {
+        WebhookDispatcher {
+            subscriptions,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            backoff: Self::DEFAULT_BACKOFF,
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// Attempts per delivery, at least one, with `backoff` before the first retry doubling after each
+    pub fn with_retries(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.backoff = backoff;
+        self
+    }
+
+    /// Notify every subscription matching `record`, failure of a delivery is reported and doesn't stop
+    /// the others. Returns number of delivered notifications.
+    pub fn dispatch<E: FnMut(WebhookEvent)>(&self, record: &EventRecord, on_event: &mut E) -> ClientResult<usize> {
+        let fields = EventFields::new(&record.event);
+        let subscriptions: Vec<&Subscription> = self.subscriptions
+            .iter()
+            .filter(|subscription| subscription.filter.matches(&fields))
+            .collect();
+        if subscriptions.is_empty() {
+            return Ok(0)
+        }
+        let body = serde_json::to_vec(&WebhookPayload::new(&fields, record.sequence, record.try_to_vec()?))?;
+
+        let mut delivered = 0;
+        for subscription in subscriptions {
+            let url = subscription.url.clone();
+            match self.deliver(subscription, &body) {
+                Ok(attempts) => {
+                    delivered += 1;
+                    on_event(WebhookEvent::Delivered { url, sequence: record.sequence, attempts });
+                }
+                Err(error) => on_event(WebhookEvent::Failed { url, sequence: record.sequence, error }),
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Returns number of attempts taken. Client errors other than timeout and rate limit are not retried.
+    fn deliver(&self, subscription: &Subscription, body: &[u8]) -> Result<u32, String> {
+        let signature = sign(&subscription.secret, body);
+        let mut delay = self.backoff;
+        let mut attempt = 1;
+        loop {
+            let result = self.client
+                .post(&subscription.url)
+                .header(CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .body(body.to_vec())
+                .send()
+                .and_then(|response| response.error_for_status());
+            match result {
+                Ok(_) => return Ok(attempt),
+                Err(e) if attempt >= self.max_attempts || !retryable(&e) => return Err(e.to_string()),
+                Err(_) => {}
+            }
+            thread::sleep(delay);
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+fn retryable(error: &reqwest::Error) -> bool {
+    error.status().is_none_or(|status| {
+        status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT
+    })
+}
+
+/// `X-Signature` value of `body`
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    format!("{}{}", SIGNATURE_PREFIX, hex::encode(mac(secret, body).finalize().into_bytes()))
+}
+
+/// Check `X-Signature` value of a received `body` in constant time
+pub fn verify_signature(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    signature
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(|signature| hex::decode(signature).ok())
+        .is_some_and(|signature| mac(secret, body).verify(&signature).is_ok())
+}
+
+fn mac(secret: &[u8], body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac
+}
+
+#[cfg(test)]
+mod webhook_test {
+    use solana_sdk::pubkey::Pubkey;
+    use crate::client::events::EventFields;
+    use crate::client::webhook::{sign, verify_signature, EventFilter, WebhookPayload};
+    use crate::event::{OrderPostedEvent, TokenEvent};
+
+    fn order_posted(sell_mint: Pubkey, maker: Pubkey, sell_amount: u64) -> TokenEvent {
+        TokenEvent::OrderPosted(OrderPostedEvent {
+            order: Pubkey::new_unique(),
+            maker,
+            sell_mint,
+            buy_mint: Pubkey::new_unique(),
+            sell_amount,
+            buy_amount: 100,
+        })
+    }
+
+    #[test]
+    fn when_every_criterion_met_expect_match() {
+        let (mint, maker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let fields = EventFields::new(&order_posted(mint, maker, 1_000));
+        let filter = EventFilter { mint: Some(mint), account: Some(maker), min_amount: Some(1_000) };
+
+        assert!(filter.matches(&fields));
+        assert!(EventFilter::default().matches(&fields));
+        assert!(!EventFilter { min_amount: Some(1_001), ..filter.clone() }.matches(&fields));
+        assert!(!EventFilter { mint: Some(Pubkey::new_unique()), ..filter.clone() }.matches(&fields));
+        assert!(!EventFilter { account: Some(Pubkey::new_unique()), ..filter }.matches(&fields));
+    }
+
+    #[test]
+    fn when_signature_of_body_expect_verified() {
+        let signature = sign(b"secret", b"{\"sequence\":1}");
+
+        assert!(signature.starts_with("sha256="));
+        assert!(verify_signature(b"secret", b"{\"sequence\":1}", &signature));
+        assert!(!verify_signature(b"secret", b"{\"sequence\":2}", &signature));
+        assert!(!verify_signature(b"other", b"{\"sequence\":1}", &signature));
+        assert!(!verify_signature(b"secret", b"{\"sequence\":1}", "sha256=zz"));
+    }
+
+    #[test]
+    fn when_payload_serialized_expect_fields_as_strings() {
+        let (mint, maker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let fields = EventFields::new(&order_posted(mint, maker, 1_000));
+
+        let payload = serde_json::to_value(WebhookPayload::new(&fields, Some(7), vec![1, 2, 3])).unwrap();
+
+        assert_eq!(payload["sequence"], 7);
+        assert_eq!(payload["event"], "order_posted");
+        assert_eq!(payload["mint"], mint.to_string());
+        assert_eq!(payload["accounts"][1], maker.to_string());
+        assert_eq!(payload["amount"], 1_000);
+        assert_eq!(payload["data"], "AQID");
+    }
+}
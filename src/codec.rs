@@ -0,0 +1,230 @@
+//! Instruction data fields decoded in place, byte compatible with Borsh.
+//!
+//! Integers, keys and hashes are read straight from the input without allocating, only vectors and
+//! strings allocate their contents. Tags of bools, options and enums other than the ones Borsh writes
+//! are rejected, so each instruction has exactly one encoding.
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use crate::error::TransferError;
+use crate::state::{FillMode, MilestoneTerms, RegistryMode};
+
+pub trait Field: Sized {
+    /// Decode from the front of `input`, advancing it past the field
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError>;
+
+    fn pack(&self, data: &mut Vec<u8>);
+}
+
+/// Next `N` bytes of `input`
+fn take<const N: usize>(input: &mut &[u8]) -> Result<[u8; N], ProgramError> {
+    if input.len() < N {
+        return Err(TransferError::InvalidInstructionData.into())
+    }
+    let (bytes, rest) = input.split_at(N);
+    *input = rest;
+    let mut array = [0; N];
+    array.copy_from_slice(bytes);
+    Ok(array)
+}
+
+/// Tag of a bool, option or fieldless enum, below `count`
+fn unpack_tag(input: &mut &[u8], count: u8) -> Result<u8, ProgramError> {
+    let [tag] = take::<1>(input)?;
+    if tag >= count {
+        return Err(TransferError::NonCanonicalInstructionData.into())
+    }
+    Ok(tag)
+}
+
+macro_rules! impl_int_field {
+    ($($int:ty),*) => {$(
+        impl Field for $int {
+            fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+                Ok(<$int>::from_le_bytes(take(input)?))
+            }
+
+            fn pack(&self, data: &mut Vec<u8>) {
+                data.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+impl_int_field!(u8, u16, u32, u64, i64);
+
+impl Field for bool {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        Ok(unpack_tag(input, 2)? == 1)
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        data.push(*self as u8);
+    }
+}
+
+impl Field for [u8; 32] {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        take(input)
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        data.extend_from_slice(self);
+    }
+}
+
+impl Field for Pubkey {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        Ok(Pubkey::new_from_array(take(input)?))
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        data.extend_from_slice(self.as_ref());
+    }
+}
+
+impl<T: Field> Field for Option<T> {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        match unpack_tag(input, 2)? {
+            0 => Ok(None),
+            _ => Ok(Some(T::unpack(input)?)),
+        }
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        match self {
+            None => data.push(0),
+            Some(value) => {
+                data.push(1);
+                value.pack(data);
+            }
+        }
+    }
+}
+
+impl<T: Field> Field for Vec<T> {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        let len = u32::unpack(input)? as usize;
+        // Every item takes at least a byte, a forged length can't reserve more than the input
+        let mut items = Vec::with_capacity(len.min(input.len()));
+        for _ in 0..len {
+            items.push(T::unpack(input)?);
+        }
+        Ok(items)
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        (self.len() as u32).pack(data);
+        for item in self {
+            item.pack(data);
+        }
+    }
+}
+
+impl Field for String {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        let len = u32::unpack(input)? as usize;
+        if input.len() < len {
+            return Err(TransferError::InvalidInstructionData.into())
+        }
+        let (bytes, rest) = input.split_at(len);
+        *input = rest;
+        let string = std::str::from_utf8(bytes).map_err(|_| TransferError::InvalidInstructionData)?;
+        Ok(string.to_string())
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        (self.len() as u32).pack(data);
+        data.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Field for RegistryMode {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        match unpack_tag(input, 2)? {
+            0 => Ok(RegistryMode::Allow),
+            _ => Ok(RegistryMode::Block),
+        }
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        data.push(*self as u8);
+    }
+}
+
+impl Field for FillMode {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        match unpack_tag(input, 3)? {
+            0 => Ok(FillMode::Partial),
+            1 => Ok(FillMode::FillOrKill),
+            _ => Ok(FillMode::ImmediateOrCancel),
+        }
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        data.push(*self as u8);
+    }
+}
+
+impl Field for MilestoneTerms {
+    fn unpack(input: &mut &[u8]) -> Result<Self, ProgramError> {
+        Ok(MilestoneTerms { amount: u64::unpack(input)?, deliverable_hash: <[u8; 32]>::unpack(input)? })
+    }
+
+    fn pack(&self, data: &mut Vec<u8>) {
+        self.amount.pack(data);
+        self.deliverable_hash.pack(data);
+    }
+}
+
+#[cfg(test)]
+mod codec_test {
+    use borsh::BorshSerialize;
+    use solana_program::program_error::ProgramError;
+    use solana_program::pubkey::Pubkey;
+    use crate::codec::Field;
+    use crate::error::TransferError;
+    use crate::state::{FillMode, MilestoneTerms, RegistryMode};
+
+    fn test_borsh_compatible<T: Field + BorshSerialize + PartialEq + std::fmt::Debug>(value: T) {
+        let mut data = vec![];
+        value.pack(&mut data);
+
+        assert_eq!(data, value.try_to_vec().unwrap());
+        let input = &mut &data[..];
+        assert_eq!(T::unpack(input), Ok(value));
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn when_pack_expect_borsh_encoding() {
+        test_borsh_compatible(u64::MAX - 1);
+        test_borsh_compatible(-7i64);
+        test_borsh_compatible(true);
+        test_borsh_compatible(Pubkey::new_unique());
+        test_borsh_compatible(Some([7u8; 32]));
+        test_borsh_compatible(None::<Pubkey>);
+        test_borsh_compatible(vec![1u32, 2, 3]);
+        test_borsh_compatible("memo ✓".to_string());
+        test_borsh_compatible(RegistryMode::Block);
+        test_borsh_compatible(FillMode::ImmediateOrCancel);
+        test_borsh_compatible(vec![MilestoneTerms { amount: 5, deliverable_hash: [9; 32] }]);
+    }
+
+    #[test]
+    fn when_tag_out_of_range_expect_non_canonical() {
+        let non_canonical = ProgramError::from(TransferError::NonCanonicalInstructionData);
+
+        assert_eq!(bool::unpack(&mut &[2][..]), Err(non_canonical.clone()));
+        assert_eq!(Option::<u8>::unpack(&mut &[2, 0][..]), Err(non_canonical.clone()));
+        assert_eq!(FillMode::unpack(&mut &[3][..]), Err(non_canonical));
+    }
+
+    #[test]
+    fn when_truncated_expect_invalid_data() {
+        let invalid_data = ProgramError::from(TransferError::InvalidInstructionData);
+
+        assert_eq!(u64::unpack(&mut &[1, 2, 3][..]), Err(invalid_data.clone()));
+        assert_eq!(Vec::<u64>::unpack(&mut &[u8::MAX, u8::MAX, u8::MAX, u8::MAX, 1][..]), Err(invalid_data.clone()));
+        assert_eq!(String::unpack(&mut &[1, 0, 0, 0, 0xff][..]), Err(invalid_data));
+    }
+}
@@ -0,0 +1,50 @@
+//! Instructions of the Compute Budget program, which sets the compute unit limit and price of a transaction.
+//!
+//! Instruction data is a one byte tag followed by the little endian argument. The SDK this crate is built
+//! with predates both instructions, so they are encoded here.
+use solana_program::instruction::Instruction;
+
+solana_program::declare_id!("ComputeBudget111111111111111111111111111111");
+
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+/// Max compute units of a transaction
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Compute units the transaction may consume
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    instruction(SET_COMPUTE_UNIT_LIMIT, &units.to_le_bytes())
+}
+
+/// Price of each compute unit of the transaction in micro-lamports, paid on top of the base fee
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    instruction(SET_COMPUTE_UNIT_PRICE, &micro_lamports.to_le_bytes())
+}
+
+fn instruction(tag: u8, arg: &[u8]) -> Instruction {
+    let mut data = vec![tag];
+    data.extend_from_slice(arg);
+    Instruction::new_with_bytes(id(), &data, vec![])
+}
+
+#[cfg(test)]
+mod compute_budget_test {
+    use crate::compute_budget::{id, set_compute_unit_limit, set_compute_unit_price};
+
+    #[test]
+    fn when_set_compute_unit_limit_expect_tag_and_units() {
+        let instr = set_compute_unit_limit(0x0102);
+
+        assert_eq!(instr.program_id, id());
+        assert!(instr.accounts.is_empty());
+        assert_eq!(instr.data, vec![2, 2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn when_set_compute_unit_price_expect_tag_and_price() {
+        let instr = set_compute_unit_price(0x0102);
+
+        assert_eq!(instr.program_id, id());
+        assert_eq!(instr.data, vec![3, 2, 1, 0, 0, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,89 @@
+//! Interface for programs receiving deposits through `TokenInstruction::DepositToProgram`.
+//!
+//! Instruction data is `RECEIVE_DEPOSIT_DISCRIMINATOR` followed by Borsh encoded `ReceiveDeposit`.
+//! Accounts:
+//! 0. [signer] - depositor, signature is forwarded by this program
+//! 1. [writable] - source, depositor wallet for lamports or SPL token account
+//! 2. [writable] - destination, account of the receiving program
+//! 3. [] - system program for lamports or SPL token program
+//! 4. [] - any number of extra accounts passed through unchanged
+//!
+//! The receiving program moves `amount` from source to destination itself, so a deposit
+//! is only credited once the funds actually arrive.
+use borsh::BorshSerialize;
+use borsh::BorshDeserialize;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+pub const RECEIVE_DEPOSIT_DISCRIMINATOR: [u8; 8] = *b"rcv_dpst";
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ReceiveDeposit {
+    pub amount: u64,
+}
+
+impl ReceiveDeposit {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = RECEIVE_DEPOSIT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        data
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        match input.strip_prefix(&RECEIVE_DEPOSIT_DISCRIMINATOR[..]) {
+            Some(rest) => Ok(Self::try_from_slice(rest)?),
+            None => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+pub fn receive_deposit(
+    receiver_program: Pubkey,
+    depositor: Pubkey,
+    source: Pubkey,
+    destination: Pubkey,
+    transfer_program: Pubkey,
+    extra_accounts: Vec<AccountMeta>,
+    amount: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(depositor, true),
+        AccountMeta::new(source, source == depositor),
+        AccountMeta::new(destination, false),
+        AccountMeta::new_readonly(transfer_program, false),
+    ];
+    accounts.extend(extra_accounts);
+    Instruction {
+        program_id: receiver_program,
+        accounts,
+        data: ReceiveDeposit { amount }.pack(),
+    }
+}
+
+#[cfg(test)]
+mod deposit_interface_test {
+    use solana_program::program_error::ProgramError;
+    use crate::deposit_interface::ReceiveDeposit;
+
+    const RECEIVE_DEPOSIT: ReceiveDeposit = ReceiveDeposit { amount: 1_000 };
+    const BINARY_RECEIVE_DEPOSIT: [u8; 16] = [114, 99, 118, 95, 100, 112, 115, 116, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    #[test]
+    fn when_pack_receive_deposit_expect_ok() {
+        assert_eq!(RECEIVE_DEPOSIT.pack(), BINARY_RECEIVE_DEPOSIT);
+    }
+
+    #[test]
+    fn when_unpack_receive_deposit_expect_ok() {
+        assert_eq!(ReceiveDeposit::unpack(&BINARY_RECEIVE_DEPOSIT).unwrap(), RECEIVE_DEPOSIT);
+    }
+
+    #[test]
+    fn when_unpack_wrong_discriminator_expect_error() {
+        let mut binary = BINARY_RECEIVE_DEPOSIT;
+        binary[0] = 0;
+
+        assert_eq!(ReceiveDeposit::unpack(&binary), Err(ProgramError::InvalidInstructionData));
+    }
+}
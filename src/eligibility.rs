@@ -0,0 +1,111 @@
+//! Attestor signed eligibility proofs for gated distribution claims.
+//!
+//! Attestor signs `message(distributor, claimer, amount)` off-chain. The claim transaction carries
+//! the signature in an ed25519 program instruction right before the claim, the runtime verifies it
+//! and the claim checks through the instructions sysvar that it covers the expected key and message.
+use solana_program::account_info::AccountInfo;
+use solana_program::ed25519_program;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use crate::error::TransferError;
+
+const MESSAGE_PREFIX: &[u8] = b"claim";
+const SIGNATURE_OFFSETS_START: usize = 2;
+const DATA_START: usize = SIGNATURE_OFFSETS_START + 14;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+/// Offsets referring to data of the ed25519 instruction itself
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Message signed by the attestor
+pub fn message(distributor: &Pubkey, claimer: &Pubkey, amount: u64) -> Vec<u8> {
+    [MESSAGE_PREFIX, distributor.as_ref(), claimer.as_ref(), &amount.to_le_bytes()].concat()
+}
+
+/// Ed25519 program instruction carrying `signature` of `attestor` over `message`, placed right before the claim
+pub fn ed25519_instruction(attestor: &Pubkey, signature: &[u8; SIGNATURE_LEN], message: &[u8]) -> Instruction {
+    let pubkey_offset = DATA_START;
+    let signature_offset = pubkey_offset + PUBKEY_LEN;
+    let message_offset = signature_offset + SIGNATURE_LEN;
+    let offsets = [
+        signature_offset as u16,
+        CURRENT_INSTRUCTION,
+        pubkey_offset as u16,
+        CURRENT_INSTRUCTION,
+        message_offset as u16,
+        message.len() as u16,
+        CURRENT_INSTRUCTION,
+    ];
+    let mut data = vec![1, 0];
+    data.extend(offsets.iter().flat_map(|offset| offset.to_le_bytes()));
+    data.extend_from_slice(attestor.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction::new_with_bytes(ed25519_program::id(), &data, vec![])
+}
+
+/// Instruction preceding the current one must verify a single signature of `attestor` over `message`
+pub fn verify(instructions_acc: &AccountInfo, attestor: &Pubkey, message: &[u8]) -> ProgramResult {
+    let current_index = load_current_index_checked(instructions_acc)?;
+    let ed25519_index = current_index.checked_sub(1).ok_or(TransferError::InvalidAttestation)?;
+    let ed25519_instr = load_instruction_at_checked(ed25519_index as usize, instructions_acc)?;
+    if ed25519_instr.program_id != ed25519_program::id() {
+        return Err(TransferError::InvalidAttestation.into())
+    }
+    match signed(&ed25519_instr.data) {
+        Some((pubkey, signed_message)) if pubkey == attestor.as_ref() && signed_message == message => Ok(()),
+        _ => Err(TransferError::InvalidAttestation.into()),
+    }
+}
+
+/// Public key and message of ed25519 instruction data with a single signature
+fn signed(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    if data.first() != Some(&1) {
+        return None
+    }
+    let offsets = data.get(SIGNATURE_OFFSETS_START..)?;
+    let field = |index: usize| offsets.get(index * 2..index * 2 + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let (signature_instr, pubkey_offset, pubkey_instr) = (field(1)?, field(2)? as usize, field(3)?);
+    let (message_offset, message_size, message_instr) = (field(4)? as usize, field(5)? as usize, field(6)?);
+    if [signature_instr, pubkey_instr, message_instr].iter().any(|index| *index != CURRENT_INSTRUCTION) {
+        return None
+    }
+    let pubkey = data.get(pubkey_offset..pubkey_offset + PUBKEY_LEN)?;
+    let message = data.get(message_offset..message_offset + message_size)?;
+    Some((pubkey, message))
+}
+
+#[cfg(test)]
+mod eligibility_test {
+    use solana_program::pubkey::Pubkey;
+    use crate::eligibility::{ed25519_instruction, message, signed, CURRENT_INSTRUCTION};
+
+    #[test]
+    fn when_ed25519_instruction_expect_pubkey_and_message() {
+        let (attestor, distributor, claimer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let message = message(&distributor, &claimer, 500);
+
+        let instr = ed25519_instruction(&attestor, &[3; 64], &message);
+
+        assert_eq!(signed(&instr.data), Some((attestor.as_ref(), &message[..])));
+    }
+
+    #[test]
+    fn when_offsets_refer_other_instruction_expect_none() {
+        let mut data = ed25519_instruction(&Pubkey::new_unique(), &[3; 64], b"claim").data;
+        assert_eq!(data[4..6], CURRENT_INSTRUCTION.to_le_bytes());
+
+        data[4..6].copy_from_slice(&0u16.to_le_bytes());
+
+        assert_eq!(signed(&data), None);
+    }
+
+    #[test]
+    fn when_truncated_expect_none() {
+        let data = ed25519_instruction(&Pubkey::new_unique(), &[3; 64], b"claim").data;
+
+        assert_eq!(signed(&data[..40]), None);
+    }
+}
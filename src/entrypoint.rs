@@ -1,15 +1,22 @@
 use solana_program::account_info::AccountInfo;
-use solana_program::entrypoint;
 use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::PrintProgramError;
 use solana_program::pubkey::Pubkey;
+use crate::error::TransferError;
 use crate::processor::Processor;
 
-entrypoint!(process_instruction);
+// Processing stays available to program-test hosts of `no-entrypoint` builds
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     input: &[u8],
 ) -> ProgramResult {
-    Processor::process(program_id, accounts, input)
+    if let Err(error) = Processor::process(program_id, accounts, input) {
+        error.print::<TransferError>();
+        return Err(error)
+    }
+    Ok(())
 }
@@ -1,14 +1,234 @@
-use solana_program::program_error::ProgramError;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::decode_error::DecodeError;
+use solana_program::msg;
+use solana_program::program_error::{PrintProgramError, ProgramError};
 use thiserror::Error;
 
-#[derive(Error, Debug, Clone)]
+/// Program custom errors, codes are variant indices so new variants are only appended
+#[derive(Error, Debug, Clone, Copy, PartialEq, FromPrimitive)]
 pub enum TransferError {
     #[error("Account is non writable")]
-    AccountNonWritable
+    AccountNonWritable,
+    #[error("Account does not match the one recorded in state")]
+    AccountMismatch,
+    #[error("Amount overflow")]
+    AmountOverflow,
+    #[error("Slash amount exceeds bond")]
+    InsufficientBond,
+    #[error("Netting period has ended")]
+    NettingPeriodEnded,
+    #[error("Netting period has not ended")]
+    NettingPeriodNotEnded,
+    #[error("Settlement must be signed by the net debtor")]
+    SettlementSignerNotDebtor,
+    #[error("Token account mint does not match")]
+    MintMismatch,
+    #[error("Token account is neither associated with nor owned by the wallet")]
+    TokenAccountOwnerMismatch,
+    #[error("Strict mode only permits transfers to associated token accounts")]
+    DestinationNotAssociated,
+    #[error("Signer is not the config admin")]
+    NotConfigAdmin,
+    #[error("Instruction family is disabled by config")]
+    FeatureDisabled,
+    #[error("Instruction is not supported by this program version")]
+    UnsupportedInstruction,
+    #[error("Merkle proof does not match committed root")]
+    InvalidMerkleProof,
+    #[error("Payout has already been executed")]
+    PayoutAlreadyExecuted,
+    #[error("Eligibility proof of the attestor is missing or does not match the claim")]
+    InvalidAttestation,
+    #[error("Destination wallet has no valid attestation of the required level")]
+    AttestationRequired,
+    #[error("Transfer above travel rule threshold requires travel rule data hash")]
+    TravelRuleDataRequired,
+    #[error("Signer is not the mint authority")]
+    NotMintAuthority,
+    #[error("Soulbound mint is not transferable")]
+    SoulboundMint,
+    #[error("Mint is not a non-fungible token")]
+    NotNft,
+    #[error("NFT is already rented")]
+    RentalUnavailable,
+    #[error("Rental has not expired")]
+    RentalNotExpired,
+    #[error("Share mint must be empty, mintable only by the fraction and without freeze authority")]
+    InvalidShareMint,
+    #[error("Fill amount exceeds remaining order amount")]
+    OrderAmountExceeded,
+    #[error("Fill-or-kill order must be filled completely")]
+    FillOrKill,
+    #[error("Price feed is older than allowed")]
+    PriceStale,
+    #[error("Price is outside of the requested band")]
+    PriceOutOfBand,
+    #[error("Keeper is banned")]
+    KeeperBanned,
+    #[error("Signer is not delegate of token account")]
+    NotDelegate,
+    #[error("Escrow amount differs from the expected one")]
+    EscrowAmountMismatch,
+    #[error("Escrow has expired")]
+    EscrowExpired,
+    #[error("Escrow has not expired")]
+    EscrowNotExpired,
+    #[error("Signer is not close authority of token account")]
+    NotCloseAuthority,
+    #[error("Token account has non-zero balance")]
+    TokenAccountNotEmpty,
+    #[error("Canary lamports are not returned")]
+    SelfTestFailed,
+    #[error("Fee basis points exceed 100%")]
+    FeeTooHigh,
+    #[error("Signer is not fee config admin")]
+    NotFeeConfigAdmin,
+    #[error("Transaction contains another instruction touching guarded accounts")]
+    UnexpectedInstruction,
+    #[error("Program is paused by config admin")]
+    ProgramPaused,
+    #[error("Transaction landed after its deadline slot")]
+    DeadlineExceeded,
+    #[error("Vesting cliff must be between start and end")]
+    InvalidVestingSchedule,
+    #[error("Nothing vested to claim")]
+    NothingVested,
+    #[error("Wallet reached sponsored account limit of pool")]
+    SponsorshipLimitReached,
+    #[error("Sponsor pool has not enough lamports left")]
+    SponsorPoolExhausted,
+    #[error("Nothing streamed to withdraw")]
+    NothingStreamed,
+    #[error("Account is not the SPL token program")]
+    InvalidTokenProgram,
+    #[error("Account is not owned by the expected program")]
+    InvalidOwner,
+    #[error("Insufficient funds")]
+    InsufficientFunds,
+    #[error("Invalid instruction data")]
+    InvalidInstructionData,
+    #[error("Delegate is revoked")]
+    DelegateRevoked,
+    #[error("Source token account is not owned by the signer")]
+    SourceOwnerMismatch,
+    #[error("Recipient is not registered on the roster of the payer")]
+    RecipientNotRegistered,
+    #[error("Contract needs 1 to 8 milestones of nonzero amount")]
+    InvalidMilestones,
+    #[error("Deliverable of milestone is not submitted")]
+    MilestoneNotSubmitted,
+    #[error("Milestone is already released")]
+    MilestoneAlreadyReleased,
+    #[error("Contract has unreleased milestones")]
+    ContractNotCompleted,
+    #[error("Unsupported version of instruction data")]
+    UnsupportedInstructionVersion,
+    #[error("Account balance would fall below rent exemption")]
+    BelowRentExemption,
+    #[error("Lamports are not conserved")]
+    LamportsNotConserved,
+    #[error("Source balance is empty, nothing to sweep")]
+    NothingToSweep,
+    #[error("Instruction data has bytes after the last field")]
+    TrailingInstructionData,
+    #[error("Instruction data field exceeds its maximum length")]
+    InstructionFieldTooLong,
+    #[error("Instruction data is not in canonical encoding")]
+    NonCanonicalInstructionData,
+    #[error("Signer is not the freeze authority")]
+    NotFreezeAuthority,
+    #[error("Decimals don't match the mint")]
+    DecimalsMismatch,
+    #[error("Recipient is not permitted by registry")]
+    RecipientNotPermitted,
+    #[error("Registry is full")]
+    RegistryFull,
+    #[error("Signer is not the registry admin")]
+    NotRegistryAdmin,
+    #[error("Signer is not the migration admin")]
+    NotMigrationAdmin,
+    #[error("Transfer exceeds spending limit of the window")]
+    SpendingLimitExceeded,
+    #[error("Transfer above approval threshold must be proposed")]
+    ApprovalRequired,
+    #[error("Signer is not the approver of the transfer")]
+    NotApprover,
+    #[error("Leaf of the distribution is already claimed")]
+    LeafAlreadyClaimed,
+    #[error("Signer is not the admin of the managed mint")]
+    NotIssuanceAdmin,
+    #[error("Issuer lacks the role")]
+    IssuerRoleMissing,
+    #[error("Issue exceeds allowance of the issuer")]
+    IssuerAllowanceExceeded,
+    #[error("Issue exceeds supply cap of the managed mint")]
+    SupplyCapExceeded,
+    #[error("No instruction earlier in the transaction matches the guard")]
+    GuardNotSatisfied,
+    #[error("Transfer requires a guard instruction right before it")]
+    GuardRequired,
+    #[error("Claim deadline of the dividend has passed")]
+    DividendClaimPeriodEnded,
+    #[error("Claim deadline of the dividend has not passed")]
+    DividendClaimPeriodNotEnded,
+    #[error("Authority has a transfer hook, its hook program must be passed")]
+    TransferHookRequired,
+    #[error("Donor is the recipient of the matching pool")]
+    SelfDonation,
+    #[error("Receiving program is not among deposit programs of program config")]
+    DepositProgramNotAllowed,
+    #[error("Deposit programs of program config are full")]
+    DepositProgramsFull,
+    #[error("Destination balance did not grow by the deposited amount")]
+    DepositNotReceived,
+    #[error("Transfer up to approval threshold must be made directly")]
+    ApprovalNotRequired,
+}
+
+impl TransferError {
+    /// Variant of `ProgramError::Custom` code, `None` for codes of other programs or newer versions
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::from_u32(code)
+    }
 }
 
 impl From<TransferError> for ProgramError {
     fn from(e: TransferError) -> Self {
         ProgramError::Custom(e as u32)
     }
-}
\ No newline at end of file
+}
+impl<T> DecodeError<T> for TransferError {
+    fn type_of() -> &'static str {
+        "TransferError"
+    }
+}
+
+impl PrintProgramError for TransferError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        msg!("Error: {}", self);
+    }
+}
+
+#[cfg(test)]
+mod error_test {
+    use solana_program::program_error::ProgramError;
+    use crate::error::TransferError;
+
+    #[test]
+    fn when_custom_code_expect_variant() {
+        let code = TransferError::InsufficientFunds as u32;
+
+        assert_eq!(ProgramError::from(TransferError::InsufficientFunds), ProgramError::Custom(code));
+        assert_eq!(TransferError::from_code(code), Some(TransferError::InsufficientFunds));
+    }
+
+    #[test]
+    fn when_unknown_code_expect_none() {
+        assert_eq!(TransferError::from_code(u32::MAX), None);
+        assert_eq!(TransferError::from_code(0), Some(TransferError::AccountNonWritable));
+    }
+}
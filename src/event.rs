@@ -0,0 +1,851 @@
+use std::convert::TryFrom;
+use borsh::BorshSerialize;
+use borsh::BorshDeserialize;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::log::sol_log_data;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+/// Structured program event, logged as Borsh encoded `Program data:` entry
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub enum TokenEvent {
+    BondPosted(BondPostedEvent),
+    BondRefunded(BondRefundedEvent),
+    BondSlashed(BondSlashedEvent),
+    Tip(TipEvent),
+    MatchingPoolCreated(MatchingPoolCreatedEvent),
+    DonationMatched(DonationMatchedEvent),
+    MatchingPoolClosed(MatchingPoolClosedEvent),
+    RoundUpSaved(RoundUpSavedEvent),
+    SavingsWithdrawn(SavingsWithdrawnEvent),
+    NettingOpened(NettingOpenedEvent),
+    ObligationRegistered(ObligationRegisteredEvent),
+    NettingSettled(NettingSettledEvent),
+    DepositToProgram(DepositToProgramEvent),
+    UnsupportedInstruction(UnsupportedInstructionEvent),
+    PayoutRootPublished(PayoutRootPublishedEvent),
+    PayoutExecuted(PayoutExecutedEvent),
+    DistributorCreated(DistributorCreatedEvent),
+    DistributionClaimed(DistributionClaimedEvent),
+    AttestationSet(AttestationSetEvent),
+    TravelRuleData(TravelRuleDataEvent),
+    MintConfigSet(MintConfigSetEvent),
+    RentalListed(RentalListedEvent),
+    NftRented(NftRentedEvent),
+    RentalReturned(RentalReturnedEvent),
+    NftFractionalized(NftFractionalizedEvent),
+    NftRedeemed(NftRedeemedEvent),
+    OrderPosted(OrderPostedEvent),
+    OrderFilled(OrderFilledEvent),
+    OrderPartiallyFilled(OrderFilledEvent),
+    OrderCancelled(OrderCancelledEvent),
+    PriceUpdated(PriceUpdatedEvent),
+    KeeperRegistered(KeeperRegisteredEvent),
+    KeeperBanSet(KeeperBanSetEvent),
+    VaultInitialized(VaultInitializedEvent),
+    VaultDeposited(VaultDepositedEvent),
+    VaultWithdrawn(VaultWithdrawnEvent),
+    EscrowInitialized(EscrowInitializedEvent),
+    EscrowExchanged(EscrowExchangedEvent),
+    EscrowCancelled(EscrowCancelledEvent),
+    SelfTestPassed(SelfTestPassedEvent),
+    VestingCreated(VestingCreatedEvent),
+    VestingClaimed(VestingClaimedEvent),
+    VestingCancelled(VestingCancelledEvent),
+    SponsorPoolCreated(SponsorPoolCreatedEvent),
+    SponsorPoolFunded(SponsorPoolFundedEvent),
+    AccountSponsored(AccountSponsoredEvent),
+    SponsorPoolClosed(SponsorPoolClosedEvent),
+    StreamCreated(StreamCreatedEvent),
+    StreamWithdrawn(StreamWithdrawnEvent),
+    StreamClosed(StreamClosedEvent),
+    SplTokenParked(SplTokenParkedEvent),
+    BalanceClaimed(BalanceClaimedEvent),
+    DelegateRevoked(DelegateRevokedEvent),
+    DelegateReinstated(DelegateReinstatedEvent),
+    RecipientRegistered(RecipientRegisteredEvent),
+    RecipientRemoved(RecipientRemovedEvent),
+    TransferLamports(TransferLamportsEvent),
+    TransferSpl(TransferSplEvent),
+    Approve(ApproveEvent),
+    ContractCreated(ContractCreatedEvent),
+    DeliverableSubmitted(DeliverableSubmittedEvent),
+    MilestoneReleased(MilestoneReleasedEvent),
+    ContractClosed(ContractClosedEvent),
+    SplTokenAccountFrozen(SplTokenAccountFrozenEvent),
+    SplTokenAccountThawed(SplTokenAccountThawedEvent),
+    ErrorContext(ErrorContextEvent),
+    MerkleDistributionCreated(MerkleDistributionCreatedEvent),
+    MerkleDistributionClaimed(MerkleDistributionClaimedEvent),
+    DepositSchemeInitialized(DepositSchemeInitializedEvent),
+    DepositSwept(DepositSweptEvent),
+    ManagedMintInitialized(ManagedMintInitializedEvent),
+    IssuerSet(IssuerSetEvent),
+    Issued(IssuedEvent),
+    Redeemed(RedeemedEvent),
+    DividendCreated(DividendCreatedEvent),
+    DividendClaimed(DividendClaimedEvent),
+    DividendClawedBack(DividendClawedBackEvent),
+}
+
+impl TokenEvent {
+    /// Log event stamped with event stream and `sequence` within it, see `EventRecord`
+    pub fn emit(self, sequence: Option<(Pubkey, u64)>) -> ProgramResult {
+        let (stream, sequence) = (sequence.map(|(stream, _)| stream), sequence.map(|(_, sequence)| sequence));
+        sol_log_data(&[&EventRecord { sequence, stream, event: self }.try_to_vec()?]);
+        Ok(())
+    }
+}
+
+/// Logged form of an event. Sequence is taken from the event stream the instruction carries and increases by one
+/// with every event of the stream, so a consumer of the stream can detect missed events; both are None when
+/// the instruction carries no event stream, see `TokenInstruction::with_event_stream`.
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct EventRecord {
+    pub sequence: Option<u64>,
+    /// Address of the event stream
+    pub stream: Option<Pubkey>,
+    pub event: TokenEvent,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct BondPostedEvent {
+    pub bond: Pubkey,
+    pub depositor: Pubkey,
+    pub arbiter: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct BondRefundedEvent {
+    pub bond: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub reason: u16,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct BondSlashedEvent {
+    pub bond: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub reason: u16,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct TipEvent {
+    pub creator: Pubkey,
+    pub tipper: Pubkey,
+    pub amount: u64,
+    pub tipper_total: u64,
+    pub creator_total: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MatchingPoolCreatedEvent {
+    pub pool: Pubkey,
+    pub sponsor: Pubkey,
+    pub recipient: Pubkey,
+    pub ratio_bps: u16,
+    pub cap: u64,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DonationMatchedEvent {
+    pub pool: Pubkey,
+    pub donor: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub matched: u64,
+    pub remaining: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MatchingPoolClosedEvent {
+    pub pool: Pubkey,
+    pub sponsor: Pubkey,
+    pub returned: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RoundUpSavedEvent {
+    pub owner: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub saved: u64,
+    pub saved_total: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SavingsWithdrawnEvent {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub saved_total: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct NettingOpenedEvent {
+    pub netting: Pubkey,
+    pub party_a: Pubkey,
+    pub party_b: Pubkey,
+    pub period_end: i64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ObligationRegisteredEvent {
+    pub netting: Pubkey,
+    pub debtor: Pubkey,
+    pub creditor: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct NettingSettledEvent {
+    pub netting: Pubkey,
+    pub debtor: Pubkey,
+    pub creditor: Pubkey,
+    pub a_owes_b: u64,
+    pub b_owes_a: u64,
+    pub net_amount: u64,
+    pub obligation_count: u32,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DepositToProgramEvent {
+    pub depositor: Pubkey,
+    pub program: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct UnsupportedInstructionEvent {
+    pub tag: u8,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct PayoutRootPublishedEvent {
+    pub payout_batch: Pubkey,
+    pub authority: Pubkey,
+    pub id: u64,
+    pub root: [u8; 32],
+    pub leaf_count: u32,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct PayoutExecutedEvent {
+    pub payout_batch: Pubkey,
+    pub index: u32,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DistributorCreatedEvent {
+    pub distributor: Pubkey,
+    pub authority: Pubkey,
+    pub attestor: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DistributionClaimedEvent {
+    pub distributor: Pubkey,
+    pub claimer: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MerkleDistributionCreatedEvent {
+    pub distribution: Pubkey,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub leaf_count: u32,
+    pub total_amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MerkleDistributionClaimedEvent {
+    pub distribution: Pubkey,
+    pub claimer: Pubkey,
+    pub index: u32,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DepositSchemeInitializedEvent {
+    pub scheme: Pubkey,
+    pub authority: Pubkey,
+    pub omnibus: Pubkey,
+}
+
+/// Whole balance of deposit address of `user_id` moved to omnibus, native mint for lamports
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DepositSweptEvent {
+    pub scheme: Pubkey,
+    pub deposit: Pubkey,
+    pub user_id: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ManagedMintInitializedEvent {
+    pub managed_mint: Pubkey,
+    pub mint: Pubkey,
+    pub admin: Pubkey,
+    pub supply_cap: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct IssuerSetEvent {
+    pub managed_mint: Pubkey,
+    pub issuer: Pubkey,
+    pub admin: Pubkey,
+    pub roles: u8,
+    pub allowance: u64,
+}
+
+/// `supply` of the mint after the issue
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct IssuedEvent {
+    pub mint: Pubkey,
+    pub issuer: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub supply: u64,
+}
+
+/// `supply` of the mint after the redemption
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RedeemedEvent {
+    pub mint: Pubkey,
+    pub redeemer: Pubkey,
+    pub from: Pubkey,
+    pub amount: u64,
+    pub supply: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DividendCreatedEvent {
+    pub dividend: Pubkey,
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub snapshot_mint: Pubkey,
+    pub holder_count: u32,
+    pub snapshot_supply: u64,
+    pub pool_amount: u64,
+    pub claim_deadline: i64,
+}
+
+/// `amount` is the share of snapshot `balance`
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DividendClaimedEvent {
+    pub dividend: Pubkey,
+    pub holder: Pubkey,
+    pub index: u32,
+    pub balance: u64,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DividendClawedBackEvent {
+    pub dividend: Pubkey,
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct AttestationSetEvent {
+    pub wallet: Pubkey,
+    pub level: u8,
+    pub expires_at: i64,
+}
+
+/// Transfer carrying hash of off-chain travel rule data, e.g. originator and beneficiary details
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct TravelRuleDataEvent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub data_hash: [u8; 32],
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MintConfigSetEvent {
+    pub mint: Pubkey,
+    pub issuer: Pubkey,
+    pub soulbound: bool,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RentalListedEvent {
+    pub rental: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub duration: i64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct NftRentedEvent {
+    pub rental: Pubkey,
+    pub renter: Pubkey,
+    pub price: u64,
+    pub expires_at: i64,
+}
+
+/// NFT returned to owner after rental expiry or cancelled listing, renter is default for the latter
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RentalReturnedEvent {
+    pub rental: Pubkey,
+    pub owner: Pubkey,
+    pub renter: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct NftFractionalizedEvent {
+    pub fraction: Pubkey,
+    pub depositor: Pubkey,
+    pub nft_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub shares: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct NftRedeemedEvent {
+    pub fraction: Pubkey,
+    pub redeemer: Pubkey,
+    pub nft_mint: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct OrderPostedEvent {
+    pub order: Pubkey,
+    pub maker: Pubkey,
+    pub sell_mint: Pubkey,
+    pub buy_mint: Pubkey,
+    pub sell_amount: u64,
+    pub buy_amount: u64,
+}
+
+/// Emitted as `OrderFilled` by the fill completing the order, `OrderPartiallyFilled` otherwise
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct OrderFilledEvent {
+    pub order: Pubkey,
+    pub taker: Pubkey,
+    /// Sell side released to taker
+    pub amount: u64,
+    /// Buy side paid to maker
+    pub paid: u64,
+    pub remaining: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct OrderCancelledEvent {
+    pub order: Pubkey,
+    pub maker: Pubkey,
+    pub refunded: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct PriceUpdatedEvent {
+    pub mint: Pubkey,
+    pub price: u64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct KeeperRegisteredEvent {
+    pub keeper: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct KeeperBanSetEvent {
+    pub keeper: Pubkey,
+    pub banned: bool,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VaultInitializedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VaultDepositedEvent {
+    pub vault: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VaultWithdrawnEvent {
+    pub vault: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct EscrowInitializedEvent {
+    pub escrow: Pubkey,
+    pub initializer: Pubkey,
+    pub amount: u64,
+    pub expected_amount: u64,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct EscrowExchangedEvent {
+    pub escrow: Pubkey,
+    pub initializer: Pubkey,
+    pub taker: Pubkey,
+    pub amount: u64,
+    pub expected_amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct EscrowCancelledEvent {
+    pub escrow: Pubkey,
+    pub initializer: Pubkey,
+    pub refunded: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SelfTestPassedEvent {
+    pub caller: Pubkey,
+    pub slot: u64,
+    /// Features of program config, defaults while config is uninitialized
+    pub features: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VestingCreatedEvent {
+    pub vesting: Pubkey,
+    pub creator: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VestingClaimedEvent {
+    pub vesting: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    /// Claimed over the whole schedule, `amount` included
+    pub claimed_amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VestingCancelledEvent {
+    pub vesting: Pubkey,
+    pub creator: Pubkey,
+    /// Vested and unclaimed, released to beneficiary
+    pub released: u64,
+    /// Unvested rest returned to creator
+    pub refunded: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SponsorPoolCreatedEvent {
+    pub pool: Pubkey,
+    pub sponsor: Pubkey,
+    pub operator: Pubkey,
+    pub max_per_wallet: u16,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SponsorPoolFundedEvent {
+    pub pool: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct AccountSponsoredEvent {
+    pub pool: Pubkey,
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    /// Lamports paid by the pool, rent of sponsored wallet record included on its first account
+    pub rent: u64,
+    pub remaining: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SponsorPoolClosedEvent {
+    pub pool: Pubkey,
+    pub sponsor: Pubkey,
+    pub returned: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct StreamCreatedEvent {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    /// Default pubkey for lamports
+    pub mint: Pubkey,
+    pub deposit: u64,
+    pub rate_per_second: u64,
+    pub start_ts: i64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct StreamWithdrawnEvent {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    /// Withdrawn over the whole stream, `amount` included
+    pub withdrawn: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct StreamClosedEvent {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    /// Streamed and not withdrawn, paid to recipient
+    pub paid: u64,
+    /// Unstreamed rest returned to sender
+    pub refunded: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SplTokenParkedEvent {
+    pub claimable: Pubkey,
+    pub from: Pubkey,
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Parked for the wallet in total, `amount` included
+    pub total: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct BalanceClaimedEvent {
+    pub claimable: Pubkey,
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DelegateRevokedEvent {
+    pub revocation: Pubkey,
+    pub scope: Pubkey,
+    pub delegate: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DelegateReinstatedEvent {
+    pub revocation: Pubkey,
+    pub scope: Pubkey,
+    pub delegate: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RecipientRegisteredEvent {
+    pub roster_entry: Pubkey,
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RecipientRemovedEvent {
+    pub roster_entry: Pubkey,
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct TransferLamportsEvent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    /// Fee included
+    pub amount: u64,
+    pub fee: u64,
+    /// Payment reference of `TransferLamportsWithMemo`
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct TransferSplEvent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Fee included
+    pub amount: u64,
+    pub fee: u64,
+    /// Payment reference of `TransferSplTokenWithMemo`
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ApproveEvent {
+    pub owner: Pubkey,
+    pub source: Pubkey,
+    pub delegate: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ContractCreatedEvent {
+    pub contract: Pubkey,
+    pub funder: Pubkey,
+    pub payee: Pubkey,
+    pub arbiter: Pubkey,
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub milestone_count: u8,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DeliverableSubmittedEvent {
+    pub contract: Pubkey,
+    pub payee: Pubkey,
+    pub index: u8,
+    pub deliverable_hash: [u8; 32],
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MilestoneReleasedEvent {
+    pub contract: Pubkey,
+    /// Funder or arbiter
+    pub authority: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+    pub released_total: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ContractClosedEvent {
+    pub contract: Pubkey,
+    pub closed_by: Pubkey,
+    /// Unreleased tranches returned to funder
+    pub refunded: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SplTokenAccountFrozenEvent {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub freeze_authority: Pubkey,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SplTokenAccountThawedEvent {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub freeze_authority: Pubkey,
+}
+
+/// Value an instruction expected and the one it got, see `ErrorContextEvent`
+#[derive(Debug, Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq)]
+pub enum ErrorValue {
+    Key(Pubkey),
+    Amount(u64),
+}
+
+/// Unsequenced, logged right before an instruction returns the error, so a failed transaction is diagnosed
+/// from its events instead of messages of this program or SPL token
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ErrorContextEvent {
+    /// Code of the returned `ProgramError`, as reported by the runtime
+    pub code: u64,
+    /// Index of the offending account among accounts of the instruction
+    pub account_index: Option<u8>,
+    pub expected: Option<ErrorValue>,
+    pub actual: Option<ErrorValue>,
+}
+
+impl ErrorContextEvent {
+    /// Log context of `error` and return the error, e.g. `return Err(ErrorContextEvent::fail(..))`
+    #[cold]
+    pub fn fail<E: Into<ProgramError>>(
+        error: E,
+        account_index: Option<u8>,
+        expected: Option<ErrorValue>,
+        actual: Option<ErrorValue>,
+    ) -> ProgramError {
+        let error = error.into();
+        let event = ErrorContextEvent { code: u64::from(error.clone()), account_index, expected, actual };
+        TokenEvent::ErrorContext(event).emit(None).err().unwrap_or(error)
+    }
+
+    /// Index of `acc` among `accounts` of the instruction, the same `AccountInfo` rather than the same key
+    pub fn account_index<'a>(accounts: &[AccountInfo<'a>], acc: &AccountInfo<'a>) -> Option<u8> {
+        let index = accounts.iter().position(|instr_acc| std::ptr::eq(instr_acc, acc))?;
+        u8::try_from(index).ok()
+    }
+}
+
+#[cfg(test)]
+mod event_test {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::account_info::AccountInfo;
+    use solana_program::program_error::ProgramError;
+    use solana_program::pubkey::Pubkey;
+    use crate::error::TransferError;
+    use crate::event::{ErrorContextEvent, ErrorValue, TokenEvent};
+
+    #[test]
+    fn when_fail_expect_error_returned() {
+        let (expected, actual) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let error = ErrorContextEvent::fail(
+            TransferError::MintMismatch,
+            Some(2),
+            Some(ErrorValue::Key(expected)),
+            Some(ErrorValue::Key(actual)),
+        );
+
+        assert_eq!(error, TransferError::MintMismatch.into());
+    }
+
+    #[test]
+    fn when_account_index_expect_position_of_same_account_info() {
+        let (key, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = ([0; 3], [vec![], vec![], vec![]]);
+        let accounts: Vec<AccountInfo> = lamports.iter_mut().zip(data.iter_mut())
+            .map(|(lamports, data)| AccountInfo::new(&key, false, false, lamports, data, &owner, false, 0))
+            .collect();
+        let copy = accounts[1].clone();
+
+        assert_eq!(ErrorContextEvent::account_index(&accounts, &accounts[1]), Some(1));
+        assert_eq!(ErrorContextEvent::account_index(&accounts[1..], &accounts[2]), Some(1));
+        assert_eq!(ErrorContextEvent::account_index(&accounts, &copy), None);
+    }
+
+    #[test]
+    fn when_error_context_serialized_expect_code_of_program_error() {
+        let event = ErrorContextEvent {
+            code: u64::from(ProgramError::MissingRequiredSignature),
+            account_index: Some(0),
+            expected: None,
+            actual: None,
+        };
+
+        let data = TokenEvent::ErrorContext(event).try_to_vec().unwrap();
+
+        let event = match TokenEvent::try_from_slice(&data).unwrap() {
+            TokenEvent::ErrorContext(event) => event,
+            event => panic!("Unexpected event {:?}", event),
+        };
+        assert_eq!(ProgramError::from(event.code), ProgramError::MissingRequiredSignature);
+    }
+}
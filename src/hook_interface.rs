@@ -0,0 +1,93 @@
+//! Interface for programs registered as transfer hooks with `TokenInstruction::SetTransferHook`.
+//!
+//! Hook is invoked before and after the transfer, returning an error vetoes the whole transfer.
+//! Instruction data is `TRANSFER_HOOK_DISCRIMINATOR` followed by Borsh encoded `TransferHookData`.
+//! Accounts, all read-only and never signers:
+//! 0. [] - authority of the transfer
+//! 1. [] - source account
+//! 2. [] - destination account
+use borsh::BorshSerialize;
+use borsh::BorshDeserialize;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+pub const TRANSFER_HOOK_DISCRIMINATOR: [u8; 8] = *b"xfr_hook";
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy)]
+pub enum TransferHookStage {
+    PreTransfer,
+    PostTransfer,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct TransferHookData {
+    pub stage: TransferHookStage,
+    pub amount: u64,
+}
+
+impl TransferHookData {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = TRANSFER_HOOK_DISCRIMINATOR.to_vec();
+        data.push(self.stage as u8);
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        data
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        match input.strip_prefix(&TRANSFER_HOOK_DISCRIMINATOR[..]) {
+            Some(rest) => Ok(Self::try_from_slice(rest)?),
+            None => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+pub fn transfer_hook(
+    hook_program: Pubkey,
+    authority: Pubkey,
+    source: Pubkey,
+    destination: Pubkey,
+    stage: TransferHookStage,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: hook_program,
+        accounts: vec![
+            AccountMeta::new_readonly(authority, false),
+            AccountMeta::new_readonly(source, false),
+            AccountMeta::new_readonly(destination, false),
+        ],
+        data: TransferHookData { stage, amount }.pack(),
+    }
+}
+
+#[cfg(test)]
+mod hook_interface_test {
+    use crate::hook_interface::{TransferHookData, TransferHookStage};
+
+    const PRE_TRANSFER: TransferHookData = TransferHookData { stage: TransferHookStage::PreTransfer, amount: 1_000 };
+    const BINARY_PRE_TRANSFER: [u8; 17] = [120, 102, 114, 95, 104, 111, 111, 107, 0, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const POST_TRANSFER: TransferHookData = TransferHookData { stage: TransferHookStage::PostTransfer, amount: 1_000 };
+    const BINARY_POST_TRANSFER: [u8; 17] = [120, 102, 114, 95, 104, 111, 111, 107, 1, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    #[test]
+    fn when_pack_pre_transfer_expect_ok() {
+        assert_eq!(PRE_TRANSFER.pack(), BINARY_PRE_TRANSFER);
+    }
+
+    #[test]
+    fn when_unpack_pre_transfer_expect_ok() {
+        assert_eq!(TransferHookData::unpack(&BINARY_PRE_TRANSFER).unwrap(), PRE_TRANSFER);
+    }
+
+    #[test]
+    fn when_pack_post_transfer_expect_ok() {
+        assert_eq!(POST_TRANSFER.pack(), BINARY_POST_TRANSFER);
+    }
+
+    #[test]
+    fn when_unpack_post_transfer_expect_ok() {
+        assert_eq!(TransferHookData::unpack(&BINARY_POST_TRANSFER).unwrap(), POST_TRANSFER);
+    }
+}
@@ -1,91 +1,2461 @@
+#[cfg(feature = "instruction-borsh")]
 use borsh::BorshSerialize;
+#[cfg(feature = "instruction-borsh")]
 use borsh::BorshDeserialize;
+use solana_program::hash::hashv;
 use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::message::Message;
+use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
-use solana_program::system_program;
+use solana_program::{bpf_loader_upgradeable, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use token_derive::{InstructionBuilder, InstructionCodec};
+use crate::compute_budget;
+use crate::error::TransferError;
 use crate::id;
+use crate::deposit_interface;
+use crate::state::{
+    ApprovalPolicy, Attestation, AuditLog, Bond, Canary, ClaimableBalance, ClaimBitmap, Config, DepositScheme,
+    DistributionClaim, Distributor, Dividend, Escrow, EventStream, FeeConfig, FillMode, Fraction, Issuer, Keeper,
+    ManagedMint, MatchingPool, MerkleDistribution, Migration, MilestoneContract, MilestoneTerms, MintConfig,
+    MintDecimals, Netting, Order, PayoutBatch, PendingTransfer, PriceFeed, Registry, RegistryMode, Rental, Revocation,
+    RosterEntry, SavingsVault, SpendingLimit, SponsorPool, SponsoredWallet, Stream, TipStats, TipperStats,
+    TransferHook, Vault, VestingSchedule,
+};
 
-#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// Encoded on-chain by `InstructionCodec`, Borsh derives with the same layout are kept for clients
+#[derive(Debug, PartialEq, InstructionBuilder, InstructionCodec)]
+#[cfg_attr(feature = "instruction-borsh", derive(BorshSerialize, BorshDeserialize))]
 pub enum TokenInstruction {
-    /// Transfer native Solana token
-    /// 0. [signer, writable] - from
-    /// 1. [writable] - to
-    /// 2. [] - system program process transfer
+    /// Transfer native Solana token, `from` owned by this program is debited without System program
+    /// and must stay rent exempt. First version of the instruction, kept with its accounts for existing clients:
+    /// transfer policies, recipient registry and pause don't apply, see `TransferLamportsV2`
+    #[account(signer, writable, name = "from")]
+    #[account(writable, name = "to")]
+    #[account(name = "system_program", desc = "system program process transfer", address = "system_program::id()")]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "fee recipient of fee config")]
     TransferLamports { amount: u64 },
 
-    /// Transfer custom token
-    /// 0. [signer] - from user account, authority
-    /// 1. [writable] - from SPL token account, PDA
-    /// 2. [writable] - to SPL token account, PDA
-    /// 3. [] - SPL token program
+    /// Transfer custom token. First version of the instruction, kept with its accounts for existing clients:
+    /// pause, strict ATA, travel rule and soulbound mints apply, transfer policies and recipient registry don't,
+    /// see `TransferSplTokenV2`
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account, PDA")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account, PDA")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "token account owned by fee recipient")]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
     TransferSplToken { amount: u64 },
 
     /// Approve custom token
-    /// 0. [signer] - from user account, authority
-    /// 1. [writable] - from SPL token account, PDA
-    /// 2. [writable] - to SPL token account, PDA
-    /// 3. [] - SPL token program
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account, PDA")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account, PDA")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
     ApproveSplToken { amount: u64 },
+
+    /// Post refundable bond, lamports are held by the bond account
+    #[account(signer, writable, name = "depositor")]
+    #[account(name = "arbiter", desc = "arbiter, decides refund or slash")]
+    #[account(name = "beneficiary", desc = "beneficiary of slashed lamports")]
+    #[account(writable, name = "bond", desc = "bond, PDA", address = "Bond::find_address(&depositor, id).0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    PostBond { id: u64, amount: u64 },
+
+    /// Refund remaining bond lamports to depositor and close bond account
+    #[builder(args = "id: u64")]
+    #[account(signer, name = "arbiter")]
+    #[account(writable, name = "depositor")]
+    #[account(writable, name = "bond", desc = "bond, PDA", address = "Bond::find_address(&depositor, id).0")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    RefundBond { reason: u16 },
+
+    /// Slash part of bond to beneficiary
+    #[builder(args = "depositor: Pubkey, id: u64")]
+    #[account(signer, name = "arbiter")]
+    #[account(writable, name = "beneficiary")]
+    #[account(writable, name = "bond", desc = "bond, PDA", address = "Bond::find_address(&depositor, id).0")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    SlashBond { amount: u64, reason: u16 },
+
+    /// Tip creator with lamports and update tip leaderboard
+    #[account(signer, writable, name = "tipper")]
+    #[account(writable, name = "creator")]
+    #[account(
+        writable, name = "tip_stats", desc = "creator tip stats, PDA",
+        address = "TipStats::find_address(&creator).0",
+    )]
+    #[account(
+        writable, name = "tipper_stats", desc = "tipper stats for creator, PDA",
+        address = "TipperStats::find_address(&creator, &tipper).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    Tip { amount: u64 },
+
+    /// Create matching pool for donations to recipient, funded by sponsor
+    #[account(signer, writable, name = "sponsor")]
+    #[account(name = "recipient")]
+    #[account(
+        writable, name = "pool", desc = "matching pool, PDA",
+        address = "MatchingPool::find_address(&sponsor, &recipient).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CreateMatchingPool { ratio_bps: u16, cap: u64, amount: u64 },
+
+    /// Donate lamports to recipient, pool adds matched amount while funds last. Recipient can't donate to itself
+    #[builder(args = "sponsor: Pubkey")]
+    #[account(signer, writable, name = "donor")]
+    #[account(writable, name = "recipient")]
+    #[account(
+        writable, name = "pool", desc = "matching pool, PDA",
+        address = "MatchingPool::find_address(&sponsor, &recipient).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    Donate { amount: u64 },
+
+    /// Close matching pool and return unmatched lamports to sponsor
+    #[builder(args = "recipient: Pubkey")]
+    #[account(signer, writable, name = "sponsor")]
+    #[account(
+        writable, name = "pool", desc = "matching pool, PDA",
+        address = "MatchingPool::find_address(&sponsor, &recipient).0",
+    )]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CloseMatchingPool,
+
+    /// Opt in to round-up savings or change granularity, 0 disables round-up
+    #[account(signer, writable, name = "owner")]
+    #[account(
+        writable, name = "savings_vault", desc = "owner savings vault, PDA",
+        address = "SavingsVault::find_address(&owner).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ConfigureRoundUp { granularity: u64 },
+
+    /// Transfer native Solana token, rounding the debited amount up to the
+    /// configured granularity and saving the difference
+    #[builder(policy = "from")]
+    #[account(signer, writable, name = "from")]
+    #[account(writable, name = "to")]
+    #[account(
+        writable, name = "savings_vault", desc = "from savings vault, PDA",
+        address = "SavingsVault::find_address(&from).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    TransferLamportsRoundUp { amount: u64 },
+
+    /// Withdraw saved lamports back to owner
+    #[account(signer, writable, name = "owner")]
+    #[account(
+        writable, name = "savings_vault", desc = "owner savings vault, PDA",
+        address = "SavingsVault::find_address(&owner).0",
+    )]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    WithdrawSavings { amount: u64 },
+
+    /// Open netting of mutual obligations until period end, both parties agree
+    #[account(signer, writable, name = "party_a", desc = "party A, pays rent")]
+    #[account(signer, name = "party_b", desc = "party B")]
+    #[account(
+        writable, name = "netting", desc = "netting, PDA",
+        address = "Netting::find_address(&party_a, &party_b, id).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    OpenNetting { id: u64, period_end: i64 },
+
+    /// Register obligation of debtor to creditor before period end
+    #[account(signer, name = "debtor")]
+    #[account(name = "creditor")]
+    #[account(writable, name = "netting", desc = "netting, PDA")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    RegisterObligation { amount: u64 },
+
+    /// Settle netting after period end with single net transfer, close netting to party A
+    #[account(signer, writable, name = "party", desc = "net debtor, any party if obligations are equal")]
+    #[account(writable, name = "counterparty")]
+    #[account(writable, name = "netting", desc = "netting, PDA")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    SettleNetting,
+
+    /// Deposit into another program implementing `deposit_interface`, depositor signature is forwarded with the CPI.
+    /// Receiving program must be among deposit programs of program config, see `SetDepositProgram`, and the
+    /// destination balance must grow by exactly `amount`
+    #[builder(skip)]
+    #[account(signer, writable, name = "depositor")]
+    #[account(writable, name = "source", desc = "source, depositor for lamports or SPL token account")]
+    #[account(writable, name = "destination", desc = "destination, account of receiving program")]
+    #[account(name = "transfer_program", desc = "system program or SPL token program")]
+    #[account(name = "receiver_program", desc = "receiving program")]
+    #[account(name = "config", desc = "program config, PDA, may be uninitialized")]
+    #[account(
+        optional, name = "extra_accounts",
+        desc = "any number of extra accounts forwarded to receiving program",
+    )]
+    DepositToProgram { amount: u64 },
+
+    /// Register hook program called around every transfer signed by owner, see `transfer_policy_accounts`,
+    /// None removes hook
+    #[account(signer, writable, name = "owner")]
+    #[account(
+        writable, name = "transfer_hook", desc = "transfer hook config, PDA",
+        address = "TransferHook::find_address(&owner).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    SetTransferHook { hook_program: Option<Pubkey> },
+
+    /// Transfer custom token to wallet, destination may be its associated or any owned token account
+    #[builder(policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(name = "to_wallet")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account, associated with or owned by wallet")]
+    #[account(name = "mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenToWallet { amount: u64 },
+
+    /// Create or update program config, created by the upgrade authority as admin, see `with_program_data`
+    #[account(signer, writable, name = "admin")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetConfig { strict_ata: bool },
+
+    /// Enable instruction families by `Config::FEATURE_*` bits, only by program config admin, see `SetConfig`
+    #[account(signer, writable, name = "admin")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetFeatures { features: u64 },
+
+    /// Commit Merkle root of an off-chain payout list before executing it, see `merkle`
+    #[account(signer, writable, name = "authority")]
+    #[account(
+        writable, name = "payout_batch", desc = "payout batch, PDA",
+        address = "PayoutBatch::find_address(&authority, id).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    PublishPayoutRoot { id: u64, root: [u8; 32], leaf_count: u32 },
+
+    /// Pay leaf `index` of a committed payout list, proof ties the transfer to the published root
+    #[builder(args = "id: u64")]
+    #[account(signer, writable, name = "authority")]
+    #[account(writable, name = "recipient")]
+    #[account(
+        writable, name = "payout_batch", desc = "payout batch, PDA",
+        address = "PayoutBatch::find_address(&authority, id).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ExecutePayout { index: u32, amount: u64, proof: Vec<[u8; 32]> },
+
+    /// Create distributor funded with `amount`, claims require an eligibility proof of `attestor`
+    #[account(signer, writable, name = "authority")]
+    #[account(
+        writable, name = "distributor", desc = "distributor, PDA",
+        address = "Distributor::find_address(&authority, id).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CreateDistributor { id: u64, attestor: Pubkey, amount: u64 },
+
+    /// Claim `amount` from distributor once, preceded by ed25519 instruction with attestor signature
+    /// over `eligibility::message`
+    #[account(signer, writable, name = "claimer")]
+    #[account(writable, name = "distributor", desc = "distributor, PDA")]
+    #[account(
+        writable, name = "distribution_claim", desc = "claim receipt, PDA",
+        address = "DistributionClaim::find_address(&distributor, &claimer).0",
+    )]
+    #[account(name = "instructions", desc = "instructions sysvar", address = "sysvar::instructions::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ClaimDistribution { amount: u64 },
+
+    /// Grant, update or revoke (level 0) attestation of `wallet`, only by config admin
+    #[account(signer, writable, name = "admin")]
+    #[account(
+        writable, name = "attestation", desc = "attestation of wallet, PDA",
+        address = "Attestation::find_address(&wallet).0",
+    )]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetAttestation { wallet: Pubkey, level: u8, expires_at: i64 },
+
+    /// Transfer custom token to wallet holding unexpired attestation of at least `min_level`
+    #[builder(policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(name = "to_wallet")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account, associated with or owned by wallet")]
+    #[account(name = "mint")]
+    #[account(
+        name = "attestation", desc = "attestation of to wallet, PDA",
+        address = "Attestation::find_address(&to_wallet).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenAttested { amount: u64, min_level: u8 },
+
+    /// Transfer custom token with optional hash of off-chain travel rule data, recorded in event.
+    /// The hash is mandatory above `Config::travel_rule_threshold` when travel rule is required
+    #[builder(args = "mint: Pubkey", policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "token account owned by fee recipient")]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenWithTravelRule { amount: u64, data_hash: Option<[u8; 32]> },
+
+    /// Require travel rule data hash on custom token transfers above `threshold`, only by config admin
+    #[account(signer, writable, name = "admin")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetTravelRule { required: bool, threshold: u64 },
+
+    /// Register or update config of `mint`, only by its mint authority. Soulbound mints are not
+    /// transferable through this program
+    #[account(signer, writable, name = "issuer", desc = "mint authority")]
+    #[account(name = "mint")]
+    #[account(writable, name = "mint_config", desc = "mint config, PDA", address = "MintConfig::find_address(&mint).0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    SetMintConfig { soulbound: bool },
+
+    /// List NFT for rent, it moves into escrow token account of the rental until returned
+    #[account(signer, writable, name = "owner")]
+    #[account(writable, name = "owner_token", desc = "owner SPL token account holding the NFT")]
+    #[account(name = "mint", desc = "NFT mint")]
+    #[account(writable, name = "rental", desc = "rental, PDA", address = "Rental::find_address(&mint).0")]
+    #[account(
+        writable, name = "escrow_token", desc = "associated token account of rental, created beforehand",
+        address = "get_associated_token_address(&rental, &mint)",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ListRental { price: u64, duration: i64 },
+
+    /// Rent listed NFT for its duration, price is paid to owner
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, writable, name = "renter")]
+    #[account(writable, name = "owner")]
+    #[account(writable, name = "rental", desc = "rental, PDA", address = "Rental::find_address(&mint).0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    RentNft,
+
+    /// Return NFT to owner and close rental once rental expired, callable by anyone
+    #[builder(args = "mint: Pubkey")]
+    #[account(writable, name = "owner")]
+    #[account(writable, name = "owner_token", desc = "owner SPL token account of the NFT mint")]
+    #[account(writable, name = "rental", desc = "rental, PDA", address = "Rental::find_address(&mint).0")]
+    #[account(
+        writable, name = "escrow_token", desc = "associated token account of rental",
+        address = "get_associated_token_address(&rental, &mint)",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(optional, signer, name = "keeper", desc = "registered keeper executing the crank")]
+    #[account(optional, writable, name = "keeper_registry", desc = "keeper registry, PDA")]
+    ReturnRental,
+
+    /// Return NFT to owner and close rental while not rented
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, writable, name = "owner")]
+    #[account(writable, name = "owner_token", desc = "owner SPL token account of the NFT mint")]
+    #[account(writable, name = "rental", desc = "rental, PDA", address = "Rental::find_address(&mint).0")]
+    #[account(
+        writable, name = "escrow_token", desc = "associated token account of rental",
+        address = "get_associated_token_address(&rental, &mint)",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CancelRental,
+
+    /// Lock NFT in fraction vault and mint `shares` share tokens to depositor. Share mint is created
+    /// beforehand with zero supply, fraction as mint authority and no freeze authority
+    #[account(signer, writable, name = "depositor")]
+    #[account(writable, name = "depositor_nft_token", desc = "depositor SPL token account holding the NFT")]
+    #[account(name = "nft_mint")]
+    #[account(writable, name = "fraction", desc = "fraction, PDA", address = "Fraction::find_address(&nft_mint).0")]
+    #[account(
+        writable, name = "vault_token", desc = "associated token account of fraction, created beforehand",
+        address = "get_associated_token_address(&fraction, &nft_mint)",
+    )]
+    #[account(writable, name = "share_mint")]
+    #[account(writable, name = "depositor_share_token", desc = "depositor SPL token account of share mint")]
+    #[account(
+        name = "mint_config", desc = "NFT mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&nft_mint).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    FractionalizeNft { shares: u64 },
+
+    /// Burn all share tokens to take the NFT out of fraction vault, vault and fraction close to depositor
+    #[account(signer, name = "redeemer", desc = "holder of all shares")]
+    #[account(writable, name = "redeemer_share_token", desc = "redeemer SPL token account of share mint")]
+    #[account(writable, name = "share_mint")]
+    #[account(writable, name = "redeemer_nft_token", desc = "redeemer SPL token account of NFT mint")]
+    #[account(name = "nft_mint")]
+    #[account(writable, name = "depositor", desc = "receives fraction and vault rent")]
+    #[account(writable, name = "fraction", desc = "fraction, PDA", address = "Fraction::find_address(&nft_mint).0")]
+    #[account(
+        writable, name = "vault_token", desc = "associated token account of fraction",
+        address = "get_associated_token_address(&fraction, &nft_mint)",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    RedeemNft,
+
+    /// Post limit order selling `sell_amount` for `buy_amount`, sell side moves into order escrow.
+    /// `fill_mode` constrains fills, see `FillMode`. Neither mint may be soulbound
+    #[account(signer, writable, name = "maker")]
+    #[account(writable, name = "maker_sell_token", desc = "maker SPL token account of sell mint")]
+    #[account(name = "sell_mint")]
+    #[account(name = "buy_mint")]
+    #[account(writable, name = "order", desc = "order, PDA", address = "Order::find_address(&maker, id).0")]
+    #[account(
+        writable, name = "escrow_token", desc = "associated token account of order for sell mint, created beforehand",
+        address = "get_associated_token_address(&order, &sell_mint)",
+    )]
+    #[account(
+        name = "mint_config", desc = "sell mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&sell_mint).0",
+    )]
+    #[account(
+        name = "buy_mint_config", desc = "buy mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&buy_mint).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    PostOrder { id: u64, sell_amount: u64, buy_amount: u64, fill_mode: FillMode },
+
+    /// Fill `amount` of order sell side, taker pays pro-rata buy side to maker in the same instruction.
+    /// Fully filled order closes to maker, as does immediate-or-cancel order with the rest refunded
+    #[builder(args = "id: u64, sell_mint: Pubkey")]
+    #[account(signer, name = "taker")]
+    #[account(writable, name = "taker_buy_token", desc = "taker SPL token account of buy mint, pays maker")]
+    #[account(writable, name = "taker_sell_token", desc = "taker SPL token account of sell mint, receives fill")]
+    #[account(writable, name = "maker")]
+    #[account(writable, name = "maker_buy_token", desc = "maker SPL token account of buy mint")]
+    #[account(writable, name = "maker_sell_token", desc = "maker SPL token account of sell mint, refund")]
+    #[account(writable, name = "order", desc = "order, PDA", address = "Order::find_address(&maker, id).0")]
+    #[account(
+        writable, name = "escrow_token", desc = "associated token account of order for sell mint",
+        address = "get_associated_token_address(&order, &sell_mint)",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(optional, name = "instructions", desc = "instructions sysvar, see `with_sandwich_guard`")]
+    FillOrder { amount: u64 },
+
+    /// Cancel order, unfilled escrow is refunded and order closes to maker
+    #[builder(args = "id: u64, sell_mint: Pubkey")]
+    #[account(signer, writable, name = "maker")]
+    #[account(writable, name = "maker_sell_token", desc = "maker SPL token account of sell mint")]
+    #[account(writable, name = "order", desc = "order, PDA", address = "Order::find_address(&maker, id).0")]
+    #[account(
+        writable, name = "escrow_token", desc = "associated token account of order for sell mint",
+        address = "get_associated_token_address(&order, &sell_mint)",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CancelOrder,
+
+    /// Publish price of `mint`, only by config admin
+    #[account(signer, writable, name = "admin")]
+    #[account(name = "mint")]
+    #[account(writable, name = "price_feed", desc = "price feed, PDA", address = "PriceFeed::find_address(&mint).0")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetPrice { price: u64 },
+
+    /// Transfer custom token only while mint price is within `min_price..=max_price` and
+    /// was updated at most `max_age` seconds ago. The only transfer not guarded by `GuardPrecedingInstruction`:
+    /// its optional instructions sysvar is the sandwich guard, which already rejects the transaction when any
+    /// other of its instructions touches the transfer or price feed accounts. `with_guard` appends the sysvar
+    /// as the sandwich guard too, see `with_sandwich_guard`
+    #[builder(policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account")]
+    #[account(name = "mint")]
+    #[account(name = "price_feed", desc = "price feed of mint, PDA", address = "PriceFeed::find_address(&mint).0")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(optional, name = "instructions", desc = "instructions sysvar, see `with_sandwich_guard`")]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenPriceProtected { amount: u64, min_price: u64, max_price: u64, max_age: i64 },
+
+    /// Register keeper executing crank instructions, tracks its executions and bounties
+    #[account(signer, writable, name = "keeper")]
+    #[account(
+        writable, name = "keeper_registry", desc = "keeper registry, PDA",
+        address = "Keeper::find_address(&keeper).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    RegisterKeeper,
+
+    /// Ban or unban keeper, only by config admin
+    #[builder(args = "keeper: Pubkey")]
+    #[account(signer, writable, name = "admin")]
+    #[account(
+        writable, name = "keeper_registry", desc = "keeper registry, PDA",
+        address = "Keeper::find_address(&keeper).0",
+    )]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetKeeperBan { banned: bool },
+
+    /// Transfer custom token approved by `ApproveSplToken`, signed by the delegate instead of the owner.
+    /// Fails while the delegate is revoked by the owner or the config admin, see `RevokeDelegate`
+    #[builder(args = "owner: Pubkey, mint: Pubkey", policy = "delegate", policy_mint = "mint")]
+    #[account(signer, name = "delegate", desc = "delegate of from SPL token account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(
+        name = "global_revocation", desc = "revocation of delegate by config admin, PDA, may be uninitialized",
+        address = "Revocation::find_address(&Pubkey::default(), &delegate).0",
+    )]
+    #[account(
+        name = "owner_revocation", desc = "revocation of delegate by token account owner, PDA, may be uninitialized",
+        address = "Revocation::find_address(&owner, &delegate).0",
+    )]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig delegate, see `with_multisig`")]
+    TransferSplTokenAsDelegate { amount: u64 },
+
+    /// Transfer custom token with `transfer_checked`, SPL token program verifies mint and decimals.
+    /// Decimals are checked upfront against the mint or its `MintDecimals` cache, see `with_mint_decimals`
+    #[builder(policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account")]
+    #[account(name = "mint", desc = "mint of both SPL token accounts")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenChecked { amount: u64, decimals: u8 },
+
+    /// Transfer native Solana token to several recipients, all or none
+    #[builder(skip)]
+    #[account(signer, writable, name = "from")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(optional, writable, name = "recipients", desc = "recipient of each amount, in order")]
+    TransferLamportsBatch { amounts: Vec<u64> },
+
+    /// Transfer custom token to several token accounts of the source mint, all or none
+    #[builder(skip)]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "mint_config", desc = "mint config, PDA, may be uninitialized")]
+    #[account(optional, writable, name = "to_spl_tokens", desc = "to SPL token account of each amount, in order")]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenBatch { amounts: Vec<u64> },
+
+    /// Initialize vault of `mint` owned by `owner`, its associated token account holds the deposits
+    #[account(signer, writable, name = "owner")]
+    #[account(name = "mint")]
+    #[account(writable, name = "vault", desc = "vault, PDA", address = "Vault::find_address(&owner, &mint).0")]
+    #[account(
+        name = "vault_token", desc = "associated token account of vault, created beforehand",
+        address = "get_associated_token_address(&vault, &mint)",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    InitializeVault,
+
+    /// Deposit custom token into vault, anyone may deposit
+    #[builder(args = "owner: Pubkey, mint: Pubkey")]
+    #[account(signer, name = "depositor")]
+    #[account(writable, name = "depositor_token", desc = "depositor SPL token account of vault mint")]
+    #[account(name = "vault", desc = "vault, PDA", address = "Vault::find_address(&owner, &mint).0")]
+    #[account(
+        writable, name = "vault_token", desc = "associated token account of vault",
+        address = "get_associated_token_address(&vault, &mint)",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    DepositToVault { amount: u64 },
+
+    /// Withdraw custom token from vault, signed by vault owner and transferred by the vault PDA
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, name = "owner")]
+    #[account(name = "vault", desc = "vault, PDA", address = "Vault::find_address(&owner, &mint).0")]
+    #[account(
+        writable, name = "vault_token", desc = "associated token account of vault",
+        address = "get_associated_token_address(&vault, &mint)",
+    )]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account of vault mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    WithdrawFromVault { amount: u64 },
+
+    /// Offer whole balance of temp token account for `expected_amount` paid into receive token account,
+    /// temp token account is handed over to the escrow PDA. Exchange is accepted until `expires_at`.
+    /// Neither mint may be soulbound
+    #[builder(args = "offer_mint: Pubkey, receive_mint: Pubkey")]
+    #[account(signer, writable, name = "initializer")]
+    #[account(writable, name = "temp_token", desc = "initializer SPL token account holding the offer")]
+    #[account(name = "receive_token", desc = "initializer SPL token account receiving the payment")]
+    #[account(writable, name = "escrow", desc = "escrow, PDA", address = "Escrow::find_address(&temp_token).0")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(
+        name = "offer_mint_config", desc = "offer mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&offer_mint).0",
+    )]
+    #[account(
+        name = "receive_mint_config", desc = "payment mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&receive_mint).0",
+    )]
+    InitializeEscrow { expected_amount: u64, expires_at: i64 },
+
+    /// Pay expected amount of escrow and receive its offer of `amount`, both legs in one instruction.
+    /// Escrow and temp token account close to initializer
+    #[account(signer, name = "taker")]
+    #[account(writable, name = "taker_send_token", desc = "taker SPL token account paying expected amount")]
+    #[account(writable, name = "taker_receive_token", desc = "taker SPL token account receiving the offer")]
+    #[account(writable, name = "initializer")]
+    #[account(writable, name = "receive_token", desc = "initializer SPL token account receiving the payment")]
+    #[account(writable, name = "temp_token", desc = "temp token account of escrow")]
+    #[account(writable, name = "escrow", desc = "escrow, PDA", address = "Escrow::find_address(&temp_token).0")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(optional, name = "instructions", desc = "instructions sysvar, see `with_sandwich_guard`")]
+    ExchangeEscrow { amount: u64 },
+
+    /// Refund offer of expired escrow, escrow and temp token account close to initializer
+    #[account(signer, writable, name = "initializer")]
+    #[account(writable, name = "initializer_token", desc = "initializer SPL token account receiving the refund")]
+    #[account(writable, name = "temp_token", desc = "temp token account of escrow")]
+    #[account(writable, name = "escrow", desc = "escrow, PDA", address = "Escrow::find_address(&temp_token).0")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CancelEscrow,
+
+    /// Mint custom token to token account, signed by mint authority
+    #[account(signer, name = "authority", desc = "mint authority")]
+    #[account(writable, name = "mint")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account of mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    MintSplToken { amount: u64 },
+
+    /// Burn custom token from token account, signed by its owner
+    #[account(signer, name = "owner", desc = "owner of from SPL token account")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account of mint")]
+    #[account(writable, name = "mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    BurnSplToken { amount: u64 },
+
+    /// Close empty token account, signed by its close authority or owner, reclaimed rent goes to destination
+    #[account(signer, name = "authority", desc = "close authority, owner when not set")]
+    #[account(writable, name = "spl_token_account", desc = "SPL token account to close")]
+    #[account(writable, name = "destination", desc = "receiver of reclaimed lamports")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    CloseSplTokenAccount,
+
+    /// Round trip of canary lamports from caller to canary and back, emits health event for uptime monitoring
+    #[account(signer, writable, name = "caller", desc = "payer of canary lamports")]
+    #[account(writable, name = "canary", desc = "canary, PDA", address = "Canary::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    SelfTest,
+
+    /// Transfer custom token to associated token account of wallet, created when it does not exist yet
+    #[builder(policy = "from", policy_mint = "mint")]
+    #[account(signer, writable, name = "from", desc = "from user account, authority, payer of created account")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(name = "to_wallet")]
+    #[account(
+        writable, name = "to_spl_token", desc = "associated token account of wallet, may be uninitialized",
+        address = "get_associated_token_address(&to_wallet, &mint)",
+    )]
+    #[account(name = "mint")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "associated_token_program", desc = "SPL associated token account program",
+        address = "spl_associated_token_account::id()",
+    )]
+    #[account(name = "rent", desc = "rent sysvar", address = "sysvar::rent::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    TransferSplTokenWithAtaCreation { amount: u64 },
+
+    /// Move lamports into wrapped SOL associated token account of owner, created when it does not exist yet
+    #[account(signer, writable, name = "owner", desc = "owner of wrapped SOL, payer of created account")]
+    #[account(
+        writable, name = "wsol_token", desc = "wrapped SOL associated token account of owner, may be uninitialized",
+        address = "get_associated_token_address(&owner, &spl_token::native_mint::id())",
+    )]
+    #[account(name = "native_mint", desc = "wrapped SOL mint", address = "spl_token::native_mint::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "associated_token_program", desc = "SPL associated token account program",
+        address = "spl_associated_token_account::id()",
+    )]
+    #[account(name = "rent", desc = "rent sysvar", address = "sysvar::rent::id()")]
+    WrapSol { amount: u64 },
+
+    /// Close wrapped SOL associated token account of owner, whole balance returns to owner as lamports
+    #[account(signer, writable, name = "owner", desc = "owner of wrapped SOL")]
+    #[account(
+        writable, name = "wsol_token", desc = "wrapped SOL associated token account of owner",
+        address = "get_associated_token_address(&owner, &spl_token::native_mint::id())",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    UnwrapSol,
+
+    /// Create protocol fee config taking `fee_bps` of transfers which pass it, only by program config admin,
+    /// who becomes fee config admin
+    #[account(signer, writable, name = "admin", desc = "program config admin")]
+    #[account(writable, name = "fee_config", desc = "fee config, PDA", address = "FeeConfig::find_address().0")]
+    #[account(name = "fee_recipient", desc = "receives lamport fees, owns token accounts receiving token fees")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    InitializeFeeConfig { fee_bps: u16 },
+
+    /// Change fee and fee recipient of protocol fee config, only by fee config admin
+    #[account(signer, name = "admin", desc = "fee config admin")]
+    #[account(writable, name = "fee_config", desc = "fee config, PDA", address = "FeeConfig::find_address().0")]
+    #[account(name = "fee_recipient", desc = "new fee recipient")]
+    UpdateFeeConfig { fee_bps: u16 },
+
+    /// Halt or resume transfer instructions in an emergency, only by config admin, along with vault, escrow,
+    /// stream, payout and other instructions moving value, which all take program config. Run in a namespace, see
+    /// `in_namespace`, halts or resumes transfers of the namespace only, by namespace config admin
+    #[account(signer, writable, name = "admin")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetPaused { paused: bool },
+
+    /// Fail once the current slot is past `valid_until_slot`. Prepended by clients so a delayed or
+    /// resubmitted transaction can not land long after its intended window
+    CheckDeadline { valid_until_slot: u64 },
+
+    /// Split native Solana token among recipients, equally while `weights` is empty, otherwise by
+    /// a weight for each recipient. Shares round down, the remainder goes to the remainder account
+    #[builder(skip)]
+    #[account(signer, writable, name = "from")]
+    #[account(writable, name = "remainder", desc = "receives the indivisible remainder")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(optional, writable, name = "recipients", desc = "recipient of each share, in order")]
+    SplitLamports { amount: u64, weights: Vec<u32> },
+
+    /// Lock `total_amount` of custom token for beneficiary, released linearly from `start_ts` to `end_ts`
+    /// and nothing before `cliff_ts`. Associated token account of the schedule holds the locked tokens
+    #[account(signer, writable, name = "creator")]
+    #[account(writable, name = "creator_token", desc = "creator SPL token account of mint")]
+    #[account(name = "beneficiary")]
+    #[account(name = "mint")]
+    #[account(
+        writable, name = "vesting", desc = "vesting schedule, PDA",
+        address = "VestingSchedule::find_address(&creator, &beneficiary, &mint).0",
+    )]
+    #[account(
+        writable, name = "vesting_token", desc = "associated token account of vesting schedule, created beforehand",
+        address = "get_associated_token_address(&vesting, &mint)",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CreateVesting { total_amount: u64, start_ts: i64, cliff_ts: i64, end_ts: i64 },
+
+    /// Claim vested and not yet claimed tokens of vesting schedule, signed by beneficiary
+    #[builder(args = "creator: Pubkey, mint: Pubkey")]
+    #[account(signer, name = "beneficiary")]
+    #[account(
+        writable, name = "vesting", desc = "vesting schedule, PDA",
+        address = "VestingSchedule::find_address(&creator, &beneficiary, &mint).0",
+    )]
+    #[account(
+        writable, name = "vesting_token", desc = "associated token account of vesting schedule",
+        address = "get_associated_token_address(&vesting, &mint)",
+    )]
+    #[account(writable, name = "beneficiary_token", desc = "beneficiary SPL token account of mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ClaimVested,
+
+    /// Cancel vesting schedule, signed by creator. Vested and unclaimed tokens go to beneficiary, the rest
+    /// back to creator. Schedule and its token account close to creator
+    #[builder(args = "beneficiary: Pubkey, mint: Pubkey")]
+    #[account(signer, writable, name = "creator")]
+    #[account(
+        writable, name = "vesting", desc = "vesting schedule, PDA",
+        address = "VestingSchedule::find_address(&creator, &beneficiary, &mint).0",
+    )]
+    #[account(
+        writable, name = "vesting_token", desc = "associated token account of vesting schedule",
+        address = "get_associated_token_address(&vesting, &mint)",
+    )]
+    #[account(writable, name = "creator_token", desc = "creator SPL token account receiving the refund")]
+    #[account(writable, name = "beneficiary_token", desc = "beneficiary SPL token account of mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CancelVesting,
+
+    /// Create pool of sponsor paying rent of associated token accounts created by operator for wallets,
+    /// at most `max_per_wallet` accounts for each wallet. Funded by sponsor with `amount`
+    #[account(signer, writable, name = "sponsor")]
+    #[account(name = "operator", desc = "relayer creating sponsored accounts, may be sponsor")]
+    #[account(writable, name = "pool", desc = "sponsor pool, PDA", address = "SponsorPool::find_address(&sponsor).0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CreateSponsorPool { max_per_wallet: u16, amount: u64 },
+
+    /// Top up sponsor pool, anyone may fund
+    #[builder(args = "sponsor: Pubkey")]
+    #[account(signer, writable, name = "funder")]
+    #[account(writable, name = "pool", desc = "sponsor pool, PDA", address = "SponsorPool::find_address(&sponsor).0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    FundSponsorPool { amount: u64 },
+
+    /// Create associated token account of wallet paid by operator and reimbursed from sponsor pool,
+    /// so a wallet without lamports can receive tokens
+    #[builder(args = "sponsor: Pubkey")]
+    #[account(signer, writable, name = "operator", desc = "pool operator, payer of created accounts")]
+    #[account(name = "wallet")]
+    #[account(
+        writable, name = "token_account", desc = "associated token account of wallet, uninitialized",
+        address = "get_associated_token_address(&wallet, &mint)",
+    )]
+    #[account(name = "mint")]
+    #[account(writable, name = "pool", desc = "sponsor pool, PDA", address = "SponsorPool::find_address(&sponsor).0")]
+    #[account(
+        writable, name = "sponsored_wallet", desc = "sponsored wallet, PDA, may be uninitialized",
+        address = "SponsoredWallet::find_address(&pool, &wallet).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "associated_token_program", desc = "SPL associated token account program",
+        address = "spl_associated_token_account::id()",
+    )]
+    #[account(name = "rent", desc = "rent sysvar", address = "sysvar::rent::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CreateSponsoredAccount,
+
+    /// Close sponsor pool and return unspent lamports to sponsor
+    #[account(signer, writable, name = "sponsor")]
+    #[account(writable, name = "pool", desc = "sponsor pool, PDA", address = "SponsorPool::find_address(&sponsor).0")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CloseSponsorPool,
+
+    /// Stream `deposit` of lamports to recipient at `rate_per_second` since `start_ts`, custom token
+    /// with `with_stream_token`
+    #[account(signer, writable, name = "sender")]
+    #[account(
+        writable, name = "stream", desc = "stream, PDA",
+        address = "Stream::find_address(&sender, &recipient).0",
+    )]
+    #[account(name = "recipient")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(optional, name = "mint", desc = "stream mint, see `with_stream_token`")]
+    #[account(
+        optional, writable, name = "stream_token", desc = "associated token account of stream, created beforehand",
+    )]
+    #[account(optional, name = "spl_token", desc = "SPL token program")]
+    #[account(optional, writable, name = "sender_token", desc = "sender SPL token account of mint")]
+    #[account(optional, name = "mint_config", desc = "stream mint config, PDA, may be uninitialized")]
+    CreateStream { deposit: u64, rate_per_second: u64, start_ts: i64 },
+
+    /// Withdraw streamed and not yet withdrawn amount, signed by recipient
+    #[builder(args = "sender: Pubkey")]
+    #[account(signer, writable, name = "recipient")]
+    #[account(
+        writable, name = "stream", desc = "stream, PDA",
+        address = "Stream::find_address(&sender, &recipient).0",
+    )]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(optional, name = "mint", desc = "stream mint, see `with_stream_token`")]
+    #[account(optional, writable, name = "stream_token", desc = "associated token account of stream")]
+    #[account(optional, name = "spl_token", desc = "SPL token program")]
+    #[account(optional, writable, name = "recipient_token", desc = "recipient SPL token account of mint")]
+    WithdrawFromStream,
+
+    /// Close stream, signed by sender. Streamed and not withdrawn amount goes to recipient, the rest
+    /// back to sender. Stream and its token account close to sender
+    #[account(signer, writable, name = "sender")]
+    #[account(
+        writable, name = "stream", desc = "stream, PDA",
+        address = "Stream::find_address(&sender, &recipient).0",
+    )]
+    #[account(writable, name = "recipient")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(optional, name = "mint", desc = "stream mint, see `with_stream_token`")]
+    #[account(optional, writable, name = "stream_token", desc = "associated token account of stream")]
+    #[account(optional, name = "spl_token", desc = "SPL token program")]
+    #[account(optional, writable, name = "sender_token", desc = "sender SPL token account receiving the refund")]
+    #[account(optional, writable, name = "recipient_token", desc = "recipient SPL token account of mint")]
+    CloseStream,
+
+    /// Park custom token for wallet without associated token account instead of creating it, the wallet
+    /// claims it later with `ClaimBalance`. Sender pays rent of the claimable balance until then
+    #[account(signer, writable, name = "from", desc = "from user account, authority, payer of claimable balance")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(name = "to_wallet")]
+    #[account(name = "mint")]
+    #[account(
+        writable, name = "claimable", desc = "claimable balance, PDA, may be uninitialized",
+        address = "ClaimableBalance::find_address(&to_wallet, &mint).0",
+    )]
+    #[account(
+        writable, name = "claimable_token",
+        desc = "associated token account of claimable balance, may be uninitialized",
+        address = "get_associated_token_address(&claimable, &mint)",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "associated_token_program", desc = "SPL associated token account program",
+        address = "spl_associated_token_account::id()",
+    )]
+    #[account(name = "rent", desc = "rent sysvar", address = "sysvar::rent::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    ParkSplToken { amount: u64 },
+
+    /// Claim parked custom token into associated token account of wallet, created when it does not exist yet.
+    /// Claimable balance closes to the payer of its rent
+    #[account(signer, writable, name = "wallet", desc = "payer of created account")]
+    #[account(
+        writable, name = "wallet_token", desc = "associated token account of wallet, may be uninitialized",
+        address = "get_associated_token_address(&wallet, &mint)",
+    )]
+    #[account(name = "mint")]
+    #[account(
+        writable, name = "claimable", desc = "claimable balance, PDA",
+        address = "ClaimableBalance::find_address(&wallet, &mint).0",
+    )]
+    #[account(
+        writable, name = "claimable_token", desc = "associated token account of claimable balance",
+        address = "get_associated_token_address(&claimable, &mint)",
+    )]
+    #[account(writable, name = "payer", desc = "payer of claimable balance rent")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "associated_token_program", desc = "SPL associated token account program",
+        address = "spl_associated_token_account::id()",
+    )]
+    #[account(name = "rent", desc = "rent sysvar", address = "sysvar::rent::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ClaimBalance,
+
+    /// Revoke a delegate at once for every token account of the owner, or for every owner when `global`,
+    /// without unwinding its approvals. Global revocation only by config admin
+    #[account(signer, writable, name = "authority", desc = "owner of delegated accounts or config admin, payer")]
+    #[account(name = "delegate", desc = "revoked delegate")]
+    #[account(
+        writable, name = "revocation", desc = "revocation, PDA",
+        address = "Revocation::find_address(&Revocation::scope(&authority, global), &delegate).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    RevokeDelegate { global: bool },
+
+    /// Lift a revocation, returning its rent to the authority. Global revocation only by config admin
+    #[account(signer, writable, name = "authority", desc = "owner of delegated accounts or config admin")]
+    #[account(name = "delegate", desc = "revoked delegate")]
+    #[account(
+        writable, name = "revocation", desc = "revocation, PDA",
+        address = "Revocation::find_address(&Revocation::scope(&authority, global), &delegate).0",
+    )]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ReinstateDelegate { global: bool },
+
+    /// Register recipient on the roster of a payer, signed by the recipient itself so a substituted
+    /// destination can't be registered on its behalf. Recipient pays rent of the roster entry
+    #[account(signer, writable, name = "recipient", desc = "registered wallet, payer of roster entry")]
+    #[account(name = "payer", desc = "owner of the roster")]
+    #[account(
+        writable, name = "roster_entry", desc = "roster entry, PDA",
+        address = "RosterEntry::find_address(&payer, &recipient).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    RegisterRecipient,
+
+    /// Remove recipient from the roster of the payer, returning roster entry rent to the recipient
+    #[account(signer, name = "payer", desc = "owner of the roster")]
+    #[account(writable, name = "recipient", desc = "removed wallet, receives roster entry rent")]
+    #[account(
+        writable, name = "roster_entry", desc = "roster entry, PDA",
+        address = "RosterEntry::find_address(&payer, &recipient).0",
+    )]
+    RemoveRecipient,
+
+    /// Transfer lamports, fails unless the recipient is registered on the roster of the sender
+    #[builder(policy = "from")]
+    #[account(signer, writable, name = "from", desc = "from user account, roster owner")]
+    #[account(writable, name = "to", desc = "registered wallet")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "roster_entry", desc = "roster entry, PDA, may be uninitialized",
+        address = "RosterEntry::find_address(&from, &to).0",
+    )]
+    TransferLamportsToRecipient { amount: u64 },
+
+    /// Transfer custom token, fails unless the owner of the destination is registered on the roster of the sender
+    #[builder(args = "recipient: Pubkey, mint: Pubkey", policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority, roster owner")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account, owned by registered wallet")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "roster_entry", desc = "roster entry, PDA, may be uninitialized",
+        address = "RosterEntry::find_address(&from, &recipient).0",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenToRecipient { amount: u64 },
+
+    /// Escrow the sum of milestone amounts of custom token, released to payee milestone by milestone.
+    /// Associated token account of the contract holds the escrowed tokens
+    #[account(signer, writable, name = "funder")]
+    #[account(writable, name = "funder_token", desc = "funder SPL token account of mint")]
+    #[account(name = "payee")]
+    #[account(name = "arbiter", desc = "may release milestones and close the contract early, may be funder")]
+    #[account(name = "mint")]
+    #[account(
+        writable, name = "contract", desc = "milestone contract, PDA",
+        address = "MilestoneContract::find_address(&funder, &payee, id).0",
+    )]
+    #[account(
+        writable, name = "contract_token", desc = "associated token account of contract, created beforehand",
+        address = "get_associated_token_address(&contract, &mint)",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CreateContract { id: u64, milestones: Vec<MilestoneTerms> },
+
+    /// Submit hash of the deliverable of milestone `index`, signed by payee. Replaces an earlier submission
+    /// until the milestone is released
+    #[account(signer, name = "payee")]
+    #[account(writable, name = "contract", desc = "milestone contract, PDA")]
+    SubmitDeliverable { index: u8, deliverable_hash: [u8; 32] },
+
+    /// Release tranche of milestone `index` with submitted deliverable to payee, signed by funder or arbiter
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, name = "authority", desc = "funder or arbiter")]
+    #[account(writable, name = "contract", desc = "milestone contract, PDA")]
+    #[account(
+        writable, name = "contract_token", desc = "associated token account of contract",
+        address = "get_associated_token_address(&contract, &mint)",
+    )]
+    #[account(writable, name = "payee_token", desc = "payee SPL token account of mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ReleaseMilestone { index: u8 },
+
+    /// Close contract, signed by arbiter at any time or by funder once every milestone is released.
+    /// Unreleased tranches are refunded, contract and its token account close to funder
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, name = "authority", desc = "funder or arbiter")]
+    #[account(writable, name = "funder", desc = "receives refund and rent")]
+    #[account(writable, name = "contract", desc = "milestone contract, PDA")]
+    #[account(
+        writable, name = "contract_token", desc = "associated token account of contract",
+        address = "get_associated_token_address(&contract, &mint)",
+    )]
+    #[account(writable, name = "funder_token", desc = "funder SPL token account receiving the refund")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    CloseContract,
+
+    /// Transfer native Solana token with payment reference `memo`, logged through SPL memo program
+    /// and recorded in event. Optional accounts as of `TransferLamportsV2`
+    #[builder(policy = "from")]
+    #[account(signer, writable, name = "from")]
+    #[account(writable, name = "to")]
+    #[account(name = "system_program", desc = "system program process transfer", address = "system_program::id()")]
+    #[account(name = "memo_program", desc = "SPL memo program", address = "spl_memo::id()")]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "fee recipient of fee config")]
+    TransferLamportsWithMemo { amount: u64, memo: String },
+
+    /// Transfer custom token with payment reference `memo`, logged through SPL memo program
+    /// and recorded in event. Optional accounts as of `TransferSplTokenV2`
+    #[builder(args = "mint: Pubkey", policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(name = "memo_program", desc = "SPL memo program", address = "spl_memo::id()")]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "token account owned by fee recipient")]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenWithMemo { amount: u64, memo: String },
+
+    /// Create program-wide audit log, only by program config admin. Afterwards every successful instruction
+    /// carrying it as the last account is folded into its hash chain, see `with_audit_log`
+    #[account(signer, writable, name = "admin", desc = "program config admin")]
+    #[account(writable, name = "audit_log", desc = "audit log, PDA", address = "AuditLog::find_address().0")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    InitializeAuditLog,
+
+    /// Transfer the whole balance of `from` above its rent exempt minimum, read on-chain after the transaction fee
+    /// is charged. Accounts as `TransferLamportsV2`
+    #[builder(policy = "from")]
+    #[account(signer, writable, name = "from")]
+    #[account(writable, name = "to")]
+    #[account(name = "system_program", desc = "system program process transfer", address = "system_program::id()")]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "fee recipient of fee config")]
+    SweepLamports,
+
+    /// Transfer the whole balance of `from_spl_token`, read on-chain. Accounts as `TransferSplTokenV2`
+    #[builder(args = "mint: Pubkey", policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account, PDA")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account, PDA")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "token account owned by fee recipient")]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    SweepSplToken,
+
+    /// Freeze token account for compliance, signed by freeze authority of its mint
+    #[account(signer, name = "freeze_authority", desc = "freeze authority of mint")]
+    #[account(writable, name = "spl_token_account", desc = "SPL token account of mint")]
+    #[account(name = "mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    FreezeSplTokenAccount,
+
+    /// Thaw token account frozen by `FreezeSplTokenAccount`, signed by freeze authority of its mint
+    #[account(signer, name = "freeze_authority", desc = "freeze authority of mint")]
+    #[account(writable, name = "spl_token_account", desc = "SPL token account of mint")]
+    #[account(name = "mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    ThawSplTokenAccount,
+
+    /// Copy decimals and token program of `mint` to its `MintDecimals` PDA, paid by anyone.
+    /// Checked transfers given the PDA, see `with_mint_decimals`, skip unpacking the mint
+    #[account(signer, writable, name = "payer")]
+    #[account(name = "mint")]
+    #[account(
+        writable, name = "mint_decimals", desc = "mint decimals, PDA",
+        address = "MintDecimals::find_address(&mint).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    CacheMintDecimals,
+
+    /// Create program-wide recipient registry in `mode`, only by program config admin, who becomes registry admin.
+    /// Transfers only reach recipients it permits, see `transfer_policy_accounts`
+    #[account(signer, writable, name = "admin", desc = "program config admin")]
+    #[account(writable, name = "registry", desc = "registry, PDA", address = "Registry::find_address().0")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    InitializeRegistry { mode: RegistryMode },
+
+    /// Add `recipient` to the registry, only by registry admin. Adding a present recipient is a no-op
+    #[account(signer, name = "admin", desc = "registry admin")]
+    #[account(writable, name = "registry", desc = "registry, PDA", address = "Registry::find_address().0")]
+    AddToRegistry { recipient: Pubkey },
+
+    /// Remove `recipient` from the registry, only by registry admin. Removing an absent recipient is a no-op
+    #[account(signer, name = "admin", desc = "registry admin")]
+    #[account(writable, name = "registry", desc = "registry, PDA", address = "Registry::find_address().0")]
+    RemoveFromRegistry { recipient: Pubkey },
+
+    /// Start migration of vaults and escrows to `successor` deployment, only by program config admin, who becomes
+    /// migration admin. Accounts are then moved one by one, see `ExportVault` and `ExportEscrow`
+    #[account(signer, writable, name = "admin", desc = "program config admin")]
+    #[account(
+        writable, name = "migration", desc = "export side of migration, PDA",
+        address = "Migration::find_export_address().0",
+    )]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    BeginExport { successor: Pubkey },
+
+    /// Start migration of vaults and escrows exported by `predecessor` deployment into this one, only by program
+    /// config admin, who becomes migration admin, see `ImportVault` and `ImportEscrow`
+    #[account(signer, writable, name = "admin", desc = "program config admin")]
+    #[account(
+        writable, name = "migration", desc = "import side of migration, PDA",
+        address = "Migration::find_import_address().0",
+    )]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    BeginImport { predecessor: Pubkey },
+
+    /// Move the whole vault balance to associated token account of the vault of the same owner and mint
+    /// in successor deployment and freeze the vault, only by migration admin. The destination is created beforehand
+    #[builder(args = "owner: Pubkey, mint: Pubkey, successor: Pubkey")]
+    #[account(signer, name = "admin", desc = "migration admin")]
+    #[account(
+        writable, name = "migration", desc = "export side of migration, PDA",
+        address = "Migration::find_export_address().0",
+    )]
+    #[account(writable, name = "vault", desc = "vault, PDA", address = "Vault::find_address(&owner, &mint).0")]
+    #[account(
+        writable, name = "vault_token", desc = "associated token account of vault",
+        address = "get_associated_token_address(&vault, &mint)",
+    )]
+    #[account(
+        writable, name = "successor_vault_token", desc = "associated token account of vault in successor",
+        address = "get_associated_token_address(&Vault::find_address_in(&successor, &owner, &mint).0, &mint)",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    ExportVault,
+
+    /// Recreate vault exported by predecessor deployment, only by migration admin
+    #[builder(args = "predecessor: Pubkey, owner: Pubkey, mint: Pubkey")]
+    #[account(signer, writable, name = "admin", desc = "migration admin")]
+    #[account(
+        writable, name = "migration", desc = "import side of migration, PDA",
+        address = "Migration::find_import_address().0",
+    )]
+    #[account(
+        name = "exported_vault", desc = "exported vault of predecessor",
+        address = "Vault::find_address_in(&predecessor, &owner, &mint).0",
+    )]
+    #[account(writable, name = "vault", desc = "vault, PDA", address = "Vault::find_address(&owner, &mint).0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    ImportVault,
+
+    /// Hand temp token account of escrow over to the escrow of the same temp token account in successor deployment
+    /// and freeze the escrow, only by migration admin
+    #[account(signer, name = "admin", desc = "migration admin")]
+    #[account(
+        writable, name = "migration", desc = "export side of migration, PDA",
+        address = "Migration::find_export_address().0",
+    )]
+    #[account(writable, name = "temp_token", desc = "temp token account of escrow")]
+    #[account(writable, name = "escrow", desc = "escrow, PDA", address = "Escrow::find_address(&temp_token).0")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    ExportEscrow,
+
+    /// Recreate escrow exported by predecessor deployment, only by migration admin. Its temp token account
+    /// must already be owned by the recreated escrow
+    #[builder(args = "predecessor: Pubkey")]
+    #[account(signer, writable, name = "admin", desc = "migration admin")]
+    #[account(
+        writable, name = "migration", desc = "import side of migration, PDA",
+        address = "Migration::find_import_address().0",
+    )]
+    #[account(name = "temp_token", desc = "temp token account of escrow")]
+    #[account(
+        name = "exported_escrow", desc = "exported escrow of predecessor",
+        address = "Escrow::find_address_in(&predecessor, &temp_token).0",
+    )]
+    #[account(writable, name = "escrow", desc = "escrow, PDA", address = "Escrow::find_address(&temp_token).0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    ImportEscrow,
+
+    /// Limit transfers of `mint` by `authority` to `limit` base units per rolling window of `window_seconds`,
+    /// `Pubkey::default()` limits lamports, only by program config admin. Creates the limit or replaces limit and
+    /// window of an existing one, keeping what was spent within the current window. Every transfer of `mint`
+    /// signed by `authority` counts its amount, see `transfer_policy_accounts`
+    #[account(signer, writable, name = "admin", desc = "program config admin")]
+    #[account(name = "authority", desc = "signing authority of limited transfers")]
+    #[account(
+        writable, name = "spending_limit", desc = "spending limit, PDA",
+        address = "SpendingLimit::find_address(&authority, &mint).0",
+    )]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetSpendingLimit { mint: Pubkey, limit: u64, window_seconds: i64 },
+
+    /// Create config of `namespace` with the signer as its admin, an isolated configuration with its own admin,
+    /// fees, registry and vaults on the same deployment. Instructions are run in the namespace by passing its
+    /// config last, see `in_namespace`, features stay those of program config, which pauses every namespace
+    #[account(signer, writable, name = "admin", desc = "namespace admin")]
+    #[account(signer, name = "namespace", desc = "namespace key, its holder claims the namespace")]
+    #[account(
+        writable, name = "config", desc = "namespace config, PDA",
+        address = "Config::find_namespaced_address(&namespace).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    InitializeNamespace,
+
+    /// Require approval of `approver` for transfers of `authority` above `threshold`, only by program config admin.
+    /// Creates the policy or replaces approver and threshold of an existing one. Every transfer signed by
+    /// `authority` fails above the threshold, see `transfer_policy_accounts`, lamports then go through
+    /// `ProposeTransfer`
+    #[account(signer, writable, name = "admin", desc = "program config admin")]
+    #[account(name = "authority", desc = "signing authority of transfers")]
+    #[account(name = "approver", desc = "second authority approving proposed transfers")]
+    #[account(
+        writable, name = "approval_policy", desc = "approval policy, PDA",
+        address = "ApprovalPolicy::find_address(&authority).0",
+    )]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetApprovalPolicy { threshold: u64 },
+
+    /// Propose transfer of `amount` lamports above the approval threshold of the proposer to `to`, held by
+    /// the pending transfer until the approver of the proposer executes or cancels it, see `ExecuteTransfer` and
+    /// `CancelTransfer`. Amounts up to the threshold are transferred directly
+    #[builder(policy = "proposer")]
+    #[account(signer, writable, name = "proposer", desc = "sender, pays rent of pending transfer")]
+    #[account(name = "to", desc = "recipient")]
+    #[account(
+        writable, name = "pending_transfer", desc = "pending transfer, PDA",
+        address = "PendingTransfer::find_address(&proposer, &to).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    ProposeTransfer { amount: u64 },
+
+    /// Move proposed amount to the recipient, only by approver of the pending transfer. Registry, spending limit
+    /// and transfer hook of the proposer apply as to its direct transfers. Pending transfer closes to the proposer
+    #[builder(policy = "proposer")]
+    #[account(signer, name = "approver", desc = "approver of pending transfer")]
+    #[account(writable, name = "proposer", desc = "proposer, receives rent of pending transfer")]
+    #[account(writable, name = "to", desc = "recipient")]
+    #[account(
+        writable, name = "pending_transfer", desc = "pending transfer, PDA",
+        address = "PendingTransfer::find_address(&proposer, &to).0",
+    )]
+    ExecuteTransfer,
+
+    /// Reject proposed transfer, only by approver of the pending transfer.
+    /// Pending transfer closes to the proposer with the proposed amount
+    #[account(signer, name = "approver", desc = "approver of pending transfer")]
+    #[account(writable, name = "proposer", desc = "proposer, receives amount and rent of pending transfer")]
+    #[account(name = "to", desc = "recipient")]
+    #[account(
+        writable, name = "pending_transfer", desc = "pending transfer, PDA",
+        address = "PendingTransfer::find_address(&proposer, &to).0",
+    )]
+    CancelTransfer,
+
+    /// Fund airdrop of `total_amount` to claimers committed by `merkle_root` of `leaf_count` leaves, see
+    /// `merkle::payout_leaf`. Claims are made by claimers, see `ClaimMerkleDistribution`. Mint may not be soulbound
+    #[account(signer, writable, name = "authority", desc = "funder, pays rent of distribution and claim bitmap")]
+    #[account(name = "mint")]
+    #[account(writable, name = "authority_token", desc = "token account of authority funding the airdrop")]
+    #[account(
+        writable, name = "distribution", desc = "distribution, PDA",
+        address = "MerkleDistribution::find_address(&authority, &merkle_root).0",
+    )]
+    #[account(
+        writable, name = "distribution_token", desc = "associated token account of distribution, created beforehand",
+        address = "get_associated_token_address(&distribution, &mint)",
+    )]
+    #[account(
+        writable, name = "claim_bitmap", desc = "claimed leaves, PDA",
+        address = "ClaimBitmap::find_address(&distribution).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    CreateMerkleDistribution { merkle_root: [u8; 32], leaf_count: u32, total_amount: u64 },
+
+    /// Claim leaf `index` of `amount` of a Merkle distribution once, proof ties the claimer to the root
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, name = "claimer")]
+    #[account(writable, name = "claimer_token", desc = "token account of claimer receiving the amount")]
+    #[account(writable, name = "distribution", desc = "distribution, PDA")]
+    #[account(
+        writable, name = "distribution_token", desc = "associated token account of distribution",
+        address = "get_associated_token_address(&distribution, &mint)",
+    )]
+    #[account(
+        writable, name = "claim_bitmap", desc = "claimed leaves, PDA",
+        address = "ClaimBitmap::find_address(&distribution).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ClaimMerkleDistribution { index: u32, amount: u64, proof: Vec<[u8; 32]> },
+
+    /// Initialize deposit addresses of exchange `authority` swept into `omnibus`, one per user ID,
+    /// see `DepositScheme::find_deposit_address`
+    #[account(signer, writable, name = "authority", desc = "exchange, pays rent of the scheme")]
+    #[account(
+        writable, name = "deposit_scheme", desc = "deposit scheme, PDA",
+        address = "DepositScheme::find_address(&authority).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    InitializeDepositScheme { omnibus: Pubkey },
+
+    /// Sweep every lamport of the deposit address of `user_id` into the omnibus wallet, by anyone
+    #[account(name = "deposit_scheme", desc = "deposit scheme, PDA")]
+    #[account(
+        writable, name = "deposit", desc = "deposit address of the user, PDA",
+        address = "DepositScheme::find_deposit_address(&deposit_scheme, user_id).0",
+    )]
+    #[account(writable, name = "omnibus", desc = "omnibus wallet of the scheme")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    SweepDeposit { user_id: u64 },
+
+    /// Sweep the whole balance of the associated token account of the deposit address of `user_id`
+    /// into the associated token account of the omnibus wallet, by anyone
+    #[builder(args = "mint: Pubkey")]
+    #[account(name = "deposit_scheme", desc = "deposit scheme, PDA")]
+    #[account(
+        name = "deposit", desc = "deposit address of the user, PDA",
+        address = "DepositScheme::find_deposit_address(&deposit_scheme, user_id).0",
+    )]
+    #[account(
+        writable, name = "deposit_token", desc = "associated token account of deposit address",
+        address = "get_associated_token_address(&deposit, &mint)",
+    )]
+    #[account(writable, name = "omnibus_token", desc = "associated token account of omnibus wallet")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    SweepDepositSplToken { user_id: u64 },
+
+    /// Hand mint authority of `mint` over to its `ManagedMint` PDA, issues are capped at `supply_cap`
+    #[account(signer, writable, name = "admin", desc = "mint authority, becomes admin of the managed mint")]
+    #[account(writable, name = "mint")]
+    #[account(
+        writable, name = "managed_mint", desc = "managed mint, PDA",
+        address = "ManagedMint::find_address(&mint).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    InitializeManagedMint { supply_cap: u64 },
+
+    /// Grant `roles` of `Issuer::ROLE_*` bits and `allowance` left to issue to `issuer`, only by managed mint admin.
+    /// Creates the issuer or replaces roles and allowance of an existing one, no roles revoke it
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, writable, name = "admin", desc = "managed mint admin")]
+    #[account(name = "managed_mint", desc = "managed mint, PDA", address = "ManagedMint::find_address(&mint).0")]
+    #[account(name = "issuer")]
+    #[account(
+        writable, name = "issuer_role", desc = "issuer, PDA",
+        address = "Issuer::find_address(&managed_mint, &issuer).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    SetIssuer { roles: u8, allowance: u64 },
+
+    /// Mint managed token to token account, signed by issuer with `Issuer::ROLE_ISSUE` within its allowance
+    /// and the supply cap
+    #[account(signer, name = "issuer")]
+    #[account(writable, name = "mint")]
+    #[account(
+        writable, name = "managed_mint", desc = "managed mint, PDA",
+        address = "ManagedMint::find_address(&mint).0",
+    )]
+    #[account(
+        writable, name = "issuer_role", desc = "issuer, PDA",
+        address = "Issuer::find_address(&managed_mint, &issuer).0",
+    )]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account of mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    Issue { amount: u64 },
+
+    /// Burn managed token from token account of redeemer with `Issuer::ROLE_REDEEM`
+    #[account(signer, name = "redeemer", desc = "issuer, owner of from SPL token account")]
+    #[account(writable, name = "mint")]
+    #[account(
+        writable, name = "managed_mint", desc = "managed mint, PDA",
+        address = "ManagedMint::find_address(&mint).0",
+    )]
+    #[account(
+        writable, name = "issuer_role", desc = "issuer, PDA",
+        address = "Issuer::find_address(&managed_mint, &redeemer).0",
+    )]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account of mint")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    Redeem { amount: u64 },
+
+    /// Approve delegate of each amount on its token account of the owner, all or none
+    #[builder(skip)]
+    #[account(signer, name = "owner", desc = "owner of every SPL token account")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        optional, writable, name = "approvals",
+        desc = "SPL token account and its delegate of each amount, in order",
+    )]
+    BatchApprove { amounts: Vec<u64> },
+
+    /// Succeed only when an instruction of `program` precedes this one in the transaction, with account keys
+    /// and data hashing to `accounts_hash` and `data_hash`, see `guard_hashes`. Transfers appended
+    /// the instructions sysvar by `with_guard` require a guard right before them
+    #[account(name = "instructions", desc = "instructions sysvar", address = "sysvar::instructions::id()")]
+    GuardPrecedingInstruction { program: Pubkey, accounts_hash: [u8; 32], data_hash: [u8; 32] },
+
+    /// Fund `pool_amount` of `mint` paid pro rata to holders of `snapshot_mint`, committed by `snapshot_root`
+    /// of `holder_count` leaves with snapshot balances adding up to `snapshot_supply`, see `Dividend`.
+    /// Holders claim until `claim_deadline`, see `ClaimDividend`. Mint of the pool may not be soulbound
+    #[account(signer, writable, name = "admin", desc = "funder, pays rent of dividend and claim bitmap")]
+    #[account(name = "mint", desc = "mint of the pool")]
+    #[account(name = "snapshot_mint", desc = "mint of the snapshotted holdings")]
+    #[account(writable, name = "admin_token", desc = "token account of admin funding the pool")]
+    #[account(
+        writable, name = "dividend", desc = "dividend, PDA",
+        address = "Dividend::find_address(&admin, &snapshot_root).0",
+    )]
+    #[account(
+        writable, name = "dividend_token", desc = "associated token account of dividend, created beforehand",
+        address = "get_associated_token_address(&dividend, &mint)",
+    )]
+    #[account(
+        writable, name = "claim_bitmap", desc = "claimed leaves, PDA",
+        address = "ClaimBitmap::find_address(&dividend).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    #[account(
+        name = "mint_config", desc = "mint config of the pool, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    CreateDividend {
+        snapshot_root: [u8; 32],
+        holder_count: u32,
+        snapshot_supply: u64,
+        pool_amount: u64,
+        claim_deadline: i64,
+    },
+
+    /// Claim the share of leaf `index` with snapshot `balance` once before the claim deadline,
+    /// proof ties the holder to the snapshot root
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, name = "holder")]
+    #[account(writable, name = "holder_token", desc = "token account of holder receiving the share")]
+    #[account(writable, name = "dividend", desc = "dividend, PDA")]
+    #[account(
+        writable, name = "dividend_token", desc = "associated token account of dividend",
+        address = "get_associated_token_address(&dividend, &mint)",
+    )]
+    #[account(
+        writable, name = "claim_bitmap", desc = "claimed leaves, PDA",
+        address = "ClaimBitmap::find_address(&dividend).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ClaimDividend { index: u32, balance: u64, proof: Vec<[u8; 32]> },
+
+    /// Return the unclaimed rest of the pool to the admin after the claim deadline and close the dividend,
+    /// its token account and claim bitmap
+    #[builder(args = "mint: Pubkey")]
+    #[account(signer, writable, name = "admin", desc = "admin, receives rent of the closed accounts")]
+    #[account(writable, name = "admin_token", desc = "token account of admin receiving the rest")]
+    #[account(writable, name = "dividend", desc = "dividend, PDA")]
+    #[account(
+        writable, name = "dividend_token", desc = "associated token account of dividend",
+        address = "get_associated_token_address(&dividend, &mint)",
+    )]
+    #[account(
+        writable, name = "claim_bitmap", desc = "claimed leaves, PDA",
+        address = "ClaimBitmap::find_address(&dividend).0",
+    )]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "config", desc = "program config, PDA, may be uninitialized",
+        address = "Config::find_address().0",
+    )]
+    ClawbackDividend,
+
+    /// Allow or disallow `program` as receiving program of `DepositToProgram`, only by program config admin
+    #[account(signer, writable, name = "admin")]
+    #[account(writable, name = "config", desc = "program config, PDA", address = "Config::find_address().0")]
+    #[account(name = "system_program", address = "system_program::id()")]
+    SetDepositProgram { program: Pubkey, allowed: bool },
+
+    /// Create event stream of `key`, any key its creator chooses, e.g. its wallet or one of the shards its clients
+    /// are spread over. Afterwards events of instructions carrying the stream are sequenced within it,
+    /// see `with_event_stream`
+    #[account(signer, writable, name = "payer")]
+    #[account(
+        writable, name = "event_stream", desc = "event stream, PDA",
+        address = "EventStream::find_address(&key).0",
+    )]
+    #[account(name = "system_program", address = "system_program::id()")]
+    InitializeEventStream { key: Pubkey },
+
+    /// Transfer native Solana token as `TransferLamports`, starting with `transfer_policy_accounts` of `from`,
+    /// so transfer policies, recipient registry and pause apply
+    #[builder(policy = "from")]
+    #[account(signer, writable, name = "from")]
+    #[account(writable, name = "to")]
+    #[account(name = "system_program", desc = "system program process transfer", address = "system_program::id()")]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "fee recipient of fee config")]
+    TransferLamportsV2 { amount: u64 },
+
+    /// Transfer custom token as `TransferSplToken`, starting with `transfer_policy_accounts` of `from`,
+    /// so transfer policies and recipient registry apply
+    #[builder(args = "mint: Pubkey", policy = "from", policy_mint = "mint")]
+    #[account(signer, name = "from", desc = "from user account, authority")]
+    #[account(writable, name = "from_spl_token", desc = "from SPL token account, PDA")]
+    #[account(writable, name = "to_spl_token", desc = "to SPL token account, PDA")]
+    #[account(name = "spl_token", desc = "SPL token program", address = "spl_token::id()")]
+    #[account(
+        name = "mint_config", desc = "mint config, PDA, may be uninitialized",
+        address = "MintConfig::find_address(&mint).0",
+    )]
+    #[account(optional, name = "fee_config", desc = "fee config, PDA, see `with_fee`")]
+    #[account(optional, writable, name = "fee_account", desc = "token account owned by fee recipient")]
+    #[account(optional, signer, name = "multisig_signers", desc = "signers of multisig owner, see `with_multisig`")]
+    TransferSplTokenV2 { amount: u64 },
 }
 
 impl TokenInstruction {
-    pub fn transfer_lamports(from: Pubkey, to: Pubkey, amount: u64) -> Instruction {
-        let instr = TokenInstruction::TransferLamports { amount };
-        Instruction::new_with_borsh(
-            id(),
-            &instr,
-            vec![
-                AccountMeta::new(from, true),
-                AccountMeta::new(to, false),
-                AccountMeta::new_readonly(system_program::id(), false),
-            ],
+    /// First byte of versioned instruction data, never a tag of v1
+    pub const VERSION_MARKER: u8 = u8::MAX;
+    /// Version of instruction data written by `pack_versioned`
+    pub const LATEST_VERSION: u8 = 2;
+    /// Max amounts of batch transfers and weights of `SplitLamports`, more recipients don't fit a transaction
+    pub const MAX_BATCH_LEN: usize = 32;
+    /// Max proof of `ExecutePayout`, depth of a tree of `PayoutBatch::MAX_LEAVES`
+    pub const MAX_PROOF_LEN: usize = PayoutBatch::MAX_LEAVES.trailing_zeros() as usize;
+    /// Max proof of `ClaimMerkleDistribution` and `ClaimDividend`, depth of a tree of `MerkleDistribution::MAX_LEAVES`
+    pub const MAX_DISTRIBUTION_PROOF_LEN: usize = MerkleDistribution::MAX_LEAVES.trailing_zeros() as usize;
+    /// Max memo of memo transfer variants, bytes
+    pub const MAX_MEMO_LEN: usize = 256;
+    /// Max serialized transaction, payload of an IPv6 packet
+    pub const MAX_TRANSACTION_SIZE: usize = 1232;
+    /// Compute units of a batch transfer besides its recipients, estimated with a margin
+    pub const BATCH_BASE_UNITS: u32 = 20_000;
+    /// Compute units of each recipient of a batch transfer, a transfer CPI and its logs, estimated with a margin
+    pub const BATCH_UNITS_PER_RECIPIENT: u32 = 8_000;
+    /// Number of `transfer_policy_accounts`
+    pub const TRANSFER_POLICY_LEN: usize = 8;
+    /// Position of the hook program among `transfer_policy_accounts`
+    const TRANSFER_HOOK_PROGRAM_INDEX: usize = 3;
+    /// Position of the namespace config among `transfer_policy_accounts`
+    const TRANSFER_NAMESPACE_CONFIG_INDEX: usize = 6;
+    /// Position of the recipient registry of the namespace among `transfer_policy_accounts`
+    const TRANSFER_NAMESPACE_REGISTRY_INDEX: usize = 7;
+
+    /// Decode instruction data of any supported version:
+    /// - v1: tag followed by fields, written by `pack` and every builder and consumed exactly
+    /// - v2: `VERSION_MARKER`, version, then v1 body. Bytes past the fields known to this program are ignored,
+    ///   so clients may append fields read by newer programs without breaking deployed ones
+    ///
+    /// Fields are read in place without Borsh, only vectors and strings allocate. Vectors and strings longer
+    /// than their max and fields with tags other than `pack` writes are rejected, so every decoder reads
+    /// the same instruction out of accepted data
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (body, trailing_allowed) = match input {
+            [Self::VERSION_MARKER, 2, body @ ..] => (body, true),
+            [Self::VERSION_MARKER, ..] => return Err(TransferError::UnsupportedInstructionVersion.into()),
+            body => (body, false),
+        };
+        let rest = &mut &body[..];
+        let instr = Self::unpack_fields(rest)?;
+        if !rest.is_empty() && !trailing_allowed {
+            return Err(TransferError::TrailingInstructionData.into())
+        }
+        instr.check_field_lengths()?;
+        Ok(instr)
+    }
+
+    fn check_field_lengths(&self) -> Result<(), ProgramError> {
+        let (len, max_len) = match self {
+            Self::TransferLamportsBatch { amounts }
+            | Self::TransferSplTokenBatch { amounts }
+            | Self::BatchApprove { amounts } => (amounts.len(), Self::MAX_BATCH_LEN),
+            Self::SplitLamports { weights, .. } => (weights.len(), Self::MAX_BATCH_LEN),
+            Self::ExecutePayout { proof, .. } => (proof.len(), Self::MAX_PROOF_LEN),
+            Self::ClaimMerkleDistribution { proof, .. } | Self::ClaimDividend { proof, .. } => {
+                (proof.len(), Self::MAX_DISTRIBUTION_PROOF_LEN)
+            }
+            Self::CreateContract { milestones, .. } => (milestones.len(), MilestoneContract::MAX_MILESTONES),
+            Self::TransferLamportsWithMemo { memo, .. } | Self::TransferSplTokenWithMemo { memo, .. } => {
+                (memo.len(), Self::MAX_MEMO_LEN)
+            }
+            _ => return Ok(()),
+        };
+        if len > max_len {
+            return Err(TransferError::InstructionFieldTooLong.into())
+        }
+        Ok(())
+    }
+
+    /// Encode as `LATEST_VERSION`, see `unpack`
+    pub fn pack_versioned(&self) -> Vec<u8> {
+        let mut data = vec![Self::VERSION_MARKER, Self::LATEST_VERSION];
+        data.extend(self.pack());
+        data
+    }
+
+    /// Tag of instruction data of any supported version, `None` when empty or of unsupported version
+    pub fn tag(input: &[u8]) -> Option<u8> {
+        match input {
+            [Self::VERSION_MARKER, 2, tag, ..] => Some(*tag),
+            [Self::VERSION_MARKER, ..] | [] => None,
+            [tag, ..] => Some(*tag),
+        }
+    }
+
+    /// Instruction moving value between users, halted while program is paused, see `transfer_policy_accounts`.
+    /// First versions of `TransferLamports` and `TransferSplToken` predate the policy accounts and are not among them
+    pub fn is_transfer(&self) -> bool {
+        matches!(
+            self,
+            TokenInstruction::TransferLamportsV2 { .. }
+                | TokenInstruction::TransferSplTokenV2 { .. }
+                | TokenInstruction::TransferLamportsRoundUp { .. }
+                | TokenInstruction::TransferSplTokenToWallet { .. }
+                | TokenInstruction::TransferSplTokenAttested { .. }
+                | TokenInstruction::TransferSplTokenWithTravelRule { .. }
+                | TokenInstruction::TransferSplTokenPriceProtected { .. }
+                | TokenInstruction::TransferSplTokenAsDelegate { .. }
+                | TokenInstruction::TransferSplTokenChecked { .. }
+                | TokenInstruction::TransferLamportsBatch { .. }
+                | TokenInstruction::TransferSplTokenBatch { .. }
+                | TokenInstruction::TransferSplTokenWithAtaCreation { .. }
+                | TokenInstruction::SplitLamports { .. }
+                | TokenInstruction::TransferLamportsToRecipient { .. }
+                | TokenInstruction::TransferSplTokenToRecipient { .. }
+                | TokenInstruction::TransferLamportsWithMemo { .. }
+                | TokenInstruction::TransferSplTokenWithMemo { .. }
+                | TokenInstruction::SweepLamports
+                | TokenInstruction::SweepSplToken
+                | TokenInstruction::ProposeTransfer { .. }
+                | TokenInstruction::ExecuteTransfer
+        )
+    }
+
+    /// Instruction seeding its PDAs with the namespace config passed last, see `in_namespace`
+    pub fn is_namespaced(&self) -> bool {
+        matches!(
+            self,
+            TokenInstruction::InitializeVault
+                | TokenInstruction::WithdrawFromVault { .. }
+                | TokenInstruction::InitializeFeeConfig { .. }
+                | TokenInstruction::InitializeRegistry { .. }
         )
     }
 
-    pub fn transfer_spl_token(
+    /// Accounts every transfer instruction starts with, see `is_transfer`, so policies set for `authority`
+    /// apply whether the caller passes them or not. `mint` is the transferred mint, `Pubkey::default()` for lamports:
+    /// 0. [writable] - spending limit of `authority` for `mint`, PDA, may be uninitialized
+    /// 1. [] - approval policy of `authority`, PDA, may be uninitialized
+    /// 2. [] - transfer hook of `authority`, PDA, may be uninitialized
+    /// 3. [] - hook program of the transfer hook, this program as placeholder, see `with_transfer_hook`
+    /// 4. [] - recipient registry of the program, PDA, may be uninitialized, applies in every namespace
+    /// 5. [] - program config, PDA, may be uninitialized, its pause and safety switches apply to the transfer
+    /// 6. [] - config of the namespace the transfer runs in, its pause applies to the transfer, program config as
+    ///    placeholder in the default namespace, see `in_namespace`
+    /// 7. [] - recipient registry of the namespace, PDA, may be uninitialized, applies in addition to the registry
+    ///    of the program, its registry as placeholder in the default namespace
+    pub fn transfer_policy_accounts(authority: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+        let (config, _) = Config::find_address();
+        let (registry, _) = Registry::find_address();
+        vec![
+            AccountMeta::new(SpendingLimit::find_address(authority, mint).0, false),
+            AccountMeta::new_readonly(ApprovalPolicy::find_address(authority).0, false),
+            AccountMeta::new_readonly(TransferHook::find_address(authority).0, false),
+            AccountMeta::new_readonly(id(), false),
+            AccountMeta::new_readonly(registry, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(registry, false),
+        ]
+    }
+
+    /// `accounts` of the instruction past the `transfer_policy_accounts` a transfer starts with, see `is_transfer`,
+    /// so they are indexed as in the variant docs
+    pub fn skip_transfer_policy<'a, T>(&self, accounts: &'a [T]) -> &'a [T] {
+        match self.is_transfer() {
+            true => accounts.get(Self::TRANSFER_POLICY_LEN..).unwrap_or_default(),
+            false => accounts,
+        }
+    }
+
+    /// Amount folded into the audit log, `None` for instructions without `amount`
+    pub fn amount(&self) -> Option<u64> {
+        match self {
+            TokenInstruction::TransferLamports { amount }
+            | TokenInstruction::TransferSplToken { amount }
+            | TokenInstruction::ApproveSplToken { amount }
+            | TokenInstruction::PostBond { amount, .. }
+            | TokenInstruction::SlashBond { amount, .. }
+            | TokenInstruction::Tip { amount }
+            | TokenInstruction::CreateMatchingPool { amount, .. }
+            | TokenInstruction::Donate { amount }
+            | TokenInstruction::TransferLamportsRoundUp { amount }
+            | TokenInstruction::WithdrawSavings { amount }
+            | TokenInstruction::RegisterObligation { amount }
+            | TokenInstruction::DepositToProgram { amount }
+            | TokenInstruction::TransferSplTokenToWallet { amount }
+            | TokenInstruction::ExecutePayout { amount, .. }
+            | TokenInstruction::CreateDistributor { amount, .. }
+            | TokenInstruction::ClaimDistribution { amount }
+            | TokenInstruction::TransferSplTokenAttested { amount, .. }
+            | TokenInstruction::TransferSplTokenWithTravelRule { amount, .. }
+            | TokenInstruction::FillOrder { amount }
+            | TokenInstruction::TransferSplTokenPriceProtected { amount, .. }
+            | TokenInstruction::TransferSplTokenAsDelegate { amount }
+            | TokenInstruction::TransferSplTokenChecked { amount, .. }
+            | TokenInstruction::DepositToVault { amount }
+            | TokenInstruction::WithdrawFromVault { amount }
+            | TokenInstruction::ExchangeEscrow { amount }
+            | TokenInstruction::MintSplToken { amount }
+            | TokenInstruction::BurnSplToken { amount }
+            | TokenInstruction::TransferSplTokenWithAtaCreation { amount }
+            | TokenInstruction::WrapSol { amount }
+            | TokenInstruction::SplitLamports { amount, .. }
+            | TokenInstruction::CreateSponsorPool { amount, .. }
+            | TokenInstruction::FundSponsorPool { amount }
+            | TokenInstruction::ParkSplToken { amount }
+            | TokenInstruction::TransferLamportsToRecipient { amount }
+            | TokenInstruction::TransferSplTokenToRecipient { amount }
+            | TokenInstruction::TransferLamportsWithMemo { amount, .. }
+            | TokenInstruction::TransferSplTokenWithMemo { amount, .. }
+            | TokenInstruction::ProposeTransfer { amount }
+            | TokenInstruction::ClaimMerkleDistribution { amount, .. }
+            | TokenInstruction::Issue { amount }
+            | TokenInstruction::Redeem { amount }
+            | TokenInstruction::TransferLamportsV2 { amount }
+            | TokenInstruction::TransferSplTokenV2 { amount } => Some(*amount),
+            _ => None,
+        }
+    }
+
+    pub fn deposit_to_program(
+        receiver_program: Pubkey,
+        depositor: Pubkey,
+        source: Pubkey,
+        destination: Pubkey,
+        transfer_program: Pubkey,
+        extra_accounts: Vec<AccountMeta>,
+        amount: u64,
+    ) -> Instruction {
+        let (config, _) = Config::find_address();
+        let instr = TokenInstruction::DepositToProgram { amount };
+        let mut receive_deposit_instr = deposit_interface::receive_deposit(
+            receiver_program,
+            depositor,
+            source,
+            destination,
+            transfer_program,
+            extra_accounts,
+            amount,
+        );
+        receive_deposit_instr.accounts[0] = AccountMeta::new(depositor, true);
+        receive_deposit_instr.accounts.insert(4, AccountMeta::new_readonly(receiver_program, false));
+        receive_deposit_instr.accounts.insert(5, AccountMeta::new_readonly(config, false));
+        Instruction::new_with_bytes(id(), &instr.pack(), receive_deposit_instr.accounts)
+    }
+
+    pub fn transfer_lamports_batch(from: Pubkey, transfers: &[(Pubkey, u64)]) -> Instruction {
+        let amounts = transfers.iter().map(|(_, amount)| *amount).collect();
+        let mut accounts = Self::transfer_policy_accounts(&from, &Pubkey::default());
+        accounts.extend([AccountMeta::new(from, true), AccountMeta::new_readonly(system_program::id(), false)]);
+        accounts.extend(transfers.iter().map(|(to, _)| AccountMeta::new(*to, false)));
+        Instruction::new_with_bytes(id(), &TokenInstruction::TransferLamportsBatch { amounts }.pack(), accounts)
+    }
+
+    pub fn split_lamports(
+        from: Pubkey,
+        remainder: Pubkey,
+        recipients: &[Pubkey],
+        amount: u64,
+        weights: &[u32],
+    ) -> Instruction {
+        let mut accounts = Self::transfer_policy_accounts(&from, &Pubkey::default());
+        accounts.extend([
+            AccountMeta::new(from, true),
+            AccountMeta::new(remainder, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ]);
+        accounts.extend(recipients.iter().map(|recipient| AccountMeta::new(*recipient, false)));
+        let instr = TokenInstruction::SplitLamports { amount, weights: weights.to_vec() };
+        Instruction::new_with_bytes(id(), &instr.pack(), accounts)
+    }
+
+    pub fn transfer_spl_token_batch(
         from: Pubkey,
         from_spl_token: Pubkey,
-        to_spl_token: Pubkey,
-        amount: u64
+        mint: Pubkey,
+        transfers: &[(Pubkey, u64)],
     ) -> Instruction {
-        let instr = TokenInstruction::TransferSplToken { amount };
-        Instruction::new_with_borsh(
-            id(),
-            &instr,
-            vec![
-                AccountMeta::new_readonly(from, true),
-                AccountMeta::new(from_spl_token, false),
-                AccountMeta::new(to_spl_token, false),
-                AccountMeta::new_readonly(spl_token::id(), false)
-            ],
-        )
+        let amounts = transfers.iter().map(|(_, amount)| *amount).collect();
+        let mut accounts = Self::transfer_policy_accounts(&from, &mint);
+        accounts.extend([
+            AccountMeta::new_readonly(from, true),
+            AccountMeta::new(from_spl_token, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(MintConfig::find_address(&mint).0, false),
+        ]);
+        accounts.extend(transfers.iter().map(|(to_spl_token, _)| AccountMeta::new(*to_spl_token, false)));
+        Instruction::new_with_bytes(id(), &TokenInstruction::TransferSplTokenBatch { amounts }.pack(), accounts)
+    }
+
+    /// Approve each `(spl_token_account, delegate, amount)` of `approvals`, token accounts owned by `owner`
+    pub fn batch_approve(owner: Pubkey, approvals: &[(Pubkey, Pubkey, u64)]) -> Instruction {
+        let amounts = approvals.iter().map(|(_, _, amount)| *amount).collect();
+        let mut accounts = vec![
+            AccountMeta::new_readonly(owner, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+        for (spl_token_account, delegate, _) in approvals {
+            accounts.push(AccountMeta::new(*spl_token_account, false));
+            accounts.push(AccountMeta::new_readonly(*delegate, false));
+        }
+        Instruction::new_with_bytes(id(), &TokenInstruction::BatchApprove { amounts }.pack(), accounts)
+    }
+
+    /// `TransferLamportsBatch` instruction sets paying `transfers` in order, each fits a transaction paid by
+    /// `from`. With `compute_unit_price` every set starts with its compute unit limit and price
+    pub fn build_batched_transfers(
+        from: Pubkey,
+        transfers: &[(Pubkey, u64)],
+        compute_unit_price: Option<u64>,
+    ) -> Vec<Vec<Instruction>> {
+        Self::batched(&from, transfers, compute_unit_price, |chunk| Self::transfer_lamports_batch(from, chunk))
     }
 
-    pub fn approve_spl_token(
+    /// `TransferSplTokenBatch` instruction sets paying `transfers` to token accounts in order,
+    /// see `build_batched_transfers`
+    pub fn build_batched_spl_transfers(
         from: Pubkey,
         from_spl_token: Pubkey,
-        to_spl_token: Pubkey,
-        amount: u64
+        mint: Pubkey,
+        transfers: &[(Pubkey, u64)],
+        compute_unit_price: Option<u64>,
+    ) -> Vec<Vec<Instruction>> {
+        Self::batched(&from, transfers, compute_unit_price, |chunk| {
+            Self::transfer_spl_token_batch(from, from_spl_token, mint, chunk)
+        })
+    }
+
+    /// `BatchApprove` instruction sets re-establishing `approvals` in order, see `build_batched_transfers`
+    pub fn build_batched_approvals(
+        owner: Pubkey,
+        approvals: &[(Pubkey, Pubkey, u64)],
+        compute_unit_price: Option<u64>,
+    ) -> Vec<Vec<Instruction>> {
+        Self::batched(&owner, approvals, compute_unit_price, |chunk| Self::batch_approve(owner, chunk))
+    }
+
+    /// Size of a transaction of `instructions` paid by `payer` once signed
+    pub fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+        let message = Message::new(instructions, Some(payer));
+        let signatures = message.header.num_required_signatures as usize;
+        short_vec_len(signatures) + signatures * 64 + message.serialize().len()
+    }
+
+    /// Instruction sets of the longest prefixes of `items` fitting a transaction, up to `MAX_BATCH_LEN` each
+    fn batched<T, F>(
+        payer: &Pubkey,
+        items: &[T],
+        compute_unit_price: Option<u64>,
+        batch: F,
+    ) -> Vec<Vec<Instruction>>
+    where
+        F: Fn(&[T]) -> Instruction,
+    {
+        let set = |chunk: &[T]| {
+            let mut instructions = match compute_unit_price {
+                Some(price) => {
+                    let units = Self::BATCH_BASE_UNITS + Self::BATCH_UNITS_PER_RECIPIENT * chunk.len() as u32;
+                    vec![compute_budget::set_compute_unit_limit(units), compute_budget::set_compute_unit_price(price)]
+                }
+                None => Vec::with_capacity(1),
+            };
+            instructions.push(batch(chunk));
+            instructions
+        };
+        let mut sets = Vec::new();
+        let mut rest = items;
+        while !rest.is_empty() {
+            let mut len = rest.len().min(Self::MAX_BATCH_LEN);
+            let mut instructions = set(&rest[..len]);
+            while len > 1 && Self::transaction_size(&instructions, payer) > Self::MAX_TRANSACTION_SIZE {
+                len -= 1;
+                instructions = set(&rest[..len]);
+            }
+            sets.push(instructions);
+            rest = &rest[len..];
+        }
+        sets
+    }
+
+    /// Pass `hook_program` of the transfer hook set for the authority of a transfer instruction, see `is_transfer`,
+    /// in place of the placeholder of its `transfer_policy_accounts`
+    pub fn with_transfer_hook(mut instruction: Instruction, hook_program: Pubkey) -> Instruction {
+        instruction.accounts[Self::TRANSFER_HOOK_PROGRAM_INDEX].pubkey = hook_program;
+        instruction
+    }
+
+    /// `GuardPrecedingInstruction` requiring `expected` earlier in the transaction
+    pub fn guard_preceding(expected: &Instruction) -> Instruction {
+        let (accounts_hash, data_hash) = Self::guard_hashes(expected);
+        Self::guard_preceding_instruction(expected.program_id, accounts_hash, data_hash)
+    }
+
+    /// Hashes of account keys in order and of data of `instruction`, matched by `GuardPrecedingInstruction`.
+    /// Signer and writable flags are not hashed, the runtime may promote them when merging account metas
+    pub fn guard_hashes(instruction: &Instruction) -> ([u8; 32], [u8; 32]) {
+        let keys: Vec<&[u8]> = instruction.accounts.iter().map(|meta| meta.pubkey.as_ref()).collect();
+        (hashv(&keys).to_bytes(), hashv(&[&instruction.data]).to_bytes())
+    }
+
+    /// Append instructions sysvar to a transfer instruction, see `is_transfer`, or to the first version of
+    /// `TransferLamports` or `TransferSplToken`, so the processor rejects the transfer unless
+    /// `GuardPrecedingInstruction` is the instruction right before it. Goes after every other account but event
+    /// stream and audit log. `TransferSplTokenPriceProtected` takes the sysvar as its sandwich guard instead,
+    /// see `with_sandwich_guard`
+    pub fn with_guard(mut instruction: Instruction) -> Instruction {
+        instruction.accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+        instruction
+    }
+
+    /// Append fee accounts to `TransferLamports` or `TransferSplToken`, their V2, memo and sweep variants.
+    /// `fee_account` is the fee recipient itself for lamports, its token account
+    /// of the mint for custom tokens
+    pub fn with_fee(mut instruction: Instruction, fee_account: Pubkey) -> Instruction {
+        let (fee_config, _) = FeeConfig::find_address();
+        instruction.accounts.push(AccountMeta::new_readonly(fee_config, false));
+        instruction.accounts.push(AccountMeta::new(fee_account, false));
+        instruction
+    }
+
+    /// Append instructions sysvar to `TransferSplTokenPriceProtected`, `FillOrder` or `ExchangeEscrow`,
+    /// the transaction is then rejected when any other of its instructions touches the priced or swapped accounts
+    pub fn with_sandwich_guard(mut instruction: Instruction) -> Instruction {
+        instruction.accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+        instruction
+    }
+
+    /// Let M of N `signers` authorize `TransferSplToken`, its V2, memo and sweep variants, batch, checked, to wallet,
+    /// as delegate, attested, price protected and to recipient transfers or `ApproveSplToken` for an SPL multisig
+    /// owner, which no longer signs itself. Signers are appended after any other optional accounts.
+    /// `TransferSplTokenWithAtaCreation` is left out, its owner pays for the associated token account
+    pub fn with_multisig(mut instruction: Instruction, signers: &[Pubkey]) -> Instruction {
+        if let Some(owner) = instruction.accounts.iter_mut().find(|meta| meta.is_signer) {
+            owner.is_signer = false;
+        }
+        instruction.accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(*signer, true)));
+        instruction
+    }
+
+    /// Stream custom token of `mint` instead of lamports with `CreateStream`, `WithdrawFromStream` or `CloseStream`.
+    /// `token_accounts` are SPL token accounts of the stream parties in order of the instruction accounts,
+    /// `CreateStream` gets the mint config appended after them
+    pub fn with_stream_token(mut instruction: Instruction, mint: Pubkey, token_accounts: &[Pubkey]) -> Instruction {
+        let stream = instruction.accounts[1].pubkey;
+        instruction.accounts.push(AccountMeta::new_readonly(mint, false));
+        instruction.accounts.push(AccountMeta::new(get_associated_token_address(&stream, &mint), false));
+        instruction.accounts.push(AccountMeta::new_readonly(spl_token::id(), false));
+        instruction.accounts.extend(token_accounts.iter().map(|account| AccountMeta::new(*account, false)));
+        if let Ok(TokenInstruction::CreateStream { .. }) = TokenInstruction::unpack(&instruction.data) {
+            instruction.accounts.push(AccountMeta::new_readonly(MintConfig::find_address(&mint).0, false));
+        }
+        instruction
+    }
+
+    /// Append audit log to any instruction, after every other account, so the processor folds the instruction
+    /// into the hash chain once it succeeds, see `AuditLog::fold`
+    pub fn with_audit_log(mut instruction: Instruction) -> Instruction {
+        let (audit_log, _) = AuditLog::find_address();
+        instruction.accounts.push(AccountMeta::new(audit_log, false));
+        instruction
+    }
+
+    /// Append event stream of `key` to any instruction, after every other account but audit log, so events
+    /// of the instruction are stamped with the stream and their sequence number within it, see `InitializeEventStream`
+    pub fn with_event_stream(mut instruction: Instruction, key: &Pubkey) -> Instruction {
+        let (event_stream, _) = EventStream::find_address(key);
+        instruction.accounts.push(AccountMeta::new(event_stream, false));
+        instruction
+    }
+
+    /// Append ProgramData account of the program to an instruction of program config admin, so the upgrade
+    /// authority may create program config on its first use. Must precede `in_namespace` and `with_audit_log`
+    pub fn with_program_data(mut instruction: Instruction) -> Instruction {
+        let (program_data, _) = Pubkey::find_program_address(
+            &[instruction.program_id.as_ref()],
+            &bpf_loader_upgradeable::id(),
+        );
+        instruction.accounts.push(AccountMeta::new_readonly(program_data, false));
+        instruction
+    }
+
+    /// Append `MintDecimals` cache of `mint` to `TransferSplTokenChecked`, so the processor reads decimals
+    /// from the cache instead of unpacking the mint. The cache must be created by `CacheMintDecimals`
+    pub fn with_mint_decimals(mut instruction: Instruction, mint: &Pubkey) -> Instruction {
+        let (mint_decimals, _) = MintDecimals::find_address(mint);
+        instruction.accounts.push(AccountMeta::new_readonly(mint_decimals, false));
+        instruction
+    }
+
+    /// Run instruction in `namespace`: fee config and registry are replaced with those of the namespace, see
+    /// `InitializeNamespace`, but never among `transfer_policy_accounts`, where the namespace config and registry
+    /// take the place of their placeholders and program registry keeps applying. Instructions creating namespaced
+    /// PDAs, see `is_namespaced`, get the namespace config appended, `SetPaused` gets it appended writable. Program
+    /// config stays in place, features and other switches are always read from it, and its pause halts every
+    /// namespace. Must precede
+    /// `with_event_stream` and `with_audit_log`, vault instructions are moved with `vault_in_namespace`
+    pub fn in_namespace(instruction: Instruction, namespace: &Pubkey) -> Instruction {
+        let (namespace_config, _) = Config::find_namespaced_address(namespace);
+        let (namespaced_registry, _) = Registry::find_namespaced_address(&namespace_config);
+        let mut instruction = Self::replace_accounts(instruction, &[
+            (FeeConfig::find_address().0, FeeConfig::find_namespaced_address(&namespace_config).0),
+            (Registry::find_address().0, namespaced_registry),
+        ]);
+        match TokenInstruction::unpack(&instruction.data) {
+            Ok(instr) if instr.is_transfer() => {
+                instruction.accounts[Self::TRANSFER_NAMESPACE_CONFIG_INDEX].pubkey = namespace_config;
+                instruction.accounts[Self::TRANSFER_NAMESPACE_REGISTRY_INDEX].pubkey = namespaced_registry;
+            }
+            Ok(TokenInstruction::SetPaused { .. }) => {
+                instruction.accounts.push(AccountMeta::new(namespace_config, false));
+            }
+            Ok(instr) if instr.is_namespaced() => {
+                instruction.accounts.push(AccountMeta::new_readonly(namespace_config, false));
+            }
+            _ => {}
+        }
+        instruction
+    }
+
+    /// Run vault instruction of `owner` and `mint` in `namespace`, with the vault of the namespace
+    pub fn vault_in_namespace(
+        instruction: Instruction,
+        namespace: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
     ) -> Instruction {
-        let instr = TokenInstruction::ApproveSplToken { amount };
-        Instruction::new_with_borsh(
-            id(),
-            &instr,
-            vec![
-                AccountMeta::new_readonly(from, true),
-                AccountMeta::new(from_spl_token, false),
-                AccountMeta::new(to_spl_token, false),
-                AccountMeta::new_readonly(spl_token::id(), false)
-            ]
-        )
+        let (namespace_config, _) = Config::find_namespaced_address(namespace);
+        let (vault, _) = Vault::find_address(owner, mint);
+        let (namespaced_vault, _) = Vault::find_namespaced_address(&namespace_config, owner, mint);
+        let instruction = Self::replace_accounts(instruction, &[
+            (vault, namespaced_vault),
+            (get_associated_token_address(&vault, mint), get_associated_token_address(&namespaced_vault, mint)),
+        ]);
+        Self::in_namespace(instruction, namespace)
+    }
+
+    fn replace_accounts(mut instruction: Instruction, replacements: &[(Pubkey, Pubkey)]) -> Instruction {
+        let policy_len = match TokenInstruction::unpack(&instruction.data) {
+            Ok(instr) if instr.is_transfer() => Self::TRANSFER_POLICY_LEN,
+            _ => 0,
+        };
+        for meta in instruction.accounts.iter_mut().skip(policy_len) {
+            if let Some((_, replacement)) = replacements.iter().find(|(replaced, _)| *replaced == meta.pubkey) {
+                meta.pubkey = *replacement;
+            }
+        }
+        instruction
+    }
+
+    /// Append keeper accounts to a crank instruction, e.g. `ReturnRental`, to credit the execution
+    pub fn with_keeper(mut instruction: Instruction, keeper: Pubkey) -> Instruction {
+        let (keeper_registry, _) = Keeper::find_address(&keeper);
+        instruction.accounts.push(AccountMeta::new_readonly(keeper, true));
+        instruction.accounts.push(AccountMeta::new(keeper_registry, false));
+        instruction
+    }
+}
+
+/// Bytes of compact-u16 length prefix of `len` items in wire format
+fn short_vec_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
     }
 }
 
 #[cfg(test)]
 mod transfer_instruction_test {
+    #[cfg(feature = "instruction-borsh")]
     use borsh::BorshSerialize;
+    #[cfg(feature = "instruction-borsh")]
     use borsh::BorshDeserialize;
+    use solana_program::instruction::AccountMeta;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::{bpf_loader_upgradeable, system_program, sysvar};
+    use spl_associated_token_account::get_associated_token_address;
+    use crate::compute_budget;
+    use crate::error::TransferError;
+    use crate::id;
     use crate::instruction::TokenInstruction;
+    use crate::state::{
+        ApprovalPolicy, AuditLog, Bond, Config, EventStream, FillMode, MerkleDistribution, MilestoneTerms, MintConfig,
+        PayoutBatch, Registry, RegistryMode, SpendingLimit, Stream, TransferHook, Vault,
+    };
 
     const TRANSFER_LAMPORTS: TokenInstruction = TokenInstruction::TransferLamports { amount: 1_234_567 };
     const BINARY_TRANSFER_LAMPORTS: [u8; 9] = [0, 135, 214, 18, 0, 0, 0, 0, 0];
@@ -96,45 +2466,2297 @@ mod transfer_instruction_test {
     const APPROVE_SLP_TOKEN: TokenInstruction = TokenInstruction::ApproveSplToken { amount: 2_222_222 };
     const BINARY_APPROVE_SLP_TOKEN: [u8; 9] = [2, 142, 232, 33, 0, 0, 0, 0, 0];
 
-    #[test]
-    fn when_serialization_transfer_lamports_expect_ok() {
-        test_serialization(&TRANSFER_LAMPORTS, &BINARY_TRANSFER_LAMPORTS);
-    }
+    const POST_BOND: TokenInstruction = TokenInstruction::PostBond { id: 7, amount: 1_000_000 };
+    const BINARY_POST_BOND: [u8; 17] = [3, 7, 0, 0, 0, 0, 0, 0, 0, 64, 66, 15, 0, 0, 0, 0, 0];
 
-    #[test]
-    fn when_deserialization_transfer_lamports_expect_ok() {
-        test_deserialization(&TRANSFER_LAMPORTS, &BINARY_TRANSFER_LAMPORTS);
-    }
+    const REFUND_BOND: TokenInstruction = TokenInstruction::RefundBond { reason: 2 };
+    const BINARY_REFUND_BOND: [u8; 3] = [4, 2, 0];
 
-    #[test]
-    fn when_serialization_transfer_spl_token_expect_ok() {
-        test_serialization(&TRANSFER_SLP_TOKEN, &BINARY_TRANSFER_SLP_TOKEN)
-    }
+    const SLASH_BOND: TokenInstruction = TokenInstruction::SlashBond { amount: 250_000, reason: 3 };
+    const BINARY_SLASH_BOND: [u8; 11] = [5, 144, 208, 3, 0, 0, 0, 0, 0, 3, 0];
 
-    #[test]
-    fn when_deserialization_transfer_spl_token_expect_ok() {
-        test_deserialization(&TRANSFER_SLP_TOKEN, &BINARY_TRANSFER_SLP_TOKEN)
-    }
+    const TIP: TokenInstruction = TokenInstruction::Tip { amount: 5_000 };
+    const BINARY_TIP: [u8; 9] = [6, 136, 19, 0, 0, 0, 0, 0, 0];
 
-    #[test]
-    fn when_serialization_approve_spl_token_expect_ok() {
-        test_serialization(&APPROVE_SLP_TOKEN, &BINARY_APPROVE_SLP_TOKEN)
-    }
+    const CREATE_MATCHING_POOL: TokenInstruction = TokenInstruction::CreateMatchingPool {
+        ratio_bps: 5_000,
+        cap: 1_000,
+        amount: 100_000,
+    };
+    const BINARY_CREATE_MATCHING_POOL: [u8; 19] = [
+        7, 136, 19, 232, 3, 0, 0, 0, 0, 0, 0, 160, 134, 1, 0, 0, 0, 0, 0
+    ];
 
-    #[test]
-    fn when_deserialization_approve_spl_token_expect_ok() {
-        test_deserialization(&APPROVE_SLP_TOKEN, &BINARY_APPROVE_SLP_TOKEN)
-    }
+    const DONATE: TokenInstruction = TokenInstruction::Donate { amount: 600 };
+    const BINARY_DONATE: [u8; 9] = [8, 88, 2, 0, 0, 0, 0, 0, 0];
 
-    fn test_serialization(instr: &TokenInstruction, binary_instr: &[u8]) {
-        let serialized_instruction = instr.try_to_vec().unwrap();
+    const CLOSE_MATCHING_POOL: TokenInstruction = TokenInstruction::CloseMatchingPool;
+    const BINARY_CLOSE_MATCHING_POOL: [u8; 1] = [9];
 
-        assert_eq!(serialized_instruction, binary_instr);
-    }
+    const CONFIGURE_ROUND_UP: TokenInstruction = TokenInstruction::ConfigureRoundUp { granularity: 1_000 };
+    const BINARY_CONFIGURE_ROUND_UP: [u8; 9] = [10, 232, 3, 0, 0, 0, 0, 0, 0];
 
-    fn test_deserialization(instr: &TokenInstruction, binary_instr: &[u8]) {
-        let deserialized_instr = TokenInstruction::try_from_slice(binary_instr).unwrap();
+    const TRANSFER_LAMPORTS_ROUND_UP: TokenInstruction = TokenInstruction::TransferLamportsRoundUp { amount: 2_300 };
+    const BINARY_TRANSFER_LAMPORTS_ROUND_UP: [u8; 9] = [11, 252, 8, 0, 0, 0, 0, 0, 0];
 
-        assert_eq!(&deserialized_instr, instr);
-    }
-}
\ No newline at end of file
+    const WITHDRAW_SAVINGS: TokenInstruction = TokenInstruction::WithdrawSavings { amount: 700 };
+    const BINARY_WITHDRAW_SAVINGS: [u8; 9] = [12, 188, 2, 0, 0, 0, 0, 0, 0];
+
+    const OPEN_NETTING: TokenInstruction = TokenInstruction::OpenNetting { id: 3, period_end: 1_700_000_000 };
+    const BINARY_OPEN_NETTING: [u8; 17] = [13, 3, 0, 0, 0, 0, 0, 0, 0, 0, 241, 83, 101, 0, 0, 0, 0];
+
+    const REGISTER_OBLIGATION: TokenInstruction = TokenInstruction::RegisterObligation { amount: 900 };
+    const BINARY_REGISTER_OBLIGATION: [u8; 9] = [14, 132, 3, 0, 0, 0, 0, 0, 0];
+
+    const SETTLE_NETTING: TokenInstruction = TokenInstruction::SettleNetting;
+    const BINARY_SETTLE_NETTING: [u8; 1] = [15];
+
+    const DEPOSIT_TO_PROGRAM: TokenInstruction = TokenInstruction::DepositToProgram { amount: 1_000 };
+    const BINARY_DEPOSIT_TO_PROGRAM: [u8; 9] = [16, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const TRANSFER_SPL_TOKEN_TO_WALLET: TokenInstruction =
+        TokenInstruction::TransferSplTokenToWallet { amount: 1_234_567 };
+    const BINARY_TRANSFER_SPL_TOKEN_TO_WALLET: [u8; 9] = [18, 135, 214, 18, 0, 0, 0, 0, 0];
+
+    const SET_CONFIG: TokenInstruction = TokenInstruction::SetConfig { strict_ata: true };
+    const BINARY_SET_CONFIG: [u8; 2] = [19, 1];
+
+    const SET_FEATURES: TokenInstruction = TokenInstruction::SetFeatures { features: 0b101 };
+    const BINARY_SET_FEATURES: [u8; 9] = [20, 5, 0, 0, 0, 0, 0, 0, 0];
+
+    const SET_TRANSFER_HOOK: TokenInstruction = TokenInstruction::SetTransferHook {
+        hook_program: Some(Pubkey::new_from_array([7; 32])),
+    };
+    const BINARY_SET_TRANSFER_HOOK: [u8; 34] = [
+        17, 1, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7
+    ];
+
+    const PUBLISH_PAYOUT_ROOT: TokenInstruction =
+        TokenInstruction::PublishPayoutRoot { id: 2, root: [1; 32], leaf_count: 3 };
+    const BINARY_PUBLISH_PAYOUT_ROOT: [u8; 45] = [
+        21, 2, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 3, 0, 0, 0
+    ];
+
+    const BINARY_EXECUTE_PAYOUT: [u8; 49] = [
+        22, 1, 0, 0, 0, 244, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+        2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2
+    ];
+
+    const CREATE_DISTRIBUTOR: TokenInstruction = TokenInstruction::CreateDistributor {
+        id: 3,
+        attestor: Pubkey::new_from_array([9; 32]),
+        amount: 1_000,
+    };
+    const BINARY_CREATE_DISTRIBUTOR: [u8; 49] = [
+        23, 3, 0, 0, 0, 0, 0, 0, 0, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9,
+        9, 9, 9, 9, 9, 9, 9, 9, 232, 3, 0, 0, 0, 0, 0, 0
+    ];
+
+    const CLAIM_DISTRIBUTION: TokenInstruction = TokenInstruction::ClaimDistribution { amount: 500 };
+    const BINARY_CLAIM_DISTRIBUTION: [u8; 9] = [24, 244, 1, 0, 0, 0, 0, 0, 0];
+
+    const SET_ATTESTATION: TokenInstruction = TokenInstruction::SetAttestation {
+        wallet: Pubkey::new_from_array([7; 32]),
+        level: 2,
+        expires_at: 1_700_000_000,
+    };
+    const BINARY_SET_ATTESTATION: [u8; 42] = [
+        25, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+        2, 0, 241, 83, 101, 0, 0, 0, 0
+    ];
+
+    const TRANSFER_SPL_TOKEN_ATTESTED: TokenInstruction =
+        TokenInstruction::TransferSplTokenAttested { amount: 1_000, min_level: 2 };
+    const BINARY_TRANSFER_SPL_TOKEN_ATTESTED: [u8; 10] = [26, 232, 3, 0, 0, 0, 0, 0, 0, 2];
+
+    const TRANSFER_SPL_TOKEN_WITH_TRAVEL_RULE: TokenInstruction =
+        TokenInstruction::TransferSplTokenWithTravelRule { amount: 1_000, data_hash: Some([5; 32]) };
+    const BINARY_TRANSFER_SPL_TOKEN_WITH_TRAVEL_RULE: [u8; 42] = [
+        27, 232, 3, 0, 0, 0, 0, 0, 0, 1, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5,
+        5, 5, 5, 5, 5, 5, 5, 5, 5
+    ];
+
+    const SET_TRAVEL_RULE: TokenInstruction = TokenInstruction::SetTravelRule { required: true, threshold: 1_000 };
+    const BINARY_SET_TRAVEL_RULE: [u8; 10] = [28, 1, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const SET_MINT_CONFIG: TokenInstruction = TokenInstruction::SetMintConfig { soulbound: true };
+    const BINARY_SET_MINT_CONFIG: [u8; 2] = [29, 1];
+
+    const LIST_RENTAL: TokenInstruction = TokenInstruction::ListRental { price: 1_000, duration: 86_400 };
+    const BINARY_LIST_RENTAL: [u8; 17] = [30, 232, 3, 0, 0, 0, 0, 0, 0, 128, 81, 1, 0, 0, 0, 0, 0];
+
+    const RENT_NFT: TokenInstruction = TokenInstruction::RentNft;
+    const BINARY_RENT_NFT: [u8; 1] = [31];
+
+    const RETURN_RENTAL: TokenInstruction = TokenInstruction::ReturnRental;
+    const BINARY_RETURN_RENTAL: [u8; 1] = [32];
+
+    const CANCEL_RENTAL: TokenInstruction = TokenInstruction::CancelRental;
+    const BINARY_CANCEL_RENTAL: [u8; 1] = [33];
+
+    const FRACTIONALIZE_NFT: TokenInstruction = TokenInstruction::FractionalizeNft { shares: 1_000_000 };
+    const BINARY_FRACTIONALIZE_NFT: [u8; 9] = [34, 64, 66, 15, 0, 0, 0, 0, 0];
+
+    const REDEEM_NFT: TokenInstruction = TokenInstruction::RedeemNft;
+    const BINARY_REDEEM_NFT: [u8; 1] = [35];
+
+    const POST_ORDER: TokenInstruction = TokenInstruction::PostOrder {
+        id: 1,
+        sell_amount: 1_000,
+        buy_amount: 3_000,
+        fill_mode: FillMode::ImmediateOrCancel,
+    };
+    const BINARY_POST_ORDER: [u8; 26] = [
+        36, 1, 0, 0, 0, 0, 0, 0, 0, 232, 3, 0, 0, 0, 0, 0, 0, 184, 11, 0, 0, 0, 0, 0, 0, 2
+    ];
+
+    const FILL_ORDER: TokenInstruction = TokenInstruction::FillOrder { amount: 500 };
+    const BINARY_FILL_ORDER: [u8; 9] = [37, 244, 1, 0, 0, 0, 0, 0, 0];
+
+    const CANCEL_ORDER: TokenInstruction = TokenInstruction::CancelOrder;
+    const BINARY_CANCEL_ORDER: [u8; 1] = [38];
+
+    const SET_PRICE: TokenInstruction = TokenInstruction::SetPrice { price: 1_000 };
+    const BINARY_SET_PRICE: [u8; 9] = [39, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const TRANSFER_SPL_TOKEN_PRICE_PROTECTED: TokenInstruction = TokenInstruction::TransferSplTokenPriceProtected {
+        amount: 500,
+        min_price: 900,
+        max_price: 1_100,
+        max_age: 60,
+    };
+    const BINARY_TRANSFER_SPL_TOKEN_PRICE_PROTECTED: [u8; 33] = [
+        40, 244, 1, 0, 0, 0, 0, 0, 0, 132, 3, 0, 0, 0, 0, 0, 0, 76, 4, 0, 0, 0, 0, 0, 0, 60, 0, 0, 0, 0, 0, 0, 0
+    ];
+
+    const REGISTER_KEEPER: TokenInstruction = TokenInstruction::RegisterKeeper;
+    const BINARY_REGISTER_KEEPER: [u8; 1] = [41];
+
+    const SET_KEEPER_BAN: TokenInstruction = TokenInstruction::SetKeeperBan { banned: true };
+    const BINARY_SET_KEEPER_BAN: [u8; 2] = [42, 1];
+
+    const TRANSFER_SPL_TOKEN_AS_DELEGATE: TokenInstruction =
+        TokenInstruction::TransferSplTokenAsDelegate { amount: 800 };
+
+    const TRANSFER_SPL_TOKEN_CHECKED: TokenInstruction =
+        TokenInstruction::TransferSplTokenChecked { amount: 800, decimals: 6 };
+    const BINARY_TRANSFER_SPL_TOKEN_CHECKED: [u8; 10] = [44, 32, 3, 0, 0, 0, 0, 0, 0, 6];
+
+    const BINARY_TRANSFER_LAMPORTS_BATCH: [u8; 21] = [
+        45, 2, 0, 0, 0, 100, 0, 0, 0, 0, 0, 0, 0, 200, 0, 0, 0, 0, 0, 0, 0
+    ];
+
+    const BINARY_TRANSFER_SPL_TOKEN_BATCH: [u8; 13] = [46, 1, 0, 0, 0, 44, 1, 0, 0, 0, 0, 0, 0];
+
+    const INITIALIZE_VAULT: TokenInstruction = TokenInstruction::InitializeVault;
+    const BINARY_INITIALIZE_VAULT: [u8; 1] = [47];
+
+    const DEPOSIT_TO_VAULT: TokenInstruction = TokenInstruction::DepositToVault { amount: 2_500 };
+    const BINARY_DEPOSIT_TO_VAULT: [u8; 9] = [48, 196, 9, 0, 0, 0, 0, 0, 0];
+
+    const WITHDRAW_FROM_VAULT: TokenInstruction = TokenInstruction::WithdrawFromVault { amount: 1_000 };
+    const BINARY_WITHDRAW_FROM_VAULT: [u8; 9] = [49, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const INITIALIZE_ESCROW: TokenInstruction = TokenInstruction::InitializeEscrow {
+        expected_amount: 3_000,
+        expires_at: 1_700_000_000,
+    };
+    const BINARY_INITIALIZE_ESCROW: [u8; 17] = [50, 184, 11, 0, 0, 0, 0, 0, 0, 0, 241, 83, 101, 0, 0, 0, 0];
+
+    const EXCHANGE_ESCROW: TokenInstruction = TokenInstruction::ExchangeEscrow { amount: 1_000 };
+    const BINARY_EXCHANGE_ESCROW: [u8; 9] = [51, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const CANCEL_ESCROW: TokenInstruction = TokenInstruction::CancelEscrow;
+    const BINARY_CANCEL_ESCROW: [u8; 1] = [52];
+
+    const MINT_SPL_TOKEN: TokenInstruction = TokenInstruction::MintSplToken { amount: 5_000 };
+    const BINARY_MINT_SPL_TOKEN: [u8; 9] = [53, 136, 19, 0, 0, 0, 0, 0, 0];
+
+    const BURN_SPL_TOKEN: TokenInstruction = TokenInstruction::BurnSplToken { amount: 700 };
+    const BINARY_BURN_SPL_TOKEN: [u8; 9] = [54, 188, 2, 0, 0, 0, 0, 0, 0];
+
+    const CLOSE_SPL_TOKEN_ACCOUNT: TokenInstruction = TokenInstruction::CloseSplTokenAccount;
+    const BINARY_CLOSE_SPL_TOKEN_ACCOUNT: [u8; 1] = [55];
+
+    const SELF_TEST: TokenInstruction = TokenInstruction::SelfTest;
+    const BINARY_SELF_TEST: [u8; 1] = [56];
+
+    const TRANSFER_SPL_TOKEN_WITH_ATA_CREATION: TokenInstruction =
+        TokenInstruction::TransferSplTokenWithAtaCreation { amount: 2_500 };
+    const BINARY_TRANSFER_SPL_TOKEN_WITH_ATA_CREATION: [u8; 9] = [57, 196, 9, 0, 0, 0, 0, 0, 0];
+
+    const WRAP_SOL: TokenInstruction = TokenInstruction::WrapSol { amount: 1_000_000 };
+    const BINARY_WRAP_SOL: [u8; 9] = [58, 64, 66, 15, 0, 0, 0, 0, 0];
+
+    const UNWRAP_SOL: TokenInstruction = TokenInstruction::UnwrapSol;
+    const BINARY_UNWRAP_SOL: [u8; 1] = [59];
+
+    const INITIALIZE_FEE_CONFIG: TokenInstruction = TokenInstruction::InitializeFeeConfig { fee_bps: 30 };
+    const BINARY_INITIALIZE_FEE_CONFIG: [u8; 3] = [60, 30, 0];
+
+    const UPDATE_FEE_CONFIG: TokenInstruction = TokenInstruction::UpdateFeeConfig { fee_bps: 500 };
+    const BINARY_UPDATE_FEE_CONFIG: [u8; 3] = [61, 244, 1];
+
+    const SET_PAUSED: TokenInstruction = TokenInstruction::SetPaused { paused: true };
+    const BINARY_SET_PAUSED: [u8; 2] = [62, 1];
+
+    const CHECK_DEADLINE: TokenInstruction = TokenInstruction::CheckDeadline { valid_until_slot: 1_000 };
+    const BINARY_CHECK_DEADLINE: [u8; 9] = [63, 232, 3, 0, 0, 0, 0, 0, 0];
+    const BINARY_SPLIT_LAMPORTS: [u8; 21] = [64, 232, 3, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0];
+
+    const CREATE_VESTING: TokenInstruction = TokenInstruction::CreateVesting {
+        total_amount: 1_000,
+        start_ts: 1,
+        cliff_ts: 2,
+        end_ts: 3,
+    };
+    const BINARY_CREATE_VESTING: [u8; 33] = [
+        65, 232, 3, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0,
+    ];
+
+    const CLAIM_VESTED: TokenInstruction = TokenInstruction::ClaimVested;
+    const BINARY_CLAIM_VESTED: [u8; 1] = [66];
+
+    const CANCEL_VESTING: TokenInstruction = TokenInstruction::CancelVesting;
+    const BINARY_CANCEL_VESTING: [u8; 1] = [67];
+
+    const CREATE_SPONSOR_POOL: TokenInstruction = TokenInstruction::CreateSponsorPool {
+        max_per_wallet: 3,
+        amount: 1_000,
+    };
+    const BINARY_CREATE_SPONSOR_POOL: [u8; 11] = [68, 3, 0, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const FUND_SPONSOR_POOL: TokenInstruction = TokenInstruction::FundSponsorPool { amount: 1_000 };
+    const BINARY_FUND_SPONSOR_POOL: [u8; 9] = [69, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const CREATE_SPONSORED_ACCOUNT: TokenInstruction = TokenInstruction::CreateSponsoredAccount;
+    const BINARY_CREATE_SPONSORED_ACCOUNT: [u8; 1] = [70];
+
+    const CLOSE_SPONSOR_POOL: TokenInstruction = TokenInstruction::CloseSponsorPool;
+    const BINARY_CLOSE_SPONSOR_POOL: [u8; 1] = [71];
+
+    const CREATE_STREAM: TokenInstruction = TokenInstruction::CreateStream {
+        deposit: 1_000,
+        rate_per_second: 10,
+        start_ts: 1,
+    };
+    const BINARY_CREATE_STREAM: [u8; 25] = [
+        72, 232, 3, 0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+    ];
+
+    const WITHDRAW_FROM_STREAM: TokenInstruction = TokenInstruction::WithdrawFromStream;
+    const BINARY_WITHDRAW_FROM_STREAM: [u8; 1] = [73];
+
+    const CLOSE_STREAM: TokenInstruction = TokenInstruction::CloseStream;
+    const BINARY_CLOSE_STREAM: [u8; 1] = [74];
+
+    const PARK_SPL_TOKEN: TokenInstruction = TokenInstruction::ParkSplToken { amount: 1_000 };
+    const BINARY_PARK_SPL_TOKEN: [u8; 9] = [75, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const CLAIM_BALANCE: TokenInstruction = TokenInstruction::ClaimBalance;
+    const BINARY_CLAIM_BALANCE: [u8; 1] = [76];
+
+    const REVOKE_DELEGATE: TokenInstruction = TokenInstruction::RevokeDelegate { global: true };
+    const BINARY_REVOKE_DELEGATE: [u8; 2] = [77, 1];
+
+    const REINSTATE_DELEGATE: TokenInstruction = TokenInstruction::ReinstateDelegate { global: false };
+    const BINARY_REINSTATE_DELEGATE: [u8; 2] = [78, 0];
+
+    const REGISTER_RECIPIENT: TokenInstruction = TokenInstruction::RegisterRecipient;
+    const BINARY_REGISTER_RECIPIENT: [u8; 1] = [79];
+
+    const REMOVE_RECIPIENT: TokenInstruction = TokenInstruction::RemoveRecipient;
+    const BINARY_REMOVE_RECIPIENT: [u8; 1] = [80];
+
+    const TRANSFER_LAMPORTS_TO_RECIPIENT: TokenInstruction =
+        TokenInstruction::TransferLamportsToRecipient { amount: 1_000 };
+    const BINARY_TRANSFER_LAMPORTS_TO_RECIPIENT: [u8; 9] = [81, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const TRANSFER_SPL_TOKEN_TO_RECIPIENT: TokenInstruction =
+        TokenInstruction::TransferSplTokenToRecipient { amount: 1_000 };
+    const BINARY_TRANSFER_SPL_TOKEN_TO_RECIPIENT: [u8; 9] = [82, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const SUBMIT_DELIVERABLE: TokenInstruction =
+        TokenInstruction::SubmitDeliverable { index: 1, deliverable_hash: [7; 32] };
+    const BINARY_SUBMIT_DELIVERABLE: [u8; 34] = [
+        84, 1, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7
+    ];
+
+    const RELEASE_MILESTONE: TokenInstruction = TokenInstruction::ReleaseMilestone { index: 1 };
+    const BINARY_RELEASE_MILESTONE: [u8; 2] = [85, 1];
+
+    const CLOSE_CONTRACT: TokenInstruction = TokenInstruction::CloseContract;
+    const BINARY_CLOSE_CONTRACT: [u8; 1] = [86];
+
+    const BINARY_TRANSFER_LAMPORTS_WITH_MEMO: [u8; 18] =
+        [87, 232, 3, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 73, 78, 86, 45, 55];
+    const BINARY_TRANSFER_SPL_TOKEN_WITH_MEMO: [u8; 18] =
+        [88, 232, 3, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 73, 78, 86, 45, 55];
+    const INITIALIZE_AUDIT_LOG: TokenInstruction = TokenInstruction::InitializeAuditLog;
+    const BINARY_INITIALIZE_AUDIT_LOG: [u8; 1] = [89];
+
+    const SWEEP_LAMPORTS: TokenInstruction = TokenInstruction::SweepLamports;
+    const BINARY_SWEEP_LAMPORTS: [u8; 1] = [90];
+
+    const SWEEP_SPL_TOKEN: TokenInstruction = TokenInstruction::SweepSplToken;
+    const BINARY_SWEEP_SPL_TOKEN: [u8; 1] = [91];
+
+    const FREEZE_SPL_TOKEN_ACCOUNT: TokenInstruction = TokenInstruction::FreezeSplTokenAccount;
+    const BINARY_FREEZE_SPL_TOKEN_ACCOUNT: [u8; 1] = [92];
+
+    const THAW_SPL_TOKEN_ACCOUNT: TokenInstruction = TokenInstruction::ThawSplTokenAccount;
+    const BINARY_THAW_SPL_TOKEN_ACCOUNT: [u8; 1] = [93];
+
+    const CACHE_MINT_DECIMALS: TokenInstruction = TokenInstruction::CacheMintDecimals;
+    const BINARY_CACHE_MINT_DECIMALS: [u8; 1] = [94];
+
+    const INITIALIZE_REGISTRY: TokenInstruction = TokenInstruction::InitializeRegistry { mode: RegistryMode::Block };
+    const BINARY_INITIALIZE_REGISTRY: [u8; 2] = [95, 1];
+
+    const ADD_TO_REGISTRY: TokenInstruction =
+        TokenInstruction::AddToRegistry { recipient: Pubkey::new_from_array([5; 32]) };
+    const BINARY_ADD_TO_REGISTRY: [u8; 33] = [
+        96, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5
+    ];
+
+    const REMOVE_FROM_REGISTRY: TokenInstruction =
+        TokenInstruction::RemoveFromRegistry { recipient: Pubkey::new_from_array([5; 32]) };
+    const BINARY_REMOVE_FROM_REGISTRY: [u8; 33] = [
+        97, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5
+    ];
+
+    const BEGIN_EXPORT: TokenInstruction =
+        TokenInstruction::BeginExport { successor: Pubkey::new_from_array([6; 32]) };
+    const BINARY_BEGIN_EXPORT: [u8; 33] = [
+        98, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6
+    ];
+
+    const BEGIN_IMPORT: TokenInstruction =
+        TokenInstruction::BeginImport { predecessor: Pubkey::new_from_array([6; 32]) };
+    const BINARY_BEGIN_IMPORT: [u8; 33] = [
+        99, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6
+    ];
+
+    const EXPORT_VAULT: TokenInstruction = TokenInstruction::ExportVault;
+    const BINARY_EXPORT_VAULT: [u8; 1] = [100];
+
+    const IMPORT_VAULT: TokenInstruction = TokenInstruction::ImportVault;
+    const BINARY_IMPORT_VAULT: [u8; 1] = [101];
+
+    const EXPORT_ESCROW: TokenInstruction = TokenInstruction::ExportEscrow;
+    const BINARY_EXPORT_ESCROW: [u8; 1] = [102];
+
+    const IMPORT_ESCROW: TokenInstruction = TokenInstruction::ImportEscrow;
+    const BINARY_IMPORT_ESCROW: [u8; 1] = [103];
+
+    const SET_SPENDING_LIMIT: TokenInstruction = TokenInstruction::SetSpendingLimit {
+        mint: Pubkey::new_from_array([7; 32]),
+        limit: 1_000,
+        window_seconds: 86_400,
+    };
+    const BINARY_SET_SPENDING_LIMIT: [u8; 49] = [
+        104, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+        232, 3, 0, 0, 0, 0, 0, 0, 128, 81, 1, 0, 0, 0, 0, 0
+    ];
+
+    const INITIALIZE_NAMESPACE: TokenInstruction = TokenInstruction::InitializeNamespace;
+    const BINARY_INITIALIZE_NAMESPACE: [u8; 1] = [105];
+
+    const SET_APPROVAL_POLICY: TokenInstruction = TokenInstruction::SetApprovalPolicy { threshold: 1_000 };
+    const BINARY_SET_APPROVAL_POLICY: [u8; 9] = [106, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const PROPOSE_TRANSFER: TokenInstruction = TokenInstruction::ProposeTransfer { amount: 1_000 };
+    const BINARY_PROPOSE_TRANSFER: [u8; 9] = [107, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const EXECUTE_TRANSFER: TokenInstruction = TokenInstruction::ExecuteTransfer;
+    const BINARY_EXECUTE_TRANSFER: [u8; 1] = [108];
+
+    const CANCEL_TRANSFER: TokenInstruction = TokenInstruction::CancelTransfer;
+    const BINARY_CANCEL_TRANSFER: [u8; 1] = [109];
+
+    const CREATE_MERKLE_DISTRIBUTION: TokenInstruction = TokenInstruction::CreateMerkleDistribution {
+        merkle_root: [7; 32],
+        leaf_count: 3,
+        total_amount: 1_000,
+    };
+    const BINARY_CREATE_MERKLE_DISTRIBUTION: [u8; 45] = [
+        110, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+        3, 0, 0, 0, 232, 3, 0, 0, 0, 0, 0, 0
+    ];
+
+    const BINARY_CLAIM_MERKLE_DISTRIBUTION: [u8; 49] = [
+        111, 1, 0, 0, 0, 244, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0,
+        2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2
+    ];
+
+    const INITIALIZE_MANAGED_MINT: TokenInstruction = TokenInstruction::InitializeManagedMint { supply_cap: 1_000 };
+    const BINARY_INITIALIZE_MANAGED_MINT: [u8; 9] = [115, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const SET_ISSUER: TokenInstruction = TokenInstruction::SetIssuer { roles: 3, allowance: 1_000 };
+    const BINARY_SET_ISSUER: [u8; 10] = [116, 3, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const ISSUE: TokenInstruction = TokenInstruction::Issue { amount: 1_000 };
+    const BINARY_ISSUE: [u8; 9] = [117, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const REDEEM: TokenInstruction = TokenInstruction::Redeem { amount: 1_000 };
+    const BINARY_REDEEM: [u8; 9] = [118, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const BINARY_BATCH_APPROVE: [u8; 21] = [119, 2, 0, 0, 0, 100, 0, 0, 0, 0, 0, 0, 0, 200, 0, 0, 0, 0, 0, 0, 0];
+
+    const CREATE_DIVIDEND: TokenInstruction = TokenInstruction::CreateDividend {
+        snapshot_root: [7; 32],
+        holder_count: 3,
+        snapshot_supply: 3_000,
+        pool_amount: 1_000,
+        claim_deadline: 1_700_000_000,
+    };
+    const BINARY_CREATE_DIVIDEND: [u8; 61] = [
+        121, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+        3, 0, 0, 0, 184, 11, 0, 0, 0, 0, 0, 0, 232, 3, 0, 0, 0, 0, 0, 0, 0, 241, 83, 101, 0, 0, 0, 0
+    ];
+
+    const BINARY_CLAIM_DIVIDEND: [u8; 49] = [
+        122, 1, 0, 0, 0, 220, 5, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0,
+        2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2
+    ];
+
+    const CLAWBACK_DIVIDEND: TokenInstruction = TokenInstruction::ClawbackDividend;
+    const BINARY_CLAWBACK_DIVIDEND: [u8; 1] = [123];
+
+    const BINARY_SET_DEPOSIT_PROGRAM: [u8; 34] = [
+        124, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 1
+    ];
+
+    const BINARY_INITIALIZE_EVENT_STREAM: [u8; 33] = [
+        125, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9
+    ];
+
+    const TRANSFER_LAMPORTS_V2: TokenInstruction = TokenInstruction::TransferLamportsV2 { amount: 1_234_567 };
+    const BINARY_TRANSFER_LAMPORTS_V2: [u8; 9] = [126, 135, 214, 18, 0, 0, 0, 0, 0];
+
+    const TRANSFER_SPL_TOKEN_V2: TokenInstruction = TokenInstruction::TransferSplTokenV2 { amount: 1_111_111 };
+    const BINARY_TRANSFER_SPL_TOKEN_V2: [u8; 9] = [127, 71, 244, 16, 0, 0, 0, 0, 0];
+
+    const BINARY_INITIALIZE_DEPOSIT_SCHEME: [u8; 33] = [
+        112, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9
+    ];
+
+    const SWEEP_DEPOSIT: TokenInstruction = TokenInstruction::SweepDeposit { user_id: 1_000 };
+    const BINARY_SWEEP_DEPOSIT: [u8; 9] = [113, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const SWEEP_DEPOSIT_SPL_TOKEN: TokenInstruction = TokenInstruction::SweepDepositSplToken { user_id: 1_000 };
+    const BINARY_SWEEP_DEPOSIT_SPL_TOKEN: [u8; 9] = [114, 232, 3, 0, 0, 0, 0, 0, 0];
+
+    const BINARY_TRANSFER_SPL_TOKEN_AS_DELEGATE: [u8; 9] = [43, 32, 3, 0, 0, 0, 0, 0, 0];
+
+    #[test]
+    fn when_serialization_transfer_lamports_expect_ok() {
+        test_serialization(&TRANSFER_LAMPORTS, &BINARY_TRANSFER_LAMPORTS);
+    }
+
+    #[test]
+    fn when_deserialization_transfer_lamports_expect_ok() {
+        test_deserialization(&TRANSFER_LAMPORTS, &BINARY_TRANSFER_LAMPORTS);
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_expect_ok() {
+        test_serialization(&TRANSFER_SLP_TOKEN, &BINARY_TRANSFER_SLP_TOKEN)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_expect_ok() {
+        test_deserialization(&TRANSFER_SLP_TOKEN, &BINARY_TRANSFER_SLP_TOKEN)
+    }
+
+    #[test]
+    fn when_serialization_approve_spl_token_expect_ok() {
+        test_serialization(&APPROVE_SLP_TOKEN, &BINARY_APPROVE_SLP_TOKEN)
+    }
+
+    #[test]
+    fn when_deserialization_approve_spl_token_expect_ok() {
+        test_deserialization(&APPROVE_SLP_TOKEN, &BINARY_APPROVE_SLP_TOKEN)
+    }
+
+    #[test]
+    fn when_serialization_post_bond_expect_ok() {
+        test_serialization(&POST_BOND, &BINARY_POST_BOND)
+    }
+
+    #[test]
+    fn when_deserialization_post_bond_expect_ok() {
+        test_deserialization(&POST_BOND, &BINARY_POST_BOND)
+    }
+
+    #[test]
+    fn when_serialization_refund_bond_expect_ok() {
+        test_serialization(&REFUND_BOND, &BINARY_REFUND_BOND)
+    }
+
+    #[test]
+    fn when_deserialization_refund_bond_expect_ok() {
+        test_deserialization(&REFUND_BOND, &BINARY_REFUND_BOND)
+    }
+
+    #[test]
+    fn when_serialization_slash_bond_expect_ok() {
+        test_serialization(&SLASH_BOND, &BINARY_SLASH_BOND)
+    }
+
+    #[test]
+    fn when_deserialization_slash_bond_expect_ok() {
+        test_deserialization(&SLASH_BOND, &BINARY_SLASH_BOND)
+    }
+
+    #[test]
+    fn when_serialization_tip_expect_ok() {
+        test_serialization(&TIP, &BINARY_TIP)
+    }
+
+    #[test]
+    fn when_deserialization_tip_expect_ok() {
+        test_deserialization(&TIP, &BINARY_TIP)
+    }
+
+    #[test]
+    fn when_serialization_create_matching_pool_expect_ok() {
+        test_serialization(&CREATE_MATCHING_POOL, &BINARY_CREATE_MATCHING_POOL)
+    }
+
+    #[test]
+    fn when_deserialization_create_matching_pool_expect_ok() {
+        test_deserialization(&CREATE_MATCHING_POOL, &BINARY_CREATE_MATCHING_POOL)
+    }
+
+    #[test]
+    fn when_serialization_donate_expect_ok() {
+        test_serialization(&DONATE, &BINARY_DONATE)
+    }
+
+    #[test]
+    fn when_deserialization_donate_expect_ok() {
+        test_deserialization(&DONATE, &BINARY_DONATE)
+    }
+
+    #[test]
+    fn when_serialization_close_matching_pool_expect_ok() {
+        test_serialization(&CLOSE_MATCHING_POOL, &BINARY_CLOSE_MATCHING_POOL)
+    }
+
+    #[test]
+    fn when_deserialization_close_matching_pool_expect_ok() {
+        test_deserialization(&CLOSE_MATCHING_POOL, &BINARY_CLOSE_MATCHING_POOL)
+    }
+
+    #[test]
+    fn when_serialization_configure_round_up_expect_ok() {
+        test_serialization(&CONFIGURE_ROUND_UP, &BINARY_CONFIGURE_ROUND_UP)
+    }
+
+    #[test]
+    fn when_deserialization_configure_round_up_expect_ok() {
+        test_deserialization(&CONFIGURE_ROUND_UP, &BINARY_CONFIGURE_ROUND_UP)
+    }
+
+    #[test]
+    fn when_serialization_transfer_lamports_round_up_expect_ok() {
+        test_serialization(&TRANSFER_LAMPORTS_ROUND_UP, &BINARY_TRANSFER_LAMPORTS_ROUND_UP)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_lamports_round_up_expect_ok() {
+        test_deserialization(&TRANSFER_LAMPORTS_ROUND_UP, &BINARY_TRANSFER_LAMPORTS_ROUND_UP)
+    }
+
+    #[test]
+    fn when_serialization_withdraw_savings_expect_ok() {
+        test_serialization(&WITHDRAW_SAVINGS, &BINARY_WITHDRAW_SAVINGS)
+    }
+
+    #[test]
+    fn when_deserialization_withdraw_savings_expect_ok() {
+        test_deserialization(&WITHDRAW_SAVINGS, &BINARY_WITHDRAW_SAVINGS)
+    }
+
+    #[test]
+    fn when_serialization_open_netting_expect_ok() {
+        test_serialization(&OPEN_NETTING, &BINARY_OPEN_NETTING)
+    }
+
+    #[test]
+    fn when_deserialization_open_netting_expect_ok() {
+        test_deserialization(&OPEN_NETTING, &BINARY_OPEN_NETTING)
+    }
+
+    #[test]
+    fn when_serialization_register_obligation_expect_ok() {
+        test_serialization(&REGISTER_OBLIGATION, &BINARY_REGISTER_OBLIGATION)
+    }
+
+    #[test]
+    fn when_deserialization_register_obligation_expect_ok() {
+        test_deserialization(&REGISTER_OBLIGATION, &BINARY_REGISTER_OBLIGATION)
+    }
+
+    #[test]
+    fn when_serialization_settle_netting_expect_ok() {
+        test_serialization(&SETTLE_NETTING, &BINARY_SETTLE_NETTING)
+    }
+
+    #[test]
+    fn when_deserialization_settle_netting_expect_ok() {
+        test_deserialization(&SETTLE_NETTING, &BINARY_SETTLE_NETTING)
+    }
+
+    #[test]
+    fn when_serialization_deposit_to_program_expect_ok() {
+        test_serialization(&DEPOSIT_TO_PROGRAM, &BINARY_DEPOSIT_TO_PROGRAM)
+    }
+
+    #[test]
+    fn when_deserialization_deposit_to_program_expect_ok() {
+        test_deserialization(&DEPOSIT_TO_PROGRAM, &BINARY_DEPOSIT_TO_PROGRAM)
+    }
+
+    #[test]
+    fn when_serialization_set_transfer_hook_expect_ok() {
+        test_serialization(&SET_TRANSFER_HOOK, &BINARY_SET_TRANSFER_HOOK)
+    }
+
+    #[test]
+    fn when_deserialization_set_transfer_hook_expect_ok() {
+        test_deserialization(&SET_TRANSFER_HOOK, &BINARY_SET_TRANSFER_HOOK)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_to_wallet_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_TO_WALLET, &BINARY_TRANSFER_SPL_TOKEN_TO_WALLET)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_to_wallet_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_TO_WALLET, &BINARY_TRANSFER_SPL_TOKEN_TO_WALLET)
+    }
+
+    #[test]
+    fn when_serialization_set_config_expect_ok() {
+        test_serialization(&SET_CONFIG, &BINARY_SET_CONFIG)
+    }
+
+    #[test]
+    fn when_deserialization_set_config_expect_ok() {
+        test_deserialization(&SET_CONFIG, &BINARY_SET_CONFIG)
+    }
+
+    #[test]
+    fn when_serialization_set_features_expect_ok() {
+        test_serialization(&SET_FEATURES, &BINARY_SET_FEATURES)
+    }
+
+    #[test]
+    fn when_deserialization_set_features_expect_ok() {
+        test_deserialization(&SET_FEATURES, &BINARY_SET_FEATURES)
+    }
+
+    #[test]
+    fn when_serialization_publish_payout_root_expect_ok() {
+        test_serialization(&PUBLISH_PAYOUT_ROOT, &BINARY_PUBLISH_PAYOUT_ROOT)
+    }
+
+    #[test]
+    fn when_deserialization_publish_payout_root_expect_ok() {
+        test_deserialization(&PUBLISH_PAYOUT_ROOT, &BINARY_PUBLISH_PAYOUT_ROOT)
+    }
+
+    #[test]
+    fn when_serialization_execute_payout_expect_ok() {
+        test_serialization(&execute_payout(), &BINARY_EXECUTE_PAYOUT)
+    }
+
+    #[test]
+    fn when_deserialization_execute_payout_expect_ok() {
+        test_deserialization(&execute_payout(), &BINARY_EXECUTE_PAYOUT)
+    }
+
+    fn execute_payout() -> TokenInstruction {
+        TokenInstruction::ExecutePayout { index: 1, amount: 500, proof: vec![[2; 32]] }
+    }
+
+    #[test]
+    fn when_serialization_create_distributor_expect_ok() {
+        test_serialization(&CREATE_DISTRIBUTOR, &BINARY_CREATE_DISTRIBUTOR)
+    }
+
+    #[test]
+    fn when_deserialization_create_distributor_expect_ok() {
+        test_deserialization(&CREATE_DISTRIBUTOR, &BINARY_CREATE_DISTRIBUTOR)
+    }
+
+    #[test]
+    fn when_serialization_claim_distribution_expect_ok() {
+        test_serialization(&CLAIM_DISTRIBUTION, &BINARY_CLAIM_DISTRIBUTION)
+    }
+
+    #[test]
+    fn when_deserialization_claim_distribution_expect_ok() {
+        test_deserialization(&CLAIM_DISTRIBUTION, &BINARY_CLAIM_DISTRIBUTION)
+    }
+
+    #[test]
+    fn when_serialization_set_attestation_expect_ok() {
+        test_serialization(&SET_ATTESTATION, &BINARY_SET_ATTESTATION)
+    }
+
+    #[test]
+    fn when_deserialization_set_attestation_expect_ok() {
+        test_deserialization(&SET_ATTESTATION, &BINARY_SET_ATTESTATION)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_attested_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_ATTESTED, &BINARY_TRANSFER_SPL_TOKEN_ATTESTED)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_attested_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_ATTESTED, &BINARY_TRANSFER_SPL_TOKEN_ATTESTED)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_with_travel_rule_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_WITH_TRAVEL_RULE, &BINARY_TRANSFER_SPL_TOKEN_WITH_TRAVEL_RULE)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_with_travel_rule_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_WITH_TRAVEL_RULE, &BINARY_TRANSFER_SPL_TOKEN_WITH_TRAVEL_RULE)
+    }
+
+    #[test]
+    fn when_serialization_set_travel_rule_expect_ok() {
+        test_serialization(&SET_TRAVEL_RULE, &BINARY_SET_TRAVEL_RULE)
+    }
+
+    #[test]
+    fn when_deserialization_set_travel_rule_expect_ok() {
+        test_deserialization(&SET_TRAVEL_RULE, &BINARY_SET_TRAVEL_RULE)
+    }
+
+    #[test]
+    fn when_serialization_set_mint_config_expect_ok() {
+        test_serialization(&SET_MINT_CONFIG, &BINARY_SET_MINT_CONFIG)
+    }
+
+    #[test]
+    fn when_deserialization_set_mint_config_expect_ok() {
+        test_deserialization(&SET_MINT_CONFIG, &BINARY_SET_MINT_CONFIG)
+    }
+
+    #[test]
+    fn when_serialization_list_rental_expect_ok() {
+        test_serialization(&LIST_RENTAL, &BINARY_LIST_RENTAL)
+    }
+
+    #[test]
+    fn when_deserialization_list_rental_expect_ok() {
+        test_deserialization(&LIST_RENTAL, &BINARY_LIST_RENTAL)
+    }
+
+    #[test]
+    fn when_serialization_rent_nft_expect_ok() {
+        test_serialization(&RENT_NFT, &BINARY_RENT_NFT)
+    }
+
+    #[test]
+    fn when_deserialization_rent_nft_expect_ok() {
+        test_deserialization(&RENT_NFT, &BINARY_RENT_NFT)
+    }
+
+    #[test]
+    fn when_serialization_return_rental_expect_ok() {
+        test_serialization(&RETURN_RENTAL, &BINARY_RETURN_RENTAL)
+    }
+
+    #[test]
+    fn when_deserialization_return_rental_expect_ok() {
+        test_deserialization(&RETURN_RENTAL, &BINARY_RETURN_RENTAL)
+    }
+
+    #[test]
+    fn when_serialization_cancel_rental_expect_ok() {
+        test_serialization(&CANCEL_RENTAL, &BINARY_CANCEL_RENTAL)
+    }
+
+    #[test]
+    fn when_deserialization_cancel_rental_expect_ok() {
+        test_deserialization(&CANCEL_RENTAL, &BINARY_CANCEL_RENTAL)
+    }
+
+    #[test]
+    fn when_serialization_fractionalize_nft_expect_ok() {
+        test_serialization(&FRACTIONALIZE_NFT, &BINARY_FRACTIONALIZE_NFT)
+    }
+
+    #[test]
+    fn when_deserialization_fractionalize_nft_expect_ok() {
+        test_deserialization(&FRACTIONALIZE_NFT, &BINARY_FRACTIONALIZE_NFT)
+    }
+
+    #[test]
+    fn when_serialization_redeem_nft_expect_ok() {
+        test_serialization(&REDEEM_NFT, &BINARY_REDEEM_NFT)
+    }
+
+    #[test]
+    fn when_deserialization_redeem_nft_expect_ok() {
+        test_deserialization(&REDEEM_NFT, &BINARY_REDEEM_NFT)
+    }
+
+    #[test]
+    fn when_serialization_post_order_expect_ok() {
+        test_serialization(&POST_ORDER, &BINARY_POST_ORDER)
+    }
+
+    #[test]
+    fn when_deserialization_post_order_expect_ok() {
+        test_deserialization(&POST_ORDER, &BINARY_POST_ORDER)
+    }
+
+    #[test]
+    fn when_serialization_fill_order_expect_ok() {
+        test_serialization(&FILL_ORDER, &BINARY_FILL_ORDER)
+    }
+
+    #[test]
+    fn when_deserialization_fill_order_expect_ok() {
+        test_deserialization(&FILL_ORDER, &BINARY_FILL_ORDER)
+    }
+
+    #[test]
+    fn when_serialization_cancel_order_expect_ok() {
+        test_serialization(&CANCEL_ORDER, &BINARY_CANCEL_ORDER)
+    }
+
+    #[test]
+    fn when_deserialization_cancel_order_expect_ok() {
+        test_deserialization(&CANCEL_ORDER, &BINARY_CANCEL_ORDER)
+    }
+
+    #[test]
+    fn when_serialization_set_price_expect_ok() {
+        test_serialization(&SET_PRICE, &BINARY_SET_PRICE)
+    }
+
+    #[test]
+    fn when_deserialization_set_price_expect_ok() {
+        test_deserialization(&SET_PRICE, &BINARY_SET_PRICE)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_price_protected_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_PRICE_PROTECTED, &BINARY_TRANSFER_SPL_TOKEN_PRICE_PROTECTED)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_price_protected_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_PRICE_PROTECTED, &BINARY_TRANSFER_SPL_TOKEN_PRICE_PROTECTED)
+    }
+
+    #[test]
+    fn when_serialization_register_keeper_expect_ok() {
+        test_serialization(&REGISTER_KEEPER, &BINARY_REGISTER_KEEPER)
+    }
+
+    #[test]
+    fn when_deserialization_register_keeper_expect_ok() {
+        test_deserialization(&REGISTER_KEEPER, &BINARY_REGISTER_KEEPER)
+    }
+
+    #[test]
+    fn when_serialization_set_keeper_ban_expect_ok() {
+        test_serialization(&SET_KEEPER_BAN, &BINARY_SET_KEEPER_BAN)
+    }
+
+    #[test]
+    fn when_deserialization_set_keeper_ban_expect_ok() {
+        test_deserialization(&SET_KEEPER_BAN, &BINARY_SET_KEEPER_BAN)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_as_delegate_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_AS_DELEGATE, &BINARY_TRANSFER_SPL_TOKEN_AS_DELEGATE)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_as_delegate_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_AS_DELEGATE, &BINARY_TRANSFER_SPL_TOKEN_AS_DELEGATE)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_checked_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_CHECKED, &BINARY_TRANSFER_SPL_TOKEN_CHECKED)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_checked_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_CHECKED, &BINARY_TRANSFER_SPL_TOKEN_CHECKED)
+    }
+
+    #[test]
+    fn when_serialization_transfer_lamports_batch_expect_ok() {
+        let transfer_lamports_batch = TokenInstruction::TransferLamportsBatch { amounts: vec![100, 200] };
+        test_serialization(&transfer_lamports_batch, &BINARY_TRANSFER_LAMPORTS_BATCH)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_lamports_batch_expect_ok() {
+        let transfer_lamports_batch = TokenInstruction::TransferLamportsBatch { amounts: vec![100, 200] };
+        test_deserialization(&transfer_lamports_batch, &BINARY_TRANSFER_LAMPORTS_BATCH)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_batch_expect_ok() {
+        let transfer_spl_token_batch = TokenInstruction::TransferSplTokenBatch { amounts: vec![300] };
+        test_serialization(&transfer_spl_token_batch, &BINARY_TRANSFER_SPL_TOKEN_BATCH)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_batch_expect_ok() {
+        let transfer_spl_token_batch = TokenInstruction::TransferSplTokenBatch { amounts: vec![300] };
+        test_deserialization(&transfer_spl_token_batch, &BINARY_TRANSFER_SPL_TOKEN_BATCH)
+    }
+
+    #[test]
+    fn when_serialization_initialize_vault_expect_ok() {
+        test_serialization(&INITIALIZE_VAULT, &BINARY_INITIALIZE_VAULT)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_vault_expect_ok() {
+        test_deserialization(&INITIALIZE_VAULT, &BINARY_INITIALIZE_VAULT)
+    }
+
+    #[test]
+    fn when_serialization_deposit_to_vault_expect_ok() {
+        test_serialization(&DEPOSIT_TO_VAULT, &BINARY_DEPOSIT_TO_VAULT)
+    }
+
+    #[test]
+    fn when_deserialization_deposit_to_vault_expect_ok() {
+        test_deserialization(&DEPOSIT_TO_VAULT, &BINARY_DEPOSIT_TO_VAULT)
+    }
+
+    #[test]
+    fn when_serialization_withdraw_from_vault_expect_ok() {
+        test_serialization(&WITHDRAW_FROM_VAULT, &BINARY_WITHDRAW_FROM_VAULT)
+    }
+
+    #[test]
+    fn when_deserialization_withdraw_from_vault_expect_ok() {
+        test_deserialization(&WITHDRAW_FROM_VAULT, &BINARY_WITHDRAW_FROM_VAULT)
+    }
+
+    #[test]
+    fn when_serialization_initialize_escrow_expect_ok() {
+        test_serialization(&INITIALIZE_ESCROW, &BINARY_INITIALIZE_ESCROW)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_escrow_expect_ok() {
+        test_deserialization(&INITIALIZE_ESCROW, &BINARY_INITIALIZE_ESCROW)
+    }
+
+    #[test]
+    fn when_serialization_exchange_escrow_expect_ok() {
+        test_serialization(&EXCHANGE_ESCROW, &BINARY_EXCHANGE_ESCROW)
+    }
+
+    #[test]
+    fn when_deserialization_exchange_escrow_expect_ok() {
+        test_deserialization(&EXCHANGE_ESCROW, &BINARY_EXCHANGE_ESCROW)
+    }
+
+    #[test]
+    fn when_serialization_cancel_escrow_expect_ok() {
+        test_serialization(&CANCEL_ESCROW, &BINARY_CANCEL_ESCROW)
+    }
+
+    #[test]
+    fn when_deserialization_cancel_escrow_expect_ok() {
+        test_deserialization(&CANCEL_ESCROW, &BINARY_CANCEL_ESCROW)
+    }
+
+    #[test]
+    fn when_serialization_mint_spl_token_expect_ok() {
+        test_serialization(&MINT_SPL_TOKEN, &BINARY_MINT_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_deserialization_mint_spl_token_expect_ok() {
+        test_deserialization(&MINT_SPL_TOKEN, &BINARY_MINT_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_serialization_burn_spl_token_expect_ok() {
+        test_serialization(&BURN_SPL_TOKEN, &BINARY_BURN_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_deserialization_burn_spl_token_expect_ok() {
+        test_deserialization(&BURN_SPL_TOKEN, &BINARY_BURN_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_serialization_close_spl_token_account_expect_ok() {
+        test_serialization(&CLOSE_SPL_TOKEN_ACCOUNT, &BINARY_CLOSE_SPL_TOKEN_ACCOUNT)
+    }
+
+    #[test]
+    fn when_deserialization_close_spl_token_account_expect_ok() {
+        test_deserialization(&CLOSE_SPL_TOKEN_ACCOUNT, &BINARY_CLOSE_SPL_TOKEN_ACCOUNT)
+    }
+
+    #[test]
+    fn when_serialization_self_test_expect_ok() {
+        test_serialization(&SELF_TEST, &BINARY_SELF_TEST)
+    }
+
+    #[test]
+    fn when_deserialization_self_test_expect_ok() {
+        test_deserialization(&SELF_TEST, &BINARY_SELF_TEST)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_with_ata_creation_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_WITH_ATA_CREATION, &BINARY_TRANSFER_SPL_TOKEN_WITH_ATA_CREATION)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_with_ata_creation_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_WITH_ATA_CREATION, &BINARY_TRANSFER_SPL_TOKEN_WITH_ATA_CREATION)
+    }
+
+    #[test]
+    fn when_serialization_wrap_sol_expect_ok() {
+        test_serialization(&WRAP_SOL, &BINARY_WRAP_SOL)
+    }
+
+    #[test]
+    fn when_deserialization_wrap_sol_expect_ok() {
+        test_deserialization(&WRAP_SOL, &BINARY_WRAP_SOL)
+    }
+
+    #[test]
+    fn when_serialization_unwrap_sol_expect_ok() {
+        test_serialization(&UNWRAP_SOL, &BINARY_UNWRAP_SOL)
+    }
+
+    #[test]
+    fn when_deserialization_unwrap_sol_expect_ok() {
+        test_deserialization(&UNWRAP_SOL, &BINARY_UNWRAP_SOL)
+    }
+
+    #[test]
+    fn when_serialization_initialize_fee_config_expect_ok() {
+        test_serialization(&INITIALIZE_FEE_CONFIG, &BINARY_INITIALIZE_FEE_CONFIG)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_fee_config_expect_ok() {
+        test_deserialization(&INITIALIZE_FEE_CONFIG, &BINARY_INITIALIZE_FEE_CONFIG)
+    }
+
+    #[test]
+    fn when_serialization_update_fee_config_expect_ok() {
+        test_serialization(&UPDATE_FEE_CONFIG, &BINARY_UPDATE_FEE_CONFIG)
+    }
+
+    #[test]
+    fn when_deserialization_update_fee_config_expect_ok() {
+        test_deserialization(&UPDATE_FEE_CONFIG, &BINARY_UPDATE_FEE_CONFIG)
+    }
+
+    #[test]
+    fn when_serialization_set_paused_expect_ok() {
+        test_serialization(&SET_PAUSED, &BINARY_SET_PAUSED)
+    }
+
+    #[test]
+    fn when_deserialization_set_paused_expect_ok() {
+        test_deserialization(&SET_PAUSED, &BINARY_SET_PAUSED)
+    }
+
+    #[test]
+    fn when_serialization_check_deadline_expect_ok() {
+        test_serialization(&CHECK_DEADLINE, &BINARY_CHECK_DEADLINE)
+    }
+
+    #[test]
+    fn when_deserialization_check_deadline_expect_ok() {
+        test_deserialization(&CHECK_DEADLINE, &BINARY_CHECK_DEADLINE)
+    }
+
+    #[test]
+    fn when_serialization_split_lamports_expect_ok() {
+        let split_lamports = TokenInstruction::SplitLamports { amount: 1_000, weights: vec![1, 3] };
+        test_serialization(&split_lamports, &BINARY_SPLIT_LAMPORTS)
+    }
+
+    #[test]
+    fn when_deserialization_split_lamports_expect_ok() {
+        let split_lamports = TokenInstruction::SplitLamports { amount: 1_000, weights: vec![1, 3] };
+        test_deserialization(&split_lamports, &BINARY_SPLIT_LAMPORTS)
+    }
+
+    #[test]
+    fn when_serialization_create_vesting_expect_ok() {
+        test_serialization(&CREATE_VESTING, &BINARY_CREATE_VESTING)
+    }
+
+    #[test]
+    fn when_deserialization_create_vesting_expect_ok() {
+        test_deserialization(&CREATE_VESTING, &BINARY_CREATE_VESTING)
+    }
+
+    #[test]
+    fn when_serialization_claim_vested_expect_ok() {
+        test_serialization(&CLAIM_VESTED, &BINARY_CLAIM_VESTED)
+    }
+
+    #[test]
+    fn when_deserialization_claim_vested_expect_ok() {
+        test_deserialization(&CLAIM_VESTED, &BINARY_CLAIM_VESTED)
+    }
+
+    #[test]
+    fn when_serialization_cancel_vesting_expect_ok() {
+        test_serialization(&CANCEL_VESTING, &BINARY_CANCEL_VESTING)
+    }
+
+    #[test]
+    fn when_deserialization_cancel_vesting_expect_ok() {
+        test_deserialization(&CANCEL_VESTING, &BINARY_CANCEL_VESTING)
+    }
+
+    #[test]
+    fn when_serialization_create_sponsor_pool_expect_ok() {
+        test_serialization(&CREATE_SPONSOR_POOL, &BINARY_CREATE_SPONSOR_POOL)
+    }
+
+    #[test]
+    fn when_deserialization_create_sponsor_pool_expect_ok() {
+        test_deserialization(&CREATE_SPONSOR_POOL, &BINARY_CREATE_SPONSOR_POOL)
+    }
+
+    #[test]
+    fn when_serialization_fund_sponsor_pool_expect_ok() {
+        test_serialization(&FUND_SPONSOR_POOL, &BINARY_FUND_SPONSOR_POOL)
+    }
+
+    #[test]
+    fn when_deserialization_fund_sponsor_pool_expect_ok() {
+        test_deserialization(&FUND_SPONSOR_POOL, &BINARY_FUND_SPONSOR_POOL)
+    }
+
+    #[test]
+    fn when_serialization_create_sponsored_account_expect_ok() {
+        test_serialization(&CREATE_SPONSORED_ACCOUNT, &BINARY_CREATE_SPONSORED_ACCOUNT)
+    }
+
+    #[test]
+    fn when_deserialization_create_sponsored_account_expect_ok() {
+        test_deserialization(&CREATE_SPONSORED_ACCOUNT, &BINARY_CREATE_SPONSORED_ACCOUNT)
+    }
+
+    #[test]
+    fn when_serialization_close_sponsor_pool_expect_ok() {
+        test_serialization(&CLOSE_SPONSOR_POOL, &BINARY_CLOSE_SPONSOR_POOL)
+    }
+
+    #[test]
+    fn when_deserialization_close_sponsor_pool_expect_ok() {
+        test_deserialization(&CLOSE_SPONSOR_POOL, &BINARY_CLOSE_SPONSOR_POOL)
+    }
+
+    #[test]
+    fn when_serialization_create_stream_expect_ok() {
+        test_serialization(&CREATE_STREAM, &BINARY_CREATE_STREAM)
+    }
+
+    #[test]
+    fn when_deserialization_create_stream_expect_ok() {
+        test_deserialization(&CREATE_STREAM, &BINARY_CREATE_STREAM)
+    }
+
+    #[test]
+    fn when_serialization_withdraw_from_stream_expect_ok() {
+        test_serialization(&WITHDRAW_FROM_STREAM, &BINARY_WITHDRAW_FROM_STREAM)
+    }
+
+    #[test]
+    fn when_deserialization_withdraw_from_stream_expect_ok() {
+        test_deserialization(&WITHDRAW_FROM_STREAM, &BINARY_WITHDRAW_FROM_STREAM)
+    }
+
+    #[test]
+    fn when_serialization_close_stream_expect_ok() {
+        test_serialization(&CLOSE_STREAM, &BINARY_CLOSE_STREAM)
+    }
+
+    #[test]
+    fn when_deserialization_close_stream_expect_ok() {
+        test_deserialization(&CLOSE_STREAM, &BINARY_CLOSE_STREAM)
+    }
+
+    #[test]
+    fn when_serialization_park_spl_token_expect_ok() {
+        test_serialization(&PARK_SPL_TOKEN, &BINARY_PARK_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_deserialization_park_spl_token_expect_ok() {
+        test_deserialization(&PARK_SPL_TOKEN, &BINARY_PARK_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_serialization_claim_balance_expect_ok() {
+        test_serialization(&CLAIM_BALANCE, &BINARY_CLAIM_BALANCE)
+    }
+
+    #[test]
+    fn when_deserialization_claim_balance_expect_ok() {
+        test_deserialization(&CLAIM_BALANCE, &BINARY_CLAIM_BALANCE)
+    }
+
+    #[test]
+    fn when_serialization_revoke_delegate_expect_ok() {
+        test_serialization(&REVOKE_DELEGATE, &BINARY_REVOKE_DELEGATE)
+    }
+
+    #[test]
+    fn when_deserialization_revoke_delegate_expect_ok() {
+        test_deserialization(&REVOKE_DELEGATE, &BINARY_REVOKE_DELEGATE)
+    }
+
+    #[test]
+    fn when_serialization_reinstate_delegate_expect_ok() {
+        test_serialization(&REINSTATE_DELEGATE, &BINARY_REINSTATE_DELEGATE)
+    }
+
+    #[test]
+    fn when_deserialization_reinstate_delegate_expect_ok() {
+        test_deserialization(&REINSTATE_DELEGATE, &BINARY_REINSTATE_DELEGATE)
+    }
+
+    #[test]
+    fn when_serialization_register_recipient_expect_ok() {
+        test_serialization(&REGISTER_RECIPIENT, &BINARY_REGISTER_RECIPIENT)
+    }
+
+    #[test]
+    fn when_deserialization_register_recipient_expect_ok() {
+        test_deserialization(&REGISTER_RECIPIENT, &BINARY_REGISTER_RECIPIENT)
+    }
+
+    #[test]
+    fn when_serialization_remove_recipient_expect_ok() {
+        test_serialization(&REMOVE_RECIPIENT, &BINARY_REMOVE_RECIPIENT)
+    }
+
+    #[test]
+    fn when_deserialization_remove_recipient_expect_ok() {
+        test_deserialization(&REMOVE_RECIPIENT, &BINARY_REMOVE_RECIPIENT)
+    }
+
+    #[test]
+    fn when_serialization_transfer_lamports_to_recipient_expect_ok() {
+        test_serialization(&TRANSFER_LAMPORTS_TO_RECIPIENT, &BINARY_TRANSFER_LAMPORTS_TO_RECIPIENT)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_lamports_to_recipient_expect_ok() {
+        test_deserialization(&TRANSFER_LAMPORTS_TO_RECIPIENT, &BINARY_TRANSFER_LAMPORTS_TO_RECIPIENT)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_to_recipient_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_TO_RECIPIENT, &BINARY_TRANSFER_SPL_TOKEN_TO_RECIPIENT)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_to_recipient_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_TO_RECIPIENT, &BINARY_TRANSFER_SPL_TOKEN_TO_RECIPIENT)
+    }
+
+    #[test]
+    fn when_serialization_create_contract_expect_ok() {
+        test_serialization(&create_contract(), &binary_create_contract())
+    }
+
+    #[test]
+    fn when_deserialization_create_contract_expect_ok() {
+        test_deserialization(&create_contract(), &binary_create_contract())
+    }
+
+    #[test]
+    fn when_serialization_submit_deliverable_expect_ok() {
+        test_serialization(&SUBMIT_DELIVERABLE, &BINARY_SUBMIT_DELIVERABLE)
+    }
+
+    #[test]
+    fn when_deserialization_submit_deliverable_expect_ok() {
+        test_deserialization(&SUBMIT_DELIVERABLE, &BINARY_SUBMIT_DELIVERABLE)
+    }
+
+    #[test]
+    fn when_serialization_release_milestone_expect_ok() {
+        test_serialization(&RELEASE_MILESTONE, &BINARY_RELEASE_MILESTONE)
+    }
+
+    #[test]
+    fn when_deserialization_release_milestone_expect_ok() {
+        test_deserialization(&RELEASE_MILESTONE, &BINARY_RELEASE_MILESTONE)
+    }
+
+    #[test]
+    fn when_serialization_close_contract_expect_ok() {
+        test_serialization(&CLOSE_CONTRACT, &BINARY_CLOSE_CONTRACT)
+    }
+
+    #[test]
+    fn when_deserialization_close_contract_expect_ok() {
+        test_deserialization(&CLOSE_CONTRACT, &BINARY_CLOSE_CONTRACT)
+    }
+
+    #[test]
+    fn when_serialization_transfer_lamports_with_memo_expect_ok() {
+        let transfer = TokenInstruction::TransferLamportsWithMemo { amount: 1_000, memo: "INV-7".to_string() };
+        test_serialization(&transfer, &BINARY_TRANSFER_LAMPORTS_WITH_MEMO)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_lamports_with_memo_expect_ok() {
+        let transfer = TokenInstruction::TransferLamportsWithMemo { amount: 1_000, memo: "INV-7".to_string() };
+        test_deserialization(&transfer, &BINARY_TRANSFER_LAMPORTS_WITH_MEMO)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_with_memo_expect_ok() {
+        let transfer = TokenInstruction::TransferSplTokenWithMemo { amount: 1_000, memo: "INV-7".to_string() };
+        test_serialization(&transfer, &BINARY_TRANSFER_SPL_TOKEN_WITH_MEMO)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_with_memo_expect_ok() {
+        let transfer = TokenInstruction::TransferSplTokenWithMemo { amount: 1_000, memo: "INV-7".to_string() };
+        test_deserialization(&transfer, &BINARY_TRANSFER_SPL_TOKEN_WITH_MEMO)
+    }
+
+    #[test]
+    fn when_serialization_initialize_audit_log_expect_ok() {
+        test_serialization(&INITIALIZE_AUDIT_LOG, &BINARY_INITIALIZE_AUDIT_LOG)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_audit_log_expect_ok() {
+        test_deserialization(&INITIALIZE_AUDIT_LOG, &BINARY_INITIALIZE_AUDIT_LOG)
+    }
+
+    #[test]
+    fn when_serialization_sweep_lamports_expect_ok() {
+        test_serialization(&SWEEP_LAMPORTS, &BINARY_SWEEP_LAMPORTS)
+    }
+
+    #[test]
+    fn when_deserialization_sweep_lamports_expect_ok() {
+        test_deserialization(&SWEEP_LAMPORTS, &BINARY_SWEEP_LAMPORTS)
+    }
+
+    #[test]
+    fn when_serialization_sweep_spl_token_expect_ok() {
+        test_serialization(&SWEEP_SPL_TOKEN, &BINARY_SWEEP_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_deserialization_sweep_spl_token_expect_ok() {
+        test_deserialization(&SWEEP_SPL_TOKEN, &BINARY_SWEEP_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_serialization_freeze_spl_token_account_expect_ok() {
+        test_serialization(&FREEZE_SPL_TOKEN_ACCOUNT, &BINARY_FREEZE_SPL_TOKEN_ACCOUNT)
+    }
+
+    #[test]
+    fn when_deserialization_freeze_spl_token_account_expect_ok() {
+        test_deserialization(&FREEZE_SPL_TOKEN_ACCOUNT, &BINARY_FREEZE_SPL_TOKEN_ACCOUNT)
+    }
+
+    #[test]
+    fn when_serialization_thaw_spl_token_account_expect_ok() {
+        test_serialization(&THAW_SPL_TOKEN_ACCOUNT, &BINARY_THAW_SPL_TOKEN_ACCOUNT)
+    }
+
+    #[test]
+    fn when_deserialization_thaw_spl_token_account_expect_ok() {
+        test_deserialization(&THAW_SPL_TOKEN_ACCOUNT, &BINARY_THAW_SPL_TOKEN_ACCOUNT)
+    }
+
+    #[test]
+    fn when_serialization_cache_mint_decimals_expect_ok() {
+        test_serialization(&CACHE_MINT_DECIMALS, &BINARY_CACHE_MINT_DECIMALS)
+    }
+
+    #[test]
+    fn when_deserialization_cache_mint_decimals_expect_ok() {
+        test_deserialization(&CACHE_MINT_DECIMALS, &BINARY_CACHE_MINT_DECIMALS)
+    }
+
+    #[test]
+    fn when_serialization_initialize_registry_expect_ok() {
+        test_serialization(&INITIALIZE_REGISTRY, &BINARY_INITIALIZE_REGISTRY)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_registry_expect_ok() {
+        test_deserialization(&INITIALIZE_REGISTRY, &BINARY_INITIALIZE_REGISTRY)
+    }
+
+    #[test]
+    fn when_serialization_add_to_registry_expect_ok() {
+        test_serialization(&ADD_TO_REGISTRY, &BINARY_ADD_TO_REGISTRY)
+    }
+
+    #[test]
+    fn when_deserialization_add_to_registry_expect_ok() {
+        test_deserialization(&ADD_TO_REGISTRY, &BINARY_ADD_TO_REGISTRY)
+    }
+
+    #[test]
+    fn when_serialization_remove_from_registry_expect_ok() {
+        test_serialization(&REMOVE_FROM_REGISTRY, &BINARY_REMOVE_FROM_REGISTRY)
+    }
+
+    #[test]
+    fn when_deserialization_remove_from_registry_expect_ok() {
+        test_deserialization(&REMOVE_FROM_REGISTRY, &BINARY_REMOVE_FROM_REGISTRY)
+    }
+
+    #[test]
+    fn when_serialization_begin_export_expect_ok() {
+        test_serialization(&BEGIN_EXPORT, &BINARY_BEGIN_EXPORT)
+    }
+
+    #[test]
+    fn when_deserialization_begin_export_expect_ok() {
+        test_deserialization(&BEGIN_EXPORT, &BINARY_BEGIN_EXPORT)
+    }
+
+    #[test]
+    fn when_serialization_begin_import_expect_ok() {
+        test_serialization(&BEGIN_IMPORT, &BINARY_BEGIN_IMPORT)
+    }
+
+    #[test]
+    fn when_deserialization_begin_import_expect_ok() {
+        test_deserialization(&BEGIN_IMPORT, &BINARY_BEGIN_IMPORT)
+    }
+
+    #[test]
+    fn when_serialization_export_vault_expect_ok() {
+        test_serialization(&EXPORT_VAULT, &BINARY_EXPORT_VAULT)
+    }
+
+    #[test]
+    fn when_deserialization_export_vault_expect_ok() {
+        test_deserialization(&EXPORT_VAULT, &BINARY_EXPORT_VAULT)
+    }
+
+    #[test]
+    fn when_serialization_import_vault_expect_ok() {
+        test_serialization(&IMPORT_VAULT, &BINARY_IMPORT_VAULT)
+    }
+
+    #[test]
+    fn when_deserialization_import_vault_expect_ok() {
+        test_deserialization(&IMPORT_VAULT, &BINARY_IMPORT_VAULT)
+    }
+
+    #[test]
+    fn when_serialization_export_escrow_expect_ok() {
+        test_serialization(&EXPORT_ESCROW, &BINARY_EXPORT_ESCROW)
+    }
+
+    #[test]
+    fn when_deserialization_export_escrow_expect_ok() {
+        test_deserialization(&EXPORT_ESCROW, &BINARY_EXPORT_ESCROW)
+    }
+
+    #[test]
+    fn when_serialization_import_escrow_expect_ok() {
+        test_serialization(&IMPORT_ESCROW, &BINARY_IMPORT_ESCROW)
+    }
+
+    #[test]
+    fn when_deserialization_import_escrow_expect_ok() {
+        test_deserialization(&IMPORT_ESCROW, &BINARY_IMPORT_ESCROW)
+    }
+
+    #[test]
+    fn when_serialization_set_spending_limit_expect_ok() {
+        test_serialization(&SET_SPENDING_LIMIT, &BINARY_SET_SPENDING_LIMIT)
+    }
+
+    #[test]
+    fn when_deserialization_set_spending_limit_expect_ok() {
+        test_deserialization(&SET_SPENDING_LIMIT, &BINARY_SET_SPENDING_LIMIT)
+    }
+
+    #[test]
+    fn when_serialization_initialize_namespace_expect_ok() {
+        test_serialization(&INITIALIZE_NAMESPACE, &BINARY_INITIALIZE_NAMESPACE)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_namespace_expect_ok() {
+        test_deserialization(&INITIALIZE_NAMESPACE, &BINARY_INITIALIZE_NAMESPACE)
+    }
+
+    #[test]
+    fn when_serialization_set_approval_policy_expect_ok() {
+        test_serialization(&SET_APPROVAL_POLICY, &BINARY_SET_APPROVAL_POLICY)
+    }
+
+    #[test]
+    fn when_deserialization_set_approval_policy_expect_ok() {
+        test_deserialization(&SET_APPROVAL_POLICY, &BINARY_SET_APPROVAL_POLICY)
+    }
+
+    #[test]
+    fn when_serialization_propose_transfer_expect_ok() {
+        test_serialization(&PROPOSE_TRANSFER, &BINARY_PROPOSE_TRANSFER)
+    }
+
+    #[test]
+    fn when_deserialization_propose_transfer_expect_ok() {
+        test_deserialization(&PROPOSE_TRANSFER, &BINARY_PROPOSE_TRANSFER)
+    }
+
+    #[test]
+    fn when_serialization_execute_transfer_expect_ok() {
+        test_serialization(&EXECUTE_TRANSFER, &BINARY_EXECUTE_TRANSFER)
+    }
+
+    #[test]
+    fn when_deserialization_execute_transfer_expect_ok() {
+        test_deserialization(&EXECUTE_TRANSFER, &BINARY_EXECUTE_TRANSFER)
+    }
+
+    #[test]
+    fn when_serialization_cancel_transfer_expect_ok() {
+        test_serialization(&CANCEL_TRANSFER, &BINARY_CANCEL_TRANSFER)
+    }
+
+    #[test]
+    fn when_deserialization_cancel_transfer_expect_ok() {
+        test_deserialization(&CANCEL_TRANSFER, &BINARY_CANCEL_TRANSFER)
+    }
+
+    #[test]
+    fn when_serialization_create_merkle_distribution_expect_ok() {
+        test_serialization(&CREATE_MERKLE_DISTRIBUTION, &BINARY_CREATE_MERKLE_DISTRIBUTION)
+    }
+
+    #[test]
+    fn when_deserialization_create_merkle_distribution_expect_ok() {
+        test_deserialization(&CREATE_MERKLE_DISTRIBUTION, &BINARY_CREATE_MERKLE_DISTRIBUTION)
+    }
+
+    #[test]
+    fn when_serialization_claim_merkle_distribution_expect_ok() {
+        test_serialization(&claim_merkle_distribution(), &BINARY_CLAIM_MERKLE_DISTRIBUTION)
+    }
+
+    #[test]
+    fn when_deserialization_claim_merkle_distribution_expect_ok() {
+        test_deserialization(&claim_merkle_distribution(), &BINARY_CLAIM_MERKLE_DISTRIBUTION)
+    }
+
+    fn claim_merkle_distribution() -> TokenInstruction {
+        TokenInstruction::ClaimMerkleDistribution { index: 1, amount: 500, proof: vec![[2; 32]] }
+    }
+
+    #[test]
+    fn when_serialization_initialize_deposit_scheme_expect_ok() {
+        test_serialization(&initialize_deposit_scheme(), &BINARY_INITIALIZE_DEPOSIT_SCHEME)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_deposit_scheme_expect_ok() {
+        test_deserialization(&initialize_deposit_scheme(), &BINARY_INITIALIZE_DEPOSIT_SCHEME)
+    }
+
+    #[test]
+    fn when_serialization_sweep_deposit_expect_ok() {
+        test_serialization(&SWEEP_DEPOSIT, &BINARY_SWEEP_DEPOSIT)
+    }
+
+    #[test]
+    fn when_deserialization_sweep_deposit_expect_ok() {
+        test_deserialization(&SWEEP_DEPOSIT, &BINARY_SWEEP_DEPOSIT)
+    }
+
+    #[test]
+    fn when_serialization_sweep_deposit_spl_token_expect_ok() {
+        test_serialization(&SWEEP_DEPOSIT_SPL_TOKEN, &BINARY_SWEEP_DEPOSIT_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_deserialization_sweep_deposit_spl_token_expect_ok() {
+        test_deserialization(&SWEEP_DEPOSIT_SPL_TOKEN, &BINARY_SWEEP_DEPOSIT_SPL_TOKEN)
+    }
+
+    #[test]
+    fn when_serialization_initialize_managed_mint_expect_ok() {
+        test_serialization(&INITIALIZE_MANAGED_MINT, &BINARY_INITIALIZE_MANAGED_MINT)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_managed_mint_expect_ok() {
+        test_deserialization(&INITIALIZE_MANAGED_MINT, &BINARY_INITIALIZE_MANAGED_MINT)
+    }
+
+    #[test]
+    fn when_serialization_set_issuer_expect_ok() {
+        test_serialization(&SET_ISSUER, &BINARY_SET_ISSUER)
+    }
+
+    #[test]
+    fn when_deserialization_set_issuer_expect_ok() {
+        test_deserialization(&SET_ISSUER, &BINARY_SET_ISSUER)
+    }
+
+    #[test]
+    fn when_serialization_issue_expect_ok() {
+        test_serialization(&ISSUE, &BINARY_ISSUE)
+    }
+
+    #[test]
+    fn when_deserialization_issue_expect_ok() {
+        test_deserialization(&ISSUE, &BINARY_ISSUE)
+    }
+
+    #[test]
+    fn when_serialization_redeem_expect_ok() {
+        test_serialization(&REDEEM, &BINARY_REDEEM)
+    }
+
+    #[test]
+    fn when_deserialization_redeem_expect_ok() {
+        test_deserialization(&REDEEM, &BINARY_REDEEM)
+    }
+
+    #[test]
+    fn when_serialization_batch_approve_expect_ok() {
+        let batch_approve = TokenInstruction::BatchApprove { amounts: vec![100, 200] };
+        test_serialization(&batch_approve, &BINARY_BATCH_APPROVE)
+    }
+
+    #[test]
+    fn when_deserialization_batch_approve_expect_ok() {
+        let batch_approve = TokenInstruction::BatchApprove { amounts: vec![100, 200] };
+        test_deserialization(&batch_approve, &BINARY_BATCH_APPROVE)
+    }
+
+    fn guard_preceding_instruction() -> (TokenInstruction, Vec<u8>) {
+        let instr = TokenInstruction::GuardPrecedingInstruction {
+            program: Pubkey::new_from_array([1; 32]),
+            accounts_hash: [2; 32],
+            data_hash: [3; 32],
+        };
+        (instr, [&[120][..], &[1; 32], &[2; 32], &[3; 32]].concat())
+    }
+
+    #[test]
+    fn when_serialization_guard_preceding_instruction_expect_ok() {
+        let (instr, binary_instr) = guard_preceding_instruction();
+        test_serialization(&instr, &binary_instr)
+    }
+
+    #[test]
+    fn when_deserialization_guard_preceding_instruction_expect_ok() {
+        let (instr, binary_instr) = guard_preceding_instruction();
+        test_deserialization(&instr, &binary_instr)
+    }
+
+    #[test]
+    fn when_guard_preceding_expect_hashes_of_expected_instruction() {
+        let expected = TokenInstruction::transfer_lamports_v2(Pubkey::new_unique(), Pubkey::new_unique(), 1_000);
+        let mut promoted = expected.clone();
+        promoted.accounts[3].is_signer = true;
+        let mut other_data = expected.clone();
+        other_data.data = TokenInstruction::TransferLamportsV2 { amount: 1_001 }.pack();
+
+        let guard = TokenInstruction::guard_preceding(&expected);
+
+        let (accounts_hash, data_hash) = TokenInstruction::guard_hashes(&expected);
+        let guard_instr = TokenInstruction::GuardPrecedingInstruction { program: id(), accounts_hash, data_hash };
+        assert_eq!(TokenInstruction::unpack(&guard.data).unwrap(), guard_instr);
+        assert_eq!(guard.accounts, vec![AccountMeta::new_readonly(sysvar::instructions::id(), false)]);
+        assert_eq!(TokenInstruction::guard_hashes(&promoted), (accounts_hash, data_hash));
+        assert_ne!(TokenInstruction::guard_hashes(&other_data).1, data_hash);
+    }
+
+    #[test]
+    fn when_serialization_create_dividend_expect_ok() {
+        test_serialization(&CREATE_DIVIDEND, &BINARY_CREATE_DIVIDEND)
+    }
+
+    #[test]
+    fn when_deserialization_create_dividend_expect_ok() {
+        test_deserialization(&CREATE_DIVIDEND, &BINARY_CREATE_DIVIDEND)
+    }
+
+    #[test]
+    fn when_serialization_claim_dividend_expect_ok() {
+        test_serialization(&claim_dividend(), &BINARY_CLAIM_DIVIDEND)
+    }
+
+    #[test]
+    fn when_deserialization_claim_dividend_expect_ok() {
+        test_deserialization(&claim_dividend(), &BINARY_CLAIM_DIVIDEND)
+    }
+
+    fn claim_dividend() -> TokenInstruction {
+        TokenInstruction::ClaimDividend { index: 1, balance: 1_500, proof: vec![[2; 32]] }
+    }
+
+    #[test]
+    fn when_serialization_clawback_dividend_expect_ok() {
+        test_serialization(&CLAWBACK_DIVIDEND, &BINARY_CLAWBACK_DIVIDEND)
+    }
+
+    #[test]
+    fn when_deserialization_clawback_dividend_expect_ok() {
+        test_deserialization(&CLAWBACK_DIVIDEND, &BINARY_CLAWBACK_DIVIDEND)
+    }
+
+    #[test]
+    fn when_serialization_set_deposit_program_expect_ok() {
+        test_serialization(&set_deposit_program(), &BINARY_SET_DEPOSIT_PROGRAM)
+    }
+
+    #[test]
+    fn when_deserialization_set_deposit_program_expect_ok() {
+        test_deserialization(&set_deposit_program(), &BINARY_SET_DEPOSIT_PROGRAM)
+    }
+
+    #[test]
+    fn when_serialization_initialize_event_stream_expect_ok() {
+        test_serialization(&initialize_event_stream(), &BINARY_INITIALIZE_EVENT_STREAM)
+    }
+
+    #[test]
+    fn when_deserialization_initialize_event_stream_expect_ok() {
+        test_deserialization(&initialize_event_stream(), &BINARY_INITIALIZE_EVENT_STREAM)
+    }
+
+    #[test]
+    fn when_serialization_transfer_lamports_v2_expect_ok() {
+        test_serialization(&TRANSFER_LAMPORTS_V2, &BINARY_TRANSFER_LAMPORTS_V2)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_lamports_v2_expect_ok() {
+        test_deserialization(&TRANSFER_LAMPORTS_V2, &BINARY_TRANSFER_LAMPORTS_V2)
+    }
+
+    #[test]
+    fn when_serialization_transfer_spl_token_v2_expect_ok() {
+        test_serialization(&TRANSFER_SPL_TOKEN_V2, &BINARY_TRANSFER_SPL_TOKEN_V2)
+    }
+
+    #[test]
+    fn when_deserialization_transfer_spl_token_v2_expect_ok() {
+        test_deserialization(&TRANSFER_SPL_TOKEN_V2, &BINARY_TRANSFER_SPL_TOKEN_V2)
+    }
+
+    #[test]
+    fn when_build_transfer_v1_expect_accounts_without_transfer_policy() {
+        let (owner, from, to) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = Pubkey::new_unique();
+
+        let instr = TokenInstruction::transfer_spl_token(owner, from, to, mint, 1_000);
+
+        assert_eq!(instr.accounts, vec![
+            AccountMeta::new_readonly(owner, true),
+            AccountMeta::new(from, false),
+            AccountMeta::new(to, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Config::find_address().0, false),
+            AccountMeta::new_readonly(MintConfig::find_address(&mint).0, false),
+        ]);
+        let instr = TokenInstruction::transfer_lamports(owner, to, 1_000);
+        assert_eq!(instr.accounts[0], AccountMeta::new(owner, true));
+        assert_eq!(instr.accounts.len(), 3);
+    }
+
+    #[test]
+    fn when_with_event_stream_expect_event_stream_before_audit_log() {
+        let key = Pubkey::new_unique();
+        let instr = TokenInstruction::transfer_lamports(Pubkey::new_unique(), Pubkey::new_unique(), 1_000);
+
+        let instr = TokenInstruction::with_audit_log(TokenInstruction::with_event_stream(instr, &key));
+
+        let event_stream_index = instr.accounts.len() - 2;
+        assert_eq!(instr.accounts[event_stream_index], AccountMeta::new(EventStream::find_address(&key).0, false));
+        assert_eq!(instr.accounts.last(), Some(&AccountMeta::new(AuditLog::find_address().0, false)));
+    }
+
+    #[test]
+    fn when_with_guard_expect_instructions_sysvar_after_transfer_accounts() {
+        let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instr = TokenInstruction::transfer_spl_token_v2(owner, from, to, mint, 1_000);
+
+        let instr = TokenInstruction::with_guard(instr);
+
+        assert_eq!(instr.accounts[0], AccountMeta::new(SpendingLimit::find_address(&owner, &mint).0, false));
+        let instructions_index = TokenInstruction::TRANSFER_POLICY_LEN + 5;
+        assert_eq!(instr.accounts[instructions_index], AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    }
+
+    fn set_deposit_program() -> TokenInstruction {
+        TokenInstruction::SetDepositProgram { program: Pubkey::new_from_array([9; 32]), allowed: true }
+    }
+
+    fn initialize_event_stream() -> TokenInstruction {
+        TokenInstruction::InitializeEventStream { key: Pubkey::new_from_array([9; 32]) }
+    }
+
+    fn initialize_deposit_scheme() -> TokenInstruction {
+        TokenInstruction::InitializeDepositScheme { omnibus: Pubkey::new_from_array([9; 32]) }
+    }
+
+    #[test]
+    fn when_with_program_data_in_namespace_expect_program_data_before_namespace_config() {
+        let (admin, namespace) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (program_data, _) = Pubkey::find_program_address(&[id().as_ref()], &bpf_loader_upgradeable::id());
+
+        let instr = TokenInstruction::in_namespace(
+            TokenInstruction::with_program_data(TokenInstruction::initialize_registry(admin, RegistryMode::Allow)),
+            &namespace,
+        );
+
+        let (namespace_config, _) = Config::find_namespaced_address(&namespace);
+        assert_eq!(instr.accounts[4], AccountMeta::new_readonly(program_data, false));
+        assert_eq!(instr.accounts[5], AccountMeta::new_readonly(namespace_config, false));
+        assert_eq!(instr.accounts.len(), 6);
+    }
+
+    #[test]
+    fn when_with_audit_log_expect_writable_audit_log_last() {
+        let instr = TokenInstruction::with_fee(
+            TokenInstruction::transfer_lamports_v2(Pubkey::new_unique(), Pubkey::new_unique(), 1_000),
+            Pubkey::new_unique(),
+        );
+
+        let instr = TokenInstruction::with_audit_log(instr);
+
+        assert_eq!(instr.accounts.last(), Some(&AccountMeta::new(AuditLog::find_address().0, false)));
+        assert_eq!(instr.accounts.len(), TokenInstruction::TRANSFER_POLICY_LEN + 6);
+    }
+
+    #[test]
+    fn when_transfer_in_namespace_expect_registry_and_config_of_namespace_in_policy() {
+        let (from, namespace) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instr = TokenInstruction::transfer_lamports_v2(from, Pubkey::new_unique(), 1_000);
+
+        let moved = TokenInstruction::in_namespace(instr.clone(), &namespace);
+
+        let (namespace_config, _) = Config::find_namespaced_address(&namespace);
+        let (registry, _) = Registry::find_namespaced_address(&namespace_config);
+        assert_eq!(moved.accounts[4], AccountMeta::new_readonly(Registry::find_address().0, false));
+        assert_eq!(moved.accounts[5], AccountMeta::new_readonly(Config::find_address().0, false));
+        assert_eq!(moved.accounts[6], AccountMeta::new_readonly(namespace_config, false));
+        assert_eq!(moved.accounts[7], AccountMeta::new_readonly(registry, false));
+        assert_eq!((&moved.accounts[..6], &moved.accounts[8..]), (&instr.accounts[..6], &instr.accounts[8..]));
+    }
+
+    #[test]
+    fn when_set_paused_in_namespace_expect_writable_namespace_config_last() {
+        let namespace = Pubkey::new_unique();
+
+        let instr = TokenInstruction::in_namespace(TokenInstruction::set_paused(Pubkey::new_unique(), true), &namespace);
+
+        let (namespace_config, _) = Config::find_namespaced_address(&namespace);
+        assert_eq!(instr.accounts.last(), Some(&AccountMeta::new(namespace_config, false)));
+        assert_eq!(instr.accounts.len(), 4);
+    }
+
+    #[test]
+    fn when_in_namespace_expect_vault_of_namespace_and_its_config_last() {
+        let (owner, mint, namespace) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (namespace_config, _) = Config::find_namespaced_address(&namespace);
+        let (vault, _) = Vault::find_namespaced_address(&namespace_config, &owner, &mint);
+
+        let instr = TokenInstruction::vault_in_namespace(
+            TokenInstruction::initialize_vault(owner, mint),
+            &namespace,
+            &owner,
+            &mint,
+        );
+
+        assert_eq!(instr.accounts[2].pubkey, vault);
+        assert_eq!(instr.accounts[3].pubkey, get_associated_token_address(&vault, &mint));
+        assert_eq!(instr.accounts[5], AccountMeta::new_readonly(Config::find_address().0, false));
+        assert_eq!(instr.accounts.last(), Some(&AccountMeta::new_readonly(namespace_config, false)));
+        assert_eq!(instr.accounts[0].pubkey, owner);
+    }
+
+    #[test]
+    fn when_transfer_expect_policy_of_authority_first() {
+        let (from, delegate, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instr = TokenInstruction::transfer_lamports_v2(from, Pubkey::new_unique(), 1_000);
+        let batch = TokenInstruction::transfer_lamports_batch(from, &[(Pubkey::new_unique(), 1)]);
+        let as_delegate = TokenInstruction::transfer_spl_token_as_delegate(
+            delegate,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            mint,
+            1_000,
+        );
+        let lamports = Pubkey::default();
+
+        assert_eq!(instr.accounts[0], AccountMeta::new(SpendingLimit::find_address(&from, &lamports).0, false));
+        assert_eq!(instr.accounts[1], AccountMeta::new_readonly(ApprovalPolicy::find_address(&from).0, false));
+        assert_eq!(instr.accounts[2], AccountMeta::new_readonly(TransferHook::find_address(&from).0, false));
+        assert_eq!(instr.accounts[3], AccountMeta::new_readonly(id(), false));
+        assert_eq!(instr.accounts[4], AccountMeta::new_readonly(Registry::find_address().0, false));
+        assert_eq!(instr.accounts[5], AccountMeta::new_readonly(Config::find_address().0, false));
+        assert_eq!(instr.accounts[7], AccountMeta::new_readonly(Registry::find_address().0, false));
+        let policy_len = TokenInstruction::transfer_policy_accounts(&from, &lamports).len();
+        assert_eq!(policy_len, TokenInstruction::TRANSFER_POLICY_LEN);
+        assert_eq!(batch.accounts[0], AccountMeta::new(SpendingLimit::find_address(&from, &lamports).0, false));
+        assert_eq!(as_delegate.accounts[0], AccountMeta::new(SpendingLimit::find_address(&delegate, &mint).0, false));
+    }
+
+    #[test]
+    fn when_with_transfer_hook_expect_hook_program_in_place_of_placeholder() {
+        let (owner, hook_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instr = TokenInstruction::transfer_spl_token_v2(
+            owner,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            1_000,
+        );
+        let len = instr.accounts.len();
+
+        let instr = TokenInstruction::with_transfer_hook(instr, hook_program);
+
+        assert_eq!(instr.accounts[2], AccountMeta::new_readonly(TransferHook::find_address(&owner).0, false));
+        assert_eq!(instr.accounts[3], AccountMeta::new_readonly(hook_program, false));
+        assert_eq!(instr.accounts.len(), len);
+    }
+
+    #[test]
+    fn when_transaction_size_expect_wire_size() {
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instr = TokenInstruction::transfer_lamports_batch(from, &[(to, 1)]);
+
+        // 1 signature, header, 9 keys, blockhash, instruction of 11 accounts and 13 bytes of data
+        let size = 1 + 64 + 3 + (1 + 9 * 32) + 32 + (1 + 1 + (1 + 11) + (1 + 13));
+        assert_eq!(TokenInstruction::transaction_size(&[instr], &from), size);
+    }
+
+    #[test]
+    fn when_build_batched_transfers_expect_largest_sets_fitting_transaction() {
+        let from = Pubkey::new_unique();
+        let transfers: Vec<(Pubkey, u64)> = (0..100).map(|amount| (Pubkey::new_unique(), amount)).collect();
+
+        let sets = TokenInstruction::build_batched_transfers(from, &transfers, None);
+
+        let mut rest = &transfers[..];
+        for instructions in &sets {
+            assert_eq!(instructions.len(), 1);
+            let len = instructions[0].accounts.len() - TokenInstruction::TRANSFER_POLICY_LEN - 2;
+            assert_eq!(instructions[0], TokenInstruction::transfer_lamports_batch(from, &rest[..len]));
+            assert!(TokenInstruction::transaction_size(instructions, &from) <= TokenInstruction::MAX_TRANSACTION_SIZE);
+            if len < rest.len() {
+                let longer = TokenInstruction::transfer_lamports_batch(from, &rest[..len + 1]);
+                assert!(TokenInstruction::transaction_size(&[longer], &from) > TokenInstruction::MAX_TRANSACTION_SIZE);
+            }
+            rest = &rest[len..];
+        }
+        assert!(rest.is_empty());
+        assert_eq!(sets.len(), 5);
+    }
+
+    #[test]
+    fn when_build_batched_spl_transfers_with_price_expect_compute_budget_first() {
+        let (from, from_spl_token, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let transfers: Vec<(Pubkey, u64)> = (0..40).map(|amount| (Pubkey::new_unique(), amount)).collect();
+
+        let sets = TokenInstruction::build_batched_spl_transfers(from, from_spl_token, mint, &transfers, Some(5_000));
+
+        let mut sent = 0;
+        for instructions in &sets {
+            let len = instructions[2].accounts.len() - TokenInstruction::TRANSFER_POLICY_LEN - 4;
+            let units = TokenInstruction::BATCH_BASE_UNITS + TokenInstruction::BATCH_UNITS_PER_RECIPIENT * len as u32;
+            assert_eq!(instructions[0], compute_budget::set_compute_unit_limit(units));
+            assert_eq!(instructions[1], compute_budget::set_compute_unit_price(5_000));
+            assert!(TokenInstruction::transaction_size(instructions, &from) <= TokenInstruction::MAX_TRANSACTION_SIZE);
+            sent += len;
+        }
+        assert_eq!(sent, transfers.len());
+        assert!(TokenInstruction::build_batched_transfers(from, &[], Some(5_000)).is_empty());
+    }
+
+    #[test]
+    fn when_build_batched_approvals_expect_pairs_of_token_account_and_delegate() {
+        let owner = Pubkey::new_unique();
+        let approvals: Vec<(Pubkey, Pubkey, u64)> =
+            (0..60).map(|amount| (Pubkey::new_unique(), Pubkey::new_unique(), amount)).collect();
+
+        let sets = TokenInstruction::build_batched_approvals(owner, &approvals, None);
+
+        let mut rest = &approvals[..];
+        for instructions in &sets {
+            let len = (instructions[0].accounts.len() - 2) / 2;
+            assert_eq!(instructions[0], TokenInstruction::batch_approve(owner, &rest[..len]));
+            assert_eq!(instructions[0].accounts[2], AccountMeta::new(rest[0].0, false));
+            assert_eq!(instructions[0].accounts[3], AccountMeta::new_readonly(rest[0].1, false));
+            let size = TokenInstruction::transaction_size(instructions, &owner);
+            assert!(size <= TokenInstruction::MAX_TRANSACTION_SIZE);
+            rest = &rest[len..];
+        }
+        assert!(rest.is_empty());
+        assert!(sets.len() > 1);
+    }
+
+    #[test]
+    fn when_instruction_with_amount_expect_amount() {
+        assert_eq!(TRANSFER_LAMPORTS.amount(), Some(1_234_567));
+        assert_eq!(TokenInstruction::SlashBond { amount: 5, reason: 1 }.amount(), Some(5));
+        assert_eq!(SET_PAUSED.amount(), None);
+    }
+
+    #[test]
+    fn when_memo_transfer_expect_memo_program_before_optional_accounts() {
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instr = TokenInstruction::transfer_lamports_with_memo(from, to, 1_000, "INV-7".to_string());
+        let instr = TokenInstruction::with_fee(instr, Pubkey::new_unique());
+
+        let memo_index = TokenInstruction::TRANSFER_POLICY_LEN + 3;
+        assert_eq!(instr.accounts[memo_index], AccountMeta::new_readonly(spl_memo::id(), false));
+        assert_eq!(instr.accounts.len(), TokenInstruction::TRANSFER_POLICY_LEN + 6);
+    }
+
+    fn create_contract() -> TokenInstruction {
+        let milestones = vec![MilestoneTerms { amount: 1_000, deliverable_hash: [7; 32] }];
+        TokenInstruction::CreateContract { id: 1, milestones }
+    }
+
+    fn binary_create_contract() -> Vec<u8> {
+        let mut binary = vec![83, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 232, 3, 0, 0, 0, 0, 0, 0];
+        binary.extend_from_slice(&[7; 32]);
+        binary
+    }
+
+    #[test]
+    fn when_transfer_instruction_expect_pausable() {
+        assert!(TRANSFER_LAMPORTS_V2.is_transfer());
+        assert!(TRANSFER_SPL_TOKEN_V2.is_transfer());
+        assert!(TRANSFER_SPL_TOKEN_WITH_TRAVEL_RULE.is_transfer());
+        assert!(TRANSFER_SPL_TOKEN_TO_RECIPIENT.is_transfer());
+        assert!(SWEEP_SPL_TOKEN.is_transfer());
+        assert!(EXECUTE_TRANSFER.is_transfer());
+        assert!(!TRANSFER_LAMPORTS.is_transfer());
+        assert!(!TRANSFER_SLP_TOKEN.is_transfer());
+        assert!(!SET_PAUSED.is_transfer());
+        assert!(!SET_TRAVEL_RULE.is_transfer());
+    }
+
+    #[test]
+    fn when_build_post_bond_expect_accounts_in_processor_order() {
+        let (depositor, arbiter, beneficiary) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instr = TokenInstruction::post_bond(depositor, arbiter, beneficiary, 7, 1_000_000);
+
+        assert_eq!(instr.program_id, crate::id());
+        assert_eq!(instr.data, BINARY_POST_BOND);
+        assert_eq!(instr.accounts, vec![
+            AccountMeta::new(depositor, true),
+            AccountMeta::new_readonly(arbiter, false),
+            AccountMeta::new_readonly(beneficiary, false),
+            AccountMeta::new(Bond::find_address(&depositor, 7).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(Config::find_address().0, false),
+        ]);
+    }
+
+    #[test]
+    fn when_with_multisig_expect_owner_not_signer_and_signers_appended() {
+        let (owner, from_spl_token, delegate) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let signers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let instr = TokenInstruction::with_multisig(
+            TokenInstruction::approve_spl_token(owner, from_spl_token, delegate, 1_000),
+            &signers,
+        );
+
+        assert_eq!(instr.accounts[0], AccountMeta::new_readonly(owner, false));
+        assert_eq!(instr.accounts[4..], [
+            AccountMeta::new_readonly(signers[0], true),
+            AccountMeta::new_readonly(signers[1], true),
+        ]);
+    }
+
+    #[test]
+    fn when_with_stream_token_expect_stream_token_accounts_appended() {
+        let (sender, recipient, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let sender_token = Pubkey::new_unique();
+        let instr = TokenInstruction::with_stream_token(
+            TokenInstruction::create_stream(sender, recipient, 1_000, 10, 1),
+            mint,
+            &[sender_token],
+        );
+
+        let (stream, _) = Stream::find_address(&sender, &recipient);
+        assert_eq!(instr.accounts[5..], [
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(get_associated_token_address(&stream, &mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(sender_token, false),
+            AccountMeta::new_readonly(MintConfig::find_address(&mint).0, false),
+        ]);
+    }
+
+    #[test]
+    fn when_last_instruction_expect_tag_count() {
+        assert_eq!(BINARY_TRANSFER_SPL_TOKEN_V2[0] + 1, TokenInstruction::TAG_COUNT)
+    }
+
+    #[test]
+    fn when_v2_with_trailing_bytes_expect_trailing_ignored() {
+        let mut binary_instr = TRANSFER_LAMPORTS.pack_versioned();
+        binary_instr.extend_from_slice(&[1, 2, 3]);
+
+        assert_eq!(binary_instr[..3], [TokenInstruction::VERSION_MARKER, 2, BINARY_TRANSFER_LAMPORTS[0]]);
+        assert_eq!(TokenInstruction::unpack(&binary_instr).unwrap(), TRANSFER_LAMPORTS);
+        assert_eq!(TokenInstruction::tag(&binary_instr), Some(BINARY_TRANSFER_LAMPORTS[0]));
+    }
+
+    #[test]
+    fn when_v1_with_trailing_bytes_expect_error() {
+        let binary_instr = [&BINARY_TRANSFER_LAMPORTS[..], &[1]].concat();
+
+        let result = TokenInstruction::unpack(&binary_instr);
+
+        assert_eq!(result, Err(TransferError::TrailingInstructionData.into()));
+    }
+
+    #[test]
+    fn when_field_too_long_expect_error() {
+        let batch = TokenInstruction::TransferLamportsBatch { amounts: vec![1; TokenInstruction::MAX_BATCH_LEN] };
+        let long_batch = TokenInstruction::TransferLamportsBatch {
+            amounts: vec![1; TokenInstruction::MAX_BATCH_LEN + 1],
+        };
+        let long_memo = TokenInstruction::TransferSplTokenWithMemo {
+            amount: 1,
+            memo: "7".repeat(TokenInstruction::MAX_MEMO_LEN + 1),
+        };
+
+        assert_eq!(TokenInstruction::unpack(&batch.pack()), Ok(batch));
+        for instr in [long_batch, long_memo] {
+            let result = TokenInstruction::unpack(&instr.pack_versioned());
+
+            assert_eq!(result, Err(TransferError::InstructionFieldTooLong.into()));
+        }
+    }
+
+    #[test]
+    fn when_proof_deeper_than_payout_tree_expect_error() {
+        let proof = vec![[0; 32]; TokenInstruction::MAX_PROOF_LEN + 1];
+        let payout = TokenInstruction::ExecutePayout { index: 0, amount: 1, proof };
+
+        let result = TokenInstruction::unpack(&payout.pack());
+
+        assert_eq!(result, Err(TransferError::InstructionFieldTooLong.into()));
+        assert_eq!(1 << TokenInstruction::MAX_PROOF_LEN, PayoutBatch::MAX_LEAVES);
+    }
+
+    #[test]
+    fn when_proof_deeper_than_distribution_tree_expect_error() {
+        let proof = vec![[0; 32]; TokenInstruction::MAX_DISTRIBUTION_PROOF_LEN + 1];
+        let claim = TokenInstruction::ClaimMerkleDistribution { index: 0, amount: 1, proof };
+
+        let result = TokenInstruction::unpack(&claim.pack());
+
+        assert_eq!(result, Err(TransferError::InstructionFieldTooLong.into()));
+        assert_eq!(1 << TokenInstruction::MAX_DISTRIBUTION_PROOF_LEN, MerkleDistribution::MAX_LEAVES);
+    }
+
+    #[test]
+    fn when_field_tag_not_written_by_pack_expect_non_canonical() {
+        let mut binary_instr = TokenInstruction::SetConfig { strict_ata: true }.pack();
+        *binary_instr.last_mut().unwrap() = 2;
+
+        let result = TokenInstruction::unpack(&binary_instr);
+
+        assert_eq!(result, Err(TransferError::NonCanonicalInstructionData.into()));
+    }
+
+    #[test]
+    fn when_unknown_tag_expect_error() {
+        let result = TokenInstruction::unpack(&[TokenInstruction::TAG_COUNT]);
+
+        assert_eq!(result, Err(TransferError::InvalidInstructionData.into()));
+    }
+
+    #[test]
+    fn when_v2_truncated_expect_error() {
+        let binary_instr = TRANSFER_LAMPORTS.pack_versioned();
+
+        let result = TokenInstruction::unpack(&binary_instr[..binary_instr.len() - 1]);
+
+        assert_eq!(result, Err(TransferError::InvalidInstructionData.into()));
+    }
+
+    #[test]
+    fn when_unknown_version_expect_error() {
+        let binary_instr = [&[TokenInstruction::VERSION_MARKER, 3][..], &BINARY_TRANSFER_LAMPORTS[..]].concat();
+
+        let result = TokenInstruction::unpack(&binary_instr);
+
+        assert_eq!(result, Err(TransferError::UnsupportedInstructionVersion.into()));
+        assert_eq!(TokenInstruction::tag(&binary_instr), None);
+    }
+
+    #[test]
+    fn when_version_marker_or_empty_expect_no_tag() {
+        assert_eq!(TokenInstruction::tag(&[TokenInstruction::VERSION_MARKER]), None);
+        assert_eq!(TokenInstruction::tag(&[]), None);
+    }
+
+    fn test_serialization(instr: &TokenInstruction, binary_instr: &[u8]) {
+        let serialized_instruction = instr.pack();
+        let versioned_instruction = instr.pack_versioned();
+
+        assert_eq!(serialized_instruction, binary_instr);
+        assert_eq!(versioned_instruction[2..], *binary_instr);
+        assert_eq!(&TokenInstruction::unpack(&versioned_instruction).unwrap(), instr);
+        #[cfg(feature = "instruction-borsh")]
+        assert_eq!(instr.try_to_vec().unwrap(), binary_instr);
+    }
+
+    fn test_deserialization(instr: &TokenInstruction, binary_instr: &[u8]) {
+        let unpacked_instr = TokenInstruction::unpack(binary_instr).unwrap();
+
+        assert_eq!(&unpacked_instr, instr);
+        #[cfg(feature = "instruction-borsh")]
+        assert_eq!(&TokenInstruction::try_from_slice(binary_instr).unwrap(), instr);
+    }
+}
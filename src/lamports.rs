@@ -0,0 +1,142 @@
+//! Lamport moves out of accounts owned by this program. The runtime lets the owner debit an account directly,
+//! while a System program transfer only debits system owned accounts, so program owned balances move without CPI,
+//! which is also cheaper.
+//!
+//! Every move checks that the program owns the source and that both balances add up to the same total
+//! afterwards, which also rejects moving an account to itself.
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use crate::error::TransferError;
+use crate::event::{ErrorContextEvent, ErrorValue};
+
+/// Move `amount` from program owned `from` to `to`, `from` must stay rent exempt
+pub fn transfer(program_id: &Pubkey, from: &AccountInfo, to: &AccountInfo, amount: u64, rent: &Rent) -> ProgramResult {
+    let remaining = match from.lamports().checked_sub(amount) {
+        Some(remaining) => remaining,
+        None => {
+            let (expected, actual) = (ErrorValue::Amount(amount), ErrorValue::Amount(from.lamports()));
+            return Err(ErrorContextEvent::fail(TransferError::InsufficientFunds, None, Some(expected), Some(actual)))
+        }
+    };
+    if !rent.is_exempt(remaining, from.data_len()) {
+        let minimum = ErrorValue::Amount(rent.minimum_balance(from.data_len()));
+        let error = TransferError::BelowRentExemption;
+        return Err(ErrorContextEvent::fail(error, None, Some(minimum), Some(ErrorValue::Amount(remaining))))
+    }
+    move_lamports(program_id, from, to, amount)
+}
+
+/// Move every lamport of program owned `acc` to `to` and wipe its data, the runtime then deletes the account
+pub fn close(program_id: &Pubkey, acc: &AccountInfo, to: &AccountInfo) -> ProgramResult {
+    move_lamports(program_id, acc, to, acc.lamports())?;
+    acc.try_borrow_mut_data()?.fill(0);
+    Ok(())
+}
+
+fn move_lamports(program_id: &Pubkey, from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    if from.owner != program_id {
+        return Err(TransferError::InvalidOwner.into())
+    }
+    let total = from.lamports().checked_add(to.lamports()).ok_or(TransferError::AmountOverflow)?;
+    let from_lamports = from.lamports().checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    let to_lamports = to.lamports().checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+    **from.try_borrow_mut_lamports()? = from_lamports;
+    **to.try_borrow_mut_lamports()? = to_lamports;
+    if from.lamports().checked_add(to.lamports()) != Some(total) {
+        return Err(TransferError::LamportsNotConserved.into())
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod lamports_test {
+    use solana_program::account_info::AccountInfo;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::rent::Rent;
+    use crate::error::TransferError;
+    use crate::lamports::{close, transfer};
+
+    #[test]
+    fn when_transfer_expect_balances_moved() {
+        let (program_id, from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rent = Rent::default();
+        let (mut from_lamports, mut from_data) = (rent.minimum_balance(8) + 1_000, vec![0; 8]);
+        let (mut to_lamports, mut to_data) = (500, vec![]);
+        let from = AccountInfo::new(&from_key, false, true, &mut from_lamports, &mut from_data, &program_id, false, 0);
+        let to = AccountInfo::new(&to_key, false, true, &mut to_lamports, &mut to_data, &to_key, false, 0);
+
+        transfer(&program_id, &from, &to, 1_000, &rent).unwrap();
+
+        assert_eq!(from.lamports(), rent.minimum_balance(8));
+        assert_eq!(to.lamports(), 1_500);
+    }
+
+    #[test]
+    fn when_transfer_below_rent_exemption_expect_error() {
+        let (program_id, from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rent = Rent::default();
+        let (mut from_lamports, mut from_data) = (rent.minimum_balance(8) + 1_000, vec![0; 8]);
+        let (mut to_lamports, mut to_data) = (0, vec![]);
+        let from = AccountInfo::new(&from_key, false, true, &mut from_lamports, &mut from_data, &program_id, false, 0);
+        let to = AccountInfo::new(&to_key, false, true, &mut to_lamports, &mut to_data, &to_key, false, 0);
+
+        let result = transfer(&program_id, &from, &to, 1_001, &rent);
+
+        assert_eq!(result, Err(TransferError::BelowRentExemption.into()));
+        assert_eq!(to.lamports(), 0);
+    }
+
+    #[test]
+    fn when_source_not_owned_expect_error() {
+        let (program_id, from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut from_lamports, mut from_data) = (1_000, vec![]);
+        let (mut to_lamports, mut to_data) = (0, vec![]);
+        let from = AccountInfo::new(&from_key, false, true, &mut from_lamports, &mut from_data, &from_key, false, 0);
+        let to = AccountInfo::new(&to_key, false, true, &mut to_lamports, &mut to_data, &to_key, false, 0);
+
+        let result = close(&program_id, &from, &to);
+
+        assert_eq!(result, Err(TransferError::InvalidOwner.into()));
+        assert_eq!(from.lamports(), 1_000);
+    }
+
+    #[test]
+    fn when_transfer_to_itself_expect_not_conserved() {
+        let (program_id, key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (1_000_000_000, vec![]);
+        let acc = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+
+        let result = transfer(&program_id, &acc, &acc.clone(), 100, &Rent::default());
+
+        assert_eq!(result, Err(TransferError::LamportsNotConserved.into()));
+    }
+
+    #[test]
+    fn when_credit_overflows_expect_error() {
+        let (program_id, from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut from_lamports, mut from_data) = (1_000, vec![]);
+        let (mut to_lamports, mut to_data) = (u64::MAX - 999, vec![]);
+        let from = AccountInfo::new(&from_key, false, true, &mut from_lamports, &mut from_data, &program_id, false, 0);
+        let to = AccountInfo::new(&to_key, false, true, &mut to_lamports, &mut to_data, &to_key, false, 0);
+
+        let result = close(&program_id, &from, &to);
+
+        assert_eq!(result, Err(TransferError::AmountOverflow.into()));
+    }
+
+    #[test]
+    fn when_close_expect_drained_and_wiped() {
+        let (program_id, acc_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (1_000, vec![7; 8]);
+        let (mut to_lamports, mut to_data) = (500, vec![]);
+        let acc = AccountInfo::new(&acc_key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+        let to = AccountInfo::new(&to_key, false, true, &mut to_lamports, &mut to_data, &to_key, false, 0);
+
+        close(&program_id, &acc, &to).unwrap();
+
+        assert_eq!((acc.lamports(), to.lamports()), (0, 1_500));
+        assert!(acc.try_borrow_data().unwrap().iter().all(|byte| *byte == 0));
+    }
+}
@@ -1,8 +1,26 @@
+#[macro_use]
+mod macros;
+pub mod account;
 pub mod instruction;
+pub mod codec;
 pub mod processor;
 pub mod error;
+pub mod state;
+pub mod event;
+pub mod deposit_interface;
+pub mod hook_interface;
+pub mod eligibility;
+pub mod merkle;
+pub mod split;
+pub mod lamports;
+pub mod compute_budget;
+
+#[cfg(feature = "client")]
+pub mod client;
 
-#[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
+#[cfg(not(feature = "no-entrypoint"))]
+mod security;
 
-solana_program::declare_id!("4ieTTSrJzX1GbW9susJJpLE3bv6kuWqguCjrzYr3jUJ1");
\ No newline at end of file
+// declare_id! and CONFIG_ADMIN generated by build.rs, see TOKEN_PROGRAM_ID and TOKEN_CONFIG_ADMIN
+include!(concat!(env!("OUT_DIR"), "/program_id.rs"));
\ No newline at end of file
@@ -0,0 +1,89 @@
+//! Macros shared by instruction processors.
+
+/// Parse accounts of the instruction from `$iter` over `$accounts` in declared order and validate their roles.
+///
+/// Roles are checked once every account is parsed, so role arguments may refer to any account:
+/// - `signer` - account signed the transaction, see `SignerAccount`
+/// - `writable` - account is writable, see `WritableAccount`
+/// - `program(id)` - account key is `id`, see `InvokedProgram`
+/// - `token_program` - account is the SPL token program, otherwise `TransferError::InvalidTokenProgram`
+/// - `token_account(mint)` - SPL token account of `mint`, see `TokenAccount`,
+///   otherwise `TransferError::MintMismatch`
+/// - `mint` - initialized SPL token mint, see `MintAccount`
+///
+/// A missing account or failed role is logged as `ErrorContextEvent` with the index of the account,
+/// and expected and actual key where the role compares one. Helpers given only the iterator pass
+/// `accounts!(acc_iter => { .. })` and log no index.
+///
+/// ```ignore
+/// let acc_iter = &mut accounts.iter();
+/// accounts!(acc_iter in accounts => {
+///     owner_acc: signer,
+///     to_spl_token_acc: writable token_account(mint_acc.key),
+///     mint_acc: mint,
+///     spl_token_acc: token_program,
+/// });
+/// ```
+macro_rules! accounts {
+    ($iter:ident in $accounts:ident => { $($body:tt)* }) => {
+        let instr_accs = Some($accounts);
+        accounts!(@parse $iter instr_accs { $($body)* });
+    };
+    ($iter:ident => { $($body:tt)* }) => {
+        let instr_accs: Option<&[solana_program::account_info::AccountInfo]> = None;
+        accounts!(@parse $iter instr_accs { $($body)* });
+    };
+    (@parse $iter:ident $accounts:ident {
+        $($name:ident $(: $($role:ident $(($($arg:expr),*))?)+)?),* $(,)?
+    }) => {
+        $(
+            let $name = match $iter.next() {
+                Some(acc) => acc,
+                None => {
+                    let error = solana_program::program_error::ProgramError::NotEnoughAccountKeys;
+                    let index = $accounts.and_then(|accounts| std::convert::TryFrom::try_from(accounts.len()).ok());
+                    return Err($crate::event::ErrorContextEvent::fail(error, index, None, None))
+                }
+            };
+        )*
+        $($($(
+            accounts!(@check $accounts $name $role $(($($arg),*))?);
+        )+)?)*
+    };
+    (@fail $accounts:ident $acc:ident $error:expr) => {{
+        let index = $accounts.and_then(|accounts| $crate::event::ErrorContextEvent::account_index(accounts, $acc));
+        $crate::event::ErrorContextEvent::fail($error, index, None, None)
+    }};
+    (@fail $accounts:ident $acc:ident $error:expr, $expected:expr, $actual:expr) => {{
+        let index = $accounts.and_then(|accounts| $crate::event::ErrorContextEvent::account_index(accounts, $acc));
+        let (expected, actual) = ($crate::event::ErrorValue::Key($expected), $crate::event::ErrorValue::Key($actual));
+        $crate::event::ErrorContextEvent::fail($error, index, Some(expected), Some(actual))
+    }};
+    (@check $accounts:ident $acc:ident signer) => {
+        $crate::account::SignerAccount::new($acc).map_err(|e| accounts!(@fail $accounts $acc e))?;
+    };
+    (@check $accounts:ident $acc:ident writable) => {
+        $crate::account::WritableAccount::new($acc).map_err(|e| accounts!(@fail $accounts $acc e))?;
+    };
+    (@check $accounts:ident $acc:ident program($id:expr)) => {
+        if let Err(error) = $crate::account::InvokedProgram::new($acc, &$id) {
+            return Err(accounts!(@fail $accounts $acc error, $id, *$acc.key))
+        }
+    };
+    (@check $accounts:ident $acc:ident token_program) => {
+        if *$acc.key != spl_token::id() {
+            let error = $crate::error::TransferError::InvalidTokenProgram;
+            return Err(accounts!(@fail $accounts $acc error, spl_token::id(), *$acc.key))
+        }
+    };
+    (@check $accounts:ident $acc:ident token_account($mint:expr)) => {
+        let token_mint = $crate::account::TokenAccount::new($acc).map_err(|e| accounts!(@fail $accounts $acc e))?.mint;
+        if token_mint != *$mint {
+            let error = $crate::error::TransferError::MintMismatch;
+            return Err(accounts!(@fail $accounts $acc error, *$mint, token_mint))
+        }
+    };
+    (@check $accounts:ident $acc:ident mint) => {
+        $crate::account::MintAccount::new($acc).map_err(|e| accounts!(@fail $accounts $acc e))?;
+    };
+}
@@ -0,0 +1,98 @@
+//! Merkle tree over payout lists, see `PayoutBatch`.
+//!
+//! Leaves and nodes are hashed with distinct prefixes and node children are sorted,
+//! so a proof is just the list of siblings from leaf to root. A level with odd
+//! number of nodes promotes the last one unchanged.
+use solana_program::hash::hashv;
+use solana_program::pubkey::Pubkey;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Leaf of payout `index` of the list
+pub fn payout_leaf(index: u32, recipient: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, &index.to_le_bytes(), recipient.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if left <= right { (left, right) } else { (right, left) };
+    hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+pub fn verify(leaf: [u8; 32], proof: &[[u8; 32]], root: &[u8; 32]) -> bool {
+    proof.iter().fold(leaf, |hash, sibling| node(&hash, sibling)) == *root
+}
+
+/// Root of `leaves`, zeroes for empty list
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied().unwrap_or_default()
+}
+
+/// Siblings of leaf `index` from the bottom up
+pub fn proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = vec![];
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| if let [left, right] = pair { node(left, right) } else { pair[0] })
+        .collect()
+}
+
+#[cfg(test)]
+mod merkle_test {
+    use solana_program::pubkey::Pubkey;
+    use crate::merkle::{payout_leaf, proof, root, verify};
+
+    fn leaves(count: u32) -> Vec<[u8; 32]> {
+        (0..count).map(|index| payout_leaf(index, &Pubkey::new_unique(), 100 + index as u64)).collect()
+    }
+
+    #[test]
+    fn when_proof_of_every_leaf_expect_verified() {
+        for count in [1, 2, 5, 8] {
+            let leaves = leaves(count);
+            let root = root(&leaves);
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                assert!(verify(*leaf, &proof(&leaves, index), &root), "count={}, index={}", count, index);
+            }
+        }
+    }
+
+    #[test]
+    fn when_leaf_amount_changed_expect_not_verified() {
+        let recipient = Pubkey::new_unique();
+        let mut leaves = leaves(4);
+        leaves[2] = payout_leaf(2, &recipient, 1_000);
+        let root = root(&leaves);
+
+        let forged = payout_leaf(2, &recipient, 1_001);
+
+        assert!(!verify(forged, &proof(&leaves, 2), &root));
+    }
+
+    #[test]
+    fn when_leaf_index_changed_expect_not_verified() {
+        let recipient = Pubkey::new_unique();
+        let mut leaves = leaves(4);
+        leaves[1] = payout_leaf(1, &recipient, 1_000);
+        let root = root(&leaves);
+
+        assert!(!verify(payout_leaf(3, &recipient, 1_000), &proof(&leaves, 1), &root));
+    }
+}
@@ -1,99 +1,806 @@
-use borsh::BorshDeserialize;
-use solana_program::account_info::{AccountInfo, next_account_info};
+use std::convert::TryFrom;
+#[cfg(feature = "oracle")]
+use std::ops::RangeInclusive;
+use solana_program::account_info::AccountInfo;
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_program::clock::Clock;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::{msg, system_instruction};
-use solana_program::program::invoke;
+use solana_program::instruction::AccountMeta;
+use solana_program::{msg, system_instruction, system_program, sysvar};
+use solana_program::program::{invoke, invoke_signed};
 use solana_program::program_error::ProgramError;
+use solana_program::program_option::COption;
+#[cfg(feature = "sponsorship")]
+use solana_program::program_pack::Pack;
 use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::sysvar::Sysvar;
+use spl_associated_token_account::get_associated_token_address;
+#[cfg(feature = "escrow")]
+use spl_token::instruction::AuthorityType;
+use crate::account::{MintAccount, ProgramAccount, TokenAccount, WritableAccount};
+use crate::deposit_interface;
+#[cfg(feature = "payouts")]
+use crate::eligibility;
+use crate::hook_interface::{self, TransferHookStage};
+use crate::lamports;
+use crate::split;
 use crate::error::TransferError;
+use crate::CONFIG_ADMIN;
+use crate::event::{
+    ApproveEvent, AttestationSetEvent, DelegateReinstatedEvent, DelegateRevokedEvent, DepositToProgramEvent,
+    ErrorContextEvent, ErrorValue, MintConfigSetEvent, SelfTestPassedEvent, SplTokenAccountFrozenEvent,
+    SplTokenAccountThawedEvent, TokenEvent, TransferLamportsEvent, TransferSplEvent, TravelRuleDataEvent,
+    UnsupportedInstructionEvent,
+};
+#[cfg(feature = "bonds")]
+use crate::event::{BondPostedEvent, BondRefundedEvent, BondSlashedEvent};
+#[cfg(feature = "tips")]
+use crate::event::{DonationMatchedEvent, MatchingPoolClosedEvent, MatchingPoolCreatedEvent, TipEvent};
+#[cfg(feature = "round-up")]
+use crate::event::{RoundUpSavedEvent, SavingsWithdrawnEvent};
+#[cfg(feature = "netting")]
+use crate::event::{NettingOpenedEvent, NettingSettledEvent, ObligationRegisteredEvent};
+#[cfg(feature = "payouts")]
+use crate::event::{
+    DistributionClaimedEvent, DistributorCreatedEvent, MerkleDistributionClaimedEvent, MerkleDistributionCreatedEvent,
+    PayoutExecutedEvent, PayoutRootPublishedEvent,
+};
+#[cfg(feature = "nft")]
+use crate::event::{NftFractionalizedEvent, NftRedeemedEvent, NftRentedEvent, RentalListedEvent, RentalReturnedEvent};
+#[cfg(feature = "orders")]
+use crate::event::{OrderCancelledEvent, OrderFilledEvent, OrderPostedEvent};
+#[cfg(feature = "oracle")]
+use crate::event::PriceUpdatedEvent;
+#[cfg(feature = "keepers")]
+use crate::event::{KeeperBanSetEvent, KeeperRegisteredEvent};
+#[cfg(feature = "vaults")]
+use crate::event::{
+    DepositSchemeInitializedEvent, DepositSweptEvent, VaultDepositedEvent, VaultInitializedEvent, VaultWithdrawnEvent,
+};
+#[cfg(feature = "issuance")]
+use crate::event::{IssuedEvent, IssuerSetEvent, ManagedMintInitializedEvent, RedeemedEvent};
+#[cfg(feature = "escrow")]
+use crate::event::{EscrowCancelledEvent, EscrowExchangedEvent, EscrowInitializedEvent};
+#[cfg(feature = "vesting")]
+use crate::event::{VestingCancelledEvent, VestingClaimedEvent, VestingCreatedEvent};
+#[cfg(feature = "sponsorship")]
+use crate::event::{AccountSponsoredEvent, SponsorPoolClosedEvent, SponsorPoolCreatedEvent, SponsorPoolFundedEvent};
+#[cfg(feature = "streams")]
+use crate::event::{StreamClosedEvent, StreamCreatedEvent, StreamWithdrawnEvent};
+#[cfg(feature = "claimables")]
+use crate::event::{BalanceClaimedEvent, SplTokenParkedEvent};
+#[cfg(feature = "rosters")]
+use crate::event::{RecipientRegisteredEvent, RecipientRemovedEvent};
+#[cfg(feature = "dividends")]
+use crate::event::{DividendClaimedEvent, DividendClawedBackEvent, DividendCreatedEvent};
+#[cfg(feature = "contracts")]
+use crate::event::{ContractClosedEvent, ContractCreatedEvent, DeliverableSubmittedEvent, MilestoneReleasedEvent};
 use crate::instruction::TokenInstruction;
+#[cfg(any(feature = "payouts", feature = "dividends"))]
+use crate::merkle;
+use crate::state::{
+    namespaced_seeds, AccountState, AccountType, ApprovalPolicy, Attestation, AuditLog, Canary, Config, EventStream,
+    FeeConfig, Migration, MintConfig, MintDecimals, PendingTransfer, Registry, RegistryMode, Revocation, SpendingLimit,
+    TransferHook,
+};
+#[cfg(feature = "bonds")]
+use crate::state::Bond;
+#[cfg(feature = "tips")]
+use crate::state::{MatchingPool, TipStats, TipperStats};
+#[cfg(feature = "round-up")]
+use crate::state::SavingsVault;
+#[cfg(feature = "netting")]
+use crate::state::Netting;
+#[cfg(feature = "payouts")]
+use crate::state::{DistributionClaim, Distributor, PayoutBatch};
+#[cfg(any(feature = "payouts", feature = "dividends"))]
+use crate::state::{ClaimBitmap, MerkleDistribution};
+#[cfg(feature = "dividends")]
+use crate::state::Dividend;
+#[cfg(feature = "nft")]
+use crate::state::{Fraction, Rental};
+#[cfg(feature = "orders")]
+use crate::state::{FillMode, Order};
+#[cfg(feature = "oracle")]
+use crate::state::PriceFeed;
+#[cfg(feature = "keepers")]
+use crate::state::Keeper;
+#[cfg(feature = "vaults")]
+use crate::state::{DepositScheme, Vault};
+#[cfg(feature = "issuance")]
+use crate::state::{Issuer, ManagedMint};
+#[cfg(feature = "escrow")]
+use crate::state::Escrow;
+#[cfg(feature = "vesting")]
+use crate::state::VestingSchedule;
+#[cfg(feature = "sponsorship")]
+use crate::state::{SponsorPool, SponsoredWallet};
+#[cfg(feature = "streams")]
+use crate::state::Stream;
+#[cfg(feature = "claimables")]
+use crate::state::ClaimableBalance;
+#[cfg(feature = "rosters")]
+use crate::state::RosterEntry;
+#[cfg(feature = "contracts")]
+use crate::state::{Milestone, MilestoneContract, MilestoneStatus, MilestoneTerms};
 
+/// Fee recipient, fee account and fee of a transfer passing fee config
+type TransferFee<'a, 'b> = (Pubkey, &'b AccountInfo<'a>, u64);
+
+/// Accounts every transfer instruction starts with, see `TokenInstruction::transfer_policy_accounts`
+struct TransferPolicy<'a, 'b> {
+    spending_limit_acc: &'b AccountInfo<'a>,
+    approval_policy_acc: &'b AccountInfo<'a>,
+    transfer_hook_acc: &'b AccountInfo<'a>,
+    hook_program_acc: &'b AccountInfo<'a>,
+    registry_acc: &'b AccountInfo<'a>,
+    config_acc: &'b AccountInfo<'a>,
+    namespace_config_acc: &'b AccountInfo<'a>,
+    namespace_registry_acc: &'b AccountInfo<'a>,
+}
+
+/// Stream token account and SPL token program of custom token stream
+#[cfg(feature = "streams")]
+type StreamTokenAccounts<'a, 'b> = (&'b AccountInfo<'a>, &'b AccountInfo<'a>);
 
 pub struct Processor;
 
 impl Processor {
 
     pub fn process(
-        _program_id: &Pubkey,
+        program_id: &Pubkey,
         accounts: &[AccountInfo],
         input: &[u8],
     ) -> ProgramResult {
-        msg!("input {:?}", input);
-        let instr = TokenInstruction::try_from_slice(input)?;
+        let tag = TokenInstruction::tag(input).unwrap_or_default();
+        if tag >= TokenInstruction::TAG_COUNT {
+            msg!("Unsupported instruction tag {}", tag);
+            TokenEvent::UnsupportedInstruction(UnsupportedInstructionEvent { tag }).emit(None)?;
+            return Err(TransferError::UnsupportedInstruction.into());
+        }
+        let instr = TokenInstruction::unpack(input)?;
+        let (accounts, audit_log_acc) = Self::split_last(program_id, accounts, AccountType::AuditLog);
+        let (instr_accs, event_stream_acc) = Self::split_last(program_id, accounts, AccountType::EventStream);
+        let amount = instr.amount();
+        Self::dispatch(program_id, instr_accs, event_stream_acc, instr, tag)?;
+        if let Some(audit_log_acc) = audit_log_acc {
+            Self::fold_audit_log(program_id, audit_log_acc, tag, accounts, amount)?;
+        }
+        Ok(())
+    }
+
+    fn dispatch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        instr: TokenInstruction,
+        tag: u8,
+    ) -> ProgramResult {
+        let accounts = if instr.is_transfer() {
+            let (policy, _) = Self::split_transfer_policy(accounts)?;
+            Self::check_transfer_not_paused(program_id, &policy)?;
+            Self::split_guard(program_id, &instr, accounts)?
+        } else if matches!(
+            instr,
+            TokenInstruction::TransferLamports { .. } | TokenInstruction::TransferSplToken { .. }
+        ) {
+            // First versions predate transfer policy accounts but are guarded alike, see `with_guard`
+            Self::split_guard(program_id, &instr, accounts)?
+        } else {
+            accounts
+        };
         match instr {
-            TokenInstruction::TransferLamports { amount } => Self::transfer_lamports(accounts, amount),
-            TokenInstruction::TransferSplToken { amount } => Self::transfer_spl_token(accounts, amount),
-            TokenInstruction::ApproveSplToken { amount } => Self::approve_spl_token(accounts, amount),
+            TokenInstruction::TransferLamports { amount } => {
+                Self::transfer_lamports(program_id, accounts, event_stream_acc, None, Some(amount), None)
+            }
+            TokenInstruction::TransferSplToken { amount } => {
+                Self::transfer_spl_token(program_id, accounts, event_stream_acc, None, Some(amount), None, None)
+            }
+            TokenInstruction::TransferLamportsV2 { amount } => {
+                let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+                Self::transfer_lamports(program_id, accounts, event_stream_acc, Some(policy), Some(amount), None)
+            }
+            TokenInstruction::TransferSplTokenV2 { amount } => {
+                let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+                let amount = Some(amount);
+                Self::transfer_spl_token(program_id, accounts, event_stream_acc, Some(policy), amount, None, None)
+            }
+            TokenInstruction::ApproveSplToken { amount } => {
+                Self::approve_spl_token(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "bonds")]
+            TokenInstruction::PostBond { id, amount } => {
+                Self::post_bond(program_id, accounts, event_stream_acc, id, amount)
+            }
+            #[cfg(feature = "bonds")]
+            TokenInstruction::RefundBond { reason } => {
+                Self::refund_bond(program_id, accounts, event_stream_acc, reason)
+            }
+            #[cfg(feature = "bonds")]
+            TokenInstruction::SlashBond { amount, reason } => {
+                Self::slash_bond(program_id, accounts, event_stream_acc, amount, reason)
+            }
+            #[cfg(feature = "tips")]
+            TokenInstruction::Tip { amount } => Self::tip(program_id, accounts, event_stream_acc, amount),
+            #[cfg(feature = "tips")]
+            TokenInstruction::CreateMatchingPool { ratio_bps, cap, amount } => {
+                Self::create_matching_pool(program_id, accounts, event_stream_acc, ratio_bps, cap, amount)
+            }
+            #[cfg(feature = "tips")]
+            TokenInstruction::Donate { amount } => Self::donate(program_id, accounts, event_stream_acc, amount),
+            #[cfg(feature = "tips")]
+            TokenInstruction::CloseMatchingPool => Self::close_matching_pool(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "round-up")]
+            TokenInstruction::ConfigureRoundUp { granularity } => {
+                Self::configure_round_up(program_id, accounts, granularity)
+            }
+            #[cfg(feature = "round-up")]
+            TokenInstruction::TransferLamportsRoundUp { amount } => {
+                Self::transfer_lamports_round_up(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "round-up")]
+            TokenInstruction::WithdrawSavings { amount } => {
+                Self::withdraw_savings(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "netting")]
+            TokenInstruction::OpenNetting { id, period_end } => {
+                Self::open_netting(program_id, accounts, event_stream_acc, id, period_end)
+            }
+            #[cfg(feature = "netting")]
+            TokenInstruction::RegisterObligation { amount } => {
+                Self::register_obligation(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "netting")]
+            TokenInstruction::SettleNetting => Self::settle_netting(program_id, accounts, event_stream_acc),
+            TokenInstruction::DepositToProgram { amount } => {
+                Self::deposit_to_program(program_id, accounts, event_stream_acc, amount)
+            }
+            TokenInstruction::SetTransferHook { hook_program } => {
+                Self::set_transfer_hook(program_id, accounts, hook_program)
+            }
+            TokenInstruction::TransferSplTokenToWallet { amount } => {
+                Self::transfer_spl_token_to_wallet(program_id, accounts, event_stream_acc, amount)
+            }
+            TokenInstruction::SetConfig { strict_ata } => Self::set_config(program_id, accounts, strict_ata),
+            TokenInstruction::SetFeatures { features } => Self::set_features(program_id, accounts, features),
+            #[cfg(feature = "payouts")]
+            TokenInstruction::PublishPayoutRoot { id, root, leaf_count } => {
+                Self::publish_payout_root(program_id, accounts, event_stream_acc, id, root, leaf_count)
+            }
+            #[cfg(feature = "payouts")]
+            TokenInstruction::ExecutePayout { index, amount, proof } => {
+                Self::execute_payout(program_id, accounts, event_stream_acc, index, amount, &proof)
+            }
+            #[cfg(feature = "payouts")]
+            TokenInstruction::CreateDistributor { id, attestor, amount } => {
+                Self::create_distributor(program_id, accounts, event_stream_acc, id, attestor, amount)
+            }
+            #[cfg(feature = "payouts")]
+            TokenInstruction::ClaimDistribution { amount } => {
+                Self::claim_distribution(program_id, accounts, event_stream_acc, amount)
+            }
+            TokenInstruction::SetAttestation { wallet, level, expires_at } => {
+                Self::set_attestation(program_id, accounts, event_stream_acc, wallet, level, expires_at)
+            }
+            TokenInstruction::TransferSplTokenAttested { amount, min_level } => {
+                Self::transfer_spl_token_attested(program_id, accounts, event_stream_acc, amount, min_level)
+            }
+            TokenInstruction::TransferSplTokenWithTravelRule { amount, data_hash } => {
+                let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+                let amount = Some(amount);
+                Self::transfer_spl_token(program_id, accounts, event_stream_acc, Some(policy), amount, data_hash, None)
+            }
+            TokenInstruction::SetTravelRule { required, threshold } => {
+                Self::set_travel_rule(program_id, accounts, required, threshold)
+            }
+            TokenInstruction::SetMintConfig { soulbound } => {
+                Self::set_mint_config(program_id, accounts, event_stream_acc, soulbound)
+            }
+            #[cfg(feature = "nft")]
+            TokenInstruction::ListRental { price, duration } => {
+                Self::list_rental(program_id, accounts, event_stream_acc, price, duration)
+            }
+            #[cfg(feature = "nft")]
+            TokenInstruction::RentNft => Self::rent_nft(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "nft")]
+            TokenInstruction::ReturnRental => Self::return_rental(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "nft")]
+            TokenInstruction::CancelRental => Self::cancel_rental(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "nft")]
+            TokenInstruction::FractionalizeNft { shares } => {
+                Self::fractionalize_nft(program_id, accounts, event_stream_acc, shares)
+            }
+            #[cfg(feature = "nft")]
+            TokenInstruction::RedeemNft => Self::redeem_nft(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "orders")]
+            TokenInstruction::PostOrder { id, sell_amount, buy_amount, fill_mode } => {
+                Self::post_order(program_id, accounts, event_stream_acc, id, sell_amount, buy_amount, fill_mode)
+            }
+            #[cfg(feature = "orders")]
+            TokenInstruction::FillOrder { amount } => Self::fill_order(program_id, accounts, event_stream_acc, amount),
+            #[cfg(feature = "orders")]
+            TokenInstruction::CancelOrder => Self::cancel_order(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "oracle")]
+            TokenInstruction::SetPrice { price } => Self::set_price(program_id, accounts, event_stream_acc, price),
+            #[cfg(feature = "oracle")]
+            TokenInstruction::TransferSplTokenPriceProtected { amount, min_price, max_price, max_age } => {
+                let band = min_price..=max_price;
+                Self::transfer_spl_token_price_protected(program_id, accounts, event_stream_acc, amount, band, max_age)
+            }
+            #[cfg(feature = "keepers")]
+            TokenInstruction::RegisterKeeper => Self::register_keeper(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "keepers")]
+            TokenInstruction::SetKeeperBan { banned } => {
+                Self::set_keeper_ban(program_id, accounts, event_stream_acc, banned)
+            }
+            TokenInstruction::TransferSplTokenAsDelegate { amount } => {
+                Self::transfer_spl_token_as_delegate(program_id, accounts, event_stream_acc, amount)
+            }
+            TokenInstruction::TransferSplTokenChecked { amount, decimals } => {
+                Self::transfer_spl_token_checked(program_id, accounts, event_stream_acc, amount, decimals)
+            }
+            TokenInstruction::TransferLamportsBatch { amounts } => {
+                Self::transfer_lamports_batch(program_id, accounts, event_stream_acc, &amounts)
+            }
+            TokenInstruction::TransferSplTokenBatch { amounts } => {
+                Self::transfer_spl_token_batch(program_id, accounts, event_stream_acc, &amounts)
+            }
+            #[cfg(feature = "vaults")]
+            TokenInstruction::InitializeVault => Self::initialize_vault(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "vaults")]
+            TokenInstruction::DepositToVault { amount } => {
+                Self::deposit_to_vault(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "vaults")]
+            TokenInstruction::WithdrawFromVault { amount } => {
+                Self::withdraw_from_vault(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "escrow")]
+            TokenInstruction::InitializeEscrow { expected_amount, expires_at } => {
+                Self::initialize_escrow(program_id, accounts, event_stream_acc, expected_amount, expires_at)
+            }
+            #[cfg(feature = "escrow")]
+            TokenInstruction::ExchangeEscrow { amount } => {
+                Self::exchange_escrow(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "escrow")]
+            TokenInstruction::CancelEscrow => Self::cancel_escrow(program_id, accounts, event_stream_acc),
+            TokenInstruction::MintSplToken { amount } => Self::mint_spl_token(accounts, amount),
+            TokenInstruction::BurnSplToken { amount } => Self::burn_spl_token(accounts, amount),
+            TokenInstruction::CloseSplTokenAccount => Self::close_spl_token_account(accounts),
+            TokenInstruction::SelfTest => Self::self_test(program_id, accounts, event_stream_acc),
+            TokenInstruction::TransferSplTokenWithAtaCreation { amount } => {
+                Self::transfer_spl_token_with_ata_creation(program_id, accounts, event_stream_acc, amount)
+            }
+            TokenInstruction::WrapSol { amount } => Self::wrap_sol(accounts, amount),
+            TokenInstruction::UnwrapSol => Self::unwrap_sol(accounts),
+            TokenInstruction::InitializeFeeConfig { fee_bps } => {
+                Self::initialize_fee_config(program_id, accounts, fee_bps)
+            }
+            TokenInstruction::UpdateFeeConfig { fee_bps } => Self::update_fee_config(program_id, accounts, fee_bps),
+            TokenInstruction::SetPaused { paused } => Self::set_paused(program_id, accounts, paused),
+            TokenInstruction::CheckDeadline { valid_until_slot } => Self::check_deadline(valid_until_slot),
+            TokenInstruction::SplitLamports { amount, weights } => {
+                Self::split_lamports(program_id, accounts, event_stream_acc, amount, &weights)
+            }
+            #[cfg(feature = "vesting")]
+            TokenInstruction::CreateVesting { total_amount, start_ts, cliff_ts, end_ts } => {
+                Self::create_vesting(program_id, accounts, event_stream_acc, total_amount, start_ts, cliff_ts, end_ts)
+            }
+            #[cfg(feature = "vesting")]
+            TokenInstruction::ClaimVested => Self::claim_vested(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "vesting")]
+            TokenInstruction::CancelVesting => Self::cancel_vesting(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "sponsorship")]
+            TokenInstruction::CreateSponsorPool { max_per_wallet, amount } => {
+                Self::create_sponsor_pool(program_id, accounts, event_stream_acc, max_per_wallet, amount)
+            }
+            #[cfg(feature = "sponsorship")]
+            TokenInstruction::FundSponsorPool { amount } => {
+                Self::fund_sponsor_pool(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "sponsorship")]
+            TokenInstruction::CreateSponsoredAccount => {
+                Self::create_sponsored_account(program_id, accounts, event_stream_acc)
+            }
+            #[cfg(feature = "sponsorship")]
+            TokenInstruction::CloseSponsorPool => Self::close_sponsor_pool(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "streams")]
+            TokenInstruction::CreateStream { deposit, rate_per_second, start_ts } => {
+                Self::create_stream(program_id, accounts, event_stream_acc, deposit, rate_per_second, start_ts)
+            }
+            #[cfg(feature = "streams")]
+            TokenInstruction::WithdrawFromStream => Self::withdraw_from_stream(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "streams")]
+            TokenInstruction::CloseStream => Self::close_stream(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "claimables")]
+            TokenInstruction::ParkSplToken { amount } => {
+                Self::park_spl_token(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "claimables")]
+            TokenInstruction::ClaimBalance => Self::claim_balance(program_id, accounts, event_stream_acc),
+            TokenInstruction::RevokeDelegate { global } => {
+                Self::revoke_delegate(program_id, accounts, event_stream_acc, global)
+            }
+            TokenInstruction::ReinstateDelegate { global } => {
+                Self::reinstate_delegate(program_id, accounts, event_stream_acc, global)
+            }
+            #[cfg(feature = "rosters")]
+            TokenInstruction::RegisterRecipient => Self::register_recipient(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "rosters")]
+            TokenInstruction::RemoveRecipient => Self::remove_recipient(program_id, accounts, event_stream_acc),
+            #[cfg(feature = "rosters")]
+            TokenInstruction::TransferLamportsToRecipient { amount } => {
+                Self::transfer_lamports_to_recipient(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "rosters")]
+            TokenInstruction::TransferSplTokenToRecipient { amount } => {
+                Self::transfer_spl_token_to_recipient(program_id, accounts, event_stream_acc, amount)
+            }
+            #[cfg(feature = "contracts")]
+            TokenInstruction::CreateContract { id, milestones } => {
+                Self::create_contract(program_id, accounts, event_stream_acc, id, &milestones)
+            }
+            #[cfg(feature = "contracts")]
+            TokenInstruction::SubmitDeliverable { index, deliverable_hash } => {
+                Self::submit_deliverable(program_id, accounts, event_stream_acc, index, deliverable_hash)
+            }
+            #[cfg(feature = "contracts")]
+            TokenInstruction::ReleaseMilestone { index } => {
+                Self::release_milestone(program_id, accounts, event_stream_acc, index)
+            }
+            #[cfg(feature = "contracts")]
+            TokenInstruction::CloseContract => Self::close_contract(program_id, accounts, event_stream_acc),
+            TokenInstruction::TransferLamportsWithMemo { amount, memo } => {
+                let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+                Self::transfer_lamports(program_id, accounts, event_stream_acc, Some(policy), Some(amount), Some(&memo))
+            }
+            TokenInstruction::TransferSplTokenWithMemo { amount, memo } => {
+                let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+                let (amount, memo) = (Some(amount), Some(memo.as_str()));
+                Self::transfer_spl_token(program_id, accounts, event_stream_acc, Some(policy), amount, None, memo)
+            }
+            TokenInstruction::InitializeAuditLog => Self::initialize_audit_log(program_id, accounts),
+            TokenInstruction::SweepLamports => {
+                let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+                Self::transfer_lamports(program_id, accounts, event_stream_acc, Some(policy), None, None)
+            }
+            TokenInstruction::SweepSplToken => {
+                let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+                Self::transfer_spl_token(program_id, accounts, event_stream_acc, Some(policy), None, None, None)
+            }
+            TokenInstruction::FreezeSplTokenAccount => {
+                Self::set_spl_token_account_frozen(program_id, accounts, event_stream_acc, true)
+            }
+            TokenInstruction::ThawSplTokenAccount => {
+                Self::set_spl_token_account_frozen(program_id, accounts, event_stream_acc, false)
+            }
+            TokenInstruction::CacheMintDecimals => Self::cache_mint_decimals(program_id, accounts),
+            TokenInstruction::InitializeRegistry { mode } => Self::initialize_registry(program_id, accounts, mode),
+            TokenInstruction::AddToRegistry { recipient } => {
+                Self::update_registry(program_id, accounts, recipient, true)
+            }
+            TokenInstruction::RemoveFromRegistry { recipient } => {
+                Self::update_registry(program_id, accounts, recipient, false)
+            }
+            TokenInstruction::BeginExport { successor } => {
+                Self::begin_migration(program_id, accounts, successor, true)
+            }
+            TokenInstruction::BeginImport { predecessor } => {
+                Self::begin_migration(program_id, accounts, predecessor, false)
+            }
+            #[cfg(feature = "vaults")]
+            TokenInstruction::ExportVault => Self::export_vault(program_id, accounts),
+            #[cfg(feature = "vaults")]
+            TokenInstruction::ImportVault => Self::import_vault(program_id, accounts),
+            #[cfg(feature = "escrow")]
+            TokenInstruction::ExportEscrow => Self::export_escrow(program_id, accounts),
+            #[cfg(feature = "escrow")]
+            TokenInstruction::ImportEscrow => Self::import_escrow(program_id, accounts),
+            TokenInstruction::SetSpendingLimit { mint, limit, window_seconds } => {
+                Self::set_spending_limit(program_id, accounts, &mint, limit, window_seconds)
+            }
+            TokenInstruction::InitializeNamespace => Self::initialize_namespace(program_id, accounts),
+            TokenInstruction::SetApprovalPolicy { threshold } => {
+                Self::set_approval_policy(program_id, accounts, threshold)
+            }
+            TokenInstruction::ProposeTransfer { amount } => Self::propose_transfer(program_id, accounts, amount),
+            TokenInstruction::ExecuteTransfer => Self::execute_transfer(program_id, accounts, event_stream_acc),
+            TokenInstruction::CancelTransfer => Self::cancel_transfer(program_id, accounts),
+            #[cfg(feature = "payouts")]
+            TokenInstruction::CreateMerkleDistribution { merkle_root, leaf_count, total_amount } => {
+                Self::create_merkle_distribution(
+                    program_id,
+                    accounts,
+                    event_stream_acc,
+                    merkle_root,
+                    leaf_count,
+                    total_amount,
+                )
+            }
+            #[cfg(feature = "payouts")]
+            TokenInstruction::ClaimMerkleDistribution { index, amount, proof } => {
+                Self::claim_merkle_distribution(program_id, accounts, event_stream_acc, index, amount, &proof)
+            }
+            #[cfg(feature = "vaults")]
+            TokenInstruction::InitializeDepositScheme { omnibus } => {
+                Self::initialize_deposit_scheme(program_id, accounts, event_stream_acc, omnibus)
+            }
+            #[cfg(feature = "vaults")]
+            TokenInstruction::SweepDeposit { user_id } => {
+                Self::sweep_deposit(program_id, accounts, event_stream_acc, user_id)
+            }
+            #[cfg(feature = "vaults")]
+            TokenInstruction::SweepDepositSplToken { user_id } => {
+                Self::sweep_deposit_spl_token(program_id, accounts, event_stream_acc, user_id)
+            }
+            #[cfg(feature = "issuance")]
+            TokenInstruction::InitializeManagedMint { supply_cap } => {
+                Self::initialize_managed_mint(program_id, accounts, event_stream_acc, supply_cap)
+            }
+            #[cfg(feature = "issuance")]
+            TokenInstruction::SetIssuer { roles, allowance } => {
+                Self::set_issuer(program_id, accounts, event_stream_acc, roles, allowance)
+            }
+            #[cfg(feature = "issuance")]
+            TokenInstruction::Issue { amount } => Self::issue(program_id, accounts, event_stream_acc, amount),
+            #[cfg(feature = "issuance")]
+            TokenInstruction::Redeem { amount } => Self::redeem(program_id, accounts, event_stream_acc, amount),
+            TokenInstruction::BatchApprove { amounts } => {
+                Self::batch_approve(program_id, accounts, event_stream_acc, &amounts)
+            }
+            TokenInstruction::GuardPrecedingInstruction { program, accounts_hash, data_hash } => {
+                Self::guard_preceding_instruction(accounts, &program, &accounts_hash, &data_hash)
+            }
+            #[cfg(feature = "dividends")]
+            TokenInstruction::CreateDividend {
+                snapshot_root, holder_count, snapshot_supply, pool_amount, claim_deadline,
+            } => Self::create_dividend(
+                program_id,
+                accounts,
+                event_stream_acc,
+                (snapshot_root, holder_count, snapshot_supply),
+                pool_amount,
+                claim_deadline,
+            ),
+            #[cfg(feature = "dividends")]
+            TokenInstruction::ClaimDividend { index, balance, proof } => {
+                Self::claim_dividend(program_id, accounts, event_stream_acc, index, balance, &proof)
+            }
+            #[cfg(feature = "dividends")]
+            TokenInstruction::ClawbackDividend => Self::clawback_dividend(program_id, accounts, event_stream_acc),
+            TokenInstruction::SetDepositProgram { program, allowed } => {
+                Self::set_deposit_program(program_id, accounts, program, allowed)
+            }
+            TokenInstruction::InitializeEventStream { key } => Self::initialize_event_stream(program_id, accounts, key),
+            // Family compiled out by its cargo feature
+            #[allow(unreachable_patterns)]
+            _ => {
+                msg!("Unsupported instruction tag {}, not compiled in", tag);
+                TokenEvent::UnsupportedInstruction(UnsupportedInstructionEvent { tag }).emit(None)?;
+                Err(TransferError::UnsupportedInstruction.into())
+            }
         }
     }
 
-    fn transfer_lamports(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    /// `memo` is given by `TransferLamportsWithMemo`, its memo program account follows system program.
+    /// `amount` is `None` for `SweepLamports`, which moves the balance of `from` above its rent exempt minimum
+    /// `from` owned by this program is debited directly and must stay rent exempt, see `pay_lamports`.
+    /// `policy` is split off by every variant but the first version of `TransferLamports`, which predates it
+    fn transfer_lamports<'a, 'b>(
+        program_id: &Pubkey,
+        accounts: &'b [AccountInfo<'a>],
+        event_stream_acc: Option<&AccountInfo>,
+        policy: Option<TransferPolicy<'a, 'b>>,
+        amount: Option<u64>,
+        memo: Option<&str>,
+    ) -> ProgramResult {
         let acc_iter = &mut accounts.iter();
-        let from_acc = next_account_info(acc_iter)?;
-        let to_acc = next_account_info(acc_iter)?;
+        accounts!(acc_iter in accounts => {
+            from_acc: signer writable,
+            to_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        let amount = match amount {
+            Some(amount) => amount,
+            None => {
+                let rent = Rent::get()?.minimum_balance(from_acc.data_len());
+                Self::sweep_amount(from_acc.lamports().saturating_sub(rent))?
+            }
+        };
         msg!("Transfer lamports from={:?}, to={:?}, amount={}", from_acc.key, to_acc.key, amount);
+        let hook_program_acc = match &policy {
+            Some(policy) => {
+                Self::check_registry(program_id, policy, to_acc.key)?;
+                Self::check_transfer_policy(program_id, policy, from_acc.key, &Pubkey::default(), amount)?
+            }
+            None => None,
+        };
 
-        if !from_acc.is_signer {
-            return Err(ProgramError::MissingRequiredSignature)
+        let memo_program_acc = Self::load_memo_program(acc_iter, memo)?;
+        let fee = Self::load_fee(program_id, acc_iter.as_slice(), amount)?;
+
+        if let Some(hook_program_acc) = hook_program_acc {
+            Self::invoke_transfer_hook(hook_program_acc, from_acc, from_acc, to_acc, TransferHookStage::PreTransfer, amount)?;
         }
-        if !from_acc.is_writable {
-            return Err(TransferError::AccountNonWritable.into())
+        let fee_amount = match fee {
+            Some((fee_recipient, fee_acc, fee_amount)) => {
+                if fee_recipient != *fee_acc.key {
+                    return Err(TransferError::AccountMismatch.into())
+                }
+                msg!("Transfer lamports fee={}, fee_recipient={:?}", fee_amount, fee_acc.key);
+                Self::pay_lamports(program_id, from_acc, fee_acc, system_program_acc, fee_amount)?;
+                fee_amount
+            }
+            None => 0,
+        };
+        Self::pay_lamports(program_id, from_acc, to_acc, system_program_acc, amount - fee_amount)?;
+        if let Some(hook_program_acc) = hook_program_acc {
+            Self::invoke_transfer_hook(hook_program_acc, from_acc, from_acc, to_acc, TransferHookStage::PostTransfer, amount)?;
         }
-        if !to_acc.is_writable {
-            return Err(TransferError::AccountNonWritable.into())
+        if let (Some(memo_program_acc), Some(memo)) = (memo_program_acc, memo) {
+            Self::invoke_memo(memo_program_acc, memo)?;
         }
-
-        let transfer_instr = system_instruction::transfer(
-            from_acc.key,
-            to_acc.key,
+        TokenEvent::TransferLamports(TransferLamportsEvent {
+            from: *from_acc.key,
+            to: *to_acc.key,
             amount,
-        );
-        invoke(
-            &transfer_instr,
-            &[from_acc.clone(), to_acc.clone()],
-        )?;
+            fee: fee_amount,
+            memo: memo.map(str::to_string),
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
 
         msg!("Transfer lamports from={:?}, to={:?}, amount={} done", from_acc.key, to_acc.key, amount);
         Ok(())
     }
 
-    fn transfer_spl_token(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    /// `memo` is given by `TransferSplTokenWithMemo`, its memo program account follows mint config.
+    /// `amount` is `None` for `SweepSplToken`, which moves the whole balance of `from_spl_token`.
+    /// `policy` is split off by every variant but the first version of `TransferSplToken`, which passes
+    /// program config after SPL token program instead
+    fn transfer_spl_token<'a, 'b>(
+        program_id: &Pubkey,
+        accounts: &'b [AccountInfo<'a>],
+        event_stream_acc: Option<&AccountInfo>,
+        policy: Option<TransferPolicy<'a, 'b>>,
+        amount: Option<u64>,
+        data_hash: Option<[u8; 32]>,
+        memo: Option<&str>,
+    ) -> ProgramResult {
         let acc_iter = &mut accounts.iter();
-        let owner_acc = next_account_info(acc_iter)?;
-        let from_spl_token_acc = next_account_info(acc_iter)?;
-        let to_spl_token_acc = next_account_info(acc_iter)?;
-        let spl_token_acc = next_account_info(acc_iter)?;
+        accounts!(acc_iter in accounts => {
+            owner_acc,
+            from_spl_token_acc: writable,
+            to_spl_token_acc: writable,
+            spl_token_acc: token_program,
+        });
+        let config_acc = match &policy {
+            Some(policy) => policy.config_acc,
+            None => {
+                accounts!(acc_iter in accounts => {
+                    config_acc,
+                });
+                Self::check_not_paused(program_id, config_acc)?;
+                config_acc
+            }
+        };
+        accounts!(acc_iter in accounts => {
+            mint_config_acc,
+        });
+        let memo_program_acc = Self::load_memo_program(acc_iter, memo)?;
+        let (trailing_accs, signer_accs) = Self::split_multisig_signers(owner_acc, acc_iter.as_slice())?;
+        let mint = Self::check_token_transfer(from_spl_token_acc, to_spl_token_acc, owner_acc.key)?;
+        let balance = TokenAccount::new(from_spl_token_acc)?.amount;
+        let amount = match amount {
+            Some(amount) => amount,
+            None => Self::sweep_amount(balance)?,
+        };
         msg!(
             "Transfer spl token from={:?}, to={:?}, amount={}",
             from_spl_token_acc.key, to_spl_token_acc.key, amount
         );
-
-        if !owner_acc.is_signer {
-            return Err(ProgramError::MissingRequiredSignature)
-        }
-        if !from_spl_token_acc.is_writable {
-            return Err(TransferError::AccountNonWritable.into())
-        }
-        if !to_spl_token_acc.is_writable {
-            return Err(TransferError::AccountNonWritable.into())
+        if balance < amount {
+            // Checked ahead of SPL token, whose `InsufficientFunds` code collides with `AccountMismatch`
+            let index = ErrorContextEvent::account_index(accounts, from_spl_token_acc);
+            let (expected, actual) = (ErrorValue::Amount(amount), ErrorValue::Amount(balance));
+            let error = TransferError::InsufficientFunds;
+            return Err(ErrorContextEvent::fail(error, index, Some(expected), Some(actual)))
         }
+        Self::check_transferable(program_id, mint_config_acc, &mint)?;
+        Self::check_strict_ata(program_id, config_acc, to_spl_token_acc)?;
+        Self::check_travel_rule(program_id, config_acc, amount, data_hash.as_ref())?;
+        let hook_program_acc = match &policy {
+            Some(policy) => {
+                Self::check_registry(program_id, policy, &TokenAccount::new(to_spl_token_acc)?.owner)?;
+                Self::check_transfer_policy(program_id, policy, owner_acc.key, &mint, amount)?
+            }
+            None => None,
+        };
+        let signer_keys: Vec<&Pubkey> = signer_accs.iter().map(|acc| acc.key).collect();
+        let fee = Self::load_fee(program_id, trailing_accs, amount)?;
 
+        if let Some(hook_program_acc) = hook_program_acc {
+            Self::invoke_transfer_hook(
+                hook_program_acc,
+                owner_acc,
+                from_spl_token_acc,
+                to_spl_token_acc,
+                TransferHookStage::PreTransfer,
+                amount,
+            )?;
+        }
+        let fee_amount = match fee {
+            Some((fee_recipient, fee_acc, fee_amount)) => {
+                if TokenAccount::new(fee_acc)?.owner != fee_recipient {
+                    return Err(TransferError::TokenAccountOwnerMismatch.into())
+                }
+                msg!("Transfer spl token fee={}, fee_account={:?}", fee_amount, fee_acc.key);
+                let fee_instr = spl_token::instruction::transfer(
+                    spl_token_acc.key,
+                    from_spl_token_acc.key,
+                    fee_acc.key,
+                    owner_acc.key,
+                    &signer_keys,
+                    fee_amount,
+                )?;
+                let mut fee_accs = vec![
+                    owner_acc.clone(),
+                    from_spl_token_acc.clone(),
+                    fee_acc.clone(),
+                    spl_token_acc.clone(),
+                ];
+                fee_accs.extend_from_slice(signer_accs);
+                invoke(&fee_instr, &fee_accs)?;
+                fee_amount
+            }
+            None => 0,
+        };
         let transfer_instr = spl_token::instruction::transfer(
             spl_token_acc.key,
             from_spl_token_acc.key,
             to_spl_token_acc.key,
             owner_acc.key,
-            &[&owner_acc.key],
-            amount,
-        )?;
-        invoke(&transfer_instr,
-               &[
-                   owner_acc.clone(),
-                   from_spl_token_acc.clone(),
-                   to_spl_token_acc.clone(),
-                   spl_token_acc.clone()
-               ],
+            &signer_keys,
+            amount - fee_amount,
         )?;
+        let mut transfer_accs = vec![
+            owner_acc.clone(),
+            from_spl_token_acc.clone(),
+            to_spl_token_acc.clone(),
+            spl_token_acc.clone(),
+        ];
+        transfer_accs.extend_from_slice(signer_accs);
+        invoke(&transfer_instr, &transfer_accs)?;
+        if let Some(hook_program_acc) = hook_program_acc {
+            Self::invoke_transfer_hook(
+                hook_program_acc,
+                owner_acc,
+                from_spl_token_acc,
+                to_spl_token_acc,
+                TransferHookStage::PostTransfer,
+                amount,
+            )?;
+        }
+        if let (Some(memo_program_acc), Some(memo)) = (memo_program_acc, memo) {
+            Self::invoke_memo(memo_program_acc, memo)?;
+        }
+        TokenEvent::TransferSpl(TransferSplEvent {
+            from: *from_spl_token_acc.key,
+            to: *to_spl_token_acc.key,
+            owner: *owner_acc.key,
+            mint,
+            amount,
+            fee: fee_amount,
+            memo: memo.map(str::to_string),
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        if let Some(data_hash) = data_hash {
+            TokenEvent::TravelRuleData(TravelRuleDataEvent {
+                from: *from_spl_token_acc.key,
+                to: *to_spl_token_acc.key,
+                amount,
+                data_hash,
+            }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        }
 
         msg!(
             "Transfer spl token from={:?}, to={:?}, amount={} done",
@@ -102,53 +809,7671 @@ impl Processor {
         Ok(())
     }
 
-    fn approve_spl_token(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    /// Balance read on-chain by a sweep, so it can not race fee deductions of the caller
+    fn sweep_amount(balance: u64) -> Result<u64, ProgramError> {
+        if balance == 0 {
+            return Err(TransferError::NothingToSweep.into())
+        }
+        Ok(balance)
+    }
+
+    #[cfg(feature = "vaults")]
+    fn initialize_vault(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let (accounts, namespace_config_acc) = Self::split_namespace_config(program_id, accounts);
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            mint_acc: mint,
+            vault_acc: writable,
+            vault_token_acc: token_account(mint_acc.key),
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Initialize vault={:?}, owner={:?}, mint={:?}", vault_acc.key, owner_acc.key, mint_acc.key);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_VAULTS)?;
+        let keys = [owner_acc.key.as_ref(), mint_acc.key.as_ref()];
+        let mut seeds = namespaced_seeds(Vault::SEED, namespace_config_acc.map(|acc| acc.key), &keys);
+        let (vault_key, bump) = Pubkey::find_program_address(&seeds, program_id);
+        if vault_key != *vault_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(vault_acc.key, mint_acc.key) != *vault_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let bump_seed = [bump];
+        seeds.push(&bump_seed);
+        Self::create_program_account(
+            program_id,
+            owner_acc,
+            vault_acc,
+            system_program_acc,
+            Vault::LEN,
+            Rent::get()?.minimum_balance(Vault::LEN),
+            &seeds,
+        )?;
+        Vault {
+            account_type: AccountType::Vault,
+            owner: *owner_acc.key,
+            mint: *mint_acc.key,
+            bump,
+        }.save(vault_acc)?;
+
+        TokenEvent::VaultInitialized(VaultInitializedEvent {
+            vault: *vault_acc.key,
+            owner: *owner_acc.key,
+            mint: *mint_acc.key,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Initialize vault={:?}, owner={:?}, mint={:?} done", vault_acc.key, owner_acc.key, mint_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "vaults")]
+    fn deposit_to_vault(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
         let acc_iter = &mut accounts.iter();
-        let owner_acc = next_account_info(acc_iter)?;
-        let from_spl_token_acc = next_account_info(acc_iter)?;
-        let to_spl_token_acc = next_account_info(acc_iter)?;
-        let spl_token_acc = next_account_info(acc_iter)?;
-        msg!(
-            "Approve spl token from={:?}, to={:?}, amount={}",
-            from_spl_token_acc.key,
+        accounts!(acc_iter in accounts => {
+            depositor_acc: signer,
+            depositor_token_acc: writable,
+            vault_acc,
+            vault_token_acc: writable,
+            mint_config_acc,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Deposit to vault={:?}, amount={}", vault_acc.key, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_VAULTS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        if amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let vault = Vault::load(vault_acc, program_id)?;
+        if get_associated_token_address(vault_acc.key, &vault.mint) != *vault_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(depositor_token_acc)?.mint != vault.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+        Self::check_transferable(program_id, mint_config_acc, &vault.mint)?;
+
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            depositor_token_acc.key,
+            vault_token_acc.key,
+            depositor_acc.key,
+            &[depositor_acc.key],
+            amount,
+        )?;
+        invoke(
+            &transfer_instr,
+            &[depositor_token_acc.clone(), vault_token_acc.clone(), depositor_acc.clone(), spl_token_acc.clone()],
+        )?;
+
+        TokenEvent::VaultDeposited(VaultDepositedEvent {
+            vault: *vault_acc.key,
+            depositor: *depositor_acc.key,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Deposit to vault={:?}, amount={} done", vault_acc.key, amount);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_VAULTS`, so disabling vaults never locks deposits in
+    #[cfg(feature = "vaults")]
+    fn withdraw_from_vault(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (accounts, namespace_config_acc) = Self::split_namespace_config(program_id, accounts);
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer,
+            vault_acc,
+            vault_token_acc: writable,
+            to_spl_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Withdraw from vault={:?}, to={:?}, amount={}", vault_acc.key, to_spl_token_acc.key, amount);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        if amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let vault = Vault::load(vault_acc, program_id)?;
+        if vault.owner != *owner_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if get_associated_token_address(vault_acc.key, &vault.mint) != *vault_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(to_spl_token_acc)?.mint != vault.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            vault_token_acc.key,
             to_spl_token_acc.key,
-            amount
+            vault_acc.key,
+            &[],
+            amount,
+        )?;
+        let bump_seed = [vault.bump];
+        let keys = [vault.owner.as_ref(), vault.mint.as_ref(), &bump_seed];
+        invoke_signed(
+            &transfer_instr,
+            &[vault_token_acc.clone(), to_spl_token_acc.clone(), vault_acc.clone(), spl_token_acc.clone()],
+            &[&namespaced_seeds(Vault::SEED, namespace_config_acc.map(|acc| acc.key), &keys)],
+        )?;
+
+        TokenEvent::VaultWithdrawn(VaultWithdrawnEvent {
+            vault: *vault_acc.key,
+            to: *to_spl_token_acc.key,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Withdraw from vault={:?}, to={:?}, amount={} done", vault_acc.key, to_spl_token_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "vaults")]
+    fn initialize_deposit_scheme(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        omnibus: Pubkey,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer writable,
+            deposit_scheme_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Initialize deposit scheme={:?}, omnibus={:?}", deposit_scheme_acc.key, omnibus);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_VAULTS)?;
+        let (scheme_key, bump) = Pubkey::find_program_address(
+            &[DepositScheme::SEED, authority_acc.key.as_ref()],
+            program_id,
         );
+        if scheme_key != *deposit_scheme_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
 
-        if !owner_acc.is_signer {
-            return Err(ProgramError::MissingRequiredSignature)
+        Self::create_program_account(
+            program_id,
+            authority_acc,
+            deposit_scheme_acc,
+            system_program_acc,
+            DepositScheme::LEN,
+            Rent::get()?.minimum_balance(DepositScheme::LEN),
+            &[DepositScheme::SEED, authority_acc.key.as_ref(), &[bump]],
+        )?;
+        DepositScheme {
+            account_type: AccountType::DepositScheme,
+            authority: *authority_acc.key,
+            omnibus,
+            bump,
+        }.save(deposit_scheme_acc)?;
+
+        TokenEvent::DepositSchemeInitialized(DepositSchemeInitializedEvent {
+            scheme: *deposit_scheme_acc.key,
+            authority: *authority_acc.key,
+            omnibus,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Initialize deposit scheme={:?}, omnibus={:?} done", deposit_scheme_acc.key, omnibus);
+        Ok(())
+    }
+
+    /// Bump of deposit address of `user_id`, the address is the only proof the user ID belongs to it
+    #[cfg(feature = "vaults")]
+    fn deposit_bump(program_id: &Pubkey, scheme: &Pubkey, deposit: &Pubkey, user_id: u64) -> Result<u8, ProgramError> {
+        let (deposit_key, bump) = Pubkey::find_program_address(
+            &[DepositScheme::DEPOSIT_SEED, scheme.as_ref(), &user_id.to_le_bytes()],
+            program_id,
+        );
+        if deposit_key != *deposit {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        Ok(bump)
+    }
+
+    /// Permissionless and not gated by `FEATURE_VAULTS`, so customer deposits always reach the omnibus
+    #[cfg(feature = "vaults")]
+    fn sweep_deposit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        user_id: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            deposit_scheme_acc,
+            deposit_acc: writable,
+            omnibus_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Sweep deposit={:?}, user_id={}", deposit_acc.key, user_id);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let scheme = DepositScheme::load(deposit_scheme_acc, program_id)?;
+        if scheme.omnibus != *omnibus_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let bump = Self::deposit_bump(program_id, deposit_scheme_acc.key, deposit_acc.key, user_id)?;
+        let amount = Self::sweep_amount(deposit_acc.lamports())?;
+
+        invoke_signed(
+            &system_instruction::transfer(deposit_acc.key, omnibus_acc.key, amount),
+            &[deposit_acc.clone(), omnibus_acc.clone(), system_program_acc.clone()],
+            &[&[DepositScheme::DEPOSIT_SEED, deposit_scheme_acc.key.as_ref(), &user_id.to_le_bytes(), &[bump]]],
+        )?;
+
+        TokenEvent::DepositSwept(DepositSweptEvent {
+            scheme: *deposit_scheme_acc.key,
+            deposit: *deposit_acc.key,
+            user_id,
+            mint: spl_token::native_mint::id(),
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Sweep deposit={:?}, user_id={}, amount={} done", deposit_acc.key, user_id, amount);
+        Ok(())
+    }
+
+    /// Permissionless and not gated by `FEATURE_VAULTS`, so customer deposits always reach the omnibus
+    #[cfg(feature = "vaults")]
+    fn sweep_deposit_spl_token(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        user_id: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            deposit_scheme_acc,
+            deposit_acc,
+            deposit_token_acc: writable,
+            omnibus_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Sweep deposit token={:?}, user_id={}", deposit_token_acc.key, user_id);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let scheme = DepositScheme::load(deposit_scheme_acc, program_id)?;
+        let bump = Self::deposit_bump(program_id, deposit_scheme_acc.key, deposit_acc.key, user_id)?;
+        let deposit_token = TokenAccount::new(deposit_token_acc)?;
+        if get_associated_token_address(deposit_acc.key, &deposit_token.mint) != *deposit_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
         }
-        if !from_spl_token_acc.is_writable {
-            return Err(TransferError::AccountNonWritable.into())
+        let omnibus_token = TokenAccount::new(omnibus_token_acc)?;
+        if omnibus_token.owner != scheme.omnibus {
+            return Err(TransferError::AccountMismatch.into())
         }
-        if !to_spl_token_acc.is_writable {
-            return Err(TransferError::AccountNonWritable.into())
+        if omnibus_token.mint != deposit_token.mint {
+            return Err(TransferError::MintMismatch.into())
         }
+        let amount = Self::sweep_amount(deposit_token.amount)?;
 
-        let approve_instr = spl_token::instruction::approve(
+        let transfer_instr = spl_token::instruction::transfer(
             spl_token_acc.key,
-            from_spl_token_acc.key,
+            deposit_token_acc.key,
+            omnibus_token_acc.key,
+            deposit_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke_signed(
+            &transfer_instr,
+            &[deposit_token_acc.clone(), omnibus_token_acc.clone(), deposit_acc.clone(), spl_token_acc.clone()],
+            &[&[DepositScheme::DEPOSIT_SEED, deposit_scheme_acc.key.as_ref(), &user_id.to_le_bytes(), &[bump]]],
+        )?;
+
+        TokenEvent::DepositSwept(DepositSweptEvent {
+            scheme: *deposit_scheme_acc.key,
+            deposit: *deposit_acc.key,
+            user_id,
+            mint: deposit_token.mint,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Sweep deposit token={:?}, user_id={}, amount={} done", deposit_token_acc.key, user_id, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "issuance")]
+    fn initialize_managed_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        supply_cap: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            mint_acc: writable mint,
+            managed_mint_acc: writable,
+            system_program_acc: program(system_program::id()),
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Initialize managed mint={:?}, supply_cap={}", mint_acc.key, supply_cap);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_ISSUANCE)?;
+        if MintAccount::new(mint_acc)?.mint_authority != COption::Some(*admin_acc.key) {
+            return Err(TransferError::NotMintAuthority.into())
+        }
+        let (managed_mint_key, bump) = Pubkey::find_program_address(
+            &[ManagedMint::SEED, mint_acc.key.as_ref()],
+            program_id,
+        );
+        if managed_mint_key != *managed_mint_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            managed_mint_acc,
+            system_program_acc,
+            ManagedMint::LEN,
+            Rent::get()?.minimum_balance(ManagedMint::LEN),
+            &[ManagedMint::SEED, mint_acc.key.as_ref(), &[bump]],
+        )?;
+        ManagedMint {
+            account_type: AccountType::ManagedMint,
+            mint: *mint_acc.key,
+            admin: *admin_acc.key,
+            supply_cap,
+            issued: 0,
+            redeemed: 0,
+            bump,
+        }.save(managed_mint_acc)?;
+
+        let set_authority_instr = spl_token::instruction::set_authority(
+            spl_token_acc.key,
+            mint_acc.key,
+            Some(managed_mint_acc.key),
+            spl_token::instruction::AuthorityType::MintTokens,
+            admin_acc.key,
+            &[],
+        )?;
+        invoke(&set_authority_instr, &[mint_acc.clone(), admin_acc.clone(), spl_token_acc.clone()])?;
+
+        TokenEvent::ManagedMintInitialized(ManagedMintInitializedEvent {
+            managed_mint: *managed_mint_acc.key,
+            mint: *mint_acc.key,
+            admin: *admin_acc.key,
+            supply_cap,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Initialize managed mint={:?}, supply_cap={} done", mint_acc.key, supply_cap);
+        Ok(())
+    }
+
+    #[cfg(feature = "issuance")]
+    fn set_issuer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        roles: u8,
+        allowance: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            managed_mint_acc,
+            issuer_acc,
+            issuer_role_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Set issuer={:?}, roles={}, allowance={}", issuer_acc.key, roles, allowance);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_ISSUANCE)?;
+        if ManagedMint::load(managed_mint_acc, program_id)?.admin != *admin_acc.key {
+            return Err(TransferError::NotIssuanceAdmin.into())
+        }
+        let (issuer_role_key, bump) = Pubkey::find_program_address(
+            &[Issuer::SEED, managed_mint_acc.key.as_ref(), issuer_acc.key.as_ref()],
+            program_id,
+        );
+        if issuer_role_key != *issuer_role_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if issuer_role_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                admin_acc,
+                issuer_role_acc,
+                system_program_acc,
+                Issuer::LEN,
+                Rent::get()?.minimum_balance(Issuer::LEN),
+                &[Issuer::SEED, managed_mint_acc.key.as_ref(), issuer_acc.key.as_ref(), &[bump]],
+            )?;
+            Issuer {
+                account_type: AccountType::Issuer,
+                managed_mint: *managed_mint_acc.key,
+                issuer: *issuer_acc.key,
+                roles,
+                allowance,
+                issued: 0,
+                redeemed: 0,
+                bump,
+            }.save(issuer_role_acc)?;
+        } else {
+            let mut issuer = ProgramAccount::<Issuer>::new(issuer_role_acc, program_id)?;
+            issuer.roles = roles;
+            issuer.allowance = allowance;
+            issuer.save()?;
+        }
+
+        TokenEvent::IssuerSet(IssuerSetEvent {
+            managed_mint: *managed_mint_acc.key,
+            issuer: *issuer_acc.key,
+            admin: *admin_acc.key,
+            roles,
+            allowance,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Set issuer={:?}, roles={}, allowance={} done", issuer_acc.key, roles, allowance);
+        Ok(())
+    }
+
+    /// Managed mint of `mint_acc` and issuer of `issuer_acc` holding `role`
+    #[cfg(feature = "issuance")]
+    fn load_issuer<'a, 'b>(
+        program_id: &Pubkey,
+        mint_acc: &AccountInfo,
+        managed_mint_acc: &'b AccountInfo<'a>,
+        issuer_acc: &AccountInfo,
+        issuer_role_acc: &'b AccountInfo<'a>,
+        role: u8,
+    ) -> Result<(ProgramAccount<'a, 'b, ManagedMint>, ProgramAccount<'a, 'b, Issuer>), ProgramError> {
+        let managed_mint = ProgramAccount::<ManagedMint>::new(managed_mint_acc, program_id)?;
+        if managed_mint.mint != *mint_acc.key {
+            return Err(TransferError::MintMismatch.into())
+        }
+        let issuer = ProgramAccount::<Issuer>::new(issuer_role_acc, program_id)?;
+        if issuer.managed_mint != *managed_mint_acc.key || issuer.issuer != *issuer_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if !issuer.has_role(role) {
+            return Err(TransferError::IssuerRoleMissing.into())
+        }
+        Ok((managed_mint, issuer))
+    }
+
+    #[cfg(feature = "issuance")]
+    fn issue(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            issuer_acc: signer,
+            mint_acc: writable mint,
+            managed_mint_acc: writable,
+            issuer_role_acc: writable,
+            to_spl_token_acc: writable token_account(mint_acc.key),
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Issue mint={:?}, to={:?}, amount={}", mint_acc.key, to_spl_token_acc.key, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_ISSUANCE)?;
+        if amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (mut managed_mint, mut issuer) = Self::load_issuer(
+            program_id,
+            mint_acc,
+            managed_mint_acc,
+            issuer_acc,
+            issuer_role_acc,
+            Issuer::ROLE_ISSUE,
+        )?;
+        issuer.allowance = issuer.allowance.checked_sub(amount).ok_or(TransferError::IssuerAllowanceExceeded)?;
+        let supply = MintAccount::new(mint_acc)?.supply.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        if supply > managed_mint.supply_cap {
+            return Err(TransferError::SupplyCapExceeded.into())
+        }
+        issuer.issued = issuer.issued.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        managed_mint.issued = managed_mint.issued.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+
+        let mint_to_instr = spl_token::instruction::mint_to(
+            spl_token_acc.key,
+            mint_acc.key,
             to_spl_token_acc.key,
-            owner_acc.key,
-            &[owner_acc.key],
+            managed_mint_acc.key,
+            &[],
             amount,
         )?;
-        invoke(
-            &approve_instr,
-            &[
-                owner_acc.clone(),
-                from_spl_token_acc.clone(),
-                to_spl_token_acc.clone(),
-                spl_token_acc.clone()
-            ],
+        invoke_signed(
+            &mint_to_instr,
+            &[mint_acc.clone(), to_spl_token_acc.clone(), managed_mint_acc.clone(), spl_token_acc.clone()],
+            &[&[ManagedMint::SEED, mint_acc.key.as_ref(), &[managed_mint.bump]]],
         )?;
+        issuer.save()?;
+        managed_mint.save()?;
 
-        msg!(
-            "Approve spl token from={:?}, to={:?}, amount={} done",
+        TokenEvent::Issued(IssuedEvent {
+            mint: *mint_acc.key,
+            issuer: *issuer_acc.key,
+            to: *to_spl_token_acc.key,
+            amount,
+            supply,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Issue mint={:?}, to={:?}, amount={} done", mint_acc.key, to_spl_token_acc.key, amount);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_ISSUANCE`, so disabling issuance never blocks redemptions
+    #[cfg(feature = "issuance")]
+    fn redeem(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            redeemer_acc: signer,
+            mint_acc: writable mint,
+            managed_mint_acc: writable,
+            issuer_role_acc: writable,
+            from_spl_token_acc: writable token_account(mint_acc.key),
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Redeem mint={:?}, from={:?}, amount={}", mint_acc.key, from_spl_token_acc.key, amount);
+
+        Self::load_config(program_id, config_acc)?;
+        if amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (mut managed_mint, mut issuer) = Self::load_issuer(
+            program_id,
+            mint_acc,
+            managed_mint_acc,
+            redeemer_acc,
+            issuer_role_acc,
+            Issuer::ROLE_REDEEM,
+        )?;
+        if TokenAccount::new(from_spl_token_acc)?.owner != *redeemer_acc.key {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+        let supply = MintAccount::new(mint_acc)?.supply.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+        issuer.redeemed = issuer.redeemed.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        managed_mint.redeemed = managed_mint.redeemed.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+
+        let burn_instr = spl_token::instruction::burn(
+            spl_token_acc.key,
             from_spl_token_acc.key,
-            to_spl_token_acc.key,
-            amount
-        );
+            mint_acc.key,
+            redeemer_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke(
+            &burn_instr,
+            &[from_spl_token_acc.clone(), mint_acc.clone(), redeemer_acc.clone(), spl_token_acc.clone()],
+        )?;
+        issuer.save()?;
+        managed_mint.save()?;
+
+        TokenEvent::Redeemed(RedeemedEvent {
+            mint: *mint_acc.key,
+            redeemer: *redeemer_acc.key,
+            from: *from_spl_token_acc.key,
+            amount,
+            supply,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Redeem mint={:?}, from={:?}, amount={} done", mint_acc.key, from_spl_token_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "escrow")]
+    fn initialize_escrow(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        expected_amount: u64,
+        expires_at: i64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            initializer_acc: signer writable,
+            temp_token_acc: writable,
+            receive_token_acc,
+            escrow_acc: writable,
+            spl_token_acc: token_program,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+            offer_mint_config_acc,
+            receive_mint_config_acc,
+        });
+        msg!("Initialize escrow={:?}, expected amount={}", escrow_acc.key, expected_amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_ESCROWS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        let temp_token = TokenAccount::new(temp_token_acc)?;
+        let amount = temp_token.amount;
+        if amount == 0 || expected_amount == 0 || temp_token.close_authority.is_some() {
+            return Err(ProgramError::InvalidArgument)
+        }
+        Self::check_transferable(program_id, offer_mint_config_acc, &temp_token.mint)?;
+        Self::check_transferable(program_id, receive_mint_config_acc, &TokenAccount::new(receive_token_acc)?.mint)?;
+        if expires_at <= Clock::get()?.unix_timestamp {
+            return Err(TransferError::EscrowExpired.into())
+        }
+        let (escrow_key, bump) = Escrow::find_address(temp_token_acc.key);
+        if escrow_key != *escrow_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        Self::create_program_account(
+            program_id,
+            initializer_acc,
+            escrow_acc,
+            system_program_acc,
+            Escrow::LEN,
+            Rent::get()?.minimum_balance(Escrow::LEN),
+            &[Escrow::SEED, temp_token_acc.key.as_ref(), &[bump]],
+        )?;
+        let set_authority_instr = spl_token::instruction::set_authority(
+            spl_token_acc.key,
+            temp_token_acc.key,
+            Some(escrow_acc.key),
+            AuthorityType::AccountOwner,
+            initializer_acc.key,
+            &[initializer_acc.key],
+        )?;
+        invoke(
+            &set_authority_instr,
+            &[temp_token_acc.clone(), initializer_acc.clone(), spl_token_acc.clone()],
+        )?;
+        Escrow {
+            account_type: AccountType::Escrow,
+            initializer: *initializer_acc.key,
+            temp_token: *temp_token_acc.key,
+            receive_token: *receive_token_acc.key,
+            expected_amount,
+            expires_at,
+            bump,
+        }.save(escrow_acc)?;
+
+        TokenEvent::EscrowInitialized(EscrowInitializedEvent {
+            escrow: *escrow_acc.key,
+            initializer: *initializer_acc.key,
+            amount,
+            expected_amount,
+            expires_at,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Initialize escrow={:?}, expected amount={} done", escrow_acc.key, expected_amount);
+        Ok(())
+    }
+
+    /// Taker states the offer `amount` it expects, so a changed offer fails instead of being accepted
+    #[cfg(feature = "escrow")]
+    fn exchange_escrow(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            taker_acc: signer,
+            taker_send_token_acc: writable,
+            taker_receive_token_acc: writable,
+            initializer_acc: writable,
+            receive_token_acc: writable,
+            temp_token_acc: writable,
+            escrow_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Exchange escrow={:?}, taker={:?}, amount={}", escrow_acc.key, taker_acc.key, amount);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        Self::check_sandwich(accounts.get(9), &[
+            taker_send_token_acc,
+            taker_receive_token_acc,
+            receive_token_acc,
+            temp_token_acc,
+            escrow_acc,
+        ])?;
+
+        let escrow = Escrow::load(escrow_acc, program_id)?;
+        if escrow.initializer != *initializer_acc.key
+            || escrow.temp_token != *temp_token_acc.key
+            || escrow.receive_token != *receive_token_acc.key
+        {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if Clock::get()?.unix_timestamp >= escrow.expires_at {
+            return Err(TransferError::EscrowExpired.into())
+        }
+        if TokenAccount::new(temp_token_acc)?.amount != amount {
+            return Err(TransferError::EscrowAmountMismatch.into())
+        }
+
+        let pay_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            taker_send_token_acc.key,
+            receive_token_acc.key,
+            taker_acc.key,
+            &[taker_acc.key],
+            escrow.expected_amount,
+        )?;
+        invoke(
+            &pay_instr,
+            &[taker_send_token_acc.clone(), receive_token_acc.clone(), taker_acc.clone(), spl_token_acc.clone()],
+        )?;
+        let release_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            temp_token_acc.key,
+            taker_receive_token_acc.key,
+            escrow_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke_signed(
+            &release_instr,
+            &[temp_token_acc.clone(), taker_receive_token_acc.clone(), escrow_acc.clone(), spl_token_acc.clone()],
+            &[&[Escrow::SEED, escrow.temp_token.as_ref(), &[escrow.bump]]],
+        )?;
+        Self::close_escrow(program_id, &escrow, initializer_acc, temp_token_acc, escrow_acc, spl_token_acc)?;
+
+        TokenEvent::EscrowExchanged(EscrowExchangedEvent {
+            escrow: *escrow_acc.key,
+            initializer: escrow.initializer,
+            taker: *taker_acc.key,
+            amount,
+            expected_amount: escrow.expected_amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Exchange escrow={:?}, taker={:?}, amount={} done", escrow_acc.key, taker_acc.key, amount);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "escrow")]
+    fn cancel_escrow(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            initializer_acc: signer writable,
+            initializer_token_acc: writable,
+            temp_token_acc: writable,
+            escrow_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Cancel escrow={:?}", escrow_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let escrow = Escrow::load(escrow_acc, program_id)?;
+        if escrow.initializer != *initializer_acc.key || escrow.temp_token != *temp_token_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if Clock::get()?.unix_timestamp < escrow.expires_at {
+            return Err(TransferError::EscrowNotExpired.into())
+        }
+
+        let refunded = TokenAccount::new(temp_token_acc)?.amount;
+        let refund_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            temp_token_acc.key,
+            initializer_token_acc.key,
+            escrow_acc.key,
+            &[],
+            refunded,
+        )?;
+        invoke_signed(
+            &refund_instr,
+            &[temp_token_acc.clone(), initializer_token_acc.clone(), escrow_acc.clone(), spl_token_acc.clone()],
+            &[&[Escrow::SEED, escrow.temp_token.as_ref(), &[escrow.bump]]],
+        )?;
+        Self::close_escrow(program_id, &escrow, initializer_acc, temp_token_acc, escrow_acc, spl_token_acc)?;
+
+        TokenEvent::EscrowCancelled(EscrowCancelledEvent {
+            escrow: *escrow_acc.key,
+            initializer: escrow.initializer,
+            refunded,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Cancel escrow={:?}, refunded={} done", escrow_acc.key, refunded);
+        Ok(())
+    }
+
+    #[cfg(feature = "vesting")]
+    fn create_vesting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        total_amount: u64,
+        start_ts: i64,
+        cliff_ts: i64,
+        end_ts: i64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            creator_acc: signer writable,
+            creator_token_acc: writable,
+            beneficiary_acc,
+            mint_acc: mint,
+            vesting_acc: writable,
+            vesting_token_acc: writable token_account(mint_acc.key),
+            mint_config_acc,
+            spl_token_acc: token_program,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Create vesting={:?}, beneficiary={:?}, amount={}", vesting_acc.key, beneficiary_acc.key, total_amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_VESTING)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        if total_amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        if !VestingSchedule::is_valid_schedule(start_ts, cliff_ts, end_ts) {
+            return Err(TransferError::InvalidVestingSchedule.into())
+        }
+        let (vesting_key, bump) = VestingSchedule::find_address(creator_acc.key, beneficiary_acc.key, mint_acc.key);
+        if vesting_key != *vesting_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(vesting_acc.key, mint_acc.key) != *vesting_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+
+        Self::create_program_account(
+            program_id,
+            creator_acc,
+            vesting_acc,
+            system_program_acc,
+            VestingSchedule::LEN,
+            Rent::get()?.minimum_balance(VestingSchedule::LEN),
+            &[
+                VestingSchedule::SEED,
+                creator_acc.key.as_ref(),
+                beneficiary_acc.key.as_ref(),
+                mint_acc.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            creator_token_acc.key,
+            vesting_token_acc.key,
+            creator_acc.key,
+            &[creator_acc.key],
+            total_amount,
+        )?;
+        invoke(
+            &transfer_instr,
+            &[creator_token_acc.clone(), vesting_token_acc.clone(), creator_acc.clone(), spl_token_acc.clone()],
+        )?;
+        VestingSchedule {
+            account_type: AccountType::VestingSchedule,
+            creator: *creator_acc.key,
+            beneficiary: *beneficiary_acc.key,
+            mint: *mint_acc.key,
+            total_amount,
+            claimed_amount: 0,
+            start_ts,
+            cliff_ts,
+            end_ts,
+            bump,
+        }.save(vesting_acc)?;
+
+        TokenEvent::VestingCreated(VestingCreatedEvent {
+            vesting: *vesting_acc.key,
+            creator: *creator_acc.key,
+            beneficiary: *beneficiary_acc.key,
+            mint: *mint_acc.key,
+            total_amount,
+            start_ts,
+            cliff_ts,
+            end_ts,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create vesting={:?}, amount={} done", vesting_acc.key, total_amount);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_VESTING`, so disabling vesting never locks schedules in
+    #[cfg(feature = "vesting")]
+    fn claim_vested(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            beneficiary_acc: signer,
+            vesting_acc: writable,
+            vesting_token_acc: writable,
+            beneficiary_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Claim vested vesting={:?}, beneficiary={:?}", vesting_acc.key, beneficiary_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut vesting = VestingSchedule::load(vesting_acc, program_id)?;
+        if vesting.beneficiary != *beneficiary_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let amount = vesting.claimable_amount(Clock::get()?.unix_timestamp);
+        if amount == 0 {
+            return Err(TransferError::NothingVested.into())
+        }
+        Self::release_vesting(&vesting, vesting_acc, vesting_token_acc, beneficiary_token_acc, spl_token_acc, amount)?;
+        vesting.claimed_amount += amount;
+        vesting.save(vesting_acc)?;
+
+        TokenEvent::VestingClaimed(VestingClaimedEvent {
+            vesting: *vesting_acc.key,
+            beneficiary: vesting.beneficiary,
+            amount,
+            claimed_amount: vesting.claimed_amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Claim vested vesting={:?}, amount={} done", vesting_acc.key, amount);
+        Ok(())
+    }
+
+    /// Vested part is not forfeited by cancel, it is released to beneficiary before the refund
+    #[cfg(feature = "vesting")]
+    fn cancel_vesting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            creator_acc: signer writable,
+            vesting_acc: writable,
+            vesting_token_acc: writable,
+            creator_token_acc: writable,
+            beneficiary_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Cancel vesting={:?}", vesting_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let vesting = VestingSchedule::load(vesting_acc, program_id)?;
+        if vesting.creator != *creator_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if TokenAccount::new(beneficiary_token_acc)?.owner != vesting.beneficiary {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+        let released = vesting.claimable_amount(Clock::get()?.unix_timestamp);
+        let refunded = TokenAccount::new(vesting_token_acc)?.amount.saturating_sub(released);
+        let transfers = [(beneficiary_token_acc, released), (creator_token_acc, refunded)];
+        for (to_spl_token_acc, amount) in transfers.iter().filter(|(_, amount)| *amount > 0) {
+            Self::release_vesting(&vesting, vesting_acc, vesting_token_acc, to_spl_token_acc, spl_token_acc, *amount)?;
+        }
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            vesting_token_acc.key,
+            creator_acc.key,
+            vesting_acc.key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_instr,
+            &[vesting_token_acc.clone(), creator_acc.clone(), vesting_acc.clone(), spl_token_acc.clone()],
+            &[&Self::vesting_seeds(&vesting)],
+        )?;
+        Self::close_program_account(program_id, vesting_acc, creator_acc)?;
+
+        TokenEvent::VestingCancelled(VestingCancelledEvent {
+            vesting: *vesting_acc.key,
+            creator: vesting.creator,
+            released,
+            refunded,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Cancel vesting={:?}, released={}, refunded={} done", vesting_acc.key, released, refunded);
+        Ok(())
+    }
+
+    #[cfg(feature = "sponsorship")]
+    fn create_sponsor_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        max_per_wallet: u16,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            sponsor_acc: signer writable,
+            operator_acc,
+            pool_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!(
+            "Create sponsor pool sponsor={:?}, max_per_wallet={}, amount={}",
+            sponsor_acc.key, max_per_wallet, amount
+        );
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_SPONSORSHIP)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        if max_per_wallet == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (pool_key, bump) = SponsorPool::find_address(sponsor_acc.key);
+        if pool_key != *pool_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let lamports = Rent::get()?
+            .minimum_balance(SponsorPool::LEN)
+            .checked_add(amount)
+            .ok_or(TransferError::AmountOverflow)?;
+        Self::create_program_account(
+            program_id,
+            sponsor_acc,
+            pool_acc,
+            system_program_acc,
+            SponsorPool::LEN,
+            lamports,
+            &[SponsorPool::SEED, sponsor_acc.key.as_ref(), &[bump]],
+        )?;
+        SponsorPool {
+            account_type: AccountType::SponsorPool,
+            sponsor: *sponsor_acc.key,
+            operator: *operator_acc.key,
+            max_per_wallet,
+            remaining: amount,
+            sponsored_count: 0,
+            bump,
+        }.save(pool_acc)?;
+
+        TokenEvent::SponsorPoolCreated(SponsorPoolCreatedEvent {
+            pool: *pool_acc.key,
+            sponsor: *sponsor_acc.key,
+            operator: *operator_acc.key,
+            max_per_wallet,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create sponsor pool={:?} done", pool_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "sponsorship")]
+    fn fund_sponsor_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            funder_acc: signer writable,
+            pool_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Fund sponsor pool={:?}, funder={:?}, amount={}", pool_acc.key, funder_acc.key, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_SPONSORSHIP)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        if amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let mut pool = ProgramAccount::<SponsorPool>::new(pool_acc, program_id)?;
+
+        let transfer_instr = system_instruction::transfer(funder_acc.key, pool_acc.key, amount);
+        invoke(&transfer_instr, &[funder_acc.clone(), pool_acc.clone(), system_program_acc.clone()])?;
+        pool.remaining = pool.remaining.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        pool.save()?;
+
+        TokenEvent::SponsorPoolFunded(SponsorPoolFundedEvent {
+            pool: *pool_acc.key,
+            funder: *funder_acc.key,
+            amount,
+            remaining: pool.remaining,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Fund sponsor pool={:?}, amount={} done", pool_acc.key, amount);
+        Ok(())
+    }
+
+    /// Pool reimburses operator before the accounts are created, operator pays them through system program
+    /// as program owned pool can not fund a system account creation
+    #[cfg(feature = "sponsorship")]
+    fn create_sponsored_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            operator_acc: signer writable,
+            wallet_acc,
+            token_account_acc: writable,
+            mint_acc: mint,
+            pool_acc: writable,
+            sponsored_wallet_acc: writable,
+            system_program_acc: program(system_program::id()),
+            spl_token_acc: token_program,
+            associated_token_program_acc: program(spl_associated_token_account::id()),
+            rent_acc: program(sysvar::rent::id()),
+            config_acc,
+        });
+        msg!("Create sponsored account wallet={:?}, mint={:?}", wallet_acc.key, mint_acc.key);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_SPONSORSHIP)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut pool = ProgramAccount::<SponsorPool>::new(pool_acc, program_id)?;
+        if pool.operator != *operator_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if get_associated_token_address(wallet_acc.key, mint_acc.key) != *token_account_acc.key {
+            return Err(TransferError::DestinationNotAssociated.into())
+        }
+        if !token_account_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        let (sponsored_wallet_key, bump) = SponsoredWallet::find_address(pool_acc.key, wallet_acc.key);
+        if sponsored_wallet_key != *sponsored_wallet_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let rent = Rent::get()?;
+        let is_new_wallet = sponsored_wallet_acc.data_is_empty();
+        let mut sponsored_wallet = if is_new_wallet {
+            SponsoredWallet {
+                account_type: AccountType::SponsoredWallet,
+                pool: *pool_acc.key,
+                wallet: *wallet_acc.key,
+                count: 0,
+                bump,
+            }
+        } else {
+            SponsoredWallet::load(sponsored_wallet_acc, program_id)?
+        };
+        if sponsored_wallet.count >= pool.max_per_wallet {
+            return Err(TransferError::SponsorshipLimitReached.into())
+        }
+        let wallet_rent = if is_new_wallet { rent.minimum_balance(SponsoredWallet::LEN) } else { 0 };
+        let cost = rent.minimum_balance(spl_token::state::Account::LEN) + wallet_rent;
+        if cost > pool.remaining {
+            return Err(TransferError::SponsorPoolExhausted.into())
+        }
+
+        pool.remaining -= cost;
+        pool.sponsored_count += 1;
+        if is_new_wallet {
+            Self::create_program_account(
+                program_id,
+                operator_acc,
+                sponsored_wallet_acc,
+                system_program_acc,
+                SponsoredWallet::LEN,
+                wallet_rent,
+                &[SponsoredWallet::SEED, pool_acc.key.as_ref(), wallet_acc.key.as_ref(), &[bump]],
+            )?;
+        }
+        let create_instr = spl_associated_token_account::instruction::create_associated_token_account(
+            operator_acc.key,
+            wallet_acc.key,
+            mint_acc.key,
+            &spl_token::id(),
+        );
+        invoke(
+            &create_instr,
+            &[
+                operator_acc.clone(),
+                token_account_acc.clone(),
+                wallet_acc.clone(),
+                mint_acc.clone(),
+                system_program_acc.clone(),
+                spl_token_acc.clone(),
+                rent_acc.clone(),
+                associated_token_program_acc.clone(),
+            ],
+        )?;
+        // Refunded once the CPIs are done, the runtime rejects a CPI while caller moved lamports outside of it
+        Self::move_lamports(program_id, pool_acc, operator_acc, cost)?;
+        sponsored_wallet.count += 1;
+        sponsored_wallet.save(sponsored_wallet_acc)?;
+        pool.save()?;
+
+        TokenEvent::AccountSponsored(AccountSponsoredEvent {
+            pool: *pool_acc.key,
+            wallet: *wallet_acc.key,
+            mint: *mint_acc.key,
+            token_account: *token_account_acc.key,
+            rent: cost,
+            remaining: pool.remaining,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create sponsored account={:?}, rent={} done", token_account_acc.key, cost);
+        Ok(())
+    }
+
+    #[cfg(feature = "sponsorship")]
+    fn close_sponsor_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            sponsor_acc: signer writable,
+            pool_acc: writable,
+            config_acc,
+        });
+        msg!("Close sponsor pool={:?}", pool_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let pool = ProgramAccount::<SponsorPool>::new(pool_acc, program_id)?;
+        if pool.sponsor != *sponsor_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+
+        Self::close_program_account(program_id, pool_acc, sponsor_acc)?;
+
+        TokenEvent::SponsorPoolClosed(SponsorPoolClosedEvent {
+            pool: *pool_acc.key,
+            sponsor: pool.sponsor,
+            returned: pool.remaining,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Close sponsor pool={:?} done", pool_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "streams")]
+    fn create_stream(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        deposit: u64,
+        rate_per_second: u64,
+        start_ts: i64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            sender_acc: signer writable,
+            stream_acc: writable,
+            recipient_acc,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        let token_accs = if acc_iter.len() == 0 {
+            None
+        } else {
+            accounts!(acc_iter in accounts => {
+                mint_acc: mint,
+                stream_token_acc: writable token_account(mint_acc.key),
+                spl_token_acc: token_program,
+                sender_token_acc: writable,
+                mint_config_acc,
+            });
+            Some((mint_acc, stream_token_acc, spl_token_acc, sender_token_acc, mint_config_acc))
+        };
+        msg!("Create stream={:?}, recipient={:?}, deposit={}", stream_acc.key, recipient_acc.key, deposit);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_STREAMS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        if deposit == 0 || rate_per_second == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (stream_key, bump) = Stream::find_address(sender_acc.key, recipient_acc.key);
+        if stream_key != *stream_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if let Some((mint_acc, stream_token_acc, .., mint_config_acc)) = token_accs {
+            if get_associated_token_address(stream_acc.key, mint_acc.key) != *stream_token_acc.key {
+                return Err(ProgramError::InvalidSeeds)
+            }
+            Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+        }
+
+        let rent = Rent::get()?.minimum_balance(Stream::LEN);
+        let lamports = match token_accs {
+            None => rent.checked_add(deposit).ok_or(TransferError::AmountOverflow)?,
+            Some(_) => rent,
+        };
+        Self::create_program_account(
+            program_id,
+            sender_acc,
+            stream_acc,
+            system_program_acc,
+            Stream::LEN,
+            lamports,
+            &[Stream::SEED, sender_acc.key.as_ref(), recipient_acc.key.as_ref(), &[bump]],
+        )?;
+        let mint = match token_accs {
+            None => Pubkey::default(),
+            Some((mint_acc, stream_token_acc, spl_token_acc, sender_token_acc, _)) => {
+                let transfer_instr = spl_token::instruction::transfer(
+                    spl_token_acc.key,
+                    sender_token_acc.key,
+                    stream_token_acc.key,
+                    sender_acc.key,
+                    &[sender_acc.key],
+                    deposit,
+                )?;
+                invoke(
+                    &transfer_instr,
+                    &[sender_token_acc.clone(), stream_token_acc.clone(), sender_acc.clone(), spl_token_acc.clone()],
+                )?;
+                *mint_acc.key
+            }
+        };
+        Stream {
+            account_type: AccountType::Stream,
+            sender: *sender_acc.key,
+            recipient: *recipient_acc.key,
+            mint,
+            deposit,
+            rate_per_second,
+            start_ts,
+            withdrawn: 0,
+            bump,
+        }.save(stream_acc)?;
+
+        TokenEvent::StreamCreated(StreamCreatedEvent {
+            stream: *stream_acc.key,
+            sender: *sender_acc.key,
+            recipient: *recipient_acc.key,
+            mint,
+            deposit,
+            rate_per_second,
+            start_ts,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create stream={:?}, deposit={} done", stream_acc.key, deposit);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_STREAMS`, so disabling streams never locks deposits in
+    #[cfg(feature = "streams")]
+    fn withdraw_from_stream(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            recipient_acc: signer writable,
+            stream_acc: writable,
+            config_acc,
+        });
+        msg!("Withdraw from stream={:?}, recipient={:?}", stream_acc.key, recipient_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut stream = Stream::load(stream_acc, program_id)?;
+        if stream.recipient != *recipient_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let token_accs = Self::load_stream_token(&stream, stream_acc, acc_iter)?;
+        let to_acc = match token_accs {
+            None => recipient_acc,
+            Some(_) => {
+                accounts!(acc_iter in accounts => {
+                    recipient_token_acc: writable,
+                });
+                recipient_token_acc
+            }
+        };
+
+        let amount = stream.withdrawable_amount(Clock::get()?.unix_timestamp);
+        if amount == 0 {
+            return Err(TransferError::NothingStreamed.into())
+        }
+        Self::pay_from_stream(program_id, &stream, stream_acc, to_acc, token_accs, amount)?;
+        stream.withdrawn += amount;
+        stream.save(stream_acc)?;
+
+        TokenEvent::StreamWithdrawn(StreamWithdrawnEvent {
+            stream: *stream_acc.key,
+            recipient: stream.recipient,
+            amount,
+            withdrawn: stream.withdrawn,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Withdraw from stream={:?}, amount={} done", stream_acc.key, amount);
+        Ok(())
+    }
+
+    /// Streamed part is not forfeited by close, it is paid to recipient before the refund
+    #[cfg(feature = "streams")]
+    fn close_stream(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            sender_acc: signer writable,
+            stream_acc: writable,
+            recipient_acc: writable,
+            config_acc,
+        });
+        msg!("Close stream={:?}", stream_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let stream = Stream::load(stream_acc, program_id)?;
+        if stream.sender != *sender_acc.key || stream.recipient != *recipient_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let token_accs = Self::load_stream_token(&stream, stream_acc, acc_iter)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let paid = stream.withdrawable_amount(now);
+        let refunded = stream.deposit - stream.streamed_amount(now);
+        match token_accs {
+            None => Self::pay_from_stream(program_id, &stream, stream_acc, recipient_acc, None, paid)?,
+            Some((stream_token_acc, spl_token_acc)) => {
+                accounts!(acc_iter in accounts => {
+                    sender_token_acc: writable,
+                    recipient_token_acc: writable,
+                });
+                if TokenAccount::new(recipient_token_acc)?.owner != stream.recipient {
+                    return Err(TransferError::TokenAccountOwnerMismatch.into())
+                }
+                Self::pay_from_stream(program_id, &stream, stream_acc, recipient_token_acc, token_accs, paid)?;
+                let rest = TokenAccount::new(stream_token_acc)?.amount;
+                Self::pay_from_stream(program_id, &stream, stream_acc, sender_token_acc, token_accs, rest)?;
+                let close_instr = spl_token::instruction::close_account(
+                    spl_token_acc.key,
+                    stream_token_acc.key,
+                    sender_acc.key,
+                    stream_acc.key,
+                    &[],
+                )?;
+                invoke_signed(
+                    &close_instr,
+                    &[stream_token_acc.clone(), sender_acc.clone(), stream_acc.clone(), spl_token_acc.clone()],
+                    &[&Self::stream_seeds(&stream)],
+                )?;
+            }
+        }
+        Self::close_program_account(program_id, stream_acc, sender_acc)?;
+
+        TokenEvent::StreamClosed(StreamClosedEvent {
+            stream: *stream_acc.key,
+            sender: stream.sender,
+            paid,
+            refunded,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Close stream={:?}, paid={}, refunded={} done", stream_acc.key, paid, refunded);
+        Ok(())
+    }
+
+    #[cfg(feature = "claimables")]
+    fn park_spl_token(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            from_acc: signer writable,
+            from_spl_token_acc: writable,
+            to_wallet_acc,
+            mint_acc: mint,
+            claimable_acc: writable,
+            claimable_token_acc: writable,
+            system_program_acc: program(system_program::id()),
+            spl_token_acc: token_program,
+            associated_token_program_acc: program(spl_associated_token_account::id()),
+            rent_acc: program(sysvar::rent::id()),
+            config_acc,
+            mint_config_acc,
+        });
+        msg!("Park spl token from={:?}, to wallet={:?}, amount={}", from_spl_token_acc.key, to_wallet_acc.key, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_CLAIMABLE_BALANCES)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        if amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let from_spl_token = TokenAccount::new(from_spl_token_acc)?;
+        if from_spl_token.owner != *from_acc.key {
+            return Err(TransferError::SourceOwnerMismatch.into())
+        }
+        if from_spl_token.mint != *mint_acc.key {
+            return Err(TransferError::MintMismatch.into())
+        }
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+        let (claimable_key, bump) = ClaimableBalance::find_address(to_wallet_acc.key, mint_acc.key);
+        if claimable_key != *claimable_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(claimable_acc.key, mint_acc.key) != *claimable_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let mut claimable = if claimable_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                from_acc,
+                claimable_acc,
+                system_program_acc,
+                ClaimableBalance::LEN,
+                Rent::get()?.minimum_balance(ClaimableBalance::LEN),
+                &[ClaimableBalance::SEED, to_wallet_acc.key.as_ref(), mint_acc.key.as_ref(), &[bump]],
+            )?;
+            let create_instr = spl_associated_token_account::instruction::create_associated_token_account(
+                from_acc.key,
+                claimable_acc.key,
+                mint_acc.key,
+                &spl_token::id(),
+            );
+            invoke(
+                &create_instr,
+                &[
+                    from_acc.clone(),
+                    claimable_token_acc.clone(),
+                    claimable_acc.clone(),
+                    mint_acc.clone(),
+                    system_program_acc.clone(),
+                    spl_token_acc.clone(),
+                    rent_acc.clone(),
+                    associated_token_program_acc.clone(),
+                ],
+            )?;
+            ClaimableBalance {
+                account_type: AccountType::ClaimableBalance,
+                wallet: *to_wallet_acc.key,
+                mint: *mint_acc.key,
+                amount: 0,
+                payer: *from_acc.key,
+                bump,
+            }
+        } else {
+            ClaimableBalance::load(claimable_acc, program_id)?
+        };
+
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            from_spl_token_acc.key,
+            claimable_token_acc.key,
+            from_acc.key,
+            &[from_acc.key],
+            amount,
+        )?;
+        invoke(
+            &transfer_instr,
+            &[from_spl_token_acc.clone(), claimable_token_acc.clone(), from_acc.clone(), spl_token_acc.clone()],
+        )?;
+        claimable.amount = claimable.amount.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        claimable.save(claimable_acc)?;
+
+        TokenEvent::SplTokenParked(SplTokenParkedEvent {
+            claimable: *claimable_acc.key,
+            from: *from_acc.key,
+            wallet: claimable.wallet,
+            mint: claimable.mint,
+            amount,
+            total: claimable.amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Park spl token to wallet={:?}, amount={} done", to_wallet_acc.key, amount);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_CLAIMABLE_BALANCES`, so disabling claimable balances never locks parked tokens in
+    #[cfg(feature = "claimables")]
+    fn claim_balance(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            wallet_acc: signer writable,
+            wallet_token_acc: writable,
+            mint_acc: mint,
+            claimable_acc: writable,
+            claimable_token_acc: writable,
+            payer_acc: writable,
+            system_program_acc: program(system_program::id()),
+            spl_token_acc: token_program,
+            associated_token_program_acc: program(spl_associated_token_account::id()),
+            rent_acc: program(sysvar::rent::id()),
+            config_acc,
+        });
+        msg!("Claim balance={:?}, wallet={:?}", claimable_acc.key, wallet_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let claimable = ClaimableBalance::load(claimable_acc, program_id)?;
+        if claimable.wallet != *wallet_acc.key
+            || claimable.mint != *mint_acc.key
+            || claimable.payer != *payer_acc.key
+        {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if get_associated_token_address(wallet_acc.key, mint_acc.key) != *wallet_token_acc.key {
+            return Err(TransferError::DestinationNotAssociated.into())
+        }
+        if get_associated_token_address(claimable_acc.key, mint_acc.key) != *claimable_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        if wallet_token_acc.data_is_empty() {
+            msg!("Create associated token account={:?}", wallet_token_acc.key);
+            let create_instr = spl_associated_token_account::instruction::create_associated_token_account(
+                wallet_acc.key,
+                wallet_acc.key,
+                mint_acc.key,
+                &spl_token::id(),
+            );
+            invoke(
+                &create_instr,
+                &[
+                    wallet_acc.clone(),
+                    wallet_token_acc.clone(),
+                    wallet_acc.clone(),
+                    mint_acc.clone(),
+                    system_program_acc.clone(),
+                    spl_token_acc.clone(),
+                    rent_acc.clone(),
+                    associated_token_program_acc.clone(),
+                ],
+            )?;
+        }
+        let seeds: &[&[u8]] = &[
+            ClaimableBalance::SEED,
+            claimable.wallet.as_ref(),
+            claimable.mint.as_ref(),
+            &[claimable.bump],
+        ];
+        let amount = TokenAccount::new(claimable_token_acc)?.amount;
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            claimable_token_acc.key,
+            wallet_token_acc.key,
+            claimable_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke_signed(
+            &transfer_instr,
+            &[claimable_token_acc.clone(), wallet_token_acc.clone(), claimable_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            claimable_token_acc.key,
+            payer_acc.key,
+            claimable_acc.key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_instr,
+            &[claimable_token_acc.clone(), payer_acc.clone(), claimable_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        Self::close_program_account(program_id, claimable_acc, payer_acc)?;
+
+        TokenEvent::BalanceClaimed(BalanceClaimedEvent {
+            claimable: *claimable_acc.key,
+            wallet: claimable.wallet,
+            mint: claimable.mint,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Claim balance={:?}, amount={} done", claimable_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "rosters")]
+    fn register_recipient(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            recipient_acc: signer writable,
+            payer_acc,
+            roster_entry_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Register recipient={:?}, payer={:?}", recipient_acc.key, payer_acc.key);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_RECIPIENT_ROSTERS)?;
+        let (roster_entry_key, bump) = RosterEntry::find_address(payer_acc.key, recipient_acc.key);
+        if roster_entry_key != *roster_entry_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !roster_entry_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+
+        Self::create_program_account(
+            program_id,
+            recipient_acc,
+            roster_entry_acc,
+            system_program_acc,
+            RosterEntry::LEN,
+            Rent::get()?.minimum_balance(RosterEntry::LEN),
+            &[RosterEntry::SEED, payer_acc.key.as_ref(), recipient_acc.key.as_ref(), &[bump]],
+        )?;
+        RosterEntry {
+            account_type: AccountType::RosterEntry,
+            payer: *payer_acc.key,
+            recipient: *recipient_acc.key,
+            registered_at: Clock::get()?.unix_timestamp,
+            bump,
+        }.save(roster_entry_acc)?;
+
+        TokenEvent::RecipientRegistered(RecipientRegisteredEvent {
+            roster_entry: *roster_entry_acc.key,
+            payer: *payer_acc.key,
+            recipient: *recipient_acc.key,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Register recipient={:?} done", recipient_acc.key);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_RECIPIENT_ROSTERS`, so disabling rosters never locks recipients' rent in
+    #[cfg(feature = "rosters")]
+    fn remove_recipient(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            payer_acc: signer,
+            recipient_acc: writable,
+            roster_entry_acc: writable,
+        });
+        msg!("Remove recipient={:?}, payer={:?}", recipient_acc.key, payer_acc.key);
+
+        let roster_entry = RosterEntry::load(roster_entry_acc, program_id)?;
+        if roster_entry.payer != *payer_acc.key || roster_entry.recipient != *recipient_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        Self::close_program_account(program_id, roster_entry_acc, recipient_acc)?;
+
+        TokenEvent::RecipientRemoved(RecipientRemovedEvent {
+            roster_entry: *roster_entry_acc.key,
+            payer: *payer_acc.key,
+            recipient: *recipient_acc.key,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Remove recipient={:?} done", recipient_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "rosters")]
+    fn transfer_lamports_to_recipient(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            from_acc: signer writable,
+            to_acc: writable,
+            system_program_acc: program(system_program::id()),
+            roster_entry_acc,
+        });
+        msg!("Transfer lamports to recipient from={:?}, to={:?}, amount={}", from_acc.key, to_acc.key, amount);
+
+        Self::check_feature(program_id, policy.config_acc, Config::FEATURE_RECIPIENT_ROSTERS)?;
+        Self::check_registered(program_id, roster_entry_acc, from_acc.key, to_acc.key)?;
+        Self::check_registry(program_id, &policy, to_acc.key)?;
+        let hook_program_acc = Self::check_transfer_policy(
+            program_id, &policy, from_acc.key, &Pubkey::default(), amount,
+        )?;
+
+        let transfer_instr = system_instruction::transfer(from_acc.key, to_acc.key, amount);
+        Self::hooked_transfer(hook_program_acc, from_acc, from_acc, to_acc, amount, || {
+            invoke(&transfer_instr, &[from_acc.clone(), to_acc.clone(), system_program_acc.clone()])
+        })?;
+        TokenEvent::TransferLamports(TransferLamportsEvent {
+            from: *from_acc.key,
+            to: *to_acc.key,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!("Transfer lamports to recipient from={:?}, to={:?}, amount={} done", from_acc.key, to_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "rosters")]
+    fn transfer_spl_token_to_recipient(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            from_acc,
+            from_spl_token_acc: writable,
+            to_spl_token_acc: writable,
+            spl_token_acc: token_program,
+            roster_entry_acc,
+            mint_config_acc,
+        });
+        msg!(
+            "Transfer spl token to recipient from={:?}, to={:?}, amount={}",
+            from_spl_token_acc.key, to_spl_token_acc.key, amount
+        );
+
+        let (_, signer_accs) = Self::split_multisig_signers(from_acc, acc_iter.as_slice())?;
+        Self::check_feature(program_id, policy.config_acc, Config::FEATURE_RECIPIENT_ROSTERS)?;
+        let mint = Self::check_token_transfer(from_spl_token_acc, to_spl_token_acc, from_acc.key)?;
+        let recipient = TokenAccount::new(to_spl_token_acc)?.owner;
+        Self::check_registered(program_id, roster_entry_acc, from_acc.key, &recipient)?;
+        Self::check_registry(program_id, &policy, &recipient)?;
+        Self::check_transferable(program_id, mint_config_acc, &mint)?;
+        let hook_program_acc = Self::check_transfer_policy(program_id, &policy, from_acc.key, &mint, amount)?;
+        Self::check_strict_ata(program_id, policy.config_acc, to_spl_token_acc)?;
+        Self::check_travel_rule(program_id, policy.config_acc, amount, None)?;
+
+        Self::hooked_transfer(hook_program_acc, from_acc, from_spl_token_acc, to_spl_token_acc, amount, || {
+            Self::invoke_token_transfer(
+                spl_token_acc,
+                from_spl_token_acc,
+                to_spl_token_acc,
+                from_acc,
+                signer_accs,
+                amount,
+            )
+        })?;
+        TokenEvent::TransferSpl(TransferSplEvent {
+            from: *from_spl_token_acc.key,
+            to: *to_spl_token_acc.key,
+            owner: *from_acc.key,
+            mint,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!(
+            "Transfer spl token to recipient from={:?}, to={:?}, amount={} done",
+            from_spl_token_acc.key, to_spl_token_acc.key, amount
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "contracts")]
+    fn create_contract(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        id: u64,
+        milestones: &[MilestoneTerms],
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            funder_acc: signer writable,
+            funder_token_acc: writable,
+            payee_acc,
+            arbiter_acc,
+            mint_acc: mint,
+            contract_acc: writable,
+            contract_token_acc: writable token_account(mint_acc.key),
+            mint_config_acc,
+            spl_token_acc: token_program,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Create contract={:?}, payee={:?}, milestones={}", contract_acc.key, payee_acc.key, milestones.len());
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_MILESTONE_CONTRACTS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        let total_amount = MilestoneContract::total_amount(milestones).ok_or(TransferError::InvalidMilestones)?;
+        let (contract_key, bump) = MilestoneContract::find_address(funder_acc.key, payee_acc.key, id);
+        if contract_key != *contract_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(contract_acc.key, mint_acc.key) != *contract_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+
+        Self::create_program_account(
+            program_id,
+            funder_acc,
+            contract_acc,
+            system_program_acc,
+            MilestoneContract::LEN,
+            Rent::get()?.minimum_balance(MilestoneContract::LEN),
+            &[MilestoneContract::SEED, funder_acc.key.as_ref(), payee_acc.key.as_ref(), &id.to_le_bytes(), &[bump]],
+        )?;
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            funder_token_acc.key,
+            contract_token_acc.key,
+            funder_acc.key,
+            &[funder_acc.key],
+            total_amount,
+        )?;
+        invoke(
+            &transfer_instr,
+            &[funder_token_acc.clone(), contract_token_acc.clone(), funder_acc.clone(), spl_token_acc.clone()],
+        )?;
+        let mut contract_milestones = [Milestone::EMPTY; MilestoneContract::MAX_MILESTONES];
+        for (milestone, terms) in contract_milestones.iter_mut().zip(milestones) {
+            milestone.terms = *terms;
+        }
+        MilestoneContract {
+            account_type: AccountType::MilestoneContract,
+            funder: *funder_acc.key,
+            payee: *payee_acc.key,
+            arbiter: *arbiter_acc.key,
+            mint: *mint_acc.key,
+            id,
+            released_total: 0,
+            milestone_count: milestones.len() as u8,
+            milestones: contract_milestones,
+            bump,
+        }.save(contract_acc)?;
+
+        TokenEvent::ContractCreated(ContractCreatedEvent {
+            contract: *contract_acc.key,
+            funder: *funder_acc.key,
+            payee: *payee_acc.key,
+            arbiter: *arbiter_acc.key,
+            mint: *mint_acc.key,
+            total_amount,
+            milestone_count: milestones.len() as u8,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create contract={:?}, amount={} done", contract_acc.key, total_amount);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_MILESTONE_CONTRACTS`, so disabling contracts never locks escrowed tranches in
+    #[cfg(feature = "contracts")]
+    fn submit_deliverable(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        index: u8,
+        deliverable_hash: [u8; 32],
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            payee_acc: signer,
+            contract_acc: writable,
+        });
+        msg!("Submit deliverable contract={:?}, index={}", contract_acc.key, index);
+
+        let mut contract = MilestoneContract::load(contract_acc, program_id)?;
+        if contract.payee != *payee_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let milestone = contract.milestone_mut(index).ok_or(ProgramError::InvalidArgument)?;
+        if milestone.status == MilestoneStatus::Released {
+            return Err(TransferError::MilestoneAlreadyReleased.into())
+        }
+        milestone.submitted_hash = deliverable_hash;
+        milestone.status = MilestoneStatus::Submitted;
+        contract.save(contract_acc)?;
+
+        TokenEvent::DeliverableSubmitted(DeliverableSubmittedEvent {
+            contract: *contract_acc.key,
+            payee: contract.payee,
+            index,
+            deliverable_hash,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Submit deliverable contract={:?}, index={} done", contract_acc.key, index);
+        Ok(())
+    }
+
+    #[cfg(feature = "contracts")]
+    fn release_milestone(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        index: u8,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer,
+            contract_acc: writable,
+            contract_token_acc: writable,
+            payee_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Release milestone contract={:?}, index={}", contract_acc.key, index);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut contract = MilestoneContract::load(contract_acc, program_id)?;
+        if *authority_acc.key != contract.funder && *authority_acc.key != contract.arbiter {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if TokenAccount::new(payee_token_acc)?.owner != contract.payee {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+        let milestone = contract.milestone_mut(index).ok_or(ProgramError::InvalidArgument)?;
+        match milestone.status {
+            MilestoneStatus::Pending => return Err(TransferError::MilestoneNotSubmitted.into()),
+            MilestoneStatus::Released => return Err(TransferError::MilestoneAlreadyReleased.into()),
+            MilestoneStatus::Submitted => milestone.status = MilestoneStatus::Released,
+        }
+        let amount = milestone.terms.amount;
+        contract.released_total += amount;
+        Self::release_contract_tokens(
+            &contract,
+            contract_acc,
+            contract_token_acc,
+            payee_token_acc,
+            spl_token_acc,
+            amount,
+        )?;
+        contract.save(contract_acc)?;
+
+        TokenEvent::MilestoneReleased(MilestoneReleasedEvent {
+            contract: *contract_acc.key,
+            authority: *authority_acc.key,
+            index,
+            amount,
+            released_total: contract.released_total,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Release milestone contract={:?}, index={}, amount={} done", contract_acc.key, index, amount);
+        Ok(())
+    }
+
+    /// Funder may only close a completed contract, so it can't pull tranches of submitted deliverables back
+    #[cfg(feature = "contracts")]
+    fn close_contract(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer,
+            funder_acc: writable,
+            contract_acc: writable,
+            contract_token_acc: writable,
+            funder_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Close contract={:?}, authority={:?}", contract_acc.key, authority_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let contract = MilestoneContract::load(contract_acc, program_id)?;
+        if contract.funder != *funder_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if *authority_acc.key != contract.arbiter {
+            if *authority_acc.key != contract.funder {
+                return Err(TransferError::AccountMismatch.into())
+            }
+            if !contract.is_completed() {
+                return Err(TransferError::ContractNotCompleted.into())
+            }
+        }
+        if get_associated_token_address(contract_acc.key, &contract.mint) != *contract_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(funder_token_acc)?.owner != contract.funder {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+        let refunded = TokenAccount::new(contract_token_acc)?.amount;
+        if refunded > 0 {
+            Self::release_contract_tokens(
+                &contract,
+                contract_acc,
+                contract_token_acc,
+                funder_token_acc,
+                spl_token_acc,
+                refunded,
+            )?;
+        }
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            contract_token_acc.key,
+            funder_acc.key,
+            contract_acc.key,
+            &[],
+        )?;
+        let id = contract.id.to_le_bytes();
+        invoke_signed(
+            &close_instr,
+            &[contract_token_acc.clone(), funder_acc.clone(), contract_acc.clone(), spl_token_acc.clone()],
+            &[&Self::contract_seeds(&contract, &id)],
+        )?;
+        Self::close_program_account(program_id, contract_acc, funder_acc)?;
+
+        TokenEvent::ContractClosed(ContractClosedEvent {
+            contract: *contract_acc.key,
+            closed_by: *authority_acc.key,
+            refunded,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Close contract={:?}, refunded={} done", contract_acc.key, refunded);
+        Ok(())
+    }
+
+    /// Every recipient is validated before its transfer, any failure reverts the whole instruction
+    fn transfer_lamports_batch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amounts: &[u64],
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            from_acc: signer writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Transfer lamports batch from={:?}, recipients={}", from_acc.key, amounts.len());
+
+        Self::check_batch_recipients(amounts, acc_iter.len())?;
+        let total = amounts.iter().try_fold(0u64, |total, amount| total.checked_add(*amount));
+        let total = total.ok_or(TransferError::AmountOverflow)?;
+        let hook_program_acc = Self::check_transfer_policy(
+            program_id, &policy, from_acc.key, &Pubkey::default(), total,
+        )?;
+        for &amount in amounts {
+            accounts!(acc_iter in accounts => {
+                to_acc: writable,
+            });
+            msg!("Transfer lamports to={:?}, amount={}", to_acc.key, amount);
+            Self::check_registry(program_id, &policy, to_acc.key)?;
+            let transfer_instr = system_instruction::transfer(from_acc.key, to_acc.key, amount);
+            Self::hooked_transfer(hook_program_acc, from_acc, from_acc, to_acc, amount, || {
+                invoke(&transfer_instr, &[from_acc.clone(), to_acc.clone(), system_program_acc.clone()])
+            })?;
+            TokenEvent::TransferLamports(TransferLamportsEvent {
+                from: *from_acc.key,
+                to: *to_acc.key,
+                amount,
+                fee: 0,
+                memo: None,
+            }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        }
+
+        msg!("Transfer lamports batch from={:?}, recipients={} done", from_acc.key, amounts.len());
+        Ok(())
+    }
+
+    fn split_lamports(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+        weights: &[u32],
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            from_acc: signer writable,
+            remainder_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Split lamports from={:?}, amount={}, recipients={}", from_acc.key, amount, acc_iter.len());
+
+        let hook_program_acc = Self::check_transfer_policy(
+            program_id, &policy, from_acc.key, &Pubkey::default(), amount,
+        )?;
+        let (shares, remainder) = split::shares(amount, weights, acc_iter.len())?;
+        let transfers = acc_iter.zip(shares).chain(std::iter::once((remainder_acc, remainder)));
+        for (to_acc, share) in transfers.filter(|(_, share)| *share > 0) {
+            WritableAccount::new(to_acc)?;
+            msg!("Transfer lamports to={:?}, amount={}", to_acc.key, share);
+            Self::check_registry(program_id, &policy, to_acc.key)?;
+            let transfer_instr = system_instruction::transfer(from_acc.key, to_acc.key, share);
+            Self::hooked_transfer(hook_program_acc, from_acc, from_acc, to_acc, share, || {
+                invoke(&transfer_instr, &[from_acc.clone(), to_acc.clone(), system_program_acc.clone()])
+            })?;
+            TokenEvent::TransferLamports(TransferLamportsEvent {
+                from: *from_acc.key,
+                to: *to_acc.key,
+                amount: share,
+                fee: 0,
+                memo: None,
+            }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        }
+
+        msg!("Split lamports from={:?}, amount={}, remainder={} done", from_acc.key, amount, remainder);
+        Ok(())
+    }
+
+    /// Every recipient must be a token account of the source mint and pass strict ATA and travel rule checks
+    fn transfer_spl_token_batch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amounts: &[u64],
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc,
+            from_spl_token_acc: writable,
+            spl_token_acc: token_program,
+            mint_config_acc,
+        });
+        msg!("Transfer spl token batch from={:?}, recipients={}", from_spl_token_acc.key, amounts.len());
+
+        let (recipient_accs, signer_accs) = Self::split_multisig_signers(owner_acc, acc_iter.as_slice())?;
+        Self::check_batch_recipients(amounts, recipient_accs.len())?;
+        let acc_iter = &mut recipient_accs.iter();
+        let from_spl_token = TokenAccount::new(from_spl_token_acc)?;
+        if from_spl_token.owner != *owner_acc.key {
+            return Err(TransferError::SourceOwnerMismatch.into())
+        }
+        let mint = from_spl_token.mint;
+        Self::check_transferable(program_id, mint_config_acc, &mint)?;
+        let total = amounts.iter().try_fold(0u64, |total, amount| total.checked_add(*amount));
+        let total = total.ok_or(TransferError::AmountOverflow)?;
+        let hook_program_acc = Self::check_transfer_policy(program_id, &policy, owner_acc.key, &mint, total)?;
+
+        for &amount in amounts {
+            accounts!(acc_iter in accounts => {
+                to_spl_token_acc: writable token_account(&mint),
+            });
+            msg!("Transfer spl token to={:?}, amount={}", to_spl_token_acc.key, amount);
+            Self::check_registry(program_id, &policy, &TokenAccount::new(to_spl_token_acc)?.owner)?;
+            Self::check_strict_ata(program_id, policy.config_acc, to_spl_token_acc)?;
+            Self::check_travel_rule(program_id, policy.config_acc, amount, None)?;
+
+            Self::hooked_transfer(hook_program_acc, owner_acc, from_spl_token_acc, to_spl_token_acc, amount, || {
+                Self::invoke_token_transfer(
+                    spl_token_acc,
+                    from_spl_token_acc,
+                    to_spl_token_acc,
+                    owner_acc,
+                    signer_accs,
+                    amount,
+                )
+            })?;
+            TokenEvent::TransferSpl(TransferSplEvent {
+                from: *from_spl_token_acc.key,
+                to: *to_spl_token_acc.key,
+                owner: *owner_acc.key,
+                mint,
+                amount,
+                fee: 0,
+                memo: None,
+            }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        }
+
+        msg!("Transfer spl token batch from={:?}, recipients={} done", from_spl_token_acc.key, amounts.len());
+        Ok(())
+    }
+
+    fn transfer_spl_token_to_wallet(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc,
+            from_spl_token_acc: writable,
+            to_wallet_acc,
+            to_spl_token_acc: writable token_account(mint_acc.key),
+            mint_acc: mint,
+            spl_token_acc: token_program,
+            mint_config_acc,
+        });
+        msg!(
+            "Transfer spl token from={:?}, to wallet={:?}, amount={}",
+            from_spl_token_acc.key, to_wallet_acc.key, amount
+        );
+
+        let (_, signer_accs) = Self::split_multisig_signers(owner_acc, acc_iter.as_slice())?;
+        if Self::check_token_transfer(from_spl_token_acc, to_spl_token_acc, owner_acc.key)? != *mint_acc.key {
+            return Err(TransferError::MintMismatch.into())
+        }
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+        let hook_program_acc = Self::check_transfer_policy(program_id, &policy, owner_acc.key, mint_acc.key, amount)?;
+        Self::check_wallet_token_account(to_wallet_acc, mint_acc, to_spl_token_acc)?;
+        Self::check_registry(program_id, &policy, &TokenAccount::new(to_spl_token_acc)?.owner)?;
+        Self::check_strict_ata(program_id, policy.config_acc, to_spl_token_acc)?;
+        Self::check_travel_rule(program_id, policy.config_acc, amount, None)?;
+
+        Self::hooked_transfer(hook_program_acc, owner_acc, from_spl_token_acc, to_spl_token_acc, amount, || {
+            Self::invoke_token_transfer(
+                spl_token_acc,
+                from_spl_token_acc,
+                to_spl_token_acc,
+                owner_acc,
+                signer_accs,
+                amount,
+            )
+        })?;
+        TokenEvent::TransferSpl(TransferSplEvent {
+            from: *from_spl_token_acc.key,
+            to: *to_spl_token_acc.key,
+            owner: *owner_acc.key,
+            mint: *mint_acc.key,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!(
+            "Transfer spl token from={:?}, to={:?}, amount={} done",
+            from_spl_token_acc.key, to_spl_token_acc.key, amount
+        );
+        Ok(())
+    }
+
+    fn transfer_spl_token_with_ata_creation(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            from_spl_token_acc: writable,
+            to_wallet_acc,
+            to_spl_token_acc: writable,
+            mint_acc: mint,
+            system_program_acc: program(system_program::id()),
+            spl_token_acc: token_program,
+            associated_token_program_acc: program(spl_associated_token_account::id()),
+            rent_acc: program(sysvar::rent::id()),
+            mint_config_acc,
+        });
+        msg!(
+            "Transfer spl token from={:?}, to wallet={:?}, amount={}",
+            from_spl_token_acc.key, to_wallet_acc.key, amount
+        );
+
+        if get_associated_token_address(to_wallet_acc.key, mint_acc.key) != *to_spl_token_acc.key {
+            return Err(TransferError::DestinationNotAssociated.into())
+        }
+        if to_spl_token_acc.data_is_empty() {
+            msg!("Create associated token account={:?}", to_spl_token_acc.key);
+            let create_instr = spl_associated_token_account::instruction::create_associated_token_account(
+                owner_acc.key,
+                to_wallet_acc.key,
+                mint_acc.key,
+                &spl_token::id(),
+            );
+            invoke(
+                &create_instr,
+                &[
+                    owner_acc.clone(),
+                    to_spl_token_acc.clone(),
+                    to_wallet_acc.clone(),
+                    mint_acc.clone(),
+                    system_program_acc.clone(),
+                    spl_token_acc.clone(),
+                    rent_acc.clone(),
+                    associated_token_program_acc.clone(),
+                ],
+            )?;
+        }
+        // Source is checked against the associated token account, which exists by now
+        let mint = Self::check_token_transfer(from_spl_token_acc, to_spl_token_acc, owner_acc.key)?;
+        Self::check_transferable(program_id, mint_config_acc, &mint)?;
+        let hook_program_acc = Self::check_transfer_policy(program_id, &policy, owner_acc.key, &mint, amount)?;
+        Self::check_registry(program_id, &policy, &TokenAccount::new(to_spl_token_acc)?.owner)?;
+        Self::check_travel_rule(program_id, policy.config_acc, amount, None)?;
+
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            from_spl_token_acc.key,
+            to_spl_token_acc.key,
+            owner_acc.key,
+            &[owner_acc.key],
+            amount,
+        )?;
+        Self::hooked_transfer(hook_program_acc, owner_acc, from_spl_token_acc, to_spl_token_acc, amount, || {
+            invoke(
+                &transfer_instr,
+                &[
+                    owner_acc.clone(),
+                    from_spl_token_acc.clone(),
+                    to_spl_token_acc.clone(),
+                    spl_token_acc.clone(),
+                ],
+            )
+        })?;
+        TokenEvent::TransferSpl(TransferSplEvent {
+            from: *from_spl_token_acc.key,
+            to: *to_spl_token_acc.key,
+            owner: *owner_acc.key,
+            mint,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!(
+            "Transfer spl token from={:?}, to={:?}, amount={} done",
+            from_spl_token_acc.key, to_spl_token_acc.key, amount
+        );
+        Ok(())
+    }
+
+    fn wrap_sol(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            wsol_token_acc: writable,
+            native_mint_acc: program(spl_token::native_mint::id()),
+            system_program_acc: program(system_program::id()),
+            spl_token_acc: token_program,
+            associated_token_program_acc: program(spl_associated_token_account::id()),
+            rent_acc: program(sysvar::rent::id()),
+        });
+        msg!("Wrap sol owner={:?}, amount={}", owner_acc.key, amount);
+
+        if get_associated_token_address(owner_acc.key, native_mint_acc.key) != *wsol_token_acc.key {
+            return Err(TransferError::DestinationNotAssociated.into())
+        }
+
+        if wsol_token_acc.data_is_empty() {
+            msg!("Create associated token account={:?}", wsol_token_acc.key);
+            let create_instr = spl_associated_token_account::instruction::create_associated_token_account(
+                owner_acc.key,
+                owner_acc.key,
+                native_mint_acc.key,
+                &spl_token::id(),
+            );
+            invoke(
+                &create_instr,
+                &[
+                    owner_acc.clone(),
+                    wsol_token_acc.clone(),
+                    native_mint_acc.clone(),
+                    system_program_acc.clone(),
+                    spl_token_acc.clone(),
+                    rent_acc.clone(),
+                    associated_token_program_acc.clone(),
+                ],
+            )?;
+        }
+
+        let transfer_instr = system_instruction::transfer(owner_acc.key, wsol_token_acc.key, amount);
+        invoke(&transfer_instr, &[owner_acc.clone(), wsol_token_acc.clone(), system_program_acc.clone()])?;
+        let sync_instr = spl_token::instruction::sync_native(spl_token_acc.key, wsol_token_acc.key)?;
+        invoke(&sync_instr, &[wsol_token_acc.clone(), spl_token_acc.clone()])?;
+
+        msg!("Wrap sol owner={:?}, amount={} done", owner_acc.key, amount);
+        Ok(())
+    }
+
+    fn unwrap_sol(accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            wsol_token_acc: writable token_account(&spl_token::native_mint::id()),
+            spl_token_acc: token_program,
+        });
+        msg!("Unwrap sol owner={:?}, account={:?}", owner_acc.key, wsol_token_acc.key);
+
+        let wsol_token = TokenAccount::new(wsol_token_acc)?;
+        if wsol_token.owner != *owner_acc.key {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+        let lamports = wsol_token_acc.lamports();
+
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            wsol_token_acc.key,
+            owner_acc.key,
+            owner_acc.key,
+            &[],
+        )?;
+        invoke(&close_instr, &[wsol_token_acc.clone(), owner_acc.clone(), spl_token_acc.clone()])?;
+
+        msg!("Unwrap sol owner={:?}, lamports={} done", owner_acc.key, lamports);
+        Ok(())
+    }
+
+    fn check_deadline(valid_until_slot: u64) -> ProgramResult {
+        let slot = Clock::get()?.slot;
+        if slot > valid_until_slot {
+            msg!("Deadline exceeded slot={}, valid_until_slot={}", slot, valid_until_slot);
+            return Err(TransferError::DeadlineExceeded.into())
+        }
+        Ok(())
+    }
+
+    fn initialize_fee_config(program_id: &Pubkey, accounts: &[AccountInfo], fee_bps: u16) -> ProgramResult {
+        let (accounts, namespace_config_acc) = Self::split_namespace_config(program_id, accounts);
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            fee_config_acc: writable,
+            fee_recipient_acc,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Initialize fee config admin={:?}, fee_bps={}", admin_acc.key, fee_bps);
+
+        if fee_bps > FeeConfig::MAX_FEE_BPS {
+            return Err(TransferError::FeeTooHigh.into())
+        }
+        Self::check_namespace_admin(
+            program_id,
+            admin_acc,
+            config_acc,
+            namespace_config_acc,
+            system_program_acc,
+            accounts,
+        )?;
+
+        let mut seeds = namespaced_seeds(FeeConfig::SEED, namespace_config_acc.map(|acc| acc.key), &[]);
+        let (fee_config_key, bump) = Pubkey::find_program_address(&seeds, program_id);
+        if fee_config_key != *fee_config_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !fee_config_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        let bump_seed = [bump];
+        seeds.push(&bump_seed);
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            fee_config_acc,
+            system_program_acc,
+            FeeConfig::LEN,
+            Rent::get()?.minimum_balance(FeeConfig::LEN),
+            &seeds,
+        )?;
+        FeeConfig {
+            account_type: AccountType::FeeConfig,
+            admin: *admin_acc.key,
+            fee_bps,
+            fee_recipient: *fee_recipient_acc.key,
+            bump,
+        }.save(fee_config_acc)?;
+
+        msg!("Initialize fee config fee_recipient={:?}, fee_bps={} done", fee_recipient_acc.key, fee_bps);
+        Ok(())
+    }
+
+    fn initialize_audit_log(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            audit_log_acc: writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Initialize audit log admin={:?}", admin_acc.key);
+
+        let config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        config.save(config_acc)?;
+
+        let (audit_log_key, bump) = AuditLog::find_address();
+        if audit_log_key != *audit_log_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !audit_log_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            audit_log_acc,
+            system_program_acc,
+            AuditLog::LEN,
+            Rent::get()?.minimum_balance(AuditLog::LEN),
+            &[AuditLog::SEED, &[bump]],
+        )?;
+        AuditLog {
+            account_type: AccountType::AuditLog,
+            head: [0; 32],
+            count: 0,
+            last_slot: 0,
+            bump,
+        }.save(audit_log_acc)?;
+
+        msg!("Initialize audit log={:?} done", audit_log_acc.key);
+        Ok(())
+    }
+
+    fn initialize_event_stream(program_id: &Pubkey, accounts: &[AccountInfo], key: Pubkey) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            payer_acc: signer writable,
+            event_stream_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Initialize event stream key={:?}", key);
+
+        let (event_stream_key, bump) = EventStream::find_address(&key);
+        if event_stream_key != *event_stream_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !event_stream_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        Self::create_program_account(
+            program_id,
+            payer_acc,
+            event_stream_acc,
+            system_program_acc,
+            EventStream::LEN,
+            Rent::get()?.minimum_balance(EventStream::LEN),
+            &[EventStream::SEED, key.as_ref(), &[bump]],
+        )?;
+        EventStream {
+            account_type: AccountType::EventStream,
+            key,
+            sequence: 0,
+            bump,
+        }.save(event_stream_acc)?;
+
+        msg!("Initialize event stream={:?} done", event_stream_acc.key);
+        Ok(())
+    }
+
+    fn update_fee_config(program_id: &Pubkey, accounts: &[AccountInfo], fee_bps: u16) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer,
+            fee_config_acc: writable,
+            fee_recipient_acc,
+        });
+        msg!("Update fee config admin={:?}, fee_bps={}", admin_acc.key, fee_bps);
+
+        if fee_bps > FeeConfig::MAX_FEE_BPS {
+            return Err(TransferError::FeeTooHigh.into())
+        }
+        let mut fee_config = ProgramAccount::<FeeConfig>::new(fee_config_acc, program_id)?;
+        if fee_config.admin != *admin_acc.key {
+            return Err(TransferError::NotFeeConfigAdmin.into())
+        }
+        fee_config.fee_bps = fee_bps;
+        fee_config.fee_recipient = *fee_recipient_acc.key;
+        fee_config.save()?;
+
+        msg!("Update fee config fee_recipient={:?}, fee_bps={} done", fee_recipient_acc.key, fee_bps);
+        Ok(())
+    }
+
+    fn approve_spl_token(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc,
+            from_spl_token_acc: writable,
+            to_spl_token_acc: writable,
+            spl_token_acc: token_program,
+        });
+        msg!(
+            "Approve spl token from={:?}, to={:?}, amount={}",
+            from_spl_token_acc.key,
+            to_spl_token_acc.key,
+            amount
+        );
+
+        let (_, signer_accs) = Self::split_multisig_signers(owner_acc, acc_iter.as_slice())?;
+        if TokenAccount::new(from_spl_token_acc)?.owner != *owner_acc.key {
+            return Err(TransferError::SourceOwnerMismatch.into())
+        }
+        let signer_keys: Vec<&Pubkey> = signer_accs.iter().map(|acc| acc.key).collect();
+        let approve_instr = spl_token::instruction::approve(
+            spl_token_acc.key,
+            from_spl_token_acc.key,
+            to_spl_token_acc.key,
+            owner_acc.key,
+            &signer_keys,
+            amount,
+        )?;
+        let mut approve_accs = vec![
+            owner_acc.clone(),
+            from_spl_token_acc.clone(),
+            to_spl_token_acc.clone(),
+            spl_token_acc.clone(),
+        ];
+        approve_accs.extend_from_slice(signer_accs);
+        invoke(&approve_instr, &approve_accs)?;
+        TokenEvent::Approve(ApproveEvent {
+            owner: *owner_acc.key,
+            source: *from_spl_token_acc.key,
+            delegate: *to_spl_token_acc.key,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!(
+            "Approve spl token from={:?}, to={:?}, amount={} done",
+            from_spl_token_acc.key,
+            to_spl_token_acc.key,
+            amount
+        );
+        Ok(())
+    }
+
+    fn batch_approve(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amounts: &[u64],
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer,
+            spl_token_acc: token_program,
+        });
+        msg!("Batch approve owner={:?}, delegates={}", owner_acc.key, amounts.len());
+
+        // Token account and delegate of each amount
+        let pairs = acc_iter.len() / 2;
+        Self::check_batch_recipients(amounts, pairs)?;
+        if acc_iter.len() != pairs * 2 {
+            return Err(ProgramError::InvalidArgument)
+        }
+
+        for &amount in amounts {
+            accounts!(acc_iter in accounts => {
+                spl_token_account_acc: writable,
+                delegate_acc,
+            });
+            msg!(
+                "Approve spl token from={:?}, to={:?}, amount={}",
+                spl_token_account_acc.key, delegate_acc.key, amount
+            );
+            if TokenAccount::new(spl_token_account_acc)?.owner != *owner_acc.key {
+                return Err(TransferError::SourceOwnerMismatch.into())
+            }
+
+            let approve_instr = spl_token::instruction::approve(
+                spl_token_acc.key,
+                spl_token_account_acc.key,
+                delegate_acc.key,
+                owner_acc.key,
+                &[owner_acc.key],
+                amount,
+            )?;
+            invoke(
+                &approve_instr,
+                &[
+                    owner_acc.clone(),
+                    spl_token_account_acc.clone(),
+                    delegate_acc.clone(),
+                    spl_token_acc.clone(),
+                ],
+            )?;
+            TokenEvent::Approve(ApproveEvent {
+                owner: *owner_acc.key,
+                source: *spl_token_account_acc.key,
+                delegate: *delegate_acc.key,
+                amount,
+            }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        }
+
+        msg!("Batch approve owner={:?}, delegates={} done", owner_acc.key, amounts.len());
+        Ok(())
+    }
+
+    /// Scans instructions before the current one for `program` with account keys and data of the given hashes
+    fn guard_preceding_instruction(
+        accounts: &[AccountInfo],
+        program: &Pubkey,
+        accounts_hash: &[u8; 32],
+        data_hash: &[u8; 32],
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            instructions_acc: program(sysvar::instructions::id()),
+        });
+        msg!("Guard preceding instruction of program={:?}", program);
+
+        let current_index = sysvar::instructions::load_current_index_checked(instructions_acc)? as usize;
+        for index in 0..current_index {
+            let instr = sysvar::instructions::load_instruction_at_checked(index, instructions_acc)?;
+            if instr.program_id == *program && TokenInstruction::guard_hashes(&instr) == (*accounts_hash, *data_hash) {
+                msg!("Guard preceding instruction of program={:?} matched instruction {}", program, index);
+                return Ok(())
+            }
+        }
+        let expected = ErrorValue::Key(*program);
+        Err(ErrorContextEvent::fail(TransferError::GuardNotSatisfied, None, Some(expected), None))
+    }
+
+    fn mint_spl_token(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer,
+            mint_acc: writable mint,
+            to_spl_token_acc: writable token_account(mint_acc.key),
+            spl_token_acc: token_program,
+        });
+        msg!("Mint spl token mint={:?}, to={:?}, amount={}", mint_acc.key, to_spl_token_acc.key, amount);
+
+        if MintAccount::new(mint_acc)?.mint_authority != COption::Some(*authority_acc.key) {
+            return Err(TransferError::NotMintAuthority.into())
+        }
+
+        let mint_to_instr = spl_token::instruction::mint_to(
+            spl_token_acc.key,
+            mint_acc.key,
+            to_spl_token_acc.key,
+            authority_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke(
+            &mint_to_instr,
+            &[mint_acc.clone(), to_spl_token_acc.clone(), authority_acc.clone(), spl_token_acc.clone()],
+        )?;
+
+        msg!("Mint spl token mint={:?}, to={:?}, amount={} done", mint_acc.key, to_spl_token_acc.key, amount);
+        Ok(())
+    }
+
+    /// Freeze token account when `frozen`, thaw otherwise
+    fn set_spl_token_account_frozen(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        frozen: bool,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            freeze_authority_acc: signer,
+            spl_token_account_acc: writable token_account(mint_acc.key),
+            mint_acc: mint,
+            spl_token_acc: token_program,
+        });
+        msg!("Set spl token account frozen account={:?}, frozen={}", spl_token_account_acc.key, frozen);
+
+        if MintAccount::new(mint_acc)?.freeze_authority != COption::Some(*freeze_authority_acc.key) {
+            return Err(TransferError::NotFreezeAuthority.into())
+        }
+
+        let build_freeze_instr = if frozen {
+            spl_token::instruction::freeze_account
+        } else {
+            spl_token::instruction::thaw_account
+        };
+        let freeze_instr = build_freeze_instr(
+            spl_token_acc.key,
+            spl_token_account_acc.key,
+            mint_acc.key,
+            freeze_authority_acc.key,
+            &[],
+        )?;
+        invoke(
+            &freeze_instr,
+            &[spl_token_account_acc.clone(), mint_acc.clone(), freeze_authority_acc.clone(), spl_token_acc.clone()],
+        )?;
+        let (account, mint, freeze_authority) = (*spl_token_account_acc.key, *mint_acc.key, *freeze_authority_acc.key);
+        let event = if frozen {
+            TokenEvent::SplTokenAccountFrozen(SplTokenAccountFrozenEvent { account, mint, freeze_authority })
+        } else {
+            TokenEvent::SplTokenAccountThawed(SplTokenAccountThawedEvent { account, mint, freeze_authority })
+        };
+        event.emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!("Set spl token account frozen account={:?}, frozen={} done", spl_token_account_acc.key, frozen);
+        Ok(())
+    }
+
+    fn burn_spl_token(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer,
+            from_spl_token_acc: writable token_account(mint_acc.key),
+            mint_acc: writable mint,
+            spl_token_acc: token_program,
+        });
+        msg!("Burn spl token mint={:?}, from={:?}, amount={}", mint_acc.key, from_spl_token_acc.key, amount);
+
+        if TokenAccount::new(from_spl_token_acc)?.owner != *owner_acc.key {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+
+        let burn_instr = spl_token::instruction::burn(
+            spl_token_acc.key,
+            from_spl_token_acc.key,
+            mint_acc.key,
+            owner_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke(
+            &burn_instr,
+            &[from_spl_token_acc.clone(), mint_acc.clone(), owner_acc.clone(), spl_token_acc.clone()],
+        )?;
+
+        msg!("Burn spl token mint={:?}, from={:?}, amount={} done", mint_acc.key, from_spl_token_acc.key, amount);
+        Ok(())
+    }
+
+    fn close_spl_token_account(accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer,
+            spl_token_account_acc: writable,
+            destination_acc: writable,
+            spl_token_acc: token_program,
+        });
+        msg!("Close spl token account={:?}, destination={:?}", spl_token_account_acc.key, destination_acc.key);
+
+        let token_account = TokenAccount::new(spl_token_account_acc)?;
+        if token_account.close_authority.unwrap_or(token_account.owner) != *authority_acc.key {
+            return Err(TransferError::NotCloseAuthority.into())
+        }
+        if token_account.amount != 0 {
+            return Err(TransferError::TokenAccountNotEmpty.into())
+        }
+
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            spl_token_account_acc.key,
+            destination_acc.key,
+            authority_acc.key,
+            &[],
+        )?;
+        invoke(
+            &close_instr,
+            &[spl_token_account_acc.clone(), destination_acc.clone(), authority_acc.clone(), spl_token_acc.clone()],
+        )?;
+
+        msg!("Close spl token account={:?}, destination={:?} done", spl_token_account_acc.key, destination_acc.key);
+        Ok(())
+    }
+
+    fn self_test(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            caller_acc: signer writable,
+            canary_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Self test caller={:?}", caller_acc.key);
+
+        let (canary_key, bump) = Canary::find_address();
+        if canary_key != *canary_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let features = Self::load_config(program_id, config_acc)?
+            .map_or(Config::DEFAULT_FEATURES, |config| config.features);
+
+        let canary_lamports = canary_acc.lamports();
+        let to_canary_instr = system_instruction::transfer(caller_acc.key, canary_acc.key, Canary::LAMPORTS);
+        invoke(&to_canary_instr, &[caller_acc.clone(), canary_acc.clone(), system_program_acc.clone()])?;
+        let from_canary_instr = system_instruction::transfer(canary_acc.key, caller_acc.key, Canary::LAMPORTS);
+        invoke_signed(
+            &from_canary_instr,
+            &[canary_acc.clone(), caller_acc.clone(), system_program_acc.clone()],
+            &[&[Canary::SEED, &[bump]]],
+        )?;
+        if canary_acc.lamports() != canary_lamports {
+            return Err(TransferError::SelfTestFailed.into())
+        }
+
+        let slot = Clock::get()?.slot;
+        TokenEvent::SelfTestPassed(SelfTestPassedEvent { caller: *caller_acc.key, slot, features })
+            .emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Self test caller={:?} done", caller_acc.key);
+        Ok(())
+    }
+
+    fn transfer_spl_token_checked(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc,
+            from_spl_token_acc: writable,
+            to_spl_token_acc: writable,
+            mint_acc,
+            spl_token_acc: token_program,
+            mint_config_acc,
+        });
+        msg!(
+            "Transfer spl token checked from={:?}, to={:?}, amount={}",
+            from_spl_token_acc.key, to_spl_token_acc.key, amount
+        );
+
+        if Self::check_token_transfer(from_spl_token_acc, to_spl_token_acc, owner_acc.key)? != *mint_acc.key {
+            return Err(TransferError::MintMismatch.into())
+        }
+        let (trailing_accs, signer_accs) = Self::split_multisig_signers(owner_acc, acc_iter.as_slice())?;
+        if Self::mint_decimals(program_id, mint_acc, trailing_accs.first())? != decimals {
+            return Err(TransferError::DecimalsMismatch.into())
+        }
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+        let hook_program_acc = Self::check_transfer_policy(program_id, &policy, owner_acc.key, mint_acc.key, amount)?;
+        Self::check_registry(program_id, &policy, &TokenAccount::new(to_spl_token_acc)?.owner)?;
+        Self::check_strict_ata(program_id, policy.config_acc, to_spl_token_acc)?;
+        Self::check_travel_rule(program_id, policy.config_acc, amount, None)?;
+
+        let signer_keys: Vec<&Pubkey> = signer_accs.iter().map(|acc| acc.key).collect();
+        let transfer_instr = spl_token::instruction::transfer_checked(
+            spl_token_acc.key,
+            from_spl_token_acc.key,
+            mint_acc.key,
+            to_spl_token_acc.key,
+            owner_acc.key,
+            &signer_keys,
+            amount,
+            decimals,
+        )?;
+        let mut transfer_accs = vec![
+            from_spl_token_acc.clone(),
+            mint_acc.clone(),
+            to_spl_token_acc.clone(),
+            owner_acc.clone(),
+            spl_token_acc.clone(),
+        ];
+        transfer_accs.extend_from_slice(signer_accs);
+        Self::hooked_transfer(hook_program_acc, owner_acc, from_spl_token_acc, to_spl_token_acc, amount, || {
+            invoke(&transfer_instr, &transfer_accs)
+        })?;
+        TokenEvent::TransferSpl(TransferSplEvent {
+            from: *from_spl_token_acc.key,
+            to: *to_spl_token_acc.key,
+            owner: *owner_acc.key,
+            mint: *mint_acc.key,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!(
+            "Transfer spl token checked from={:?}, to={:?}, amount={} done",
+            from_spl_token_acc.key, to_spl_token_acc.key, amount
+        );
+        Ok(())
+    }
+
+    /// Anyone may pay for the cache, its content is copied from the mint
+    fn cache_mint_decimals(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            payer_acc: signer writable,
+            mint_acc: mint,
+            mint_decimals_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Cache mint decimals mint={:?}", mint_acc.key);
+
+        let (mint_decimals_key, bump) = MintDecimals::find_address(mint_acc.key);
+        if mint_decimals_key != *mint_decimals_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !mint_decimals_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        let decimals = MintAccount::new(mint_acc)?.decimals;
+        Self::create_program_account(
+            program_id,
+            payer_acc,
+            mint_decimals_acc,
+            system_program_acc,
+            MintDecimals::LEN,
+            Rent::get()?.minimum_balance(MintDecimals::LEN),
+            &[MintDecimals::SEED, mint_acc.key.as_ref(), &[bump]],
+        )?;
+        MintDecimals {
+            account_type: AccountType::MintDecimals,
+            mint: *mint_acc.key,
+            token_program: *mint_acc.owner,
+            decimals,
+            bump,
+        }.save(mint_decimals_acc)?;
+
+        msg!("Cache mint decimals mint={:?}, decimals={} done", mint_acc.key, decimals);
+        Ok(())
+    }
+
+    fn initialize_registry(program_id: &Pubkey, accounts: &[AccountInfo], mode: RegistryMode) -> ProgramResult {
+        let (accounts, namespace_config_acc) = Self::split_namespace_config(program_id, accounts);
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            registry_acc: writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Initialize registry admin={:?}, mode={:?}", admin_acc.key, mode);
+
+        Self::check_namespace_admin(
+            program_id,
+            admin_acc,
+            config_acc,
+            namespace_config_acc,
+            system_program_acc,
+            accounts,
+        )?;
+
+        let mut seeds = namespaced_seeds(Registry::SEED, namespace_config_acc.map(|acc| acc.key), &[]);
+        let (registry_key, bump) = Pubkey::find_program_address(&seeds, program_id);
+        if registry_key != *registry_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !registry_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        let bump_seed = [bump];
+        seeds.push(&bump_seed);
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            registry_acc,
+            system_program_acc,
+            Registry::LEN,
+            Rent::get()?.minimum_balance(Registry::LEN),
+            &seeds,
+        )?;
+        Registry {
+            account_type: AccountType::Registry,
+            admin: *admin_acc.key,
+            mode,
+            bump,
+            entries: vec![],
+        }.save(registry_acc)?;
+
+        msg!("Initialize registry={:?} done", registry_acc.key);
+        Ok(())
+    }
+
+    /// Add `recipient` to the registry when `add`, otherwise remove it
+    fn update_registry(program_id: &Pubkey, accounts: &[AccountInfo], recipient: Pubkey, add: bool) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer,
+            registry_acc: writable,
+        });
+        msg!("Update registry recipient={:?}, add={}", recipient, add);
+
+        let mut registry = ProgramAccount::<Registry>::new(registry_acc, program_id)?;
+        if registry.admin != *admin_acc.key {
+            return Err(TransferError::NotRegistryAdmin.into())
+        }
+        if add {
+            if !registry.add(recipient) {
+                return Err(TransferError::RegistryFull.into())
+            }
+        } else {
+            registry.remove(&recipient);
+        }
+        registry.save()?;
+
+        msg!("Update registry entries={} done", registry.entries.len());
+        Ok(())
+    }
+
+    fn set_spending_limit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        mint: &Pubkey,
+        limit: u64,
+        window_seconds: i64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            authority_acc,
+            spending_limit_acc: writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!(
+            "Set spending limit authority={:?}, mint={:?}, limit={}, window_seconds={}",
+            authority_acc.key, mint, limit, window_seconds
+        );
+
+        let config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        config.save(config_acc)?;
+
+        if window_seconds <= 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (spending_limit_key, bump) = SpendingLimit::find_address(authority_acc.key, mint);
+        if spending_limit_key != *spending_limit_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !spending_limit_acc.data_is_empty() {
+            let mut spending_limit = ProgramAccount::<SpendingLimit>::new(spending_limit_acc, program_id)?;
+            let now = Clock::get()?.unix_timestamp;
+            spending_limit.limit = limit;
+            spending_limit.set_window(window_seconds, now);
+            spending_limit.save()?;
+            msg!("Set spending limit={:?} spent={} done", spending_limit_acc.key, spending_limit.spent(now));
+            return Ok(())
+        }
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            spending_limit_acc,
+            system_program_acc,
+            SpendingLimit::LEN,
+            Rent::get()?.minimum_balance(SpendingLimit::LEN),
+            &[SpendingLimit::SEED, authority_acc.key.as_ref(), mint.as_ref(), &[bump]],
+        )?;
+        SpendingLimit {
+            account_type: AccountType::SpendingLimit,
+            authority: *authority_acc.key,
+            mint: *mint,
+            limit,
+            window_seconds,
+            spent: [0; SpendingLimit::BUCKETS],
+            last_bucket: 0,
+            bump,
+        }.save(spending_limit_acc)?;
+
+        msg!("Set spending limit={:?} done", spending_limit_acc.key);
+        Ok(())
+    }
+
+    fn initialize_namespace(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            namespace_acc: signer,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Initialize namespace={:?}, admin={:?}", namespace_acc.key, admin_acc.key);
+
+        let (config_key, bump) = Config::find_namespaced_address(namespace_acc.key);
+        if config_key != *config_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !config_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            config_acc,
+            system_program_acc,
+            Config::LEN,
+            Rent::get()?.minimum_balance(Config::LEN),
+            &[Config::SEED, namespace_acc.key.as_ref(), &[bump]],
+        )?;
+        Config {
+            account_type: AccountType::Config,
+            admin: *admin_acc.key,
+            strict_ata: false,
+            features: Config::DEFAULT_FEATURES,
+            travel_rule_required: false,
+            travel_rule_threshold: 0,
+            paused: false,
+            deposit_programs: [Pubkey::default(); 4],
+            bump,
+        }.save(config_acc)?;
+
+        msg!("Initialize namespace config={:?} done", config_acc.key);
+        Ok(())
+    }
+
+    fn set_approval_policy(program_id: &Pubkey, accounts: &[AccountInfo], threshold: u64) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            authority_acc,
+            approver_acc,
+            approval_policy_acc: writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!(
+            "Set approval policy authority={:?}, approver={:?}, threshold={}",
+            authority_acc.key, approver_acc.key, threshold
+        );
+
+        let config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        config.save(config_acc)?;
+
+        let (approval_policy_key, bump) = ApprovalPolicy::find_address(authority_acc.key);
+        if approval_policy_key != *approval_policy_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if approval_policy_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                admin_acc,
+                approval_policy_acc,
+                system_program_acc,
+                ApprovalPolicy::LEN,
+                Rent::get()?.minimum_balance(ApprovalPolicy::LEN),
+                &[ApprovalPolicy::SEED, authority_acc.key.as_ref(), &[bump]],
+            )?;
+        } else {
+            ApprovalPolicy::load(approval_policy_acc, program_id)?;
+        }
+        ApprovalPolicy {
+            account_type: AccountType::ApprovalPolicy,
+            authority: *authority_acc.key,
+            approver: *approver_acc.key,
+            threshold,
+            bump,
+        }.save(approval_policy_acc)?;
+
+        msg!("Set approval policy={:?} done", approval_policy_acc.key);
+        Ok(())
+    }
+
+    /// Proposed amount is moved into the pending transfer along with its rent
+    fn propose_transfer(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            proposer_acc: signer writable,
+            to_acc,
+            pending_transfer_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Propose transfer from={:?}, to={:?}, amount={}", proposer_acc.key, to_acc.key, amount);
+
+        if amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        if ApprovalPolicy::find_address(proposer_acc.key).0 != *policy.approval_policy_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let approval_policy = ApprovalPolicy::load(policy.approval_policy_acc, program_id)?;
+        if !approval_policy.requires_approval(amount) {
+            msg!("Transfer up to threshold={} is made directly", approval_policy.threshold);
+            return Err(TransferError::ApprovalNotRequired.into())
+        }
+        let (pending_transfer_key, bump) = PendingTransfer::find_address(proposer_acc.key, to_acc.key);
+        if pending_transfer_key != *pending_transfer_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !pending_transfer_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+
+        let rent = Rent::get()?.minimum_balance(PendingTransfer::LEN);
+        Self::create_program_account(
+            program_id,
+            proposer_acc,
+            pending_transfer_acc,
+            system_program_acc,
+            PendingTransfer::LEN,
+            rent.checked_add(amount).ok_or(TransferError::AmountOverflow)?,
+            &[PendingTransfer::SEED, proposer_acc.key.as_ref(), to_acc.key.as_ref(), &[bump]],
+        )?;
+        PendingTransfer {
+            account_type: AccountType::PendingTransfer,
+            proposer: *proposer_acc.key,
+            approver: approval_policy.approver,
+            to: *to_acc.key,
+            amount,
+            bump,
+        }.save(pending_transfer_acc)?;
+
+        msg!("Propose transfer={:?}, approver={:?} done", pending_transfer_acc.key, approval_policy.approver);
+        Ok(())
+    }
+
+    /// Approval is the signature of the approver, the other policies of the proposer apply on execution
+    fn execute_transfer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            approver_acc: signer,
+            proposer_acc: writable,
+            to_acc: writable,
+            pending_transfer_acc: writable,
+        });
+        msg!("Execute transfer={:?}, approver={:?}", pending_transfer_acc.key, approver_acc.key);
+
+        let pending_transfer =
+            Self::load_pending_transfer(program_id, approver_acc, proposer_acc, to_acc, pending_transfer_acc)?;
+        let amount = pending_transfer.amount;
+        Self::check_registry(program_id, &policy, to_acc.key)?;
+        Self::spend_limit(program_id, policy.spending_limit_acc, proposer_acc.key, &Pubkey::default(), amount)?;
+        let hook_program_acc = Self::load_transfer_hook(program_id, &policy, proposer_acc.key)?;
+        Self::hooked_transfer(hook_program_acc, proposer_acc, pending_transfer_acc, to_acc, amount, || {
+            Self::move_lamports(program_id, pending_transfer_acc, to_acc, amount)
+        })?;
+        Self::close_program_account(program_id, pending_transfer_acc, proposer_acc)?;
+        TokenEvent::TransferLamports(TransferLamportsEvent {
+            from: pending_transfer.proposer,
+            to: pending_transfer.to,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!("Execute transfer to={:?}, amount={} done", to_acc.key, pending_transfer.amount);
+        Ok(())
+    }
+
+    fn cancel_transfer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            approver_acc: signer,
+            proposer_acc: writable,
+            to_acc,
+            pending_transfer_acc: writable,
+        });
+        msg!("Cancel transfer={:?}, approver={:?}", pending_transfer_acc.key, approver_acc.key);
+
+        let pending_transfer =
+            Self::load_pending_transfer(program_id, approver_acc, proposer_acc, to_acc, pending_transfer_acc)?;
+        Self::close_program_account(program_id, pending_transfer_acc, proposer_acc)?;
+
+        msg!("Cancel transfer amount={} done", pending_transfer.amount);
+        Ok(())
+    }
+
+    /// Pending transfer from `proposer_acc` to `to_acc`, approved by `approver_acc`
+    fn load_pending_transfer(
+        program_id: &Pubkey,
+        approver_acc: &AccountInfo,
+        proposer_acc: &AccountInfo,
+        to_acc: &AccountInfo,
+        pending_transfer_acc: &AccountInfo,
+    ) -> Result<PendingTransfer, ProgramError> {
+        if PendingTransfer::find_address(proposer_acc.key, to_acc.key).0 != *pending_transfer_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let pending_transfer = PendingTransfer::load(pending_transfer_acc, program_id)?;
+        if pending_transfer.approver != *approver_acc.key {
+            return Err(TransferError::NotApprover.into())
+        }
+        Ok(pending_transfer)
+    }
+
+    /// Export side of migration to `peer` when `export`, otherwise import side of migration from `peer`
+    fn begin_migration(program_id: &Pubkey, accounts: &[AccountInfo], peer: Pubkey, export: bool) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            migration_acc: writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Begin migration peer={:?}, export={}", peer, export);
+
+        let config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        config.save(config_acc)?;
+
+        let (seed, (migration_key, bump)) = if export {
+            (Migration::EXPORT_SEED, Migration::find_export_address())
+        } else {
+            (Migration::IMPORT_SEED, Migration::find_import_address())
+        };
+        if migration_key != *migration_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !migration_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        if peer == *program_id {
+            return Err(ProgramError::InvalidArgument)
+        }
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            migration_acc,
+            system_program_acc,
+            Migration::LEN,
+            Rent::get()?.minimum_balance(Migration::LEN),
+            &[seed, &[bump]],
+        )?;
+        Migration {
+            account_type: AccountType::Migration,
+            admin: *admin_acc.key,
+            peer,
+            commitment: [0; 32],
+            count: 0,
+            bump,
+        }.save(migration_acc)?;
+
+        msg!("Begin migration={:?} done", migration_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "vaults")]
+    fn export_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer,
+            migration_acc: writable,
+            vault_acc: writable,
+            vault_token_acc: writable,
+            successor_vault_token_acc: writable,
+            spl_token_acc: token_program,
+        });
+        msg!("Export vault={:?}", vault_acc.key);
+
+        let mut migration = Self::load_migration(program_id, admin_acc, migration_acc, true)?;
+        let mut vault = Vault::load(vault_acc, program_id)?;
+        if get_associated_token_address(vault_acc.key, &vault.mint) != *vault_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let (successor_vault, _) = Vault::find_address_in(&migration.peer, &vault.owner, &vault.mint);
+        if get_associated_token_address(&successor_vault, &vault.mint) != *successor_vault_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(successor_vault_token_acc)?.mint != vault.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+
+        let amount = TokenAccount::new(vault_token_acc)?.amount;
+        if amount > 0 {
+            let transfer_instr = spl_token::instruction::transfer(
+                spl_token_acc.key,
+                vault_token_acc.key,
+                successor_vault_token_acc.key,
+                vault_acc.key,
+                &[],
+                amount,
+            )?;
+            let transfer_accs = [
+                vault_token_acc.clone(),
+                successor_vault_token_acc.clone(),
+                vault_acc.clone(),
+                spl_token_acc.clone(),
+            ];
+            invoke_signed(
+                &transfer_instr,
+                &transfer_accs,
+                &[&[Vault::SEED, vault.owner.as_ref(), vault.mint.as_ref(), &[vault.bump]]],
+            )?;
+        }
+        migration.fold(vault.migration_record());
+        migration.save(migration_acc)?;
+        vault.account_type = AccountType::Exported;
+        vault.save(vault_acc)?;
+
+        msg!("Export vault={:?}, amount={}, count={} done", vault_acc.key, amount, migration.count);
+        Ok(())
+    }
+
+    #[cfg(feature = "vaults")]
+    fn import_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            migration_acc: writable,
+            exported_vault_acc,
+            vault_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Import vault={:?}, exported={:?}", vault_acc.key, exported_vault_acc.key);
+
+        let mut migration = Self::load_migration(program_id, admin_acc, migration_acc, false)?;
+        let exported: Vault = Self::load_exported(&migration, exported_vault_acc)?;
+        let (vault_key, bump) = Vault::find_address(&exported.owner, &exported.mint);
+        if vault_key != *vault_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !vault_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            vault_acc,
+            system_program_acc,
+            Vault::LEN,
+            Rent::get()?.minimum_balance(Vault::LEN),
+            &[Vault::SEED, exported.owner.as_ref(), exported.mint.as_ref(), &[bump]],
+        )?;
+        let vault = Vault { account_type: AccountType::Vault, bump, ..exported };
+        vault.save(vault_acc)?;
+        migration.fold(vault.migration_record());
+        migration.save(migration_acc)?;
+
+        msg!("Import vault={:?}, count={} done", vault_acc.key, migration.count);
+        Ok(())
+    }
+
+    #[cfg(feature = "escrow")]
+    fn export_escrow(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer,
+            migration_acc: writable,
+            temp_token_acc: writable,
+            escrow_acc: writable,
+            spl_token_acc: token_program,
+        });
+        msg!("Export escrow={:?}", escrow_acc.key);
+
+        let mut migration = Self::load_migration(program_id, admin_acc, migration_acc, true)?;
+        let mut escrow = Escrow::load(escrow_acc, program_id)?;
+        if escrow.temp_token != *temp_token_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let (successor_escrow, _) = Escrow::find_address_in(&migration.peer, &escrow.temp_token);
+
+        let set_authority_instr = spl_token::instruction::set_authority(
+            spl_token_acc.key,
+            temp_token_acc.key,
+            Some(&successor_escrow),
+            AuthorityType::AccountOwner,
+            escrow_acc.key,
+            &[],
+        )?;
+        invoke_signed(
+            &set_authority_instr,
+            &[temp_token_acc.clone(), escrow_acc.clone(), spl_token_acc.clone()],
+            &[&[Escrow::SEED, escrow.temp_token.as_ref(), &[escrow.bump]]],
+        )?;
+        migration.fold(escrow.migration_record());
+        migration.save(migration_acc)?;
+        escrow.account_type = AccountType::Exported;
+        escrow.save(escrow_acc)?;
+
+        msg!("Export escrow={:?}, count={} done", escrow_acc.key, migration.count);
+        Ok(())
+    }
+
+    #[cfg(feature = "escrow")]
+    fn import_escrow(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            migration_acc: writable,
+            temp_token_acc,
+            exported_escrow_acc,
+            escrow_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Import escrow={:?}, exported={:?}", escrow_acc.key, exported_escrow_acc.key);
+
+        let mut migration = Self::load_migration(program_id, admin_acc, migration_acc, false)?;
+        let exported: Escrow = Self::load_exported(&migration, exported_escrow_acc)?;
+        if exported.temp_token != *temp_token_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let (escrow_key, bump) = Escrow::find_address(&exported.temp_token);
+        if escrow_key != *escrow_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !escrow_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        if TokenAccount::new(temp_token_acc)?.owner != escrow_key {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            escrow_acc,
+            system_program_acc,
+            Escrow::LEN,
+            Rent::get()?.minimum_balance(Escrow::LEN),
+            &[Escrow::SEED, exported.temp_token.as_ref(), &[bump]],
+        )?;
+        let escrow = Escrow { account_type: AccountType::Escrow, bump, ..exported };
+        escrow.save(escrow_acc)?;
+        migration.fold(escrow.migration_record());
+        migration.save(migration_acc)?;
+
+        msg!("Import escrow={:?}, count={} done", escrow_acc.key, migration.count);
+        Ok(())
+    }
+
+    fn transfer_spl_token_as_delegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            delegate_acc,
+            from_spl_token_acc: writable,
+            to_spl_token_acc: writable,
+            spl_token_acc: token_program,
+            mint_config_acc,
+            global_revocation_acc,
+            owner_revocation_acc,
+        });
+        msg!(
+            "Transfer spl token as delegate from={:?}, to={:?}, amount={}",
+            from_spl_token_acc.key, to_spl_token_acc.key, amount
+        );
+
+        let (_, signer_accs) = Self::split_multisig_signers(delegate_acc, acc_iter.as_slice())?;
+        let from_spl_token = TokenAccount::new(from_spl_token_acc)?;
+        if from_spl_token.delegate != COption::Some(*delegate_acc.key) {
+            return Err(TransferError::NotDelegate.into())
+        }
+        if TokenAccount::new(to_spl_token_acc)?.mint != from_spl_token.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+        Self::check_not_revoked(program_id, global_revocation_acc, &Pubkey::default(), delegate_acc.key)?;
+        Self::check_not_revoked(program_id, owner_revocation_acc, &from_spl_token.owner, delegate_acc.key)?;
+        Self::check_transferable(program_id, mint_config_acc, &from_spl_token.mint)?;
+        let hook_program_acc = Self::check_transfer_policy(
+            program_id, &policy, delegate_acc.key, &from_spl_token.mint, amount,
+        )?;
+        Self::check_registry(program_id, &policy, &TokenAccount::new(to_spl_token_acc)?.owner)?;
+        Self::check_strict_ata(program_id, policy.config_acc, to_spl_token_acc)?;
+        Self::check_travel_rule(program_id, policy.config_acc, amount, None)?;
+
+        Self::hooked_transfer(hook_program_acc, delegate_acc, from_spl_token_acc, to_spl_token_acc, amount, || {
+            Self::invoke_token_transfer(
+                spl_token_acc,
+                from_spl_token_acc,
+                to_spl_token_acc,
+                delegate_acc,
+                signer_accs,
+                amount,
+            )
+        })?;
+        TokenEvent::TransferSpl(TransferSplEvent {
+            from: *from_spl_token_acc.key,
+            to: *to_spl_token_acc.key,
+            owner: from_spl_token.owner,
+            mint: from_spl_token.mint,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!(
+            "Transfer spl token as delegate from={:?}, to={:?}, amount={} done",
+            from_spl_token_acc.key, to_spl_token_acc.key, amount
+        );
+        Ok(())
+    }
+
+    fn revoke_delegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        global: bool,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer writable,
+            delegate_acc,
+            revocation_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Revoke delegate={:?}, authority={:?}, global={}", delegate_acc.key, authority_acc.key, global);
+
+        if global {
+            Self::check_config_admin(program_id, authority_acc, config_acc)?;
+        }
+        let scope = Revocation::scope(authority_acc.key, global);
+        let (revocation_key, bump) = Revocation::find_address(&scope, delegate_acc.key);
+        if revocation_key != *revocation_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !revocation_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+
+        Self::create_program_account(
+            program_id,
+            authority_acc,
+            revocation_acc,
+            system_program_acc,
+            Revocation::LEN,
+            Rent::get()?.minimum_balance(Revocation::LEN),
+            &[Revocation::SEED, scope.as_ref(), delegate_acc.key.as_ref(), &[bump]],
+        )?;
+        Revocation {
+            account_type: AccountType::Revocation,
+            scope,
+            delegate: *delegate_acc.key,
+            revoked_by: *authority_acc.key,
+            revoked_at: Clock::get()?.unix_timestamp,
+            bump,
+        }.save(revocation_acc)?;
+
+        TokenEvent::DelegateRevoked(DelegateRevokedEvent {
+            revocation: *revocation_acc.key,
+            scope,
+            delegate: *delegate_acc.key,
+            authority: *authority_acc.key,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Revoke delegate={:?}, global={} done", delegate_acc.key, global);
+        Ok(())
+    }
+
+    fn reinstate_delegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        global: bool,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer writable,
+            delegate_acc,
+            revocation_acc: writable,
+            config_acc,
+        });
+        msg!("Reinstate delegate={:?}, authority={:?}, global={}", delegate_acc.key, authority_acc.key, global);
+
+        if global {
+            Self::check_config_admin(program_id, authority_acc, config_acc)?;
+        }
+        let revocation = Revocation::load(revocation_acc, program_id)?;
+        let scope = Revocation::scope(authority_acc.key, global);
+        if revocation.scope != scope || revocation.delegate != *delegate_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        Self::close_program_account(program_id, revocation_acc, authority_acc)?;
+
+        TokenEvent::DelegateReinstated(DelegateReinstatedEvent {
+            revocation: *revocation_acc.key,
+            scope,
+            delegate: *delegate_acc.key,
+            authority: *authority_acc.key,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Reinstate delegate={:?}, global={} done", delegate_acc.key, global);
+        Ok(())
+    }
+
+    #[cfg(feature = "bonds")]
+    fn post_bond(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        id: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            depositor_acc: signer writable,
+            arbiter_acc,
+            beneficiary_acc,
+            bond_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Post bond depositor={:?}, id={}, amount={}", depositor_acc.key, id, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_BONDS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+
+        let (bond_key, bump) = Bond::find_address(depositor_acc.key, id);
+        if bond_key != *bond_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let lamports = Rent::get()?
+            .minimum_balance(Bond::LEN)
+            .checked_add(amount)
+            .ok_or(TransferError::AmountOverflow)?;
+        Self::create_program_account(
+            program_id,
+            depositor_acc,
+            bond_acc,
+            system_program_acc,
+            Bond::LEN,
+            lamports,
+            &[Bond::SEED, depositor_acc.key.as_ref(), &id.to_le_bytes(), &[bump]],
+        )?;
+        let bond = Bond {
+            account_type: AccountType::Bond,
+            depositor: *depositor_acc.key,
+            arbiter: *arbiter_acc.key,
+            beneficiary: *beneficiary_acc.key,
+            id,
+            amount,
+            bump,
+        };
+        bond.save(bond_acc)?;
+
+        TokenEvent::BondPosted(BondPostedEvent {
+            bond: *bond_acc.key,
+            depositor: bond.depositor,
+            arbiter: bond.arbiter,
+            beneficiary: bond.beneficiary,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Post bond depositor={:?}, id={}, amount={} done", depositor_acc.key, id, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "bonds")]
+    fn refund_bond(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        reason: u16,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            arbiter_acc: signer,
+            depositor_acc: writable,
+            bond_acc: writable,
+            config_acc,
+        });
+        msg!("Refund bond={:?}, reason={}", bond_acc.key, reason);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let bond = ProgramAccount::<Bond>::new(bond_acc, program_id)?;
+        if bond.arbiter != *arbiter_acc.key || bond.depositor != *depositor_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+
+        Self::close_program_account(program_id, bond_acc, depositor_acc)?;
+
+        TokenEvent::BondRefunded(BondRefundedEvent {
+            bond: *bond_acc.key,
+            depositor: bond.depositor,
+            amount: bond.amount,
+            reason,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Refund bond={:?}, reason={} done", bond_acc.key, reason);
+        Ok(())
+    }
+
+    #[cfg(feature = "bonds")]
+    fn slash_bond(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+        reason: u16,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            arbiter_acc: signer,
+            beneficiary_acc: writable,
+            bond_acc: writable,
+            config_acc,
+        });
+        msg!("Slash bond={:?}, amount={}, reason={}", bond_acc.key, amount, reason);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut bond = ProgramAccount::<Bond>::new(bond_acc, program_id)?;
+        if bond.arbiter != *arbiter_acc.key || bond.beneficiary != *beneficiary_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        bond.amount = bond.amount
+            .checked_sub(amount)
+            .ok_or(TransferError::InsufficientBond)?;
+
+        Self::move_lamports(program_id, bond_acc, beneficiary_acc, amount)?;
+        bond.save()?;
+
+        TokenEvent::BondSlashed(BondSlashedEvent {
+            bond: *bond_acc.key,
+            beneficiary: bond.beneficiary,
+            amount,
+            remaining: bond.amount,
+            reason,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Slash bond={:?}, amount={}, reason={} done", bond_acc.key, amount, reason);
+        Ok(())
+    }
+
+    #[cfg(feature = "tips")]
+    fn tip(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            tipper_acc: signer writable,
+            creator_acc: writable,
+            tip_stats_acc: writable,
+            tipper_stats_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Tip from={:?}, creator={:?}, amount={}", tipper_acc.key, creator_acc.key, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_TIPS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+
+        let (tip_stats_key, tip_stats_bump) = TipStats::find_address(creator_acc.key);
+        let (tipper_stats_key, tipper_stats_bump) = TipperStats::find_address(creator_acc.key, tipper_acc.key);
+        if tip_stats_key != *tip_stats_acc.key || tipper_stats_key != *tipper_stats_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let rent = Rent::get()?;
+        let mut tip_stats = if tip_stats_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                tipper_acc,
+                tip_stats_acc,
+                system_program_acc,
+                TipStats::LEN,
+                rent.minimum_balance(TipStats::LEN),
+                &[TipStats::SEED, creator_acc.key.as_ref(), &[tip_stats_bump]],
+            )?;
+            TipStats {
+                account_type: AccountType::TipStats,
+                creator: *creator_acc.key,
+                total: 0,
+                count: 0,
+                top_tipper: Pubkey::default(),
+                top_tipper_total: 0,
+                bump: tip_stats_bump,
+            }
+        } else {
+            TipStats::load(tip_stats_acc, program_id)?
+        };
+        let mut tipper_stats = if tipper_stats_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                tipper_acc,
+                tipper_stats_acc,
+                system_program_acc,
+                TipperStats::LEN,
+                rent.minimum_balance(TipperStats::LEN),
+                &[TipperStats::SEED, creator_acc.key.as_ref(), tipper_acc.key.as_ref(), &[tipper_stats_bump]],
+            )?;
+            TipperStats {
+                account_type: AccountType::TipperStats,
+                creator: *creator_acc.key,
+                tipper: *tipper_acc.key,
+                total: 0,
+                count: 0,
+                bump: tipper_stats_bump,
+            }
+        } else {
+            TipperStats::load(tipper_stats_acc, program_id)?
+        };
+
+        let transfer_instr = system_instruction::transfer(tipper_acc.key, creator_acc.key, amount);
+        invoke(
+            &transfer_instr,
+            &[tipper_acc.clone(), creator_acc.clone(), system_program_acc.clone()],
+        )?;
+
+        tip_stats.total = tip_stats.total.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        tip_stats.count = tip_stats.count.checked_add(1).ok_or(TransferError::AmountOverflow)?;
+        tipper_stats.total = tipper_stats.total.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        tipper_stats.count = tipper_stats.count.checked_add(1).ok_or(TransferError::AmountOverflow)?;
+        if tipper_stats.total > tip_stats.top_tipper_total {
+            tip_stats.top_tipper = tipper_stats.tipper;
+            tip_stats.top_tipper_total = tipper_stats.total;
+        }
+        tip_stats.save(tip_stats_acc)?;
+        tipper_stats.save(tipper_stats_acc)?;
+
+        TokenEvent::Tip(TipEvent {
+            creator: tip_stats.creator,
+            tipper: tipper_stats.tipper,
+            amount,
+            tipper_total: tipper_stats.total,
+            creator_total: tip_stats.total,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Tip from={:?}, creator={:?}, amount={} done", tipper_acc.key, creator_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "tips")]
+    fn create_matching_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        ratio_bps: u16,
+        cap: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            sponsor_acc: signer writable,
+            recipient_acc,
+            pool_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!(
+            "Create matching pool sponsor={:?}, recipient={:?}, ratio_bps={}, cap={}, amount={}",
+            sponsor_acc.key, recipient_acc.key, ratio_bps, cap, amount
+        );
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_MATCHING_POOLS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+
+        let (pool_key, bump) = MatchingPool::find_address(sponsor_acc.key, recipient_acc.key);
+        if pool_key != *pool_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let lamports = Rent::get()?
+            .minimum_balance(MatchingPool::LEN)
+            .checked_add(amount)
+            .ok_or(TransferError::AmountOverflow)?;
+        Self::create_program_account(
+            program_id,
+            sponsor_acc,
+            pool_acc,
+            system_program_acc,
+            MatchingPool::LEN,
+            lamports,
+            &[MatchingPool::SEED, sponsor_acc.key.as_ref(), recipient_acc.key.as_ref(), &[bump]],
+        )?;
+        let pool = MatchingPool {
+            account_type: AccountType::MatchingPool,
+            sponsor: *sponsor_acc.key,
+            recipient: *recipient_acc.key,
+            ratio_bps,
+            cap,
+            remaining: amount,
+            matched_total: 0,
+            bump,
+        };
+        pool.save(pool_acc)?;
+
+        TokenEvent::MatchingPoolCreated(MatchingPoolCreatedEvent {
+            pool: *pool_acc.key,
+            sponsor: pool.sponsor,
+            recipient: pool.recipient,
+            ratio_bps,
+            cap,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create matching pool={:?} done", pool_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "tips")]
+    fn donate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            donor_acc: signer writable,
+            recipient_acc: writable,
+            pool_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Donate from={:?}, to={:?}, amount={}", donor_acc.key, recipient_acc.key, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_MATCHING_POOLS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        if donor_acc.key == recipient_acc.key {
+            return Err(TransferError::SelfDonation.into())
+        }
+
+        let mut pool = ProgramAccount::<MatchingPool>::new(pool_acc, program_id)?;
+        if pool.recipient != *recipient_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+
+        let transfer_instr = system_instruction::transfer(donor_acc.key, recipient_acc.key, amount);
+        invoke(
+            &transfer_instr,
+            &[donor_acc.clone(), recipient_acc.clone(), system_program_acc.clone()],
+        )?;
+
+        let matched = pool.match_amount(amount);
+        pool.remaining -= matched;
+        pool.matched_total = pool.matched_total.checked_add(matched).ok_or(TransferError::AmountOverflow)?;
+        Self::move_lamports(program_id, pool_acc, recipient_acc, matched)?;
+        pool.save()?;
+
+        TokenEvent::DonationMatched(DonationMatchedEvent {
+            pool: *pool_acc.key,
+            donor: *donor_acc.key,
+            recipient: pool.recipient,
+            amount,
+            matched,
+            remaining: pool.remaining,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Donate from={:?}, to={:?}, amount={} done", donor_acc.key, recipient_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "tips")]
+    fn close_matching_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            sponsor_acc: signer writable,
+            pool_acc: writable,
+            config_acc,
+        });
+        msg!("Close matching pool={:?}", pool_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let pool = ProgramAccount::<MatchingPool>::new(pool_acc, program_id)?;
+        if pool.sponsor != *sponsor_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+
+        Self::close_program_account(program_id, pool_acc, sponsor_acc)?;
+
+        TokenEvent::MatchingPoolClosed(MatchingPoolClosedEvent {
+            pool: *pool_acc.key,
+            sponsor: pool.sponsor,
+            returned: pool.remaining,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Close matching pool={:?} done", pool_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "round-up")]
+    fn configure_round_up(program_id: &Pubkey, accounts: &[AccountInfo], granularity: u64) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            savings_vault_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Configure round up owner={:?}, granularity={}", owner_acc.key, granularity);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_ROUND_UP)?;
+
+        let (savings_vault_key, bump) = SavingsVault::find_address(owner_acc.key);
+        if savings_vault_key != *savings_vault_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let mut savings_vault = if savings_vault_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                owner_acc,
+                savings_vault_acc,
+                system_program_acc,
+                SavingsVault::LEN,
+                Rent::get()?.minimum_balance(SavingsVault::LEN),
+                &[SavingsVault::SEED, owner_acc.key.as_ref(), &[bump]],
+            )?;
+            SavingsVault {
+                account_type: AccountType::SavingsVault,
+                owner: *owner_acc.key,
+                granularity,
+                saved: 0,
+                bump,
+            }
+        } else {
+            SavingsVault::load(savings_vault_acc, program_id)?
+        };
+        savings_vault.granularity = granularity;
+        savings_vault.save(savings_vault_acc)?;
+
+        msg!("Configure round up owner={:?}, granularity={} done", owner_acc.key, granularity);
+        Ok(())
+    }
+
+    #[cfg(feature = "round-up")]
+    fn transfer_lamports_round_up(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            from_acc: signer writable,
+            to_acc: writable,
+            savings_vault_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Transfer lamports round up from={:?}, to={:?}, amount={}", from_acc.key, to_acc.key, amount);
+
+        Self::check_feature(program_id, policy.config_acc, Config::FEATURE_ROUND_UP)?;
+        let hook_program_acc = Self::check_transfer_policy(
+            program_id, &policy, from_acc.key, &Pubkey::default(), amount,
+        )?;
+        Self::check_registry(program_id, &policy, to_acc.key)?;
+
+        let mut savings_vault = ProgramAccount::<SavingsVault>::new(savings_vault_acc, program_id)?;
+        if savings_vault.owner != *from_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let saved = savings_vault.round_up_difference(amount).ok_or(TransferError::AmountOverflow)?;
+
+        let transfer_instr = system_instruction::transfer(from_acc.key, to_acc.key, amount);
+        Self::hooked_transfer(hook_program_acc, from_acc, from_acc, to_acc, amount, || {
+            invoke(&transfer_instr, &[from_acc.clone(), to_acc.clone(), system_program_acc.clone()])
+        })?;
+        if saved > 0 {
+            let save_instr = system_instruction::transfer(from_acc.key, savings_vault_acc.key, saved);
+            invoke(
+                &save_instr,
+                &[from_acc.clone(), savings_vault_acc.clone(), system_program_acc.clone()],
+            )?;
+        }
+        savings_vault.saved = savings_vault.saved.checked_add(saved).ok_or(TransferError::AmountOverflow)?;
+        savings_vault.save()?;
+
+        TokenEvent::RoundUpSaved(RoundUpSavedEvent {
+            owner: savings_vault.owner,
+            to: *to_acc.key,
+            amount,
+            saved,
+            saved_total: savings_vault.saved,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!(
+            "Transfer lamports round up from={:?}, to={:?}, amount={} done",
+            from_acc.key, to_acc.key, amount
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "round-up")]
+    fn withdraw_savings(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            savings_vault_acc: writable,
+            config_acc,
+        });
+        msg!("Withdraw savings owner={:?}, amount={}", owner_acc.key, amount);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut savings_vault = ProgramAccount::<SavingsVault>::new(savings_vault_acc, program_id)?;
+        if savings_vault.owner != *owner_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        savings_vault.saved = savings_vault.saved
+            .checked_sub(amount)
+            .ok_or(TransferError::InsufficientFunds)?;
+
+        Self::move_lamports(program_id, savings_vault_acc, owner_acc, amount)?;
+        savings_vault.save()?;
+
+        TokenEvent::SavingsWithdrawn(SavingsWithdrawnEvent {
+            owner: savings_vault.owner,
+            amount,
+            saved_total: savings_vault.saved,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Withdraw savings owner={:?}, amount={} done", owner_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "netting")]
+    fn open_netting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        id: u64,
+        period_end: i64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            party_a_acc: signer writable,
+            party_b_acc: signer,
+            netting_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Open netting a={:?}, b={:?}, id={}", party_a_acc.key, party_b_acc.key, id);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_NETTING)?;
+
+        let (netting_key, bump) = Netting::find_address(party_a_acc.key, party_b_acc.key, id);
+        if netting_key != *netting_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        Self::create_program_account(
+            program_id,
+            party_a_acc,
+            netting_acc,
+            system_program_acc,
+            Netting::LEN,
+            Rent::get()?.minimum_balance(Netting::LEN),
+            &[Netting::SEED, party_a_acc.key.as_ref(), party_b_acc.key.as_ref(), &id.to_le_bytes(), &[bump]],
+        )?;
+        let netting = Netting {
+            account_type: AccountType::Netting,
+            party_a: *party_a_acc.key,
+            party_b: *party_b_acc.key,
+            id,
+            period_end,
+            a_owes_b: 0,
+            b_owes_a: 0,
+            obligation_count: 0,
+            bump,
+        };
+        netting.save(netting_acc)?;
+
+        TokenEvent::NettingOpened(NettingOpenedEvent {
+            netting: *netting_acc.key,
+            party_a: netting.party_a,
+            party_b: netting.party_b,
+            period_end,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Open netting={:?} done", netting_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "netting")]
+    fn register_obligation(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            debtor_acc: signer,
+            creditor_acc,
+            netting_acc: writable,
+            config_acc,
+        });
+        msg!("Register obligation debtor={:?}, creditor={:?}, amount={}", debtor_acc.key, creditor_acc.key, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_NETTING)?;
+
+        let mut netting = ProgramAccount::<Netting>::new(netting_acc, program_id)?;
+        if netting.counterparty(debtor_acc.key) != Some(*creditor_acc.key) {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if Clock::get()?.unix_timestamp >= netting.period_end {
+            return Err(TransferError::NettingPeriodEnded.into())
+        }
+
+        if *debtor_acc.key == netting.party_a {
+            netting.a_owes_b = netting.a_owes_b.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        } else {
+            netting.b_owes_a = netting.b_owes_a.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        }
+        netting.obligation_count = netting.obligation_count
+            .checked_add(1)
+            .ok_or(TransferError::AmountOverflow)?;
+        netting.save()?;
+
+        TokenEvent::ObligationRegistered(ObligationRegisteredEvent {
+            netting: *netting_acc.key,
+            debtor: *debtor_acc.key,
+            creditor: *creditor_acc.key,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Register obligation netting={:?}, amount={} done", netting_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "netting")]
+    fn settle_netting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            party_acc: signer writable,
+            counterparty_acc: writable,
+            netting_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Settle netting={:?}", netting_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let netting = ProgramAccount::<Netting>::new(netting_acc, program_id)?;
+        if netting.counterparty(party_acc.key) != Some(*counterparty_acc.key) {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if Clock::get()?.unix_timestamp < netting.period_end {
+            return Err(TransferError::NettingPeriodNotEnded.into())
+        }
+        let (debtor, creditor, net_amount) = netting.net();
+        if net_amount > 0 && debtor != *party_acc.key {
+            return Err(TransferError::SettlementSignerNotDebtor.into())
+        }
+
+        if net_amount > 0 {
+            let transfer_instr = system_instruction::transfer(party_acc.key, counterparty_acc.key, net_amount);
+            invoke(
+                &transfer_instr,
+                &[party_acc.clone(), counterparty_acc.clone(), system_program_acc.clone()],
+            )?;
+        }
+        let rent_receiver_acc = if *party_acc.key == netting.party_a { party_acc } else { counterparty_acc };
+        Self::close_program_account(program_id, netting_acc, rent_receiver_acc)?;
+
+        TokenEvent::NettingSettled(NettingSettledEvent {
+            netting: *netting_acc.key,
+            debtor,
+            creditor,
+            a_owes_b: netting.a_owes_b,
+            b_owes_a: netting.b_owes_a,
+            net_amount,
+            obligation_count: netting.obligation_count,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Settle netting={:?}, net_amount={} done", netting_acc.key, net_amount);
+        Ok(())
+    }
+
+    fn deposit_to_program(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            depositor_acc: signer writable,
+            source_acc: writable,
+            destination_acc: writable,
+            transfer_program_acc,
+            receiver_program_acc,
+            config_acc,
+        });
+        let extra_accs = acc_iter.as_slice();
+        msg!(
+            "Deposit to program={:?}, destination={:?}, amount={}",
+            receiver_program_acc.key, destination_acc.key, amount
+        );
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_DEPOSITS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+
+        if *transfer_program_acc.key != system_program::id() && *transfer_program_acc.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId)
+        }
+        if !receiver_program_acc.executable || receiver_program_acc.key == program_id {
+            return Err(ProgramError::IncorrectProgramId)
+        }
+        match Self::load_config(program_id, config_acc)? {
+            Some(config) if config.is_deposit_program(receiver_program_acc.key) => (),
+            _ => return Err(TransferError::DepositProgramNotAllowed.into()),
+        }
+
+        let extra_metas = extra_accs
+            .iter()
+            .map(|acc| AccountMeta { pubkey: *acc.key, is_signer: acc.is_signer, is_writable: acc.is_writable })
+            .collect();
+        let receive_deposit_instr = deposit_interface::receive_deposit(
+            *receiver_program_acc.key,
+            *depositor_acc.key,
+            *source_acc.key,
+            *destination_acc.key,
+            *transfer_program_acc.key,
+            extra_metas,
+            amount,
+        );
+        let mut cpi_accs = vec![
+            depositor_acc.clone(),
+            source_acc.clone(),
+            destination_acc.clone(),
+            transfer_program_acc.clone(),
+            receiver_program_acc.clone(),
+        ];
+        cpi_accs.extend(extra_accs.iter().cloned());
+        let balance_before = Self::deposit_balance(transfer_program_acc, destination_acc)?;
+        invoke(&receive_deposit_instr, &cpi_accs)?;
+        if Self::deposit_balance(transfer_program_acc, destination_acc)?.checked_sub(balance_before) != Some(amount) {
+            return Err(TransferError::DepositNotReceived.into())
+        }
+
+        TokenEvent::DepositToProgram(DepositToProgramEvent {
+            depositor: *depositor_acc.key,
+            program: *receiver_program_acc.key,
+            destination: *destination_acc.key,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Deposit to program={:?}, amount={} done", receiver_program_acc.key, amount);
+        Ok(())
+    }
+
+    /// Lamports of `acc`, or amount of SPL token account `acc` when `transfer_program_acc` is the SPL token program
+    fn deposit_balance(transfer_program_acc: &AccountInfo, acc: &AccountInfo) -> Result<u64, ProgramError> {
+        if *transfer_program_acc.key == system_program::id() {
+            Ok(acc.lamports())
+        } else {
+            Ok(TokenAccount::new(acc)?.amount)
+        }
+    }
+
+    fn set_deposit_program(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        program: Pubkey,
+        allowed: bool,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Set deposit program={:?}, allowed={}", program, allowed);
+
+        let mut config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        if allowed {
+            if !config.add_deposit_program(program) {
+                return Err(TransferError::DepositProgramsFull.into())
+            }
+        } else {
+            config.remove_deposit_program(&program);
+        }
+        config.save(config_acc)?;
+
+        msg!("Set deposit program={:?}, allowed={} done", program, allowed);
+        Ok(())
+    }
+
+    fn set_transfer_hook(program_id: &Pubkey, accounts: &[AccountInfo], hook_program: Option<Pubkey>) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            transfer_hook_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Set transfer hook owner={:?}, program={:?}", owner_acc.key, hook_program);
+
+        if hook_program.is_some() {
+            Self::check_feature(program_id, config_acc, Config::FEATURE_TRANSFER_HOOKS)?;
+        }
+
+        let (transfer_hook_key, bump) = TransferHook::find_address(owner_acc.key);
+        if transfer_hook_key != *transfer_hook_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        match hook_program {
+            Some(hook_program) => {
+                if hook_program == *program_id {
+                    return Err(ProgramError::IncorrectProgramId)
+                }
+                if transfer_hook_acc.data_is_empty() {
+                    Self::create_program_account(
+                        program_id,
+                        owner_acc,
+                        transfer_hook_acc,
+                        system_program_acc,
+                        TransferHook::LEN,
+                        Rent::get()?.minimum_balance(TransferHook::LEN),
+                        &[TransferHook::SEED, owner_acc.key.as_ref(), &[bump]],
+                    )?;
+                } else {
+                    TransferHook::load(transfer_hook_acc, program_id)?;
+                }
+                TransferHook {
+                    account_type: AccountType::TransferHook,
+                    owner: *owner_acc.key,
+                    program: hook_program,
+                    bump,
+                }.save(transfer_hook_acc)?;
+            }
+            None => {
+                TransferHook::load(transfer_hook_acc, program_id)?;
+                Self::close_program_account(program_id, transfer_hook_acc, owner_acc)?;
+            }
+        }
+
+        msg!("Set transfer hook owner={:?}, program={:?} done", owner_acc.key, hook_program);
+        Ok(())
+    }
+
+    fn set_config(program_id: &Pubkey, accounts: &[AccountInfo], strict_ata: bool) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Set config admin={:?}, strict_ata={}", admin_acc.key, strict_ata);
+
+        let mut config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        config.strict_ata = strict_ata;
+        config.save(config_acc)?;
+
+        msg!("Set config admin={:?}, strict_ata={} done", admin_acc.key, strict_ata);
+        Ok(())
+    }
+
+    fn set_features(program_id: &Pubkey, accounts: &[AccountInfo], features: u64) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Set features admin={:?}, features={:#b}", admin_acc.key, features);
+
+        let mut config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        config.features = features;
+        config.save(config_acc)?;
+
+        msg!("Set features admin={:?}, features={:#b} done", admin_acc.key, features);
+        Ok(())
+    }
+
+    fn set_paused(program_id: &Pubkey, accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+        let (accounts, namespace_config_acc) = Self::split_namespace_config(program_id, accounts);
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Set paused admin={:?}, paused={}", admin_acc.key, paused);
+
+        let (mut config, paused_config_acc) = match namespace_config_acc {
+            Some(namespace_config_acc) => {
+                WritableAccount::new(namespace_config_acc)?;
+                let config = Config::load(namespace_config_acc, program_id)?;
+                if config.admin != *admin_acc.key {
+                    return Err(TransferError::NotConfigAdmin.into())
+                }
+                (config, namespace_config_acc)
+            }
+            None => {
+                let config =
+                    Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+                (config, config_acc)
+            }
+        };
+        config.paused = paused;
+        config.save(paused_config_acc)?;
+
+        msg!("Set paused admin={:?}, paused={} done", admin_acc.key, paused);
+        Ok(())
+    }
+
+    fn set_travel_rule(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        required: bool,
+        threshold: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Set travel rule admin={:?}, required={}, threshold={}", admin_acc.key, required, threshold);
+
+        let mut config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        config.travel_rule_required = required;
+        config.travel_rule_threshold = threshold;
+        config.save(config_acc)?;
+
+        msg!("Set travel rule admin={:?}, required={}, threshold={} done", admin_acc.key, required, threshold);
+        Ok(())
+    }
+
+    fn set_mint_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        soulbound: bool,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            issuer_acc: signer writable,
+            mint_acc: mint,
+            mint_config_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Set mint config mint={:?}, soulbound={}", mint_acc.key, soulbound);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_MINT_CONFIGS)?;
+
+        if MintAccount::new(mint_acc)?.mint_authority != COption::Some(*issuer_acc.key) {
+            return Err(TransferError::NotMintAuthority.into())
+        }
+        let (mint_config_key, bump) = MintConfig::find_address(mint_acc.key);
+        if mint_config_key != *mint_config_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if mint_config_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                issuer_acc,
+                mint_config_acc,
+                system_program_acc,
+                MintConfig::LEN,
+                Rent::get()?.minimum_balance(MintConfig::LEN),
+                &[MintConfig::SEED, mint_acc.key.as_ref(), &[bump]],
+            )?;
+        } else {
+            MintConfig::load(mint_config_acc, program_id)?;
+        }
+        MintConfig {
+            account_type: AccountType::MintConfig,
+            mint: *mint_acc.key,
+            issuer: *issuer_acc.key,
+            soulbound,
+            bump,
+        }.save(mint_config_acc)?;
+
+        TokenEvent::MintConfigSet(MintConfigSetEvent {
+            mint: *mint_acc.key,
+            issuer: *issuer_acc.key,
+            soulbound,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Set mint config mint={:?}, soulbound={} done", mint_acc.key, soulbound);
+        Ok(())
+    }
+
+    #[cfg(feature = "payouts")]
+    fn publish_payout_root(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        id: u64,
+        root: [u8; 32],
+        leaf_count: u32,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer writable,
+            payout_batch_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Publish payout root authority={:?}, id={}, leaf_count={}", authority_acc.key, id, leaf_count);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_PAYOUT_COMMITMENTS)?;
+
+        if leaf_count == 0 || leaf_count > PayoutBatch::MAX_LEAVES {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (payout_batch_key, bump) = PayoutBatch::find_address(authority_acc.key, id);
+        if payout_batch_key != *payout_batch_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        Self::create_program_account(
+            program_id,
+            authority_acc,
+            payout_batch_acc,
+            system_program_acc,
+            PayoutBatch::LEN,
+            Rent::get()?.minimum_balance(PayoutBatch::LEN),
+            &[PayoutBatch::SEED, authority_acc.key.as_ref(), &id.to_le_bytes(), &[bump]],
+        )?;
+        let payout_batch = PayoutBatch {
+            account_type: AccountType::PayoutBatch,
+            authority: *authority_acc.key,
+            id,
+            root,
+            leaf_count,
+            executed_count: 0,
+            executed_total: 0,
+            executed: [0; 128],
+            bump,
+        };
+        payout_batch.save(payout_batch_acc)?;
+
+        TokenEvent::PayoutRootPublished(PayoutRootPublishedEvent {
+            payout_batch: *payout_batch_acc.key,
+            authority: payout_batch.authority,
+            id,
+            root,
+            leaf_count,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Publish payout root payout_batch={:?} done", payout_batch_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "payouts")]
+    fn execute_payout(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        index: u32,
+        amount: u64,
+        proof: &[[u8; 32]],
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer writable,
+            recipient_acc: writable,
+            payout_batch_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Execute payout batch={:?}, index={}, amount={}", payout_batch_acc.key, index, amount);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut payout_batch = ProgramAccount::<PayoutBatch>::new(payout_batch_acc, program_id)?;
+        if payout_batch.authority != *authority_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let leaf = merkle::payout_leaf(index, recipient_acc.key, amount);
+        if index >= payout_batch.leaf_count || !merkle::verify(leaf, proof, &payout_batch.root) {
+            return Err(TransferError::InvalidMerkleProof.into())
+        }
+        if payout_batch.is_executed(index) {
+            return Err(TransferError::PayoutAlreadyExecuted.into())
+        }
+
+        let transfer_instr = system_instruction::transfer(authority_acc.key, recipient_acc.key, amount);
+        invoke(
+            &transfer_instr,
+            &[authority_acc.clone(), recipient_acc.clone(), system_program_acc.clone()],
+        )?;
+        payout_batch.set_executed(index);
+        payout_batch.executed_count += 1;
+        payout_batch.executed_total = payout_batch.executed_total
+            .checked_add(amount)
+            .ok_or(TransferError::AmountOverflow)?;
+        payout_batch.save()?;
+
+        TokenEvent::PayoutExecuted(PayoutExecutedEvent {
+            payout_batch: *payout_batch_acc.key,
+            index,
+            recipient: *recipient_acc.key,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Execute payout batch={:?}, index={} done", payout_batch_acc.key, index);
+        Ok(())
+    }
+
+    #[cfg(feature = "payouts")]
+    fn create_distributor(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        id: u64,
+        attestor: Pubkey,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer writable,
+            distributor_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Create distributor authority={:?}, id={}, amount={}", authority_acc.key, id, amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_GATED_CLAIMS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+
+        let (distributor_key, bump) = Distributor::find_address(authority_acc.key, id);
+        if distributor_key != *distributor_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let lamports = Rent::get()?.minimum_balance(Distributor::LEN)
+            .checked_add(amount)
+            .ok_or(TransferError::AmountOverflow)?;
+        Self::create_program_account(
+            program_id,
+            authority_acc,
+            distributor_acc,
+            system_program_acc,
+            Distributor::LEN,
+            lamports,
+            &[Distributor::SEED, authority_acc.key.as_ref(), &id.to_le_bytes(), &[bump]],
+        )?;
+        let distributor = Distributor {
+            account_type: AccountType::Distributor,
+            authority: *authority_acc.key,
+            attestor,
+            id,
+            claimed_total: 0,
+            claim_count: 0,
+            bump,
+        };
+        distributor.save(distributor_acc)?;
+
+        TokenEvent::DistributorCreated(DistributorCreatedEvent {
+            distributor: *distributor_acc.key,
+            authority: distributor.authority,
+            attestor,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create distributor={:?} done", distributor_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "payouts")]
+    fn claim_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            claimer_acc: signer writable,
+            distributor_acc: writable,
+            distribution_claim_acc: writable,
+            instructions_acc: program(sysvar::instructions::id()),
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!(
+            "Claim distribution distributor={:?}, claimer={:?}, amount={}",
+            distributor_acc.key, claimer_acc.key, amount
+        );
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut distributor = ProgramAccount::<Distributor>::new(distributor_acc, program_id)?;
+        let message = eligibility::message(distributor_acc.key, claimer_acc.key, amount);
+        eligibility::verify(instructions_acc, &distributor.attestor, &message)?;
+
+        let (distribution_claim_key, bump) = DistributionClaim::find_address(distributor_acc.key, claimer_acc.key);
+        if distribution_claim_key != *distribution_claim_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let rent = Rent::get()?;
+        let available = distributor_acc.lamports().saturating_sub(rent.minimum_balance(Distributor::LEN));
+        if amount > available {
+            return Err(TransferError::InsufficientFunds.into())
+        }
+
+        Self::create_program_account(
+            program_id,
+            claimer_acc,
+            distribution_claim_acc,
+            system_program_acc,
+            DistributionClaim::LEN,
+            rent.minimum_balance(DistributionClaim::LEN),
+            &[DistributionClaim::SEED, distributor_acc.key.as_ref(), claimer_acc.key.as_ref(), &[bump]],
+        )?;
+        let distribution_claim = DistributionClaim {
+            account_type: AccountType::DistributionClaim,
+            distributor: *distributor_acc.key,
+            claimer: *claimer_acc.key,
+            amount,
+            bump,
+        };
+        distribution_claim.save(distribution_claim_acc)?;
+
+        Self::move_lamports(program_id, distributor_acc, claimer_acc, amount)?;
+        distributor.claimed_total = distributor.claimed_total
+            .checked_add(amount)
+            .ok_or(TransferError::AmountOverflow)?;
+        distributor.claim_count = distributor.claim_count
+            .checked_add(1)
+            .ok_or(TransferError::AmountOverflow)?;
+        distributor.save()?;
+
+        TokenEvent::DistributionClaimed(DistributionClaimedEvent {
+            distributor: *distributor_acc.key,
+            claimer: *claimer_acc.key,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Claim distribution distributor={:?}, amount={} done", distributor_acc.key, amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "payouts")]
+    fn create_merkle_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        merkle_root: [u8; 32],
+        leaf_count: u32,
+        total_amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            authority_acc: signer writable,
+            mint_acc: mint,
+            authority_token_acc: writable token_account(mint_acc.key),
+            distribution_acc: writable,
+            distribution_token_acc: writable token_account(mint_acc.key),
+            claim_bitmap_acc: writable,
+            spl_token_acc: token_program,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+            mint_config_acc,
+        });
+        msg!(
+            "Create merkle distribution authority={:?}, leaf_count={}, total_amount={}",
+            authority_acc.key, leaf_count, total_amount
+        );
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_MERKLE_DISTRIBUTIONS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+        if leaf_count == 0 || leaf_count > MerkleDistribution::MAX_LEAVES || total_amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (distribution_key, bump) = MerkleDistribution::find_address(authority_acc.key, &merkle_root);
+        if distribution_key != *distribution_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(distribution_acc.key, mint_acc.key) != *distribution_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let (claim_bitmap_key, claim_bitmap_bump) = ClaimBitmap::find_address(distribution_acc.key);
+        if claim_bitmap_key != *claim_bitmap_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let rent = Rent::get()?;
+        Self::create_program_account(
+            program_id,
+            authority_acc,
+            distribution_acc,
+            system_program_acc,
+            MerkleDistribution::LEN,
+            rent.minimum_balance(MerkleDistribution::LEN),
+            &[MerkleDistribution::SEED, authority_acc.key.as_ref(), &merkle_root, &[bump]],
+        )?;
+        Self::create_program_account(
+            program_id,
+            authority_acc,
+            claim_bitmap_acc,
+            system_program_acc,
+            ClaimBitmap::len(leaf_count),
+            rent.minimum_balance(ClaimBitmap::len(leaf_count)),
+            &[ClaimBitmap::SEED, distribution_acc.key.as_ref(), &[claim_bitmap_bump]],
+        )?;
+        claim_bitmap_acc.try_borrow_mut_data()?[0] = AccountType::ClaimBitmap as u8;
+        MerkleDistribution {
+            account_type: AccountType::MerkleDistribution,
+            authority: *authority_acc.key,
+            mint: *mint_acc.key,
+            root: merkle_root,
+            leaf_count,
+            total_amount,
+            claimed_total: 0,
+            claimed_count: 0,
+            bump,
+        }.save(distribution_acc)?;
+
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            authority_token_acc.key,
+            distribution_token_acc.key,
+            authority_acc.key,
+            &[authority_acc.key],
+            total_amount,
+        )?;
+        let transfer_accs = [
+            authority_token_acc.clone(),
+            distribution_token_acc.clone(),
+            authority_acc.clone(),
+            spl_token_acc.clone(),
+        ];
+        invoke(&transfer_instr, &transfer_accs)?;
+
+        TokenEvent::MerkleDistributionCreated(MerkleDistributionCreatedEvent {
+            distribution: *distribution_acc.key,
+            authority: *authority_acc.key,
+            mint: *mint_acc.key,
+            leaf_count,
+            total_amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create merkle distribution={:?} done", distribution_acc.key);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_MERKLE_DISTRIBUTIONS`, so disabling distributions never locks funded airdrops in
+    #[cfg(feature = "payouts")]
+    fn claim_merkle_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        index: u32,
+        amount: u64,
+        proof: &[[u8; 32]],
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            claimer_acc: signer,
+            claimer_token_acc: writable,
+            distribution_acc: writable,
+            distribution_token_acc: writable,
+            claim_bitmap_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Claim merkle distribution={:?}, index={}, amount={}", distribution_acc.key, index, amount);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut distribution = ProgramAccount::<MerkleDistribution>::new(distribution_acc, program_id)?;
+        if get_associated_token_address(distribution_acc.key, &distribution.mint) != *distribution_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if ClaimBitmap::find_address(distribution_acc.key).0 != *claim_bitmap_acc.key
+            || !Self::is_account_of_type(program_id, claim_bitmap_acc, AccountType::ClaimBitmap)
+        {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(claimer_token_acc)?.mint != distribution.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+        let leaf = merkle::payout_leaf(index, claimer_acc.key, amount);
+        if index >= distribution.leaf_count || !merkle::verify(leaf, proof, &distribution.root) {
+            return Err(TransferError::InvalidMerkleProof.into())
+        }
+        if ClaimBitmap::is_claimed(&claim_bitmap_acc.try_borrow_data()?, index) {
+            return Err(TransferError::LeafAlreadyClaimed.into())
+        }
+        let claimed_total = distribution.claimed_total.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        if claimed_total > distribution.total_amount {
+            return Err(TransferError::InsufficientFunds.into())
+        }
+
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            distribution_token_acc.key,
+            claimer_token_acc.key,
+            distribution_acc.key,
+            &[],
+            amount,
+        )?;
+        let transfer_accs = [
+            distribution_token_acc.clone(),
+            claimer_token_acc.clone(),
+            distribution_acc.clone(),
+            spl_token_acc.clone(),
+        ];
+        invoke_signed(
+            &transfer_instr,
+            &transfer_accs,
+            &[&[MerkleDistribution::SEED, distribution.authority.as_ref(), &distribution.root, &[distribution.bump]]],
+        )?;
+        ClaimBitmap::set_claimed(&mut claim_bitmap_acc.try_borrow_mut_data()?, index);
+        distribution.claimed_total = claimed_total;
+        distribution.claimed_count += 1;
+        distribution.save()?;
+
+        TokenEvent::MerkleDistributionClaimed(MerkleDistributionClaimedEvent {
+            distribution: *distribution_acc.key,
+            claimer: *claimer_acc.key,
+            index,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Claim merkle distribution={:?}, index={} done", distribution_acc.key, index);
+        Ok(())
+    }
+
+    #[cfg(feature = "dividends")]
+    fn create_dividend(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        (snapshot_root, holder_count, snapshot_supply): ([u8; 32], u32, u64),
+        pool_amount: u64,
+        claim_deadline: i64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            mint_acc: mint,
+            snapshot_mint_acc,
+            admin_token_acc: writable token_account(mint_acc.key),
+            dividend_acc: writable,
+            dividend_token_acc: writable token_account(mint_acc.key),
+            claim_bitmap_acc: writable,
+            spl_token_acc: token_program,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+            mint_config_acc,
+        });
+        msg!(
+            "Create dividend admin={:?}, holder_count={}, snapshot_supply={}, pool_amount={}",
+            admin_acc.key, holder_count, snapshot_supply, pool_amount
+        );
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_DIVIDENDS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+        if holder_count == 0 || holder_count > MerkleDistribution::MAX_LEAVES || snapshot_supply == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        if pool_amount == 0 || claim_deadline <= Clock::get()?.unix_timestamp {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (dividend_key, bump) = Dividend::find_address(admin_acc.key, &snapshot_root);
+        if dividend_key != *dividend_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(dividend_acc.key, mint_acc.key) != *dividend_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let (claim_bitmap_key, claim_bitmap_bump) = ClaimBitmap::find_address(dividend_acc.key);
+        if claim_bitmap_key != *claim_bitmap_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let rent = Rent::get()?;
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            dividend_acc,
+            system_program_acc,
+            Dividend::LEN,
+            rent.minimum_balance(Dividend::LEN),
+            &[Dividend::SEED, admin_acc.key.as_ref(), &snapshot_root, &[bump]],
+        )?;
+        Self::create_program_account(
+            program_id,
+            admin_acc,
+            claim_bitmap_acc,
+            system_program_acc,
+            ClaimBitmap::len(holder_count),
+            rent.minimum_balance(ClaimBitmap::len(holder_count)),
+            &[ClaimBitmap::SEED, dividend_acc.key.as_ref(), &[claim_bitmap_bump]],
+        )?;
+        claim_bitmap_acc.try_borrow_mut_data()?[0] = AccountType::ClaimBitmap as u8;
+        Dividend {
+            account_type: AccountType::Dividend,
+            admin: *admin_acc.key,
+            mint: *mint_acc.key,
+            snapshot_mint: *snapshot_mint_acc.key,
+            root: snapshot_root,
+            holder_count,
+            snapshot_supply,
+            pool_amount,
+            claimed_total: 0,
+            claimed_count: 0,
+            claim_deadline,
+            bump,
+        }.save(dividend_acc)?;
+
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            admin_token_acc.key,
+            dividend_token_acc.key,
+            admin_acc.key,
+            &[admin_acc.key],
+            pool_amount,
+        )?;
+        let transfer_accs = [
+            admin_token_acc.clone(),
+            dividend_token_acc.clone(),
+            admin_acc.clone(),
+            spl_token_acc.clone(),
+        ];
+        invoke(&transfer_instr, &transfer_accs)?;
+
+        TokenEvent::DividendCreated(DividendCreatedEvent {
+            dividend: *dividend_acc.key,
+            admin: *admin_acc.key,
+            mint: *mint_acc.key,
+            snapshot_mint: *snapshot_mint_acc.key,
+            holder_count,
+            snapshot_supply,
+            pool_amount,
+            claim_deadline,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Create dividend={:?} done", dividend_acc.key);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_DIVIDENDS`, so disabling dividends never locks funded pools in
+    #[cfg(feature = "dividends")]
+    fn claim_dividend(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        index: u32,
+        balance: u64,
+        proof: &[[u8; 32]],
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            holder_acc: signer,
+            holder_token_acc: writable,
+            dividend_acc: writable,
+            dividend_token_acc: writable,
+            claim_bitmap_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Claim dividend={:?}, index={}, balance={}", dividend_acc.key, index, balance);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut dividend = ProgramAccount::<Dividend>::new(dividend_acc, program_id)?;
+        if Clock::get()?.unix_timestamp >= dividend.claim_deadline {
+            return Err(TransferError::DividendClaimPeriodEnded.into())
+        }
+        if get_associated_token_address(dividend_acc.key, &dividend.mint) != *dividend_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if ClaimBitmap::find_address(dividend_acc.key).0 != *claim_bitmap_acc.key
+            || !Self::is_account_of_type(program_id, claim_bitmap_acc, AccountType::ClaimBitmap)
+        {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(holder_token_acc)?.mint != dividend.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+        let leaf = merkle::payout_leaf(index, holder_acc.key, balance);
+        if index >= dividend.holder_count || !merkle::verify(leaf, proof, &dividend.root) {
+            return Err(TransferError::InvalidMerkleProof.into())
+        }
+        if ClaimBitmap::is_claimed(&claim_bitmap_acc.try_borrow_data()?, index) {
+            return Err(TransferError::LeafAlreadyClaimed.into())
+        }
+        // A leaf above the snapshot supply can only come from a malformed snapshot
+        let amount = dividend.share(balance).ok_or(TransferError::InvalidMerkleProof)?;
+        let claimed_total = dividend.claimed_total.checked_add(amount).ok_or(TransferError::AmountOverflow)?;
+        if claimed_total > dividend.pool_amount {
+            return Err(TransferError::InsufficientFunds.into())
+        }
+
+        if amount > 0 {
+            let transfer_instr = spl_token::instruction::transfer(
+                spl_token_acc.key,
+                dividend_token_acc.key,
+                holder_token_acc.key,
+                dividend_acc.key,
+                &[],
+                amount,
+            )?;
+            let transfer_accs = [
+                dividend_token_acc.clone(),
+                holder_token_acc.clone(),
+                dividend_acc.clone(),
+                spl_token_acc.clone(),
+            ];
+            invoke_signed(
+                &transfer_instr,
+                &transfer_accs,
+                &[&[Dividend::SEED, dividend.admin.as_ref(), &dividend.root, &[dividend.bump]]],
+            )?;
+        }
+        ClaimBitmap::set_claimed(&mut claim_bitmap_acc.try_borrow_mut_data()?, index);
+        dividend.claimed_total = claimed_total;
+        dividend.claimed_count += 1;
+        dividend.save()?;
+
+        TokenEvent::DividendClaimed(DividendClaimedEvent {
+            dividend: *dividend_acc.key,
+            holder: *holder_acc.key,
+            index,
+            balance,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Claim dividend={:?}, index={}, amount={} done", dividend_acc.key, index, amount);
+        Ok(())
+    }
+
+    /// Not gated by `FEATURE_DIVIDENDS`, like claims
+    #[cfg(feature = "dividends")]
+    fn clawback_dividend(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            admin_token_acc: writable,
+            dividend_acc: writable,
+            dividend_token_acc: writable,
+            claim_bitmap_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Clawback dividend={:?}", dividend_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let dividend = Dividend::load(dividend_acc, program_id)?;
+        if dividend.admin != *admin_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if Clock::get()?.unix_timestamp < dividend.claim_deadline {
+            return Err(TransferError::DividendClaimPeriodNotEnded.into())
+        }
+        if get_associated_token_address(dividend_acc.key, &dividend.mint) != *dividend_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if ClaimBitmap::find_address(dividend_acc.key).0 != *claim_bitmap_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(admin_token_acc)?.mint != dividend.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+
+        let seeds: &[&[u8]] = &[Dividend::SEED, dividend.admin.as_ref(), &dividend.root, &[dividend.bump]];
+        let amount = TokenAccount::new(dividend_token_acc)?.amount;
+        if amount > 0 {
+            let transfer_instr = spl_token::instruction::transfer(
+                spl_token_acc.key,
+                dividend_token_acc.key,
+                admin_token_acc.key,
+                dividend_acc.key,
+                &[],
+                amount,
+            )?;
+            let transfer_accs = [
+                dividend_token_acc.clone(),
+                admin_token_acc.clone(),
+                dividend_acc.clone(),
+                spl_token_acc.clone(),
+            ];
+            invoke_signed(&transfer_instr, &transfer_accs, &[seeds])?;
+        }
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            dividend_token_acc.key,
+            admin_acc.key,
+            dividend_acc.key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_instr,
+            &[dividend_token_acc.clone(), admin_acc.clone(), dividend_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        Self::close_program_account(program_id, claim_bitmap_acc, admin_acc)?;
+        Self::close_program_account(program_id, dividend_acc, admin_acc)?;
+
+        TokenEvent::DividendClawedBack(DividendClawedBackEvent {
+            dividend: *dividend_acc.key,
+            admin: dividend.admin,
+            mint: dividend.mint,
+            amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Clawback dividend={:?}, amount={} done", dividend_acc.key, amount);
+        Ok(())
+    }
+
+    fn set_attestation(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        wallet: Pubkey,
+        level: u8,
+        expires_at: i64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            attestation_acc: writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Set attestation wallet={:?}, level={}, expires_at={}", wallet, level, expires_at);
+
+        let config = Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+        if config.features & Config::FEATURE_ATTESTATIONS == 0 {
+            return Err(TransferError::FeatureDisabled.into())
+        }
+
+        let (attestation_key, bump) = Attestation::find_address(&wallet);
+        if attestation_key != *attestation_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if attestation_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                admin_acc,
+                attestation_acc,
+                system_program_acc,
+                Attestation::LEN,
+                Rent::get()?.minimum_balance(Attestation::LEN),
+                &[Attestation::SEED, wallet.as_ref(), &[bump]],
+            )?;
+        } else {
+            Attestation::load(attestation_acc, program_id)?;
+        }
+        Attestation {
+            account_type: AccountType::Attestation,
+            wallet,
+            level,
+            expires_at,
+            bump,
+        }.save(attestation_acc)?;
+
+        TokenEvent::AttestationSet(AttestationSetEvent { wallet, level, expires_at })
+            .emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Set attestation wallet={:?}, level={} done", wallet, level);
+        Ok(())
+    }
+
+    fn transfer_spl_token_attested(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+        min_level: u8,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc,
+            from_spl_token_acc: writable,
+            to_wallet_acc,
+            to_spl_token_acc: writable token_account(mint_acc.key),
+            mint_acc: mint,
+            attestation_acc,
+            spl_token_acc: token_program,
+            mint_config_acc,
+        });
+        msg!(
+            "Transfer spl token attested to wallet={:?}, amount={}, min_level={}",
+            to_wallet_acc.key, amount, min_level
+        );
+
+        let (_, signer_accs) = Self::split_multisig_signers(owner_acc, acc_iter.as_slice())?;
+        Self::check_feature(program_id, policy.config_acc, Config::FEATURE_ATTESTATIONS)?;
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+        let hook_program_acc = Self::check_transfer_policy(program_id, &policy, owner_acc.key, mint_acc.key, amount)?;
+        Self::check_wallet_token_account(to_wallet_acc, mint_acc, to_spl_token_acc)?;
+        Self::check_registry(program_id, &policy, &TokenAccount::new(to_spl_token_acc)?.owner)?;
+        Self::check_strict_ata(program_id, policy.config_acc, to_spl_token_acc)?;
+        Self::check_travel_rule(program_id, policy.config_acc, amount, None)?;
+
+        let (attestation_key, _) = Attestation::find_address(to_wallet_acc.key);
+        if attestation_key != *attestation_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if attestation_acc.data_is_empty() {
+            return Err(TransferError::AttestationRequired.into())
+        }
+        let attestation = Attestation::load(attestation_acc, program_id)?;
+        if !attestation.satisfies(min_level, Clock::get()?.unix_timestamp) {
+            return Err(TransferError::AttestationRequired.into())
+        }
+
+        Self::hooked_transfer(hook_program_acc, owner_acc, from_spl_token_acc, to_spl_token_acc, amount, || {
+            Self::invoke_token_transfer(
+                spl_token_acc,
+                from_spl_token_acc,
+                to_spl_token_acc,
+                owner_acc,
+                signer_accs,
+                amount,
+            )
+        })?;
+        TokenEvent::TransferSpl(TransferSplEvent {
+            from: *from_spl_token_acc.key,
+            to: *to_spl_token_acc.key,
+            owner: *owner_acc.key,
+            mint: *mint_acc.key,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!(
+            "Transfer spl token attested to wallet={:?}, amount={}, min_level={} done",
+            to_wallet_acc.key, amount, min_level
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "nft")]
+    fn list_rental(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        price: u64,
+        duration: i64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            owner_token_acc: writable token_account(mint_acc.key),
+            mint_acc: mint,
+            rental_acc: writable,
+            escrow_token_acc: writable token_account(mint_acc.key),
+            mint_config_acc,
+            spl_token_acc: token_program,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("List rental mint={:?}, price={}, duration={}", mint_acc.key, price, duration);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_RENTALS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+
+        Self::check_nft(mint_acc)?;
+        if duration <= 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (rental_key, bump) = Rental::find_address(mint_acc.key);
+        if rental_key != *rental_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(rental_acc.key, mint_acc.key) != *escrow_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        Self::create_program_account(
+            program_id,
+            owner_acc,
+            rental_acc,
+            system_program_acc,
+            Rental::LEN,
+            Rent::get()?.minimum_balance(Rental::LEN),
+            &[Rental::SEED, mint_acc.key.as_ref(), &[bump]],
+        )?;
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            owner_token_acc.key,
+            escrow_token_acc.key,
+            owner_acc.key,
+            &[owner_acc.key],
+            1,
+        )?;
+        invoke(
+            &transfer_instr,
+            &[owner_token_acc.clone(), escrow_token_acc.clone(), owner_acc.clone(), spl_token_acc.clone()],
+        )?;
+        Rental {
+            account_type: AccountType::Rental,
+            owner: *owner_acc.key,
+            mint: *mint_acc.key,
+            renter: Pubkey::default(),
+            price,
+            duration,
+            expires_at: 0,
+            bump,
+        }.save(rental_acc)?;
+
+        TokenEvent::RentalListed(RentalListedEvent {
+            rental: *rental_acc.key,
+            owner: *owner_acc.key,
+            mint: *mint_acc.key,
+            price,
+            duration,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("List rental mint={:?}, price={} done", mint_acc.key, price);
+        Ok(())
+    }
+
+    #[cfg(feature = "nft")]
+    fn rent_nft(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            renter_acc: signer writable,
+            owner_acc: writable,
+            rental_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Rent nft rental={:?}, renter={:?}", rental_acc.key, renter_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let mut rental = ProgramAccount::<Rental>::new(rental_acc, program_id)?;
+        if rental.owner != *owner_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if rental.is_rented() {
+            return Err(TransferError::RentalUnavailable.into())
+        }
+
+        invoke(
+            &system_instruction::transfer(renter_acc.key, owner_acc.key, rental.price),
+            &[renter_acc.clone(), owner_acc.clone(), system_program_acc.clone()],
+        )?;
+        rental.renter = *renter_acc.key;
+        rental.expires_at = Clock::get()?.unix_timestamp
+            .checked_add(rental.duration)
+            .ok_or(TransferError::AmountOverflow)?;
+        rental.save()?;
+
+        TokenEvent::NftRented(NftRentedEvent {
+            rental: *rental_acc.key,
+            renter: rental.renter,
+            price: rental.price,
+            expires_at: rental.expires_at,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Rent nft rental={:?}, expires_at={} done", rental_acc.key, rental.expires_at);
+        Ok(())
+    }
+
+    #[cfg(feature = "nft")]
+    fn return_rental(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: writable,
+            owner_token_acc: writable,
+            rental_acc: writable,
+            escrow_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Return rental={:?}", rental_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let rental = Rental::load(rental_acc, program_id)?;
+        if !rental.is_rented() || Clock::get()?.unix_timestamp < rental.expires_at {
+            return Err(TransferError::RentalNotExpired.into())
+        }
+        let event =
+            Self::close_rental(
+                program_id,
+                &rental,
+                owner_acc,
+                owner_token_acc,
+                rental_acc,
+                escrow_token_acc,
+                spl_token_acc,
+            )?;
+        TokenEvent::RentalReturned(event).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        // Return pays no bounty, the execution is still credited to the keeper
+        Self::record_keeper_execution(program_id, accounts.get(6..).unwrap_or(&[]), 0)?;
+
+        msg!("Return rental={:?} done", rental_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "nft")]
+    fn cancel_rental(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            owner_acc: signer writable,
+            owner_token_acc: writable,
+            rental_acc: writable,
+            escrow_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Cancel rental={:?}", rental_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let rental = Rental::load(rental_acc, program_id)?;
+        if rental.is_rented() {
+            return Err(TransferError::RentalUnavailable.into())
+        }
+        let event =
+            Self::close_rental(
+                program_id,
+                &rental,
+                owner_acc,
+                owner_token_acc,
+                rental_acc,
+                escrow_token_acc,
+                spl_token_acc,
+            )?;
+        TokenEvent::RentalReturned(event).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!("Cancel rental={:?} done", rental_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "nft")]
+    fn fractionalize_nft(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        shares: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            depositor_acc: signer writable,
+            depositor_nft_token_acc: writable token_account(nft_mint_acc.key),
+            nft_mint_acc: mint,
+            fraction_acc: writable,
+            vault_token_acc: writable token_account(nft_mint_acc.key),
+            share_mint_acc: writable mint,
+            depositor_share_token_acc: writable token_account(share_mint_acc.key),
+            mint_config_acc,
+            spl_token_acc: token_program,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Fractionalize nft mint={:?}, shares={}", nft_mint_acc.key, shares);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_FRACTIONS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        Self::check_transferable(program_id, mint_config_acc, nft_mint_acc.key)?;
+        Self::check_nft(nft_mint_acc)?;
+        if shares == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (fraction_key, bump) = Fraction::find_address(nft_mint_acc.key);
+        if fraction_key != *fraction_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(fraction_acc.key, nft_mint_acc.key) != *vault_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let share_mint = MintAccount::new(share_mint_acc)?;
+        if share_mint.supply != 0
+            || share_mint.mint_authority != COption::Some(fraction_key)
+            || share_mint.freeze_authority.is_some()
+        {
+            return Err(TransferError::InvalidShareMint.into())
+        }
+
+        Self::create_program_account(
+            program_id,
+            depositor_acc,
+            fraction_acc,
+            system_program_acc,
+            Fraction::LEN,
+            Rent::get()?.minimum_balance(Fraction::LEN),
+            &[Fraction::SEED, nft_mint_acc.key.as_ref(), &[bump]],
+        )?;
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            depositor_nft_token_acc.key,
+            vault_token_acc.key,
+            depositor_acc.key,
+            &[depositor_acc.key],
+            1,
+        )?;
+        invoke(
+            &transfer_instr,
+            &[depositor_nft_token_acc.clone(), vault_token_acc.clone(), depositor_acc.clone(), spl_token_acc.clone()],
+        )?;
+        let mint_to_instr = spl_token::instruction::mint_to(
+            spl_token_acc.key,
+            share_mint_acc.key,
+            depositor_share_token_acc.key,
+            fraction_acc.key,
+            &[],
+            shares,
+        )?;
+        invoke_signed(
+            &mint_to_instr,
+            &[share_mint_acc.clone(), depositor_share_token_acc.clone(), fraction_acc.clone(), spl_token_acc.clone()],
+            &[&[Fraction::SEED, nft_mint_acc.key.as_ref(), &[bump]]],
+        )?;
+        Fraction {
+            account_type: AccountType::Fraction,
+            depositor: *depositor_acc.key,
+            nft_mint: *nft_mint_acc.key,
+            share_mint: *share_mint_acc.key,
+            shares,
+            bump,
+        }.save(fraction_acc)?;
+
+        TokenEvent::NftFractionalized(NftFractionalizedEvent {
+            fraction: *fraction_acc.key,
+            depositor: *depositor_acc.key,
+            nft_mint: *nft_mint_acc.key,
+            share_mint: *share_mint_acc.key,
+            shares,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Fractionalize nft mint={:?}, shares={} done", nft_mint_acc.key, shares);
+        Ok(())
+    }
+
+    #[cfg(feature = "nft")]
+    fn redeem_nft(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            redeemer_acc: signer,
+            redeemer_share_token_acc: writable token_account(share_mint_acc.key),
+            share_mint_acc: writable,
+            redeemer_nft_token_acc: writable token_account(nft_mint_acc.key),
+            nft_mint_acc,
+            depositor_acc: writable,
+            fraction_acc: writable,
+            vault_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Redeem nft mint={:?}, redeemer={:?}", nft_mint_acc.key, redeemer_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let fraction = Fraction::load(fraction_acc, program_id)?;
+        if fraction.share_mint != *share_mint_acc.key
+            || fraction.nft_mint != *nft_mint_acc.key
+            || fraction.depositor != *depositor_acc.key
+        {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if get_associated_token_address(fraction_acc.key, nft_mint_acc.key) != *vault_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let burn_instr = spl_token::instruction::burn(
+            spl_token_acc.key,
+            redeemer_share_token_acc.key,
+            share_mint_acc.key,
+            redeemer_acc.key,
+            &[],
+            fraction.shares,
+        )?;
+        invoke(
+            &burn_instr,
+            &[redeemer_share_token_acc.clone(), share_mint_acc.clone(), redeemer_acc.clone(), spl_token_acc.clone()],
+        )?;
+        let seeds: &[&[u8]] = &[Fraction::SEED, fraction.nft_mint.as_ref(), &[fraction.bump]];
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            vault_token_acc.key,
+            redeemer_nft_token_acc.key,
+            fraction_acc.key,
+            &[],
+            1,
+        )?;
+        invoke_signed(
+            &transfer_instr,
+            &[vault_token_acc.clone(), redeemer_nft_token_acc.clone(), fraction_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            vault_token_acc.key,
+            depositor_acc.key,
+            fraction_acc.key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_instr,
+            &[vault_token_acc.clone(), depositor_acc.clone(), fraction_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        Self::close_program_account(program_id, fraction_acc, depositor_acc)?;
+
+        TokenEvent::NftRedeemed(NftRedeemedEvent {
+            fraction: *fraction_acc.key,
+            redeemer: *redeemer_acc.key,
+            nft_mint: fraction.nft_mint,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Redeem nft mint={:?} done", nft_mint_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "orders")]
+    fn post_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        id: u64,
+        sell_amount: u64,
+        buy_amount: u64,
+        fill_mode: FillMode,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            maker_acc: signer writable,
+            maker_sell_token_acc: writable token_account(sell_mint_acc.key),
+            sell_mint_acc: mint,
+            buy_mint_acc: mint,
+            order_acc: writable,
+            escrow_token_acc: writable token_account(sell_mint_acc.key),
+            mint_config_acc,
+            buy_mint_config_acc,
+            spl_token_acc: token_program,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Post order={:?}, sell={}, buy={}", order_acc.key, sell_amount, buy_amount);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_ORDERS)?;
+        Self::check_not_paused(program_id, config_acc)?;
+        Self::check_transferable(program_id, mint_config_acc, sell_mint_acc.key)?;
+        Self::check_transferable(program_id, buy_mint_config_acc, buy_mint_acc.key)?;
+        if sell_amount == 0 || buy_amount == 0 || sell_mint_acc.key == buy_mint_acc.key {
+            return Err(ProgramError::InvalidArgument)
+        }
+        let (order_key, bump) = Order::find_address(maker_acc.key, id);
+        if order_key != *order_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if get_associated_token_address(order_acc.key, sell_mint_acc.key) != *escrow_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        Self::create_program_account(
+            program_id,
+            maker_acc,
+            order_acc,
+            system_program_acc,
+            Order::LEN,
+            Rent::get()?.minimum_balance(Order::LEN),
+            &[Order::SEED, maker_acc.key.as_ref(), &id.to_le_bytes(), &[bump]],
+        )?;
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            maker_sell_token_acc.key,
+            escrow_token_acc.key,
+            maker_acc.key,
+            &[maker_acc.key],
+            sell_amount,
+        )?;
+        invoke(
+            &transfer_instr,
+            &[maker_sell_token_acc.clone(), escrow_token_acc.clone(), maker_acc.clone(), spl_token_acc.clone()],
+        )?;
+        Order {
+            account_type: AccountType::Order,
+            maker: *maker_acc.key,
+            id,
+            sell_mint: *sell_mint_acc.key,
+            buy_mint: *buy_mint_acc.key,
+            sell_total: sell_amount,
+            buy_total: buy_amount,
+            remaining: sell_amount,
+            fill_mode,
+            bump,
+        }.save(order_acc)?;
+
+        TokenEvent::OrderPosted(OrderPostedEvent {
+            order: *order_acc.key,
+            maker: *maker_acc.key,
+            sell_mint: *sell_mint_acc.key,
+            buy_mint: *buy_mint_acc.key,
+            sell_amount,
+            buy_amount,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Post order={:?}, sell={}, buy={} done", order_acc.key, sell_amount, buy_amount);
+        Ok(())
+    }
+
+    #[cfg(feature = "orders")]
+    fn fill_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            taker_acc: signer,
+            taker_buy_token_acc: writable,
+            taker_sell_token_acc: writable,
+            maker_acc: writable,
+            maker_buy_token_acc: writable,
+            maker_sell_token_acc: writable,
+            order_acc: writable,
+            escrow_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Fill order={:?}, taker={:?}, amount={}", order_acc.key, taker_acc.key, amount);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        Self::check_sandwich(accounts.get(10), &[
+            taker_buy_token_acc,
+            taker_sell_token_acc,
+            maker_buy_token_acc,
+            maker_sell_token_acc,
+            order_acc,
+            escrow_token_acc,
+        ])?;
+
+        let mut order = ProgramAccount::<Order>::new(order_acc, program_id)?;
+        if order.maker != *maker_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let maker_buy_token = TokenAccount::new(maker_buy_token_acc)?;
+        if maker_buy_token.owner != order.maker || maker_buy_token.mint != order.buy_mint {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+        if get_associated_token_address(order_acc.key, &order.sell_mint) != *escrow_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if amount == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        if order.fill_mode == FillMode::FillOrKill && amount != order.remaining {
+            return Err(TransferError::FillOrKill.into())
+        }
+        order.remaining = order.remaining
+            .checked_sub(amount)
+            .ok_or(TransferError::OrderAmountExceeded)?;
+        let paid = order.price_of(amount).ok_or(TransferError::AmountOverflow)?;
+
+        let pay_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            taker_buy_token_acc.key,
+            maker_buy_token_acc.key,
+            taker_acc.key,
+            &[taker_acc.key],
+            paid,
+        )?;
+        invoke(
+            &pay_instr,
+            &[taker_buy_token_acc.clone(), maker_buy_token_acc.clone(), taker_acc.clone(), spl_token_acc.clone()],
+        )?;
+        let seeds: &[&[u8]] = &[Order::SEED, order.maker.as_ref(), &order.id.to_le_bytes(), &[order.bump]];
+        let release_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            escrow_token_acc.key,
+            taker_sell_token_acc.key,
+            order_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke_signed(
+            &release_instr,
+            &[escrow_token_acc.clone(), taker_sell_token_acc.clone(), order_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        let remaining = order.remaining;
+        let fill_event = OrderFilledEvent {
+            order: *order_acc.key,
+            taker: *taker_acc.key,
+            amount,
+            paid,
+            remaining,
+        };
+        let sequence = Self::next_event_sequence(program_id, event_stream_acc)?;
+        if remaining == 0 {
+            TokenEvent::OrderFilled(fill_event).emit(sequence)?;
+        } else {
+            TokenEvent::OrderPartiallyFilled(fill_event).emit(sequence)?;
+        }
+        let cancel_rest = remaining > 0 && order.fill_mode == FillMode::ImmediateOrCancel;
+        if cancel_rest {
+            let maker_sell_token = TokenAccount::new(maker_sell_token_acc)?;
+            if maker_sell_token.owner != order.maker || maker_sell_token.mint != order.sell_mint {
+                return Err(TransferError::TokenAccountOwnerMismatch.into())
+            }
+        }
+        if remaining == 0 || cancel_rest {
+            let refund = Self::refund_order(
+                program_id,
+                &order,
+                maker_acc,
+                maker_sell_token_acc,
+                order_acc,
+                escrow_token_acc,
+                spl_token_acc,
+            )?;
+            if let Some(event) = refund {
+                TokenEvent::OrderCancelled(event).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+            }
+        } else {
+            order.save()?;
+        }
+
+        msg!("Fill order={:?}, amount={}, remaining={} done", order_acc.key, amount, remaining);
+        Ok(())
+    }
+
+    #[cfg(feature = "orders")]
+    fn cancel_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            maker_acc: signer writable,
+            maker_sell_token_acc: writable,
+            order_acc: writable,
+            escrow_token_acc: writable,
+            spl_token_acc: token_program,
+            config_acc,
+        });
+        msg!("Cancel order={:?}", order_acc.key);
+
+        Self::check_not_paused(program_id, config_acc)?;
+        let order = Order::load(order_acc, program_id)?;
+        if order.maker != *maker_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        if get_associated_token_address(order_acc.key, &order.sell_mint) != *escrow_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let refund =
+            Self::refund_order(
+                program_id,
+                &order,
+                maker_acc,
+                maker_sell_token_acc,
+                order_acc,
+                escrow_token_acc,
+                spl_token_acc,
+            )?;
+        if let Some(event) = refund {
+            TokenEvent::OrderCancelled(event).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        }
+
+        msg!("Cancel order={:?}, refunded={} done", order_acc.key, order.remaining);
+        Ok(())
+    }
+
+    #[cfg(feature = "oracle")]
+    fn set_price(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        price: u64,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            mint_acc: mint,
+            price_feed_acc: writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Set price mint={:?}, price={}", mint_acc.key, price);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_PRICE_FEEDS)?;
+        Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+
+        let (price_feed_key, bump) = PriceFeed::find_address(mint_acc.key);
+        if price_feed_key != *price_feed_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if price_feed_acc.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                admin_acc,
+                price_feed_acc,
+                system_program_acc,
+                PriceFeed::LEN,
+                Rent::get()?.minimum_balance(PriceFeed::LEN),
+                &[PriceFeed::SEED, mint_acc.key.as_ref(), &[bump]],
+            )?;
+        } else {
+            PriceFeed::load(price_feed_acc, program_id)?;
+        }
+        let updated_at = Clock::get()?.unix_timestamp;
+        PriceFeed {
+            account_type: AccountType::PriceFeed,
+            mint: *mint_acc.key,
+            price,
+            updated_at,
+            bump,
+        }.save(price_feed_acc)?;
+
+        TokenEvent::PriceUpdated(PriceUpdatedEvent { mint: *mint_acc.key, price, updated_at })
+            .emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Set price mint={:?}, price={} done", mint_acc.key, price);
+        Ok(())
+    }
+
+    /// Transfer aborted when the price feed of mint is stale or its price left the band,
+    /// protects cranked transfers of volatile tokens from a price dislocation
+    #[cfg(feature = "oracle")]
+    fn transfer_spl_token_price_protected(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        amount: u64,
+        band: RangeInclusive<u64>,
+        max_age: i64,
+    ) -> ProgramResult {
+        let (policy, accounts) = Self::split_transfer_policy(accounts)?;
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            from_acc,
+            from_spl_token_acc: writable token_account(mint_acc.key),
+            to_spl_token_acc: writable token_account(mint_acc.key),
+            mint_acc: mint,
+            price_feed_acc,
+            mint_config_acc,
+            spl_token_acc: token_program,
+        });
+        msg!("Transfer spl token price protected amount={}, band={:?}", amount, band);
+
+        let (trailing_accs, signer_accs) = Self::split_multisig_signers(from_acc, acc_iter.as_slice())?;
+        Self::check_feature(program_id, policy.config_acc, Config::FEATURE_PRICE_FEEDS)?;
+        Self::check_transferable(program_id, mint_config_acc, mint_acc.key)?;
+        let hook_program_acc = Self::check_transfer_policy(program_id, &policy, from_acc.key, mint_acc.key, amount)?;
+        Self::check_registry(program_id, &policy, &TokenAccount::new(to_spl_token_acc)?.owner)?;
+        Self::check_strict_ata(program_id, policy.config_acc, to_spl_token_acc)?;
+        Self::check_travel_rule(program_id, policy.config_acc, amount, None)?;
+
+        Self::check_sandwich(trailing_accs.first(), &[from_spl_token_acc, to_spl_token_acc, price_feed_acc])?;
+
+        let (price_feed_key, _) = PriceFeed::find_address(mint_acc.key);
+        if price_feed_key != *price_feed_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let price_feed = PriceFeed::load(price_feed_acc, program_id)?;
+        let age = Clock::get()?.unix_timestamp.saturating_sub(price_feed.updated_at);
+        if age > max_age {
+            return Err(TransferError::PriceStale.into())
+        }
+        if !band.contains(&price_feed.price) {
+            return Err(TransferError::PriceOutOfBand.into())
+        }
+
+        Self::hooked_transfer(hook_program_acc, from_acc, from_spl_token_acc, to_spl_token_acc, amount, || {
+            Self::invoke_token_transfer(
+                spl_token_acc,
+                from_spl_token_acc,
+                to_spl_token_acc,
+                from_acc,
+                signer_accs,
+                amount,
+            )
+        })?;
+        TokenEvent::TransferSpl(TransferSplEvent {
+            from: *from_spl_token_acc.key,
+            to: *to_spl_token_acc.key,
+            owner: *from_acc.key,
+            mint: *mint_acc.key,
+            amount,
+            fee: 0,
+            memo: None,
+        }).emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+
+        msg!("Transfer spl token price protected amount={}, price={} done", amount, price_feed.price);
+        Ok(())
+    }
+
+    #[cfg(feature = "keepers")]
+    fn register_keeper(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            keeper_acc: signer writable,
+            keeper_registry_acc: writable,
+            system_program_acc: program(system_program::id()),
+            config_acc,
+        });
+        msg!("Register keeper={:?}", keeper_acc.key);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_KEEPERS)?;
+
+        let (keeper_registry_key, bump) = Keeper::find_address(keeper_acc.key);
+        if keeper_registry_key != *keeper_registry_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !keeper_registry_acc.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized)
+        }
+        Self::create_program_account(
+            program_id,
+            keeper_acc,
+            keeper_registry_acc,
+            system_program_acc,
+            Keeper::LEN,
+            Rent::get()?.minimum_balance(Keeper::LEN),
+            &[Keeper::SEED, keeper_acc.key.as_ref(), &[bump]],
+        )?;
+        Keeper {
+            account_type: AccountType::Keeper,
+            keeper: *keeper_acc.key,
+            executions: 0,
+            bounties: 0,
+            banned: false,
+            bump,
+        }.save(keeper_registry_acc)?;
+
+        TokenEvent::KeeperRegistered(KeeperRegisteredEvent { keeper: *keeper_acc.key })
+            .emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Register keeper={:?} done", keeper_acc.key);
+        Ok(())
+    }
+
+    #[cfg(feature = "keepers")]
+    fn set_keeper_ban(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        event_stream_acc: Option<&AccountInfo>,
+        banned: bool,
+    ) -> ProgramResult {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            admin_acc: signer writable,
+            keeper_registry_acc: writable,
+            config_acc: writable,
+            system_program_acc: program(system_program::id()),
+        });
+        msg!("Set keeper ban registry={:?}, banned={}", keeper_registry_acc.key, banned);
+
+        Self::check_feature(program_id, config_acc, Config::FEATURE_KEEPERS)?;
+        Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+
+        let mut keeper = ProgramAccount::<Keeper>::new(keeper_registry_acc, program_id)?;
+        keeper.banned = banned;
+        keeper.save()?;
+
+        TokenEvent::KeeperBanSet(KeeperBanSetEvent { keeper: keeper.keeper, banned })
+            .emit(Self::next_event_sequence(program_id, event_stream_acc)?)?;
+        msg!("Set keeper ban registry={:?}, banned={} done", keeper_registry_acc.key, banned);
+        Ok(())
+    }
+
+    /// Credit crank execution and its bounty to keeper passed as optional trailing
+    /// `[keeper, keeper registry]` accounts, banned keeper can't execute the crank
+    #[cfg(feature = "nft")]
+    fn record_keeper_execution(program_id: &Pubkey, keeper_accs: &[AccountInfo], bounty: u64) -> ProgramResult {
+        let acc_iter = &mut keeper_accs.iter();
+        let keeper_acc = match acc_iter.next() {
+            Some(acc) => acc,
+            None => return Ok(()),
+        };
+        let keeper_registry_acc = acc_iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        crate::account::SignerAccount::new(keeper_acc)?;
+
+        let (keeper_registry_key, _) = Keeper::find_address(keeper_acc.key);
+        if keeper_registry_key != *keeper_registry_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let mut keeper = ProgramAccount::<Keeper>::new(keeper_registry_acc, program_id)?;
+        if keeper.banned {
+            return Err(TransferError::KeeperBanned.into())
+        }
+        keeper.executions = keeper.executions.checked_add(1).ok_or(TransferError::AmountOverflow)?;
+        keeper.bounties = keeper.bounties.checked_add(bounty).ok_or(TransferError::AmountOverflow)?;
+        keeper.save()
+    }
+
+    /// Config for update by admin, created on first use with signer as admin. Only the upgrade authority of the
+    /// program, see `upgrade_authority`, or `CONFIG_ADMIN` may create it
+    fn load_config_as_admin<'a>(
+        program_id: &Pubkey,
+        admin_acc: &AccountInfo<'a>,
+        config_acc: &AccountInfo<'a>,
+        system_program_acc: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+    ) -> Result<Config, ProgramError> {
+        let (config_key, bump) = Config::find_address();
+        if config_key != *config_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let config = if config_acc.data_is_empty() {
+            let creator = Some(*admin_acc.key);
+            if CONFIG_ADMIN != creator && Self::upgrade_authority(program_id, accounts) != creator {
+                return Err(TransferError::NotConfigAdmin.into())
+            }
+            Self::create_program_account(
+                program_id,
+                admin_acc,
+                config_acc,
+                system_program_acc,
+                Config::LEN,
+                Rent::get()?.minimum_balance(Config::LEN),
+                &[Config::SEED, &[bump]],
+            )?;
+            Config {
+                account_type: AccountType::Config,
+                admin: *admin_acc.key,
+                strict_ata: false,
+                features: Config::DEFAULT_FEATURES,
+                travel_rule_required: false,
+                travel_rule_threshold: 0,
+                paused: false,
+                deposit_programs: [Pubkey::default(); 4],
+                bump,
+            }
+        } else {
+            Config::load(config_acc, program_id)?
+        };
+        if config.admin != *admin_acc.key {
+            return Err(TransferError::NotConfigAdmin.into())
+        }
+        Ok(config)
+    }
+
+    /// Upgrade authority of the program from its ProgramData account passed last, see
+    /// `TokenInstruction::with_program_data`, None without the account or for an immutable program
+    fn upgrade_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> Option<Pubkey> {
+        let (program_data_key, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+        let program_data_acc = accounts.last()
+            .filter(|acc| *acc.key == program_data_key && *acc.owner == bpf_loader_upgradeable::id())?;
+        let data = program_data_acc.try_borrow_data().ok()?;
+        let metadata = data.get(..UpgradeableLoaderState::size_of_programdata_metadata())?;
+        // Bincode of `UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }`
+        match metadata.split_at(12) {
+            ([3, 0, 0, 0, ..], [1, authority @ ..]) => Pubkey::try_from(authority).ok(),
+            _ => None,
+        }
+    }
+
+    /// Rejects the transaction when another of its instructions touches `guarded_accs`, so price or swap
+    /// accounts can not be moved around a cranked execution. Checked only when the optional trailing
+    /// instructions sysvar account is passed
+    #[cfg(any(feature = "oracle", feature = "orders", feature = "escrow"))]
+    fn check_sandwich(instructions_acc: Option<&AccountInfo>, guarded_accs: &[&AccountInfo]) -> ProgramResult {
+        let instructions_acc = match instructions_acc {
+            Some(acc) => acc,
+            None => return Ok(()),
+        };
+        let current_index = sysvar::instructions::load_current_index_checked(instructions_acc)? as usize;
+
+        for index in (0..).filter(|index| *index != current_index) {
+            let instr = match sysvar::instructions::load_instruction_at_checked(index, instructions_acc) {
+                Ok(instr) => instr,
+                Err(ProgramError::InvalidArgument) => break,
+                Err(e) => return Err(e),
+            };
+            let touches_guarded = instr.accounts
+                .iter()
+                .any(|meta| guarded_accs.iter().any(|acc| *acc.key == meta.pubkey));
+            if touches_guarded {
+                msg!("Instruction {} of program {:?} touches guarded accounts", index, instr.program_id);
+                return Err(TransferError::UnexpectedInstruction.into())
+            }
+        }
+        Ok(())
+    }
+
+    /// Accounts of transfer `instr` with its optional trailing instructions sysvar split off, once the guard
+    /// it requests is checked, see `check_guarded`. Price protected transfers keep the sysvar, it's their sandwich
+    /// guard, see `TokenInstruction::with_sandwich_guard`
+    fn split_guard<'a, 'b>(
+        program_id: &Pubkey,
+        instr: &TokenInstruction,
+        accounts: &'b [AccountInfo<'a>],
+    ) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+        if matches!(instr, TokenInstruction::TransferSplTokenPriceProtected { .. }) {
+            return Ok(accounts)
+        }
+        let (accounts, instructions_acc) = Self::split_last_instructions(accounts);
+        Self::check_guarded(program_id, instructions_acc)?;
+        Ok(accounts)
+    }
+
+    /// Rejects the transfer unless `GuardPrecedingInstruction` of this program is the instruction right before it,
+    /// so one guard never covers several transfers. Checked only when the optional trailing instructions sysvar
+    /// account is passed, see `with_guard`
+    fn check_guarded(program_id: &Pubkey, instructions_acc: Option<&AccountInfo>) -> ProgramResult {
+        let instructions_acc = match instructions_acc {
+            Some(acc) => acc,
+            None => return Ok(()),
+        };
+        let current_index = sysvar::instructions::load_current_index_checked(instructions_acc)? as usize;
+        let guard_index = current_index.checked_sub(1).ok_or(TransferError::GuardRequired)?;
+
+        let instr = sysvar::instructions::load_instruction_at_checked(guard_index, instructions_acc)?;
+        let is_guard = matches!(
+            TokenInstruction::unpack(&instr.data),
+            Ok(TokenInstruction::GuardPrecedingInstruction { .. })
+        );
+        if instr.program_id != *program_id || !is_guard {
+            return Err(TransferError::GuardRequired.into())
+        }
+        Ok(())
+    }
+
+    /// Instructions sysvar passed as the last account, see `with_guard`
+    fn split_last_instructions<'a, 'b>(
+        accounts: &'b [AccountInfo<'a>],
+    ) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+        match accounts.split_last() {
+            Some((last_acc, instr_accs)) if *last_acc.key == sysvar::instructions::id() => (instr_accs, Some(last_acc)),
+            _ => (accounts, None),
+        }
+    }
+
+    /// Signers of a multisig owner, passed as trailing signer accounts after any other optional accounts,
+    /// returned after the accounts before them. Without multisig signers the owner itself must sign
+    fn split_multisig_signers<'a, 'b>(
+        owner_acc: &AccountInfo<'a>,
+        trailing_accs: &'b [AccountInfo<'a>],
+    ) -> Result<(&'b [AccountInfo<'a>], &'b [AccountInfo<'a>]), ProgramError> {
+        let signer_count = trailing_accs.iter().rev().take_while(|acc| acc.is_signer).count();
+        let (trailing_accs, signer_accs) = trailing_accs.split_at(trailing_accs.len() - signer_count);
+        if signer_accs.is_empty() && !owner_acc.is_signer {
+            return Err(ProgramError::MissingRequiredSignature)
+        }
+        Ok((trailing_accs, signer_accs))
+    }
+
+    /// Transfer `amount` of SPL token authorized by `authority_acc` itself, or by `signer_accs` of its multisig,
+    /// see `split_multisig_signers`
+    fn invoke_token_transfer<'a>(
+        spl_token_acc: &AccountInfo<'a>,
+        source_acc: &AccountInfo<'a>,
+        destination_acc: &AccountInfo<'a>,
+        authority_acc: &AccountInfo<'a>,
+        signer_accs: &[AccountInfo<'a>],
+        amount: u64,
+    ) -> ProgramResult {
+        let signer_keys: Vec<&Pubkey> = signer_accs.iter().map(|acc| acc.key).collect();
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            source_acc.key,
+            destination_acc.key,
+            authority_acc.key,
+            &signer_keys,
+            amount,
+        )?;
+        let mut transfer_accs = vec![
+            authority_acc.clone(),
+            source_acc.clone(),
+            destination_acc.clone(),
+            spl_token_acc.clone(),
+        ];
+        transfer_accs.extend_from_slice(signer_accs);
+        invoke(&transfer_instr, &transfer_accs)
+    }
+
+    /// Fee of `amount` when optional trailing `[fee config, fee account]` accounts are passed
+    fn load_fee<'a, 'b>(
+        program_id: &Pubkey,
+        trailing_accs: &'b [AccountInfo<'a>],
+        amount: u64,
+    ) -> Result<Option<TransferFee<'a, 'b>>, ProgramError> {
+        let fee_config_acc = match trailing_accs.first() {
+            Some(acc) if Self::is_account_of_type(program_id, acc, AccountType::FeeConfig) => acc,
+            _ => return Ok(None),
+        };
+        let fee_acc = trailing_accs.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        let fee_config = FeeConfig::load(fee_config_acc, program_id)?;
+        Ok(Some((fee_config.fee_recipient, fee_acc, fee_config.fee(amount))))
+    }
+
+    fn invoke_transfer_hook<'a>(
+        hook_program_acc: &AccountInfo<'a>,
+        authority_acc: &AccountInfo<'a>,
+        source_acc: &AccountInfo<'a>,
+        destination_acc: &AccountInfo<'a>,
+        stage: TransferHookStage,
+        amount: u64,
+    ) -> ProgramResult {
+        let hook_instr = hook_interface::transfer_hook(
+            *hook_program_acc.key,
+            *authority_acc.key,
+            *source_acc.key,
+            *destination_acc.key,
+            stage,
+            amount,
+        );
+        invoke(
+            &hook_instr,
+            &[authority_acc.clone(), source_acc.clone(), destination_acc.clone(), hook_program_acc.clone()],
+        )
+    }
+
+    /// SPL memo program account, next in `acc_iter` of a transfer carrying `memo`
+    fn load_memo_program<'a, 'b>(
+        acc_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+        memo: Option<&str>,
+    ) -> Result<Option<&'b AccountInfo<'a>>, ProgramError> {
+        if memo.is_none() {
+            return Ok(None)
+        }
+        accounts!(acc_iter => {
+            memo_program_acc: program(spl_memo::id()),
+        });
+        Ok(Some(memo_program_acc))
+    }
+
+    /// Log `memo` through SPL memo program, where explorers and reconciliation tools look for it
+    fn invoke_memo(memo_program_acc: &AccountInfo, memo: &str) -> ProgramResult {
+        invoke(&spl_memo::build_memo(memo.as_bytes(), &[]), std::slice::from_ref(memo_program_acc))
+    }
+
+    /// Program config, None while it was never set. Config of a namespace is rejected, so pause, features
+    /// and safety switches are always those of the program
+    fn load_config(program_id: &Pubkey, config_acc: &AccountInfo) -> Result<Option<Config>, ProgramError> {
+        let (config_key, _) = Config::find_address();
+        if config_key != *config_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if config_acc.data_is_empty() {
+            return Ok(None)
+        }
+        Ok(Some(Config::load(config_acc, program_id)?))
+    }
+
+    /// Source must be an SPL token account of `owner` and destination an SPL token account of the same mint,
+    /// returns the mint
+    fn check_token_transfer(
+        from_spl_token_acc: &AccountInfo,
+        to_spl_token_acc: &AccountInfo,
+        owner: &Pubkey,
+    ) -> Result<Pubkey, ProgramError> {
+        let from_spl_token = TokenAccount::new(from_spl_token_acc)?;
+        if from_spl_token.owner != *owner {
+            let (expected, actual) = (ErrorValue::Key(*owner), ErrorValue::Key(from_spl_token.owner));
+            return Err(ErrorContextEvent::fail(TransferError::SourceOwnerMismatch, None, Some(expected), Some(actual)))
+        }
+        let to_mint = TokenAccount::new(to_spl_token_acc)?.mint;
+        if to_mint != from_spl_token.mint {
+            let (expected, actual) = (ErrorValue::Key(from_spl_token.mint), ErrorValue::Key(to_mint));
+            return Err(ErrorContextEvent::fail(TransferError::MintMismatch, None, Some(expected), Some(actual)))
+        }
+        Ok(from_spl_token.mint)
+    }
+
+    /// Signer must be the admin of initialized config
+    fn check_config_admin(program_id: &Pubkey, admin_acc: &AccountInfo, config_acc: &AccountInfo) -> ProgramResult {
+        match Self::load_config(program_id, config_acc)? {
+            Some(config) if config.admin == *admin_acc.key => Ok(()),
+            _ => Err(TransferError::NotConfigAdmin.into()),
+        }
+    }
+
+    /// Delegate must not be revoked within `scope`, revocation account is a PDA that may be uninitialized
+    fn check_not_revoked(
+        program_id: &Pubkey,
+        revocation_acc: &AccountInfo,
+        scope: &Pubkey,
+        delegate: &Pubkey,
+    ) -> ProgramResult {
+        let (revocation_key, _) = Revocation::find_address(scope, delegate);
+        if revocation_key != *revocation_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if revocation_acc.owner == program_id && !revocation_acc.data_is_empty() {
+            return Err(TransferError::DelegateRevoked.into())
+        }
+        Ok(())
+    }
+
+    /// Recipient must be registered on the roster of payer, roster entry is a PDA that may be uninitialized
+    #[cfg(feature = "rosters")]
+    fn check_registered(
+        program_id: &Pubkey,
+        roster_entry_acc: &AccountInfo,
+        payer: &Pubkey,
+        recipient: &Pubkey,
+    ) -> ProgramResult {
+        let (roster_entry_key, _) = RosterEntry::find_address(payer, recipient);
+        if roster_entry_key != *roster_entry_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if roster_entry_acc.owner != program_id || roster_entry_acc.data_is_empty() {
+            return Err(TransferError::RecipientNotRegistered.into())
+        }
+        Ok(())
+    }
+
+    /// Program-wide account of `account_type` passed as the last account, e.g. audit log or registry, see
+    /// `TokenInstruction::with_audit_log`, is split off the accounts of the instruction. It is recognized
+    /// by owner and account type, being only ever created at its address
+    fn split_last<'a, 'b>(
+        program_id: &Pubkey,
+        accounts: &'b [AccountInfo<'a>],
+        account_type: AccountType,
+    ) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+        match accounts.split_last() {
+            Some((last_acc, instr_accs)) if Self::is_account_of_type(program_id, last_acc, account_type) => {
+                (instr_accs, Some(last_acc))
+            }
+            _ => (accounts, None),
+        }
+    }
+
+    /// Program owned account of `account_type`, e.g. config of a namespace, which is only ever created at its
+    /// address, so it's recognized without deriving the address
+    fn is_account_of_type(program_id: &Pubkey, acc: &AccountInfo, account_type: AccountType) -> bool {
+        let first_byte = acc.try_borrow_data().ok().and_then(|data| data.first().copied());
+        acc.owner == program_id && first_byte == Some(account_type as u8)
+    }
+
+    /// Config of the namespace passed last, see `TokenInstruction::in_namespace`, None in the default namespace.
+    /// PDAs of the namespace are seeded with it, see `namespaced_seeds`, switches are read from program config only
+    fn split_namespace_config<'a, 'b>(
+        program_id: &Pubkey,
+        accounts: &'b [AccountInfo<'a>],
+    ) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+        match Self::split_last(program_id, accounts, AccountType::Config) {
+            (instr_accs, Some(acc)) if *acc.key != Config::find_address().0 => (instr_accs, Some(acc)),
+            _ => (accounts, None),
+        }
+    }
+
+    /// Signer must be the admin of the namespace config, or of program config in the default namespace,
+    /// which is created on first use, see `load_config_as_admin`
+    fn check_namespace_admin<'a>(
+        program_id: &Pubkey,
+        admin_acc: &AccountInfo<'a>,
+        config_acc: &AccountInfo<'a>,
+        namespace_config_acc: Option<&AccountInfo<'a>>,
+        system_program_acc: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        let namespace_config_acc = match namespace_config_acc {
+            Some(acc) => acc,
+            None => {
+                let config =
+                    Self::load_config_as_admin(program_id, admin_acc, config_acc, system_program_acc, accounts)?;
+                return config.save(config_acc)
+            }
+        };
+        if Config::load(namespace_config_acc, program_id)?.admin != *admin_acc.key {
+            return Err(TransferError::NotConfigAdmin.into())
+        }
+        Ok(())
+    }
+
+    /// Export side of migration when `export`, otherwise import side, for update by its admin
+    #[cfg(any(feature = "vaults", feature = "escrow"))]
+    fn load_migration(
+        program_id: &Pubkey,
+        admin_acc: &AccountInfo,
+        migration_acc: &AccountInfo,
+        export: bool,
+    ) -> Result<Migration, ProgramError> {
+        let (migration_key, _) = if export {
+            Migration::find_export_address()
+        } else {
+            Migration::find_import_address()
+        };
+        if migration_key != *migration_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let migration = Migration::load(migration_acc, program_id)?;
+        if migration.admin != *admin_acc.key {
+            return Err(TransferError::NotMigrationAdmin.into())
+        }
+        Ok(migration)
+    }
+
+    /// State exported by the predecessor of import side `migration`, it is owned by the predecessor and keeps
+    /// its layout with `AccountType::Exported` discriminator
+    #[cfg(any(feature = "vaults", feature = "escrow"))]
+    fn load_exported<T: AccountState>(migration: &Migration, exported_acc: &AccountInfo) -> Result<T, ProgramError> {
+        if *exported_acc.owner != migration.peer {
+            return Err(TransferError::InvalidOwner.into())
+        }
+        let data = exported_acc.try_borrow_data()?;
+        if data.len() != T::LEN || data.first() != Some(&(AccountType::Exported as u8)) {
+            return Err(ProgramError::InvalidAccountData)
+        }
+        Ok(T::deserialize(&mut &data[..])?)
+    }
+
+    /// Reject `recipient` unless program registry and, in a namespace, the registry of the namespace
+    /// permit it, each once initialized
+    fn check_registry(program_id: &Pubkey, policy: &TransferPolicy, recipient: &Pubkey) -> ProgramResult {
+        Self::check_registry_permits(program_id, policy.registry_acc, &Registry::find_address().0, recipient)?;
+        if let Some(namespace_config_acc) = Self::transfer_namespace_config(program_id, policy)? {
+            let (registry_key, _) = Registry::find_namespaced_address(namespace_config_acc.key);
+            Self::check_registry_permits(program_id, policy.namespace_registry_acc, &registry_key, recipient)?;
+        }
+        Ok(())
+    }
+
+    /// Reject `recipient` unless registry at `registry_key` permits it once initialized
+    fn check_registry_permits(
+        program_id: &Pubkey,
+        registry_acc: &AccountInfo,
+        registry_key: &Pubkey,
+        recipient: &Pubkey,
+    ) -> ProgramResult {
+        if *registry_key != *registry_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if registry_acc.owner != program_id || registry_acc.data_is_empty() {
+            return Ok(())
+        }
+        if !Registry::load(registry_acc, program_id)?.permits(recipient) {
+            msg!("Recipient {:?} is not permitted by registry", recipient);
+            return Err(TransferError::RecipientNotPermitted.into())
+        }
+        Ok(())
+    }
+
+    /// Split the transfer policy accounts off the head of `accounts`, the rest are the accounts of the transfer
+    fn split_transfer_policy<'a, 'b>(
+        accounts: &'b [AccountInfo<'a>],
+    ) -> Result<(TransferPolicy<'a, 'b>, &'b [AccountInfo<'a>]), ProgramError> {
+        let acc_iter = &mut accounts.iter();
+        accounts!(acc_iter in accounts => {
+            spending_limit_acc: writable,
+            approval_policy_acc,
+            transfer_hook_acc,
+            hook_program_acc,
+            registry_acc,
+            config_acc,
+            namespace_config_acc,
+            namespace_registry_acc,
+        });
+        let policy = TransferPolicy {
+            spending_limit_acc,
+            approval_policy_acc,
+            transfer_hook_acc,
+            hook_program_acc,
+            registry_acc,
+            config_acc,
+            namespace_config_acc,
+            namespace_registry_acc,
+        };
+        Ok((policy, acc_iter.as_slice()))
+    }
+
+    /// Policies `authority` set for its transfers must permit moving `amount` of `mint` in total, `Pubkey::default()`
+    /// for lamports, returns the hook program to run around each transfer once `authority` set a transfer hook
+    fn check_transfer_policy<'a, 'b>(
+        program_id: &Pubkey,
+        policy: &TransferPolicy<'a, 'b>,
+        authority: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Result<Option<&'b AccountInfo<'a>>, ProgramError> {
+        Self::check_approval_policy(program_id, policy.approval_policy_acc, authority, amount)?;
+        Self::spend_limit(program_id, policy.spending_limit_acc, authority, mint, amount)?;
+        Self::load_transfer_hook(program_id, policy, authority)
+    }
+
+    /// Hook program of the transfer hook `authority` set, which must be passed once initialized,
+    /// see `TokenInstruction::with_transfer_hook`
+    fn load_transfer_hook<'a, 'b>(
+        program_id: &Pubkey,
+        policy: &TransferPolicy<'a, 'b>,
+        authority: &Pubkey,
+    ) -> Result<Option<&'b AccountInfo<'a>>, ProgramError> {
+        if TransferHook::find_address(authority).0 != *policy.transfer_hook_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if policy.transfer_hook_acc.owner != program_id || policy.transfer_hook_acc.data_is_empty() {
+            return Ok(None)
+        }
+        let transfer_hook = ProgramAccount::<TransferHook>::new(policy.transfer_hook_acc, program_id)?;
+        if transfer_hook.program != *policy.hook_program_acc.key {
+            msg!("Transfer hook program={:?} of authority={:?} is not passed", transfer_hook.program, authority);
+            return Err(TransferError::TransferHookRequired.into())
+        }
+        Ok(Some(policy.hook_program_acc))
+    }
+
+    /// Run `transfer` of `amount` between the pre and post transfer hook of `hook_program_acc`, when given
+    fn hooked_transfer<'a>(
+        hook_program_acc: Option<&AccountInfo<'a>>,
+        authority_acc: &AccountInfo<'a>,
+        source_acc: &AccountInfo<'a>,
+        destination_acc: &AccountInfo<'a>,
+        amount: u64,
+        transfer: impl FnOnce() -> ProgramResult,
+    ) -> ProgramResult {
+        if let Some(hook_program_acc) = hook_program_acc {
+            let stage = TransferHookStage::PreTransfer;
+            Self::invoke_transfer_hook(hook_program_acc, authority_acc, source_acc, destination_acc, stage, amount)?;
+        }
+        transfer()?;
+        if let Some(hook_program_acc) = hook_program_acc {
+            let stage = TransferHookStage::PostTransfer;
+            Self::invoke_transfer_hook(hook_program_acc, authority_acc, source_acc, destination_acc, stage, amount)?;
+        }
+        Ok(())
+    }
+
+    /// Count `amount` against the spending limit of `authority` for `mint` once initialized, see `SpendingLimit::spend`
+    fn spend_limit(
+        program_id: &Pubkey,
+        spending_limit_acc: &AccountInfo,
+        authority: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> ProgramResult {
+        if SpendingLimit::find_address(authority, mint).0 != *spending_limit_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if spending_limit_acc.owner != program_id || spending_limit_acc.data_is_empty() {
+            return Ok(())
+        }
+        let mut spending_limit = ProgramAccount::<SpendingLimit>::new(spending_limit_acc, program_id)?;
+        let now = Clock::get()?.unix_timestamp;
+        if !spending_limit.spend(amount, now) {
+            msg!("Spending limit of {:?} exceeded, spent={}", authority, spending_limit.spent(now));
+            return Err(TransferError::SpendingLimitExceeded.into())
+        }
+        spending_limit.save()
+    }
+
+    /// Reject `amount` above the threshold of the approval policy of `authority` once initialized
+    fn check_approval_policy(
+        program_id: &Pubkey,
+        approval_policy_acc: &AccountInfo,
+        authority: &Pubkey,
+        amount: u64,
+    ) -> ProgramResult {
+        if ApprovalPolicy::find_address(authority).0 != *approval_policy_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if approval_policy_acc.owner != program_id || approval_policy_acc.data_is_empty() {
+            return Ok(())
+        }
+        let approval_policy = ApprovalPolicy::load(approval_policy_acc, program_id)?;
+        if approval_policy.requires_approval(amount) {
+            msg!("Transfer of {:?} above threshold={} requires approval", authority, approval_policy.threshold);
+            return Err(TransferError::ApprovalRequired.into())
+        }
+        Ok(())
+    }
+
+    /// Fold successful instruction into the audit log, see `AuditLog::fold`
+    fn fold_audit_log(
+        program_id: &Pubkey,
+        audit_log_acc: &AccountInfo,
+        tag: u8,
+        accounts: &[AccountInfo],
+        amount: Option<u64>,
+    ) -> ProgramResult {
+        WritableAccount::new(audit_log_acc)?;
+        let mut audit_log = AuditLog::load(audit_log_acc, program_id)?;
+        let keys: Vec<Pubkey> = accounts.iter().map(|acc| *acc.key).collect();
+        audit_log.fold(tag, &keys, amount.unwrap_or_default(), Clock::get()?.slot);
+        audit_log.save(audit_log_acc)?;
+        msg!("Audit log count={}", audit_log.count);
+        Ok(())
+    }
+
+    /// Address of the event stream the instruction carries and sequence number for the next event within it,
+    /// counted by the stream, None without one, see `TokenInstruction::with_event_stream`
+    fn next_event_sequence(
+        program_id: &Pubkey,
+        event_stream_acc: Option<&AccountInfo>,
+    ) -> Result<Option<(Pubkey, u64)>, ProgramError> {
+        let event_stream_acc = match event_stream_acc {
+            Some(acc) => acc,
+            None => return Ok(None),
+        };
+        WritableAccount::new(event_stream_acc)?;
+        let mut event_stream = ProgramAccount::<EventStream>::new(event_stream_acc, program_id)?;
+        let sequence = event_stream.next_sequence().ok_or(TransferError::AmountOverflow)?;
+        event_stream.save()?;
+        Ok(Some((*event_stream_acc.key, sequence)))
+    }
+
+    /// Instruction family `feature` must be enabled in config, see `Config::DEFAULT_FEATURES`
+    fn check_feature(program_id: &Pubkey, config_acc: &AccountInfo, feature: u64) -> ProgramResult {
+        let features = Self::load_config(program_id, config_acc)?
+            .map_or(Config::DEFAULT_FEATURES, |config| config.features);
+        if features & feature == 0 {
+            return Err(TransferError::FeatureDisabled.into())
+        }
+        Ok(())
+    }
+
+    /// Transfers and other instructions moving value are rejected while program config is paused
+    fn check_not_paused(program_id: &Pubkey, config_acc: &AccountInfo) -> ProgramResult {
+        if Config::find_address().0 != *config_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if matches!(Self::load_config(program_id, config_acc)?, Some(Config { paused: true, .. })) {
+            return Err(TransferError::ProgramPaused.into())
+        }
+        Ok(())
+    }
+
+    /// Transfer is rejected while program config or config of the namespace it runs in is paused
+    fn check_transfer_not_paused(program_id: &Pubkey, policy: &TransferPolicy) -> ProgramResult {
+        Self::check_not_paused(program_id, policy.config_acc)?;
+        if let Some(namespace_config_acc) = Self::transfer_namespace_config(program_id, policy)? {
+            if Config::load(namespace_config_acc, program_id)?.paused {
+                return Err(TransferError::ProgramPaused.into())
+            }
+        }
+        Ok(())
+    }
+
+    /// Config of the namespace the transfer runs in, None in the default namespace where program config is passed
+    /// in its place, see `TokenInstruction::transfer_policy_accounts`
+    fn transfer_namespace_config<'a, 'b>(
+        program_id: &Pubkey,
+        policy: &TransferPolicy<'a, 'b>,
+    ) -> Result<Option<&'b AccountInfo<'a>>, ProgramError> {
+        if policy.namespace_config_acc.key == policy.config_acc.key {
+            return Ok(None)
+        }
+        if !Self::is_account_of_type(program_id, policy.namespace_config_acc, AccountType::Config) {
+            return Err(ProgramError::InvalidAccountData)
+        }
+        Ok(Some(policy.namespace_config_acc))
+    }
+
+    /// Batch carries an amount for each remaining account, checked before any transfer is made
+    fn check_batch_recipients(amounts: &[u64], recipients: usize) -> ProgramResult {
+        if recipients < amounts.len() {
+            return Err(ProgramError::NotEnoughAccountKeys)
+        }
+        if amounts.is_empty() || recipients > amounts.len() {
+            return Err(ProgramError::InvalidArgument)
+        }
+        Ok(())
+    }
+
+    /// Decimals of `mint_acc`, read from its `MintDecimals` cache when given instead of unpacking the mint
+    fn mint_decimals(
+        program_id: &Pubkey,
+        mint_acc: &AccountInfo,
+        mint_decimals_acc: Option<&AccountInfo>,
+    ) -> Result<u8, ProgramError> {
+        let mint_decimals_acc = match mint_decimals_acc {
+            Some(mint_decimals_acc) => mint_decimals_acc,
+            None => return Ok(MintAccount::new(mint_acc)?.decimals),
+        };
+        let mint_decimals = MintDecimals::load(mint_decimals_acc, program_id)?;
+        if mint_decimals.mint != *mint_acc.key || mint_decimals.token_program != *mint_acc.owner {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        Ok(mint_decimals.decimals)
+    }
+
+    /// Soulbound mints registered in mint config can't be transferred through this program
+    fn check_transferable(program_id: &Pubkey, mint_config_acc: &AccountInfo, mint: &Pubkey) -> ProgramResult {
+        let (mint_config_key, _) = MintConfig::find_address(mint);
+        if mint_config_key != *mint_config_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if mint_config_acc.data_is_empty() {
+            return Ok(())
+        }
+        if MintConfig::load(mint_config_acc, program_id)?.soulbound {
+            return Err(TransferError::SoulboundMint.into())
+        }
+        Ok(())
+    }
+
+    /// NFT mint has supply of a single indivisible token
+    #[cfg(feature = "nft")]
+    fn check_nft(mint_acc: &AccountInfo) -> ProgramResult {
+        let mint = MintAccount::new(mint_acc)?;
+        if mint.supply != 1 || mint.decimals != 0 {
+            return Err(TransferError::NotNft.into())
+        }
+        Ok(())
+    }
+
+    /// With travel rule required, transfers above the threshold must carry hash of travel rule data
+    fn check_travel_rule(
+        program_id: &Pubkey,
+        config_acc: &AccountInfo,
+        amount: u64,
+        data_hash: Option<&[u8; 32]>,
+    ) -> ProgramResult {
+        match Self::load_config(program_id, config_acc)? {
+            Some(config) if config.travel_rule_required && amount > config.travel_rule_threshold => {}
+            _ => return Ok(()),
+        }
+        if data_hash.is_none() {
+            return Err(TransferError::TravelRuleDataRequired.into())
+        }
+        Ok(())
+    }
+
+    /// In strict mode accept only associated token account of its owner and mint
+    fn check_strict_ata(program_id: &Pubkey, config_acc: &AccountInfo, token_acc: &AccountInfo) -> ProgramResult {
+        match Self::load_config(program_id, config_acc)? {
+            Some(config) if config.strict_ata => {}
+            _ => return Ok(()),
+        }
+        let token_account = TokenAccount::new(token_acc)?;
+        if get_associated_token_address(&token_account.owner, &token_account.mint) != *token_account.key() {
+            return Err(TransferError::DestinationNotAssociated.into())
+        }
+        Ok(())
+    }
+
+    /// Token account of `mint` is accepted when it is the wallet associated token account
+    /// or any auxiliary token account whose owner is the wallet
+    fn check_wallet_token_account(
+        wallet_acc: &AccountInfo,
+        mint_acc: &AccountInfo,
+        token_acc: &AccountInfo,
+    ) -> ProgramResult {
+        let token_account = TokenAccount::new(token_acc)?;
+        let associated_token_key = get_associated_token_address(wallet_acc.key, mint_acc.key);
+        if associated_token_key != *token_acc.key && token_account.owner != *wallet_acc.key {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+        Ok(())
+    }
+
+    /// Move escrowed NFT back to owner token account, close escrow and rental to owner
+    #[cfg(feature = "nft")]
+    fn close_rental<'a>(
+        program_id: &Pubkey,
+        rental: &Rental,
+        owner_acc: &AccountInfo<'a>,
+        owner_token_acc: &AccountInfo<'a>,
+        rental_acc: &AccountInfo<'a>,
+        escrow_token_acc: &AccountInfo<'a>,
+        spl_token_acc: &AccountInfo<'a>,
+    ) -> Result<RentalReturnedEvent, ProgramError> {
+        if rental.owner != *owner_acc.key {
+            return Err(TransferError::AccountMismatch.into())
+        }
+        let owner_token = TokenAccount::new(owner_token_acc)?;
+        if owner_token.owner != rental.owner || owner_token.mint != rental.mint {
+            return Err(TransferError::TokenAccountOwnerMismatch.into())
+        }
+        if get_associated_token_address(rental_acc.key, &rental.mint) != *escrow_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let seeds: &[&[u8]] = &[Rental::SEED, rental.mint.as_ref(), &[rental.bump]];
+        let amount = TokenAccount::new(escrow_token_acc)?.amount;
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            escrow_token_acc.key,
+            owner_token_acc.key,
+            rental_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke_signed(
+            &transfer_instr,
+            &[escrow_token_acc.clone(), owner_token_acc.clone(), rental_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            escrow_token_acc.key,
+            owner_acc.key,
+            rental_acc.key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_instr,
+            &[escrow_token_acc.clone(), owner_acc.clone(), rental_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        Self::close_program_account(program_id, rental_acc, owner_acc)?;
+
+        Ok(RentalReturnedEvent { rental: *rental_acc.key, owner: rental.owner, renter: rental.renter })
+    }
+
+    /// Refund unfilled rest of order to maker, close order escrow and order to maker,
+    /// returns event for a refund to emit
+    #[cfg(feature = "orders")]
+    fn refund_order<'a>(
+        program_id: &Pubkey,
+        order: &Order,
+        maker_acc: &AccountInfo<'a>,
+        maker_sell_token_acc: &AccountInfo<'a>,
+        order_acc: &AccountInfo<'a>,
+        escrow_token_acc: &AccountInfo<'a>,
+        spl_token_acc: &AccountInfo<'a>,
+    ) -> Result<Option<OrderCancelledEvent>, ProgramError> {
+        let seeds: &[&[u8]] = &[Order::SEED, order.maker.as_ref(), &order.id.to_le_bytes(), &[order.bump]];
+        if order.remaining > 0 {
+            let refund_instr = spl_token::instruction::transfer(
+                spl_token_acc.key,
+                escrow_token_acc.key,
+                maker_sell_token_acc.key,
+                order_acc.key,
+                &[],
+                order.remaining,
+            )?;
+            invoke_signed(
+                &refund_instr,
+                &[escrow_token_acc.clone(), maker_sell_token_acc.clone(), order_acc.clone(), spl_token_acc.clone()],
+                &[seeds],
+            )?;
+        }
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            escrow_token_acc.key,
+            maker_acc.key,
+            order_acc.key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_instr,
+            &[escrow_token_acc.clone(), maker_acc.clone(), order_acc.clone(), spl_token_acc.clone()],
+            &[seeds],
+        )?;
+        Self::close_program_account(program_id, order_acc, maker_acc)?;
+
+        let refunded = order.remaining;
+        Ok((refunded > 0).then_some(OrderCancelledEvent { order: *order_acc.key, maker: order.maker, refunded }))
+    }
+
+    /// Close emptied temp token account and escrow, rent of both returns to initializer
+    #[cfg(feature = "escrow")]
+    fn close_escrow<'a>(
+        program_id: &Pubkey,
+        escrow: &Escrow,
+        initializer_acc: &AccountInfo<'a>,
+        temp_token_acc: &AccountInfo<'a>,
+        escrow_acc: &AccountInfo<'a>,
+        spl_token_acc: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let close_instr = spl_token::instruction::close_account(
+            spl_token_acc.key,
+            temp_token_acc.key,
+            initializer_acc.key,
+            escrow_acc.key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_instr,
+            &[temp_token_acc.clone(), initializer_acc.clone(), escrow_acc.clone(), spl_token_acc.clone()],
+            &[&[Escrow::SEED, escrow.temp_token.as_ref(), &[escrow.bump]]],
+        )?;
+        Self::close_program_account(program_id, escrow_acc, initializer_acc)
+    }
+
+    /// Transfer `amount` out of associated token account of vesting schedule to token account of its mint,
+    /// signed by the schedule PDA
+    #[cfg(feature = "vesting")]
+    fn release_vesting<'a>(
+        vesting: &VestingSchedule,
+        vesting_acc: &AccountInfo<'a>,
+        vesting_token_acc: &AccountInfo<'a>,
+        to_spl_token_acc: &AccountInfo<'a>,
+        spl_token_acc: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        if get_associated_token_address(vesting_acc.key, &vesting.mint) != *vesting_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(to_spl_token_acc)?.mint != vesting.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            vesting_token_acc.key,
+            to_spl_token_acc.key,
+            vesting_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke_signed(
+            &transfer_instr,
+            &[vesting_token_acc.clone(), to_spl_token_acc.clone(), vesting_acc.clone(), spl_token_acc.clone()],
+            &[&Self::vesting_seeds(vesting)],
+        )
+    }
+
+    #[cfg(feature = "vesting")]
+    fn vesting_seeds(vesting: &VestingSchedule) -> [&[u8]; 5] {
+        [
+            VestingSchedule::SEED,
+            vesting.creator.as_ref(),
+            vesting.beneficiary.as_ref(),
+            vesting.mint.as_ref(),
+            std::slice::from_ref(&vesting.bump),
+        ]
+    }
+
+    /// Stream token account and SPL token program of custom token stream, see `TokenInstruction::with_stream_token`.
+    /// Nothing is consumed for lamport streams
+    #[cfg(feature = "streams")]
+    fn load_stream_token<'a, 'b>(
+        stream: &Stream,
+        stream_acc: &AccountInfo<'a>,
+        acc_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    ) -> Result<Option<StreamTokenAccounts<'a, 'b>>, ProgramError> {
+        if stream.is_native() {
+            return Ok(None)
+        }
+        accounts!(acc_iter => {
+            mint_acc,
+            stream_token_acc: writable,
+            spl_token_acc: token_program,
+        });
+        if *mint_acc.key != stream.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+        if get_associated_token_address(stream_acc.key, &stream.mint) != *stream_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        Ok(Some((stream_token_acc, spl_token_acc)))
+    }
+
+    /// Pay `amount` out of stream to `to_acc`, its token account of stream mint for custom token streams
+    /// transferred signed by the stream PDA
+    #[cfg(feature = "streams")]
+    fn pay_from_stream<'a>(
+        program_id: &Pubkey,
+        stream: &Stream,
+        stream_acc: &AccountInfo<'a>,
+        to_acc: &AccountInfo<'a>,
+        token_accs: Option<StreamTokenAccounts<'a, '_>>,
+        amount: u64,
+    ) -> ProgramResult {
+        if amount == 0 {
+            return Ok(())
+        }
+        let (stream_token_acc, spl_token_acc) = match token_accs {
+            Some(token_accs) => token_accs,
+            None => return Self::move_lamports(program_id, stream_acc, to_acc, amount),
+        };
+        if TokenAccount::new(to_acc)?.mint != stream.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            stream_token_acc.key,
+            to_acc.key,
+            stream_acc.key,
+            &[],
+            amount,
+        )?;
+        invoke_signed(
+            &transfer_instr,
+            &[stream_token_acc.clone(), to_acc.clone(), stream_acc.clone(), spl_token_acc.clone()],
+            &[&Self::stream_seeds(stream)],
+        )
+    }
+
+    #[cfg(feature = "streams")]
+    fn stream_seeds(stream: &Stream) -> [&[u8]; 4] {
+        [Stream::SEED, stream.sender.as_ref(), stream.recipient.as_ref(), std::slice::from_ref(&stream.bump)]
+    }
+
+    #[cfg(feature = "contracts")]
+    fn release_contract_tokens<'a>(
+        contract: &MilestoneContract,
+        contract_acc: &AccountInfo<'a>,
+        contract_token_acc: &AccountInfo<'a>,
+        to_spl_token_acc: &AccountInfo<'a>,
+        spl_token_acc: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        if get_associated_token_address(contract_acc.key, &contract.mint) != *contract_token_acc.key {
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if TokenAccount::new(to_spl_token_acc)?.mint != contract.mint {
+            return Err(TransferError::MintMismatch.into())
+        }
+        let transfer_instr = spl_token::instruction::transfer(
+            spl_token_acc.key,
+            contract_token_acc.key,
+            to_spl_token_acc.key,
+            contract_acc.key,
+            &[],
+            amount,
+        )?;
+        let id = contract.id.to_le_bytes();
+        invoke_signed(
+            &transfer_instr,
+            &[contract_token_acc.clone(), to_spl_token_acc.clone(), contract_acc.clone(), spl_token_acc.clone()],
+            &[&Self::contract_seeds(contract, &id)],
+        )
+    }
+
+    /// `id` is the little endian id of the contract
+    #[cfg(feature = "contracts")]
+    fn contract_seeds<'a>(contract: &'a MilestoneContract, id: &'a [u8; 8]) -> [&'a [u8]; 5] {
+        [
+            MilestoneContract::SEED,
+            contract.funder.as_ref(),
+            contract.payee.as_ref(),
+            id,
+            std::slice::from_ref(&contract.bump),
+        ]
+    }
+
+    /// Create account owned by this program at PDA address signed by `seeds`
+    fn create_program_account<'a>(
+        program_id: &Pubkey,
+        payer_acc: &AccountInfo<'a>,
+        new_acc: &AccountInfo<'a>,
+        system_program_acc: &AccountInfo<'a>,
+        space: usize,
+        lamports: u64,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        let create_instr = system_instruction::create_account(
+            payer_acc.key,
+            new_acc.key,
+            lamports,
+            space as u64,
+            program_id,
+        );
+        invoke_signed(
+            &create_instr,
+            &[payer_acc.clone(), new_acc.clone(), system_program_acc.clone()],
+            &[seeds],
+        )
+    }
+
+    /// Move lamports out of program owned account without System program, see `lamports::transfer`
+    fn move_lamports(program_id: &Pubkey, from_acc: &AccountInfo, to_acc: &AccountInfo, amount: u64) -> ProgramResult {
+        lamports::transfer(program_id, from_acc, to_acc, amount, &Rent::get()?)
+    }
+
+    /// Debit program owned `from_acc` directly, any other is debited through System program transfer
+    fn pay_lamports<'a>(
+        program_id: &Pubkey,
+        from_acc: &AccountInfo<'a>,
+        to_acc: &AccountInfo<'a>,
+        system_program_acc: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        if from_acc.owner == program_id {
+            return Self::move_lamports(program_id, from_acc, to_acc, amount)
+        }
+        if from_acc.lamports() < amount {
+            // System program fails with its own custom code, indistinguishable from ours
+            let (expected, actual) = (ErrorValue::Amount(amount), ErrorValue::Amount(from_acc.lamports()));
+            return Err(ErrorContextEvent::fail(TransferError::InsufficientFunds, None, Some(expected), Some(actual)))
+        }
+        let transfer_instr = system_instruction::transfer(from_acc.key, to_acc.key, amount);
+        invoke(&transfer_instr, &[from_acc.clone(), to_acc.clone(), system_program_acc.clone()])
+    }
+
+    /// Drain program owned account to `to_acc` and wipe its data
+    fn close_program_account(program_id: &Pubkey, acc: &AccountInfo, to_acc: &AccountInfo) -> ProgramResult {
+        lamports::close(program_id, acc, to_acc)
+    }
+}
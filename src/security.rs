@@ -0,0 +1,11 @@
+//! Security contact metadata embedded into the `.security.txt` section of the program binary,
+//! so scanners and auditors find disclosure channels from the deployed program.
+use solana_security_txt::security_txt;
+
+security_txt! {
+    name: "Solana token",
+    project_url: "https://github.com/EGORAGANIN/solana-token",
+    contacts: "email:aganin.ev@gmail.com",
+    policy: "https://github.com/EGORAGANIN/solana-token/blob/master/SECURITY.md",
+    source_code: "https://github.com/EGORAGANIN/solana-token"
+}
@@ -0,0 +1,54 @@
+//! Division of an amount among recipients, equally or by weights, for "split the bill" flows.
+//!
+//! Every share is rounded down, the indivisible remainder is returned separately so it goes to a
+//! designated account instead of leaking as dust.
+use solana_program::program_error::ProgramError;
+
+/// Share of each of `recipients` and the remainder. Empty `weights` split equally, otherwise
+/// there is a weight for each recipient
+pub fn shares(amount: u64, weights: &[u32], recipients: usize) -> Result<(Vec<u64>, u64), ProgramError> {
+    if recipients < weights.len() {
+        return Err(ProgramError::NotEnoughAccountKeys)
+    }
+    if recipients == 0 || (!weights.is_empty() && recipients > weights.len()) {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let shares: Vec<u64> = if weights.is_empty() {
+        vec![amount / recipients as u64; recipients]
+    } else {
+        let total_weight: u128 = weights.iter().map(|weight| *weight as u128).sum();
+        if total_weight == 0 {
+            return Err(ProgramError::InvalidArgument)
+        }
+        weights.iter().map(|weight| (amount as u128 * *weight as u128 / total_weight) as u64).collect()
+    };
+    let remainder = amount - shares.iter().sum::<u64>();
+    Ok((shares, remainder))
+}
+
+#[cfg(test)]
+mod split_test {
+    use solana_program::program_error::ProgramError;
+    use crate::split::shares;
+
+    #[test]
+    fn when_equal_split_expect_remainder() {
+        assert_eq!(shares(100, &[], 3), Ok((vec![33, 33, 33], 1)));
+        assert_eq!(shares(2, &[], 3), Ok((vec![0, 0, 0], 2)));
+    }
+
+    #[test]
+    fn when_weighted_split_expect_shares_by_weight() {
+        assert_eq!(shares(1_000, &[1, 2, 3], 3), Ok((vec![166, 333, 500], 1)));
+        assert_eq!(shares(u64::MAX, &[u32::MAX, u32::MAX], 2), Ok((vec![u64::MAX / 2, u64::MAX / 2], 1)));
+    }
+
+    #[test]
+    fn when_recipients_mismatch_weights_expect_error() {
+        assert_eq!(shares(100, &[1, 1], 1), Err(ProgramError::NotEnoughAccountKeys));
+        assert_eq!(shares(100, &[1, 1], 3), Err(ProgramError::InvalidArgument));
+        assert_eq!(shares(100, &[], 0), Err(ProgramError::InvalidArgument));
+        assert_eq!(shares(100, &[0, 0], 2), Err(ProgramError::InvalidArgument));
+    }
+}
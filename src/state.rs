@@ -0,0 +1,2782 @@
+use std::convert::TryFrom;
+use borsh::BorshSerialize;
+use borsh::BorshDeserialize;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::hash::hashv;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use crate::error::TransferError;
+
+/// Discriminator stored in the first byte of every program owned account
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy)]
+pub enum AccountType {
+    Uninitialized,
+    Bond,
+    TipStats,
+    TipperStats,
+    MatchingPool,
+    SavingsVault,
+    Netting,
+    TransferHook,
+    Config,
+    PayoutBatch,
+    Distributor,
+    DistributionClaim,
+    Attestation,
+    MintConfig,
+    Rental,
+    Fraction,
+    Order,
+    PriceFeed,
+    Keeper,
+    Vault,
+    Escrow,
+    FeeConfig,
+    VestingSchedule,
+    SponsorPool,
+    SponsoredWallet,
+    Stream,
+    ClaimableBalance,
+    Revocation,
+    RosterEntry,
+    MilestoneContract,
+    AuditLog,
+    MintDecimals,
+    Registry,
+    Migration,
+    /// Vault or escrow moved to a successor deployment, its data is kept frozen for the successor to import
+    Exported,
+    SpendingLimit,
+    ApprovalPolicy,
+    PendingTransfer,
+    MerkleDistribution,
+    ClaimBitmap,
+    DepositScheme,
+    ManagedMint,
+    Issuer,
+    Dividend,
+    EventStream,
+}
+
+/// Seeds of a PDA with `seed` and `keys` within the namespace of `namespace_config`, see
+/// `Config::find_namespaced_address`. The default namespace, None, keeps the seeds PDAs had before namespaces
+pub fn namespaced_seeds<'a>(
+    seed: &'a [u8],
+    namespace_config: Option<&'a Pubkey>,
+    keys: &[&'a [u8]],
+) -> Vec<&'a [u8]> {
+    let mut seeds = vec![seed];
+    seeds.extend(namespace_config.map(Pubkey::as_ref));
+    seeds.extend_from_slice(keys);
+    seeds
+}
+
+/// Program owned account with fixed size Borsh layout
+pub trait AccountState: BorshSerialize + BorshDeserialize {
+    const ACCOUNT_TYPE: AccountType;
+    const LEN: usize;
+
+    /// Deserialize account data, checking owner and discriminator
+    fn load(acc: &AccountInfo, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        if acc.owner != program_id {
+            return Err(TransferError::InvalidOwner.into())
+        }
+        let data = acc.try_borrow_data()?;
+        if data.first() != Some(&(Self::ACCOUNT_TYPE as u8)) {
+            return Err(ProgramError::InvalidAccountData)
+        }
+        Ok(Self::deserialize(&mut &data[..])?)
+    }
+
+    fn save(&self, acc: &AccountInfo) -> ProgramResult {
+        self.serialize(&mut &mut acc.try_borrow_mut_data()?[..])?;
+        Ok(())
+    }
+}
+
+/// Refundable deposit, lamports are held by the bond account itself
+/// until the arbiter refunds or slashes them
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Bond {
+    pub account_type: AccountType,
+    pub depositor: Pubkey,
+    pub arbiter: Pubkey,
+    pub beneficiary: Pubkey,
+    pub id: u64,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl Bond {
+    pub const SEED: &'static [u8] = b"bond";
+
+    pub fn find_address(depositor: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, depositor.as_ref(), &id.to_le_bytes()], &crate::id())
+    }
+}
+
+impl AccountState for Bond {
+    const ACCOUNT_TYPE: AccountType = AccountType::Bond;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Per-creator tip totals with the leading tipper
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct TipStats {
+    pub account_type: AccountType,
+    pub creator: Pubkey,
+    pub total: u64,
+    pub count: u64,
+    pub top_tipper: Pubkey,
+    pub top_tipper_total: u64,
+    pub bump: u8,
+}
+
+impl TipStats {
+    pub const SEED: &'static [u8] = b"tip_stats";
+
+    pub fn find_address(creator: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, creator.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for TipStats {
+    const ACCOUNT_TYPE: AccountType = AccountType::TipStats;
+    const LEN: usize = 1 + 32 + 8 + 8 + 32 + 8 + 1;
+}
+
+/// Cumulative tips of one tipper to one creator, leaderboard entry
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct TipperStats {
+    pub account_type: AccountType,
+    pub creator: Pubkey,
+    pub tipper: Pubkey,
+    pub total: u64,
+    pub count: u64,
+    pub bump: u8,
+}
+
+impl TipperStats {
+    pub const SEED: &'static [u8] = b"tipper_stats";
+
+    pub fn find_address(creator: &Pubkey, tipper: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, creator.as_ref(), tipper.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for TipperStats {
+    const ACCOUNT_TYPE: AccountType = AccountType::TipperStats;
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Sponsor funded pool matching donations to a recipient, lamports held by the pool account
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MatchingPool {
+    pub account_type: AccountType,
+    pub sponsor: Pubkey,
+    pub recipient: Pubkey,
+    /// Matched lamports per donated lamport, 10_000 is 1:1
+    pub ratio_bps: u16,
+    /// Max matched amount per donation
+    pub cap: u64,
+    pub remaining: u64,
+    pub matched_total: u64,
+    pub bump: u8,
+}
+
+impl MatchingPool {
+    pub const SEED: &'static [u8] = b"matching_pool";
+    pub const RATIO_BPS_DENOMINATOR: u64 = 10_000;
+
+    pub fn find_address(sponsor: &Pubkey, recipient: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, sponsor.as_ref(), recipient.as_ref()], &crate::id())
+    }
+
+    /// Amount matched for donation, bounded by cap and what is left in the pool
+    pub fn match_amount(&self, donation: u64) -> u64 {
+        let by_ratio = donation as u128 * self.ratio_bps as u128 / Self::RATIO_BPS_DENOMINATOR as u128;
+        by_ratio.min(self.cap as u128).min(self.remaining as u128) as u64
+    }
+}
+
+impl AccountState for MatchingPool {
+    const ACCOUNT_TYPE: AccountType = AccountType::MatchingPool;
+    const LEN: usize = 1 + 32 + 32 + 2 + 8 + 8 + 8 + 1;
+}
+
+/// Per-owner opt-in round-up configuration, the account itself holds saved lamports
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SavingsVault {
+    pub account_type: AccountType,
+    pub owner: Pubkey,
+    /// Debited amounts are rounded up to a multiple of granularity, 0 disables round-up
+    pub granularity: u64,
+    pub saved: u64,
+    pub bump: u8,
+}
+
+impl SavingsVault {
+    pub const SEED: &'static [u8] = b"savings_vault";
+
+    pub fn find_address(owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, owner.as_ref()], &crate::id())
+    }
+
+    /// Difference between amount rounded up to granularity and amount itself
+    pub fn round_up_difference(&self, amount: u64) -> Option<u64> {
+        if self.granularity == 0 {
+            return Some(0)
+        }
+        let remainder = amount % self.granularity;
+        if remainder == 0 {
+            return Some(0)
+        }
+        let difference = self.granularity - remainder;
+        amount.checked_add(difference).map(|_| difference)
+    }
+}
+
+impl AccountState for SavingsVault {
+    const ACCOUNT_TYPE: AccountType = AccountType::SavingsVault;
+    const LEN: usize = 1 + 32 + 8 + 8 + 1;
+}
+
+/// Mutual obligations of two parties collected over a period and settled by one net transfer
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Netting {
+    pub account_type: AccountType,
+    pub party_a: Pubkey,
+    pub party_b: Pubkey,
+    pub id: u64,
+    /// Unix timestamp, obligations are accepted before and settlement after it
+    pub period_end: i64,
+    pub a_owes_b: u64,
+    pub b_owes_a: u64,
+    pub obligation_count: u32,
+    pub bump: u8,
+}
+
+impl Netting {
+    pub const SEED: &'static [u8] = b"netting";
+
+    pub fn find_address(party_a: &Pubkey, party_b: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEED, party_a.as_ref(), party_b.as_ref(), &id.to_le_bytes()],
+            &crate::id(),
+        )
+    }
+
+    /// Counterparty of `party` or None if `party` is not part of the netting
+    pub fn counterparty(&self, party: &Pubkey) -> Option<Pubkey> {
+        if *party == self.party_a {
+            Some(self.party_b)
+        } else if *party == self.party_b {
+            Some(self.party_a)
+        } else {
+            None
+        }
+    }
+
+    /// Net debtor, net creditor and net amount
+    pub fn net(&self) -> (Pubkey, Pubkey, u64) {
+        if self.a_owes_b >= self.b_owes_a {
+            (self.party_a, self.party_b, self.a_owes_b - self.b_owes_a)
+        } else {
+            (self.party_b, self.party_a, self.b_owes_a - self.a_owes_b)
+        }
+    }
+}
+
+impl AccountState for Netting {
+    const ACCOUNT_TYPE: AccountType = AccountType::Netting;
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 + 8 + 4 + 1;
+}
+
+/// Hook program called before and after transfers of the owner, see `hook_interface`
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct TransferHook {
+    pub account_type: AccountType,
+    pub owner: Pubkey,
+    pub program: Pubkey,
+    pub bump: u8,
+}
+
+impl TransferHook {
+    pub const SEED: &'static [u8] = b"transfer_hook";
+
+    pub fn find_address(owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, owner.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for TransferHook {
+    const ACCOUNT_TYPE: AccountType = AccountType::TransferHook;
+    const LEN: usize = 1 + 32 + 32 + 1;
+}
+
+/// Program wide settings, managed by admin
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Config {
+    pub account_type: AccountType,
+    pub admin: Pubkey,
+    /// Only associated token accounts are accepted as SPL token transfer destinations
+    pub strict_ata: bool,
+    /// Enabled instruction families, `FEATURE_*` bits
+    pub features: u64,
+    /// Custom token transfers above the threshold must carry hash of off-chain travel rule data
+    pub travel_rule_required: bool,
+    pub travel_rule_threshold: u64,
+    /// Emergency halt of transfer instructions, see `TokenInstruction::is_transfer`, and of every other instruction
+    /// moving value out of or into program accounts
+    pub paused: bool,
+    /// Receiving programs of `TokenInstruction::DepositToProgram`, unused slots are the default key
+    pub deposit_programs: [Pubkey; 4],
+    pub bump: u8,
+}
+
+impl Config {
+    pub const SEED: &'static [u8] = b"config";
+
+    pub const FEATURE_BONDS: u64 = 1 << 0;
+    pub const FEATURE_TIPS: u64 = 1 << 1;
+    pub const FEATURE_MATCHING_POOLS: u64 = 1 << 2;
+    pub const FEATURE_ROUND_UP: u64 = 1 << 3;
+    pub const FEATURE_NETTING: u64 = 1 << 4;
+    pub const FEATURE_DEPOSITS: u64 = 1 << 5;
+    pub const FEATURE_TRANSFER_HOOKS: u64 = 1 << 6;
+    pub const FEATURE_PAYOUT_COMMITMENTS: u64 = 1 << 7;
+    pub const FEATURE_GATED_CLAIMS: u64 = 1 << 8;
+    pub const FEATURE_ATTESTATIONS: u64 = 1 << 9;
+    pub const FEATURE_MINT_CONFIGS: u64 = 1 << 10;
+    pub const FEATURE_RENTALS: u64 = 1 << 11;
+    pub const FEATURE_FRACTIONS: u64 = 1 << 12;
+    pub const FEATURE_ORDERS: u64 = 1 << 13;
+    pub const FEATURE_PRICE_FEEDS: u64 = 1 << 14;
+    pub const FEATURE_KEEPERS: u64 = 1 << 15;
+    pub const FEATURE_VAULTS: u64 = 1 << 16;
+    pub const FEATURE_ESCROWS: u64 = 1 << 17;
+    pub const FEATURE_VESTING: u64 = 1 << 18;
+    pub const FEATURE_SPONSORSHIP: u64 = 1 << 19;
+    pub const FEATURE_STREAMS: u64 = 1 << 20;
+    pub const FEATURE_CLAIMABLE_BALANCES: u64 = 1 << 21;
+    pub const FEATURE_RECIPIENT_ROSTERS: u64 = 1 << 22;
+    pub const FEATURE_MILESTONE_CONTRACTS: u64 = 1 << 23;
+    pub const FEATURE_MERKLE_DISTRIBUTIONS: u64 = 1 << 24;
+    pub const FEATURE_ISSUANCE: u64 = 1 << 25;
+    pub const FEATURE_DIVIDENDS: u64 = 1 << 26;
+
+    /// Features enabled while config is not set, new families ship disabled
+    pub const DEFAULT_FEATURES: u64 = Self::FEATURE_BONDS
+        | Self::FEATURE_TIPS
+        | Self::FEATURE_MATCHING_POOLS
+        | Self::FEATURE_ROUND_UP
+        | Self::FEATURE_NETTING
+        | Self::FEATURE_DEPOSITS
+        | Self::FEATURE_TRANSFER_HOOKS;
+
+    pub fn find_address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], &crate::id())
+    }
+
+    /// Config of `namespace`, an isolated configuration with its own admin on the same deployment, see
+    /// `TokenInstruction::InitializeNamespace`. PDAs of the namespace are seeded with this address
+    pub fn find_namespaced_address(namespace: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, namespace.as_ref()], &crate::id())
+    }
+
+    pub fn is_deposit_program(&self, program: &Pubkey) -> bool {
+        *program != Pubkey::default() && self.deposit_programs.contains(program)
+    }
+
+    /// Add deposit `program` unless it is there already, false when all slots are taken
+    pub fn add_deposit_program(&mut self, program: Pubkey) -> bool {
+        if self.is_deposit_program(&program) {
+            return true
+        }
+        match self.deposit_programs.iter_mut().find(|slot| **slot == Pubkey::default()) {
+            Some(slot) => {
+                *slot = program;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_deposit_program(&mut self, program: &Pubkey) {
+        for slot in self.deposit_programs.iter_mut().filter(|slot| *slot == program) {
+            *slot = Pubkey::default();
+        }
+    }
+}
+
+impl AccountState for Config {
+    const ACCOUNT_TYPE: AccountType = AccountType::Config;
+    const LEN: usize = 1 + 32 + 1 + 8 + 1 + 8 + 1 + 4 * 32 + 1;
+}
+
+/// Off-chain payout list committed by its Merkle root before execution, see `merkle`.
+/// Each leaf is paid at most once, executed leaves are tracked in a bitmap.
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct PayoutBatch {
+    pub account_type: AccountType,
+    pub authority: Pubkey,
+    pub id: u64,
+    pub root: [u8; 32],
+    pub leaf_count: u32,
+    pub executed_count: u32,
+    pub executed_total: u64,
+    pub executed: [u8; 128],
+    pub bump: u8,
+}
+
+impl PayoutBatch {
+    pub const SEED: &'static [u8] = b"payout_batch";
+    pub const MAX_LEAVES: u32 = 128 * 8;
+
+    pub fn find_address(authority: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, authority.as_ref(), &id.to_le_bytes()], &crate::id())
+    }
+
+    pub fn is_executed(&self, index: u32) -> bool {
+        self.executed[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_executed(&mut self, index: u32) {
+        self.executed[index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+impl AccountState for PayoutBatch {
+    const ACCOUNT_TYPE: AccountType = AccountType::PayoutBatch;
+    const LEN: usize = 1 + 32 + 8 + 32 + 4 + 4 + 8 + 128 + 1;
+}
+
+/// Lamports released to claimers holding an eligibility proof of the attestor, see `eligibility`.
+/// The account itself holds undistributed lamports.
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Distributor {
+    pub account_type: AccountType,
+    pub authority: Pubkey,
+    pub attestor: Pubkey,
+    pub id: u64,
+    pub claimed_total: u64,
+    pub claim_count: u32,
+    pub bump: u8,
+}
+
+impl Distributor {
+    pub const SEED: &'static [u8] = b"distributor";
+
+    pub fn find_address(authority: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, authority.as_ref(), &id.to_le_bytes()], &crate::id())
+    }
+}
+
+impl AccountState for Distributor {
+    const ACCOUNT_TYPE: AccountType = AccountType::Distributor;
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 4 + 1;
+}
+
+/// Receipt of a claim, its existence prevents claiming the same distributor twice
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DistributionClaim {
+    pub account_type: AccountType,
+    pub distributor: Pubkey,
+    pub claimer: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl DistributionClaim {
+    pub const SEED: &'static [u8] = b"distribution_claim";
+
+    pub fn find_address(distributor: &Pubkey, claimer: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, distributor.as_ref(), claimer.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for DistributionClaim {
+    const ACCOUNT_TYPE: AccountType = AccountType::DistributionClaim;
+    const LEN: usize = 1 + 32 + 32 + 8 + 1;
+}
+
+/// Custom token airdrop committed by the Merkle root of its claim list, leaves are `merkle::payout_leaf`
+/// of claimers. Associated token account of the distribution holds the tokens, claimed leaves are tracked
+/// in `ClaimBitmap`
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MerkleDistribution {
+    pub account_type: AccountType,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub root: [u8; 32],
+    pub leaf_count: u32,
+    pub total_amount: u64,
+    pub claimed_total: u64,
+    pub claimed_count: u32,
+    pub bump: u8,
+}
+
+impl MerkleDistribution {
+    pub const SEED: &'static [u8] = b"merkle_distribution";
+    /// Bitmap of the max leaves fits into an account created by CPI
+    pub const MAX_LEAVES: u32 = 1 << 16;
+
+    pub fn find_address(authority: &Pubkey, root: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, authority.as_ref(), root], &crate::id())
+    }
+}
+
+impl AccountState for MerkleDistribution {
+    const ACCOUNT_TYPE: AccountType = AccountType::MerkleDistribution;
+    const LEN: usize = 1 + 32 + 32 + 32 + 4 + 8 + 8 + 4 + 1;
+}
+
+/// Claimed leaves of a `MerkleDistribution`, a bit per leaf after the account type. Its size follows
+/// the leaf count, so the account is accessed as raw data
+pub struct ClaimBitmap;
+
+impl ClaimBitmap {
+    pub const SEED: &'static [u8] = b"claim_bitmap";
+
+    pub fn find_address(distribution: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, distribution.as_ref()], &crate::id())
+    }
+
+    /// Account type and a bit per leaf, rounded up to bytes
+    pub fn len(leaf_count: u32) -> usize {
+        1 + ((leaf_count as usize + 7) >> 3)
+    }
+
+    pub fn is_claimed(data: &[u8], index: u32) -> bool {
+        data[1 + index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(data: &mut [u8], index: u32) {
+        data[1 + index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+/// Attestation level of a wallet granted by the config admin, e.g. passed KYC tier
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Attestation {
+    pub account_type: AccountType,
+    pub wallet: Pubkey,
+    /// 0 revokes attestation
+    pub level: u8,
+    /// Unix timestamp
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Attestation {
+    pub const SEED: &'static [u8] = b"attestation";
+
+    pub fn find_address(wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, wallet.as_ref()], &crate::id())
+    }
+
+    pub fn satisfies(&self, min_level: u8, now: i64) -> bool {
+        self.level > 0 && self.level >= min_level && now < self.expires_at
+    }
+}
+
+impl AccountState for Attestation {
+    const ACCOUNT_TYPE: AccountType = AccountType::Attestation;
+    const LEN: usize = 1 + 32 + 1 + 8 + 1;
+}
+
+/// Per-mint settings registered by the mint authority
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MintConfig {
+    pub account_type: AccountType,
+    pub mint: Pubkey,
+    pub issuer: Pubkey,
+    /// Transfers through this program are rejected, e.g. credential tokens
+    pub soulbound: bool,
+    pub bump: u8,
+}
+
+impl MintConfig {
+    pub const SEED: &'static [u8] = b"mint_config";
+
+    pub fn find_address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, mint.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for MintConfig {
+    const ACCOUNT_TYPE: AccountType = AccountType::MintConfig;
+    const LEN: usize = 1 + 32 + 32 + 1 + 1;
+}
+
+/// NFT listed for rent, held by associated token account of the rental until returned to owner.
+/// While rented, `renter` is the delegated user of the NFT until `expires_at`
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Rental {
+    pub account_type: AccountType,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Default pubkey while not rented
+    pub renter: Pubkey,
+    /// Lamports paid by renter to owner
+    pub price: u64,
+    /// Seconds
+    pub duration: i64,
+    /// Unix timestamp, 0 while not rented
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Rental {
+    pub const SEED: &'static [u8] = b"rental";
+
+    pub fn find_address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, mint.as_ref()], &crate::id())
+    }
+
+    pub fn is_rented(&self) -> bool {
+        self.renter != Pubkey::default()
+    }
+}
+
+impl AccountState for Rental {
+    const ACCOUNT_TYPE: AccountType = AccountType::Rental;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+/// NFT locked in associated token account of the fraction against `shares` share tokens,
+/// share mint authority is the fraction
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Fraction {
+    pub account_type: AccountType,
+    pub depositor: Pubkey,
+    pub nft_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub shares: u64,
+    pub bump: u8,
+}
+
+impl Fraction {
+    pub const SEED: &'static [u8] = b"fraction";
+
+    pub fn find_address(nft_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, nft_mint.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for Fraction {
+    const ACCOUNT_TYPE: AccountType = AccountType::Fraction;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 1;
+}
+
+/// Execution constraint of an order checked at fill time
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy)]
+pub enum FillMode {
+    /// Any number of fills up to the remaining amount
+    Partial,
+    /// Single fill of the whole order
+    FillOrKill,
+    /// Single fill of any amount, the unfilled rest is refunded to maker
+    ImmediateOrCancel,
+}
+
+/// Limit order selling `sell_total` of sell mint for `buy_total` of buy mint, unfilled rest is escrowed
+/// in associated token account of the order
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Order {
+    pub account_type: AccountType,
+    pub maker: Pubkey,
+    pub id: u64,
+    pub sell_mint: Pubkey,
+    pub buy_mint: Pubkey,
+    pub sell_total: u64,
+    pub buy_total: u64,
+    /// Sell amount left to fill
+    pub remaining: u64,
+    pub fill_mode: FillMode,
+    pub bump: u8,
+}
+
+impl Order {
+    pub const SEED: &'static [u8] = b"order";
+
+    pub fn find_address(maker: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, maker.as_ref(), &id.to_le_bytes()], &crate::id())
+    }
+
+    /// Buy amount due for filling `amount` of the sell side, rounded up in favor of maker
+    pub fn price_of(&self, amount: u64) -> Option<u64> {
+        let numerator = (amount as u128).checked_mul(self.buy_total as u128)?;
+        let sell_total = self.sell_total as u128;
+        let price = numerator.checked_add(sell_total.checked_sub(1)?)? / sell_total;
+        u64::try_from(price).ok()
+    }
+}
+
+impl AccountState for Order {
+    const ACCOUNT_TYPE: AccountType = AccountType::Order;
+    const LEN: usize = 1 + 32 + 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1;
+}
+
+/// Price of a mint published by the config admin, read by price protected transfers
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct PriceFeed {
+    pub account_type: AccountType,
+    pub mint: Pubkey,
+    /// Units are agreed off-chain per mint, e.g. quote currency with fixed decimals
+    pub price: u64,
+    /// Unix timestamp
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl PriceFeed {
+    pub const SEED: &'static [u8] = b"price_feed";
+
+    pub fn find_address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, mint.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for PriceFeed {
+    const ACCOUNT_TYPE: AccountType = AccountType::PriceFeed;
+    const LEN: usize = 1 + 32 + 8 + 8 + 1;
+}
+
+/// Registered crank bot, credited for crank executions it signs
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Keeper {
+    pub account_type: AccountType,
+    pub keeper: Pubkey,
+    pub executions: u64,
+    /// Lamports earned by crank executions
+    pub bounties: u64,
+    /// Set by config admin, banned keeper can't execute cranks
+    pub banned: bool,
+    pub bump: u8,
+}
+
+impl Keeper {
+    pub const SEED: &'static [u8] = b"keeper";
+
+    pub fn find_address(keeper: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, keeper.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for Keeper {
+    const ACCOUNT_TYPE: AccountType = AccountType::Keeper;
+    const LEN: usize = 1 + 32 + 8 + 8 + 1 + 1;
+}
+
+/// Custody of `mint` tokens held in associated token account of the vault, only owner withdraws
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Vault {
+    pub account_type: AccountType,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub bump: u8,
+}
+
+impl Vault {
+    pub const SEED: &'static [u8] = b"vault";
+
+    pub fn find_address(owner: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Self::find_address_in(&crate::id(), owner, mint)
+    }
+
+    /// Address in deployment `program_id`, e.g. migration successor
+    pub fn find_address_in(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, owner.as_ref(), mint.as_ref()], program_id)
+    }
+
+    /// Vault within the namespace of `namespace_config`, see `namespaced_seeds`
+    pub fn find_namespaced_address(namespace_config: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        let seeds = namespaced_seeds(Self::SEED, Some(namespace_config), &[owner.as_ref(), mint.as_ref()]);
+        Pubkey::find_program_address(&seeds, &crate::id())
+    }
+
+    /// Deployment independent record of the vault, see `Migration::fold`
+    pub fn migration_record(&self) -> [u8; 32] {
+        hashv(&[&[AccountType::Vault as u8], self.owner.as_ref(), self.mint.as_ref()]).to_bytes()
+    }
+}
+
+impl AccountState for Vault {
+    const ACCOUNT_TYPE: AccountType = AccountType::Vault;
+    const LEN: usize = 1 + 32 + 32 + 1;
+}
+
+/// Per-customer deposit addresses of an exchange `authority`, all swept into `omnibus`, e.g. a vault.
+/// Pool of `mint` paid pro rata to holders of `snapshot_mint`, committed by the Merkle root of a holder snapshot.
+/// Leaves are `merkle::payout_leaf` of holders and their snapshot balances, each claimed once and tracked
+/// in `ClaimBitmap`. Associated token account of the dividend holds the pool until the claim deadline,
+/// the admin claws back the rest afterwards
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Dividend {
+    pub account_type: AccountType,
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub snapshot_mint: Pubkey,
+    pub root: [u8; 32],
+    pub holder_count: u32,
+    /// Sum of snapshot balances of every leaf
+    pub snapshot_supply: u64,
+    pub pool_amount: u64,
+    pub claimed_total: u64,
+    pub claimed_count: u32,
+    pub claim_deadline: i64,
+    pub bump: u8,
+}
+
+impl Dividend {
+    pub const SEED: &'static [u8] = b"dividend";
+
+    pub fn find_address(admin: &Pubkey, root: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, admin.as_ref(), root], &crate::id())
+    }
+
+    /// Share of the pool of snapshot `balance`, rounded down so shares never add up above the pool.
+    /// `None` for a balance above the snapshot supply
+    pub fn share(&self, balance: u64) -> Option<u64> {
+        if balance > self.snapshot_supply {
+            return None
+        }
+        let share = (u128::from(self.pool_amount) * u128::from(balance)).checked_div(u128::from(self.snapshot_supply))?;
+        u64::try_from(share).ok()
+    }
+}
+
+impl AccountState for Dividend {
+    const ACCOUNT_TYPE: AccountType = AccountType::Dividend;
+    const LEN: usize = 1 + 32 + 32 + 32 + 32 + 4 + 8 + 8 + 8 + 4 + 8 + 1;
+}
+
+/// A deposit address is a PDA of the scheme and the user ID, so addresses are derived without limit,
+/// only this program signs for them and the user ID is known to every sweep
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct DepositScheme {
+    pub account_type: AccountType,
+    pub authority: Pubkey,
+    /// Wallet receiving swept lamports, its associated token accounts receive swept tokens
+    pub omnibus: Pubkey,
+    pub bump: u8,
+}
+
+impl DepositScheme {
+    pub const SEED: &'static [u8] = b"deposit_scheme";
+    pub const DEPOSIT_SEED: &'static [u8] = b"deposit";
+
+    pub fn find_address(authority: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, authority.as_ref()], &crate::id())
+    }
+
+    /// Deposit address of `user_id` within `scheme`, a system owned wallet signed for by this program
+    pub fn find_deposit_address(scheme: &Pubkey, user_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::DEPOSIT_SEED, scheme.as_ref(), &user_id.to_le_bytes()], &crate::id())
+    }
+}
+
+impl AccountState for DepositScheme {
+    const ACCOUNT_TYPE: AccountType = AccountType::DepositScheme;
+    const LEN: usize = 1 + 32 + 32 + 1;
+}
+
+/// Mint whose mint authority is this PDA, tokens are issued and redeemed only by `Issuer` roles of the admin
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ManagedMint {
+    pub account_type: AccountType,
+    pub mint: Pubkey,
+    pub admin: Pubkey,
+    /// Supply never exceeds the cap by issues
+    pub supply_cap: u64,
+    pub issued: u64,
+    pub redeemed: u64,
+    pub bump: u8,
+}
+
+impl ManagedMint {
+    pub const SEED: &'static [u8] = b"managed_mint";
+
+    pub fn find_address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, mint.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for ManagedMint {
+    const ACCOUNT_TYPE: AccountType = AccountType::ManagedMint;
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+/// Roles of `issuer` on a managed mint, granted by its admin
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Issuer {
+    pub account_type: AccountType,
+    pub managed_mint: Pubkey,
+    pub issuer: Pubkey,
+    /// `ROLE_*` bits
+    pub roles: u8,
+    /// Left to issue, spent by issues and reset by the admin
+    pub allowance: u64,
+    pub issued: u64,
+    pub redeemed: u64,
+    pub bump: u8,
+}
+
+impl Issuer {
+    pub const SEED: &'static [u8] = b"issuer";
+    pub const ROLE_ISSUE: u8 = 1 << 0;
+    pub const ROLE_REDEEM: u8 = 1 << 1;
+
+    pub fn find_address(managed_mint: &Pubkey, issuer: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, managed_mint.as_ref(), issuer.as_ref()], &crate::id())
+    }
+
+    pub fn has_role(&self, role: u8) -> bool {
+        self.roles & role == role
+    }
+}
+
+impl AccountState for Issuer {
+    const ACCOUNT_TYPE: AccountType = AccountType::Issuer;
+    const LEN: usize = 1 + 32 + 32 + 1 + 8 + 8 + 8 + 1;
+}
+
+/// Swap offer: whole balance of the temp token account, owned by the escrow, for `expected_amount`
+/// paid into the receive token account of the initializer
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Escrow {
+    pub account_type: AccountType,
+    pub initializer: Pubkey,
+    pub temp_token: Pubkey,
+    pub receive_token: Pubkey,
+    pub expected_amount: u64,
+    /// Exchange is accepted before, cancel after
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Escrow {
+    pub const SEED: &'static [u8] = b"escrow";
+
+    pub fn find_address(temp_token: &Pubkey) -> (Pubkey, u8) {
+        Self::find_address_in(&crate::id(), temp_token)
+    }
+
+    /// Address in deployment `program_id`, e.g. migration successor
+    pub fn find_address_in(program_id: &Pubkey, temp_token: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, temp_token.as_ref()], program_id)
+    }
+
+    /// Deployment independent record of the escrow, see `Migration::fold`
+    pub fn migration_record(&self) -> [u8; 32] {
+        hashv(&[
+            &[AccountType::Escrow as u8],
+            self.initializer.as_ref(),
+            self.temp_token.as_ref(),
+            self.receive_token.as_ref(),
+            &self.expected_amount.to_le_bytes(),
+            &self.expires_at.to_le_bytes(),
+        ]).to_bytes()
+    }
+}
+
+impl AccountState for Escrow {
+    const ACCOUNT_TYPE: AccountType = AccountType::Escrow;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Protocol fee taken from transfers which pass the fee config, managed by its admin
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct FeeConfig {
+    pub account_type: AccountType,
+    pub admin: Pubkey,
+    /// Fee in basis points of the transferred amount
+    pub fee_bps: u16,
+    /// Receives lamport fees, owns token accounts receiving SPL token fees
+    pub fee_recipient: Pubkey,
+    pub bump: u8,
+}
+
+impl FeeConfig {
+    pub const SEED: &'static [u8] = b"fee_config";
+    pub const MAX_FEE_BPS: u16 = 10_000;
+
+    pub fn find_address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], &crate::id())
+    }
+
+    /// Fee config within the namespace of `namespace_config`, see `namespaced_seeds`
+    pub fn find_namespaced_address(namespace_config: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&namespaced_seeds(Self::SEED, Some(namespace_config), &[]), &crate::id())
+    }
+
+    /// Fee part of `amount`, rounded down
+    pub fn fee(&self, amount: u64) -> u64 {
+        (amount as u128 * self.fee_bps as u128 / Self::MAX_FEE_BPS as u128) as u64
+    }
+}
+
+impl AccountState for FeeConfig {
+    const ACCOUNT_TYPE: AccountType = AccountType::FeeConfig;
+    const LEN: usize = 1 + 32 + 2 + 32 + 1;
+}
+
+/// Tokens of `mint` released to beneficiary linearly from `start_ts` to `end_ts`, nothing before `cliff_ts`.
+/// Held in associated token account of the schedule, creator may cancel and take back the unvested rest
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VestingSchedule {
+    pub account_type: AccountType,
+    pub creator: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+    pub bump: u8,
+}
+
+impl VestingSchedule {
+    pub const SEED: &'static [u8] = b"vesting";
+
+    pub fn find_address(creator: &Pubkey, beneficiary: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEED, creator.as_ref(), beneficiary.as_ref(), mint.as_ref()],
+            &crate::id(),
+        )
+    }
+
+    /// Start before end, cliff in between
+    pub fn is_valid_schedule(start_ts: i64, cliff_ts: i64, end_ts: i64) -> bool {
+        start_ts <= cliff_ts && cliff_ts <= end_ts && start_ts < end_ts
+    }
+
+    /// Amount vested by `now`, claimed amount included
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        if now < self.cliff_ts {
+            return 0
+        }
+        if now >= self.end_ts {
+            return self.total_amount
+        }
+        let elapsed = (now as i128 - self.start_ts as i128) as u128;
+        let duration = (self.end_ts as i128 - self.start_ts as i128) as u128;
+        (self.total_amount as u128 * elapsed / duration) as u64
+    }
+
+    /// Vested and not yet claimed by `now`
+    pub fn claimable_amount(&self, now: i64) -> u64 {
+        self.vested_amount(now).saturating_sub(self.claimed_amount)
+    }
+}
+
+impl AccountState for VestingSchedule {
+    const ACCOUNT_TYPE: AccountType = AccountType::VestingSchedule;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+/// Sponsor funded pool paying rent of associated token accounts created for wallets by the pool operator,
+/// lamports held by the pool account
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SponsorPool {
+    pub account_type: AccountType,
+    pub sponsor: Pubkey,
+    /// Relayer creating sponsored accounts, reimbursed from the pool
+    pub operator: Pubkey,
+    /// Max sponsored accounts per wallet
+    pub max_per_wallet: u16,
+    pub remaining: u64,
+    pub sponsored_count: u64,
+    pub bump: u8,
+}
+
+impl SponsorPool {
+    pub const SEED: &'static [u8] = b"sponsor_pool";
+
+    pub fn find_address(sponsor: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, sponsor.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for SponsorPool {
+    const ACCOUNT_TYPE: AccountType = AccountType::SponsorPool;
+    const LEN: usize = 1 + 32 + 32 + 2 + 8 + 8 + 1;
+}
+
+/// Accounts sponsored for a wallet by a pool, created with the first one and paid by the pool as well
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SponsoredWallet {
+    pub account_type: AccountType,
+    pub pool: Pubkey,
+    pub wallet: Pubkey,
+    pub count: u16,
+    pub bump: u8,
+}
+
+impl SponsoredWallet {
+    pub const SEED: &'static [u8] = b"sponsored_wallet";
+
+    pub fn find_address(pool: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, pool.as_ref(), wallet.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for SponsoredWallet {
+    const ACCOUNT_TYPE: AccountType = AccountType::SponsoredWallet;
+    const LEN: usize = 1 + 32 + 32 + 2 + 1;
+}
+
+/// Payment of `deposit` streamed from sender to recipient at `rate_per_second` since `start_ts`.
+/// Lamports are held by the stream account, custom token by its associated token account
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Stream {
+    pub account_type: AccountType,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    /// Default pubkey for lamports
+    pub mint: Pubkey,
+    pub deposit: u64,
+    pub rate_per_second: u64,
+    pub start_ts: i64,
+    pub withdrawn: u64,
+    pub bump: u8,
+}
+
+impl Stream {
+    pub const SEED: &'static [u8] = b"stream";
+
+    pub fn find_address(sender: &Pubkey, recipient: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, sender.as_ref(), recipient.as_ref()], &crate::id())
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    /// Amount streamed by `now`, withdrawn amount included, never above deposit
+    pub fn streamed_amount(&self, now: i64) -> u64 {
+        if now <= self.start_ts {
+            return 0
+        }
+        let elapsed = (now as i128 - self.start_ts as i128) as u128;
+        (self.rate_per_second as u128).saturating_mul(elapsed).min(self.deposit as u128) as u64
+    }
+
+    /// Streamed and not yet withdrawn by `now`
+    pub fn withdrawable_amount(&self, now: i64) -> u64 {
+        self.streamed_amount(now).saturating_sub(self.withdrawn)
+    }
+}
+
+impl AccountState for Stream {
+    const ACCOUNT_TYPE: AccountType = AccountType::Stream;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1;
+}
+
+/// Custom token parked for a wallet without associated token account, held in associated token account
+/// of the claimable balance until the wallet claims it. Rent is returned to the payer of the first park
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ClaimableBalance {
+    pub account_type: AccountType,
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+impl ClaimableBalance {
+    pub const SEED: &'static [u8] = b"claimable";
+
+    pub fn find_address(wallet: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, wallet.as_ref(), mint.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for ClaimableBalance {
+    const ACCOUNT_TYPE: AccountType = AccountType::ClaimableBalance;
+    const LEN: usize = 1 + 32 + 32 + 8 + 32 + 1;
+}
+
+/// Revoked delegate, delegated transfers signed by it fail while the revocation exists.
+/// Scope is the owner of delegated token accounts, or the default pubkey for a revocation by the config admin
+/// covering every owner
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Revocation {
+    pub account_type: AccountType,
+    pub scope: Pubkey,
+    pub delegate: Pubkey,
+    pub revoked_by: Pubkey,
+    pub revoked_at: i64,
+    pub bump: u8,
+}
+
+impl Revocation {
+    pub const SEED: &'static [u8] = b"revocation";
+
+    pub fn find_address(scope: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, scope.as_ref(), delegate.as_ref()], &crate::id())
+    }
+
+    /// Scope of a revocation by `authority`, the default pubkey when `global`
+    pub fn scope(authority: &Pubkey, global: bool) -> Pubkey {
+        if global {
+            Pubkey::default()
+        } else {
+            *authority
+        }
+    }
+}
+
+impl AccountState for Revocation {
+    const ACCOUNT_TYPE: AccountType = AccountType::Revocation;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 1;
+}
+
+/// Recipient registered on the roster of a payer by signing itself, roster-gated transfers of the payer
+/// only reach registered recipients. Rent is paid by the recipient and returned to it on removal
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct RosterEntry {
+    pub account_type: AccountType,
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+    pub registered_at: i64,
+    pub bump: u8,
+}
+
+impl RosterEntry {
+    pub const SEED: &'static [u8] = b"roster";
+
+    pub fn find_address(payer: &Pubkey, recipient: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, payer.as_ref(), recipient.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for RosterEntry {
+    const ACCOUNT_TYPE: AccountType = AccountType::RosterEntry;
+    const LEN: usize = 1 + 32 + 32 + 8 + 1;
+}
+
+/// Amount and deliverable of a milestone as defined by funder at contract creation
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy)]
+pub struct MilestoneTerms {
+    pub amount: u64,
+    /// Hash of the agreed deliverable, e.g. of its specification
+    pub deliverable_hash: [u8; 32],
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy)]
+pub enum MilestoneStatus {
+    Pending,
+    /// Deliverable submitted by payee, tranche awaits release
+    Submitted,
+    Released,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy)]
+pub struct Milestone {
+    pub terms: MilestoneTerms,
+    /// Hash of the deliverable submitted by payee, zeroed until submitted
+    pub submitted_hash: [u8; 32],
+    pub status: MilestoneStatus,
+}
+
+impl Milestone {
+    pub const EMPTY: Milestone = Milestone {
+        terms: MilestoneTerms { amount: 0, deliverable_hash: [0; 32] },
+        submitted_hash: [0; 32],
+        status: MilestoneStatus::Pending,
+    };
+    pub const LEN: usize = 8 + 32 + 32 + 1;
+}
+
+/// Unused slot past `milestone_count`, required by Borsh to deserialize the milestone array
+impl Default for Milestone {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// Tokens of `mint` escrowed by funder and released to payee tranche by tranche, each once payee submitted
+/// the deliverable of the milestone and funder or arbiter approved it. Held in associated token account
+/// of the contract, arbiter may close it early and refund unreleased tranches to funder
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MilestoneContract {
+    pub account_type: AccountType,
+    pub funder: Pubkey,
+    pub payee: Pubkey,
+    /// Funder itself when there is no third party
+    pub arbiter: Pubkey,
+    pub mint: Pubkey,
+    pub id: u64,
+    pub released_total: u64,
+    pub milestone_count: u8,
+    /// First `milestone_count` are used, the rest stay empty
+    pub milestones: [Milestone; MilestoneContract::MAX_MILESTONES],
+    pub bump: u8,
+}
+
+impl MilestoneContract {
+    pub const SEED: &'static [u8] = b"contract";
+    pub const MAX_MILESTONES: usize = 8;
+
+    pub fn find_address(funder: &Pubkey, payee: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, funder.as_ref(), payee.as_ref(), &id.to_le_bytes()], &crate::id())
+    }
+
+    /// Sum of tranches, None unless there are 1 to `MAX_MILESTONES` milestones of nonzero amount
+    pub fn total_amount(terms: &[MilestoneTerms]) -> Option<u64> {
+        if terms.is_empty() || terms.len() > Self::MAX_MILESTONES || terms.iter().any(|terms| terms.amount == 0) {
+            return None
+        }
+        terms.iter().try_fold(0u64, |total, terms| total.checked_add(terms.amount))
+    }
+
+    pub fn milestone_mut(&mut self, index: u8) -> Option<&mut Milestone> {
+        self.milestones[..self.milestone_count as usize].get_mut(index as usize)
+    }
+
+    /// Every tranche released
+    pub fn is_completed(&self) -> bool {
+        self.milestones[..self.milestone_count as usize]
+            .iter()
+            .all(|milestone| milestone.status == MilestoneStatus::Released)
+    }
+}
+
+impl AccountState for MilestoneContract {
+    const ACCOUNT_TYPE: AccountType = AccountType::MilestoneContract;
+    const LEN: usize = 1 + 32 + 32 + 32 + 32 + 8 + 8 + 1 + Milestone::LEN * Self::MAX_MILESTONES + 1;
+}
+
+/// Rolling hash chain over successful instructions carrying it as their last account, a single on-chain
+/// commitment auditors recompute from an off-chain archive to prove the archive complete and untampered
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct AuditLog {
+    pub account_type: AccountType,
+    /// Chain head after the last audited instruction, zeroed before the first one
+    pub head: [u8; 32],
+    /// Number of audited instructions
+    pub count: u64,
+    pub last_slot: u64,
+    pub bump: u8,
+}
+
+impl AuditLog {
+    pub const SEED: &'static [u8] = b"audit_log";
+
+    pub fn find_address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], &crate::id())
+    }
+
+    /// Fold instruction into the chain, `head = sha256(head, tag, account keys, amount, slot)` with integers
+    /// little endian. Account keys exclude the audit log, amount is zero for instructions without one
+    pub fn fold(&mut self, tag: u8, accounts: &[Pubkey], amount: u64, slot: u64) {
+        let (tag, amount_bytes, slot_bytes) = ([tag], amount.to_le_bytes(), slot.to_le_bytes());
+        let mut data: Vec<&[u8]> = vec![&self.head, &tag];
+        data.extend(accounts.iter().map(|account| account.as_ref()));
+        data.push(&amount_bytes);
+        data.push(&slot_bytes);
+        self.head = hashv(&data).to_bytes();
+        self.count += 1;
+        self.last_slot = slot;
+    }
+}
+
+impl AccountState for AuditLog {
+    const ACCOUNT_TYPE: AccountType = AccountType::AuditLog;
+    const LEN: usize = 1 + 32 + 8 + 8 + 1;
+}
+
+/// Sequence numbers of the events of instructions carrying the stream as their last account but the audit log,
+/// see `TokenInstruction::with_event_stream`. Only instructions of one stream contend for it, so clients spread
+/// over several streams run in parallel
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct EventStream {
+    pub account_type: AccountType,
+    /// Key the stream is derived from, chosen by its creator
+    pub key: Pubkey,
+    /// Sequence number of the next event
+    pub sequence: u64,
+    pub bump: u8,
+}
+
+impl EventStream {
+    pub const SEED: &'static [u8] = b"event_stream";
+
+    pub fn find_address(key: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, key.as_ref()], &crate::id())
+    }
+
+    /// Sequence number for the next event, counting it
+    pub fn next_sequence(&mut self) -> Option<u64> {
+        let sequence = self.sequence;
+        self.sequence = sequence.checked_add(1)?;
+        Some(sequence)
+    }
+}
+
+impl AccountState for EventStream {
+    const ACCOUNT_TYPE: AccountType = AccountType::EventStream;
+    const LEN: usize = 1 + 32 + 8 + 1;
+}
+
+/// Decimals and token program of a mint, copied once from the mint so hot paths skip unpacking it.
+/// Decimals of an SPL mint never change, so the cache can't go stale
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct MintDecimals {
+    pub account_type: AccountType,
+    pub mint: Pubkey,
+    /// Owner of the mint
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub bump: u8,
+}
+
+impl MintDecimals {
+    pub const SEED: &'static [u8] = b"mint_decimals";
+
+    pub fn find_address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, mint.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for MintDecimals {
+    const ACCOUNT_TYPE: AccountType = AccountType::MintDecimals;
+    const LEN: usize = 1 + 32 + 32 + 1 + 1;
+}
+
+/// Whether recipients in a registry are the only ones allowed or the blocked ones
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy)]
+pub enum RegistryMode {
+    Allow,
+    Block,
+}
+
+/// Recipients vetted by registry admin, transfers carrying the registry only reach recipients it permits.
+/// Recipient of a token transfer is the owner of the destination token account
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Registry {
+    pub account_type: AccountType,
+    pub admin: Pubkey,
+    pub mode: RegistryMode,
+    pub bump: u8,
+    /// Unordered and unique, at most `MAX_ENTRIES`. Kept last and on the heap, the account is sized for the maximum
+    pub entries: Vec<Pubkey>,
+}
+
+impl Registry {
+    pub const SEED: &'static [u8] = b"registry";
+    pub const MAX_ENTRIES: usize = 256;
+
+    pub fn find_address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], &crate::id())
+    }
+
+    /// Registry within the namespace of `namespace_config`, see `namespaced_seeds`
+    pub fn find_namespaced_address(namespace_config: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&namespaced_seeds(Self::SEED, Some(namespace_config), &[]), &crate::id())
+    }
+
+    pub fn permits(&self, recipient: &Pubkey) -> bool {
+        self.entries.contains(recipient) == (self.mode == RegistryMode::Allow)
+    }
+
+    /// Add `recipient` unless it is there already, false when the registry is full
+    pub fn add(&mut self, recipient: Pubkey) -> bool {
+        if self.entries.contains(&recipient) {
+            return true
+        }
+        if self.entries.len() >= Self::MAX_ENTRIES {
+            return false
+        }
+        self.entries.push(recipient);
+        true
+    }
+
+    pub fn remove(&mut self, recipient: &Pubkey) {
+        self.entries.retain(|entry| entry != recipient);
+    }
+}
+
+impl AccountState for Registry {
+    const ACCOUNT_TYPE: AccountType = AccountType::Registry;
+    const LEN: usize = 1 + 32 + 1 + 1 + 4 + 32 * Self::MAX_ENTRIES;
+}
+
+/// One side of a two-phase migration of vaults and escrows between deployments. The predecessor exports,
+/// handing custody of the tokens to the successor PDAs and freezing its own state, the successor imports what
+/// was exported. Each side folds records of moved accounts into its commitment, they match once every exported
+/// account is imported
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct Migration {
+    pub account_type: AccountType,
+    pub admin: Pubkey,
+    /// Successor on the export side, predecessor on the import side
+    pub peer: Pubkey,
+    /// XOR of migration records, independent of the order accounts are moved in
+    pub commitment: [u8; 32],
+    pub count: u64,
+    pub bump: u8,
+}
+
+impl Migration {
+    pub const EXPORT_SEED: &'static [u8] = b"migration_export";
+    pub const IMPORT_SEED: &'static [u8] = b"migration_import";
+
+    pub fn find_export_address() -> (Pubkey, u8) {
+        Self::find_export_address_in(&crate::id())
+    }
+
+    /// Export side of deployment `program_id`, e.g. migration predecessor
+    pub fn find_export_address_in(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::EXPORT_SEED], program_id)
+    }
+
+    pub fn find_import_address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::IMPORT_SEED], &crate::id())
+    }
+
+    /// Fold record of a moved account, see `Vault::migration_record`. Each account is folded once per side,
+    /// exported state can't be exported again and imported state can't be created twice
+    pub fn fold(&mut self, record: [u8; 32]) {
+        self.commitment.iter_mut().zip(record.iter()).for_each(|(byte, record_byte)| *byte ^= record_byte);
+        self.count += 1;
+    }
+
+    /// Both sides moved the same accounts
+    pub fn matches(&self, peer_side: &Migration) -> bool {
+        self.count == peer_side.count && self.commitment == peer_side.commitment
+    }
+}
+
+impl AccountState for Migration {
+    const ACCOUNT_TYPE: AccountType = AccountType::Migration;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 1;
+}
+
+/// Amount of `mint` `authority` may transfer within any `window_seconds` long span, set by program config admin.
+/// Limits of each mint and of lamports, `Pubkey::default()`, are separate accounts, so base units of different
+/// mints never add up. Transfers are counted in `BUCKETS` buckets of `bucket_seconds`, a bucket leaves the window once it has
+/// fully elapsed, so the window may look back up to one bucket longer but never shorter
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct SpendingLimit {
+    pub account_type: AccountType,
+    pub authority: Pubkey,
+    /// `Pubkey::default()` for lamports
+    pub mint: Pubkey,
+    pub limit: u64,
+    pub window_seconds: i64,
+    /// Spent per bucket, bucket `i` is kept at `i % BUCKETS`
+    pub spent: [u64; SpendingLimit::BUCKETS],
+    /// Latest bucket spent in, older buckets of `spent` are cleared once they fall out of the window
+    pub last_bucket: i64,
+    pub bump: u8,
+}
+
+impl SpendingLimit {
+    pub const SEED: &'static [u8] = b"spending_limit";
+    pub const BUCKETS: usize = 24;
+
+    pub fn find_address(authority: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, authority.as_ref(), mint.as_ref()], &crate::id())
+    }
+
+    /// All buckets but the current one span the window
+    pub fn bucket_seconds(&self) -> i64 {
+        let spans = Self::BUCKETS as i64 - 1;
+        (self.window_seconds.saturating_add(spans - 1) / spans).max(1)
+    }
+
+    /// Spent within the window ending at `now`
+    pub fn spent(&self, now: i64) -> u64 {
+        let bucket_seconds = self.bucket_seconds();
+        let bucket = now.div_euclid(bucket_seconds).max(self.last_bucket);
+        let first = now.saturating_sub(self.window_seconds).div_euclid(bucket_seconds);
+        (bucket.saturating_sub(Self::BUCKETS as i64 - 1).max(first)..=bucket)
+            .filter(|i| *i <= self.last_bucket)
+            .map(|i| self.spent[i.rem_euclid(Self::BUCKETS as i64) as usize])
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Count `amount` spent at `now`. False and nothing counted when the window would exceed the limit
+    pub fn spend(&mut self, amount: u64, now: i64) -> bool {
+        match self.spent(now).checked_add(amount) {
+            Some(spent) if spent <= self.limit => {}
+            _ => return false,
+        }
+        let bucket = now.div_euclid(self.bucket_seconds()).max(self.last_bucket);
+        let cleared = self.last_bucket.saturating_add(1).max(bucket.saturating_sub(Self::BUCKETS as i64 - 1));
+        for i in cleared..=bucket {
+            self.spent[i.rem_euclid(Self::BUCKETS as i64) as usize] = 0;
+        }
+        let slot = &mut self.spent[bucket.rem_euclid(Self::BUCKETS as i64) as usize];
+        *slot = slot.saturating_add(amount);
+        self.last_bucket = bucket;
+        true
+    }
+
+    /// Change the window at `now`, what was spent within the old window stays counted in the current bucket
+    pub fn set_window(&mut self, window_seconds: i64, now: i64) {
+        if window_seconds == self.window_seconds {
+            return
+        }
+        let spent = self.spent(now);
+        self.window_seconds = window_seconds;
+        self.spent = [0; Self::BUCKETS];
+        self.last_bucket = now.div_euclid(self.bucket_seconds());
+        self.spent[self.last_bucket.rem_euclid(Self::BUCKETS as i64) as usize] = spent;
+    }
+}
+
+impl AccountState for SpendingLimit {
+    const ACCOUNT_TYPE: AccountType = AccountType::SpendingLimit;
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 * SpendingLimit::BUCKETS + 8 + 1;
+}
+
+/// Lamport transfers of `authority` above `threshold` take a second approval: they are proposed, see
+/// `PendingTransfer`, and executed by `approver`. Set by program config admin
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct ApprovalPolicy {
+    pub account_type: AccountType,
+    pub authority: Pubkey,
+    pub approver: Pubkey,
+    pub threshold: u64,
+    pub bump: u8,
+}
+
+impl ApprovalPolicy {
+    pub const SEED: &'static [u8] = b"approval_policy";
+
+    pub fn find_address(authority: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, authority.as_ref()], &crate::id())
+    }
+
+    pub fn requires_approval(&self, amount: u64) -> bool {
+        amount > self.threshold
+    }
+}
+
+impl AccountState for ApprovalPolicy {
+    const ACCOUNT_TYPE: AccountType = AccountType::ApprovalPolicy;
+    const LEN: usize = 1 + 32 + 32 + 8 + 1;
+}
+
+/// Lamport transfer proposed by `proposer` awaiting `approver`, the account holds `amount` on top of its rent.
+/// One pending transfer per proposer and recipient, rent is returned to the proposer on execution or cancel
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct PendingTransfer {
+    pub account_type: AccountType,
+    pub proposer: Pubkey,
+    pub approver: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl PendingTransfer {
+    pub const SEED: &'static [u8] = b"pending_transfer";
+
+    pub fn find_address(proposer: &Pubkey, to: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, proposer.as_ref(), to.as_ref()], &crate::id())
+    }
+}
+
+impl AccountState for PendingTransfer {
+    const ACCOUNT_TYPE: AccountType = AccountType::PendingTransfer;
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 1;
+}
+
+/// System account without data, receives and returns the lamports of a `SelfTest` round trip
+pub struct Canary;
+
+impl Canary {
+    pub const SEED: &'static [u8] = b"canary";
+    pub const LAMPORTS: u64 = 1;
+
+    pub fn find_address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], &crate::id())
+    }
+}
+
+#[cfg(test)]
+mod state_test {
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+    use crate::state::{
+        namespaced_seeds, AccountState, AccountType, ApprovalPolicy, Attestation, AuditLog, Bond, ClaimableBalance,
+        ClaimBitmap, Config, DepositScheme, DistributionClaim, Distributor, Dividend, Escrow, EventStream, FeeConfig,
+        FillMode, Fraction, Issuer, Keeper, ManagedMint, MatchingPool, MerkleDistribution, Migration, Milestone,
+        MilestoneContract, MilestoneStatus, MilestoneTerms, MintConfig, MintDecimals, Netting, Order, PayoutBatch,
+        PendingTransfer, PriceFeed, Registry, RegistryMode, Rental, Revocation, RosterEntry, SavingsVault,
+        SpendingLimit, SponsorPool, SponsoredWallet, Stream, TipStats, TipperStats, TransferHook, Vault,
+        VestingSchedule,
+    };
+
+    #[test]
+    fn when_serialization_bond_expect_len() {
+        let bond = Bond {
+            account_type: AccountType::Bond,
+            depositor: Pubkey::new_unique(),
+            arbiter: Pubkey::new_unique(),
+            beneficiary: Pubkey::new_unique(),
+            id: 1,
+            amount: 1_000,
+            bump: 255,
+        };
+
+        assert_eq!(bond.try_to_vec().unwrap().len(), Bond::LEN);
+    }
+
+    #[test]
+    fn when_serialization_tip_stats_expect_len() {
+        let tip_stats = TipStats {
+            account_type: AccountType::TipStats,
+            creator: Pubkey::new_unique(),
+            total: 10,
+            count: 2,
+            top_tipper: Pubkey::new_unique(),
+            top_tipper_total: 7,
+            bump: 254,
+        };
+
+        assert_eq!(tip_stats.try_to_vec().unwrap().len(), TipStats::LEN);
+    }
+
+    #[test]
+    fn when_serialization_tipper_stats_expect_len() {
+        let tipper_stats = TipperStats {
+            account_type: AccountType::TipperStats,
+            creator: Pubkey::new_unique(),
+            tipper: Pubkey::new_unique(),
+            total: 7,
+            count: 1,
+            bump: 253,
+        };
+
+        assert_eq!(tipper_stats.try_to_vec().unwrap().len(), TipperStats::LEN);
+    }
+
+    #[test]
+    fn when_serialization_matching_pool_expect_len() {
+        assert_eq!(matching_pool(10_000, 100, 1_000).try_to_vec().unwrap().len(), MatchingPool::LEN);
+    }
+
+    #[test]
+    fn when_match_amount_by_ratio_expect_ratio() {
+        assert_eq!(matching_pool(5_000, 1_000, 10_000).match_amount(600), 300);
+    }
+
+    #[test]
+    fn when_match_amount_above_cap_expect_cap() {
+        assert_eq!(matching_pool(20_000, 1_000, 10_000).match_amount(600), 1_000);
+    }
+
+    #[test]
+    fn when_match_amount_above_remaining_expect_remaining() {
+        assert_eq!(matching_pool(10_000, 1_000, 250).match_amount(600), 250);
+    }
+
+    fn matching_pool(ratio_bps: u16, cap: u64, remaining: u64) -> MatchingPool {
+        MatchingPool {
+            account_type: AccountType::MatchingPool,
+            sponsor: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            ratio_bps,
+            cap,
+            remaining,
+            matched_total: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn when_serialization_savings_vault_expect_len() {
+        assert_eq!(savings_vault(1_000).try_to_vec().unwrap().len(), SavingsVault::LEN);
+    }
+
+    #[test]
+    fn when_round_up_difference_expect_rest_to_granularity() {
+        assert_eq!(savings_vault(1_000).round_up_difference(2_300), Some(700));
+    }
+
+    #[test]
+    fn when_round_up_difference_of_multiple_expect_zero() {
+        assert_eq!(savings_vault(1_000).round_up_difference(3_000), Some(0));
+    }
+
+    #[test]
+    fn when_round_up_difference_disabled_expect_zero() {
+        assert_eq!(savings_vault(0).round_up_difference(2_300), Some(0));
+    }
+
+    #[test]
+    fn when_round_up_difference_overflow_expect_none() {
+        assert_eq!(savings_vault(1_000).round_up_difference(u64::MAX), None);
+    }
+
+    fn savings_vault(granularity: u64) -> SavingsVault {
+        SavingsVault {
+            account_type: AccountType::SavingsVault,
+            owner: Pubkey::new_unique(),
+            granularity,
+            saved: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn when_serialization_netting_expect_len() {
+        assert_eq!(netting(0, 0).try_to_vec().unwrap().len(), Netting::LEN);
+    }
+
+    #[test]
+    fn when_net_a_owes_more_expect_a_debtor() {
+        let netting = netting(900, 400);
+
+        assert_eq!(netting.net(), (netting.party_a, netting.party_b, 500));
+    }
+
+    #[test]
+    fn when_net_b_owes_more_expect_b_debtor() {
+        let netting = netting(100, 400);
+
+        assert_eq!(netting.net(), (netting.party_b, netting.party_a, 300));
+    }
+
+    #[test]
+    fn when_counterparty_of_stranger_expect_none() {
+        assert_eq!(netting(0, 0).counterparty(&Pubkey::new_unique()), None);
+    }
+
+    #[test]
+    fn when_serialization_transfer_hook_expect_len() {
+        let transfer_hook = TransferHook {
+            account_type: AccountType::TransferHook,
+            owner: Pubkey::new_unique(),
+            program: Pubkey::new_unique(),
+            bump: 255,
+        };
+
+        assert_eq!(transfer_hook.try_to_vec().unwrap().len(), TransferHook::LEN);
+    }
+
+    #[test]
+    fn when_serialization_config_expect_len() {
+        let config = Config {
+            account_type: AccountType::Config,
+            admin: Pubkey::new_unique(),
+            strict_ata: true,
+            features: Config::DEFAULT_FEATURES,
+            travel_rule_required: true,
+            travel_rule_threshold: 1_000_000,
+            paused: true,
+            deposit_programs: [Pubkey::new_unique(); 4],
+            bump: 255,
+        };
+
+        assert_eq!(config.try_to_vec().unwrap().len(), Config::LEN);
+    }
+
+    #[test]
+    fn when_serialization_payout_batch_expect_len() {
+        assert_eq!(payout_batch().try_to_vec().unwrap().len(), PayoutBatch::LEN);
+    }
+
+    #[test]
+    fn when_set_executed_expect_only_that_leaf() {
+        let mut payout_batch = payout_batch();
+
+        payout_batch.set_executed(9);
+
+        assert!(payout_batch.is_executed(9));
+        assert!(!payout_batch.is_executed(8));
+        assert!(!payout_batch.is_executed(PayoutBatch::MAX_LEAVES - 1));
+    }
+
+    fn payout_batch() -> PayoutBatch {
+        PayoutBatch {
+            account_type: AccountType::PayoutBatch,
+            authority: Pubkey::new_unique(),
+            id: 1,
+            root: [7; 32],
+            leaf_count: 10,
+            executed_count: 0,
+            executed_total: 0,
+            executed: [0; 128],
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn when_serialization_distributor_expect_len() {
+        let distributor = Distributor {
+            account_type: AccountType::Distributor,
+            authority: Pubkey::new_unique(),
+            attestor: Pubkey::new_unique(),
+            id: 1,
+            claimed_total: 1_000,
+            claim_count: 2,
+            bump: 255,
+        };
+
+        assert_eq!(distributor.try_to_vec().unwrap().len(), Distributor::LEN);
+    }
+
+    #[test]
+    fn when_serialization_distribution_claim_expect_len() {
+        let distribution_claim = DistributionClaim {
+            account_type: AccountType::DistributionClaim,
+            distributor: Pubkey::new_unique(),
+            claimer: Pubkey::new_unique(),
+            amount: 500,
+            bump: 255,
+        };
+
+        assert_eq!(distribution_claim.try_to_vec().unwrap().len(), DistributionClaim::LEN);
+    }
+
+    #[test]
+    fn when_serialization_attestation_expect_len() {
+        let attestation = attestation(2, 1_700_000_000);
+
+        assert_eq!(attestation.try_to_vec().unwrap().len(), Attestation::LEN);
+    }
+
+    #[test]
+    fn when_attestation_checked_expect_level_and_expiry() {
+        let attested = attestation(2, 1_700_000_000);
+        let revoked = attestation(0, 1_700_000_000);
+
+        assert!(attested.satisfies(2, 1_699_999_999));
+        assert!(!attested.satisfies(3, 1_699_999_999));
+        assert!(!attested.satisfies(1, 1_700_000_000));
+        assert!(!revoked.satisfies(0, 0));
+    }
+
+    #[test]
+    fn when_serialization_mint_config_expect_len() {
+        let mint_config = MintConfig {
+            account_type: AccountType::MintConfig,
+            mint: Pubkey::new_unique(),
+            issuer: Pubkey::new_unique(),
+            soulbound: true,
+            bump: 255,
+        };
+
+        assert_eq!(mint_config.try_to_vec().unwrap().len(), MintConfig::LEN);
+    }
+
+    #[test]
+    fn when_serialization_rental_expect_len() {
+        let rental = Rental {
+            account_type: AccountType::Rental,
+            owner: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            renter: Pubkey::new_unique(),
+            price: 1_000,
+            duration: 86_400,
+            expires_at: 1_700_000_000,
+            bump: 255,
+        };
+
+        assert_eq!(rental.try_to_vec().unwrap().len(), Rental::LEN);
+    }
+
+    #[test]
+    fn when_serialization_fraction_expect_len() {
+        let fraction = Fraction {
+            account_type: AccountType::Fraction,
+            depositor: Pubkey::new_unique(),
+            nft_mint: Pubkey::new_unique(),
+            share_mint: Pubkey::new_unique(),
+            shares: 1_000_000,
+            bump: 255,
+        };
+
+        assert_eq!(fraction.try_to_vec().unwrap().len(), Fraction::LEN);
+    }
+
+    #[test]
+    fn when_serialization_order_expect_len() {
+        let order = order(1_000, 3_000);
+
+        assert_eq!(order.try_to_vec().unwrap().len(), Order::LEN);
+    }
+
+    #[test]
+    fn when_partial_fill_expect_price_rounded_up() {
+        let overflowing = order(1, u64::MAX);
+        let order = order(1_000, 3_001);
+
+        assert_eq!(order.price_of(1_000), Some(3_001));
+        assert_eq!(order.price_of(500), Some(1_501));
+        assert_eq!(order.price_of(1), Some(4));
+        assert_eq!(overflowing.price_of(2), None);
+    }
+
+    #[test]
+    fn when_serialization_price_feed_expect_len() {
+        let price_feed = PriceFeed {
+            account_type: AccountType::PriceFeed,
+            mint: Pubkey::new_unique(),
+            price: 1_250_000,
+            updated_at: 1_700_000_000,
+            bump: 255,
+        };
+
+        assert_eq!(price_feed.try_to_vec().unwrap().len(), PriceFeed::LEN);
+    }
+
+    #[test]
+    fn when_serialization_keeper_expect_len() {
+        let keeper = Keeper {
+            account_type: AccountType::Keeper,
+            keeper: Pubkey::new_unique(),
+            executions: 12,
+            bounties: 5_000,
+            banned: false,
+            bump: 255,
+        };
+
+        assert_eq!(keeper.try_to_vec().unwrap().len(), Keeper::LEN);
+    }
+
+    #[test]
+    fn when_serialization_vault_expect_len() {
+        let vault = Vault {
+            account_type: AccountType::Vault,
+            owner: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            bump: 255,
+        };
+
+        assert_eq!(vault.try_to_vec().unwrap().len(), Vault::LEN);
+    }
+
+    #[test]
+    fn when_serialization_escrow_expect_len() {
+        let escrow = Escrow {
+            account_type: AccountType::Escrow,
+            initializer: Pubkey::new_unique(),
+            temp_token: Pubkey::new_unique(),
+            receive_token: Pubkey::new_unique(),
+            expected_amount: 3_000,
+            expires_at: 1_700_000_000,
+            bump: 255,
+        };
+
+        assert_eq!(escrow.try_to_vec().unwrap().len(), Escrow::LEN);
+    }
+
+    fn fee_config(fee_bps: u16) -> FeeConfig {
+        FeeConfig {
+            account_type: AccountType::FeeConfig,
+            admin: Pubkey::new_unique(),
+            fee_bps,
+            fee_recipient: Pubkey::new_unique(),
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn when_serialization_fee_config_expect_len() {
+        assert_eq!(fee_config(30).try_to_vec().unwrap().len(), FeeConfig::LEN);
+    }
+
+    #[test]
+    fn when_fee_expect_rounded_down() {
+        assert_eq!(fee_config(30).fee(1_000), 3);
+        assert_eq!(fee_config(30).fee(333), 0);
+        assert_eq!(fee_config(FeeConfig::MAX_FEE_BPS).fee(u64::MAX), u64::MAX);
+        assert_eq!(fee_config(0).fee(u64::MAX), 0);
+    }
+
+    fn vesting(claimed_amount: u64) -> VestingSchedule {
+        VestingSchedule {
+            account_type: AccountType::VestingSchedule,
+            creator: Pubkey::new_unique(),
+            beneficiary: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            total_amount: 1_000,
+            claimed_amount,
+            start_ts: 1_000,
+            cliff_ts: 1_250,
+            end_ts: 2_000,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn when_serialization_vesting_schedule_expect_len() {
+        assert_eq!(vesting(0).try_to_vec().unwrap().len(), VestingSchedule::LEN);
+    }
+
+    #[test]
+    fn when_vested_amount_expect_linear_after_cliff() {
+        let vesting = vesting(0);
+
+        assert_eq!(vesting.vested_amount(1_249), 0);
+        assert_eq!(vesting.vested_amount(1_250), 250);
+        assert_eq!(vesting.vested_amount(1_999), 999);
+        assert_eq!(vesting.vested_amount(2_000), 1_000);
+        assert_eq!(vesting.vested_amount(i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn when_vested_amount_extreme_timestamps_expect_no_overflow() {
+        let vesting = VestingSchedule { start_ts: i64::MIN, cliff_ts: i64::MIN, end_ts: i64::MAX, ..vesting(0) };
+
+        assert!(VestingSchedule::is_valid_schedule(vesting.start_ts, vesting.cliff_ts, vesting.end_ts));
+        assert_eq!(vesting.vested_amount(i64::MIN), 0);
+        assert_eq!(vesting.vested_amount(0), 500);
+        assert_eq!(vesting.vested_amount(i64::MAX - 1), 999);
+        assert_eq!(vesting.vested_amount(i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn when_claimable_amount_expect_claimed_excluded() {
+        assert_eq!(vesting(400).claimable_amount(1_500), 100);
+        assert_eq!(vesting(400).claimable_amount(1_300), 0);
+        assert_eq!(vesting(1_000).claimable_amount(2_000), 0);
+    }
+
+    #[test]
+    fn when_schedule_expect_cliff_between_start_and_end() {
+        assert!(VestingSchedule::is_valid_schedule(0, 0, 1));
+        assert!(VestingSchedule::is_valid_schedule(0, 1, 1));
+        assert!(!VestingSchedule::is_valid_schedule(1, 1, 1));
+        assert!(!VestingSchedule::is_valid_schedule(1, 0, 2));
+        assert!(!VestingSchedule::is_valid_schedule(0, 3, 2));
+    }
+
+    #[test]
+    fn when_serialization_sponsor_pool_expect_len() {
+        let pool = SponsorPool {
+            account_type: AccountType::SponsorPool,
+            sponsor: Pubkey::new_unique(),
+            operator: Pubkey::new_unique(),
+            max_per_wallet: 3,
+            remaining: 1_000_000,
+            sponsored_count: 0,
+            bump: 255,
+        };
+
+        assert_eq!(pool.try_to_vec().unwrap().len(), SponsorPool::LEN);
+    }
+
+    #[test]
+    fn when_serialization_sponsored_wallet_expect_len() {
+        let sponsored_wallet = SponsoredWallet {
+            account_type: AccountType::SponsoredWallet,
+            pool: Pubkey::new_unique(),
+            wallet: Pubkey::new_unique(),
+            count: 1,
+            bump: 255,
+        };
+
+        assert_eq!(sponsored_wallet.try_to_vec().unwrap().len(), SponsoredWallet::LEN);
+    }
+
+    #[test]
+    fn when_serialization_claimable_balance_expect_len() {
+        let claimable = ClaimableBalance {
+            account_type: AccountType::ClaimableBalance,
+            wallet: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            amount: 1_000,
+            payer: Pubkey::new_unique(),
+            bump: 255,
+        };
+
+        assert_eq!(claimable.try_to_vec().unwrap().len(), ClaimableBalance::LEN);
+    }
+
+    #[test]
+    fn when_serialization_revocation_expect_len() {
+        let revocation = Revocation {
+            account_type: AccountType::Revocation,
+            scope: Pubkey::default(),
+            delegate: Pubkey::new_unique(),
+            revoked_by: Pubkey::new_unique(),
+            revoked_at: 1_700_000_000,
+            bump: 255,
+        };
+
+        assert_eq!(revocation.try_to_vec().unwrap().len(), Revocation::LEN);
+    }
+
+    #[test]
+    fn when_global_revocation_expect_default_scope() {
+        let authority = Pubkey::new_unique();
+
+        assert_eq!(Revocation::scope(&authority, true), Pubkey::default());
+        assert_eq!(Revocation::scope(&authority, false), authority);
+    }
+
+    #[test]
+    fn when_serialization_roster_entry_expect_len() {
+        let roster_entry = RosterEntry {
+            account_type: AccountType::RosterEntry,
+            payer: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            registered_at: 1_700_000_000,
+            bump: 255,
+        };
+
+        assert_eq!(roster_entry.try_to_vec().unwrap().len(), RosterEntry::LEN);
+    }
+
+    fn milestone_contract(statuses: &[MilestoneStatus]) -> MilestoneContract {
+        let mut milestones = [Milestone::EMPTY; MilestoneContract::MAX_MILESTONES];
+        for (milestone, status) in milestones.iter_mut().zip(statuses) {
+            milestone.terms = MilestoneTerms { amount: 1_000, deliverable_hash: [1; 32] };
+            milestone.status = *status;
+        }
+        MilestoneContract {
+            account_type: AccountType::MilestoneContract,
+            funder: Pubkey::new_unique(),
+            payee: Pubkey::new_unique(),
+            arbiter: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            id: 1,
+            released_total: 0,
+            milestone_count: statuses.len() as u8,
+            milestones,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn when_serialization_milestone_contract_expect_len() {
+        let contract = milestone_contract(&[MilestoneStatus::Pending; 3]);
+
+        assert_eq!(contract.try_to_vec().unwrap().len(), MilestoneContract::LEN);
+    }
+
+    #[test]
+    fn when_milestone_terms_invalid_expect_no_total() {
+        let terms = |amount| MilestoneTerms { amount, deliverable_hash: [1; 32] };
+
+        assert_eq!(MilestoneContract::total_amount(&[terms(100), terms(200)]), Some(300));
+        assert_eq!(MilestoneContract::total_amount(&[]), None);
+        assert_eq!(MilestoneContract::total_amount(&[terms(100), terms(0)]), None);
+        assert_eq!(MilestoneContract::total_amount(&[terms(u64::MAX), terms(1)]), None);
+        assert_eq!(MilestoneContract::total_amount(&[terms(1); MilestoneContract::MAX_MILESTONES + 1]), None);
+    }
+
+    #[test]
+    fn when_milestones_released_expect_completed() {
+        let mut contract = milestone_contract(&[MilestoneStatus::Released, MilestoneStatus::Submitted]);
+
+        assert!(!contract.is_completed());
+        assert!(contract.milestone_mut(2).is_none());
+        contract.milestone_mut(1).unwrap().status = MilestoneStatus::Released;
+        assert!(contract.is_completed());
+    }
+
+    #[test]
+    fn when_serialization_audit_log_expect_len() {
+        let audit_log = audit_log([1; 32]);
+
+        assert_eq!(audit_log.try_to_vec().unwrap().len(), AuditLog::LEN);
+    }
+
+    #[test]
+    fn when_serialization_event_stream_expect_len() {
+        let event_stream = EventStream {
+            account_type: AccountType::EventStream,
+            key: Pubkey::new_unique(),
+            sequence: 7,
+            bump: 255,
+        };
+
+        assert_eq!(event_stream.try_to_vec().unwrap().len(), EventStream::LEN);
+    }
+
+    #[test]
+    fn when_event_stream_next_expect_sequence_counted() {
+        let mut event_stream = EventStream {
+            account_type: AccountType::EventStream,
+            key: Pubkey::new_unique(),
+            sequence: u64::MAX - 1,
+            bump: 255,
+        };
+
+        assert_eq!(event_stream.next_sequence(), Some(u64::MAX - 1));
+        assert_eq!(event_stream.sequence, u64::MAX);
+        assert_eq!(event_stream.next_sequence(), None);
+    }
+
+    #[test]
+    fn when_folded_in_other_order_expect_other_head() {
+        let accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let (mut folded, mut replayed, mut reordered) = (audit_log([0; 32]), audit_log([0; 32]), audit_log([0; 32]));
+
+        folded.fold(0, &accounts, 100, 7);
+        folded.fold(1, &accounts[..1], 0, 8);
+        replayed.fold(0, &accounts, 100, 7);
+        replayed.fold(1, &accounts[..1], 0, 8);
+        reordered.fold(1, &accounts[..1], 0, 8);
+        reordered.fold(0, &accounts, 100, 7);
+
+        assert_eq!(folded, replayed);
+        assert_ne!(folded.head, reordered.head);
+        assert_eq!((folded.count, folded.last_slot), (2, 8));
+    }
+
+    #[test]
+    fn when_serialization_mint_decimals_expect_len() {
+        let mint_decimals = MintDecimals {
+            account_type: AccountType::MintDecimals,
+            mint: Pubkey::new_unique(),
+            token_program: spl_token::id(),
+            decimals: 9,
+            bump: 255,
+        };
+
+        assert_eq!(mint_decimals.try_to_vec().unwrap().len(), MintDecimals::LEN);
+    }
+
+    #[test]
+    fn when_serialization_full_registry_expect_len() {
+        let mut registry = registry(RegistryMode::Allow);
+        registry.entries = (0..Registry::MAX_ENTRIES).map(|_| Pubkey::new_unique()).collect();
+
+        assert_eq!(registry.try_to_vec().unwrap().len(), Registry::LEN);
+    }
+
+    #[test]
+    fn when_registry_mode_expect_entries_allowed_or_blocked() {
+        let (entry, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut allowlist, mut blocklist) = (registry(RegistryMode::Allow), registry(RegistryMode::Block));
+        allowlist.add(entry);
+        blocklist.add(entry);
+
+        assert!(allowlist.permits(&entry) && !allowlist.permits(&other));
+        assert!(!blocklist.permits(&entry) && blocklist.permits(&other));
+    }
+
+    #[test]
+    fn when_registry_add_twice_or_remove_expect_unique_entries() {
+        let entry = Pubkey::new_unique();
+        let mut registry = registry(RegistryMode::Allow);
+
+        assert!(registry.add(entry) && registry.add(entry));
+        assert_eq!(registry.entries, vec![entry]);
+        registry.remove(&entry);
+        registry.remove(&entry);
+        assert!(registry.entries.is_empty());
+    }
+
+    #[test]
+    fn when_registry_full_expect_add_rejected() {
+        let mut registry = registry(RegistryMode::Block);
+        registry.entries = (0..Registry::MAX_ENTRIES).map(|_| Pubkey::new_unique()).collect();
+
+        assert!(!registry.add(Pubkey::new_unique()));
+        assert!(registry.add(registry.entries[0]));
+        assert_eq!(registry.entries.len(), Registry::MAX_ENTRIES);
+    }
+
+    #[test]
+    fn when_deposit_program_added_twice_or_removed_expect_unique_slot() {
+        let program = Pubkey::new_unique();
+        let mut config = config();
+
+        assert!(config.add_deposit_program(program) && config.add_deposit_program(program));
+        assert_eq!(config.deposit_programs.iter().filter(|slot| **slot == program).count(), 1);
+        assert!(config.is_deposit_program(&program) && !config.is_deposit_program(&Pubkey::default()));
+        config.remove_deposit_program(&program);
+        assert!(!config.is_deposit_program(&program));
+    }
+
+    #[test]
+    fn when_deposit_programs_full_expect_add_rejected() {
+        let mut config = config();
+        for slot in config.deposit_programs.iter_mut() {
+            *slot = Pubkey::new_unique();
+        }
+
+        assert!(!config.add_deposit_program(Pubkey::new_unique()));
+        assert!(config.add_deposit_program(config.deposit_programs[0]));
+    }
+
+    #[test]
+    fn when_serialization_migration_expect_len() {
+        assert_eq!(migration().try_to_vec().unwrap().len(), Migration::LEN);
+    }
+
+    #[test]
+    fn when_folded_in_any_order_expect_sides_match() {
+        let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let vault = Vault { account_type: AccountType::Vault, owner, mint, bump: 255 };
+        let escrow = Escrow {
+            account_type: AccountType::Escrow,
+            initializer: Pubkey::new_unique(),
+            temp_token: Pubkey::new_unique(),
+            receive_token: Pubkey::new_unique(),
+            expected_amount: 3_000,
+            expires_at: 1_700_000_000,
+            bump: 255,
+        };
+        let (mut export, mut import) = (migration(), migration());
+        export.fold(vault.migration_record());
+        export.fold(escrow.migration_record());
+        import.fold(escrow.migration_record());
+
+        assert!(!export.matches(&import));
+        import.fold(Vault { account_type: AccountType::Vault, owner, mint, bump: 254 }.migration_record());
+        assert!(export.matches(&import));
+        assert_ne!(escrow.migration_record(), Escrow { expected_amount: 1, ..escrow }.migration_record());
+    }
+
+    #[test]
+    fn when_default_namespace_expect_seeds_unchanged() {
+        let (owner, mint, namespace) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (namespace_config, _) = Config::find_namespaced_address(&namespace);
+        let keys = [owner.as_ref(), mint.as_ref()];
+
+        assert_eq!(namespaced_seeds(Vault::SEED, None, &keys), vec![Vault::SEED, owner.as_ref(), mint.as_ref()]);
+        assert_eq!(
+            namespaced_seeds(Vault::SEED, Some(&namespace_config), &keys),
+            vec![Vault::SEED, namespace_config.as_ref(), owner.as_ref(), mint.as_ref()],
+        );
+        let namespaced_vault = Vault::find_namespaced_address(&namespace_config, &owner, &mint);
+        assert_ne!(namespaced_vault, Vault::find_address(&owner, &mint));
+        assert_ne!(namespace_config, Config::find_address().0);
+    }
+
+    #[test]
+    fn when_serialization_spending_limit_expect_len() {
+        assert_eq!(spending_limit(1_000).try_to_vec().unwrap().len(), SpendingLimit::LEN);
+    }
+
+    #[test]
+    fn when_spend_within_window_expect_limit_enforced() {
+        let mut limit = spending_limit(1_000);
+
+        assert!(limit.spend(600, 1_010));
+        assert!(!limit.spend(401, 1_020));
+        assert_eq!(limit.spent(1_020), 600);
+        assert!(limit.spend(400, 1_099));
+        assert!(!limit.spend(1, 1_099));
+    }
+
+    #[test]
+    fn when_window_rolls_expect_only_elapsed_spends_released() {
+        let mut limit = spending_limit(1_000);
+        assert_eq!(limit.bucket_seconds(), 5);
+
+        assert!(limit.spend(600, 1_000));
+        assert!(limit.spend(400, 1_050));
+        assert!(!limit.spend(1, 1_104));
+        assert!(!limit.spend(601, 1_105));
+        assert!(limit.spend(600, 1_105));
+        assert_eq!(limit.spent(1_149), 1_000);
+        assert_eq!(limit.spent(1_155), 600);
+        assert!(limit.spend(1_000, 1_210));
+        assert!(!limit.spend(1, 1_309));
+        assert_eq!(limit.spent(5_000), 0);
+    }
+
+    #[test]
+    fn when_set_window_expect_spent_kept() {
+        let mut limit = spending_limit(1_000);
+        assert!(limit.spend(700, 1_000));
+
+        limit.set_window(1_000, 1_050);
+
+        assert_eq!(limit.spent(1_900), 700);
+        assert!(!limit.spend(301, 1_900));
+        assert!(limit.spend(1_000, 2_100));
+    }
+
+    #[test]
+    fn when_serialization_approval_policy_expect_len() {
+        assert_eq!(approval_policy(1_000).try_to_vec().unwrap().len(), ApprovalPolicy::LEN);
+    }
+
+    #[test]
+    fn when_amount_above_threshold_expect_approval_required() {
+        let policy = approval_policy(1_000);
+
+        assert!(!policy.requires_approval(1_000));
+        assert!(policy.requires_approval(1_001));
+    }
+
+    #[test]
+    fn when_serialization_pending_transfer_expect_len() {
+        let pending_transfer = PendingTransfer {
+            account_type: AccountType::PendingTransfer,
+            proposer: Pubkey::new_unique(),
+            approver: Pubkey::new_unique(),
+            to: Pubkey::new_unique(),
+            amount: 5_000,
+            bump: 255,
+        };
+
+        assert_eq!(pending_transfer.try_to_vec().unwrap().len(), PendingTransfer::LEN);
+    }
+
+    #[test]
+    fn when_serialization_merkle_distribution_expect_len() {
+        let distribution = MerkleDistribution {
+            account_type: AccountType::MerkleDistribution,
+            authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            root: [7; 32],
+            leaf_count: 1_000,
+            total_amount: 1_000_000,
+            claimed_total: 0,
+            claimed_count: 0,
+            bump: 255,
+        };
+
+        assert_eq!(distribution.try_to_vec().unwrap().len(), MerkleDistribution::LEN);
+    }
+
+    #[test]
+    fn when_serialization_deposit_scheme_expect_len() {
+        let scheme = DepositScheme {
+            account_type: AccountType::DepositScheme,
+            authority: Pubkey::new_unique(),
+            omnibus: Pubkey::new_unique(),
+            bump: 255,
+        };
+
+        assert_eq!(scheme.try_to_vec().unwrap().len(), DepositScheme::LEN);
+    }
+
+    #[test]
+    fn when_deposit_addresses_expect_distinct_per_user_and_scheme() {
+        let (scheme, other_scheme) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let (address, _) = DepositScheme::find_deposit_address(&scheme, 7);
+
+        assert_eq!(DepositScheme::find_deposit_address(&scheme, 7).0, address);
+        assert_ne!(DepositScheme::find_deposit_address(&scheme, 8).0, address);
+        assert_ne!(DepositScheme::find_deposit_address(&other_scheme, 7).0, address);
+    }
+
+    #[test]
+    fn when_serialization_managed_mint_expect_len() {
+        let managed_mint = ManagedMint {
+            account_type: AccountType::ManagedMint,
+            mint: Pubkey::new_unique(),
+            admin: Pubkey::new_unique(),
+            supply_cap: u64::MAX,
+            issued: u64::MAX,
+            redeemed: u64::MAX,
+            bump: 255,
+        };
+
+        assert_eq!(managed_mint.try_to_vec().unwrap().len(), ManagedMint::LEN);
+    }
+
+    #[test]
+    fn when_serialization_issuer_expect_len() {
+        let issuer = Issuer {
+            account_type: AccountType::Issuer,
+            managed_mint: Pubkey::new_unique(),
+            issuer: Pubkey::new_unique(),
+            roles: Issuer::ROLE_ISSUE | Issuer::ROLE_REDEEM,
+            allowance: u64::MAX,
+            issued: u64::MAX,
+            redeemed: u64::MAX,
+            bump: 255,
+        };
+
+        assert_eq!(issuer.try_to_vec().unwrap().len(), Issuer::LEN);
+        assert!(issuer.has_role(Issuer::ROLE_REDEEM));
+        assert!(!Issuer { roles: Issuer::ROLE_ISSUE, ..issuer }.has_role(Issuer::ROLE_REDEEM));
+    }
+
+    #[test]
+    fn when_set_claimed_expect_only_that_leaf_claimed() {
+        let mut data = vec![AccountType::ClaimBitmap as u8; ClaimBitmap::len(10)];
+        data[1..].fill(0);
+
+        ClaimBitmap::set_claimed(&mut data, 9);
+
+        assert_eq!(data.len(), 3);
+        assert!(ClaimBitmap::is_claimed(&data, 9));
+        assert!((0..9).all(|index| !ClaimBitmap::is_claimed(&data, index)));
+        assert_eq!(ClaimBitmap::len(MerkleDistribution::MAX_LEAVES), 8_193);
+    }
+
+    fn dividend(pool_amount: u64, snapshot_supply: u64) -> Dividend {
+        Dividend {
+            account_type: AccountType::Dividend,
+            admin: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            snapshot_mint: Pubkey::new_unique(),
+            root: [7; 32],
+            holder_count: 3,
+            snapshot_supply,
+            pool_amount,
+            claimed_total: 0,
+            claimed_count: 0,
+            claim_deadline: 1_700_000_000,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn when_serialization_dividend_expect_len() {
+        assert_eq!(dividend(1_000, 3_000).try_to_vec().unwrap().len(), Dividend::LEN);
+    }
+
+    #[test]
+    fn when_share_expect_pro_rata_rounded_down() {
+        let pool = dividend(1_000, 3_000);
+
+        let shares: Vec<Option<u64>> = [1_000, 1_500, 500, 0, 3_001].iter().map(|b| pool.share(*b)).collect();
+
+        assert_eq!(shares, vec![Some(333), Some(500), Some(166), Some(0), None]);
+        assert_eq!(dividend(u64::MAX, u64::MAX).share(u64::MAX), Some(u64::MAX));
+        assert_eq!(dividend(1_000, 0).share(0), None);
+    }
+
+    fn approval_policy(threshold: u64) -> ApprovalPolicy {
+        ApprovalPolicy {
+            account_type: AccountType::ApprovalPolicy,
+            authority: Pubkey::new_unique(),
+            approver: Pubkey::new_unique(),
+            threshold,
+            bump: 255,
+        }
+    }
+
+    fn spending_limit(limit: u64) -> SpendingLimit {
+        SpendingLimit {
+            account_type: AccountType::SpendingLimit,
+            authority: Pubkey::new_unique(),
+            mint: Pubkey::default(),
+            limit,
+            window_seconds: 100,
+            spent: [0; SpendingLimit::BUCKETS],
+            last_bucket: 0,
+            bump: 255,
+        }
+    }
+
+    fn migration() -> Migration {
+        Migration {
+            account_type: AccountType::Migration,
+            admin: Pubkey::new_unique(),
+            peer: Pubkey::new_unique(),
+            commitment: [0; 32],
+            count: 0,
+            bump: 255,
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            account_type: AccountType::Config,
+            admin: Pubkey::new_unique(),
+            strict_ata: false,
+            features: Config::DEFAULT_FEATURES,
+            travel_rule_required: false,
+            travel_rule_threshold: 0,
+            paused: false,
+            deposit_programs: [Pubkey::default(); 4],
+            bump: 255,
+        }
+    }
+
+    fn registry(mode: RegistryMode) -> Registry {
+        Registry { account_type: AccountType::Registry, admin: Pubkey::new_unique(), mode, bump: 255, entries: vec![] }
+    }
+
+    fn audit_log(head: [u8; 32]) -> AuditLog {
+        AuditLog { account_type: AccountType::AuditLog, head, count: 0, last_slot: 0, bump: 255 }
+    }
+
+    fn stream(deposit: u64, rate_per_second: u64, withdrawn: u64) -> Stream {
+        Stream {
+            account_type: AccountType::Stream,
+            sender: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            mint: Pubkey::default(),
+            deposit,
+            rate_per_second,
+            start_ts: 1_000,
+            withdrawn,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn when_serialization_stream_expect_len() {
+        assert_eq!(stream(1_000, 10, 0).try_to_vec().unwrap().len(), Stream::LEN);
+    }
+
+    #[test]
+    fn when_streamed_amount_expect_rate_by_elapsed_up_to_deposit() {
+        let stream = stream(1_000, 10, 0);
+
+        assert_eq!(stream.streamed_amount(i64::MIN), 0);
+        assert_eq!(stream.streamed_amount(1_000), 0);
+        assert_eq!(stream.streamed_amount(1_050), 500);
+        assert_eq!(stream.streamed_amount(1_100), 1_000);
+        assert_eq!(stream.streamed_amount(i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn when_streamed_amount_overflow_expect_deposit() {
+        let mut stream = stream(u64::MAX, u64::MAX, 0);
+        stream.start_ts = i64::MIN;
+
+        assert_eq!(stream.streamed_amount(i64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn when_withdrawable_amount_expect_withdrawn_excluded() {
+        assert_eq!(stream(1_000, 10, 300).withdrawable_amount(1_050), 200);
+        assert_eq!(stream(1_000, 10, 1_000).withdrawable_amount(1_200), 0);
+    }
+
+    fn order(sell_total: u64, buy_total: u64) -> Order {
+        Order {
+            account_type: AccountType::Order,
+            maker: Pubkey::new_unique(),
+            id: 1,
+            sell_mint: Pubkey::new_unique(),
+            buy_mint: Pubkey::new_unique(),
+            sell_total,
+            buy_total,
+            remaining: sell_total,
+            fill_mode: FillMode::Partial,
+            bump: 255,
+        }
+    }
+
+    fn attestation(level: u8, expires_at: i64) -> Attestation {
+        Attestation {
+            account_type: AccountType::Attestation,
+            wallet: Pubkey::new_unique(),
+            level,
+            expires_at,
+            bump: 255,
+        }
+    }
+
+    fn netting(a_owes_b: u64, b_owes_a: u64) -> Netting {
+        Netting {
+            account_type: AccountType::Netting,
+            party_a: Pubkey::new_unique(),
+            party_b: Pubkey::new_unique(),
+            id: 1,
+            period_end: 1_700_000_000,
+            a_owes_b,
+            b_owes_a,
+            obligation_count: 2,
+            bump: 255,
+        }
+    }
+}
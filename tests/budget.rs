@@ -0,0 +1,281 @@
+#![cfg(feature = "test-bpf")]
+//! Binary size and compute unit budget of the BPF program.
+//!
+//! Every scenario runs a single instruction against accounts preloaded into the bank, so only the
+//! measured transaction executes BPF code. A scenario passes while it succeeds under its recorded
+//! compute units plus `TOLERANCE_PERCENT`; the binary is held to its recorded size the same way.
+//! Keys are derived from fixed seeds, so PDA bump searches and costs do not vary between runs.
+//!
+//! Budget is re-recorded into `BUDGET_FILE` after a deliberate change with
+//! `UPDATE_BUDGET=1 cargo test-bpf --test budget`, each scenario cost is found by binary search
+//! over the compute unit limit. An entry listed without a value is measured and written back by the
+//! next run, so a new scenario is added to the file by its name alone.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use solana_program::instruction::Instruction;
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_program;
+use solana_program_test::ProgramTest;
+use solana_sdk::account::Account;
+use solana_sdk::signature::{keypair_from_seed, Keypair};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use token::id;
+use token::instruction::TokenInstruction;
+
+const BUDGET_FILE: &str = "tests/budget.txt";
+const BINARY_SIZE: &str = "binary_size";
+const TOLERANCE_PERCENT: u64 = 10;
+/// Upper bound of the compute unit search, default limit of a transaction
+const MAX_UNITS: u64 = 200_000;
+const LAMPORTS: u64 = 1_000_000_000;
+
+struct Scenario {
+    name: &'static str,
+    accounts: Vec<(Pubkey, Account)>,
+    instruction: Instruction,
+    signers: Vec<Keypair>,
+}
+
+impl Scenario {
+    async fn succeeds(&self, max_units: u64) -> bool {
+        let mut program_test = ProgramTest::new("token", id(), None);
+        program_test.set_compute_max_units(max_units);
+        for (key, account) in &self.accounts {
+            program_test.add_account(*key, account.clone());
+        }
+        let (mut banks_client, payer, blockhash) = program_test.start().await;
+
+        let signers: Vec<&Keypair> = std::iter::once(&payer).chain(&self.signers).collect();
+        let tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&self.instruction),
+            Some(&payer.pubkey()),
+            &signers,
+            blockhash,
+        );
+        banks_client.process_transaction(tx).await.is_ok()
+    }
+
+    /// Lowest compute unit limit the instruction succeeds under
+    async fn measure(&self) -> u64 {
+        assert!(self.succeeds(MAX_UNITS).await, "{}: fails under default compute unit limit", self.name);
+        let (mut low, mut high) = (0, MAX_UNITS);
+        while high - low > 1 {
+            let middle = (low + high) / 2;
+            if self.succeeds(middle).await {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        high
+    }
+}
+
+fn keypair(seed: u8) -> Keypair {
+    keypair_from_seed(&[seed; 32]).unwrap()
+}
+
+fn wallet() -> Account {
+    Account::new(LAMPORTS, 0, &system_program::id())
+}
+
+fn packed<T: Pack>(state: T) -> Account {
+    let mut account = Account::new(Rent::default().minimum_balance(T::LEN), T::LEN, &spl_token::id());
+    T::pack(state, &mut account.data).unwrap();
+    account
+}
+
+fn mint(authority: &Pubkey) -> Account {
+    packed(spl_token::state::Mint {
+        mint_authority: COption::Some(*authority),
+        supply: LAMPORTS,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    })
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    packed(spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    })
+}
+
+fn scenarios() -> Vec<Scenario> {
+    let (from, to) = (keypair(1), keypair(2));
+    let (mint_authority, mint_key) = (keypair(3), keypair(4).pubkey());
+    let (from_spl_token, to_spl_token) = (keypair(5).pubkey(), keypair(6).pubkey());
+    let recipients = [keypair(7).pubkey(), keypair(8).pubkey(), keypair(9).pubkey()];
+    let token_accounts = || vec![
+        (from.pubkey(), wallet()),
+        (mint_key, mint(&mint_authority.pubkey())),
+        (from_spl_token, token_account(&mint_key, &from.pubkey(), LAMPORTS)),
+        (to_spl_token, token_account(&mint_key, &to.pubkey(), 0)),
+    ];
+    let transfers: Vec<(Pubkey, u64)> = recipients.iter().map(|recipient| (*recipient, 1_000)).collect();
+
+    vec![
+        Scenario {
+            name: "transfer_lamports",
+            accounts: vec![(from.pubkey(), wallet())],
+            instruction: TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), 1_000),
+            signers: vec![keypair(1)],
+        },
+        Scenario {
+            name: "transfer_lamports_batch",
+            accounts: vec![(from.pubkey(), wallet())],
+            instruction: TokenInstruction::transfer_lamports_batch(from.pubkey(), &transfers),
+            signers: vec![keypair(1)],
+        },
+        Scenario {
+            name: "transfer_spl_token",
+            accounts: token_accounts(),
+            instruction: TokenInstruction::transfer_spl_token(
+                from.pubkey(),
+                from_spl_token,
+                to_spl_token,
+                mint_key,
+                1_000,
+            ),
+            signers: vec![keypair(1)],
+        },
+        Scenario {
+            name: "transfer_spl_token_with_ata_creation",
+            accounts: token_accounts(),
+            instruction: TokenInstruction::transfer_spl_token_with_ata_creation(
+                from.pubkey(),
+                from_spl_token,
+                to.pubkey(),
+                mint_key,
+                1_000,
+            ),
+            signers: vec![keypair(1)],
+        },
+        Scenario {
+            name: "mint_spl_token",
+            accounts: token_accounts(),
+            instruction: TokenInstruction::mint_spl_token(mint_authority.pubkey(), mint_key, to_spl_token, 1_000),
+            signers: vec![keypair(3)],
+        },
+        Scenario {
+            name: "burn_spl_token",
+            accounts: token_accounts(),
+            instruction: TokenInstruction::burn_spl_token(from.pubkey(), from_spl_token, mint_key, 1_000),
+            signers: vec![keypair(1)],
+        },
+        Scenario {
+            name: "close_spl_token_account",
+            accounts: token_accounts(),
+            instruction: TokenInstruction::close_spl_token_account(to.pubkey(), to_spl_token, from.pubkey()),
+            signers: vec![keypair(2)],
+        },
+        Scenario {
+            name: "self_test",
+            accounts: vec![(from.pubkey(), wallet())],
+            instruction: TokenInstruction::self_test(from.pubkey()),
+            signers: vec![keypair(1)],
+        },
+    ]
+}
+
+/// `token.so` built by `cargo test-bpf`, otherwise built here
+fn binary() -> PathBuf {
+    let dir = std::env::var("BPF_OUT_DIR").map_or_else(|_| PathBuf::from("target/deploy"), PathBuf::from);
+    let path = dir.join("token.so");
+    if !path.exists() {
+        let status = Command::new("cargo").args(["build-bpf", "--bpf-out-dir"]).arg(&dir).status().unwrap();
+        assert!(status.success(), "cargo build-bpf failed");
+    }
+    path
+}
+
+/// `name value` lines, `#` starts a comment. `name` alone is an entry not recorded yet
+fn load_budget() -> BTreeMap<String, Option<u64>> {
+    let content = fs::read_to_string(BUDGET_FILE)
+        .unwrap_or_else(|_| panic!("{} is missing, record it with UPDATE_BUDGET=1", BUDGET_FILE));
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next().unwrap();
+            (name.to_string(), parts.next().map(|value| value.parse().expect("budget value")))
+        })
+        .collect()
+}
+
+fn save_budget(budget: &BTreeMap<String, Option<u64>>) {
+    let mut content = String::from("# Recorded by `UPDATE_BUDGET=1 cargo test-bpf --test budget`\n");
+    for (name, value) in budget {
+        match value {
+            Some(value) => content.push_str(&format!("{} {}\n", name, value)),
+            None => content.push_str(&format!("{}\n", name)),
+        }
+    }
+    fs::write(BUDGET_FILE, content).unwrap();
+}
+
+fn ceiling(recorded: u64) -> u64 {
+    recorded + recorded * TOLERANCE_PERCENT / 100
+}
+
+/// Recorded value of `name`, `None` while it is listed without a value
+fn recorded(budget: &BTreeMap<String, Option<u64>>, name: &str) -> Option<u64> {
+    *budget
+        .get(name)
+        .unwrap_or_else(|| panic!("{} is not listed in {}, add its name to record it", name, BUDGET_FILE))
+}
+
+#[tokio::test]
+async fn binary_size_and_compute_units_within_budget() {
+    let size = fs::metadata(binary()).unwrap().len();
+
+    if std::env::var("UPDATE_BUDGET").is_ok() {
+        let mut budget = BTreeMap::new();
+        budget.insert(BINARY_SIZE.to_string(), Some(size));
+        for scenario in scenarios() {
+            budget.insert(scenario.name.to_string(), Some(scenario.measure().await));
+        }
+        save_budget(&budget);
+        return
+    }
+
+    let mut budget = load_budget();
+    let mut unrecorded = false;
+    match recorded(&budget, BINARY_SIZE) {
+        Some(recorded) => assert!(size <= ceiling(recorded), "binary size {} is over budget {}", size, recorded),
+        None => {
+            budget.insert(BINARY_SIZE.to_string(), Some(size));
+            unrecorded = true;
+        }
+    }
+    for scenario in scenarios() {
+        match recorded(&budget, scenario.name) {
+            Some(recorded) => {
+                let max_units = ceiling(recorded);
+                assert!(
+                    scenario.succeeds(max_units).await,
+                    "{}: over budget of {} compute units, re-measure with UPDATE_BUDGET=1", scenario.name, max_units,
+                );
+            }
+            None => {
+                budget.insert(scenario.name.to_string(), Some(scenario.measure().await));
+                unrecorded = true;
+            }
+        }
+    }
+    if unrecorded {
+        save_budget(&budget);
+    }
+}
@@ -0,0 +1,657 @@
+//! Builder/processor account order conformance.
+//!
+//! Every instruction is built with its builder and processed natively through a mock syscall layer,
+//! CPIs succeed without effect. Processor must accept the builder layout past account validation,
+//! reject each signer or writable account once its flag is cleared and require every account.
+//! Substituted token accounts and token program are rejected as well.
+use std::collections::HashSet;
+use std::sync::Once;
+use borsh::BorshSerialize;
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
+use solana_program::entrypoint::{ProgramResult, SUCCESS};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::{system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account, AccountState, Mint};
+use token::error::TransferError;
+use token::id;
+use token::instruction::TokenInstruction;
+use token::processor::Processor;
+use token::state::{
+    AccountType as StateAccountType, Config, DepositScheme, Dividend, FillMode, Fraction, MerkleDistribution,
+    MilestoneContract, MilestoneTerms, Order, RegistryMode, Rental, Stream, Vault, VestingSchedule,
+};
+
+struct MockSyscalls;
+
+impl SyscallStubs for MockSyscalls {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        _instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+}
+
+fn mock_syscalls() {
+    static MOCK: Once = Once::new();
+    MOCK.call_once(|| {
+        set_syscall_stubs(Box::new(MockSyscalls));
+    });
+}
+
+/// Account state behind a meta, program owned and empty unless overridden
+struct TestAccount {
+    meta: AccountMeta,
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+    executable: bool,
+}
+
+struct Case {
+    name: &'static str,
+    instruction: Instruction,
+    token_accounts: Vec<(Pubkey, Pubkey)>,
+    mints: Vec<Pubkey>,
+    executables: Vec<Pubkey>,
+}
+
+impl Case {
+    fn new(name: &'static str, instruction: Instruction) -> Self {
+        Case { name, instruction, token_accounts: vec![], mints: vec![], executables: vec![] }
+    }
+
+    /// Token accounts are owned by the first signer, as a source account by its authority
+    fn accounts(&self) -> Vec<TestAccount> {
+        let authority = self.instruction.accounts.iter().find(|meta| meta.is_signer).map(|meta| meta.pubkey);
+        self.instruction.accounts.iter().map(|meta| {
+            let mut acc = TestAccount {
+                meta: meta.clone(),
+                lamports: 1_000_000_000,
+                data: vec![],
+                owner: id(),
+                executable: self.executables.contains(&meta.pubkey),
+            };
+            if let Some((_, mint)) = self.token_accounts.iter().find(|(key, _)| *key == meta.pubkey) {
+                acc.owner = spl_token::id();
+                acc.data = vec![0; Account::LEN];
+                let account = Account {
+                    mint: *mint,
+                    owner: authority.unwrap_or_default(),
+                    state: AccountState::Initialized,
+                    ..Account::default()
+                };
+                Account::pack(account, &mut acc.data).unwrap();
+            }
+            if meta.pubkey == sysvar::instructions::id() {
+                // No instructions in the transaction, current index 0
+                acc.data = vec![0; 4];
+            }
+            if self.mints.contains(&meta.pubkey) {
+                acc.owner = spl_token::id();
+                acc.data = vec![0; Mint::LEN];
+                let mint = Mint { is_initialized: true, ..Mint::default() };
+                Mint::pack(mint, &mut acc.data).unwrap();
+            }
+            acc
+        }).collect()
+    }
+
+    fn process(&self, accounts: &mut [TestAccount]) -> ProgramResult {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(|acc| AccountInfo::new(
+            &acc.meta.pubkey,
+            acc.meta.is_signer,
+            acc.meta.is_writable,
+            &mut acc.lamports,
+            &mut acc.data,
+            &acc.owner,
+            acc.executable,
+            0,
+        )).collect();
+        Processor::process(&id(), &infos, &self.instruction.data)
+    }
+
+    fn check(&self) {
+        let layout_errors = [
+            ProgramError::MissingRequiredSignature,
+            TransferError::AccountNonWritable.into(),
+            ProgramError::NotEnoughAccountKeys,
+            ProgramError::InvalidSeeds,
+        ];
+        let result = self.process(&mut self.accounts());
+        if let Err(err) = result {
+            assert!(!layout_errors.contains(&err), "{}: builder layout rejected with {:?}", self.name, err);
+        }
+
+        for index in 0..self.instruction.accounts.len() {
+            let mut accounts = self.accounts();
+            if accounts[index].meta.is_signer {
+                accounts[index].meta.is_signer = false;
+                assert_eq!(
+                    self.process(&mut accounts),
+                    Err(ProgramError::MissingRequiredSignature),
+                    "{}: signer account {} is not checked", self.name, index,
+                );
+            }
+
+            let mut accounts = self.accounts();
+            if accounts[index].meta.is_writable {
+                accounts[index].meta.is_writable = false;
+                assert_eq!(
+                    self.process(&mut accounts),
+                    Err(TransferError::AccountNonWritable.into()),
+                    "{}: writable account {} is not checked", self.name, index,
+                );
+            }
+        }
+
+        let mut accounts = self.accounts();
+        if accounts.pop().is_some() {
+            assert_eq!(
+                self.process(&mut accounts),
+                Err(ProgramError::NotEnoughAccountKeys),
+                "{}: last account is not consumed", self.name,
+            );
+        }
+    }
+}
+
+fn cases() -> Vec<Case> {
+    let [a, b, c] = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let (mint, receiver_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let netting = Pubkey::new_unique();
+
+    let mut transfer_spl_token_to_wallet = Case::new(
+        "transfer_spl_token_to_wallet",
+        TokenInstruction::transfer_spl_token_to_wallet(a, b, a, c, mint, 100),
+    );
+    transfer_spl_token_to_wallet.token_accounts.push((c, mint));
+    transfer_spl_token_to_wallet.mints.push(mint);
+    let mut transfer_spl_token_attested = Case::new(
+        "transfer_spl_token_attested",
+        TokenInstruction::transfer_spl_token_attested(a, b, a, c, mint, 100, 1),
+    );
+    transfer_spl_token_attested.token_accounts.push((c, mint));
+    transfer_spl_token_attested.mints.push(mint);
+    let mut transfer_spl_token = Case::new(
+        "transfer_spl_token",
+        TokenInstruction::transfer_spl_token(a, b, c, mint, 100),
+    );
+    transfer_spl_token.token_accounts.push((b, mint));
+    let mut transfer_spl_token_v2 = Case::new(
+        "transfer_spl_token_v2",
+        TokenInstruction::transfer_spl_token_v2(a, b, c, mint, 100),
+    );
+    transfer_spl_token_v2.token_accounts.push((b, mint));
+    let mut transfer_spl_token_with_travel_rule = Case::new(
+        "transfer_spl_token_with_travel_rule",
+        TokenInstruction::transfer_spl_token_with_travel_rule(a, b, c, mint, 100, Some([0; 32])),
+    );
+    transfer_spl_token_with_travel_rule.token_accounts.push((b, mint));
+    let mut transfer_spl_token_with_memo = Case::new(
+        "transfer_spl_token_with_memo",
+        TokenInstruction::transfer_spl_token_with_memo(a, b, c, mint, 100, "INV-7".to_string()),
+    );
+    transfer_spl_token_with_memo.token_accounts.extend([(b, mint), (c, mint)]);
+    let mut sweep_spl_token = Case::new("sweep_spl_token", TokenInstruction::sweep_spl_token(a, b, c, mint));
+    sweep_spl_token.token_accounts.extend([(b, mint), (c, mint)]);
+    let mut transfer_spl_token_as_delegate = Case::new(
+        "transfer_spl_token_as_delegate",
+        TokenInstruction::transfer_spl_token_as_delegate(a, b, c, a, mint, 100),
+    );
+    transfer_spl_token_as_delegate.token_accounts.push((b, mint));
+    let mut transfer_spl_token_checked = Case::new(
+        "transfer_spl_token_checked",
+        TokenInstruction::transfer_spl_token_checked(a, b, c, mint, 100, 6),
+    );
+    transfer_spl_token_checked.token_accounts.extend([(b, mint), (c, mint)]);
+    transfer_spl_token_checked.mints.push(mint);
+    let mut transfer_spl_token_to_recipient = Case::new(
+        "transfer_spl_token_to_recipient",
+        TokenInstruction::transfer_spl_token_to_recipient(a, b, c, a, mint, 100),
+    );
+    transfer_spl_token_to_recipient.token_accounts.extend([(b, mint), (c, mint)]);
+    let (contract, _) = MilestoneContract::find_address(&a, &c, 1);
+    let milestones = vec![MilestoneTerms { amount: 100, deliverable_hash: [1; 32] }];
+    let mut create_contract = Case::new(
+        "create_contract",
+        TokenInstruction::create_contract(a, b, c, Pubkey::new_unique(), mint, 1, milestones),
+    );
+    create_contract.token_accounts.push((get_associated_token_address(&contract, &mint), mint));
+    create_contract.mints.push(mint);
+    let mut transfer_spl_token_batch = Case::new(
+        "transfer_spl_token_batch",
+        TokenInstruction::transfer_spl_token_batch(a, b, mint, &[(c, 100), (netting, 200)]),
+    );
+    transfer_spl_token_batch.token_accounts.extend([(b, mint), (c, mint), (netting, mint)]);
+    let mut initialize_vault = Case::new("initialize_vault", TokenInstruction::initialize_vault(a, mint));
+    let (vault, _) = Vault::find_address(&a, &mint);
+    initialize_vault.token_accounts.push((get_associated_token_address(&vault, &mint), mint));
+    initialize_vault.mints.push(mint);
+    let deposit_token = get_associated_token_address(&DepositScheme::find_deposit_address(&b, 7).0, &mint);
+    let mut sweep_deposit_spl_token = Case::new(
+        "sweep_deposit_spl_token",
+        TokenInstruction::sweep_deposit_spl_token(b, c, mint, 7),
+    );
+    sweep_deposit_spl_token.token_accounts.extend([(deposit_token, mint), (c, mint)]);
+    let mut initialize_managed_mint = Case::new(
+        "initialize_managed_mint",
+        TokenInstruction::initialize_managed_mint(a, mint, 1_000),
+    );
+    initialize_managed_mint.mints.push(mint);
+    let mut issue = Case::new("issue", TokenInstruction::issue(a, mint, c, 100));
+    issue.token_accounts.push((c, mint));
+    issue.mints.push(mint);
+    let mut redeem = Case::new("redeem", TokenInstruction::redeem(a, mint, c, 100));
+    redeem.token_accounts.push((c, mint));
+    redeem.mints.push(mint);
+    let mut batch_approve = Case::new(
+        "batch_approve",
+        TokenInstruction::batch_approve(a, &[(b, c, 100), (netting, c, 200)]),
+    );
+    batch_approve.token_accounts.extend([(b, mint), (netting, mint)]);
+    let mut create_merkle_distribution = Case::new(
+        "create_merkle_distribution",
+        TokenInstruction::create_merkle_distribution(a, mint, b, [7; 32], 3, 1_000),
+    );
+    let (distribution, _) = MerkleDistribution::find_address(&a, &[7; 32]);
+    let distribution_token = get_associated_token_address(&distribution, &mint);
+    create_merkle_distribution.token_accounts.extend([(b, mint), (distribution_token, mint)]);
+    create_merkle_distribution.mints.push(mint);
+    let mut create_dividend = Case::new(
+        "create_dividend",
+        TokenInstruction::create_dividend(a, mint, c, b, [7; 32], 3, 3_000, 1_000, i64::MAX),
+    );
+    let (dividend, _) = Dividend::find_address(&a, &[7; 32]);
+    let dividend_token = get_associated_token_address(&dividend, &mint);
+    create_dividend.token_accounts.extend([(b, mint), (dividend_token, mint)]);
+    create_dividend.mints.push(mint);
+    let mut initialize_escrow = Case::new(
+        "initialize_escrow",
+        TokenInstruction::initialize_escrow(a, b, c, mint, mint, 300, i64::MAX),
+    );
+    initialize_escrow.token_accounts.extend([(b, mint), (c, mint)]);
+    let mut create_vesting = Case::new(
+        "create_vesting",
+        TokenInstruction::create_vesting(a, b, c, mint, 100, 1, 2, 3),
+    );
+    let (vesting, _) = VestingSchedule::find_address(&a, &c, &mint);
+    create_vesting.token_accounts.push((get_associated_token_address(&vesting, &mint), mint));
+    create_vesting.mints.push(mint);
+    let mut create_sponsored_account = Case::new(
+        "create_sponsored_account",
+        TokenInstruction::create_sponsored_account(a, b, mint, c),
+    );
+    create_sponsored_account.mints.push(mint);
+    let (stream, _) = Stream::find_address(&a, &b);
+    let mut create_stream = Case::new(
+        "create_stream",
+        TokenInstruction::with_stream_token(TokenInstruction::create_stream(a, b, 1_000, 10, 1), mint, &[c]),
+    );
+    create_stream.token_accounts.push((get_associated_token_address(&stream, &mint), mint));
+    create_stream.mints.push(mint);
+    let mut park_spl_token = Case::new("park_spl_token", TokenInstruction::park_spl_token(a, b, c, mint, 100));
+    park_spl_token.mints.push(mint);
+    let mut claim_balance = Case::new("claim_balance", TokenInstruction::claim_balance(a, mint, b));
+    claim_balance.mints.push(mint);
+    let mut set_mint_config = Case::new("set_mint_config", TokenInstruction::set_mint_config(a, mint, true));
+    set_mint_config.mints.push(mint);
+    let mut list_rental = Case::new("list_rental", TokenInstruction::list_rental(a, b, mint, 100, 60));
+    let (rental, _) = Rental::find_address(&mint);
+    list_rental.token_accounts.push((b, mint));
+    list_rental.token_accounts.push((get_associated_token_address(&rental, &mint), mint));
+    list_rental.mints.push(mint);
+    let share_mint = Pubkey::new_unique();
+    let mut fractionalize_nft = Case::new(
+        "fractionalize_nft",
+        TokenInstruction::fractionalize_nft(a, b, mint, share_mint, c, 100),
+    );
+    let (fraction, _) = Fraction::find_address(&mint);
+    fractionalize_nft.token_accounts.push((b, mint));
+    fractionalize_nft.token_accounts.push((get_associated_token_address(&fraction, &mint), mint));
+    fractionalize_nft.token_accounts.push((c, share_mint));
+    fractionalize_nft.mints.extend([mint, share_mint]);
+    let mut redeem_nft = Case::new("redeem_nft", TokenInstruction::redeem_nft(a, b, share_mint, c, mint, a));
+    redeem_nft.token_accounts.push((b, share_mint));
+    redeem_nft.token_accounts.push((c, mint));
+    let mut post_order = Case::new(
+        "post_order",
+        TokenInstruction::post_order(a, b, mint, share_mint, 1, 100, 300, FillMode::Partial),
+    );
+    let (order, _) = Order::find_address(&a, 1);
+    post_order.token_accounts.push((b, mint));
+    post_order.token_accounts.push((get_associated_token_address(&order, &mint), mint));
+    post_order.mints.extend([mint, share_mint]);
+    let mut set_price = Case::new("set_price", TokenInstruction::set_price(a, mint, 1_000));
+    set_price.mints.push(mint);
+    let mut transfer_spl_token_price_protected = Case::new(
+        "transfer_spl_token_price_protected",
+        TokenInstruction::transfer_spl_token_price_protected(a, b, c, mint, 100, 900, 1_100, 60),
+    );
+    transfer_spl_token_price_protected.token_accounts.extend([(b, mint), (c, mint)]);
+    transfer_spl_token_price_protected.mints.push(mint);
+    let mut mint_spl_token = Case::new("mint_spl_token", TokenInstruction::mint_spl_token(a, mint, b, 100));
+    mint_spl_token.token_accounts.push((b, mint));
+    mint_spl_token.mints.push(mint);
+    let mut burn_spl_token = Case::new("burn_spl_token", TokenInstruction::burn_spl_token(a, b, mint, 100));
+    burn_spl_token.token_accounts.push((b, mint));
+    burn_spl_token.mints.push(mint);
+    let mut freeze_spl_token_account = Case::new(
+        "freeze_spl_token_account",
+        TokenInstruction::freeze_spl_token_account(a, b, mint),
+    );
+    freeze_spl_token_account.token_accounts.push((b, mint));
+    freeze_spl_token_account.mints.push(mint);
+    let mut thaw_spl_token_account = Case::new(
+        "thaw_spl_token_account",
+        TokenInstruction::thaw_spl_token_account(a, b, mint),
+    );
+    thaw_spl_token_account.token_accounts.push((b, mint));
+    thaw_spl_token_account.mints.push(mint);
+    let mut cache_mint_decimals = Case::new("cache_mint_decimals", TokenInstruction::cache_mint_decimals(a, mint));
+    cache_mint_decimals.mints.push(mint);
+    let mut close_spl_token_account = Case::new(
+        "close_spl_token_account",
+        TokenInstruction::close_spl_token_account(a, b, c),
+    );
+    close_spl_token_account.token_accounts.push((b, mint));
+    let mut transfer_spl_token_with_ata_creation = Case::new(
+        "transfer_spl_token_with_ata_creation",
+        TokenInstruction::transfer_spl_token_with_ata_creation(a, b, c, mint, 100),
+    );
+    transfer_spl_token_with_ata_creation.mints.push(mint);
+    let mut unwrap_sol = Case::new("unwrap_sol", TokenInstruction::unwrap_sol(a));
+    let wsol_token = get_associated_token_address(&a, &spl_token::native_mint::id());
+    unwrap_sol.token_accounts.push((wsol_token, spl_token::native_mint::id()));
+
+    let mut deposit_to_program = Case::new(
+        "deposit_to_program",
+        TokenInstruction::deposit_to_program(
+            receiver_program,
+            a,
+            b,
+            c,
+            system_program::id(),
+            vec![],
+            100,
+        ),
+    );
+    deposit_to_program.executables.push(receiver_program);
+
+    vec![
+        Case::new("transfer_lamports", TokenInstruction::transfer_lamports(a, b, 100)),
+        transfer_spl_token,
+        Case::new("approve_spl_token", TokenInstruction::approve_spl_token(a, b, c, 100)),
+        Case::new("post_bond", TokenInstruction::post_bond(a, b, c, 1, 100)),
+        Case::new("refund_bond", TokenInstruction::refund_bond(b, a, 1, 0)),
+        Case::new("slash_bond", TokenInstruction::slash_bond(b, c, a, 1, 100, 0)),
+        Case::new("tip", TokenInstruction::tip(a, b, 100)),
+        Case::new("create_matching_pool", TokenInstruction::create_matching_pool(a, b, 5_000, 100, 100)),
+        Case::new("donate", TokenInstruction::donate(c, b, a, 100)),
+        Case::new("close_matching_pool", TokenInstruction::close_matching_pool(a, b)),
+        Case::new("configure_round_up", TokenInstruction::configure_round_up(a, 100)),
+        Case::new("transfer_lamports_round_up", TokenInstruction::transfer_lamports_round_up(a, b, 100)),
+        Case::new("withdraw_savings", TokenInstruction::withdraw_savings(a, 100)),
+        Case::new("open_netting", TokenInstruction::open_netting(a, b, 1, i64::MAX)),
+        Case::new("register_obligation", TokenInstruction::register_obligation(a, b, netting, 100)),
+        Case::new("settle_netting", TokenInstruction::settle_netting(a, b, netting)),
+        deposit_to_program,
+        Case::new("set_transfer_hook", TokenInstruction::set_transfer_hook(a, Some(receiver_program))),
+        transfer_spl_token_to_wallet,
+        Case::new("set_config", TokenInstruction::set_config(a, true)),
+        Case::new("set_features", TokenInstruction::set_features(a, 0)),
+        Case::new("publish_payout_root", TokenInstruction::publish_payout_root(a, 1, [0; 32], 1)),
+        Case::new("execute_payout", TokenInstruction::execute_payout(a, b, 1, 0, 100, vec![])),
+        Case::new("create_distributor", TokenInstruction::create_distributor(a, 1, b, 100)),
+        Case::new("claim_distribution", TokenInstruction::claim_distribution(a, b, 100)),
+        Case::new("set_attestation", TokenInstruction::set_attestation(a, b, 1, i64::MAX)),
+        transfer_spl_token_attested,
+        transfer_spl_token_with_travel_rule,
+        Case::new("set_travel_rule", TokenInstruction::set_travel_rule(a, true, 100)),
+        set_mint_config,
+        list_rental,
+        Case::new("rent_nft", TokenInstruction::rent_nft(a, b, mint)),
+        Case::new("return_rental", TokenInstruction::return_rental(a, b, mint)),
+        Case::new("cancel_rental", TokenInstruction::cancel_rental(a, b, mint)),
+        fractionalize_nft,
+        redeem_nft,
+        post_order,
+        Case::new("fill_order", TokenInstruction::fill_order(c, b, a, a, netting, b, 1, mint, 100)),
+        Case::new("cancel_order", TokenInstruction::cancel_order(a, b, 1, mint)),
+        set_price,
+        transfer_spl_token_price_protected,
+        Case::new("register_keeper", TokenInstruction::register_keeper(a)),
+        Case::new("set_keeper_ban", TokenInstruction::set_keeper_ban(a, b, true)),
+        transfer_spl_token_as_delegate,
+        transfer_spl_token_checked,
+        Case::new("transfer_lamports_batch", TokenInstruction::transfer_lamports_batch(a, &[(b, 100), (c, 200)])),
+        transfer_spl_token_batch,
+        initialize_vault,
+        Case::new("deposit_to_vault", TokenInstruction::deposit_to_vault(b, c, a, mint, 100)),
+        Case::new("withdraw_from_vault", TokenInstruction::withdraw_from_vault(a, b, mint, 100)),
+        initialize_escrow,
+        Case::new("exchange_escrow", TokenInstruction::exchange_escrow(a, b, c, netting, b, c, 100)),
+        Case::new("cancel_escrow", TokenInstruction::cancel_escrow(a, b, c)),
+        mint_spl_token,
+        burn_spl_token,
+        freeze_spl_token_account,
+        thaw_spl_token_account,
+        cache_mint_decimals,
+        close_spl_token_account,
+        Case::new("self_test", TokenInstruction::self_test(a)),
+        transfer_spl_token_with_ata_creation,
+        Case::new("wrap_sol", TokenInstruction::wrap_sol(a, 100)),
+        unwrap_sol,
+        Case::new("initialize_fee_config", TokenInstruction::initialize_fee_config(a, b, 30)),
+        Case::new("update_fee_config", TokenInstruction::update_fee_config(a, b, 50)),
+        Case::new("set_paused", TokenInstruction::set_paused(a, true)),
+        Case::new("check_deadline", TokenInstruction::check_deadline(100)),
+        Case::new("split_lamports", TokenInstruction::split_lamports(a, b, &[c], 100, &[1])),
+        create_vesting,
+        Case::new("claim_vested", TokenInstruction::claim_vested(c, b, a, mint)),
+        Case::new("cancel_vesting", TokenInstruction::cancel_vesting(a, b, c, c, mint)),
+        Case::new("create_sponsor_pool", TokenInstruction::create_sponsor_pool(a, b, 3, 1_000)),
+        Case::new("fund_sponsor_pool", TokenInstruction::fund_sponsor_pool(b, a, 1_000)),
+        create_sponsored_account,
+        Case::new("close_sponsor_pool", TokenInstruction::close_sponsor_pool(a)),
+        create_stream,
+        Case::new("withdraw_from_stream", TokenInstruction::withdraw_from_stream(b, a)),
+        Case::new("close_stream", TokenInstruction::close_stream(a, b)),
+        park_spl_token,
+        claim_balance,
+        Case::new("revoke_delegate", TokenInstruction::revoke_delegate(a, b, false)),
+        Case::new("reinstate_delegate", TokenInstruction::reinstate_delegate(a, b, false)),
+        Case::new("register_recipient", TokenInstruction::register_recipient(a, b)),
+        Case::new("remove_recipient", TokenInstruction::remove_recipient(a, b)),
+        Case::new("transfer_lamports_to_recipient", TokenInstruction::transfer_lamports_to_recipient(a, b, 100)),
+        transfer_spl_token_to_recipient,
+        create_contract,
+        Case::new("submit_deliverable", TokenInstruction::submit_deliverable(c, contract, 0, [2; 32])),
+        Case::new("release_milestone", TokenInstruction::release_milestone(a, contract, b, mint, 0)),
+        Case::new("close_contract", TokenInstruction::close_contract(a, a, contract, b, mint)),
+        Case::new(
+            "transfer_lamports_with_memo",
+            TokenInstruction::transfer_lamports_with_memo(a, b, 100, "INV-7".to_string()),
+        ),
+        transfer_spl_token_with_memo,
+        Case::new("initialize_audit_log", TokenInstruction::initialize_audit_log(a)),
+        Case::new("sweep_lamports", TokenInstruction::sweep_lamports(a, b)),
+        sweep_spl_token,
+        Case::new("initialize_registry", TokenInstruction::initialize_registry(a, RegistryMode::Allow)),
+        Case::new("add_to_registry", TokenInstruction::add_to_registry(a, b)),
+        Case::new("remove_from_registry", TokenInstruction::remove_from_registry(a, b)),
+        Case::new("begin_export", TokenInstruction::begin_export(a, receiver_program)),
+        Case::new("begin_import", TokenInstruction::begin_import(a, receiver_program)),
+        Case::new("export_vault", TokenInstruction::export_vault(a, b, mint, receiver_program)),
+        Case::new("import_vault", TokenInstruction::import_vault(a, receiver_program, b, mint)),
+        Case::new("export_escrow", TokenInstruction::export_escrow(a, b)),
+        Case::new("import_escrow", TokenInstruction::import_escrow(a, b, receiver_program)),
+        Case::new("set_spending_limit", TokenInstruction::set_spending_limit(a, b, mint, 1_000, 86_400)),
+        Case::new("initialize_namespace", TokenInstruction::initialize_namespace(a, b)),
+        Case::new("set_approval_policy", TokenInstruction::set_approval_policy(a, b, c, 1_000)),
+        Case::new("propose_transfer", TokenInstruction::propose_transfer(a, b, 1_000)),
+        Case::new("execute_transfer", TokenInstruction::execute_transfer(a, b, c)),
+        Case::new("cancel_transfer", TokenInstruction::cancel_transfer(a, b, c)),
+        create_merkle_distribution,
+        Case::new(
+            "claim_merkle_distribution",
+            TokenInstruction::claim_merkle_distribution(a, b, c, mint, 1, 500, vec![[2; 32]]),
+        ),
+        Case::new("initialize_deposit_scheme", TokenInstruction::initialize_deposit_scheme(a, c)),
+        Case::new("sweep_deposit", TokenInstruction::sweep_deposit(b, c, 7)),
+        sweep_deposit_spl_token,
+        initialize_managed_mint,
+        Case::new("set_issuer", TokenInstruction::set_issuer(a, b, mint, 3, 1_000)),
+        issue,
+        redeem,
+        batch_approve,
+        Case::new(
+            "guard_preceding_instruction",
+            TokenInstruction::guard_preceding_instruction(receiver_program, [1; 32], [2; 32]),
+        ),
+        create_dividend,
+        Case::new(
+            "claim_dividend",
+            TokenInstruction::claim_dividend(a, b, c, mint, 1, 500, vec![[2; 32]]),
+        ),
+        Case::new("clawback_dividend", TokenInstruction::clawback_dividend(a, b, c, mint)),
+        Case::new("set_deposit_program", TokenInstruction::set_deposit_program(a, b, true)),
+        Case::new("initialize_event_stream", TokenInstruction::initialize_event_stream(a, b)),
+        Case::new("transfer_lamports_v2", TokenInstruction::transfer_lamports_v2(a, b, 100)),
+        transfer_spl_token_v2,
+    ]
+}
+
+#[test]
+fn when_every_instruction_expect_case() {
+    let tags: HashSet<u8> = cases().iter().map(|case| case.instruction.data[0]).collect();
+
+    assert_eq!(tags, (0..TokenInstruction::TAG_COUNT).collect());
+}
+
+#[test]
+fn when_builder_accounts_expect_processor_order() {
+    mock_syscalls();
+
+    for case in cases() {
+        case.check();
+    }
+}
+
+#[test]
+fn when_paused_expect_value_moving_instructions_rejected() {
+    mock_syscalls();
+    let value_moving = [
+        "transfer_spl_token", "transfer_lamports_v2", "transfer_spl_token_v2", "post_bond", "refund_bond",
+        "slash_bond", "tip",
+        "create_matching_pool", "donate", "close_matching_pool", "transfer_lamports_round_up", "withdraw_savings",
+        "settle_netting", "deposit_to_program", "transfer_spl_token_to_wallet", "execute_payout",
+        "create_distributor", "claim_distribution", "transfer_spl_token_attested",
+        "transfer_spl_token_with_travel_rule", "list_rental", "rent_nft", "return_rental", "cancel_rental",
+        "fractionalize_nft", "redeem_nft", "post_order", "fill_order", "cancel_order",
+        "transfer_spl_token_price_protected", "transfer_spl_token_as_delegate", "transfer_spl_token_checked",
+        "transfer_lamports_batch", "transfer_spl_token_batch", "deposit_to_vault", "withdraw_from_vault",
+        "initialize_escrow", "exchange_escrow", "cancel_escrow", "transfer_spl_token_with_ata_creation",
+        "split_lamports", "create_vesting", "claim_vested", "cancel_vesting", "create_sponsor_pool",
+        "fund_sponsor_pool", "create_sponsored_account", "close_sponsor_pool", "create_stream",
+        "withdraw_from_stream", "close_stream", "park_spl_token", "claim_balance",
+        "transfer_lamports_to_recipient", "transfer_spl_token_to_recipient", "create_contract",
+        "release_milestone", "close_contract", "transfer_lamports_with_memo", "transfer_spl_token_with_memo",
+        "sweep_lamports", "sweep_spl_token", "create_merkle_distribution", "claim_merkle_distribution",
+        "sweep_deposit", "sweep_deposit_spl_token", "create_dividend", "claim_dividend", "clawback_dividend",
+        "propose_transfer", "execute_transfer",
+    ];
+    let (config_key, _) = Config::find_address();
+    let config = Config {
+        account_type: StateAccountType::Config,
+        admin: Pubkey::new_unique(),
+        strict_ata: false,
+        features: u64::MAX,
+        travel_rule_required: false,
+        travel_rule_threshold: 0,
+        paused: true,
+        deposit_programs: [Pubkey::default(); 4],
+        bump: 0,
+    };
+
+    let cases = cases();
+    for name in value_moving {
+        let case = cases.iter().find(|case| case.name == name).unwrap();
+        let mut accounts = case.accounts();
+        for acc in accounts.iter_mut().filter(|acc| acc.meta.pubkey == config_key) {
+            acc.data = config.try_to_vec().unwrap();
+        }
+        assert_eq!(
+            case.process(&mut accounts),
+            Err(TransferError::ProgramPaused.into()),
+            "{}: not halted while paused", name,
+        );
+    }
+}
+
+/// Transfer policy accounts every transfer starts with, see `TokenInstruction::transfer_policy_accounts`
+fn policy_len() -> usize {
+    TokenInstruction::transfer_policy_accounts(&Pubkey::default(), &Pubkey::default()).len()
+}
+
+fn pack_token_account(acc: &mut TestAccount, mint: Pubkey, owner: Pubkey) {
+    acc.owner = spl_token::id();
+    acc.data = vec![0; Account::LEN];
+    let account = Account { mint, owner, amount: 1_000, state: AccountState::Initialized, ..Account::default() };
+    Account::pack(account, &mut acc.data).unwrap();
+}
+
+#[test]
+fn when_token_accounts_substituted_expect_rejected() {
+    mock_syscalls();
+    let [owner, from, to] = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let mint = Pubkey::new_unique();
+    let case = Case::new("transfer_spl_token_v2", TokenInstruction::transfer_spl_token_v2(owner, from, to, mint, 100));
+    let [from_index, to_index, spl_token_index] = [1, 2, 3].map(|index| policy_len() + index);
+    let accounts = || {
+        let mut accounts = case.accounts();
+        pack_token_account(&mut accounts[from_index], mint, owner);
+        pack_token_account(&mut accounts[to_index], mint, Pubkey::new_unique());
+        accounts
+    };
+    assert_eq!(case.process(&mut accounts()), Ok(()));
+
+    let mut foreign_source = accounts();
+    pack_token_account(&mut foreign_source[from_index], mint, Pubkey::new_unique());
+    assert_eq!(case.process(&mut foreign_source), Err(TransferError::SourceOwnerMismatch.into()));
+
+    let mut other_mint_destination = accounts();
+    pack_token_account(&mut other_mint_destination[to_index], Pubkey::new_unique(), owner);
+    assert_eq!(case.process(&mut other_mint_destination), Err(TransferError::MintMismatch.into()));
+
+    let mut fake_destination = accounts();
+    fake_destination[to_index].owner = id();
+    assert_eq!(case.process(&mut fake_destination), Err(TransferError::InvalidOwner.into()));
+
+    let mut fake_token_program = accounts();
+    fake_token_program[spl_token_index].meta.pubkey = Pubkey::new_unique();
+    assert_eq!(case.process(&mut fake_token_program), Err(TransferError::InvalidTokenProgram.into()));
+}
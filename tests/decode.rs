@@ -0,0 +1,85 @@
+#![cfg(feature = "test-bpf")]
+//! Heap reserved by decoding instruction data with `TokenInstruction::unpack` against Borsh.
+//!
+//! Savings are reported in heap bytes rather than compute units: processors run natively are not
+//! metered, and the program has no Borsh build to meter against, its compute units as a whole are held
+//! by `tests/budget.rs`. Heap costs a BPF program the same whichever way it runs, a fixed bump region
+//! that is never freed. Both decoders read the same bytes, but Borsh reserves up to 4 KiB for a vector
+//! length it has not checked against the input.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use borsh::BorshDeserialize;
+use solana_program::pubkey::Pubkey;
+use token::instruction::TokenInstruction;
+
+/// Counts bytes allocated by the current thread, so tests running in parallel don't skew each other
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + layout.size()));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+/// Bytes allocated while decoding `data` with the codec and with Borsh
+fn heap_bytes(data: &[u8]) -> (usize, usize) {
+    let allocated = || ALLOCATED.with(Cell::get);
+    let start = allocated();
+    let codec = TokenInstruction::unpack(data);
+    let codec_end = allocated();
+    let borsh = TokenInstruction::try_from_slice(data);
+    let borsh_end = allocated();
+    assert_eq!(codec.is_ok(), borsh.is_ok(), "decoders disagree on {:?}", data);
+    (codec_end - start, borsh_end - codec_end)
+}
+
+#[test]
+fn when_fixed_size_fields_expect_no_heap() {
+    let instructions = [
+        TokenInstruction::CheckDeadline { valid_until_slot: u64::MAX },
+        TokenInstruction::TransferLamports { amount: 1 },
+        TokenInstruction::SetDepositProgram { program: Pubkey::new_unique(), allowed: true },
+    ];
+
+    for instr in &instructions {
+        assert_eq!(heap_bytes(&instr.pack()), (0, 0), "{:?}", instr);
+    }
+}
+
+#[test]
+fn when_vector_fields_expect_heap_of_borsh() {
+    let instructions = [
+        TokenInstruction::TransferLamportsBatch { amounts: vec![1; TokenInstruction::MAX_BATCH_LEN] },
+        TokenInstruction::ExecutePayout { index: 0, amount: 1, proof: vec![[7; 32]; 3] },
+        TokenInstruction::TransferLamportsWithMemo { amount: 1, memo: "memo".to_string() },
+    ];
+
+    for instr in &instructions {
+        let (codec, borsh) = heap_bytes(&instr.pack());
+        assert_eq!(codec, borsh, "{:?}", instr);
+    }
+}
+
+#[test]
+fn when_forged_vector_len_expect_less_heap_than_borsh() {
+    let mut data = TokenInstruction::TransferLamportsBatch { amounts: vec![1] }.pack();
+    data[1..5].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let (codec, borsh) = heap_bytes(&data);
+
+    // One `u64` per remaining input byte at most, against the 4 KiB Borsh reserves up front
+    assert!(codec <= 8 * 8, "codec reserved {} bytes", codec);
+    assert!(borsh >= 4096, "Borsh reserved {} bytes", borsh);
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "test-bpf")]
+// Every example declares its own `mod common;` to build standalone
+#![allow(clippy::duplicate_mod)]
+//! Example flows of `examples/` run as regression scenarios.
+
+#[path = "../examples/airdrop_claim.rs"]
+mod airdrop_claim;
+#[path = "../examples/escrow_lifecycle.rs"]
+mod escrow_lifecycle;
+#[path = "../examples/payroll_run.rs"]
+mod payroll_run;
+#[path = "../examples/vesting_grant.rs"]
+mod vesting_grant;
+
+#[tokio::test]
+async fn airdrop_claim() {
+    airdrop_claim::run().await;
+}
+
+#[tokio::test]
+async fn escrow_lifecycle() {
+    escrow_lifecycle::run().await;
+}
+
+#[tokio::test]
+async fn payroll_run() {
+    payroll_run::run().await;
+}
+
+#[tokio::test]
+async fn vesting_grant() {
+    vesting_grant::run().await;
+}
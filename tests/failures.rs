@@ -0,0 +1,172 @@
+#![cfg(feature = "test-bpf")]
+//! Failure paths of transfers driven by proptest.
+//!
+//! Amounts are drawn around the source balance, including 0 and `u64::MAX`. A transfer either moves
+//! exactly the amount or fails with `TransferError::InsufficientFunds`, and every rejected account layout
+//! fails with its own error whatever the amount, before any balance is touched.
+mod support;
+
+use proptest::prelude::*;
+use solana_program::instruction::Instruction;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use token::error::TransferError;
+use support::{block_on, instruction_error, Fixture};
+
+/// Amounts around `balance`, edges drawn as often as the ranges
+fn amount(balance: u64) -> impl Strategy<Value = u64> {
+    prop_oneof![
+        Just(0),
+        Just(balance),
+        Just(balance + 1),
+        Just(u64::MAX),
+        0..=balance,
+        any::<u64>(),
+    ]
+}
+
+/// Send `instr` paid by the bank payer, also signed by the owner unless its signer flag is cleared
+async fn process(fixture: &mut Fixture, instr: Instruction) -> Result<(), TransactionError> {
+    let (payer, owner) = (fixture.payer(), Keypair::from_bytes(&fixture.owner.to_bytes()).unwrap());
+    if instr.accounts.iter().any(|meta| meta.pubkey == owner.pubkey() && meta.is_signer) {
+        fixture.process(&[instr], &[&payer, &owner]).await
+    } else {
+        fixture.process(&[instr], &[&payer]).await
+    }
+}
+
+/// Result of a token transfer of `amount` altered by `tamper`, with token balances of both accounts afterwards
+async fn transfer_spl_token<F>(amount: u64, tamper: F) -> (Result<(), TransactionError>, u64, u64)
+where
+    F: FnOnce(&mut Instruction),
+{
+    let mut fixture = Fixture::new().await;
+    let mut transfer_instr = fixture.transfer_spl_token(amount);
+    tamper(&mut transfer_instr);
+    let result = process(&mut fixture, transfer_instr).await;
+    let (from_spl_token, to_spl_token) = (fixture.from_spl_token, fixture.to_spl_token);
+    (result, fixture.token_balance(&from_spl_token).await, fixture.token_balance(&to_spl_token).await)
+}
+
+/// Same as `transfer_spl_token` for `TransferLamports`, with lamport balances of both wallets
+async fn transfer_lamports<F>(amount: u64, tamper: F) -> (Result<(), TransactionError>, u64, u64)
+where
+    F: FnOnce(&mut Instruction),
+{
+    let mut fixture = Fixture::new().await;
+    let mut transfer_instr = fixture.transfer_lamports(amount);
+    tamper(&mut transfer_instr);
+    let result = process(&mut fixture, transfer_instr).await;
+    let (owner, recipient) = (fixture.owner.pubkey(), fixture.recipient.pubkey());
+    (result, fixture.balance(&owner).await, fixture.balance(&recipient).await)
+}
+
+proptest! {
+    // Every case starts a bank
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn when_token_amount_expect_transfer_or_insufficient_funds(amount in amount(Fixture::MINT_AMOUNT)) {
+        let (result, from_balance, to_balance) = block_on(transfer_spl_token(amount, |_| ()));
+
+        if amount <= Fixture::MINT_AMOUNT {
+            prop_assert_eq!(result, Ok(()));
+            prop_assert_eq!(from_balance, Fixture::MINT_AMOUNT - amount);
+            prop_assert_eq!(to_balance, amount);
+        } else {
+            prop_assert_eq!(result, Err(instruction_error(TransferError::InsufficientFunds)));
+            prop_assert_eq!((from_balance, to_balance), (Fixture::MINT_AMOUNT, 0));
+        }
+    }
+
+    #[test]
+    fn when_lamport_amount_expect_transfer_or_insufficient_funds(amount in amount(Fixture::WALLET_LAMPORTS)) {
+        let (result, from_balance, to_balance) = block_on(transfer_lamports(amount, |_| ()));
+
+        if amount <= Fixture::WALLET_LAMPORTS {
+            prop_assert_eq!(result, Ok(()));
+            prop_assert_eq!(from_balance, Fixture::WALLET_LAMPORTS - amount);
+            prop_assert_eq!(to_balance, Fixture::WALLET_LAMPORTS + amount);
+        } else {
+            prop_assert_eq!(result, Err(instruction_error(TransferError::InsufficientFunds)));
+            prop_assert_eq!((from_balance, to_balance), (Fixture::WALLET_LAMPORTS, Fixture::WALLET_LAMPORTS));
+        }
+    }
+
+    #[test]
+    fn when_token_owner_not_signer_expect_missing_signature(amount in amount(Fixture::MINT_AMOUNT)) {
+        let (result, from_balance, _) = block_on(transfer_spl_token(amount, |instr| {
+            instr.accounts[0].is_signer = false;
+        }));
+
+        prop_assert_eq!(result, Err(instruction_error(ProgramError::MissingRequiredSignature)));
+        prop_assert_eq!(from_balance, Fixture::MINT_AMOUNT);
+    }
+
+    #[test]
+    fn when_lamport_source_not_signer_expect_missing_signature(amount in amount(Fixture::WALLET_LAMPORTS)) {
+        let (result, from_balance, _) = block_on(transfer_lamports(amount, |instr| {
+            instr.accounts[0].is_signer = false;
+        }));
+
+        prop_assert_eq!(result, Err(instruction_error(ProgramError::MissingRequiredSignature)));
+        prop_assert_eq!(from_balance, Fixture::WALLET_LAMPORTS);
+    }
+
+    #[test]
+    fn when_token_destination_non_writable_expect_rejected(amount in amount(Fixture::MINT_AMOUNT)) {
+        let (result, from_balance, _) = block_on(transfer_spl_token(amount, |instr| {
+            instr.accounts[2].is_writable = false;
+        }));
+
+        prop_assert_eq!(result, Err(instruction_error(TransferError::AccountNonWritable)));
+        prop_assert_eq!(from_balance, Fixture::MINT_AMOUNT);
+    }
+
+    #[test]
+    fn when_lamport_destination_non_writable_expect_rejected(amount in amount(Fixture::WALLET_LAMPORTS)) {
+        let (result, from_balance, _) = block_on(transfer_lamports(amount, |instr| {
+            instr.accounts[1].is_writable = false;
+        }));
+
+        prop_assert_eq!(result, Err(instruction_error(TransferError::AccountNonWritable)));
+        prop_assert_eq!(from_balance, Fixture::WALLET_LAMPORTS);
+    }
+
+    #[test]
+    fn when_wrong_token_program_expect_rejected(amount in amount(Fixture::MINT_AMOUNT)) {
+        let (result, from_balance, _) = block_on(transfer_spl_token(amount, |instr| {
+            instr.accounts[3].pubkey = Pubkey::new_unique();
+        }));
+
+        prop_assert_eq!(result, Err(instruction_error(TransferError::InvalidTokenProgram)));
+        prop_assert_eq!(from_balance, Fixture::MINT_AMOUNT);
+    }
+}
+
+proptest! {
+    // Creates a second mint on top of the fixture
+    #![proptest_config(ProptestConfig::with_cases(8))]
+
+    #[test]
+    fn when_destination_of_other_mint_expect_mint_mismatch(amount in amount(Fixture::MINT_AMOUNT)) {
+        let (result, from_balance) = block_on(async {
+            let mut fixture = Fixture::new().await;
+            let other_mint = fixture.create_mint().await;
+            let recipient = fixture.recipient.pubkey();
+            let other_spl_token = fixture.token_account(&other_mint, &recipient).await;
+            let mut transfer_instr = fixture.transfer_spl_token(amount);
+            transfer_instr.accounts[2].pubkey = other_spl_token;
+
+            let result = process(&mut fixture, transfer_instr).await;
+            let from_spl_token = fixture.from_spl_token;
+            (result, fixture.token_balance(&from_spl_token).await)
+        });
+
+        prop_assert_eq!(result, Err(instruction_error(TransferError::MintMismatch)));
+        prop_assert_eq!(from_balance, Fixture::MINT_AMOUNT);
+    }
+}
@@ -1,18 +1,38 @@
 #![cfg(feature = "test-bpf")]
 
+use borsh::BorshDeserialize;
+use solana_program::account_info::{AccountInfo, next_account_info};
+use solana_program::entrypoint::ProgramResult;
 use solana_program::hash::Hash;
+use solana_program::clock::{Clock, DEFAULT_MS_PER_SLOT};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::program::invoke;
+use solana_program::program_error::ProgramError;
 use solana_program::program_option::COption;
-use solana_program::system_instruction;
-use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_program::{system_instruction, system_program};
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_program_test::{processor, BanksClient, ProgramTest, ProgramTestContext};
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::{Transaction, TransactionError};
 use token::instruction::TokenInstruction;
+use token::deposit_interface::ReceiveDeposit;
+use token::hook_interface::TransferHookData;
 use token::entrypoint::process_instruction;
+use token::error::TransferError;
 use token::id;
+use token::eligibility;
+use token::merkle;
+use token::state::{
+    AccountType, AuditLog, Bond, Canary, ClaimableBalance, Config, DepositScheme, Distributor, Dividend, Escrow,
+    EventStream, FillMode, Issuer, ManagedMint, MatchingPool, Migration, MerkleDistribution, MilestoneContract,
+    MilestoneTerms, MintDecimals, Netting, Order, PayoutBatch, PendingTransfer, Registry, RegistryMode, Revocation,
+    RosterEntry, SavingsVault, SpendingLimit, SponsorPool, Stream, TipStats, TipperStats, Vault, VestingSchedule,
+};
 use solana_program::pubkey::Pubkey;
 use spl_token::solana_program::program_pack::Pack;
-use spl_token::state::{Account, Mint};
+use spl_token::state::{Account, Mint, Multisig};
+use spl_associated_token_account::get_associated_token_address;
 
 struct Env {
     ctx: ProgramTestContext,
@@ -24,195 +44,4209 @@ impl Env {
     const DEPOSIT_AMOUNT: u64 = 5_000_000_000;
 
     async fn new() -> Env {
-        let transfer_program = ProgramTest::new("token", id(), processor!(process_instruction));
+        Env::with_program_test(ProgramTest::new("token", id(), processor!(process_instruction))).await
+    }
+
+    /// Program upgradable by `from`, who creates program config with `TokenInstruction::with_program_data`
+    async fn with_program_test(mut transfer_program: ProgramTest) -> Env {
+        let from = Keypair::new();
+        let to = Keypair::new();
+        add_program_data(&mut transfer_program, &from.pubkey());
         let mut ctx = transfer_program.start_with_context().await;
 
-        let from = Keypair::new();
-        let to = Keypair::new();
+        let from_deposit_instr = system_instruction::transfer(
+            &ctx.payer.pubkey(),
+            &from.pubkey(),
+            Env::DEPOSIT_AMOUNT,
+        );
+        let to_deposit_instr = system_instruction::transfer(
+            &ctx.payer.pubkey(),
+            &to.pubkey(),
+            Env::DEPOSIT_AMOUNT,
+        );
+        let deposit_tx = Transaction::new_signed_with_payer(
+            &[from_deposit_instr, to_deposit_instr],
+            Some(&ctx.payer.pubkey()),
+            &[&ctx.payer],
+            ctx.last_blockhash,
+        );
+        ctx.banks_client.process_transaction(deposit_tx).await.unwrap();
+
+        Env { ctx, from, to }
+    }
+}
+
+/// ProgramData account of the program upgradable by `authority`, program-test loads the program without one
+fn add_program_data(program_test: &mut ProgramTest, authority: &Pubkey) {
+    let (program_data, _) = Pubkey::find_program_address(&[id().as_ref()], &bpf_loader_upgradeable::id());
+    let state = UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address: Some(*authority) };
+    let account = solana_sdk::account::Account::new_data(1_000_000_000, &state, &bpf_loader_upgradeable::id());
+    program_test.add_account(program_data, account.unwrap());
+}
+
+fn create_associated_token_account(funding: &Pubkey, wallet: &Pubkey, mint: &Pubkey) -> Instruction {
+    spl_associated_token_account::instruction::create_associated_token_account(funding, wallet, mint, &spl_token::id())
+}
+
+/// Warp `slots` ahead and move the clock on by their duration, warping alone leaves `unix_timestamp` behind
+async fn warp(ctx: &mut ProgramTestContext, slots: u64) {
+    let clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    ctx.warp_to_slot(clock.slot + slots).unwrap();
+    let warped = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let seconds = (slots * DEFAULT_MS_PER_SLOT / 1_000) as i64;
+    ctx.set_sysvar(&Clock { unix_timestamp: clock.unix_timestamp + seconds, ..warped });
+}
+
+/// Program account state, `get_account_data_with_borsh` of program-test expects another borsh version
+async fn account_state<T: BorshDeserialize>(banks_client: &mut BanksClient, address: Pubkey) -> T {
+    let account = banks_client.get_account(address).await.unwrap().unwrap();
+    T::deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+#[tokio::test]
+async fn transfer_lamports() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+
+    let transfer_amount = 1_111_111;
+    let from_balance_before_transfer = ctx
+        .banks_client
+        .get_balance(from.pubkey())
+        .await
+        .unwrap();
+    let to_balance_before_transfer = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+
+    let transfer_instr = TokenInstruction::transfer_lamports(
+        from.pubkey(),
+        to.pubkey(),
+        transfer_amount,
+    );
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[transfer_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let transfer_tx_fee = ctx.banks_client.get_fee_for_message(transfer_tx.message().clone()).await.unwrap().unwrap();
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let to_balance_after_transfer = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_balance_after_transfer - to_balance_before_transfer, transfer_amount);
+
+    let from_balance_after_transfer = ctx
+        .banks_client
+        .get_balance(from.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(from_balance_before_transfer, from_balance_after_transfer + transfer_amount + transfer_tx_fee);
+}
+
+#[tokio::test]
+async fn transfer_lamports_sequenced() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+
+    let stream_key = from.pubkey();
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_paused(from.pubkey(), false)),
+            TokenInstruction::initialize_event_stream(from.pubkey(), stream_key),
+            TokenInstruction::with_event_stream(
+                TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), 1_000),
+                &stream_key,
+            ),
+            TokenInstruction::with_event_stream(
+                TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), 2_000),
+                &stream_key,
+            ),
+            TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), 3_000),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let event_stream: EventStream =
+        account_state(&mut ctx.banks_client, EventStream::find_address(&stream_key).0).await;
+    assert_eq!(event_stream.key, stream_key);
+    assert_eq!(event_stream.sequence, 2);
+}
+
+#[tokio::test]
+async fn transfer_lamports_batch() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let other = Pubkey::new_unique();
+    let mut ctx = env.ctx;
+    let (to_amount, other_amount) = (1_111_111, 2_222_222);
+
+    let overdrawn_instr = TokenInstruction::transfer_lamports_batch(
+        from.pubkey(),
+        &[(to.pubkey(), to_amount), (other, Env::DEPOSIT_AMOUNT)],
+    );
+    let overdrawn_tx = Transaction::new_signed_with_payer(
+        &[overdrawn_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(overdrawn_tx).await.is_err());
+    assert_eq!(ctx.banks_client.get_balance(to.pubkey()).await.unwrap(), Env::DEPOSIT_AMOUNT);
+
+    let batch_instr = TokenInstruction::transfer_lamports_batch(
+        from.pubkey(),
+        &[(to.pubkey(), to_amount), (other, other_amount)],
+    );
+    let batch_tx = Transaction::new_signed_with_payer(
+        &[batch_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(batch_tx).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(to.pubkey()).await.unwrap(), Env::DEPOSIT_AMOUNT + to_amount);
+    assert_eq!(ctx.banks_client.get_balance(other).await.unwrap(), other_amount);
+}
+
+#[tokio::test]
+async fn transfer_lamports_batch_sequenced() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut ctx = env.ctx;
+
+    let stream_key = from.pubkey();
+    let batch_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_paused(from.pubkey(), false)),
+            TokenInstruction::initialize_event_stream(from.pubkey(), stream_key),
+            TokenInstruction::with_event_stream(
+                TokenInstruction::transfer_lamports_batch(
+                    from.pubkey(),
+                    &[(recipients[0], 1_000_000), (recipients[1], 1_000_000)],
+                ),
+                &stream_key,
+            ),
+            TokenInstruction::with_event_stream(
+                TokenInstruction::split_lamports(from.pubkey(), to.pubkey(), &recipients, 2_000_001, &[]),
+                &stream_key,
+            ),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(batch_tx).await.unwrap();
+
+    // One event per leg: two batch recipients, two split shares and the split remainder
+    let event_stream: EventStream =
+        account_state(&mut ctx.banks_client, EventStream::find_address(&stream_key).0).await;
+    assert_eq!(event_stream.sequence, 5);
+}
+
+#[tokio::test]
+async fn split_lamports() {
+    let env = Env::new().await;
+    let (from, remainder) = (env.from, env.to);
+    let recipients = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut ctx = env.ctx;
+
+    let equal_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::split_lamports(from.pubkey(), remainder.pubkey(), &recipients, 3_000_002, &[])],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(equal_tx).await.unwrap();
+
+    for recipient in &recipients {
+        assert_eq!(ctx.banks_client.get_balance(*recipient).await.unwrap(), 1_000_000);
+    }
+    assert_eq!(ctx.banks_client.get_balance(remainder.pubkey()).await.unwrap(), Env::DEPOSIT_AMOUNT + 2);
+
+    let weighted_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::split_lamports(from.pubkey(), remainder.pubkey(), &recipients, 1_000_000, &[1, 1, 2])],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(weighted_tx).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(recipients[0]).await.unwrap(), 1_250_000);
+    assert_eq!(ctx.banks_client.get_balance(recipients[2]).await.unwrap(), 1_500_000);
+}
+
+#[tokio::test]
+async fn transfer_lamports_with_fee() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let fee_recipient = Pubkey::new_unique();
+    let mut ctx = env.ctx;
+    let transfer_amount = 1_000_000;
+
+    // Fee of the transfer alone would leave the new fee recipient below rent exemption
+    let rent_exempt = ctx.banks_client.get_rent().await.unwrap().minimum_balance(0);
+    let init_fee_tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&from.pubkey(), &fee_recipient, rent_exempt),
+            TokenInstruction::with_program_data(
+                TokenInstruction::initialize_fee_config(from.pubkey(), fee_recipient, 30),
+            ),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(init_fee_tx).await.unwrap();
+
+    let transfer_instr = TokenInstruction::with_fee(
+        TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), transfer_amount),
+        fee_recipient,
+    );
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[transfer_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(to.pubkey()).await.unwrap(), Env::DEPOSIT_AMOUNT + 997_000);
+    assert_eq!(ctx.banks_client.get_balance(fee_recipient).await.unwrap(), rent_exempt + 3_000);
+
+    let wrong_recipient_instr = TokenInstruction::with_fee(
+        TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), transfer_amount),
+        Pubkey::new_unique(),
+    );
+    let wrong_recipient_tx = Transaction::new_signed_with_payer(
+        &[wrong_recipient_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(wrong_recipient_tx).await.is_err());
+}
+
+#[tokio::test]
+async fn sweep_spl_token_and_lamports() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+
+    let sweep_spl_token_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::sweep_spl_token(
+            from.pubkey(),
+            mint_env.from_spl_token.pubkey(),
+            mint_env.to_spl_token.pubkey(),
+            mint_env.minter.pubkey(),
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(sweep_spl_token_tx).await.unwrap();
+
+    let from_spl_token_acc: Account =
+        ctx.banks_client.get_packed_account_data(mint_env.from_spl_token.pubkey()).await.unwrap();
+    let to_spl_token_acc: Account =
+        ctx.banks_client.get_packed_account_data(mint_env.to_spl_token.pubkey()).await.unwrap();
+    assert_eq!((from_spl_token_acc.amount, to_spl_token_acc.amount), (0, MintEnv::MINT_AMOUNT));
+
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let to_balance_before_sweep = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+    let from_balance_before_sweep = ctx.banks_client.get_balance(from.pubkey()).await.unwrap();
+    let sweep_lamports_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::sweep_lamports(from.pubkey(), to.pubkey())],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let sweep_tx_fee =
+        ctx.banks_client.get_fee_for_message(sweep_lamports_tx.message().clone()).await.unwrap().unwrap();
+    ctx.banks_client.process_transaction(sweep_lamports_tx).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(from.pubkey()).await.unwrap(), rent.minimum_balance(0));
+    assert_eq!(
+        ctx.banks_client.get_balance(to.pubkey()).await.unwrap() - to_balance_before_sweep,
+        from_balance_before_sweep - sweep_tx_fee - rent.minimum_balance(0),
+    );
+}
+
+#[tokio::test]
+async fn transfer_lamports_from_program_owned_account() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let program_owned = Keypair::new();
+    let transfer_amount = 1_000_000;
+
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let create_program_owned_instr = system_instruction::create_account(
+        &from.pubkey(),
+        &program_owned.pubkey(),
+        rent.minimum_balance(0) + transfer_amount,
+        0,
+        &id(),
+    );
+    let create_program_owned_tx = Transaction::new_signed_with_payer(
+        &[create_program_owned_instr],
+        Some(&from.pubkey()),
+        &[&from, &program_owned],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_program_owned_tx).await.unwrap();
+
+    let to_balance_before_transfer = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+    let below_rent_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports(program_owned.pubkey(), to.pubkey(), transfer_amount + 1)],
+        Some(&from.pubkey()),
+        &[&from, &program_owned],
+        ctx.last_blockhash,
+    );
+    let below_rent_err = ctx.banks_client.process_transaction(below_rent_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        below_rent_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::BelowRentExemption as u32)),
+    );
+
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports(program_owned.pubkey(), to.pubkey(), transfer_amount)],
+        Some(&from.pubkey()),
+        &[&from, &program_owned],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(program_owned.pubkey()).await.unwrap(), rent.minimum_balance(0));
+    assert_eq!(
+        ctx.banks_client.get_balance(to.pubkey()).await.unwrap(),
+        to_balance_before_transfer + transfer_amount,
+    );
+}
+
+#[tokio::test]
+async fn transfer_lamports_with_audit_log() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let transfer_amount = 1_000_000;
+
+    let init_audit_log_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_program_data(TokenInstruction::initialize_audit_log(from.pubkey()))],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(init_audit_log_tx).await.unwrap();
+
+    let transfer_instr = TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), transfer_amount);
+    let keys: Vec<Pubkey> = transfer_instr.accounts.iter().map(|meta| meta.pubkey).collect();
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_audit_log(transfer_instr)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let (audit_log_key, _) = AuditLog::find_address();
+    let audit_log = account_state::<AuditLog>(&mut ctx.banks_client, audit_log_key).await;
+    let mut replayed =
+        AuditLog { account_type: AccountType::AuditLog, head: [0; 32], count: 0, last_slot: 0, bump: audit_log.bump };
+    replayed.fold(0, &keys, transfer_amount, audit_log.last_slot);
+    assert_eq!(replayed, audit_log);
+    assert_eq!(audit_log.count, 1);
+}
+
+#[tokio::test]
+async fn transfer_lamports_with_registry() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let transfer_amount = 1_000_000;
+
+    let init_registry_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_program_data(
+            TokenInstruction::initialize_registry(from.pubkey(), RegistryMode::Allow),
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(init_registry_tx).await.unwrap();
+
+    let not_permitted_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), 1)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let not_permitted_err = ctx.banks_client.process_transaction(not_permitted_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        not_permitted_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::RecipientNotPermitted as u32)),
+    );
+
+    let to_balance_before_transfer = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+    let transfer_instr = TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), transfer_amount);
+    let add_to_registry_instr = TokenInstruction::add_to_registry(from.pubkey(), to.pubkey());
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[add_to_registry_instr, transfer_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let not_permitted_batch_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports_batch(from.pubkey(), &[(to.pubkey(), 1), (Pubkey::new_unique(), 1)])],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let not_permitted_batch_err =
+        ctx.banks_client.process_transaction(not_permitted_batch_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        not_permitted_batch_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::RecipientNotPermitted as u32)),
+    );
+
+    let (registry_key, _) = Registry::find_address();
+    let registry_data = ctx.banks_client.get_account(registry_key).await.unwrap().unwrap().data;
+    let registry = Registry::deserialize(&mut &registry_data[..]).unwrap();
+    assert_eq!((registry.admin, registry.entries), (from.pubkey(), vec![to.pubkey()]));
+    assert_eq!(
+        ctx.banks_client.get_balance(to.pubkey()).await.unwrap(),
+        to_balance_before_transfer + transfer_amount,
+    );
+}
+
+#[tokio::test]
+async fn transfer_lamports_with_spending_limit() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let (limit, window_seconds) = (1_000_000, 60);
+    let transfer = |amount| TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), amount);
+
+    let set_limit_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(
+                TokenInstruction::set_spending_limit(
+                    from.pubkey(),
+                    from.pubkey(),
+                    Pubkey::default(),
+                    limit,
+                    window_seconds,
+                ),
+            ),
+            transfer(600_000),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(set_limit_tx).await.unwrap();
+
+    let exceeding_tx = Transaction::new_signed_with_payer(
+        &[transfer(400_001)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let exceeding_err = ctx.banks_client.process_transaction(exceeding_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        exceeding_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::SpendingLimitExceeded as u32)),
+    );
+
+    warp(&mut ctx, 500).await;
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let rolled_over_tx = Transaction::new_signed_with_payer(
+        &[transfer(limit)],
+        Some(&from.pubkey()),
+        &[&from],
+        blockhash,
+    );
+    ctx.banks_client.process_transaction(rolled_over_tx).await.unwrap();
+
+    let clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let (spending_limit_key, _) = SpendingLimit::find_address(&from.pubkey(), &Pubkey::default());
+    let spending_limit_data = ctx.banks_client.get_account(spending_limit_key).await.unwrap().unwrap().data;
+    let spending_limit = SpendingLimit::deserialize(&mut &spending_limit_data[..]).unwrap();
+    assert_eq!(spending_limit.spent(clock.unix_timestamp), limit);
+}
+
+#[tokio::test]
+async fn batch_split_and_spl_transfers_count_against_spending_limit_of_their_mint() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let (from_spl_token, to_spl_token, mint) =
+        (mint_env.from_spl_token.pubkey(), mint_env.to_spl_token.pubkey(), mint_env.minter.pubkey());
+    let lamports = Pubkey::default();
+
+    let spl_transfer_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(
+                TokenInstruction::set_spending_limit(from.pubkey(), from.pubkey(), lamports, 1_000, 86_400),
+            ),
+            TokenInstruction::with_program_data(
+                TokenInstruction::set_spending_limit(from.pubkey(), from.pubkey(), mint, 1_000, 86_400),
+            ),
+            TokenInstruction::transfer_spl_token_v2(from.pubkey(), from_spl_token, to_spl_token, mint, 600),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(spl_transfer_tx).await.unwrap();
+
+    // Token base units spent so far don't count against the lamport limit
+    let batch_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports_batch(from.pubkey(), &[(to.pubkey(), 600), (to.pubkey(), 400)])],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(batch_tx).await.unwrap();
+
+    let exceeding_instrs = [
+        TokenInstruction::transfer_lamports_batch(from.pubkey(), &[(to.pubkey(), 1)]),
+        TokenInstruction::split_lamports(from.pubkey(), to.pubkey(), &[to.pubkey()], 1, &[1]),
+        TokenInstruction::transfer_spl_token_batch(from.pubkey(), from_spl_token, mint, &[(to_spl_token, 401)]),
+    ];
+    for exceeding_instr in exceeding_instrs {
+        let exceeding_tx = Transaction::new_signed_with_payer(
+            &[exceeding_instr],
+            Some(&from.pubkey()),
+            &[&from],
+            ctx.last_blockhash,
+        );
+        let exceeding_err = ctx.banks_client.process_transaction(exceeding_tx).await.unwrap_err().unwrap();
+        assert_eq!(
+            exceeding_err,
+            TransactionError::InstructionError(0, InstructionError::Custom(TransferError::SpendingLimitExceeded as u32)),
+        );
+    }
+
+    let clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    for (limited_mint, spent) in [(lamports, 1_000), (mint, 600)] {
+        let (spending_limit_key, _) = SpendingLimit::find_address(&from.pubkey(), &limited_mint);
+        let spending_limit_data = ctx.banks_client.get_account(spending_limit_key).await.unwrap().unwrap().data;
+        let spending_limit = SpendingLimit::deserialize(&mut &spending_limit_data[..]).unwrap();
+        assert_eq!((spending_limit.mint, spending_limit.spent(clock.unix_timestamp)), (limited_mint, spent));
+    }
+}
+
+/// Program config by `from`, namespace with admin `to`
+async fn initialize_namespace(ctx: &mut ProgramTestContext, from: &Keypair, to: &Keypair) -> Pubkey {
+    let namespace = Keypair::new();
+    let init_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_paused(from.pubkey(), false)),
+            TokenInstruction::initialize_namespace(to.pubkey(), namespace.pubkey()),
+        ],
+        Some(&from.pubkey()),
+        &[from, to, &namespace],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(init_tx).await.unwrap();
+    namespace.pubkey()
+}
+
+#[tokio::test]
+async fn set_paused_in_namespace() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let namespace = initialize_namespace(&mut ctx, &from, &to).await;
+    let (namespace_config_key, _) = Config::find_namespaced_address(&namespace);
+    let blockhash = ctx.last_blockhash;
+    let transfer = |namespace: Option<&Pubkey>| {
+        let instr = TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), 1);
+        let instr = match namespace {
+            Some(namespace) => TokenInstruction::in_namespace(instr, namespace),
+            None => instr,
+        };
+        Transaction::new_signed_with_payer(&[instr], Some(&from.pubkey()), &[&from], blockhash)
+    };
+
+    let not_namespace_admin_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::in_namespace(TokenInstruction::set_paused(from.pubkey(), true), &namespace)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(not_namespace_admin_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::NotConfigAdmin as u32)),
+    );
+
+    let pause_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::in_namespace(TokenInstruction::set_paused(to.pubkey(), true), &namespace)],
+        Some(&from.pubkey()),
+        &[&from, &to],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(pause_tx).await.unwrap();
+
+    let err = ctx.banks_client.process_transaction(transfer(Some(&namespace))).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::ProgramPaused as u32)),
+    );
+    ctx.banks_client.process_transaction(transfer(None)).await.unwrap();
+
+    for (config_key, paused) in [(namespace_config_key, true), (Config::find_address().0, false)] {
+        let config_data = ctx.banks_client.get_account(config_key).await.unwrap().unwrap().data;
+        let config = Config::deserialize(&mut &config_data[..]).unwrap();
+        assert_eq!(config.paused, paused);
+    }
+}
+
+#[tokio::test]
+async fn transfer_lamports_with_registry_in_namespace() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let namespace = initialize_namespace(&mut ctx, &from, &to).await;
+    let (namespace_config_key, _) = Config::find_namespaced_address(&namespace);
+
+    let registry_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::in_namespace(
+            TokenInstruction::initialize_registry(to.pubkey(), RegistryMode::Allow),
+            &namespace,
+        )],
+        Some(&from.pubkey()),
+        &[&from, &to],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(registry_tx).await.unwrap();
+
+    let (registry_key, _) = Registry::find_namespaced_address(&namespace_config_key);
+    assert!(ctx.banks_client.get_account(registry_key).await.unwrap().is_some());
+    assert!(ctx.banks_client.get_account(Registry::find_address().0).await.unwrap().is_none());
+
+    let transfer = TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), 1);
+    let namespaced_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::in_namespace(transfer.clone(), &namespace)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(namespaced_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::RecipientNotPermitted as u32)),
+    );
+
+    let mut unregistered_instr = TokenInstruction::in_namespace(transfer.clone(), &namespace);
+    let registry_index = unregistered_instr.accounts.iter().position(|meta| meta.pubkey == registry_key).unwrap();
+    unregistered_instr.accounts[registry_index].pubkey = Registry::find_address().0;
+    let unregistered_tx = Transaction::new_signed_with_payer(
+        &[unregistered_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(unregistered_tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidSeeds));
+
+    let default_namespace_tx =
+        Transaction::new_signed_with_payer(&[transfer], Some(&from.pubkey()), &[&from], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(default_namespace_tx).await.unwrap();
+
+    let permitted_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::in_namespace(TokenInstruction::add_to_registry(to.pubkey(), to.pubkey()), &namespace),
+            TokenInstruction::in_namespace(
+                TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), 2),
+                &namespace,
+            ),
+        ],
+        Some(&from.pubkey()),
+        &[&from, &to],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(permitted_tx).await.unwrap();
+
+    let program_registry_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(
+                TokenInstruction::initialize_registry(from.pubkey(), RegistryMode::Allow),
+            ),
+            TokenInstruction::in_namespace(
+                TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), 3),
+                &namespace,
+            ),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(program_registry_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::Custom(TransferError::RecipientNotPermitted as u32)),
+    );
+}
+
+#[tokio::test]
+async fn create_config_by_upgrade_authority() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let not_config_admin =
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::NotConfigAdmin as u32));
+
+    let not_authority_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_program_data(TokenInstruction::set_paused(to.pubkey(), false))],
+        Some(&to.pubkey()),
+        &[&to],
+        ctx.last_blockhash,
+    );
+    let not_authority_err = ctx.banks_client.process_transaction(not_authority_tx).await.unwrap_err().unwrap();
+    assert_eq!(not_authority_err, not_config_admin);
+
+    let no_program_data_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::set_paused(from.pubkey(), false)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let no_program_data_err = ctx.banks_client.process_transaction(no_program_data_tx).await.unwrap_err().unwrap();
+    assert_eq!(no_program_data_err, not_config_admin);
+    assert!(ctx.banks_client.get_account(Config::find_address().0).await.unwrap().is_none());
+
+    let create_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_program_data(TokenInstruction::set_paused(from.pubkey(), false))],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_tx).await.unwrap();
+
+    let config: Config = account_state(&mut ctx.banks_client, Config::find_address().0).await;
+    assert_eq!(config.admin, from.pubkey());
+}
+
+#[tokio::test]
+async fn propose_and_execute_transfer() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let approver = Keypair::new();
+    let (threshold, transfer_amount) = (1_000_000, 5_000_000);
+
+    let set_policy_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_program_data(
+            TokenInstruction::set_approval_policy(from.pubkey(), from.pubkey(), approver.pubkey(), threshold),
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(set_policy_tx).await.unwrap();
+
+    let direct_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), transfer_amount)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let direct_err = ctx.banks_client.process_transaction(direct_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        direct_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::ApprovalRequired as u32)),
+    );
+
+    let propose_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::propose_transfer(from.pubkey(), to.pubkey(), transfer_amount)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(propose_tx).await.unwrap();
+
+    let not_approver_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::execute_transfer(from.pubkey(), from.pubkey(), to.pubkey())],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let not_approver_err = ctx.banks_client.process_transaction(not_approver_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        not_approver_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::NotApprover as u32)),
+    );
+
+    let to_balance_before_execute = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+    let execute_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::execute_transfer(approver.pubkey(), from.pubkey(), to.pubkey())],
+        Some(&from.pubkey()),
+        &[&from, &approver],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(execute_tx).await.unwrap();
+
+    assert_eq!(
+        ctx.banks_client.get_balance(to.pubkey()).await.unwrap(),
+        to_balance_before_execute + transfer_amount,
+    );
+    let (pending_transfer_key, _) = PendingTransfer::find_address(&from.pubkey(), &to.pubkey());
+    assert!(ctx.banks_client.get_account(pending_transfer_key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn execute_transfer_applies_policy_of_proposer() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let approver = Keypair::new();
+    let (threshold, transfer_amount) = (1_000_000, 5_000_000);
+    let lamports = Pubkey::default();
+
+    let set_policy_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(
+                TokenInstruction::set_approval_policy(from.pubkey(), from.pubkey(), approver.pubkey(), threshold),
+            ),
+            TokenInstruction::with_program_data(
+                TokenInstruction::set_spending_limit(from.pubkey(), from.pubkey(), lamports, threshold, 86_400),
+            ),
+            TokenInstruction::with_program_data(TokenInstruction::initialize_registry(from.pubkey(), RegistryMode::Allow)),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(set_policy_tx).await.unwrap();
+
+    let direct_amount_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::propose_transfer(from.pubkey(), to.pubkey(), threshold)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(direct_amount_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::ApprovalNotRequired as u32)),
+    );
+
+    let propose_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::propose_transfer(from.pubkey(), to.pubkey(), transfer_amount)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(propose_tx).await.unwrap();
+
+    let execute = |blockhash| Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_event_stream(
+            TokenInstruction::execute_transfer(approver.pubkey(), from.pubkey(), to.pubkey()),
+            &from.pubkey(),
+        )],
+        Some(&from.pubkey()),
+        &[&from, &approver],
+        blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(execute(ctx.last_blockhash)).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::RecipientNotPermitted as u32)),
+    );
+
+    let registry_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::add_to_registry(from.pubkey(), to.pubkey())],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(registry_tx).await.unwrap();
+    warp(&mut ctx, 1).await;
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let err = ctx.banks_client.process_transaction(execute(blockhash)).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::SpendingLimitExceeded as u32)),
+    );
+
+    let raise_limit_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::set_spending_limit(from.pubkey(), from.pubkey(), lamports, transfer_amount, 86_400),
+            TokenInstruction::initialize_event_stream(from.pubkey(), from.pubkey()),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        blockhash,
+    );
+    ctx.banks_client.process_transaction(raise_limit_tx).await.unwrap();
+    warp(&mut ctx, 1).await;
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let to_balance_before_execute = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+    ctx.banks_client.process_transaction(execute(blockhash)).await.unwrap();
+
+    assert_eq!(
+        ctx.banks_client.get_balance(to.pubkey()).await.unwrap(),
+        to_balance_before_execute + transfer_amount,
+    );
+    let clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let (spending_limit_key, _) = SpendingLimit::find_address(&from.pubkey(), &lamports);
+    let spending_limit: SpendingLimit = account_state(&mut ctx.banks_client, spending_limit_key).await;
+    assert_eq!(spending_limit.spent(clock.unix_timestamp), transfer_amount);
+    let event_stream: EventStream =
+        account_state(&mut ctx.banks_client, EventStream::find_address(&from.pubkey()).0).await;
+    assert_eq!(event_stream.sequence, 1);
+
+    let paused_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::set_paused(from.pubkey(), true),
+            TokenInstruction::propose_transfer(from.pubkey(), to.pubkey(), transfer_amount),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(paused_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::Custom(TransferError::ProgramPaused as u32)),
+    );
+}
+
+#[tokio::test]
+async fn batch_and_spl_transfers_require_approval_above_threshold() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let (from_spl_token, to_spl_token, mint) =
+        (mint_env.from_spl_token.pubkey(), mint_env.to_spl_token.pubkey(), mint_env.minter.pubkey());
+
+    let set_policy_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_program_data(
+            TokenInstruction::set_approval_policy(from.pubkey(), from.pubkey(), Pubkey::new_unique(), 1_000),
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(set_policy_tx).await.unwrap();
+
+    let above_threshold_instrs = [
+        TokenInstruction::transfer_spl_token_v2(from.pubkey(), from_spl_token, to_spl_token, mint, 1_001),
+        TokenInstruction::transfer_spl_token_batch(from.pubkey(), from_spl_token, mint, &[(to_spl_token, 1_001)]),
+        TokenInstruction::transfer_lamports_batch(from.pubkey(), &[(to.pubkey(), 600), (to.pubkey(), 401)]),
+    ];
+    for above_threshold_instr in above_threshold_instrs {
+        let above_threshold_tx = Transaction::new_signed_with_payer(
+            &[above_threshold_instr],
+            Some(&from.pubkey()),
+            &[&from],
+            ctx.last_blockhash,
+        );
+        let err = ctx.banks_client.process_transaction(above_threshold_tx).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(TransferError::ApprovalRequired as u32)),
+        );
+    }
+
+    let within_threshold_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::transfer_spl_token_v2(from.pubkey(), from_spl_token, to_spl_token, mint, 1_000),
+            TokenInstruction::transfer_lamports_batch(from.pubkey(), &[(to.pubkey(), 600), (to.pubkey(), 400)]),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(within_threshold_tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn transfer_spl_token() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let from = env.from;
+    let transfer_amount = MintEnv::MINT_AMOUNT;
+    let mut ctx = env.ctx;
+
+    let from_spl_token_acc_before_transfer: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.from_spl_token.pubkey())
+        .await
+        .unwrap();
+    let to_spl_token_acc_before_transfer: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+
+    let transfer_spl_token_instr = TokenInstruction::transfer_spl_token(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        mint_env.minter.pubkey(),
+        transfer_amount,
+    );
+    let transfer_spl_token_tx = Transaction::new_signed_with_payer(
+        &[transfer_spl_token_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_spl_token_tx).await.unwrap();
+
+    let from_spl_token_acc_after_transfer: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.from_spl_token.pubkey())
+        .await
+        .unwrap();
+    let to_spl_token_acc_after_transfer: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+
+    assert_eq!(from_spl_token_acc_before_transfer.mint, mint_env.minter.pubkey());
+    assert_eq!(from_spl_token_acc_before_transfer.amount,
+               from_spl_token_acc_after_transfer.amount + transfer_amount);
+    assert_eq!(to_spl_token_acc_before_transfer.mint, mint_env.minter.pubkey());
+    assert_eq!(to_spl_token_acc_before_transfer.amount,
+               to_spl_token_acc_after_transfer.amount - transfer_amount);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_with_memo() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let from = env.from;
+    let mut ctx = env.ctx;
+
+    let transfer_instr = TokenInstruction::transfer_spl_token_with_memo(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        mint_env.minter.pubkey(),
+        1_000,
+        "INV-2021-0042".to_string(),
+    );
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[transfer_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let to_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_spl_token_acc.amount, 1_000);
+
+    let mut invalid_memo_program_instr = TokenInstruction::transfer_lamports_with_memo(
+        from.pubkey(),
+        env.to.pubkey(),
+        1_000,
+        "INV-2021-0043".to_string(),
+    );
+    let memo_program = invalid_memo_program_instr.accounts.iter_mut().find(|meta| meta.pubkey == spl_memo::id());
+    memo_program.unwrap().pubkey = spl_memo::v1::id();
+    let invalid_memo_program_tx = Transaction::new_signed_with_payer(
+        &[invalid_memo_program_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(invalid_memo_program_tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IncorrectProgramId));
+}
+
+#[tokio::test]
+async fn transfer_spl_token_to_wallet() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let from = env.from;
+    let transfer_amount = MintEnv::MINT_AMOUNT;
+    let mut ctx = env.ctx;
+
+    // destination is an auxiliary token account owned by the wallet, not its associated one
+    let transfer_spl_token_instr = TokenInstruction::transfer_spl_token_to_wallet(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        from.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        mint_env.minter.pubkey(),
+        transfer_amount,
+    );
+    let transfer_spl_token_tx = Transaction::new_signed_with_payer(
+        &[transfer_spl_token_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_spl_token_tx).await.unwrap();
+
+    let to_spl_token_acc_after_transfer: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_spl_token_acc_after_transfer.amount, transfer_amount);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_strict_ata() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let from = env.from;
+    let mut ctx = env.ctx;
+
+    let set_config_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_program_data(TokenInstruction::set_config(from.pubkey(), true))],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(set_config_tx).await.unwrap();
+
+    let transfer_spl_token_instr = TokenInstruction::transfer_spl_token(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        mint_env.minter.pubkey(),
+        MintEnv::MINT_AMOUNT,
+    );
+    let transfer_spl_token_tx = Transaction::new_signed_with_payer(
+        &[transfer_spl_token_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(transfer_spl_token_tx).await.is_err());
+}
+
+#[tokio::test]
+async fn transfer_spl_token_paused() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+
+    let set_paused = |paused| Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_program_data(TokenInstruction::set_paused(from.pubkey(), paused))],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let transfer = |amount| Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_spl_token(
+            from.pubkey(),
+            mint_env.from_spl_token.pubkey(),
+            mint_env.to_spl_token.pubkey(),
+            mint_env.minter.pubkey(),
+            amount,
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let (pause_tx, resume_tx) = (set_paused(true), set_paused(false));
+    let (paused_transfer_tx, transfer_tx) = (transfer(MintEnv::MINT_AMOUNT), transfer(MintEnv::MINT_AMOUNT - 1));
+
+    ctx.banks_client.process_transaction(pause_tx).await.unwrap();
+    let err = ctx.banks_client.process_transaction(paused_transfer_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::ProgramPaused as u32))
+    );
+
+    let paused_lamports_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), 1)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(paused_lamports_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::ProgramPaused as u32))
+    );
+
+    let mut unpaused_config_instr = TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), 1);
+    let config_index =
+        unpaused_config_instr.accounts.iter().position(|meta| meta.pubkey == Config::find_address().0).unwrap();
+    unpaused_config_instr.accounts[config_index].pubkey = Pubkey::new_unique();
+    let unpaused_config_tx = Transaction::new_signed_with_payer(
+        &[unpaused_config_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(unpaused_config_tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidSeeds));
+
+    ctx.banks_client.process_transaction(resume_tx).await.unwrap();
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn approve_spl_token() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let from = env.from;
+    let transfer_amount = MintEnv::MINT_AMOUNT;
+    let mut ctx = env.ctx;
+    let from_spl_token = mint_env.from_spl_token;
+    let to_spl_token = mint_env.to_spl_token;
+
+    let from_spl_token_acc_before_transfer: Account = ctx.banks_client
+        .get_packed_account_data(from_spl_token.pubkey())
+        .await
+        .unwrap();
+    let to_spl_token_acc_before_transfer: Account = ctx.banks_client
+        .get_packed_account_data(to_spl_token.pubkey())
+        .await
+        .unwrap();
+
+    let transfer_spl_token_instr = TokenInstruction::approve_spl_token(
+        from.pubkey(),
+        from_spl_token.pubkey(),
+        to_spl_token.pubkey(),
+        transfer_amount,
+    );
+    let transfer_spl_token_tx = Transaction::new_signed_with_payer(
+        &[transfer_spl_token_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_spl_token_tx).await.unwrap();
+
+    let from_spl_token_acc_after_transfer: Account = ctx.banks_client
+        .get_packed_account_data(from_spl_token.pubkey())
+        .await
+        .unwrap();
+    let to_spl_token_acc_after_transfer: Account = ctx.banks_client
+        .get_packed_account_data(to_spl_token.pubkey())
+        .await
+        .unwrap();
+
+
+    assert_eq!(from_spl_token_acc_before_transfer.mint, mint_env.minter.pubkey());
+    assert_eq!(from_spl_token_acc_before_transfer.delegate, COption::None);
+    assert_eq!(from_spl_token_acc_before_transfer.delegated_amount, 0);
+    assert_eq!(from_spl_token_acc_after_transfer.delegate, COption::Some(to_spl_token.pubkey()));
+    assert_eq!(from_spl_token_acc_after_transfer.delegated_amount, transfer_amount);
+
+    assert_eq!(to_spl_token_acc_before_transfer.mint, mint_env.minter.pubkey());
+    assert_eq!(to_spl_token_acc_before_transfer.delegate, COption::None);
+    assert_eq!(to_spl_token_acc_before_transfer.delegated_amount, 0);
+    assert_eq!(to_spl_token_acc_after_transfer.delegate, COption::None);
+    assert_eq!(to_spl_token_acc_after_transfer.delegated_amount, 0);
+}
+
+#[tokio::test]
+async fn batch_approve() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let mint = mint_env.minter.pubkey();
+    let from_spl_token = mint_env.from_spl_token.pubkey();
+    let to_spl_token = get_associated_token_address(&to.pubkey(), &mint);
+    let (first_delegate, second_delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let create_to_spl_token_instr = create_associated_token_account(&from.pubkey(), &to.pubkey(), &mint);
+    let approve_instr = TokenInstruction::batch_approve(from.pubkey(), &[(from_spl_token, first_delegate, 1_000)]);
+    let approve_tx = Transaction::new_signed_with_payer(
+        &[create_to_spl_token_instr, approve_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    // Token account of another owner rejects the whole batch
+    let not_owned_instr = TokenInstruction::batch_approve(
+        from.pubkey(),
+        &[(from_spl_token, second_delegate, 2_000), (to_spl_token, second_delegate, 2_000)],
+    );
+    let not_owned_tx = Transaction::new_signed_with_payer(
+        &[not_owned_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+
+    ctx.banks_client.process_transaction(approve_tx).await.unwrap();
+    assert!(ctx.banks_client.process_transaction(not_owned_tx).await.is_err());
+
+    let from_spl_token_acc: Account = ctx.banks_client.get_packed_account_data(from_spl_token).await.unwrap();
+    assert_eq!(from_spl_token_acc.delegate, COption::Some(first_delegate));
+    assert_eq!(from_spl_token_acc.delegated_amount, 1_000);
+    let to_spl_token_acc: Account = ctx.banks_client.get_packed_account_data(to_spl_token).await.unwrap();
+    assert_eq!(to_spl_token_acc.delegate, COption::None);
+}
+
+#[tokio::test]
+async fn transfer_lamports_guarded() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let transfer_amount = 1_000_000;
+    let memo_instr = spl_memo::build_memo(b"order-42", &[&from.pubkey()]);
+    let transfer_instr = |amount| TokenInstruction::with_guard(
+        TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), amount),
+    );
+
+    let unguarded_tx = Transaction::new_signed_with_payer(
+        &[transfer_instr(transfer_amount)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(unguarded_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::GuardRequired as u32))
+    );
+
+    // Guard of the memo without the memo itself
+    let missing_memo_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::guard_preceding(&memo_instr), transfer_instr(transfer_amount)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(missing_memo_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::GuardNotSatisfied as u32))
+    );
+
+    let other_memo_instr = spl_memo::build_memo(b"order-43", &[&from.pubkey()]);
+    let other_memo_tx = Transaction::new_signed_with_payer(
+        &[other_memo_instr, TokenInstruction::guard_preceding(&memo_instr), transfer_instr(transfer_amount)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(other_memo_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::Custom(TransferError::GuardNotSatisfied as u32))
+    );
+
+    // Guard right before the first transfer only
+    let shared_guard_tx = Transaction::new_signed_with_payer(
+        &[
+            memo_instr.clone(),
+            TokenInstruction::guard_preceding(&memo_instr),
+            transfer_instr(transfer_amount),
+            transfer_instr(transfer_amount + 1),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(shared_guard_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(3, InstructionError::Custom(TransferError::GuardRequired as u32))
+    );
+
+    let guarded_tx = Transaction::new_signed_with_payer(
+        &[memo_instr.clone(), TokenInstruction::guard_preceding(&memo_instr), transfer_instr(transfer_amount)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(guarded_tx).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(to.pubkey()).await.unwrap(), Env::DEPOSIT_AMOUNT + transfer_amount);
+}
+
+#[tokio::test]
+async fn batch_and_split_transfers_guarded() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let memo_instr = spl_memo::build_memo(b"order-42", &[&from.pubkey()]);
+    let transfer_instrs = [
+        TokenInstruction::transfer_lamports_batch(from.pubkey(), &[(to.pubkey(), 1_000), (to.pubkey(), 2_000)]),
+        TokenInstruction::split_lamports(from.pubkey(), to.pubkey(), &[to.pubkey()], 3_000, &[1]),
+    ];
+
+    for transfer_instr in transfer_instrs {
+        let transfer_instr = TokenInstruction::with_guard(transfer_instr);
+        let unguarded_tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&transfer_instr),
+            Some(&from.pubkey()),
+            &[&from],
+            ctx.last_blockhash,
+        );
+        let err = ctx.banks_client.process_transaction(unguarded_tx).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(TransferError::GuardRequired as u32))
+        );
+
+        let to_balance_before_transfer = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+        let guarded_tx = Transaction::new_signed_with_payer(
+            &[memo_instr.clone(), TokenInstruction::guard_preceding(&memo_instr), transfer_instr],
+            Some(&from.pubkey()),
+            &[&from],
+            ctx.last_blockhash,
+        );
+        ctx.banks_client.process_transaction(guarded_tx).await.unwrap();
+        assert_eq!(ctx.banks_client.get_balance(to.pubkey()).await.unwrap(), to_balance_before_transfer + 3_000);
+    }
+}
+
+#[tokio::test]
+async fn transfer_spl_token_multisig() {
+    let mut env = Env::new().await;
+    let multisig_env = MultisigEnv::new(&mut env).await;
+    let payer = env.from;
+    let mut ctx = env.ctx;
+    let to_spl_token = multisig_env.mint_env.to_spl_token.pubkey();
+
+    let transfer = |signers: &[&Keypair], amount| {
+        let signer_keys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let transfer_instr = TokenInstruction::with_multisig(
+            TokenInstruction::transfer_spl_token(
+                multisig_env.multisig,
+                multisig_env.multisig_spl_token,
+                to_spl_token,
+                multisig_env.mint_env.minter.pubkey(),
+                amount,
+            ),
+            &signer_keys,
+        );
+        let tx_signers: Vec<&Keypair> = std::iter::once(&payer).chain(signers.iter().copied()).collect();
+        Transaction::new_signed_with_payer(&[transfer_instr], Some(&payer.pubkey()), &tx_signers, ctx.last_blockhash)
+    };
+    let [first, second, _] = &multisig_env.signers;
+    let (below_threshold_tx, transfer_tx) = (transfer(&[first], 1_000), transfer(&[first, second], 2_000));
+
+    assert!(ctx.banks_client.process_transaction(below_threshold_tx).await.is_err());
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let to_spl_token_acc: Account = ctx.banks_client.get_packed_account_data(to_spl_token).await.unwrap();
+    assert_eq!(to_spl_token_acc.amount, 2_000);
+    let multisig_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(multisig_env.multisig_spl_token)
+        .await
+        .unwrap();
+    assert_eq!(multisig_spl_token_acc.amount, MintEnv::MINT_AMOUNT - 2_000);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_variants_multisig() {
+    let mut env = Env::new().await;
+    let multisig_env = MultisigEnv::new(&mut env).await;
+    let payer = env.from;
+    let mut ctx = env.ctx;
+    let (multisig, multisig_spl_token) = (multisig_env.multisig, multisig_env.multisig_spl_token);
+    let (mint, decimals) = (multisig_env.mint_env.minter.pubkey(), multisig_env.mint_env.decimals);
+    // destination token account is owned by the payer wallet
+    let to_spl_token = multisig_env.mint_env.to_spl_token.pubkey();
+    let [first, second, _] = &multisig_env.signers;
+    let signer_keys = [first.pubkey(), second.pubkey()];
+
+    let below_threshold_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::with_multisig(
+            TokenInstruction::transfer_spl_token_checked(multisig, multisig_spl_token, to_spl_token, mint, 1, decimals),
+            &[first.pubkey()],
+        )],
+        Some(&payer.pubkey()),
+        &[&payer, first],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(below_threshold_tx).await.is_err());
+
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_multisig(
+                TokenInstruction::transfer_spl_token_batch(multisig, multisig_spl_token, mint, &[(to_spl_token, 1_000)]),
+                &signer_keys,
+            ),
+            TokenInstruction::with_multisig(
+                TokenInstruction::transfer_spl_token_checked(
+                    multisig,
+                    multisig_spl_token,
+                    to_spl_token,
+                    mint,
+                    2_000,
+                    decimals,
+                ),
+                &signer_keys,
+            ),
+            TokenInstruction::with_multisig(
+                TokenInstruction::transfer_spl_token_to_wallet(
+                    multisig,
+                    multisig_spl_token,
+                    payer.pubkey(),
+                    to_spl_token,
+                    mint,
+                    3_000,
+                ),
+                &signer_keys,
+            ),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, first, second],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let to_spl_token_acc: Account = ctx.banks_client.get_packed_account_data(to_spl_token).await.unwrap();
+    assert_eq!(to_spl_token_acc.amount, 6_000);
+}
+
+#[tokio::test]
+async fn approve_spl_token_multisig() {
+    let mut env = Env::new().await;
+    let multisig_env = MultisigEnv::new(&mut env).await;
+    let payer = env.from;
+    let mut ctx = env.ctx;
+    let delegate = Pubkey::new_unique();
+    let [first, _, third] = &multisig_env.signers;
+
+    let approve_instr = TokenInstruction::with_multisig(
+        TokenInstruction::approve_spl_token(
+            multisig_env.multisig,
+            multisig_env.multisig_spl_token,
+            delegate,
+            MintEnv::MINT_AMOUNT,
+        ),
+        &[first.pubkey(), third.pubkey()],
+    );
+    let approve_tx = Transaction::new_signed_with_payer(
+        &[approve_instr],
+        Some(&payer.pubkey()),
+        &[&payer, first, third],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(approve_tx).await.unwrap();
+
+    let multisig_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(multisig_env.multisig_spl_token)
+        .await
+        .unwrap();
+    assert_eq!(multisig_spl_token_acc.delegate, COption::Some(delegate));
+    assert_eq!(multisig_spl_token_acc.delegated_amount, MintEnv::MINT_AMOUNT);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_checked() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let from = env.from;
+    let transfer_amount = MintEnv::MINT_AMOUNT;
+    let mut ctx = env.ctx;
+
+    let transfer_checked = |decimals: u8| TokenInstruction::transfer_spl_token_checked(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        mint_env.minter.pubkey(),
+        transfer_amount,
+        decimals,
+    );
+    let wrong_decimals_tx = Transaction::new_signed_with_payer(
+        &[transfer_checked(mint_env.decimals + 1)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(wrong_decimals_tx).await.is_err());
+
+    let transfer_checked_tx = Transaction::new_signed_with_payer(
+        &[transfer_checked(mint_env.decimals)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_checked_tx).await.unwrap();
+
+    let to_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_spl_token_acc.amount, transfer_amount);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_checked_with_mint_decimals() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let from = env.from;
+    let transfer_amount = MintEnv::MINT_AMOUNT;
+    let mut ctx = env.ctx;
+
+    let cache_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::cache_mint_decimals(from.pubkey(), mint_env.minter.pubkey())],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(cache_tx).await.unwrap();
+
+    let (mint_decimals_key, _) = MintDecimals::find_address(&mint_env.minter.pubkey());
+    let mint_decimals = account_state::<MintDecimals>(&mut ctx.banks_client, mint_decimals_key).await;
+    assert_eq!((mint_decimals.decimals, mint_decimals.token_program), (mint_env.decimals, spl_token::id()));
+
+    let transfer_checked = |decimals: u8| TokenInstruction::with_mint_decimals(
+        TokenInstruction::transfer_spl_token_checked(
+            from.pubkey(),
+            mint_env.from_spl_token.pubkey(),
+            mint_env.to_spl_token.pubkey(),
+            mint_env.minter.pubkey(),
+            transfer_amount,
+            decimals,
+        ),
+        &mint_env.minter.pubkey(),
+    );
+    let wrong_decimals_tx = Transaction::new_signed_with_payer(
+        &[transfer_checked(mint_env.decimals + 1)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let wrong_decimals_err = ctx.banks_client.process_transaction(wrong_decimals_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        wrong_decimals_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::DecimalsMismatch as u32)),
+    );
+
+    let transfer_checked_tx = Transaction::new_signed_with_payer(
+        &[transfer_checked(mint_env.decimals)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_checked_tx).await.unwrap();
+
+    let to_spl_token_acc: Account =
+        ctx.banks_client.get_packed_account_data(mint_env.to_spl_token.pubkey()).await.unwrap();
+    assert_eq!(to_spl_token_acc.amount, transfer_amount);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_as_delegate() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, delegate) = (env.from, env.to);
+    let (approve_amount, transfer_amount) = (MintEnv::MINT_AMOUNT, MintEnv::MINT_AMOUNT / 2);
+    let mut ctx = env.ctx;
+
+    let approve_instr = TokenInstruction::approve_spl_token(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        delegate.pubkey(),
+        approve_amount,
+    );
+    let transfer_as_delegate_instr = TokenInstruction::transfer_spl_token_as_delegate(
+        delegate.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        from.pubkey(),
+        mint_env.minter.pubkey(),
+        transfer_amount,
+    );
+    let approve_tx = Transaction::new_signed_with_payer(
+        &[approve_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(approve_tx).await.unwrap();
+    let transfer_as_delegate_tx = Transaction::new_signed_with_payer(
+        &[transfer_as_delegate_instr],
+        Some(&delegate.pubkey()),
+        &[&delegate],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_as_delegate_tx).await.unwrap();
+
+    let from_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.from_spl_token.pubkey())
+        .await
+        .unwrap();
+    let to_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(from_spl_token_acc.amount, MintEnv::MINT_AMOUNT - transfer_amount);
+    assert_eq!(from_spl_token_acc.delegate, COption::Some(delegate.pubkey()));
+    assert_eq!(from_spl_token_acc.delegated_amount, approve_amount - transfer_amount);
+    assert_eq!(to_spl_token_acc.amount, transfer_amount);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_as_revoked_delegate() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, delegate) = (env.from, env.to);
+    let (revocation, _) = Revocation::find_address(&from.pubkey(), &delegate.pubkey());
+    let mut ctx = env.ctx;
+
+    let approve_instr = TokenInstruction::approve_spl_token(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        delegate.pubkey(),
+        MintEnv::MINT_AMOUNT,
+    );
+    let revoke_instr = TokenInstruction::revoke_delegate(from.pubkey(), delegate.pubkey(), false);
+    let revoke_tx = Transaction::new_signed_with_payer(
+        &[approve_instr, revoke_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(revoke_tx).await.unwrap();
+
+    let blockhash = ctx.last_blockhash;
+    let transfer_as_delegate = |amount: u64| Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_spl_token_as_delegate(
+            delegate.pubkey(),
+            mint_env.from_spl_token.pubkey(),
+            mint_env.to_spl_token.pubkey(),
+            from.pubkey(),
+            mint_env.minter.pubkey(),
+            amount,
+        )],
+        Some(&delegate.pubkey()),
+        &[&delegate],
+        blockhash,
+    );
+    let transfer_as_delegate_tx = transfer_as_delegate(1_000);
+    let err = ctx.banks_client.process_transaction(transfer_as_delegate_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::DelegateRevoked as u32))
+    );
+
+    let reinstate_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::reinstate_delegate(from.pubkey(), delegate.pubkey(), false)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(reinstate_tx).await.unwrap();
+    assert!(ctx.banks_client.get_account(revocation).await.unwrap().is_none());
+
+    ctx.banks_client.process_transaction(transfer_as_delegate(2_000)).await.unwrap();
+}
+
+#[tokio::test]
+async fn mint_and_burn_spl_token() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, not_authority) = (env.from, env.to);
+    let (mint_amount, burn_amount) = (1_000, 400);
+    let mut ctx = env.ctx;
+
+    let mint = |authority: &Keypair| TokenInstruction::mint_spl_token(
+        authority.pubkey(),
+        mint_env.minter.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        mint_amount,
+    );
+    let not_authority_tx = Transaction::new_signed_with_payer(
+        &[mint(&not_authority)],
+        Some(&from.pubkey()),
+        &[&from, &not_authority],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(not_authority_tx).await.is_err());
+
+    let burn_instr = TokenInstruction::burn_spl_token(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        mint_env.minter.pubkey(),
+        burn_amount,
+    );
+    let mint_and_burn_tx = Transaction::new_signed_with_payer(
+        &[mint(&mint_env.mint_authority), burn_instr],
+        Some(&from.pubkey()),
+        &[&from, &mint_env.mint_authority],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(mint_and_burn_tx).await.unwrap();
+
+    let from_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.from_spl_token.pubkey())
+        .await
+        .unwrap();
+    let to_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    let mint_acc: Mint = ctx.banks_client.get_packed_account_data(mint_env.minter.pubkey()).await.unwrap();
+    assert_eq!(from_spl_token_acc.amount, MintEnv::MINT_AMOUNT - burn_amount);
+    assert_eq!(to_spl_token_acc.amount, mint_amount);
+    assert_eq!(mint_acc.supply, MintEnv::MINT_AMOUNT + mint_amount - burn_amount);
+}
+
+#[tokio::test]
+async fn freeze_and_thaw_spl_token_account() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+
+    let freeze_instr = |authority: &Keypair| TokenInstruction::freeze_spl_token_account(
+        authority.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        mint_env.minter.pubkey(),
+    );
+    let not_authority_tx = Transaction::new_signed_with_payer(
+        &[freeze_instr(&to)],
+        Some(&from.pubkey()),
+        &[&from, &to],
+        ctx.last_blockhash,
+    );
+    let not_authority_err = ctx.banks_client.process_transaction(not_authority_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        not_authority_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::NotFreezeAuthority as u32)),
+    );
+
+    let freeze_tx = Transaction::new_signed_with_payer(
+        &[freeze_instr(&mint_env.freeze_authority)],
+        Some(&from.pubkey()),
+        &[&from, &mint_env.freeze_authority],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(freeze_tx).await.unwrap();
+
+    let from_spl_token_acc: Account =
+        ctx.banks_client.get_packed_account_data(mint_env.from_spl_token.pubkey()).await.unwrap();
+    assert!(from_spl_token_acc.is_frozen());
+    let frozen_transfer_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_spl_token(
+            from.pubkey(),
+            mint_env.from_spl_token.pubkey(),
+            mint_env.to_spl_token.pubkey(),
+            mint_env.minter.pubkey(),
+            1_000,
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(frozen_transfer_tx).await.is_err());
+
+    let thaw_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::thaw_spl_token_account(
+            mint_env.freeze_authority.pubkey(),
+            mint_env.from_spl_token.pubkey(),
+            mint_env.minter.pubkey(),
+        )],
+        Some(&from.pubkey()),
+        &[&from, &mint_env.freeze_authority],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(thaw_tx).await.unwrap();
+
+    let from_spl_token_acc: Account =
+        ctx.banks_client.get_packed_account_data(mint_env.from_spl_token.pubkey()).await.unwrap();
+    assert!(!from_spl_token_acc.is_frozen());
+}
+
+#[tokio::test]
+async fn close_spl_token_account() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let blockhash = ctx.last_blockhash;
+
+    let close = |authority: &Keypair, spl_token_account: &Keypair| {
+        let close_instr = TokenInstruction::close_spl_token_account(
+            authority.pubkey(),
+            spl_token_account.pubkey(),
+            to.pubkey(),
+        );
+        Transaction::new_signed_with_payer(
+            &[close_instr],
+            Some(&from.pubkey()),
+            &[&from, authority],
+            blockhash,
+        )
+    };
+    let not_owner_tx = close(&to, &mint_env.to_spl_token);
+    assert!(ctx.banks_client.process_transaction(not_owner_tx).await.is_err());
+    let not_empty_tx = close(&from, &mint_env.from_spl_token);
+    assert!(ctx.banks_client.process_transaction(not_empty_tx).await.is_err());
+
+    let rent = ctx.banks_client.get_balance(mint_env.to_spl_token.pubkey()).await.unwrap();
+    let to_lamports = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+    let close_tx = close(&from, &mint_env.to_spl_token);
+    ctx.banks_client.process_transaction(close_tx).await.unwrap();
+
+    assert!(ctx.banks_client.get_account(mint_env.to_spl_token.pubkey()).await.unwrap().is_none());
+    assert_eq!(ctx.banks_client.get_balance(to.pubkey()).await.unwrap(), to_lamports + rent);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_with_ata_creation() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let transfer_amount = 1_000;
+    let mut ctx = env.ctx;
+
+    let to_ata = get_associated_token_address(&to.pubkey(), &mint_env.minter.pubkey());
+    assert!(ctx.banks_client.get_account(to_ata).await.unwrap().is_none());
+
+    let transfer_instr = || TokenInstruction::transfer_spl_token_with_ata_creation(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        to.pubkey(),
+        mint_env.minter.pubkey(),
+        transfer_amount,
+    );
+    // Second transfer finds the account created by the first one
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[transfer_instr(), transfer_instr()],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let to_ata_acc: Account = ctx.banks_client.get_packed_account_data(to_ata).await.unwrap();
+    assert_eq!(to_ata_acc.owner, to.pubkey());
+    assert_eq!(to_ata_acc.amount, 2 * transfer_amount);
+}
+
+#[tokio::test]
+async fn transfer_spl_token_with_ata_creation_with_registry() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(
+                TokenInstruction::initialize_registry(from.pubkey(), RegistryMode::Allow),
+            ),
+            TokenInstruction::transfer_spl_token_with_ata_creation(
+                from.pubkey(),
+                mint_env.from_spl_token.pubkey(),
+                to.pubkey(),
+                mint_env.minter.pubkey(),
+                1_000,
+            ),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(transfer_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::Custom(TransferError::RecipientNotPermitted as u32))
+    );
+    let to_ata = get_associated_token_address(&to.pubkey(), &mint_env.minter.pubkey());
+    assert!(ctx.banks_client.get_account(to_ata).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn transfer_spl_token_with_ata_creation_of_other_mint() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let other_mint = Keypair::new();
+    let (mint_authority, freeze_authority) = (&mint_env.mint_authority, &mint_env.freeze_authority);
+    MintEnv::initialize_mint(&mut env, &other_mint, mint_authority, freeze_authority, mint_env.decimals).await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_spl_token_with_ata_creation(
+            from.pubkey(),
+            mint_env.from_spl_token.pubkey(),
+            to.pubkey(),
+            other_mint.pubkey(),
+            1_000,
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(transfer_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::MintMismatch as u32))
+    );
+    let other_ata = get_associated_token_address(&to.pubkey(), &other_mint.pubkey());
+    assert!(ctx.banks_client.get_account(other_ata).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn post_order_for_soulbound_mint() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let buy_mint = Keypair::new();
+    MintEnv::initialize_mint(&mut env, &buy_mint, &mint_env.mint_authority, &mint_env.freeze_authority, 0).await;
+    let from = env.from;
+    let sell_mint = mint_env.minter.pubkey();
+    let mut ctx = env.ctx;
+
+    let (order, _) = Order::find_address(&from.pubkey(), 1);
+    let setup_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_features(
+                from.pubkey(),
+                Config::DEFAULT_FEATURES | Config::FEATURE_ORDERS | Config::FEATURE_MINT_CONFIGS,
+            )),
+            system_instruction::transfer(&from.pubkey(), &mint_env.mint_authority.pubkey(), 10_000_000),
+            TokenInstruction::set_mint_config(mint_env.mint_authority.pubkey(), buy_mint.pubkey(), true),
+            create_associated_token_account(&from.pubkey(), &order, &sell_mint),
+        ],
+        Some(&from.pubkey()),
+        &[&from, &mint_env.mint_authority],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(setup_tx).await.unwrap();
+
+    let post_order_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::post_order(
+            from.pubkey(),
+            mint_env.from_spl_token.pubkey(),
+            sell_mint,
+            buy_mint.pubkey(),
+            1,
+            1_000,
+            10,
+            FillMode::Partial,
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(post_order_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::SoulboundMint as u32))
+    );
+    assert!(ctx.banks_client.get_account(order).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn wrap_and_unwrap_sol() {
+    let env = Env::new().await;
+    let (payer, owner) = (env.from, env.to);
+    let wrap_amount = 1_000_000;
+    let mut ctx = env.ctx;
+
+    let wsol_token = get_associated_token_address(&owner.pubkey(), &spl_token::native_mint::id());
+    let owner_balance = ctx.banks_client.get_balance(owner.pubkey()).await.unwrap();
+    let wrap_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::wrap_sol(owner.pubkey(), wrap_amount)],
+        Some(&payer.pubkey()),
+        &[&payer, &owner],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(wrap_tx).await.unwrap();
+
+    let wsol_token_acc: Account = ctx.banks_client.get_packed_account_data(wsol_token).await.unwrap();
+    assert_eq!(wsol_token_acc.owner, owner.pubkey());
+    assert_eq!(wsol_token_acc.amount, wrap_amount);
+
+    let unwrap_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::unwrap_sol(owner.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer, &owner],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(unwrap_tx).await.unwrap();
+
+    assert!(ctx.banks_client.get_account(wsol_token).await.unwrap().is_none());
+    assert_eq!(ctx.banks_client.get_balance(owner.pubkey()).await.unwrap(), owner_balance);
+}
+
+#[tokio::test]
+async fn check_deadline() {
+    let env = Env::new().await;
+    let from = env.from;
+    let mut ctx = env.ctx;
+    ctx.warp_to_slot(100).unwrap();
+
+    let guarded_tx = |valid_until_slot| Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::check_deadline(valid_until_slot),
+            TokenInstruction::transfer_lamports(from.pubkey(), Pubkey::new_unique(), 1_000_000),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let (late_tx, in_time_tx) = (guarded_tx(99), guarded_tx(200));
+
+    let err = ctx.banks_client.process_transaction(late_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::DeadlineExceeded as u32))
+    );
+    ctx.banks_client.process_transaction(in_time_tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn self_test() {
+    let env = Env::new().await;
+    let caller = env.from;
+    let mut ctx = env.ctx;
+
+    let caller_balance = ctx.banks_client.get_balance(caller.pubkey()).await.unwrap();
+    let self_test_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::self_test(caller.pubkey())],
+        Some(&caller.pubkey()),
+        &[&caller],
+        ctx.last_blockhash,
+    );
+    let self_test_tx_fee = ctx.banks_client.get_fee_for_message(self_test_tx.message().clone()).await.unwrap().unwrap();
+    ctx.banks_client.process_transaction(self_test_tx).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(caller.pubkey()).await.unwrap(), caller_balance - self_test_tx_fee);
+    assert_eq!(ctx.banks_client.get_balance(Canary::find_address().0).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn vault_deposit_and_withdraw() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, owner) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let (deposit_amount, withdraw_amount) = (MintEnv::MINT_AMOUNT, MintEnv::MINT_AMOUNT / 4);
+    let (vault, _) = Vault::find_address(&owner.pubkey(), &mint);
+    let mut ctx = env.ctx;
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        from.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_VAULTS,
+    ));
+    let features_tx = Transaction::new_signed_with_payer(
+        &[features_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(features_tx).await.unwrap();
+
+    let create_vault_token_instr = create_associated_token_account(&owner.pubkey(), &vault, &mint);
+    let initialize_instr = TokenInstruction::initialize_vault(owner.pubkey(), mint);
+    let initialize_tx = Transaction::new_signed_with_payer(
+        &[create_vault_token_instr, initialize_instr],
+        Some(&owner.pubkey()),
+        &[&owner],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(initialize_tx).await.unwrap();
+
+    let deposit_instr = TokenInstruction::deposit_to_vault(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        owner.pubkey(),
+        mint,
+        deposit_amount,
+    );
+    let deposit_tx = Transaction::new_signed_with_payer(
+        &[deposit_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(deposit_tx).await.unwrap();
+
+    let withdraw = |owner: &Keypair| TokenInstruction::withdraw_from_vault(
+        owner.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        mint,
+        withdraw_amount,
+    );
+    let foreign_withdraw_tx = Transaction::new_signed_with_payer(
+        &[withdraw(&from)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(foreign_withdraw_tx).await.is_err());
+
+    let withdraw_tx = Transaction::new_signed_with_payer(
+        &[withdraw(&owner)],
+        Some(&owner.pubkey()),
+        &[&owner],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(withdraw_tx).await.unwrap();
+
+    let vault_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(get_associated_token_address(&vault, &mint))
+        .await
+        .unwrap();
+    assert_eq!(vault_token_acc.amount, deposit_amount - withdraw_amount);
+    let to_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_spl_token_acc.amount, withdraw_amount);
+}
+
+#[tokio::test]
+async fn sweep_deposit_to_omnibus() {
+    let env = Env::new().await;
+    let (authority, omnibus, deposit_amount) = (env.from, Pubkey::new_unique(), 1_000_000);
+    let (scheme, _) = DepositScheme::find_address(&authority.pubkey());
+    let (deposit, _) = DepositScheme::find_deposit_address(&scheme, 42);
+    let mut ctx = env.ctx;
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        authority.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_VAULTS,
+    ));
+    let initialize_instr = TokenInstruction::initialize_deposit_scheme(authority.pubkey(), omnibus);
+    let deposit_instr = system_instruction::transfer(&authority.pubkey(), &deposit, deposit_amount);
+    let deposit_tx = Transaction::new_signed_with_payer(
+        &[features_instr, initialize_instr, deposit_instr],
+        Some(&authority.pubkey()),
+        &[&authority],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(deposit_tx).await.unwrap();
+
+    let sweep_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::sweep_deposit(scheme, omnibus, 42)],
+        Some(&authority.pubkey()),
+        &[&authority],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(sweep_tx).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(deposit).await.unwrap(), 0);
+    assert_eq!(ctx.banks_client.get_balance(omnibus).await.unwrap(), deposit_amount);
+}
+
+#[tokio::test]
+async fn export_vault_to_successor() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, owner) = (env.from, env.to);
+    let (mint, successor) = (mint_env.minter.pubkey(), Pubkey::new_unique());
+    let (vault, _) = Vault::find_address(&owner.pubkey(), &mint);
+    let (successor_vault, _) = Vault::find_address_in(&successor, &owner.pubkey(), &mint);
+    let mut ctx = env.ctx;
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        from.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_VAULTS,
+    ));
+    let deposit_instr = TokenInstruction::deposit_to_vault(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        owner.pubkey(),
+        mint,
+        MintEnv::MINT_AMOUNT,
+    );
+    let vault_tx = Transaction::new_signed_with_payer(
+        &[
+            features_instr,
+            create_associated_token_account(&from.pubkey(), &vault, &mint),
+            TokenInstruction::initialize_vault(owner.pubkey(), mint),
+            deposit_instr,
+        ],
+        Some(&from.pubkey()),
+        &[&from, &owner],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(vault_tx).await.unwrap();
+
+    let export_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::begin_export(from.pubkey(), successor),
+            create_associated_token_account(&from.pubkey(), &successor_vault, &mint),
+            TokenInstruction::export_vault(from.pubkey(), owner.pubkey(), mint, successor),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(export_tx).await.unwrap();
+
+    let successor_vault_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(get_associated_token_address(&successor_vault, &mint))
+        .await
+        .unwrap();
+    assert_eq!(successor_vault_token_acc.amount, MintEnv::MINT_AMOUNT);
+    let vault_data = ctx.banks_client.get_account(vault).await.unwrap().unwrap().data;
+    assert_eq!(vault_data[0], AccountType::Exported as u8);
+    let (migration_key, _) = Migration::find_export_address();
+    let migration = account_state::<Migration>(&mut ctx.banks_client, migration_key).await;
+    assert_eq!((migration.peer, migration.count), (successor, 1));
+
+    let to_spl_token = mint_env.to_spl_token.pubkey();
+    let withdraw_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::withdraw_from_vault(owner.pubkey(), to_spl_token, mint, 1)],
+        Some(&owner.pubkey()),
+        &[&owner],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(withdraw_tx).await.is_err());
+}
+
+#[tokio::test]
+async fn exchange_escrow() {
+    let mut escrow_env = EscrowEnv::new().await;
+    let exchange_instr = escrow_env.exchange(MintEnv::MINT_AMOUNT);
+    let ctx = &mut escrow_env.ctx;
+
+    let exchange_tx = Transaction::new_signed_with_payer(
+        &[exchange_instr],
+        Some(&escrow_env.taker.pubkey()),
+        &[&escrow_env.taker],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(exchange_tx).await.unwrap();
+
+    let taker_receive_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(escrow_env.taker_receive_token)
+        .await
+        .unwrap();
+    assert_eq!(taker_receive_token_acc.amount, MintEnv::MINT_AMOUNT);
+    let receive_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(escrow_env.buy.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(receive_token_acc.amount, EscrowEnv::EXPECTED_AMOUNT);
+    assert!(ctx.banks_client.get_account(escrow_env.escrow).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(escrow_env.sell.from_spl_token.pubkey()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn exchange_escrow_wrong_amount() {
+    let mut escrow_env = EscrowEnv::new().await;
+    let exchange_instr = escrow_env.exchange(MintEnv::MINT_AMOUNT + 1);
+    let ctx = &mut escrow_env.ctx;
+
+    let exchange_tx = Transaction::new_signed_with_payer(
+        &[exchange_instr],
+        Some(&escrow_env.taker.pubkey()),
+        &[&escrow_env.taker],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(exchange_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::EscrowAmountMismatch as u32))
+    );
+}
+
+#[tokio::test]
+async fn exchange_escrow_sandwich_guard() {
+    let mut escrow_env = EscrowEnv::new().await;
+    let exchange_instr = TokenInstruction::with_sandwich_guard(escrow_env.exchange(MintEnv::MINT_AMOUNT));
+    let ctx = &mut escrow_env.ctx;
+    let approve_instr = spl_token::instruction::approve(
+        &spl_token::id(),
+        &escrow_env.taker_send_token,
+        &Pubkey::new_unique(),
+        &escrow_env.taker.pubkey(),
+        &[],
+        EscrowEnv::EXPECTED_AMOUNT,
+    ).unwrap();
+
+    let sandwiched_tx = Transaction::new_signed_with_payer(
+        &[approve_instr, exchange_instr.clone()],
+        Some(&escrow_env.taker.pubkey()),
+        &[&escrow_env.taker],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(sandwiched_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::Custom(TransferError::UnexpectedInstruction as u32))
+    );
+
+    let exchange_tx = Transaction::new_signed_with_payer(
+        &[exchange_instr],
+        Some(&escrow_env.taker.pubkey()),
+        &[&escrow_env.taker],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(exchange_tx).await.unwrap();
+    assert!(ctx.banks_client.get_account(escrow_env.escrow).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn cancel_escrow_after_timeout() {
+    let EscrowEnv { mut ctx, initializer, sell, escrow, .. } = EscrowEnv::new().await;
+
+    let cancel = || TokenInstruction::cancel_escrow(
+        initializer.pubkey(),
+        sell.to_spl_token.pubkey(),
+        sell.from_spl_token.pubkey(),
+    );
+    let early_cancel_tx = Transaction::new_signed_with_payer(
+        &[cancel()],
+        Some(&initializer.pubkey()),
+        &[&initializer],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(early_cancel_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::EscrowNotExpired as u32))
+    );
+
+    warp(&mut ctx, 500).await;
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let cancel_tx = Transaction::new_signed_with_payer(
+        &[cancel()],
+        Some(&initializer.pubkey()),
+        &[&initializer],
+        blockhash,
+    );
+    ctx.banks_client.process_transaction(cancel_tx).await.unwrap();
+
+    let refund_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(sell.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(refund_token_acc.amount, MintEnv::MINT_AMOUNT);
+    assert!(ctx.banks_client.get_account(escrow).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn initialize_escrow_for_soulbound_mint() {
+    let mut env = Env::new().await;
+    let sell = MintEnv::new(&mut env).await;
+    let buy_mint = Keypair::new();
+    MintEnv::initialize_mint(&mut env, &buy_mint, &sell.mint_authority, &sell.freeze_authority, 0).await;
+    let initializer = env.from;
+    let receive_token = get_associated_token_address(&initializer.pubkey(), &buy_mint.pubkey());
+    let mut ctx = env.ctx;
+
+    let clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let initialize_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_features(
+                initializer.pubkey(),
+                Config::DEFAULT_FEATURES | Config::FEATURE_ESCROWS | Config::FEATURE_MINT_CONFIGS,
+            )),
+            system_instruction::transfer(&initializer.pubkey(), &sell.mint_authority.pubkey(), 10_000_000),
+            TokenInstruction::set_mint_config(sell.mint_authority.pubkey(), buy_mint.pubkey(), true),
+            create_associated_token_account(&initializer.pubkey(), &initializer.pubkey(), &buy_mint.pubkey()),
+            TokenInstruction::initialize_escrow(
+                initializer.pubkey(),
+                sell.from_spl_token.pubkey(),
+                receive_token,
+                sell.minter.pubkey(),
+                buy_mint.pubkey(),
+                EscrowEnv::EXPECTED_AMOUNT,
+                clock.unix_timestamp + EscrowEnv::TIMEOUT,
+            ),
+        ],
+        Some(&initializer.pubkey()),
+        &[&initializer, &sell.mint_authority],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(initialize_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(4, InstructionError::Custom(TransferError::SoulboundMint as u32))
+    );
+    let (escrow, _) = Escrow::find_address(&sell.from_spl_token.pubkey());
+    assert!(ctx.banks_client.get_account(escrow).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn refund_bond() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let arbiter = Keypair::new();
+    let bond_id = 1;
+    let bond_amount = 1_000_000;
+    let (bond, _) = Bond::find_address(&from.pubkey(), bond_id);
+
+    let post_bond_instr = TokenInstruction::post_bond(
+        from.pubkey(),
+        arbiter.pubkey(),
+        to.pubkey(),
+        bond_id,
+        bond_amount,
+    );
+    let post_bond_tx = Transaction::new_signed_with_payer(
+        &[post_bond_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(post_bond_tx).await.unwrap();
+
+    let bond_state: Bond = account_state(&mut ctx.banks_client, bond).await;
+    assert_eq!(bond_state.depositor, from.pubkey());
+    assert_eq!(bond_state.arbiter, arbiter.pubkey());
+    assert_eq!(bond_state.beneficiary, to.pubkey());
+    assert_eq!(bond_state.amount, bond_amount);
+
+    let from_balance_before_refund = ctx
+        .banks_client
+        .get_balance(from.pubkey())
+        .await
+        .unwrap();
+    let bond_balance = ctx
+        .banks_client
+        .get_balance(bond)
+        .await
+        .unwrap();
+
+    let refund_bond_instr = TokenInstruction::refund_bond(arbiter.pubkey(), from.pubkey(), bond_id, 1);
+    let refund_bond_tx = Transaction::new_signed_with_payer(
+        &[refund_bond_instr],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &arbiter],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(refund_bond_tx).await.unwrap();
+
+    let from_balance_after_refund = ctx
+        .banks_client
+        .get_balance(from.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(from_balance_after_refund, from_balance_before_refund + bond_balance);
+    assert!(ctx.banks_client.get_account(bond).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn slash_bond() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let arbiter = Keypair::new();
+    let bond_id = 2;
+    let bond_amount = 1_000_000;
+    let slash_amount = 400_000;
+    let (bond, _) = Bond::find_address(&from.pubkey(), bond_id);
+
+    let post_bond_instr = TokenInstruction::post_bond(
+        from.pubkey(),
+        arbiter.pubkey(),
+        to.pubkey(),
+        bond_id,
+        bond_amount,
+    );
+    let post_bond_tx = Transaction::new_signed_with_payer(
+        &[post_bond_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(post_bond_tx).await.unwrap();
+
+    let to_balance_before_slash = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+
+    let slash_bond_instr = TokenInstruction::slash_bond(
+        arbiter.pubkey(),
+        to.pubkey(),
+        from.pubkey(),
+        bond_id,
+        slash_amount,
+        7,
+    );
+    let slash_bond_tx = Transaction::new_signed_with_payer(
+        &[slash_bond_instr],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &arbiter],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(slash_bond_tx).await.unwrap();
+
+    let to_balance_after_slash = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_balance_after_slash - to_balance_before_slash, slash_amount);
+
+    let bond_state: Bond = account_state(&mut ctx.banks_client, bond).await;
+    assert_eq!(bond_state.amount, bond_amount - slash_amount);
+}
+#[tokio::test]
+async fn tip() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let first_tip = 300_000;
+    let second_tip = 200_000;
+
+    let to_balance_before_tip = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+
+    let first_tip_instr = TokenInstruction::tip(from.pubkey(), to.pubkey(), first_tip);
+    let second_tip_instr = TokenInstruction::tip(from.pubkey(), to.pubkey(), second_tip);
+    let tip_tx = Transaction::new_signed_with_payer(
+        &[first_tip_instr, second_tip_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(tip_tx).await.unwrap();
+
+    let to_balance_after_tip = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_balance_after_tip - to_balance_before_tip, first_tip + second_tip);
+
+    let (tip_stats, _) = TipStats::find_address(&to.pubkey());
+    let tip_stats: TipStats = account_state(&mut ctx.banks_client, tip_stats).await;
+    assert_eq!(tip_stats.total, first_tip + second_tip);
+    assert_eq!(tip_stats.count, 2);
+    assert_eq!(tip_stats.top_tipper, from.pubkey());
+    assert_eq!(tip_stats.top_tipper_total, first_tip + second_tip);
+
+    let (tipper_stats, _) = TipperStats::find_address(&to.pubkey(), &from.pubkey());
+    let tipper_stats: TipperStats = account_state(&mut ctx.banks_client, tipper_stats).await;
+    assert_eq!(tipper_stats.total, first_tip + second_tip);
+    assert_eq!(tipper_stats.count, 2);
+}
+#[tokio::test]
+async fn donate_with_matching_pool() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let sponsor = Keypair::new();
+    let pool_amount = 500_000;
+    let cap = 400_000;
+    let donation = 300_000;
+
+    let fund_sponsor_instr = system_instruction::transfer(&ctx.payer.pubkey(), &sponsor.pubkey(), Env::DEPOSIT_AMOUNT);
+    let create_pool_instr = TokenInstruction::create_matching_pool(
+        sponsor.pubkey(),
+        to.pubkey(),
+        20_000,
+        cap,
+        pool_amount,
+    );
+    let create_pool_tx = Transaction::new_signed_with_payer(
+        &[fund_sponsor_instr, create_pool_instr],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &sponsor],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_pool_tx).await.unwrap();
+
+    let self_donate_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::donate(to.pubkey(), to.pubkey(), sponsor.pubkey(), donation)],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &to],
+        ctx.last_blockhash,
+    );
+    let self_donate_err = ctx.banks_client.process_transaction(self_donate_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        self_donate_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::SelfDonation as u32)),
+    );
+
+    let to_balance_before_donation = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+
+    let first_donate_instr = TokenInstruction::donate(from.pubkey(), to.pubkey(), sponsor.pubkey(), donation);
+    let second_donate_instr = TokenInstruction::donate(from.pubkey(), to.pubkey(), sponsor.pubkey(), donation + 1);
+    let donate_tx = Transaction::new_signed_with_payer(
+        &[first_donate_instr, second_donate_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(donate_tx).await.unwrap();
+
+    let to_balance_after_donation = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(
+        to_balance_after_donation - to_balance_before_donation,
+        donation + donation + 1 + pool_amount
+    );
+
+    let (pool, _) = MatchingPool::find_address(&sponsor.pubkey(), &to.pubkey());
+    let pool: MatchingPool = account_state(&mut ctx.banks_client, pool).await;
+    assert_eq!(pool.remaining, 0);
+    assert_eq!(pool.matched_total, pool_amount);
+}
+#[tokio::test]
+async fn transfer_lamports_round_up() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let granularity = 1_000_000;
+    let transfer_amount = 2_300_000;
+    let withdraw_amount = 500_000;
+    let (savings_vault, _) = SavingsVault::find_address(&from.pubkey());
+
+    let configure_instr = TokenInstruction::configure_round_up(from.pubkey(), granularity);
+    let transfer_instr = TokenInstruction::transfer_lamports_round_up(from.pubkey(), to.pubkey(), transfer_amount);
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[configure_instr, transfer_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let savings_vault_state: SavingsVault = account_state(&mut ctx.banks_client, savings_vault).await;
+    assert_eq!(savings_vault_state.granularity, granularity);
+    assert_eq!(savings_vault_state.saved, 700_000);
+
+    let withdraw_instr = TokenInstruction::withdraw_savings(from.pubkey(), withdraw_amount);
+    let withdraw_tx = Transaction::new_signed_with_payer(
+        &[withdraw_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(withdraw_tx).await.unwrap();
+
+    let savings_vault_state: SavingsVault = account_state(&mut ctx.banks_client, savings_vault).await;
+    assert_eq!(savings_vault_state.saved, 700_000 - withdraw_amount);
+}
+#[tokio::test]
+async fn settle_netting() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let netting_id = 1;
+    let clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let period_end = clock.unix_timestamp + 60;
+    let (netting, _) = Netting::find_address(&from.pubkey(), &to.pubkey(), netting_id);
+
+    let open_instr = TokenInstruction::open_netting(from.pubkey(), to.pubkey(), netting_id, period_end);
+    let from_obligation_instr = TokenInstruction::register_obligation(from.pubkey(), to.pubkey(), netting, 900_000);
+    let to_obligation_instr = TokenInstruction::register_obligation(to.pubkey(), from.pubkey(), netting, 400_000);
+    let register_tx = Transaction::new_signed_with_payer(
+        &[open_instr, from_obligation_instr, to_obligation_instr],
+        Some(&from.pubkey()),
+        &[&from, &to],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(register_tx).await.unwrap();
+
+    let netting_state: Netting = account_state(&mut ctx.banks_client, netting).await;
+    assert_eq!(netting_state.a_owes_b, 900_000);
+    assert_eq!(netting_state.b_owes_a, 400_000);
+    assert_eq!(netting_state.obligation_count, 2);
+
+    warp(&mut ctx, 500).await;
+    let to_balance_before_settle = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+
+    let settle_instr = TokenInstruction::settle_netting(from.pubkey(), to.pubkey(), netting);
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let settle_tx = Transaction::new_signed_with_payer(
+        &[settle_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        blockhash,
+    );
+    ctx.banks_client.process_transaction(settle_tx).await.unwrap();
+
+    let to_balance_after_settle = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_balance_after_settle - to_balance_before_settle, 500_000);
+    assert!(ctx.banks_client.get_account(netting).await.unwrap().is_none());
+}
+#[tokio::test]
+async fn deposit_to_program() {
+    let (receiver_program, short_receiver_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut program_test = ProgramTest::new("token", id(), processor!(process_instruction));
+    program_test.add_program(
+        "deposit_receiver",
+        receiver_program,
+        processor!(deposit_receiver_process_instruction),
+    );
+    program_test.add_program(
+        "short_deposit_receiver",
+        short_receiver_program,
+        processor!(short_deposit_receiver_process_instruction),
+    );
+    let env = Env::with_program_test(program_test).await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let deposit_amount = 750_000;
+    let deposit_tx = |receiver_program: Pubkey, blockhash: Hash| Transaction::new_signed_with_payer(
+        &[TokenInstruction::deposit_to_program(
+            receiver_program,
+            from.pubkey(),
+            from.pubkey(),
+            to.pubkey(),
+            system_program::id(),
+            vec![],
+            deposit_amount,
+        )],
+        Some(&from.pubkey()),
+        &[&from],
+        blockhash,
+    );
+
+    let not_allowed_err = ctx.banks_client.process_transaction(deposit_tx(receiver_program, ctx.last_blockhash)).await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        not_allowed_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::DepositProgramNotAllowed as u32)),
+    );
+
+    let allow_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_deposit_program(
+                from.pubkey(),
+                receiver_program,
+                true,
+            )),
+            TokenInstruction::set_deposit_program(from.pubkey(), short_receiver_program, true),
+        ],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(allow_tx).await.unwrap();
+
+    let to_balance_before_deposit = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+
+    // Same transaction as the rejected one needs a new blockhash
+    let blockhash = ctx.get_new_latest_blockhash().await.unwrap();
+    ctx.banks_client.process_transaction(deposit_tx(receiver_program, blockhash)).await.unwrap();
+
+    let to_balance_after_deposit = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_balance_after_deposit - to_balance_before_deposit, deposit_amount);
+
+    let short_err = ctx.banks_client.process_transaction(deposit_tx(short_receiver_program, blockhash)).await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        short_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::DepositNotReceived as u32)),
+    );
+}
+
+/// Minimal `deposit_interface` implementation receiving lamports
+fn deposit_receiver_process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let deposit = ReceiveDeposit::unpack(input)?;
+    let acc_iter = &mut accounts.iter();
+    let depositor_acc = next_account_info(acc_iter)?;
+    let _source_acc = next_account_info(acc_iter)?;
+    let destination_acc = next_account_info(acc_iter)?;
+    let system_program_acc = next_account_info(acc_iter)?;
+
+    invoke(
+        &system_instruction::transfer(depositor_acc.key, destination_acc.key, deposit.amount),
+        &[depositor_acc.clone(), destination_acc.clone(), system_program_acc.clone()],
+    )
+}
+
+/// `deposit_interface` implementation crediting a lamport less than deposited
+fn short_deposit_receiver_process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let deposit = ReceiveDeposit::unpack(input)?;
+    let short_deposit = ReceiveDeposit { amount: deposit.amount - 1 };
+    deposit_receiver_process_instruction(program_id, accounts, &short_deposit.pack())
+}
+
+const HOOK_TRANSFER_LIMIT: u64 = 1_000_000;
+
+#[tokio::test]
+async fn transfer_lamports_with_hook() {
+    let hook_program = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("token", id(), processor!(process_instruction));
+    program_test.add_program(
+        "transfer_hook",
+        hook_program,
+        processor!(transfer_hook_process_instruction),
+    );
+    let env = Env::with_program_test(program_test).await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+
+    let set_hook_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::set_transfer_hook(from.pubkey(), Some(hook_program))],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(set_hook_tx).await.unwrap();
+
+    let to_balance_before_transfer = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+
+    let unhooked_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), HOOK_TRANSFER_LIMIT)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let unhooked_err = ctx.banks_client.process_transaction(unhooked_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        unhooked_err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::TransferHookRequired as u32)),
+    );
+
+    let allowed_instr = TokenInstruction::with_transfer_hook(
+        TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), HOOK_TRANSFER_LIMIT),
+        hook_program,
+    );
+    let allowed_tx = Transaction::new_signed_with_payer(
+        &[allowed_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(allowed_tx).await.unwrap();
+
+    let vetoed_instr = TokenInstruction::with_transfer_hook(
+        TokenInstruction::transfer_lamports_v2(from.pubkey(), to.pubkey(), HOOK_TRANSFER_LIMIT + 1),
+        hook_program,
+    );
+    let vetoed_tx = Transaction::new_signed_with_payer(
+        &[vetoed_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(vetoed_tx).await.is_err());
+
+    let vetoed_batch_instr = TokenInstruction::with_transfer_hook(
+        TokenInstruction::transfer_lamports_batch(
+            from.pubkey(),
+            &[(to.pubkey(), 1), (to.pubkey(), HOOK_TRANSFER_LIMIT + 1)],
+        ),
+        hook_program,
+    );
+    let vetoed_batch_tx = Transaction::new_signed_with_payer(
+        &[vetoed_batch_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    assert!(ctx.banks_client.process_transaction(vetoed_batch_tx).await.is_err());
+
+    let to_balance_after_transfer = ctx
+        .banks_client
+        .get_balance(to.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_balance_after_transfer - to_balance_before_transfer, HOOK_TRANSFER_LIMIT);
+}
+
+#[tokio::test]
+async fn unsupported_instruction() {
+    let env = Env::new().await;
+    let from = env.from;
+    let mut ctx = env.ctx;
+
+    let unsupported_instr = Instruction::new_with_bytes(
+        id(),
+        &[TokenInstruction::TAG_COUNT, 0, 0, 0, 0, 0, 0, 0, 0],
+        vec![AccountMeta::new(from.pubkey(), true)],
+    );
+    let unsupported_tx = Transaction::new_signed_with_payer(
+        &[unsupported_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(unsupported_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::UnsupportedInstruction as u32))
+    );
+}
+
+#[tokio::test]
+async fn versioned_instruction_with_appended_field() {
+    let env = Env::new().await;
+    let (from, to) = (env.from, env.to);
+    let mut ctx = env.ctx;
+    let transfer_amount = 1_111_111;
+    let to_balance_before_transfer = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+
+    let mut transfer_instr = TokenInstruction::transfer_lamports(from.pubkey(), to.pubkey(), transfer_amount);
+    let mut data = TokenInstruction::TransferLamports { amount: transfer_amount }.pack_versioned();
+    data.extend_from_slice(&[1, 2, 3]);
+    transfer_instr.data = data;
+    let transfer_tx = Transaction::new_signed_with_payer(
+        &[transfer_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+
+    let to_balance_after_transfer = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+    assert_eq!(to_balance_after_transfer - to_balance_before_transfer, transfer_amount);
+}
+
+#[tokio::test]
+async fn execute_payout() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let batch_id = 1;
+    let leaves = [
+        merkle::payout_leaf(0, &to.pubkey(), 1_000_000),
+        merkle::payout_leaf(1, &Pubkey::new_unique(), 2_000_000),
+    ];
+    let (payout_batch, _) = PayoutBatch::find_address(&from.pubkey(), batch_id);
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        from.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_PAYOUT_COMMITMENTS,
+    ));
+    let publish_instr = TokenInstruction::publish_payout_root(from.pubkey(), batch_id, merkle::root(&leaves), 2);
+    let execute_instr = TokenInstruction::execute_payout(
+        from.pubkey(),
+        to.pubkey(),
+        batch_id,
+        0,
+        1_000_000,
+        merkle::proof(&leaves, 0),
+    );
+    let payout_tx = Transaction::new_signed_with_payer(
+        &[features_instr, publish_instr, execute_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(payout_tx).await.unwrap();
+
+    let to_balance = ctx.banks_client.get_balance(to.pubkey()).await.unwrap();
+    assert_eq!(to_balance, Env::DEPOSIT_AMOUNT + 1_000_000);
+    let payout_batch_state: PayoutBatch = account_state(&mut ctx.banks_client, payout_batch).await;
+    assert_eq!(payout_batch_state.executed_count, 1);
+    assert!(payout_batch_state.is_executed(0));
+
+    let forged_instr = TokenInstruction::execute_payout(
+        from.pubkey(),
+        to.pubkey(),
+        batch_id,
+        0,
+        2_000_000,
+        merkle::proof(&leaves, 0),
+    );
+    let forged_tx = Transaction::new_signed_with_payer(
+        &[forged_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(forged_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::InvalidMerkleProof as u32))
+    );
+}
+
+#[tokio::test]
+async fn claim_distribution() {
+    let env = Env::new().await;
+    let from = env.from;
+    let to = env.to;
+    let mut ctx = env.ctx;
+    let attestor = Keypair::new();
+    let distributor_id = 1;
+    let (distributor, _) = Distributor::find_address(&from.pubkey(), distributor_id);
+    let message = eligibility::message(&distributor, &to.pubkey(), 1_000_000);
+    let signature: [u8; 64] = attestor.sign_message(&message).into();
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        from.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_GATED_CLAIMS,
+    ));
+    let create_instr = TokenInstruction::create_distributor(
+        from.pubkey(),
+        distributor_id,
+        attestor.pubkey(),
+        5_000_000,
+    );
+    let create_tx = Transaction::new_signed_with_payer(
+        &[features_instr, create_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_tx).await.unwrap();
+
+    let claim_tx = Transaction::new_signed_with_payer(
+        &[
+            eligibility::ed25519_instruction(&attestor.pubkey(), &signature, &message),
+            TokenInstruction::claim_distribution(to.pubkey(), distributor, 1_000_000),
+        ],
+        Some(&to.pubkey()),
+        &[&to],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(claim_tx).await.unwrap();
+
+    let distributor_state: Distributor = account_state(&mut ctx.banks_client, distributor).await;
+    assert_eq!(distributor_state.claimed_total, 1_000_000);
+    assert_eq!(distributor_state.claim_count, 1);
+
+    let unsigned_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::claim_distribution(to.pubkey(), distributor, 1_000_000)],
+        Some(&to.pubkey()),
+        &[&to],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(unsigned_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::InvalidAttestation as u32))
+    );
+}
+
+#[tokio::test]
+async fn claim_merkle_distribution() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let mut ctx = env.ctx;
+    let leaves = [
+        merkle::payout_leaf(0, &to.pubkey(), 1_000),
+        merkle::payout_leaf(1, &Pubkey::new_unique(), 2_000),
+    ];
+    let root = merkle::root(&leaves);
+    let (distribution, _) = MerkleDistribution::find_address(&from.pubkey(), &root);
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        from.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_MERKLE_DISTRIBUTIONS,
+    ));
+    let create_instr = TokenInstruction::create_merkle_distribution(
+        from.pubkey(),
+        mint,
+        mint_env.from_spl_token.pubkey(),
+        root,
+        2,
+        3_000,
+    );
+    let create_tx = Transaction::new_signed_with_payer(
+        &[features_instr, create_associated_token_account(&from.pubkey(), &distribution, &mint), create_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_tx).await.unwrap();
+
+    let claim = || TokenInstruction::claim_merkle_distribution(
+        to.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        distribution,
+        mint,
+        0,
+        1_000,
+        merkle::proof(&leaves, 0),
+    );
+    let claim_tx = Transaction::new_signed_with_payer(&[claim()], Some(&to.pubkey()), &[&to], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(claim_tx).await.unwrap();
+
+    let distribution_state: MerkleDistribution = account_state(&mut ctx.banks_client, distribution).await;
+    assert_eq!((distribution_state.claimed_total, distribution_state.claimed_count), (1_000, 1));
+    let to_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_spl_token_acc.amount, 1_000);
+
+    let repeated_tx = Transaction::new_signed_with_payer(
+        &[claim()],
+        Some(&from.pubkey()),
+        &[&from, &to],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(repeated_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::LeafAlreadyClaimed as u32))
+    );
+}
+
+#[tokio::test]
+async fn create_merkle_distribution_of_soulbound_mint() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let mut ctx = env.ctx;
+    let root = merkle::root(&[merkle::payout_leaf(0, &to.pubkey(), 1_000)]);
+    let (distribution, _) = MerkleDistribution::find_address(&from.pubkey(), &root);
+
+    let create_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_features(
+                from.pubkey(),
+                Config::DEFAULT_FEATURES | Config::FEATURE_MERKLE_DISTRIBUTIONS | Config::FEATURE_MINT_CONFIGS,
+            )),
+            system_instruction::transfer(&from.pubkey(), &mint_env.mint_authority.pubkey(), 10_000_000),
+            TokenInstruction::set_mint_config(mint_env.mint_authority.pubkey(), mint, true),
+            create_associated_token_account(&from.pubkey(), &distribution, &mint),
+            TokenInstruction::create_merkle_distribution(
+                from.pubkey(),
+                mint,
+                mint_env.from_spl_token.pubkey(),
+                root,
+                1,
+                1_000,
+            ),
+        ],
+        Some(&from.pubkey()),
+        &[&from, &mint_env.mint_authority],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(create_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(4, InstructionError::Custom(TransferError::SoulboundMint as u32))
+    );
+    assert!(ctx.banks_client.get_account(distribution).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn dividend_claim_and_clawback() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let mut ctx = env.ctx;
+    let leaves = [
+        merkle::payout_leaf(0, &to.pubkey(), 1_000),
+        merkle::payout_leaf(1, &Pubkey::new_unique(), 2_000),
+    ];
+    let root = merkle::root(&leaves);
+    let (dividend, _) = Dividend::find_address(&from.pubkey(), &root);
+    let claim_deadline = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp + 60;
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        from.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_DIVIDENDS,
+    ));
+    let create_instr = TokenInstruction::create_dividend(
+        from.pubkey(),
+        mint,
+        mint,
+        mint_env.from_spl_token.pubkey(),
+        root,
+        2,
+        3_000,
+        600,
+        claim_deadline,
+    );
+    let create_tx = Transaction::new_signed_with_payer(
+        &[features_instr, create_associated_token_account(&from.pubkey(), &dividend, &mint), create_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_tx).await.unwrap();
+
+    let claim = || TokenInstruction::claim_dividend(
+        to.pubkey(),
+        mint_env.to_spl_token.pubkey(),
+        dividend,
+        mint,
+        0,
+        1_000,
+        merkle::proof(&leaves, 0),
+    );
+    let claim_tx = Transaction::new_signed_with_payer(&[claim()], Some(&to.pubkey()), &[&to], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(claim_tx).await.unwrap();
+
+    let dividend_state: Dividend = account_state(&mut ctx.banks_client, dividend).await;
+    assert_eq!((dividend_state.claimed_total, dividend_state.claimed_count), (200, 1));
+    let to_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(to_spl_token_acc.amount, 200);
+
+    let repeated_tx = Transaction::new_signed_with_payer(
+        &[claim()],
+        Some(&from.pubkey()),
+        &[&from, &to],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(repeated_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::LeafAlreadyClaimed as u32))
+    );
+
+    let clawback = || TokenInstruction::clawback_dividend(
+        from.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        dividend,
+        mint,
+    );
+    let early_clawback_tx = Transaction::new_signed_with_payer(
+        &[clawback()],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(early_clawback_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TransferError::DividendClaimPeriodNotEnded as u32),
+        )
+    );
+
+    warp(&mut ctx, 500).await;
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let clawback_tx = Transaction::new_signed_with_payer(&[clawback()], Some(&from.pubkey()), &[&from], blockhash);
+    ctx.banks_client.process_transaction(clawback_tx).await.unwrap();
+
+    let from_spl_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.from_spl_token.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(from_spl_token_acc.amount, MintEnv::MINT_AMOUNT - 200);
+    assert!(ctx.banks_client.get_account(dividend).await.unwrap().is_none());
+    let dividend_token = get_associated_token_address(&dividend, &mint);
+    assert!(ctx.banks_client.get_account(dividend_token).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn create_dividend_of_soulbound_mint() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, to) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let mut ctx = env.ctx;
+    let root = merkle::root(&[merkle::payout_leaf(0, &to.pubkey(), 1_000)]);
+    let (dividend, _) = Dividend::find_address(&from.pubkey(), &root);
+    let claim_deadline = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp + 60;
+
+    let create_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_features(
+                from.pubkey(),
+                Config::DEFAULT_FEATURES | Config::FEATURE_DIVIDENDS | Config::FEATURE_MINT_CONFIGS,
+            )),
+            system_instruction::transfer(&from.pubkey(), &mint_env.mint_authority.pubkey(), 10_000_000),
+            TokenInstruction::set_mint_config(mint_env.mint_authority.pubkey(), mint, true),
+            create_associated_token_account(&from.pubkey(), &dividend, &mint),
+            TokenInstruction::create_dividend(
+                from.pubkey(),
+                mint,
+                mint,
+                mint_env.from_spl_token.pubkey(),
+                root,
+                1,
+                1_000,
+                600,
+                claim_deadline,
+            ),
+        ],
+        Some(&from.pubkey()),
+        &[&from, &mint_env.mint_authority],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(create_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(4, InstructionError::Custom(TransferError::SoulboundMint as u32))
+    );
+    assert!(ctx.banks_client.get_account(dividend).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn issue_and_redeem_managed_mint() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, admin) = (env.from, &mint_env.mint_authority);
+    let mint = mint_env.minter.pubkey();
+    let (managed_mint, _) = ManagedMint::find_address(&mint);
+    let roles = Issuer::ROLE_ISSUE | Issuer::ROLE_REDEEM;
+    let mut ctx = env.ctx;
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        from.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_ISSUANCE,
+    ));
+    let initialize_tx = Transaction::new_signed_with_payer(
+        &[
+            features_instr,
+            system_instruction::transfer(&from.pubkey(), &admin.pubkey(), 1_000_000_000),
+            TokenInstruction::initialize_managed_mint(admin.pubkey(), mint, MintEnv::MINT_AMOUNT + 1_000),
+            TokenInstruction::set_issuer(admin.pubkey(), from.pubkey(), mint, roles, 1_500),
+        ],
+        Some(&from.pubkey()),
+        &[&from, admin],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(initialize_tx).await.unwrap();
+
+    let issue = |amount| TokenInstruction::issue(from.pubkey(), mint, mint_env.to_spl_token.pubkey(), amount);
+    let issue_tx = Transaction::new_signed_with_payer(
+        &[issue(1_000)],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(issue_tx).await.unwrap();
+
+    for (amount, error) in [(600, TransferError::IssuerAllowanceExceeded), (1, TransferError::SupplyCapExceeded)] {
+        let rejected_tx = Transaction::new_signed_with_payer(
+            &[issue(amount)],
+            Some(&from.pubkey()),
+            &[&from],
+            ctx.last_blockhash,
+        );
+        let err = ctx.banks_client.process_transaction(rejected_tx).await.unwrap_err().unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, InstructionError::Custom(error as u32)));
+    }
+
+    let redeem_instr = TokenInstruction::redeem(from.pubkey(), mint, mint_env.from_spl_token.pubkey(), 6_000);
+    let redeem_tx = Transaction::new_signed_with_payer(
+        &[redeem_instr],
+        Some(&from.pubkey()),
+        &[&from],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(redeem_tx).await.unwrap();
+
+    let mint_acc: Mint = ctx.banks_client.get_packed_account_data(mint).await.unwrap();
+    assert_eq!(mint_acc.supply, MintEnv::MINT_AMOUNT + 1_000 - 6_000);
+    assert_eq!(mint_acc.mint_authority, COption::Some(managed_mint));
+    let managed_mint_state: ManagedMint = account_state(&mut ctx.banks_client, managed_mint).await;
+    assert_eq!((managed_mint_state.issued, managed_mint_state.redeemed), (1_000, 6_000));
+}
+
+/// Minimal `hook_interface` implementation vetoing transfers above `HOOK_TRANSFER_LIMIT`
+fn transfer_hook_process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let hook_data = TransferHookData::unpack(input)?;
+    if hook_data.amount > HOOK_TRANSFER_LIMIT {
+        return Err(ProgramError::InvalidArgument)
+    }
+    Ok(())
+}
+
+/// Escrow of initializer offering whole sell mint balance for `EXPECTED_AMOUNT` of buy mint,
+/// taker holds the buy mint
+struct EscrowEnv {
+    ctx: ProgramTestContext,
+    initializer: Keypair,
+    taker: Keypair,
+    sell: MintEnv,
+    buy: MintEnv,
+    escrow: Pubkey,
+    taker_send_token: Pubkey,
+    taker_receive_token: Pubkey,
+}
+
+impl EscrowEnv {
+    const EXPECTED_AMOUNT: u64 = 10_000;
+    const TIMEOUT: i64 = 60;
 
-        let from_deposit_instr = system_instruction::transfer(
-            &ctx.payer.pubkey(),
-            &from.pubkey(),
-            Env::DEPOSIT_AMOUNT,
+    async fn new() -> EscrowEnv {
+        let mut env = Env::new().await;
+        let sell = MintEnv::new(&mut env).await;
+        let buy = MintEnv::new(&mut env).await;
+        let (initializer, taker) = (env.from, env.to);
+        let mut ctx = env.ctx;
+        let (sell_mint, buy_mint) = (sell.minter.pubkey(), buy.minter.pubkey());
+        let taker_send_token = get_associated_token_address(&taker.pubkey(), &buy_mint);
+        let taker_receive_token = get_associated_token_address(&taker.pubkey(), &sell_mint);
+        let (escrow, _) = Escrow::find_address(&sell.from_spl_token.pubkey());
+
+        let taker_tokens_tx = Transaction::new_signed_with_payer(
+            &[
+                create_associated_token_account(&taker.pubkey(), &taker.pubkey(), &buy_mint),
+                create_associated_token_account(&taker.pubkey(), &taker.pubkey(), &sell_mint),
+                spl_token::instruction::transfer(
+                    &spl_token::id(),
+                    &buy.from_spl_token.pubkey(),
+                    &taker_send_token,
+                    &initializer.pubkey(),
+                    &[],
+                    EscrowEnv::EXPECTED_AMOUNT,
+                ).unwrap(),
+            ],
+            Some(&taker.pubkey()),
+            &[&taker, &initializer],
+            ctx.last_blockhash,
         );
-        let to_deposit_instr = system_instruction::transfer(
-            &ctx.payer.pubkey(),
-            &to.pubkey(),
-            Env::DEPOSIT_AMOUNT,
+        ctx.banks_client.process_transaction(taker_tokens_tx).await.unwrap();
+
+        let clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+        let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+            initializer.pubkey(),
+            Config::DEFAULT_FEATURES | Config::FEATURE_ESCROWS,
+        ));
+        let initialize_instr = TokenInstruction::initialize_escrow(
+            initializer.pubkey(),
+            sell.from_spl_token.pubkey(),
+            buy.to_spl_token.pubkey(),
+            sell_mint,
+            buy_mint,
+            EscrowEnv::EXPECTED_AMOUNT,
+            clock.unix_timestamp + EscrowEnv::TIMEOUT,
         );
-        let deposit_tx = Transaction::new_signed_with_payer(
-            &[from_deposit_instr, to_deposit_instr],
-            Some(&ctx.payer.pubkey()),
-            &[&ctx.payer],
+        let initialize_tx = Transaction::new_signed_with_payer(
+            &[features_instr, initialize_instr],
+            Some(&initializer.pubkey()),
+            &[&initializer],
             ctx.last_blockhash,
         );
-        ctx.banks_client.process_transaction(deposit_tx).await.unwrap();
+        ctx.banks_client.process_transaction(initialize_tx).await.unwrap();
 
-        Env { ctx, from, to }
+        EscrowEnv { ctx, initializer, taker, sell, buy, escrow, taker_send_token, taker_receive_token }
+    }
+
+    fn exchange(&self, amount: u64) -> Instruction {
+        TokenInstruction::exchange_escrow(
+            self.taker.pubkey(),
+            self.taker_send_token,
+            self.taker_receive_token,
+            self.initializer.pubkey(),
+            self.buy.to_spl_token.pubkey(),
+            self.sell.from_spl_token.pubkey(),
+            amount,
+        )
+    }
+}
+
+/// SPL multisig with `M` of 3 signers, owning a token account of the mint funded with `MintEnv::MINT_AMOUNT`
+struct MultisigEnv {
+    mint_env: MintEnv,
+    signers: [Keypair; 3],
+    multisig: Pubkey,
+    multisig_spl_token: Pubkey,
+}
+
+impl MultisigEnv {
+    const M: u8 = 2;
+
+    async fn new(env: &mut Env) -> MultisigEnv {
+        let mint_env = MintEnv::new(env).await;
+        let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let (multisig, multisig_spl_token) = (Keypair::new(), Keypair::new());
+        let ctx = &mut env.ctx;
+        let payer = &env.from;
+        let rent = ctx.banks_client.get_rent().await.unwrap();
+
+        let signer_keys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let signer_refs: Vec<&Pubkey> = signer_keys.iter().collect();
+        let create_multisig_instr = system_instruction::create_account(
+            &payer.pubkey(),
+            &multisig.pubkey(),
+            rent.minimum_balance(Multisig::LEN),
+            Multisig::LEN as u64,
+            &spl_token::id(),
+        );
+        let init_multisig_instr = spl_token::instruction::initialize_multisig(
+            &spl_token::id(),
+            &multisig.pubkey(),
+            &signer_refs,
+            Self::M,
+        ).unwrap();
+        let init_multisig_tx = Transaction::new_signed_with_payer(
+            &[create_multisig_instr, init_multisig_instr],
+            Some(&payer.pubkey()),
+            &[payer, &multisig],
+            ctx.last_blockhash,
+        );
+        let init_multisig_spl_token_tx = MintEnv::init_spl_holder_acc_tx(
+            payer,
+            &multisig_spl_token,
+            &mint_env.minter.pubkey(),
+            &multisig.pubkey(),
+            rent.minimum_balance(Account::LEN),
+            ctx.last_blockhash,
+        );
+        ctx.banks_client.process_transactions(vec![init_multisig_tx, init_multisig_spl_token_tx]).await.unwrap();
+        MintEnv::mint_spl_token(
+            ctx,
+            payer,
+            &mint_env.minter,
+            &multisig_spl_token,
+            &mint_env.mint_authority,
+            MintEnv::MINT_AMOUNT,
+        ).await;
+
+        MultisigEnv {
+            mint_env,
+            signers,
+            multisig: multisig.pubkey(),
+            multisig_spl_token: multisig_spl_token.pubkey(),
+        }
     }
 }
 
 #[tokio::test]
-async fn transfer_lamports() {
-    let env = Env::new().await;
-    let from = env.from;
-    let to = env.to;
+async fn claim_and_cancel_vesting() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (creator, beneficiary) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let (vesting, _) = VestingSchedule::find_address(&creator.pubkey(), &beneficiary.pubkey(), &mint);
+    let beneficiary_token = get_associated_token_address(&beneficiary.pubkey(), &mint);
     let mut ctx = env.ctx;
 
-    let transfer_amount = 1_111_111;
-    let from_balance_before_transfer = ctx
-        .banks_client
-        .get_balance(from.pubkey())
-        .await
-        .unwrap();
-    let to_balance_before_transfer = ctx
-        .banks_client
-        .get_balance(to.pubkey())
-        .await
-        .unwrap();
-    let (fee_calculator, _, _) = ctx.banks_client.get_fees().await.unwrap();
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        creator.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_VESTING,
+    ));
+    let features_tx = Transaction::new_signed_with_payer(
+        &[features_instr],
+        Some(&creator.pubkey()),
+        &[&creator],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(features_tx).await.unwrap();
 
-    let transfer_instr = TokenInstruction::transfer_lamports(
-        from.pubkey(),
-        to.pubkey(),
-        transfer_amount,
+    let now = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let create_vesting_token_instr = create_associated_token_account(&creator.pubkey(), &vesting, &mint);
+    let create_beneficiary_token_instr = create_associated_token_account(
+        &creator.pubkey(),
+        &beneficiary.pubkey(),
+        &mint,
     );
-    let transfer_tx = Transaction::new_signed_with_payer(
-        &[transfer_instr],
-        Some(&from.pubkey()),
-        &[&from],
+    let create_instr = TokenInstruction::create_vesting(
+        creator.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        beneficiary.pubkey(),
+        mint,
+        MintEnv::MINT_AMOUNT,
+        now - 1_000,
+        now - 500,
+        now + 1_000,
+    );
+    let create_tx = Transaction::new_signed_with_payer(
+        &[create_vesting_token_instr, create_beneficiary_token_instr, create_instr],
+        Some(&creator.pubkey()),
+        &[&creator],
         ctx.last_blockhash,
     );
-    let transfer_tx_fee = fee_calculator.calculate_fee(transfer_tx.message());
-    ctx.banks_client.process_transaction(transfer_tx).await.unwrap();
+    ctx.banks_client.process_transaction(create_tx).await.unwrap();
 
-    let to_balance_after_transfer = ctx
-        .banks_client
-        .get_balance(to.pubkey())
-        .await
-        .unwrap();
-    assert_eq!(to_balance_after_transfer - to_balance_before_transfer, transfer_amount);
+    let claim_instr = TokenInstruction::claim_vested(beneficiary.pubkey(), beneficiary_token, creator.pubkey(), mint);
+    let claim_tx = Transaction::new_signed_with_payer(
+        &[claim_instr],
+        Some(&beneficiary.pubkey()),
+        &[&beneficiary],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(claim_tx).await.unwrap();
 
-    let from_balance_after_transfer = ctx
-        .banks_client
-        .get_balance(from.pubkey())
+    let claimed = ctx.banks_client.get_packed_account_data::<Account>(beneficiary_token).await.unwrap().amount;
+    assert!((MintEnv::MINT_AMOUNT / 2..MintEnv::MINT_AMOUNT).contains(&claimed));
+
+    let cancel_instr = TokenInstruction::cancel_vesting(
+        creator.pubkey(),
+        mint_env.from_spl_token.pubkey(),
+        beneficiary_token,
+        beneficiary.pubkey(),
+        mint,
+    );
+    let cancel_tx = Transaction::new_signed_with_payer(
+        &[cancel_instr],
+        Some(&creator.pubkey()),
+        &[&creator],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(cancel_tx).await.unwrap();
+
+    let beneficiary_token_acc: Account = ctx.banks_client.get_packed_account_data(beneficiary_token).await.unwrap();
+    let creator_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.from_spl_token.pubkey())
         .await
         .unwrap();
-    assert_eq!(from_balance_before_transfer, from_balance_after_transfer + transfer_amount + transfer_tx_fee);
+    assert_eq!(beneficiary_token_acc.amount + creator_token_acc.amount, MintEnv::MINT_AMOUNT);
+    assert!(ctx.banks_client.get_account(vesting).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(get_associated_token_address(&vesting, &mint)).await.unwrap().is_none());
 }
 
 #[tokio::test]
-async fn transfer_spl_token() {
+async fn create_sponsored_account() {
     let mut env = Env::new().await;
     let mint_env = MintEnv::new(&mut env).await;
-    let from = env.from;
-    let transfer_amount = MintEnv::MINT_AMOUNT;
+    let other_mint_env = MintEnv::new(&mut env).await;
+    let (sponsor, operator) = (env.from, env.to);
+    let wallet = Keypair::new();
+    let (pool, _) = SponsorPool::find_address(&sponsor.pubkey());
     let mut ctx = env.ctx;
 
-    let from_spl_token_acc_before_transfer: Account = ctx.banks_client
-        .get_packed_account_data(mint_env.from_spl_token.pubkey())
-        .await
-        .unwrap();
-    let to_spl_token_acc_before_transfer: Account = ctx.banks_client
-        .get_packed_account_data(mint_env.to_spl_token.pubkey())
-        .await
-        .unwrap();
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        sponsor.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_SPONSORSHIP,
+    ));
+    let create_pool_instr = TokenInstruction::create_sponsor_pool(sponsor.pubkey(), operator.pubkey(), 1, 100_000_000);
+    let create_pool_tx = Transaction::new_signed_with_payer(
+        &[features_instr, create_pool_instr],
+        Some(&sponsor.pubkey()),
+        &[&sponsor],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_pool_tx).await.unwrap();
 
-    let transfer_spl_token_instr = TokenInstruction::transfer_spl_token(
-        from.pubkey(),
-        mint_env.from_spl_token.pubkey(),
-        mint_env.to_spl_token.pubkey(),
-        transfer_amount,
+    let sponsor_account = |mint: Pubkey| TokenInstruction::create_sponsored_account(
+        operator.pubkey(),
+        wallet.pubkey(),
+        mint,
+        sponsor.pubkey(),
     );
-    let transfer_spl_token_tx = Transaction::new_signed_with_payer(
-        &[transfer_spl_token_instr],
-        Some(&from.pubkey()),
-        &[&from],
+    let sponsor_tx = Transaction::new_signed_with_payer(
+        &[sponsor_account(mint_env.minter.pubkey())],
+        Some(&operator.pubkey()),
+        &[&operator],
         ctx.last_blockhash,
     );
-    ctx.banks_client.process_transaction(transfer_spl_token_tx).await.unwrap();
+    ctx.banks_client.process_transaction(sponsor_tx).await.unwrap();
 
-    let from_spl_token_acc_after_transfer: Account = ctx.banks_client
-        .get_packed_account_data(mint_env.from_spl_token.pubkey())
-        .await
-        .unwrap();
-    let to_spl_token_acc_after_transfer: Account = ctx.banks_client
-        .get_packed_account_data(mint_env.to_spl_token.pubkey())
+    let token_acc: Account = ctx.banks_client
+        .get_packed_account_data(get_associated_token_address(&wallet.pubkey(), &mint_env.minter.pubkey()))
         .await
         .unwrap();
+    assert_eq!(token_acc.owner, wallet.pubkey());
+    let pool: SponsorPool = account_state(&mut ctx.banks_client, pool).await;
+    assert_eq!(pool.sponsored_count, 1);
+    assert!(pool.remaining < 100_000_000);
 
-    assert_eq!(from_spl_token_acc_before_transfer.mint, mint_env.minter.pubkey());
-    assert_eq!(from_spl_token_acc_before_transfer.amount,
-               from_spl_token_acc_after_transfer.amount + transfer_amount);
-    assert_eq!(to_spl_token_acc_before_transfer.mint, mint_env.minter.pubkey());
-    assert_eq!(to_spl_token_acc_before_transfer.amount,
-               to_spl_token_acc_after_transfer.amount - transfer_amount);
+    let over_limit_tx = Transaction::new_signed_with_payer(
+        &[sponsor_account(other_mint_env.minter.pubkey())],
+        Some(&operator.pubkey()),
+        &[&operator],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(over_limit_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::SponsorshipLimitReached as u32))
+    );
 }
 
 #[tokio::test]
-async fn approve_spl_token() {
+async fn withdraw_from_and_close_lamport_stream() {
+    let Env { mut ctx, from: sender, to: recipient } = Env::new().await;
+    let (stream, _) = Stream::find_address(&sender.pubkey(), &recipient.pubkey());
+    let (deposit, rate_per_second) = (1_000_000_000, 1_000_000);
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        sender.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_STREAMS,
+    ));
+    let now = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let create_instr = TokenInstruction::create_stream(
+        sender.pubkey(),
+        recipient.pubkey(),
+        deposit,
+        rate_per_second,
+        now - 100,
+    );
+    let create_tx = Transaction::new_signed_with_payer(
+        &[features_instr, create_instr],
+        Some(&sender.pubkey()),
+        &[&sender],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_tx).await.unwrap();
+
+    let withdraw_instr = TokenInstruction::withdraw_from_stream(recipient.pubkey(), sender.pubkey());
+    let withdraw_tx = Transaction::new_signed_with_payer(
+        &[withdraw_instr],
+        Some(&recipient.pubkey()),
+        &[&recipient],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(withdraw_tx).await.unwrap();
+
+    let stream_acc: Stream = account_state(&mut ctx.banks_client, stream).await;
+    assert!((100 * rate_per_second..deposit).contains(&stream_acc.withdrawn));
+
+    let close_instr = TokenInstruction::close_stream(sender.pubkey(), recipient.pubkey());
+    let close_tx = Transaction::new_signed_with_payer(
+        &[close_instr],
+        Some(&sender.pubkey()),
+        &[&sender],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(close_tx).await.unwrap();
+    assert!(ctx.banks_client.get_account(stream).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn withdraw_from_and_close_token_stream() {
     let mut env = Env::new().await;
     let mint_env = MintEnv::new(&mut env).await;
-    let from = env.from;
-    let transfer_amount = MintEnv::MINT_AMOUNT;
+    let (sender, recipient) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let (stream, _) = Stream::find_address(&sender.pubkey(), &recipient.pubkey());
+    let recipient_token = get_associated_token_address(&recipient.pubkey(), &mint);
     let mut ctx = env.ctx;
-    let from_spl_token = mint_env.from_spl_token;
-    let to_spl_token = mint_env.to_spl_token;
 
-    let from_spl_token_acc_before_transfer: Account = ctx.banks_client
-        .get_packed_account_data(from_spl_token.pubkey())
-        .await
-        .unwrap();
-    let to_spl_token_acc_before_transfer: Account = ctx.banks_client
-        .get_packed_account_data(to_spl_token.pubkey())
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        sender.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_STREAMS,
+    ));
+    let create_stream_token_instr = create_associated_token_account(&sender.pubkey(), &stream, &mint);
+    let create_recipient_token_instr = create_associated_token_account(&sender.pubkey(), &recipient.pubkey(), &mint);
+    let now = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let create_instr = TokenInstruction::with_stream_token(
+        TokenInstruction::create_stream(sender.pubkey(), recipient.pubkey(), MintEnv::MINT_AMOUNT, 10, now - 100),
+        mint,
+        &[mint_env.from_spl_token.pubkey()],
+    );
+    let create_tx = Transaction::new_signed_with_payer(
+        &[features_instr, create_stream_token_instr, create_recipient_token_instr, create_instr],
+        Some(&sender.pubkey()),
+        &[&sender],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_tx).await.unwrap();
+
+    let withdraw_instr = TokenInstruction::with_stream_token(
+        TokenInstruction::withdraw_from_stream(recipient.pubkey(), sender.pubkey()),
+        mint,
+        &[recipient_token],
+    );
+    let withdraw_tx = Transaction::new_signed_with_payer(
+        &[withdraw_instr],
+        Some(&recipient.pubkey()),
+        &[&recipient],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(withdraw_tx).await.unwrap();
+
+    let withdrawn = ctx.banks_client.get_packed_account_data::<Account>(recipient_token).await.unwrap().amount;
+    assert!((1_000..MintEnv::MINT_AMOUNT).contains(&withdrawn));
+
+    let close_instr = TokenInstruction::with_stream_token(
+        TokenInstruction::close_stream(sender.pubkey(), recipient.pubkey()),
+        mint,
+        &[mint_env.from_spl_token.pubkey(), recipient_token],
+    );
+    let close_tx = Transaction::new_signed_with_payer(
+        &[close_instr],
+        Some(&sender.pubkey()),
+        &[&sender],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(close_tx).await.unwrap();
+
+    let recipient_token_acc: Account = ctx.banks_client.get_packed_account_data(recipient_token).await.unwrap();
+    let sender_token_acc: Account = ctx.banks_client
+        .get_packed_account_data(mint_env.from_spl_token.pubkey())
         .await
         .unwrap();
+    assert_eq!(recipient_token_acc.amount + sender_token_acc.amount, MintEnv::MINT_AMOUNT);
+    assert!(ctx.banks_client.get_account(stream).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(get_associated_token_address(&stream, &mint)).await.unwrap().is_none());
+}
 
-    let transfer_spl_token_instr = TokenInstruction::approve_spl_token(
-        from.pubkey(),
-        from_spl_token.pubkey(),
-        to_spl_token.pubkey(),
-        transfer_amount,
+#[tokio::test]
+async fn create_token_stream_of_soulbound_mint() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (sender, recipient) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let (stream, _) = Stream::find_address(&sender.pubkey(), &recipient.pubkey());
+    let mut ctx = env.ctx;
+
+    let now = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let create_tx = Transaction::new_signed_with_payer(
+        &[
+            TokenInstruction::with_program_data(TokenInstruction::set_features(
+                sender.pubkey(),
+                Config::DEFAULT_FEATURES | Config::FEATURE_STREAMS | Config::FEATURE_MINT_CONFIGS,
+            )),
+            system_instruction::transfer(&sender.pubkey(), &mint_env.mint_authority.pubkey(), 10_000_000),
+            TokenInstruction::set_mint_config(mint_env.mint_authority.pubkey(), mint, true),
+            create_associated_token_account(&sender.pubkey(), &stream, &mint),
+            TokenInstruction::with_stream_token(
+                TokenInstruction::create_stream(sender.pubkey(), recipient.pubkey(), MintEnv::MINT_AMOUNT, 10, now),
+                mint,
+                &[mint_env.from_spl_token.pubkey()],
+            ),
+        ],
+        Some(&sender.pubkey()),
+        &[&sender, &mint_env.mint_authority],
+        ctx.last_blockhash,
     );
-    let transfer_spl_token_tx = Transaction::new_signed_with_payer(
-        &[transfer_spl_token_instr],
+    let err = ctx.banks_client.process_transaction(create_tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(4, InstructionError::Custom(TransferError::SoulboundMint as u32))
+    );
+    assert!(ctx.banks_client.get_account(stream).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn park_and_claim_balance() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (from, wallet) = (env.from, env.to);
+    let mint = mint_env.minter.pubkey();
+    let (claimable, _) = ClaimableBalance::find_address(&wallet.pubkey(), &mint);
+    let wallet_token = get_associated_token_address(&wallet.pubkey(), &mint);
+    let mut ctx = env.ctx;
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        from.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_CLAIMABLE_BALANCES,
+    ));
+    let park_instrs: Vec<_> = [1_000, 2_000].iter()
+        .map(|&amount| {
+            TokenInstruction::park_spl_token(
+                from.pubkey(),
+                mint_env.from_spl_token.pubkey(),
+                wallet.pubkey(),
+                mint,
+                amount,
+            )
+        })
+        .collect();
+    let park_tx = Transaction::new_signed_with_payer(
+        &[&[features_instr][..], &park_instrs].concat(),
         Some(&from.pubkey()),
         &[&from],
         ctx.last_blockhash,
     );
-    ctx.banks_client.process_transaction(transfer_spl_token_tx).await.unwrap();
+    ctx.banks_client.process_transaction(park_tx).await.unwrap();
 
-    let from_spl_token_acc_after_transfer: Account = ctx.banks_client
-        .get_packed_account_data(from_spl_token.pubkey())
-        .await
-        .unwrap();
-    let to_spl_token_acc_after_transfer: Account = ctx.banks_client
-        .get_packed_account_data(to_spl_token.pubkey())
-        .await
-        .unwrap();
+    let parked: ClaimableBalance = account_state(&mut ctx.banks_client, claimable).await;
+    assert_eq!(parked.amount, 3_000);
+    assert!(ctx.banks_client.get_account(wallet_token).await.unwrap().is_none());
+
+    let claim_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::claim_balance(wallet.pubkey(), mint, from.pubkey())],
+        Some(&wallet.pubkey()),
+        &[&wallet],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(claim_tx).await.unwrap();
 
+    let wallet_token_acc: Account = ctx.banks_client.get_packed_account_data(wallet_token).await.unwrap();
+    assert_eq!(wallet_token_acc.amount, 3_000);
+    assert!(ctx.banks_client.get_account(claimable).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(get_associated_token_address(&claimable, &mint)).await.unwrap().is_none());
+}
 
-    assert_eq!(from_spl_token_acc_before_transfer.mint, mint_env.minter.pubkey());
-    assert_eq!(from_spl_token_acc_before_transfer.delegate, COption::None);
-    assert_eq!(from_spl_token_acc_before_transfer.delegated_amount, 0);
-    assert_eq!(from_spl_token_acc_after_transfer.delegate, COption::Some(to_spl_token.pubkey()));
-    assert_eq!(from_spl_token_acc_after_transfer.delegated_amount, transfer_amount);
+#[tokio::test]
+async fn transfer_lamports_to_registered_recipient() {
+    let env = Env::new().await;
+    let (payer, recipient) = (env.from, env.to);
+    let substitute = Keypair::new();
+    let (roster_entry, _) = RosterEntry::find_address(&payer.pubkey(), &recipient.pubkey());
+    let mut ctx = env.ctx;
 
-    assert_eq!(to_spl_token_acc_before_transfer.mint, mint_env.minter.pubkey());
-    assert_eq!(to_spl_token_acc_before_transfer.delegate, COption::None);
-    assert_eq!(to_spl_token_acc_before_transfer.delegated_amount, 0);
-    assert_eq!(to_spl_token_acc_after_transfer.delegate, COption::None);
-    assert_eq!(to_spl_token_acc_after_transfer.delegated_amount, 0);
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        payer.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_RECIPIENT_ROSTERS,
+    ));
+    let register_instr = TokenInstruction::register_recipient(recipient.pubkey(), payer.pubkey());
+    let register_tx = Transaction::new_signed_with_payer(
+        &[features_instr, register_instr],
+        Some(&payer.pubkey()),
+        &[&payer, &recipient],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(register_tx).await.unwrap();
+    let registered: RosterEntry = account_state(&mut ctx.banks_client, roster_entry).await;
+    assert_eq!(registered.recipient, recipient.pubkey());
+
+    let blockhash = ctx.last_blockhash;
+    let pay = |to: Pubkey, amount: u64| Transaction::new_signed_with_payer(
+        &[TokenInstruction::transfer_lamports_to_recipient(payer.pubkey(), to, amount)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(pay(substitute.pubkey(), 1_000)).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::RecipientNotRegistered as u32))
+    );
+
+    let balance = ctx.banks_client.get_balance(recipient.pubkey()).await.unwrap();
+    ctx.banks_client.process_transaction(pay(recipient.pubkey(), 2_000)).await.unwrap();
+    assert_eq!(ctx.banks_client.get_balance(recipient.pubkey()).await.unwrap(), balance + 2_000);
+
+    let remove_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::remove_recipient(payer.pubkey(), recipient.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(remove_tx).await.unwrap();
+    assert!(ctx.banks_client.get_account(roster_entry).await.unwrap().is_none());
+    assert!(ctx.banks_client.process_transaction(pay(recipient.pubkey(), 3_000)).await.is_err());
 }
 
+#[tokio::test]
+async fn release_milestones_and_close_contract() {
+    let mut env = Env::new().await;
+    let mint_env = MintEnv::new(&mut env).await;
+    let (funder, payee) = (env.from, env.to);
+    let arbiter = Keypair::new();
+    let mint = mint_env.minter.pubkey();
+    let funder_token = mint_env.from_spl_token.pubkey();
+    let payee_token = get_associated_token_address(&payee.pubkey(), &mint);
+    let (contract, _) = MilestoneContract::find_address(&funder.pubkey(), &payee.pubkey(), 1);
+    let mut ctx = env.ctx;
+
+    let features_instr = TokenInstruction::with_program_data(TokenInstruction::set_features(
+        funder.pubkey(),
+        Config::DEFAULT_FEATURES | Config::FEATURE_MILESTONE_CONTRACTS,
+    ));
+    let create_contract_token_instr = create_associated_token_account(&funder.pubkey(), &contract, &mint);
+    let create_payee_token_instr = create_associated_token_account(&funder.pubkey(), &payee.pubkey(), &mint);
+    let milestones = vec![
+        MilestoneTerms { amount: 10_000, deliverable_hash: [1; 32] },
+        MilestoneTerms { amount: 6_000, deliverable_hash: [2; 32] },
+    ];
+    let create_instr = TokenInstruction::create_contract(
+        funder.pubkey(),
+        funder_token,
+        payee.pubkey(),
+        arbiter.pubkey(),
+        mint,
+        1,
+        milestones,
+    );
+    let create_tx = Transaction::new_signed_with_payer(
+        &[features_instr, create_contract_token_instr, create_payee_token_instr, create_instr],
+        Some(&funder.pubkey()),
+        &[&funder],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_tx).await.unwrap();
+
+    let submit_tx = Transaction::new_signed_with_payer(
+        &[TokenInstruction::submit_deliverable(payee.pubkey(), contract, 0, [9; 32])],
+        Some(&payee.pubkey()),
+        &[&payee],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(submit_tx).await.unwrap();
+
+    let blockhash = ctx.last_blockhash;
+    let release = |index: u8| Transaction::new_signed_with_payer(
+        &[TokenInstruction::release_milestone(funder.pubkey(), contract, payee_token, mint, index)],
+        Some(&funder.pubkey()),
+        &[&funder],
+        blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(release(1)).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::MilestoneNotSubmitted as u32))
+    );
+    ctx.banks_client.process_transaction(release(0)).await.unwrap();
+
+    let payee_token_acc: Account = ctx.banks_client.get_packed_account_data(payee_token).await.unwrap();
+    assert_eq!(payee_token_acc.amount, 10_000);
+    let released: MilestoneContract = account_state(&mut ctx.banks_client, contract).await;
+    assert_eq!(released.released_total, 10_000);
+    assert_eq!(released.milestones[0].submitted_hash, [9; 32]);
+
+    let close = |authority: &Keypair| Transaction::new_signed_with_payer(
+        &[TokenInstruction::close_contract(authority.pubkey(), funder.pubkey(), contract, funder_token, mint)],
+        Some(&funder.pubkey()),
+        &[&funder, authority],
+        blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(close(&funder)).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TransferError::ContractNotCompleted as u32))
+    );
+    ctx.banks_client.process_transaction(close(&arbiter)).await.unwrap();
+
+    let funder_token_acc: Account = ctx.banks_client.get_packed_account_data(funder_token).await.unwrap();
+    assert_eq!(funder_token_acc.amount, MintEnv::MINT_AMOUNT - 10_000);
+    assert!(ctx.banks_client.get_account(contract).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(get_associated_token_address(&contract, &mint)).await.unwrap().is_none());
+}
 
 struct MintEnv {
     minter: Keypair,
-    _mint_authority: Keypair,
-    _freeze_authority: Keypair,
+    mint_authority: Keypair,
+    freeze_authority: Keypair,
     from_spl_token: Keypair,
     to_spl_token: Keypair,
-    _decimals: u8,
+    decimals: u8,
 }
 
 impl MintEnv {
@@ -239,11 +4273,11 @@ impl MintEnv {
 
         MintEnv {
             minter,
-            _mint_authority: mint_authority,
-            _freeze_authority: freeze_authority,
+            mint_authority,
+            freeze_authority,
             from_spl_token,
             to_spl_token,
-            _decimals: decimals,
+            decimals,
         }
     }
 
@@ -298,7 +4332,7 @@ impl MintEnv {
 
         let init_from_spl_holder_acc_tx = Self::init_spl_holder_acc_tx(
             from,
-            &from_spl_token,
+            from_spl_token,
             &minter.pubkey(),
             &from.pubkey(),
             acc_rent_value,
@@ -306,7 +4340,7 @@ impl MintEnv {
         );
         let init_to_spl_holder_acc_tx = Self::init_spl_holder_acc_tx(
             to,
-            &to_spl_token,
+            to_spl_token,
             &minter.pubkey(),
             &to.pubkey(),
             acc_rent_value,
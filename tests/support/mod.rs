@@ -0,0 +1,161 @@
+//! Program test fixtures shared by failure-path and property tests: a funded owner and two token accounts
+//! of one mint, the source holding `Fixture::MINT_AMOUNT`.
+//!
+//! Every case starts its own bank, so a failed transaction of one case can't leave state behind for another.
+#![allow(dead_code)]
+
+use std::future::Future;
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use spl_token::state::{Account, Mint};
+use token::entrypoint::process_instruction;
+use token::id;
+use token::instruction::TokenInstruction;
+
+pub struct Fixture {
+    pub ctx: ProgramTestContext,
+    pub owner: Keypair,
+    pub recipient: Keypair,
+    pub mint: Pubkey,
+    pub from_spl_token: Pubkey,
+    pub to_spl_token: Pubkey,
+}
+
+impl Fixture {
+    pub const WALLET_LAMPORTS: u64 = 5_000_000_000;
+    pub const MINT_AMOUNT: u64 = 26_000;
+
+    pub async fn new() -> Fixture {
+        let program_test = ProgramTest::new("token", id(), processor!(process_instruction));
+        let ctx = program_test.start_with_context().await;
+        let mut fixture = Fixture {
+            ctx,
+            owner: Keypair::new(),
+            recipient: Keypair::new(),
+            mint: Pubkey::default(),
+            from_spl_token: Pubkey::default(),
+            to_spl_token: Pubkey::default(),
+        };
+        let payer = fixture.payer();
+        let deposit_instrs = [
+            system_instruction::transfer(&payer.pubkey(), &fixture.owner.pubkey(), Fixture::WALLET_LAMPORTS),
+            system_instruction::transfer(&payer.pubkey(), &fixture.recipient.pubkey(), Fixture::WALLET_LAMPORTS),
+        ];
+        fixture.process(&deposit_instrs, &[&payer]).await.unwrap();
+
+        fixture.mint = fixture.create_mint().await;
+        let (mint, owner, recipient) = (fixture.mint, fixture.owner.pubkey(), fixture.recipient.pubkey());
+        fixture.from_spl_token = fixture.token_account(&mint, &owner).await;
+        fixture.to_spl_token = fixture.token_account(&mint, &recipient).await;
+        let mint_to_instr = spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &mint,
+            &fixture.from_spl_token,
+            &payer.pubkey(),
+            &[],
+            Fixture::MINT_AMOUNT,
+        ).unwrap();
+        fixture.process(&[mint_to_instr], &[&payer]).await.unwrap();
+        fixture
+    }
+
+    /// Bank payer, also authority of every mint created by the fixture
+    pub fn payer(&self) -> Keypair {
+        Keypair::from_bytes(&self.ctx.payer.to_bytes()).unwrap()
+    }
+
+    /// Send `instructions` in one transaction paid by the first signer, error of the transaction if it fails
+    pub async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.ctx.last_blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+    }
+
+    /// Mint with zero decimals
+    pub async fn create_mint(&mut self) -> Pubkey {
+        let (payer, mint) = (self.payer(), Keypair::new());
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let create_instr = system_instruction::create_account(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            &spl_token::id(),
+        );
+        let initialize_instr = spl_token::instruction::initialize_mint(
+            &spl_token::id(),
+            &mint.pubkey(),
+            &payer.pubkey(),
+            None,
+            0,
+        ).unwrap();
+        self.process(&[create_instr, initialize_instr], &[&payer, &mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Empty token account of `mint` owned by `owner`
+    pub async fn token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let (payer, token_account) = (self.payer(), Keypair::new());
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let create_instr = system_instruction::create_account(
+            &payer.pubkey(),
+            &token_account.pubkey(),
+            rent.minimum_balance(Account::LEN),
+            Account::LEN as u64,
+            &spl_token::id(),
+        );
+        let initialize_instr = spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            &token_account.pubkey(),
+            mint,
+            owner,
+        ).unwrap();
+        self.process(&[create_instr, initialize_instr], &[&payer, &token_account]).await.unwrap();
+        token_account.pubkey()
+    }
+
+    /// `TransferSplToken` of `amount` from the owner to the recipient token account
+    pub fn transfer_spl_token(&self, amount: u64) -> Instruction {
+        TokenInstruction::transfer_spl_token(
+            self.owner.pubkey(),
+            self.from_spl_token,
+            self.to_spl_token,
+            self.mint,
+            amount,
+        )
+    }
+
+    /// `TransferLamports` of `amount` from the owner to the recipient wallet
+    pub fn transfer_lamports(&self, amount: u64) -> Instruction {
+        TokenInstruction::transfer_lamports(self.owner.pubkey(), self.recipient.pubkey(), amount)
+    }
+
+    pub async fn token_balance(&mut self, token_account: &Pubkey) -> u64 {
+        self.ctx.banks_client.get_packed_account_data::<Account>(*token_account).await.unwrap().amount
+    }
+
+    pub async fn balance(&mut self, address: &Pubkey) -> u64 {
+        self.ctx.banks_client.get_balance(*address).await.unwrap()
+    }
+}
+
+/// Transaction error of `error` returned by the first instruction
+pub fn instruction_error<E: Into<ProgramError>>(error: E) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::from(u64::from(error.into())))
+}
+
+/// Run an async case to completion, proptest bodies are synchronous
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}